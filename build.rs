@@ -0,0 +1,50 @@
+/**
+ * Generates the gRPC service for `proto/geos.proto` when the `grpc` feature is enabled. The service
+ * is described by hand rather than compiled from the proto file so that building does not require
+ * `protoc`; the message types live in `src/grpc.rs`.
+ */
+fn main() {
+    #[cfg(feature = "grpc")]
+    grpc::compile();
+}
+
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    /// (name, route name, input type, output type, client streaming)
+    const METHODS: [(&str, &str, &str, &str, bool); 7] = [
+        ("s2_cover", "S2Cover", "S2CoverRequest", "Cell", false),
+        ("s2_cut", "S2Cut", "S2CutRequest", "Geometry", false),
+        ("s2_cell_to_poly", "S2CellToPoly", "Cell", "Geometry", true),
+        ("h3_cover", "H3Cover", "H3CoverRequest", "Cell", false),
+        ("h3_cut", "H3Cut", "H3CutRequest", "Geometry", false),
+        ("h3_cell_to_poly", "H3CellToPoly", "Cell", "Geometry", true),
+        ("sample", "Sample", "SampleRequest", "Geometry", false),
+    ];
+
+    pub fn compile() {
+        println!("cargo:rerun-if-changed=build.rs");
+        let service = METHODS.iter().fold(
+            Service::builder().name("Geos").package("geos.v1"),
+            |service, &(name, route_name, input, output, client_streaming)| {
+                let method = Method::builder()
+                    .name(name)
+                    .route_name(route_name)
+                    .input_type(format!("crate::grpc::{}", input))
+                    .output_type(format!("crate::grpc::{}", output))
+                    .codec_path("tonic_prost::ProstCodec")
+                    .server_streaming();
+                let method = if client_streaming {
+                    method.client_streaming()
+                } else {
+                    method
+                };
+                service.method(method.build())
+            },
+        );
+        Builder::new()
+            .build_client(false)
+            .compile(&[service.build()]);
+    }
+}
@@ -0,0 +1,525 @@
+/*!
+ * Writing of tables of unsigned integers, such as cell IDs and their levels, as Apache Arrow IPC
+ * files (also known as Feather v2), which dataframe libraries load without parsing. Columns have no
+ * nulls and are written uncompressed, in record batches of a fixed number of rows.
+ */
+use std::error::Error;
+use std::io::Write;
+
+/// The magic number that starts and ends Arrow IPC files, padded to 8 bytes at the start.
+const MAGIC: &[u8] = b"ARROW1";
+
+/// The number of rows buffered before they are written as a record batch.
+const BATCH_ROWS: usize = 1 << 16;
+
+/// The marker that starts each message, followed by the size of its metadata.
+const CONTINUATION: u32 = 0xFFFF_FFFF;
+
+/// The version of the Arrow metadata format, V5.
+const METADATA_VERSION: i16 = 4;
+
+/// The types of message headers and of fields in the Arrow flatbuffer schema.
+const HEADER_SCHEMA: u8 = 1;
+const HEADER_RECORD_BATCH: u8 = 3;
+const TYPE_INT: u8 = 2;
+
+/**
+ * The type of a column, as an unsigned integer of some width.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    UInt8,
+    UInt64,
+}
+
+impl ColumnType {
+    fn bytes(&self) -> usize {
+        match self {
+            ColumnType::UInt8 => 1,
+            ColumnType::UInt64 => 8,
+        }
+    }
+}
+
+/**
+ * Writes rows to an Arrow IPC file as they are produced, buffering only the current record batch.
+ * Nothing is readable until [ArrowWriter::finish] writes the footer.
+ */
+pub struct ArrowWriter<W: Write> {
+    out: W,
+    /// The number of bytes written so far.
+    offset: usize,
+    columns: Vec<(String, ColumnType)>,
+    /// The little-endian values of each column in the current batch.
+    buffers: Vec<Vec<u8>>,
+    rows: usize,
+    total_rows: usize,
+    /// The offset, metadata size, and body size of each record batch, for the footer.
+    batches: Vec<(usize, usize, usize)>,
+}
+
+impl<W: Write> ArrowWriter<W> {
+    /**
+     * Starts a file with the given columns.
+     */
+    pub fn new(out: W, columns: &[(&str, ColumnType)]) -> Result<Self, Box<dyn Error>> {
+        let mut writer = Self {
+            out,
+            offset: 0,
+            columns: columns.iter().map(|(n, k)| (n.to_string(), *k)).collect(),
+            buffers: vec![vec![]; columns.len()],
+            rows: 0,
+            total_rows: 0,
+            batches: vec![],
+        };
+        writer.write(&pad8(MAGIC.to_vec()))?;
+        let schema = Message::Schema(writer.schema());
+        writer.write_message(&schema, &[])?;
+        Ok(writer)
+    }
+
+    /**
+     * Adds a row, with a value for each column in order. Values are truncated to the width of
+     * their column.
+     */
+    pub fn write_row(&mut self, row: &[u64]) -> Result<(), Box<dyn Error>> {
+        if row.len() != self.columns.len() {
+            return Err(format!(
+                "expected {} values in a row, got {}",
+                self.columns.len(),
+                row.len()
+            )
+            .into());
+        }
+        for ((value, (_, kind)), buffer) in row.iter().zip(&self.columns).zip(&mut self.buffers) {
+            buffer.extend_from_slice(&value.to_le_bytes()[..kind.bytes()]);
+        }
+        self.rows += 1;
+        if self.rows == BATCH_ROWS {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Writes the last record batch and the footer, returning the number of rows written.
+     */
+    pub fn finish(mut self) -> Result<usize, Box<dyn Error>> {
+        if self.rows > 0 || self.batches.is_empty() {
+            self.flush_batch()?;
+        }
+        // The end of the stream of messages.
+        self.write(&CONTINUATION.to_le_bytes())?;
+        self.write(&0u32.to_le_bytes())?;
+
+        let footer = Footer {
+            schema: self.schema(),
+            batches: self.batches.clone(),
+        };
+        let footer = footer.encode();
+        self.write(&footer)?;
+        self.write(&(footer.len() as u32).to_le_bytes())?;
+        self.write(MAGIC)?;
+        self.out.flush()?;
+        Ok(self.total_rows)
+    }
+
+    fn schema(&self) -> Schema {
+        Schema {
+            columns: self.columns.clone(),
+        }
+    }
+
+    fn flush_batch(&mut self) -> Result<(), Box<dyn Error>> {
+        // Each column has an empty validity buffer, since there are no nulls, and a data buffer,
+        // padded to 8 bytes.
+        let mut body = vec![];
+        let mut buffers = vec![];
+        for buffer in std::mem::take(&mut self.buffers) {
+            buffers.push((body.len(), 0));
+            buffers.push((body.len(), buffer.len()));
+            body.extend(pad8(buffer));
+        }
+        self.buffers = vec![vec![]; self.columns.len()];
+        let batch = Message::RecordBatch {
+            rows: self.rows,
+            columns: self.columns.len(),
+            buffers,
+            body_size: body.len(),
+        };
+        let offset = self.offset;
+        let metadata_size = self.write_message(&batch, &body)?;
+        self.batches.push((offset, metadata_size, body.len()));
+        self.total_rows += self.rows;
+        self.rows = 0;
+        Ok(())
+    }
+
+    /**
+     * Writes a message with its metadata padded to 8 bytes, returning the size of the metadata
+     * including its prefix.
+     */
+    fn write_message(&mut self, message: &Message, body: &[u8]) -> Result<usize, Box<dyn Error>> {
+        let metadata = pad8(message.encode());
+        self.write(&CONTINUATION.to_le_bytes())?;
+        self.write(&(metadata.len() as u32).to_le_bytes())?;
+        self.write(&metadata)?;
+        self.write(body)?;
+        Ok(metadata.len() + 8)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.out.write_all(bytes)?;
+        self.offset += bytes.len();
+        Ok(())
+    }
+}
+
+fn pad8(mut bytes: Vec<u8>) -> Vec<u8> {
+    bytes.resize(bytes.len().next_multiple_of(8), 0);
+    bytes
+}
+
+//==================================================
+// Metadata.
+//==================================================
+struct Schema {
+    columns: Vec<(String, ColumnType)>,
+}
+
+impl Schema {
+    fn table(&self) -> Table {
+        let fields = self
+            .columns
+            .iter()
+            .map(|(name, kind)| {
+                let int = Table(vec![
+                    Some(Field::I32(kind.bytes() as i32 * 8)),
+                    Some(Field::Bool(false)),
+                ]);
+                Table(vec![
+                    Some(Field::String(name.clone())),
+                    Some(Field::Bool(false)),
+                    Some(Field::U8(TYPE_INT)),
+                    Some(Field::Table(int)),
+                    None,
+                    // Readers expect the children of every field, even when there are none.
+                    Some(Field::Tables(vec![])),
+                ])
+            })
+            .collect();
+        // Little-endian, with the fields.
+        Table(vec![Some(Field::I16(0)), Some(Field::Tables(fields))])
+    }
+}
+
+enum Message {
+    Schema(Schema),
+    RecordBatch {
+        rows: usize,
+        columns: usize,
+        /// The offset and length of each buffer in the body.
+        buffers: Vec<(usize, usize)>,
+        body_size: usize,
+    },
+}
+
+impl Message {
+    fn encode(&self) -> Vec<u8> {
+        let (kind, header, body_size) = match self {
+            Message::Schema(schema) => (HEADER_SCHEMA, schema.table(), 0),
+            Message::RecordBatch {
+                rows,
+                columns,
+                buffers,
+                body_size,
+            } => {
+                // Each column is a node with no nulls.
+                let nodes = (0..*columns).map(|_| struct_of(&[*rows as i64, 0]));
+                let buffers = buffers
+                    .iter()
+                    .map(|(offset, length)| struct_of(&[*offset as i64, *length as i64]));
+                let batch = Table(vec![
+                    Some(Field::I64(*rows as i64)),
+                    Some(Field::Structs(nodes.collect())),
+                    Some(Field::Structs(buffers.collect())),
+                ]);
+                (HEADER_RECORD_BATCH, batch, *body_size)
+            }
+        };
+        Table(vec![
+            Some(Field::I16(METADATA_VERSION)),
+            Some(Field::U8(kind)),
+            Some(Field::Table(header)),
+            Some(Field::I64(body_size as i64)),
+        ])
+        .encode()
+    }
+}
+
+struct Footer {
+    schema: Schema,
+    /// The offset, metadata size, and body size of each record batch.
+    batches: Vec<(usize, usize, usize)>,
+}
+
+impl Footer {
+    fn encode(&self) -> Vec<u8> {
+        // Blocks are structs of an i64 offset, an i32 metadata size padded to 8 bytes, and an i64
+        // body size.
+        let blocks = self.batches.iter().map(|(offset, metadata, body)| {
+            struct_of(&[*offset as i64, *metadata as i64, *body as i64])
+        });
+        Table(vec![
+            Some(Field::I16(METADATA_VERSION)),
+            Some(Field::Table(self.schema.table())),
+            Some(Field::Structs(vec![])),
+            Some(Field::Structs(blocks.collect())),
+        ])
+        .encode()
+    }
+}
+
+/**
+ * The bytes of a struct of 8-byte fields, which is how every struct in the Arrow schema is laid
+ * out. Blocks declare an i32 followed by 4 bytes of padding, which reads the same as an i64 of a
+ * non-negative value.
+ */
+fn struct_of(fields: &[i64]) -> Vec<u8> {
+    fields.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+//==================================================
+// Flatbuffer encoding.
+//==================================================
+/**
+ * A flatbuffer table, with its fields in order of their IDs and `None` for absent ones.
+ */
+struct Table(Vec<Option<Field>>);
+
+enum Field {
+    Bool(bool),
+    U8(u8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    String(String),
+    Table(Table),
+    Tables(Vec<Table>),
+    /// A vector of structs whose fields are all 8 bytes, given as the bytes of each struct.
+    Structs(Vec<Vec<u8>>),
+}
+
+impl Field {
+    /// The size of the field within its table, where referenced objects take a 4-byte offset.
+    fn size(&self) -> usize {
+        match self {
+            Field::Bool(_) | Field::U8(_) => 1,
+            Field::I16(_) => 2,
+            Field::I64(_) => 8,
+            _ => 4,
+        }
+    }
+}
+
+impl Table {
+    /**
+     * Encodes the table as the root of a flatbuffer. The buffer is built front to back, with every
+     * object written after the objects that refer to it, since offsets point forward.
+     */
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![0; 4];
+        let root = self.write(&mut buf);
+        patch_offset(&mut buf, 0, root);
+        buf
+    }
+
+    /**
+     * Writes the table's vtable, then the table, then the objects it refers to, returning the
+     * position of the table.
+     */
+    fn write(&self, buf: &mut Vec<u8>) -> usize {
+        // Fields are laid out largest first after the vtable offset, so that they stay aligned
+        // once the table starts on an 8-byte boundary.
+        let mut layout = vec![None; self.0.len()];
+        let mut size: usize = 4;
+        for align in [8, 4, 2, 1] {
+            for (i, field) in self.0.iter().enumerate() {
+                if let Some(field) = field.as_ref().filter(|f| f.size() == align) {
+                    size = size.next_multiple_of(align);
+                    layout[i] = Some(size);
+                    size += field.size();
+                }
+            }
+        }
+
+        let vtable_size = 4 + 2 * self.0.len();
+        buf.resize(buf.len().next_multiple_of(2), 0);
+        let vtable = buf.len();
+        buf.extend((vtable_size as u16).to_le_bytes());
+        buf.extend((size as u16).to_le_bytes());
+        for position in &layout {
+            buf.extend((position.unwrap_or(0) as u16).to_le_bytes());
+        }
+        buf.resize(buf.len().next_multiple_of(8), 0);
+        let table = buf.len();
+        buf.extend(((table - vtable) as i32).to_le_bytes());
+        buf.resize(table + size, 0);
+
+        let mut references = vec![];
+        for (field, position) in self.0.iter().zip(layout) {
+            let (Some(field), Some(position)) = (field, position) else {
+                continue;
+            };
+            let at = table + position;
+            match field {
+                Field::Bool(value) => buf[at] = *value as u8,
+                Field::U8(value) => buf[at] = *value,
+                Field::I16(value) => buf[at..at + 2].copy_from_slice(&value.to_le_bytes()),
+                Field::I32(value) => buf[at..at + 4].copy_from_slice(&value.to_le_bytes()),
+                Field::I64(value) => buf[at..at + 8].copy_from_slice(&value.to_le_bytes()),
+                field => references.push((at, field)),
+            }
+        }
+        for (at, field) in references {
+            let target = match field {
+                Field::String(value) => {
+                    buf.resize(buf.len().next_multiple_of(4), 0);
+                    let target = buf.len();
+                    buf.extend((value.len() as u32).to_le_bytes());
+                    buf.extend(value.as_bytes());
+                    buf.push(0);
+                    target
+                }
+                Field::Table(table) => table.write(buf),
+                Field::Tables(tables) => {
+                    buf.resize(buf.len().next_multiple_of(4), 0);
+                    let target = buf.len();
+                    buf.extend((tables.len() as u32).to_le_bytes());
+                    let offsets = buf.len();
+                    buf.resize(offsets + 4 * tables.len(), 0);
+                    for (i, table) in tables.iter().enumerate() {
+                        let position = table.write(buf);
+                        patch_offset(buf, offsets + 4 * i, position);
+                    }
+                    target
+                }
+                Field::Structs(structs) => {
+                    // The structs themselves start on an 8-byte boundary, after the length.
+                    buf.resize((buf.len() + 4).next_multiple_of(8) - 4, 0);
+                    let target = buf.len();
+                    buf.extend((structs.len() as u32).to_le_bytes());
+                    buf.extend(structs.concat());
+                    target
+                }
+                _ => unreachable!("scalars are written inline"),
+            };
+            patch_offset(buf, at, target);
+        }
+        table
+    }
+}
+
+fn patch_offset(buf: &mut [u8], at: usize, target: usize) {
+    buf[at..at + 4].copy_from_slice(&((target - at) as u32).to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{UInt64Type, UInt8Type};
+    use arrow_ipc::reader::FileReader;
+    use arrow_schema::DataType;
+
+    use super::{struct_of, ArrowWriter, ColumnType, Field, Table, BATCH_ROWS};
+
+    #[test]
+    fn test_arrow_writer() {
+        let mut out = vec![];
+        let columns = [("cell", ColumnType::UInt64), ("level", ColumnType::UInt8)];
+        let mut writer = ArrowWriter::new(&mut out, &columns).unwrap();
+        writer.write_row(&[0x85283083fffffff, 5]).unwrap();
+        writer.write_row(&[u64::MAX, 255]).unwrap();
+        assert!(writer.write_row(&[1]).is_err());
+        assert_eq!(writer.finish().unwrap(), 2);
+
+        assert_eq!(&out[..8], b"ARROW1\0\0");
+        assert_eq!(&out[out.len() - 6..], b"ARROW1");
+        // Every message starts on an 8-byte boundary.
+        assert_eq!(&out[8..12], [0xff; 4]);
+        let schema_size = u32::from_le_bytes(out[12..16].try_into().unwrap()) as usize;
+        assert_eq!(schema_size % 8, 0);
+        let batch = 16 + schema_size;
+        assert_eq!(&out[batch..batch + 4], [0xff; 4]);
+
+        // The body of the batch, after its metadata, has the 8-byte padded cells and levels.
+        let metadata_size = u32::from_le_bytes(out[batch + 4..batch + 8].try_into().unwrap());
+        let body = batch + 8 + metadata_size as usize;
+        assert_eq!(&out[body..body + 8], 0x85283083fffffffu64.to_le_bytes());
+        assert_eq!(&out[body + 8..body + 16], u64::MAX.to_le_bytes());
+        assert_eq!(&out[body + 16..body + 24], [5, 255, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_arrow_readback() {
+        let mut out = vec![];
+        let columns = [
+            ("cell", ColumnType::UInt64),
+            ("resolution", ColumnType::UInt8),
+        ];
+        let mut writer = ArrowWriter::new(&mut out, &columns).unwrap();
+        let rows = (0..BATCH_ROWS as u64 + 3)
+            .map(|i| [0x85283083fffffff + (i << 3), i % 16])
+            .collect::<Vec<_>>();
+        for row in &rows {
+            writer.write_row(row).unwrap();
+        }
+        writer.write_row(&[u64::MAX, 255]).unwrap();
+        assert_eq!(writer.finish().unwrap(), rows.len() + 1);
+
+        let reader = FileReader::try_new(Cursor::new(out), None).unwrap();
+        let schema = reader.schema();
+        let fields = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone(), f.is_nullable()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("cell", DataType::UInt64, false),
+                ("resolution", DataType::UInt8, false)
+            ]
+        );
+        assert_eq!(reader.num_batches(), 2);
+
+        let (mut cells, mut resolutions): (Vec<u64>, Vec<u8>) = (vec![], vec![]);
+        for batch in reader {
+            let batch = batch.unwrap();
+            cells.extend(batch.column(0).as_primitive::<UInt64Type>().values());
+            resolutions.extend(batch.column(1).as_primitive::<UInt8Type>().values());
+        }
+        let expected_cells = rows.iter().map(|row| row[0]).chain([u64::MAX]);
+        let expected_resolutions = rows.iter().map(|row| row[1] as u8).chain([255]);
+        assert_eq!(cells, expected_cells.collect::<Vec<_>>());
+        assert_eq!(resolutions, expected_resolutions.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_struct_vector() {
+        let structs = vec![struct_of(&[1, 2]), struct_of(&[3, 4])];
+        let buf = Table(vec![Some(Field::Structs(structs))]).encode();
+        let read_u32 = |at: usize| u32::from_le_bytes(buf[at..at + 4].try_into().unwrap()) as usize;
+
+        // The vector holds two structs rather than four fields, and its structs are 8-byte aligned.
+        let table = read_u32(0);
+        let vtable = table - read_u32(table);
+        let field = table + u16::from_le_bytes([buf[vtable + 4], buf[vtable + 5]]) as usize;
+        let vector = field + read_u32(field);
+        assert_eq!(read_u32(vector), 2);
+        assert_eq!((vector + 4) % 8, 0);
+        assert_eq!(buf[vector + 4..vector + 36], struct_of(&[1, 2, 3, 4]));
+    }
+}
@@ -0,0 +1,79 @@
+/*!
+ * Aggregation of weighted points into the cells of a grid, such as H3 or S2 cells, for hexbin maps
+ * and density counts.
+ */
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use geo::Point;
+
+/**
+ * The number and total weight of the points that fall within a cell.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Bin {
+    pub count: u64,
+    pub weight: f64,
+}
+
+/**
+ * Counts the points within each cell, as given by `cell_of`, and sums their weights. Cells without
+ * points are left out, and the bins are ordered by cell.
+ */
+pub fn bin_points<C: Ord, E: Into<Box<dyn Error>>>(
+    points: impl IntoIterator<Item = (Point, f64)>,
+    cell_of: impl Fn(Point) -> Result<C, E>,
+) -> Result<BTreeMap<C, Bin>, Box<dyn Error>> {
+    let mut bins = BTreeMap::<C, Bin>::new();
+    for (point, weight) in points {
+        let bin = bins.entry(cell_of(point).map_err(Into::into)?).or_default();
+        bin.count += 1;
+        bin.weight += weight;
+    }
+    Ok(bins)
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{point, Point};
+
+    use super::{bin_points, Bin};
+
+    #[test]
+    fn test_bin_points() {
+        // Unit squares as cells.
+        let cell_of = |p: Point| -> Result<(i64, i64), String> {
+            if p.x().is_finite() && p.y().is_finite() {
+                Ok((p.x().floor() as i64, p.y().floor() as i64))
+            } else {
+                Err(format!("invalid point {:?}", p))
+            }
+        };
+        let points = [
+            (point!(x: 0.5, y: 0.5), 2.0),
+            (point!(x: -0.5, y: 0.5), 1.0),
+            (point!(x: 0.25, y: 0.75), 0.5),
+        ];
+        let bins = bin_points(points, cell_of).unwrap();
+        assert_eq!(
+            bins.into_iter().collect::<Vec<_>>(),
+            [
+                (
+                    (-1, 0),
+                    Bin {
+                        count: 1,
+                        weight: 1.0
+                    }
+                ),
+                (
+                    (0, 0),
+                    Bin {
+                        count: 2,
+                        weight: 2.5
+                    }
+                ),
+            ]
+        );
+        assert!(bin_points([(point!(x: f64::NAN, y: 0.0), 1.0)], cell_of).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+/*!
+ * Rendering of geometries as rough maps of Unicode braille characters, each of which is a 2×4 grid
+ * of dots, for previewing geometries in a terminal.
+ */
+use geo_types::{Coord, Geometry, LineString, Polygon, Rect};
+
+/// The bit of each dot of a braille character, by row and column.
+const DOT_BITS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// The first braille character, with no dots.
+const BRAILLE_BLANK: u32 = 0x2800;
+
+/**
+ * A grid of dots covering a lng/lat bounding box. Longitudes are scaled by the cosine of the middle
+ * latitude, so that shapes keep roughly their proportions away from the equator.
+ */
+pub struct BrailleCanvas {
+    bbox: Rect,
+    /// The dots per degree of latitude.
+    scale: f64,
+    /// The ratio of the length of a degree of longitude to one of latitude.
+    aspect: f64,
+    width: usize,
+    height: usize,
+    dots: Vec<bool>,
+}
+
+impl BrailleCanvas {
+    /**
+     * A canvas as large as fits within `max_columns` characters across and `max_rows` down, with
+     * the bounding box scaled to fill it.
+     */
+    pub fn new(bbox: Rect, max_columns: usize, max_rows: usize) -> Self {
+        let middle = (bbox.min().y + bbox.max().y) / 2.0;
+        // Near the poles, longitudes would shrink to nothing.
+        let aspect = middle.to_radians().cos().max(0.05);
+        let (dx, dy) = (bbox.width() * aspect, bbox.height());
+        let max_width = (2 * max_columns.max(1) - 1) as f64;
+        let max_height = (4 * max_rows.max(1) - 1) as f64;
+        let scale = match (dx > 0.0, dy > 0.0) {
+            (true, true) => (max_width / dx).min(max_height / dy),
+            (true, false) => max_width / dx,
+            (false, true) => max_height / dy,
+            (false, false) => 1.0,
+        };
+        let width = (dx * scale).round() as usize + 1;
+        let height = (dy * scale).round() as usize + 1;
+        Self {
+            bbox,
+            scale,
+            aspect,
+            width,
+            height,
+            dots: vec![false; width * height],
+        }
+    }
+
+    /**
+     * Draws the points, lines, and polygon outlines of a geometry.
+     */
+    pub fn draw(&mut self, geometry: &Geometry) {
+        match geometry {
+            Geometry::Point(point) => self.draw_line(&[point.0]),
+            Geometry::Line(line) => self.draw_line(&[line.start, line.end]),
+            Geometry::LineString(line) => self.draw_line(&line.0),
+            Geometry::Polygon(polygon) => self.draw_polygon(polygon),
+            Geometry::MultiPoint(points) => points.iter().for_each(|p| self.draw_line(&[p.0])),
+            Geometry::MultiLineString(lines) => lines.iter().for_each(|l| self.draw_line(&l.0)),
+            Geometry::MultiPolygon(polygons) => polygons.iter().for_each(|p| self.draw_polygon(p)),
+            Geometry::Rect(rect) => self.draw_polygon(&rect.to_polygon()),
+            Geometry::Triangle(triangle) => self.draw_polygon(&triangle.to_polygon()),
+            Geometry::GeometryCollection(collection) => {
+                collection.iter().for_each(|g| self.draw(g))
+            }
+        }
+    }
+
+    /**
+     * Fills the polygons of a geometry, with holes left empty. Other geometries are drawn as with
+     * [BrailleCanvas::draw].
+     */
+    pub fn fill(&mut self, geometry: &Geometry) {
+        match geometry {
+            Geometry::Polygon(polygon) => self.fill_polygon(polygon),
+            Geometry::MultiPolygon(polygons) => polygons.iter().for_each(|p| self.fill_polygon(p)),
+            Geometry::Rect(rect) => self.fill_polygon(&rect.to_polygon()),
+            Geometry::Triangle(triangle) => self.fill_polygon(&triangle.to_polygon()),
+            Geometry::GeometryCollection(collection) => {
+                collection.iter().for_each(|g| self.fill(g))
+            }
+            geometry => self.draw(geometry),
+        }
+    }
+
+    /**
+     * The rows of braille characters, with empty characters at the ends of rows left out.
+     */
+    pub fn render(&self) -> Vec<String> {
+        let columns = self.width.div_ceil(2);
+        let rows = self.height.div_ceil(4);
+        (0..rows)
+            .map(|row| {
+                let line: String = (0..columns)
+                    .map(|column| {
+                        let mut bits = 0;
+                        for (dy, row_bits) in DOT_BITS.iter().enumerate() {
+                            for (dx, bit) in row_bits.iter().enumerate() {
+                                if self.dot(2 * column + dx, 4 * row + dy) {
+                                    bits |= bit;
+                                }
+                            }
+                        }
+                        match bits {
+                            0 => ' ',
+                            bits => char::from_u32(BRAILLE_BLANK + bits).unwrap_or(' '),
+                        }
+                    })
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    fn dot(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.dots[y * self.width + x]
+    }
+
+    fn set(&mut self, x: i64, y: i64) {
+        if (0..self.width as i64).contains(&x) && (0..self.height as i64).contains(&y) {
+            self.dots[y as usize * self.width + x as usize] = true;
+        }
+    }
+
+    /**
+     * The position of a coordinate in dots, from the top left.
+     */
+    fn project(&self, c: &Coord) -> (f64, f64) {
+        (
+            (c.x - self.bbox.min().x) * self.aspect * self.scale,
+            (self.bbox.max().y - c.y) * self.scale,
+        )
+    }
+
+    fn draw_line(&mut self, coords: &[Coord]) {
+        let dots: Vec<(i64, i64)> = coords
+            .iter()
+            .map(|c| {
+                let (x, y) = self.project(c);
+                (x.round() as i64, y.round() as i64)
+            })
+            .collect();
+        if let [(x, y)] = dots[..] {
+            self.set(x, y);
+        }
+        for pair in dots.windows(2) {
+            self.draw_segment(pair[0], pair[1]);
+        }
+    }
+
+    /**
+     * Draws a segment between two dots with Bresenham's algorithm.
+     */
+    fn draw_segment(&mut self, (mut x, mut y): (i64, i64), (x1, y1): (i64, i64)) {
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut error = dx + dy;
+        loop {
+            self.set(x, y);
+            if x == x1 && y == y1 {
+                return;
+            }
+            if 2 * error >= dy {
+                error += dy;
+                x += sx;
+            }
+            if 2 * error <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn draw_polygon(&mut self, polygon: &Polygon) {
+        self.draw_line(&polygon.exterior().0);
+        for hole in polygon.interiors() {
+            self.draw_line(&hole.0);
+        }
+    }
+
+    /**
+     * Fills a polygon by the even-odd rule, one row of dots at a time, and then draws its outline so
+     * that thin polygons still show.
+     */
+    fn fill_polygon(&mut self, polygon: &Polygon) {
+        let rings: Vec<Vec<(f64, f64)>> = std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(|ring: &LineString| ring.0.iter().map(|c| self.project(c)).collect())
+            .collect();
+        for y in 0..self.height {
+            let row = y as f64;
+            let mut crossings: Vec<f64> = rings
+                .iter()
+                .flat_map(|ring| ring.windows(2))
+                .filter(|edge| (edge[0].1 <= row) != (edge[1].1 <= row))
+                .map(|edge| {
+                    let ((x0, y0), (x1, y1)) = (edge[0], edge[1]);
+                    x0 + (row - y0) / (y1 - y0) * (x1 - x0)
+                })
+                .collect();
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                for x in span[0].ceil() as i64..=span[1].floor() as i64 {
+                    self.set(x, y as i64);
+                }
+            }
+        }
+        self.draw_polygon(polygon);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{coord, polygon, Geometry, Rect};
+
+    use super::BrailleCanvas;
+
+    #[test]
+    fn test_braille_canvas() {
+        let square = Geometry::Polygon(
+            polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 1.0)],
+        );
+        let bbox = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 1.0, y: 1.0});
+
+        let mut canvas = BrailleCanvas::new(bbox, 2, 1);
+        canvas.draw(&square);
+        assert_eq!(canvas.render(), ["⣏⣹"]);
+
+        let mut canvas = BrailleCanvas::new(bbox, 2, 1);
+        canvas.fill(&square);
+        assert_eq!(canvas.render(), ["⣿⣿"]);
+
+        // Shapes keep their proportions, so a wide canvas is only partly used.
+        let mut canvas = BrailleCanvas::new(bbox, 10, 1);
+        canvas.draw(&square);
+        assert_eq!(canvas.render(), ["⣏⣹"]);
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::VecDeque;
+
+use geo::HaversineDistance;
+use geo_types::{Coord, Point};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use rstar::primitives::GeomWithData;
+use rstar::RTree;
+
+use crate::index::search_envelopes;
+use crate::nvec::NVec;
+use crate::samplers::gen_index;
+
+type IndexedPoint = GeomWithData<[f64; 2], usize>;
+
+/**
+ * Density-based clustering (DBSCAN) of geographic points using haversine distances.
+ *
+ * Returns one label per input point, in input order. Points that belong to a cluster are labeled
+ * with the cluster index (starting at 0), while noise points are labeled `None`.
+ *
+ * https://en.wikipedia.org/wiki/DBSCAN
+ */
+pub fn dbscan(points: &[Point], eps_m: f64, min_points: usize) -> Vec<Option<usize>> {
+    let tree = RTree::bulk_load(
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| IndexedPoint::new([p.x(), p.y()], i))
+            .collect(),
+    );
+    let neighbors = |i: usize| region_query(&tree, &points[i], eps_m);
+
+    let mut labels: Vec<Option<usize>> = vec![None; points.len()];
+    let mut visited = vec![false; points.len()];
+    let mut num_clusters = 0;
+
+    for i in 0..points.len() {
+        if visited[i] {
+            continue;
+        }
+        visited[i] = true;
+
+        // Only core points can seed a new cluster.
+        let seeds = neighbors(i);
+        if seeds.len() < min_points {
+            continue;
+        }
+
+        let cluster = num_clusters;
+        num_clusters += 1;
+        labels[i] = Some(cluster);
+
+        // Expand the cluster by breadth-first search over density-reachable points.
+        let mut queue: VecDeque<usize> = seeds.into();
+        while let Some(j) = queue.pop_front() {
+            if labels[j].is_none() {
+                labels[j] = Some(cluster);
+            }
+            if visited[j] {
+                continue;
+            }
+            visited[j] = true;
+
+            let expansion = neighbors(j);
+            if expansion.len() >= min_points {
+                queue.extend(expansion);
+            }
+        }
+    }
+
+    labels
+}
+
+/**
+ * Finds the indices of all points within `eps_m` meters of `center` (including the center itself).
+ *
+ * The R-tree is queried with lat/lng envelopes that conservatively contain the search radius, and
+ * candidates are then filtered by their exact haversine distance.
+ */
+fn region_query(tree: &RTree<IndexedPoint>, center: &Point, eps_m: f64) -> Vec<usize> {
+    search_envelopes(center, eps_m)
+        .iter()
+        .flat_map(|envelope| tree.locate_in_envelope(envelope))
+        .filter(|p| {
+            let [x, y] = *p.geom();
+            center.haversine_distance(&Point::new(x, y)) <= eps_m
+        })
+        .map(|p| p.data)
+        .collect()
+}
+
+/**
+ * k-means clustering of geographic points.
+ *
+ * Points are assigned to their nearest centroid by haversine distance, and centroids are updated
+ * as the normalized mean of the member points' n-vectors, i.e. the geographic mean on the sphere.
+ * This avoids the distortions of averaging raw lat/lng values, e.g. across the antimeridian.
+ * Initial centroids are chosen with k-means++ seeding.
+ *
+ * Returns one label per input point, in input order, together with the final centroids.
+ *
+ * https://en.wikipedia.org/wiki/K-means_clustering
+ */
+pub fn kmeans<R: Rng>(
+    rng: &mut R,
+    points: &[Point],
+    k: usize,
+    max_iterations: usize,
+) -> (Vec<usize>, Vec<Point>) {
+    if points.is_empty() || k == 0 {
+        return (vec![], vec![]);
+    }
+
+    let mut centroids = kmeans_plus_plus(rng, points, usize::min(k, points.len()));
+    let mut labels = vec![0; points.len()];
+
+    for iteration in 0..max_iterations {
+        // Assignment step.
+        let mut changed = false;
+        for (label, point) in labels.iter_mut().zip(points) {
+            let nearest = nearest_centroid(&centroids, point);
+            if *label != nearest {
+                *label = nearest;
+                changed = true;
+            }
+        }
+        if !changed && iteration > 0 {
+            break;
+        }
+
+        // Update step. Empty clusters keep their previous centroid.
+        let mut sums = vec![NVec::default(); centroids.len()];
+        for (label, point) in labels.iter().zip(points) {
+            sums[*label] = sums[*label] + NVec::from(Coord::from(*point));
+        }
+        for (centroid, sum) in centroids.iter_mut().zip(sums) {
+            if sum.norm() > 0.0 {
+                *centroid = Coord::from((1.0 / sum.norm()) * sum).into();
+            }
+        }
+    }
+
+    (labels, centroids)
+}
+
+fn nearest_centroid(centroids: &[Point], point: &Point) -> usize {
+    centroids
+        .iter()
+        .map(|c| c.haversine_distance(point))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(i, _)| i)
+        .unwrap_or_default()
+}
+
+/**
+ * Chooses k initial centroids, each sampled with probability proportional to its squared distance
+ * from the nearest centroid chosen so far.
+ */
+fn kmeans_plus_plus<R: Rng>(rng: &mut R, points: &[Point], k: usize) -> Vec<Point> {
+    let mut centroids = vec![points[gen_index(rng, points.len())]];
+    while centroids.len() < k {
+        let weights = points
+            .iter()
+            .map(|p| {
+                let d = centroids
+                    .iter()
+                    .map(|c| c.haversine_distance(p))
+                    .fold(f64::INFINITY, f64::min);
+                d * d
+            })
+            .collect::<Vec<f64>>();
+
+        match WeightedIndex::new(&weights) {
+            Ok(dist) => centroids.push(points[dist.sample(rng)]),
+            // All remaining points coincide with existing centroids.
+            Err(_) => break,
+        }
+    }
+    centroids
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use super::{dbscan, kmeans};
+    use crate::samplers::create_rng;
+
+    #[test]
+    fn test_dbscan_separates_clusters_and_noise() {
+        let points = vec![
+            // Cluster around the Ferry Building, SF.
+            Point::new(-122.3937, 37.7955),
+            Point::new(-122.3938, 37.7956),
+            Point::new(-122.3936, 37.7954),
+            // Cluster around Alamo Square, SF.
+            Point::new(-122.4346, 37.7764),
+            Point::new(-122.4347, 37.7765),
+            Point::new(-122.4345, 37.7763),
+            // Isolated point in Oakland.
+            Point::new(-122.2712, 37.8044),
+        ];
+
+        let labels = dbscan(&points, 50.0, 3);
+        assert_eq!(
+            labels,
+            vec![Some(0), Some(0), Some(0), Some(1), Some(1), Some(1), None]
+        );
+    }
+
+    #[test]
+    fn test_dbscan_clusters_across_antimeridian() {
+        let points = vec![
+            // Around 180° on the equator, about 22 m apart across the antimeridian.
+            Point::new(179.9999, 0.0),
+            Point::new(-179.9999, 0.0),
+            Point::new(180.0, 0.0001),
+            // Points at the antimeridian but far from the cluster.
+            Point::new(-179.9999, 10.0),
+            Point::new(179.9999, -10.0),
+        ];
+
+        let labels = dbscan(&points, 50.0, 3);
+        assert_eq!(labels, vec![Some(0), Some(0), Some(0), None, None]);
+    }
+
+    #[test]
+    fn test_kmeans_finds_separated_clusters() {
+        let points = vec![
+            Point::new(-122.3937, 37.7955),
+            Point::new(-122.3938, 37.7956),
+            Point::new(-122.2712, 37.8044),
+            Point::new(-122.2713, 37.8045),
+        ];
+
+        let mut rng = create_rng(0);
+        let (labels, centroids) = kmeans(&mut rng, &points, 2, 100);
+        assert_eq!(centroids.len(), 2);
+        assert_eq!(labels[0], labels[1]);
+        assert_eq!(labels[2], labels[3]);
+        assert_ne!(labels[0], labels[2]);
+
+        let ferry_building = centroids[labels[0]];
+        assert!((ferry_building.x() + 122.39375).abs() < 1e-6);
+        assert!((ferry_building.y() - 37.79555).abs() < 1e-6);
+    }
+}
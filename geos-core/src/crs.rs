@@ -0,0 +1,815 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use geo::{
+    BoundingRect, Centroid, GeodesicBearing, GeodesicDestination, GeodesicDistance, MapCoords,
+    MapCoordsInPlace,
+};
+use geo_types::{coord, Coord, Geometry, Point};
+
+/// The southern and northern latitude limits of the UTM system. The polar regions beyond them are
+/// covered by the Universal Polar Stereographic (UPS) system.
+const UTM_LATITUDE_LIMITS: (f64, f64) = (-80.0, 84.0);
+/// The width in degrees of a UTM zone.
+const UTM_ZONE_WIDTH: f64 = 6.0;
+/// The semi-major axis of the WGS84 ellipsoid in meters.
+const WGS84_A: f64 = 6_378_137.0;
+/// The flattening of the WGS84 ellipsoid.
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// The scale factors on the central meridian of UTM zones and at the poles of UPS.
+const UTM_SCALE: f64 = 0.9996;
+const UPS_SCALE: f64 = 0.994;
+/// The false easting of UTM zones, and the false northing of their southern hemispheres.
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+/// The false easting and northing of UPS.
+const UPS_FALSE_ORIGIN: f64 = 2_000_000.0;
+/// The latitude limit of web mercator, at which the map is square.
+const WEB_MERCATOR_MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+/// The EPSG codes that a [Crs] can be parsed from.
+const SUPPORTED_EPSG: &str = "EPSG:3857, a UTM zone from EPSG:32601-32660 or EPSG:32701-32760, or UPS as EPSG:32661 or EPSG:32761";
+
+/**
+ * What a projected CRS will be used for, which decides the custom projection for geometries too
+ * large for a single UTM zone.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Purpose {
+    /// Areas, with an equal-area projection.
+    Area,
+    /// Distances and buffers, with a projection that preserves distances from its center.
+    Distance,
+}
+
+/**
+ * A projected coordinate reference system on the WGS84 datum, in meters.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Crs {
+    /// The spherical web mercator projection of web maps.
+    WebMercator,
+    /// A Universal Transverse Mercator zone in [1, 60].
+    Utm { zone: u8, north: bool },
+    /// The Universal Polar Stereographic projection of a polar region.
+    Ups { north: bool },
+    /// A Lambert azimuthal equal-area projection centered on a point.
+    Laea { center: Point },
+    /// An azimuthal equidistant projection centered on a point.
+    Aeqd { center: Point },
+    /// A transverse Mercator projection with its central meridian and origin at a point.
+    Tmerc { center: Point },
+}
+
+impl Crs {
+    /**
+     * The EPSG code of the CRS. Custom projections centered on a point have none.
+     */
+    pub fn epsg(&self) -> Option<u32> {
+        match self {
+            Crs::Utm { zone, north: true } => Some(32600 + *zone as u32),
+            Crs::Utm { zone, north: false } => Some(32700 + *zone as u32),
+            Crs::Ups { north: true } => Some(32661),
+            Crs::Ups { north: false } => Some(32761),
+            Crs::WebMercator => Some(3857),
+            Crs::Laea { .. } | Crs::Aeqd { .. } | Crs::Tmerc { .. } => None,
+        }
+    }
+
+    pub fn proj_string(&self) -> String {
+        let suffix = "+datum=WGS84 +units=m +no_defs";
+        match self {
+            Crs::WebMercator => "+proj=merc +a=6378137 +b=6378137 +lat_ts=0 +lon_0=0 +x_0=0 +y_0=0 +k=1 +units=m +nadgrids=@null +no_defs".to_string(),
+            Crs::Utm { zone, north } => format!(
+                "+proj=utm +zone={}{} {}",
+                zone,
+                if *north { "" } else { " +south" },
+                suffix
+            ),
+            Crs::Ups { north } => {
+                let lat = if *north { 90 } else { -90 };
+                format!(
+                    "+proj=stere +lat_0={} +lat_ts={} +lon_0=0 +k=0.994 +x_0=2000000 +y_0=2000000 {}",
+                    lat, lat, suffix
+                )
+            }
+            Crs::Laea { center } | Crs::Aeqd { center } | Crs::Tmerc { center } => format!(
+                "+proj={} +lat_0={} +lon_0={} +x_0=0 +y_0=0 {}",
+                match self {
+                    Crs::Laea { .. } => "laea",
+                    Crs::Aeqd { .. } => "aeqd",
+                    _ => "tmerc",
+                },
+                round_micro_degrees(center.y()),
+                round_micro_degrees(center.x()),
+                suffix
+            ),
+        }
+    }
+
+    /**
+     * Projects a lng/lat coordinate in degrees into the CRS. Latitudes beyond the limits of web
+     * mercator are clamped to them.
+     */
+    pub fn project(&self, c: Coord) -> Coord {
+        match self {
+            Crs::WebMercator => {
+                let lat =
+                    c.y.clamp(-WEB_MERCATOR_MAX_LATITUDE, WEB_MERCATOR_MAX_LATITUDE);
+                coord! {
+                    x: WGS84_A * c.x.to_radians(),
+                    y: WGS84_A * (FRAC_PI_4 + lat.to_radians() / 2.0).tan().ln(),
+                }
+            }
+            Crs::Utm { zone, north } => {
+                let Coord { x, y } = transverse_mercator(utm_central_meridian(*zone), c);
+                coord! {
+                    x: UTM_FALSE_EASTING + UTM_SCALE * x,
+                    y: utm_false_northing(*north) + UTM_SCALE * y,
+                }
+            }
+            Crs::Ups { north } => polar_stereographic(*north, c),
+            Crs::Laea { center } => lambert_azimuthal_equal_area(*center, c),
+            Crs::Aeqd { center } => LocalFrame::new(LocalProjection::Aeqd, *center).project(c),
+            Crs::Tmerc { center } => LocalFrame::new(LocalProjection::Tmerc, *center).project(c),
+        }
+    }
+
+    /**
+     * Unprojects a coordinate in the CRS to lng/lat in degrees.
+     */
+    pub fn unproject(&self, c: Coord) -> Coord {
+        match self {
+            Crs::WebMercator => coord! {
+                x: (c.x / WGS84_A).to_degrees(),
+                y: (2.0 * (c.y / WGS84_A).exp().atan() - FRAC_PI_2).to_degrees(),
+            },
+            Crs::Utm { zone, north } => inverse_transverse_mercator(
+                utm_central_meridian(*zone),
+                coord! {
+                    x: (c.x - UTM_FALSE_EASTING) / UTM_SCALE,
+                    y: (c.y - utm_false_northing(*north)) / UTM_SCALE,
+                },
+            ),
+            Crs::Ups { north } => inverse_polar_stereographic(*north, c),
+            Crs::Laea { center } => inverse_lambert_azimuthal_equal_area(*center, c),
+            Crs::Aeqd { center } => LocalFrame::new(LocalProjection::Aeqd, *center).unproject(c),
+            Crs::Tmerc { center } => LocalFrame::new(LocalProjection::Tmerc, *center).unproject(c),
+        }
+    }
+
+    /**
+     * Projects every coordinate of a lng/lat geometry into the CRS.
+     */
+    pub fn project_geometry(&self, geometry: &Geometry) -> Geometry {
+        geometry.map_coords(|c| self.project(c))
+    }
+
+    /**
+     * Unprojects every coordinate of a geometry in the CRS to lng/lat.
+     */
+    pub fn unproject_geometry(&self, geometry: &Geometry) -> Geometry {
+        geometry.map_coords(|c| self.unproject(c))
+    }
+}
+
+/**
+ * Parses a CRS from its EPSG code, as `EPSG:<code>` or just the code. Only the CRSs with EPSG codes
+ * are supported: web mercator, the UTM zones, and UPS.
+ */
+impl FromStr for Crs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim();
+        let code = match code.get(..5) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("epsg:") => &code[5..],
+            _ => code,
+        };
+        let crs = match code.parse::<u32>() {
+            Ok(3857) => Some(Crs::WebMercator),
+            Ok(32661) => Some(Crs::Ups { north: true }),
+            Ok(32761) => Some(Crs::Ups { north: false }),
+            Ok(code @ 32601..=32660) => Some(Crs::Utm {
+                zone: (code - 32600) as u8,
+                north: true,
+            }),
+            Ok(code @ 32701..=32760) => Some(Crs::Utm {
+                zone: (code - 32700) as u8,
+                north: false,
+            }),
+            _ => None,
+        };
+        crs.ok_or_else(|| format!("unsupported CRS '{}', expected {}", s, SUPPORTED_EPSG))
+    }
+}
+
+/**
+ * The coordinate reference system of coordinates that are read or written: lng/lat degrees on
+ * WGS84, which every operation works in, or a projected CRS to convert from and to.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum CoordinateSystem {
+    #[default]
+    Wgs84,
+    Projected(Crs),
+}
+
+impl CoordinateSystem {
+    /**
+     * The EPSG code of the coordinate system, as used for the SRIDs of PostGIS, if it has one.
+     */
+    pub fn epsg(&self) -> Option<u32> {
+        match self {
+            CoordinateSystem::Wgs84 => Some(4326),
+            CoordinateSystem::Projected(crs) => crs.epsg(),
+        }
+    }
+
+    /**
+     * Converts a geometry in this coordinate system to lng/lat.
+     */
+    pub fn to_wgs84(&self, geometry: &mut Geometry) {
+        if let CoordinateSystem::Projected(crs) = self {
+            geometry.map_coords_in_place(|c| crs.unproject(c));
+        }
+    }
+
+    /**
+     * Converts a lng/lat geometry to this coordinate system.
+     */
+    pub fn from_wgs84(&self, geometry: &mut Geometry) {
+        if let CoordinateSystem::Projected(crs) = self {
+            geometry.map_coords_in_place(|c| crs.project(c));
+        }
+    }
+}
+
+/**
+ * Parses `EPSG:4326`, or its aliases `WGS84` and `CRS84`, as lng/lat, and anything else as a
+ * projected CRS.
+ */
+impl FromStr for CoordinateSystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "EPSG:4326" | "4326" | "WGS84" | "CRS84" => Ok(CoordinateSystem::Wgs84),
+            _ => s.parse().map(CoordinateSystem::Projected).map_err(|_| {
+                format!(
+                    "unsupported CRS '{}', expected EPSG:4326, {}",
+                    s, SUPPORTED_EPSG
+                )
+            }),
+        }
+    }
+}
+
+impl Display for CoordinateSystem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoordinateSystem::Wgs84 => write!(f, "EPSG:4326"),
+            CoordinateSystem::Projected(crs) => write!(f, "{}", crs),
+        }
+    }
+}
+
+/**
+ * Formats the CRS as `EPSG:<code>`, or as its PROJ string if it has no EPSG code.
+ */
+impl Display for Crs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.epsg() {
+            Some(code) => write!(f, "EPSG:{}", code),
+            None => write!(f, "{}", self.proj_string()),
+        }
+    }
+}
+
+/**
+ * Suggests a projected CRS in which a geometry's areas or distances can be measured accurately.
+ * Geometries within 3 degrees of longitude of a UTM zone's central meridian use that zone, and
+ * geometries entirely within a polar region use UPS, both of which have scale errors under 0.1%.
+ * Larger geometries get a custom projection centered on their centroid. Returns `None` for empty
+ * geometries.
+ */
+pub fn suggest_crs(geometry: &Geometry, purpose: Purpose) -> Option<Crs> {
+    let bbox = geometry.bounding_rect()?;
+    let (min_lat, max_lat) = UTM_LATITUDE_LIMITS;
+    if bbox.min().y >= max_lat {
+        return Some(Crs::Ups { north: true });
+    }
+    if bbox.max().y <= min_lat {
+        return Some(Crs::Ups { north: false });
+    }
+
+    // The zone whose central meridian is closest to the center of the geometry.
+    let center_lng = (bbox.min().x + bbox.max().x) / 2.0;
+    let zone = (((center_lng + 180.0) / UTM_ZONE_WIDTH).floor() as i32).clamp(0, 59);
+    let central_meridian = -180.0 + UTM_ZONE_WIDTH * (zone as f64 + 0.5);
+    let half_width = UTM_ZONE_WIDTH / 2.0;
+    let fits_zone = bbox.min().x >= central_meridian - half_width
+        && bbox.max().x <= central_meridian + half_width
+        && bbox.min().y >= min_lat
+        && bbox.max().y <= max_lat;
+    // Geometries straddling the equator use the hemisphere that most of them are in.
+    let north = bbox.min().y + bbox.max().y >= 0.0;
+    if fits_zone {
+        return Some(Crs::Utm {
+            zone: zone as u8 + 1,
+            north,
+        });
+    }
+
+    let center = geometry.centroid()?;
+    Some(match purpose {
+        Purpose::Area => Crs::Laea { center },
+        Purpose::Distance => Crs::Aeqd { center },
+    })
+}
+
+fn round_micro_degrees(degrees: f64) -> f64 {
+    (degrees * 1e6).round() / 1e6
+}
+
+//==================================================
+// Local projections.
+//==================================================
+/**
+ * A projection onto a plane in meters for metric work on a region around a center point.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LocalProjection {
+    /// Azimuthal equidistant: distances and azimuths from the center are exact, computed along
+    /// geodesics on the WGS84 ellipsoid.
+    Aeqd,
+    /// Transverse Mercator with a scale of 1 on the central meridian: conformal, so shapes and
+    /// angles are kept everywhere, with a scale error under 0.1% within about 300 km of the center's
+    /// meridian.
+    Tmerc,
+}
+
+/**
+ * A local plane in meters around a center point, with x east and y north of the center. Projecting
+ * and then unprojecting returns the original coordinates to within a millimeter for points a few
+ * hundred kilometers from the center.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LocalFrame {
+    pub projection: LocalProjection,
+    pub center: Point,
+}
+
+impl LocalFrame {
+    pub fn new(projection: LocalProjection, center: Point) -> Self {
+        Self { projection, center }
+    }
+
+    /**
+     * A frame centered on the centroid of a geometry, or `None` if it is empty.
+     */
+    pub fn centered_on(geometry: &Geometry, projection: LocalProjection) -> Option<Self> {
+        Some(Self::new(projection, geometry.centroid()?))
+    }
+
+    /**
+     * The frame as a CRS, e.g. to give its PROJ string to other tools.
+     */
+    pub fn crs(&self) -> Crs {
+        match self.projection {
+            LocalProjection::Aeqd => Crs::Aeqd {
+                center: self.center,
+            },
+            LocalProjection::Tmerc => Crs::Tmerc {
+                center: self.center,
+            },
+        }
+    }
+
+    /**
+     * Projects a lng/lat coordinate in degrees into the frame.
+     */
+    pub fn project(&self, c: Coord) -> Coord {
+        match self.projection {
+            LocalProjection::Aeqd => {
+                let p = Point::from(c);
+                let distance = self.center.geodesic_distance(&p);
+                if distance == 0.0 {
+                    return coord! {x: 0.0, y: 0.0};
+                }
+                let azimuth = self.center.geodesic_bearing(p).to_radians();
+                coord! {x: distance * azimuth.sin(), y: distance * azimuth.cos()}
+            }
+            LocalProjection::Tmerc => {
+                let Coord { x, y } = transverse_mercator(self.center.x(), c);
+                coord! {x: x, y: y - transverse_mercator(self.center.x(), self.center.0).y}
+            }
+        }
+    }
+
+    /**
+     * Unprojects a coordinate in the frame to lng/lat in degrees. Longitudes are kept within 180
+     * degrees of the center, so geometries around the antimeridian come back unsplit.
+     */
+    pub fn unproject(&self, c: Coord) -> Coord {
+        let lng_lat = match self.projection {
+            LocalProjection::Aeqd => {
+                let azimuth = c.x.atan2(c.y).to_degrees();
+                self.center.geodesic_destination(azimuth, c.x.hypot(c.y)).0
+            }
+            LocalProjection::Tmerc => {
+                let y0 = transverse_mercator(self.center.x(), self.center.0).y;
+                inverse_transverse_mercator(self.center.x(), coord! {x: c.x, y: c.y + y0})
+            }
+        };
+        coord! {
+            x: self.center.x() + normalize_lng(lng_lat.x - self.center.x()),
+            y: lng_lat.y,
+        }
+    }
+
+    /**
+     * Projects every coordinate of a geometry into the frame.
+     */
+    pub fn project_geometry(&self, geometry: &Geometry) -> Geometry {
+        geometry.map_coords(|c| self.project(c))
+    }
+
+    /**
+     * Unprojects every coordinate of a geometry in the frame to lng/lat.
+     */
+    pub fn unproject_geometry(&self, geometry: &Geometry) -> Geometry {
+        geometry.map_coords(|c| self.unproject(c))
+    }
+}
+
+/**
+ * Wraps a longitude difference in degrees into [-180, 180).
+ */
+fn normalize_lng(lng: f64) -> f64 {
+    (lng + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/**
+ * The coefficients of Krüger's series for the transverse Mercator projection to third order in
+ * the third flattening `n`, which are accurate to about a millimeter within a few hundred
+ * kilometers of the central meridian: the rectifying radius, then the forward (alpha), inverse
+ * (beta), and conformal-to-geodetic latitude (delta) coefficients.
+ *
+ * Karney, C. F. F. "Transverse Mercator with an accuracy of a few nanometers." 2011.
+ */
+struct Kruger {
+    radius: f64,
+    alpha: [f64; 3],
+    beta: [f64; 3],
+    delta: [f64; 3],
+}
+
+fn kruger() -> Kruger {
+    let n = WGS84_F / (2.0 - WGS84_F);
+    let (n2, n3) = (n * n, n * n * n);
+    Kruger {
+        radius: WGS84_A / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0),
+        alpha: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0,
+            13.0 * n2 / 48.0 - 3.0 * n3 / 5.0,
+            61.0 * n3 / 240.0,
+        ],
+        beta: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0,
+            n2 / 48.0 + n3 / 15.0,
+            17.0 * n3 / 480.0,
+        ],
+        delta: [
+            2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3,
+            7.0 * n2 / 3.0 - 8.0 * n3 / 5.0,
+            56.0 * n3 / 15.0,
+        ],
+    }
+}
+
+/**
+ * Projects a lng/lat coordinate with transverse Mercator on the WGS84 ellipsoid, with a scale of 1
+ * on the central meridian `lng0` and y measured from the equator.
+ */
+fn transverse_mercator(lng0: f64, c: Coord) -> Coord {
+    let k = kruger();
+    let n = WGS84_F / (2.0 - WGS84_F);
+    let e = 2.0 * n.sqrt() / (1.0 + n);
+    let (lat, lng) = (c.y.to_radians(), normalize_lng(c.x - lng0).to_radians());
+    let t = (lat.sin().atanh() - e * (e * lat.sin()).atanh()).sinh();
+    let xi = t.atan2(lng.cos());
+    let eta = (lng.sin() / (1.0 + t * t).sqrt()).atanh();
+    let (mut x, mut y) = (eta, xi);
+    for (j, alpha) in (1..=3).zip(k.alpha) {
+        let j = 2.0 * j as f64;
+        x += alpha * (j * xi).cos() * (j * eta).sinh();
+        y += alpha * (j * xi).sin() * (j * eta).cosh();
+    }
+    coord! {x: k.radius * x, y: k.radius * y}
+}
+
+/**
+ * The inverse of [transverse_mercator].
+ */
+fn inverse_transverse_mercator(lng0: f64, c: Coord) -> Coord {
+    let k = kruger();
+    let (xi, eta) = (c.y / k.radius, c.x / k.radius);
+    let (mut xi_prime, mut eta_prime) = (xi, eta);
+    for (j, beta) in (1..=3).zip(k.beta) {
+        let j = 2.0 * j as f64;
+        xi_prime -= beta * (j * xi).sin() * (j * eta).cosh();
+        eta_prime -= beta * (j * xi).cos() * (j * eta).sinh();
+    }
+    let chi = (xi_prime.sin() / eta_prime.cosh()).clamp(-1.0, 1.0).asin();
+    let mut lat = chi;
+    for (j, delta) in (1..=3).zip(k.delta) {
+        lat += delta * (2.0 * j as f64 * chi).sin();
+    }
+    let lng = eta_prime.sinh().atan2(xi_prime.cos());
+    coord! {
+        x: lng0 + normalize_lng(lng.to_degrees()),
+        y: lat.clamp(-PI / 2.0, PI / 2.0).to_degrees(),
+    }
+}
+
+fn utm_central_meridian(zone: u8) -> f64 {
+    -180.0 + UTM_ZONE_WIDTH * (zone as f64 - 0.5)
+}
+
+fn utm_false_northing(north: bool) -> f64 {
+    if north {
+        0.0
+    } else {
+        UTM_FALSE_NORTHING_SOUTH
+    }
+}
+
+fn eccentricity() -> f64 {
+    (WGS84_F * (2.0 - WGS84_F)).sqrt()
+}
+
+/**
+ * Projects a lng/lat coordinate with the UPS polar stereographic projection of the northern or
+ * southern polar region.
+ */
+fn polar_stereographic(north: bool, c: Coord) -> Coord {
+    let e = eccentricity();
+    let (lat, lng) = (c.y.to_radians(), c.x.to_radians());
+    let lat = if north { lat } else { -lat };
+    let t = (FRAC_PI_4 - lat / 2.0).tan()
+        / ((1.0 - e * lat.sin()) / (1.0 + e * lat.sin())).powf(e / 2.0);
+    let rho = 2.0 * WGS84_A * UPS_SCALE * t / polar_stereographic_divisor(e);
+    let y = if north {
+        -rho * lng.cos()
+    } else {
+        rho * lng.cos()
+    };
+    coord! {x: UPS_FALSE_ORIGIN + rho * lng.sin(), y: UPS_FALSE_ORIGIN + y}
+}
+
+/**
+ * The inverse of [polar_stereographic].
+ */
+fn inverse_polar_stereographic(north: bool, c: Coord) -> Coord {
+    let e = eccentricity();
+    let (x, y) = (c.x - UPS_FALSE_ORIGIN, c.y - UPS_FALSE_ORIGIN);
+    let t = x.hypot(y) * polar_stereographic_divisor(e) / (2.0 * WGS84_A * UPS_SCALE);
+    // The geodetic latitude converges in a few iterations from the conformal latitude.
+    let mut lat = FRAC_PI_2 - 2.0 * t.atan();
+    for _ in 0..8 {
+        lat = FRAC_PI_2
+            - 2.0 * (t * ((1.0 - e * lat.sin()) / (1.0 + e * lat.sin())).powf(e / 2.0)).atan();
+    }
+    let (lat, lng) = if north {
+        (lat, x.atan2(-y))
+    } else {
+        (-lat, x.atan2(y))
+    };
+    coord! {x: lng.to_degrees(), y: lat.to_degrees()}
+}
+
+fn polar_stereographic_divisor(e: f64) -> f64 {
+    ((1.0 + e).powf(1.0 + e) * (1.0 - e).powf(1.0 - e)).sqrt()
+}
+
+/**
+ * The authalic `q` function of a latitude in radians, from which equal-area projections on the
+ * ellipsoid are computed.
+ *
+ * Snyder, J. P. "Map Projections: A Working Manual." 1987, pp. 187-190.
+ */
+fn authalic_q(e: f64, lat: f64) -> f64 {
+    let sin = lat.sin();
+    (1.0 - e * e)
+        * (sin / (1.0 - e * e * sin * sin) - ((1.0 - e * sin) / (1.0 + e * sin)).ln() / (2.0 * e))
+}
+
+/**
+ * The constants of a Lambert azimuthal equal-area projection centered on a point: the authalic
+ * latitude of the center, the radius of the authalic sphere, and the scale correction `D`.
+ */
+fn laea_constants(center: Point) -> (f64, f64, f64) {
+    let e = eccentricity();
+    let lat1 = center.y().to_radians();
+    let qp = authalic_q(e, FRAC_PI_2);
+    let beta1 = (authalic_q(e, lat1) / qp).clamp(-1.0, 1.0).asin();
+    let rq = WGS84_A * (qp / 2.0).sqrt();
+    let m1 = lat1.cos() / (1.0 - e * e * lat1.sin().powi(2)).sqrt();
+    (beta1, rq, WGS84_A * m1 / (rq * beta1.cos()))
+}
+
+fn lambert_azimuthal_equal_area(center: Point, c: Coord) -> Coord {
+    let e = eccentricity();
+    let (beta1, rq, d) = laea_constants(center);
+    let beta = (authalic_q(e, c.y.to_radians()) / authalic_q(e, FRAC_PI_2))
+        .clamp(-1.0, 1.0)
+        .asin();
+    let lng = normalize_lng(c.x - center.x()).to_radians();
+    let b =
+        rq * (2.0 / (1.0 + beta1.sin() * beta.sin() + beta1.cos() * beta.cos() * lng.cos())).sqrt();
+    coord! {
+        x: b * d * beta.cos() * lng.sin(),
+        y: b / d * (beta1.cos() * beta.sin() - beta1.sin() * beta.cos() * lng.cos()),
+    }
+}
+
+/**
+ * The inverse of [lambert_azimuthal_equal_area].
+ */
+fn inverse_lambert_azimuthal_equal_area(center: Point, c: Coord) -> Coord {
+    let e = eccentricity();
+    let (beta1, rq, d) = laea_constants(center);
+    let rho = (c.x / d).hypot(d * c.y);
+    if rho == 0.0 {
+        return center.0;
+    }
+    let ce = 2.0 * (rho / (2.0 * rq)).clamp(-1.0, 1.0).asin();
+    let beta = (ce.cos() * beta1.sin() + d * c.y * ce.sin() * beta1.cos() / rho)
+        .clamp(-1.0, 1.0)
+        .asin();
+    let lng = (c.x * ce.sin())
+        .atan2(d * rho * beta1.cos() * ce.cos() - d * d * c.y * beta1.sin() * ce.sin());
+    // Series from the authalic to the geodetic latitude.
+    let (e2, e4, e6) = (e * e, e.powi(4), e.powi(6));
+    let lat = beta
+        + (e2 / 3.0 + 31.0 * e4 / 180.0 + 517.0 * e6 / 5040.0) * (2.0 * beta).sin()
+        + (23.0 * e4 / 360.0 + 251.0 * e6 / 3780.0) * (4.0 * beta).sin()
+        + 761.0 * e6 / 45360.0 * (6.0 * beta).sin();
+    coord! {x: center.x() + normalize_lng(lng.to_degrees()), y: lat.to_degrees()}
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{coord, point, polygon, Geometry};
+
+    use super::{suggest_crs, CoordinateSystem, Crs, LocalFrame, LocalProjection, Purpose};
+
+    #[test]
+    fn test_suggest_crs() {
+        let sf = Geometry::from(point!(x: -122.4194, y: 37.7749));
+        let crs = suggest_crs(&sf, Purpose::Area).unwrap();
+        assert_eq!(
+            crs,
+            Crs::Utm {
+                zone: 10,
+                north: true
+            }
+        );
+        assert_eq!(crs.to_string(), "EPSG:32610");
+        assert_eq!(
+            crs.proj_string(),
+            "+proj=utm +zone=10 +datum=WGS84 +units=m +no_defs"
+        );
+
+        let sydney = Geometry::from(point!(x: 151.2093, y: -33.8688));
+        assert_eq!(
+            suggest_crs(&sydney, Purpose::Area).unwrap().to_string(),
+            "EPSG:32756"
+        );
+
+        let svalbard = Geometry::from(point!(x: 15.0, y: 85.0));
+        assert_eq!(
+            suggest_crs(&svalbard, Purpose::Area).unwrap().to_string(),
+            "EPSG:32661"
+        );
+
+        // Too wide for one zone.
+        let wide = Geometry::from(
+            polygon![(x: -10.0, y: 0.0), (x: 10.0, y: 0.0), (x: 10.0, y: 10.0), (x: -10.0, y: 10.0)],
+        );
+        assert_eq!(
+            suggest_crs(&wide, Purpose::Area).unwrap().to_string(),
+            "+proj=laea +lat_0=5 +lon_0=0 +x_0=0 +y_0=0 +datum=WGS84 +units=m +no_defs"
+        );
+        assert!(matches!(
+            suggest_crs(&wide, Purpose::Distance),
+            Some(Crs::Aeqd { .. })
+        ));
+    }
+
+    #[test]
+    fn test_local_frame() {
+        let center = point!(x: -122.4, y: 37.8);
+        let aeqd = LocalFrame::new(LocalProjection::Aeqd, center);
+        let tmerc = LocalFrame::new(LocalProjection::Tmerc, center);
+        assert_eq!(
+            tmerc.crs().proj_string(),
+            "+proj=tmerc +lat_0=37.8 +lon_0=-122.4 +x_0=0 +y_0=0 +datum=WGS84 +units=m +no_defs"
+        );
+
+        // Both are exact along the central meridian.
+        let north = aeqd.unproject(coord! {x: 0.0, y: 100_000.0});
+        assert!((north.x - center.x()).abs() < 1e-9);
+        let projected = tmerc.project(north);
+        assert!(projected.x.abs() < 1e-6 && (projected.y - 100_000.0).abs() < 1e-3);
+
+        for frame in [aeqd, tmerc] {
+            assert_eq!(frame.project(center.0), coord! {x: 0.0, y: 0.0});
+            for c in [
+                coord! {x: -122.41, y: 37.79},
+                coord! {x: -121.0, y: 39.0},
+                coord! {x: -118.2, y: 34.05},
+            ] {
+                let round_trip = frame.unproject(frame.project(c));
+                assert!(
+                    (round_trip.x - c.x).abs() < 1e-8 && (round_trip.y - c.y).abs() < 1e-8,
+                    "{:?} came back as {:?}",
+                    c,
+                    round_trip
+                );
+            }
+        }
+
+        // Longitudes stay within 180 degrees of the center.
+        let pacific = LocalFrame::new(LocalProjection::Tmerc, point!(x: 179.9, y: 0.0));
+        let east = pacific.unproject(pacific.project(coord! {x: -179.9, y: 0.0}));
+        assert!((east.x - 180.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_crs_projections() {
+        let sf = coord! {x: -122.4194, y: 37.7749};
+        let web_mercator: Crs = "EPSG:3857".parse().unwrap();
+        let projected = web_mercator.project(sf);
+        assert!((projected.x - -13627665.27).abs() < 0.01);
+        assert!((projected.y - 4547675.35).abs() < 0.01);
+
+        let utm: Crs = "32610".parse().unwrap();
+        assert_eq!(
+            utm,
+            Crs::Utm {
+                zone: 10,
+                north: true
+            }
+        );
+        let projected = utm.project(sf);
+        assert!((projected.x - 551130.77).abs() < 0.01 && (projected.y - 4180998.88).abs() < 0.01);
+        let central = Crs::Utm {
+            zone: 31,
+            north: false,
+        };
+        assert_eq!(
+            central.project(coord! {x: 3.0, y: 0.0}),
+            coord! {x: 500_000.0, y: 10_000_000.0}
+        );
+        let pole = Crs::Ups { north: true }.project(coord! {x: 0.0, y: 90.0});
+        assert!((pole.x - 2_000_000.0).abs() < 1e-6 && (pole.y - 2_000_000.0).abs() < 1e-6);
+
+        let center = point!(x: -122.4, y: 37.8);
+        for (crs, c) in [
+            (web_mercator, sf),
+            (utm, sf),
+            (
+                Crs::Utm {
+                    zone: 56,
+                    north: false,
+                },
+                coord! {x: 151.2093, y: -33.8688},
+            ),
+            (Crs::Ups { north: true }, coord! {x: 15.0, y: 85.0}),
+            (Crs::Ups { north: false }, coord! {x: -60.0, y: -82.5}),
+            (Crs::Laea { center }, coord! {x: -118.2, y: 34.05}),
+            (Crs::Laea { center }, center.0),
+            (Crs::Aeqd { center }, sf),
+        ] {
+            let round_trip = crs.unproject(crs.project(c));
+            assert!(
+                (round_trip.x - c.x).abs() < 1e-8 && (round_trip.y - c.y).abs() < 1e-8,
+                "{} returned {:?} as {:?}",
+                crs,
+                c,
+                round_trip
+            );
+        }
+
+        assert_eq!("WGS84".parse(), Ok(CoordinateSystem::Wgs84));
+        assert_eq!(
+            "epsg:3857".parse(),
+            Ok(CoordinateSystem::Projected(Crs::WebMercator))
+        );
+        assert!("EPSG:2154".parse::<CoordinateSystem>().is_err());
+        assert!("EPSG:32600".parse::<Crs>().is_err());
+    }
+}
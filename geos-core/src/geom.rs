@@ -0,0 +1,1260 @@
+use std::borrow::Cow;
+use std::error::Error;
+
+use geo::winding_order::WindingOrder;
+use geo::{
+    Area, BooleanOps, BoundingRect, Closest, CoordsIter, GeodesicBearing, GeodesicDestination,
+    GeodesicDistance, HaversineBearing, HaversineClosestPoint, HaversineDestination,
+    HaversineDistance, Intersects, LinesIter, Polygon, Rect, Translate, TriangulateEarcut, Winding,
+};
+use geo_types::{Coord, Geometry, GeometryCollection, Line, LineString, MultiPolygon, Point};
+use itertools::{Either, Itertools};
+use rstar::primitives::{GeomWithData, Line as IndexedLine, Rectangle};
+use rstar::{RTree, AABB};
+
+use crate::nvec::NVec;
+use crate::parallel::par_map;
+use crate::validity::{check_area, check_polygon, DegeneratePolygon, InvalidPolygon};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DistanceMethod {
+    /// Great-circle distance on a spherical Earth. Fast, but with up to ~0.5% error.
+    Haversine,
+    /// Geodesic distance on the WGS84 ellipsoid, computed with Karney's algorithm to within 15
+    /// nanometers.
+    ///
+    /// Karney, C. F. F. "Algorithms for geodesics." 2013.
+    Geodesic,
+}
+
+/**
+ * Cuts a polygon by intersection with partitions represented as polygons.
+ *
+ * There is no strict requirement that the partitions actually be mathematical
+ * partitions, although that is the intended use-case.
+ */
+pub fn cut_polygon(
+    polygon: &Polygon,
+    partitions: &[Polygon],
+    normalize_winding: bool,
+) -> Result<Vec<Polygon>, InvalidPolygon> {
+    cut_polygons(std::slice::from_ref(polygon), partitions, normalize_winding)
+}
+
+/**
+ * Cuts each of several polygons by intersection with partitions, in order of polygons and then of
+ * partitions. Polygons and partitions that cross the antimeridian are split on it first, so pieces
+ * along it are split too. The polygons are checked with `check_polygon` first, since intersections
+ * of invalid polygons can panic or return the wrong area; partitions are assumed to be valid.
+ * With `normalize_winding`, exteriors are wound counterclockwise and holes clockwise before the
+ * polygons are intersected, copying only the polygons with a ring to reverse.
+ *
+ * An R-tree over the partitions limits each polygon to the partitions whose bounding boxes it
+ * intersects, and an R-tree over each polygon's edges finds the partitions that its boundary does
+ * not come near. Those lie entirely inside or outside of the polygon, so only partitions along the
+ * boundary need an exact intersection. This keeps fine-grained cuts of large regions tractable,
+ * since interior partitions vastly outnumber those on the boundary. The intersections are
+ * independent, so they are computed in parallel.
+ */
+pub fn cut_polygons(
+    polygons: &[Polygon],
+    partitions: &[Polygon],
+    normalize_winding: bool,
+) -> Result<Vec<Polygon>, InvalidPolygon> {
+    let pieces = cut_polygons_by_partition(polygons, partitions, normalize_winding)?;
+    Ok(pieces.into_iter().map(|(_, piece)| piece).collect())
+}
+
+/**
+ * Like `cut_polygons`, but with the index of the partition that each piece lies within, e.g. to
+ * label the pieces with their cells.
+ */
+pub fn cut_polygons_by_partition(
+    polygons: &[Polygon],
+    partitions: &[Polygon],
+    normalize_winding: bool,
+) -> Result<Vec<(usize, Polygon)>, InvalidPolygon> {
+    polygons.iter().try_for_each(check_polygon)?;
+    let polygons = split_all_antimeridian(polygons, normalize_winding);
+    // Partitions split on the antimeridian keep the index of the partition they were split from.
+    let partitions = partitions
+        .iter()
+        .enumerate()
+        .flat_map(|(i, p)| {
+            split_all_antimeridian(std::slice::from_ref(p), false)
+                .into_iter()
+                .map(move |p| (i, p))
+        })
+        .collect_vec();
+    let partition_tree = RTree::bulk_load(
+        partitions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, p))| {
+                Some(GeomWithData::new(
+                    Rectangle::from_aabb(envelope(p.bounding_rect()?)),
+                    i,
+                ))
+            })
+            .collect_vec(),
+    );
+
+    Ok(polygons
+        .iter()
+        .flat_map(|polygon| {
+            let Some(bbox) = polygon.bounding_rect() else {
+                return vec![];
+            };
+            let boundary = BoundaryIndex::new(polygon);
+            let candidates = partition_tree
+                .locate_in_envelope_intersecting(&envelope(bbox))
+                .map(|candidate| candidate.data)
+                .sorted()
+                .collect_vec();
+            par_map(&candidates, |&i| {
+                let (index, partition) = &partitions[i];
+                let pieces = boundary.intersection(polygon, partition);
+                pieces
+                    .into_iter()
+                    .map(|piece| (*index, piece))
+                    .collect_vec()
+            })
+        })
+        .flatten()
+        .collect_vec())
+}
+
+/// The number of partitions cut in parallel at a time by [PolygonCutter::cut_each].
+const CUT_BATCH: usize = 1024;
+
+/// The finest edge proportion that `partition_region` accepts, which divides a bounding box into a
+/// million regions.
+pub const MIN_EDGE_PROPORTION: f64 = 0.001;
+
+/**
+ * An edge proportion that would divide a bounding box into no regions, or too many to build.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InvalidEdgeProportion(pub f64);
+
+impl std::fmt::Display for InvalidEdgeProportion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "edge proportion {} must be a finite number of at least {}",
+            self.0, MIN_EDGE_PROPORTION
+        )
+    }
+}
+
+impl Error for InvalidEdgeProportion {}
+
+/**
+ * Cuts polygons by partitions given one at a time, such as the cells of a covering as they are
+ * produced, so that the pieces can be written out without the partitions all being held in
+ * memory. The polygons are checked, oriented if `normalize_winding` is set, and indexed up front.
+ */
+pub struct PolygonCutter<'a> {
+    polygons: Vec<(Cow<'a, Polygon>, Rect, BoundaryIndex)>,
+}
+
+impl<'a> PolygonCutter<'a> {
+    pub fn new(polygons: &'a [Polygon], normalize_winding: bool) -> Result<Self, InvalidPolygon> {
+        polygons.iter().try_for_each(check_polygon)?;
+        let polygons = split_all_antimeridian(polygons, normalize_winding)
+            .into_iter()
+            .filter_map(|polygon| {
+                let bbox = polygon.bounding_rect()?;
+                let boundary = BoundaryIndex::new(&polygon);
+                Some((polygon, bbox, boundary))
+            })
+            .collect();
+        Ok(Self { polygons })
+    }
+
+    /**
+     * The pieces of the polygons that lie within a partition, in order of the polygons.
+     */
+    pub fn cut(&self, partition: &Polygon) -> Vec<Polygon> {
+        split_all_antimeridian(std::slice::from_ref(partition), false)
+            .iter()
+            .flat_map(|partition| {
+                let bbox = partition.bounding_rect();
+                self.polygons
+                    .iter()
+                    .filter(move |(_, polygon_bbox, _)| {
+                        bbox.is_some_and(|bbox| bbox.intersects(polygon_bbox))
+                    })
+                    .flat_map(move |(polygon, _, boundary)| {
+                        boundary.intersection(polygon, partition)
+                    })
+            })
+            .collect()
+    }
+
+    /**
+     * Cuts the polygons by each partition in turn, with the partitions labeled by some data, e.g.
+     * their cells. Partitions are cut in parallel batches, and the pieces come out in order of the
+     * partitions.
+     */
+    pub fn cut_each<'s, T: Clone + Send + Sync + 's>(
+        &'s self,
+        partitions: impl Iterator<Item = (T, Polygon)> + 's,
+    ) -> impl Iterator<Item = (T, Polygon)> + 's {
+        let mut partitions = partitions.fuse();
+        let mut pieces = vec![].into_iter();
+        std::iter::from_fn(move || loop {
+            if let Some(piece) = pieces.next() {
+                return Some(piece);
+            }
+            let batch = partitions.by_ref().take(CUT_BATCH).collect_vec();
+            if batch.is_empty() {
+                return None;
+            }
+            pieces = par_map(&batch, |(data, partition)| {
+                self.cut(partition)
+                    .into_iter()
+                    .map(|piece| (data.clone(), piece))
+                    .collect_vec()
+            })
+            .into_iter()
+            .flatten()
+            .collect_vec()
+            .into_iter();
+        })
+    }
+}
+
+/**
+ * An R-tree over the edges of a polygon's rings.
+ */
+pub(crate) struct BoundaryIndex {
+    edges: RTree<IndexedLine<[f64; 2]>>,
+    max_x: f64,
+}
+
+impl BoundaryIndex {
+    pub(crate) fn new(polygon: &Polygon) -> Self {
+        let edges = polygon
+            .lines_iter()
+            .map(|line| IndexedLine::new(line.start.x_y().into(), line.end.x_y().into()))
+            .collect_vec();
+        let max_x = polygon.bounding_rect().map_or(0.0, |bbox| bbox.max().x);
+        Self {
+            edges: RTree::bulk_load(edges),
+            max_x,
+        }
+    }
+
+    fn intersection(&self, polygon: &Polygon, partition: &Polygon) -> Vec<Polygon> {
+        let Some(bbox) = partition.bounding_rect() else {
+            return vec![];
+        };
+        match partition.exterior().0.first() {
+            Some(_) if self.near_boundary(bbox) => partition.intersection(polygon).0,
+            Some(&vertex) if self.contains(vertex) => vec![partition.clone()],
+            _ => vec![],
+        }
+    }
+
+    /**
+     * Whether the polygon intersects a partition, without computing the intersection. If none of
+     * their edges cross, either one holds the other or they are apart.
+     */
+    pub(crate) fn intersects(&self, polygon: &Polygon, partition: &Polygon) -> bool {
+        let Some(bbox) = partition.bounding_rect() else {
+            return false;
+        };
+        let crossing = self
+            .edges
+            .locate_in_envelope_intersecting(&envelope(bbox))
+            .any(|edge| Line::new(edge.from, edge.to).intersects(partition));
+        crossing
+            || partition
+                .exterior()
+                .0
+                .first()
+                .is_some_and(|&vertex| self.contains(vertex))
+            || polygon
+                .exterior()
+                .0
+                .first()
+                .is_some_and(|vertex| partition.intersects(vertex))
+    }
+
+    /**
+     * Whether a partition lies within the polygon. Partitions whose bounding boxes reach the
+     * polygon's boundary are taken not to, so this may miss some that do.
+     */
+    pub(crate) fn contains_partition(&self, partition: &Polygon) -> bool {
+        match (partition.bounding_rect(), partition.exterior().0.first()) {
+            (Some(bbox), Some(&vertex)) => !self.near_boundary(bbox) && self.contains(vertex),
+            _ => false,
+        }
+    }
+
+    fn near_boundary(&self, bbox: Rect) -> bool {
+        self.edges
+            .locate_in_envelope_intersecting(&envelope(bbox))
+            .next()
+            .is_some()
+    }
+
+    /**
+     * Whether a point off of the boundary is inside the polygon, by the even-odd rule. Only the
+     * edges crossing a ray cast from the point in the +x direction are visited.
+     */
+    fn contains(&self, point: Coord) -> bool {
+        let ray = AABB::from_corners([point.x, point.y], [self.max_x, point.y]);
+        self.edges
+            .locate_in_envelope_intersecting(&ray)
+            .filter(|edge| {
+                let (a, b) = (edge.from, edge.to);
+                (a[1] > point.y) != (b[1] > point.y)
+                    && point.x < a[0] + (point.y - a[1]) * (b[0] - a[0]) / (b[1] - a[1])
+            })
+            .count()
+            % 2
+            == 1
+    }
+}
+
+/**
+ * Splits the polygons that cross the antimeridian, borrowing the rest, which are usually all of
+ * them. With `normalize_winding`, polygons are oriented first, and copied only if they need to be.
+ */
+fn split_all_antimeridian(polygons: &[Polygon], normalize_winding: bool) -> Vec<Cow<'_, Polygon>> {
+    polygons
+        .iter()
+        .flat_map(|polygon| {
+            let polygon = match normalize_winding {
+                true => normalized_polygon(polygon),
+                false => Cow::Borrowed(polygon),
+            };
+            match crosses_antimeridian(&polygon) {
+                true => Either::Left(split_antimeridian(&polygon).into_iter().map(Cow::Owned)),
+                false => Either::Right(std::iter::once(polygon)),
+            }
+        })
+        .collect_vec()
+}
+
+fn envelope(rect: Rect) -> AABB<[f64; 2]> {
+    AABB::from_corners(rect.min().x_y().into(), rect.max().x_y().into())
+}
+
+/**
+ * Whether a polygon crosses the antimeridian, i.e. whether an edge of its exterior spans more than
+ * 180° of longitude or its exterior circles a pole. Edges are taken to go the shorter way around,
+ * as H3 and S2 cell edges do, except for edges between -180° and 180°, which are taken to run
+ * along the edge of the map, as they do in polygons like Antarctica drawn in lng/lat.
+ */
+pub fn crosses_antimeridian(polygon: &Polygon) -> bool {
+    let crossing = polygon.exterior().lines().any(|line| {
+        (line.end.x - line.start.x).abs() > 180.0
+            && !(line.start.x.abs() == 180.0 && line.end.x.abs() == 180.0)
+    });
+    crossing || enclosed_pole(polygon).is_some()
+}
+
+/**
+ * The latitude of the pole that a polygon contains, if any. A polygon contains a pole if its
+ * exterior circles the Earth's axis without passing through the pole, e.g. a ring along a line of
+ * latitude. Such rings divide the globe in two, and the polygon is taken to be the side containing
+ * the nearer pole.
+ */
+pub fn enclosed_pole(polygon: &Polygon) -> Option<f64> {
+    // Rings through a pole are drawn around it already, like Antarctica in lng/lat.
+    let exterior = polygon.exterior();
+    if exterior.0.iter().any(|c| c.y.abs() >= 90.0) {
+        return None;
+    }
+    let winding = exterior
+        .lines()
+        .map(|line| {
+            let step = line.end.x - line.start.x;
+            step - (step / 360.0).round() * 360.0
+        })
+        .sum::<f64>();
+    if winding.abs() < 180.0 {
+        return None;
+    }
+    let mean_latitude = exterior.0.iter().map(|c| c.y).sum::<f64>() / exterior.0.len() as f64;
+    Some(90f64.copysign(mean_latitude))
+}
+
+/**
+ * Splits a polygon that crosses the antimeridian into its parts on either side of it, so that
+ * planar operations like bounding boxes and intersections treat the polygon correctly. Polygons
+ * that contain a pole are first extended to the pole along the antimeridian. Other polygons are
+ * returned as they are.
+ */
+pub fn split_antimeridian(polygon: &Polygon) -> Vec<Polygon> {
+    match crosses_antimeridian(polygon).then(|| unwrap_polygon(polygon)) {
+        Some(Some(unwrapped)) => wrap_polygon(&unwrapped),
+        _ => vec![polygon.clone()],
+    }
+}
+
+/**
+ * Draws a polygon that crosses the antimeridian continuously across it, so that its longitudes
+ * extend past ±180°. Polygons that contain a pole are closed through the pole.
+ */
+pub(crate) fn unwrap_polygon(polygon: &Polygon) -> Option<Polygon> {
+    // Shift longitudes to be continuous along each ring, so that the polygon extends past ±180°.
+    let start = polygon.exterior().0.first()?.x;
+    let mut exterior = unwrap_ring(polygon.exterior(), start);
+    let first = exterior.0[0];
+    let last = *exterior
+        .0
+        .last()
+        .expect("rings that cross have coordinates");
+    if last != first {
+        // The ring circles a pole, ending 360° from where it started, so it is closed through the
+        // pole.
+        let pole = enclosed_pole(polygon)?;
+        exterior.0.extend([
+            Coord { x: last.x, y: pole },
+            Coord {
+                x: first.x,
+                y: pole,
+            },
+            first,
+        ]);
+    }
+    let interiors = polygon
+        .interiors()
+        .iter()
+        .map(|ring| unwrap_ring(ring, first.x))
+        .collect_vec();
+    Some(Polygon::new(exterior, interiors))
+}
+
+/**
+ * Moves the parts of an unwrapped polygon that extend past ±180° back into [-180°, 180°].
+ */
+pub(crate) fn wrap_polygon(unwrapped: &Polygon) -> Vec<Polygon> {
+    [-360.0, 0.0, 360.0]
+        .into_iter()
+        .flat_map(|offset| {
+            let world = Rect::new(
+                Coord {
+                    x: offset - 180.0,
+                    y: -90.0,
+                },
+                Coord {
+                    x: offset + 180.0,
+                    y: 90.0,
+                },
+            );
+            let mut part = unwrapped.intersection(&world.to_polygon());
+            part.translate_mut(-offset, 0.0);
+            part.0
+        })
+        .collect_vec()
+}
+
+/**
+ * Splits the polygons of a geometry that cross the antimeridian, as with `split_antimeridian`,
+ * into multipolygons. Geometries without such polygons are borrowed.
+ */
+pub fn split_geometry_antimeridian(geometry: &Geometry) -> Cow<'_, Geometry> {
+    match geometry {
+        Geometry::Polygon(polygon) if crosses_antimeridian(polygon) => {
+            Cow::Owned(MultiPolygon::new(split_antimeridian(polygon)).into())
+        }
+        Geometry::MultiPolygon(mpoly) if mpoly.iter().any(crosses_antimeridian) => Cow::Owned(
+            MultiPolygon::new(mpoly.iter().flat_map(split_antimeridian).collect_vec()).into(),
+        ),
+        Geometry::GeometryCollection(collection) => {
+            let parts = collection
+                .iter()
+                .map(split_geometry_antimeridian)
+                .collect_vec();
+            if parts.iter().all(|part| matches!(part, Cow::Borrowed(_))) {
+                return Cow::Borrowed(geometry);
+            }
+            Cow::Owned(Geometry::GeometryCollection(GeometryCollection::new_from(
+                parts.into_iter().map(Cow::into_owned).collect_vec(),
+            )))
+        }
+        _ => Cow::Borrowed(geometry),
+    }
+}
+
+/**
+ * Shifts the longitudes of a ring by multiples of 360° so that consecutive coordinates are never
+ * more than 180° apart, starting within 180° of `reference`.
+ */
+fn unwrap_ring(ring: &LineString, reference: f64) -> LineString {
+    let Some(start) = ring.0.first() else {
+        return ring.clone();
+    };
+    let mut shift = ((reference - start.x) / 360.0).round() * 360.0;
+    let mut previous = start.x;
+    ring.0
+        .iter()
+        .map(|coord| {
+            let step = coord.x - previous;
+            if step > 180.0 {
+                shift -= 360.0;
+            } else if step < -180.0 {
+                shift += 360.0;
+            }
+            previous = coord.x;
+            Coord {
+                x: coord.x + shift,
+                y: coord.y,
+            }
+        })
+        .collect()
+}
+
+/**
+ * Linearly interpolate between two geographic coordinates.
+ *
+ * This implementation uses n-vectors as the underlying coordinate representation to perform linear
+ * interpolation in a tangent space to the ellipsoid model of the Earth's surface.
+ *
+ * https://en.wikipedia.org/wiki/N-vector
+ */
+pub fn lerp(t: f64, c1: Coord, c2: Coord) -> Coord {
+    let nvec_c1: NVec = c1.into();
+    let nvec_c2: NVec = c2.into();
+
+    let nv = (1.0 - t) * nvec_c1 + t * nvec_c2;
+    let nv = (1.0 / (nv.norm() + 1e-8)) * nv;
+
+    nv.into()
+}
+
+/**
+ * Triangulates a polygon by ear clipping. Polygons without area are rejected rather than clipped,
+ * since ear clipping panics on them.
+ */
+pub fn triangulate(polygon: &Polygon) -> Result<Vec<Polygon>, DegeneratePolygon> {
+    check_area(polygon)?;
+    Ok(polygon.earcut_triangles_iter().map(Polygon::from).collect())
+}
+
+/**
+ * This algorithm approximately partitions a geometry into uniform subregions. First, the geometry
+ * is approximated by its minimal bounding box. Then, the bounding box is divided into regions. The
+ * edge_proportion argument determines the region size. For example, edge_proportion = 0.5 would divide into 4 regions.
+ * edge_proportion = 0.33 would divide into 9 regions. Regions are selected in parallel.
+ *
+ * Polygons without area, which have no bounding box to divide, are rejected. Selecting regions by
+ * an area threshold intersects them with the polygon, so the polygon is then checked with
+ * `check_polygon` first, and oriented if `normalize_winding` is set. Edge proportions below
+ * `MIN_EDGE_PROPORTION`, or that are not finite, are rejected too, since the regions would never
+ * stop being divided.
+ */
+pub fn partition_region(
+    polygon: &Polygon,
+    edge_proportion: f64,
+    area_threshold: Option<f64>,
+    normalize_winding: bool,
+) -> Result<Vec<Polygon>, Box<dyn Error>> {
+    if !edge_proportion.is_finite() || edge_proportion < MIN_EDGE_PROPORTION {
+        return Err(InvalidEdgeProportion(edge_proportion).into());
+    }
+    check_area(polygon)?;
+    if area_threshold.is_some() {
+        check_polygon(polygon)?;
+    }
+    let polygon = match normalize_winding {
+        true => normalized_polygon(polygon),
+        false => Cow::Borrowed(polygon),
+    };
+    let mut candidates: Vec<Rect> = vec![];
+    let bbox = polygon.bounding_rect().ok_or(DegeneratePolygon)?;
+
+    // This ensures that we return bbox in cases where edge_proportion > 1.0 i.e. would correspond
+    // to a dilation.
+    let edge_budget = f64::max(1.0, edge_proportion);
+
+    // Bounding box corners.
+    //  c1 -- c2
+    //  |     |
+    // c0 -- c3
+    let c0 = bbox.min();
+    let c1 = Coord {
+        x: bbox.min().x,
+        y: bbox.max().y,
+    };
+    let c2 = bbox.max();
+    let c3 = Coord {
+        x: bbox.max().x,
+        y: bbox.min().y,
+    };
+
+    // Forward axes.
+    let l01_lerp = |t| lerp(t, c0, c1);
+    let l32_lerp = |t| lerp(t, c3, c2);
+
+    // Sweep in the c0 -> c1 direction.
+    let mut fx = 0.0;
+    let mut lp_prev = Line { start: c0, end: c3 };
+    while fx < edge_budget {
+        // This is a line in the c0 -> c3 direction that is lerped towards c1. We use this to
+        // compute the upper right corner of the rect.
+        let lp = Line {
+            start: l01_lerp(fx + edge_proportion),
+            end: l32_lerp(fx + edge_proportion),
+        };
+
+        // Sweep in the c0 -> c3 direction.
+        let mut fy = 0.0;
+        while fy < edge_budget {
+            // The rect can be defined in terms of two corners.
+            let partition = Rect::new(
+                lerp(fy, lp_prev.start, lp_prev.end),
+                lerp(fy + edge_proportion, lp.start, lp.end),
+            );
+
+            candidates.push(partition);
+
+            fy += edge_proportion;
+        }
+        fx += edge_proportion;
+        lp_prev = lp;
+    }
+
+    // Not all partitions computed from the minimal bounding box intersect with the underlying
+    // geometry.
+    let selected = par_map(&candidates, |partition| match area_threshold {
+        Some(threshold) => {
+            // More expensive selection criterion based on the amount of intersection.
+            let intersection = polygon.intersection(&partition.to_polygon());
+            let area_ratio = intersection.unsigned_area() / partition.unsigned_area();
+            area_ratio >= threshold
+        }
+
+        // Fast selection criterion of detecting any intersection. This is the deafult.
+        None => partition.intersects(polygon.as_ref()),
+    });
+    Ok(candidates
+        .into_iter()
+        .zip(selected)
+        .filter(|(_, selected)| *selected)
+        .map(|(partition, _)| partition.into())
+        .collect())
+}
+
+/**
+ * Orients the rings of every polygon in a geometry, winding exteriors counterclockwise and holes
+ * clockwise as the OGC and GeoJSON specifications require. Returns how many rings were reversed.
+ */
+pub fn normalize_winding(geometry: &mut Geometry) -> usize {
+    match geometry {
+        Geometry::Polygon(polygon) => normalize_polygon_winding(polygon),
+        Geometry::MultiPolygon(mpoly) => mpoly.iter_mut().map(normalize_polygon_winding).sum(),
+        Geometry::GeometryCollection(collection) => {
+            collection.iter_mut().map(normalize_winding).sum()
+        }
+        _ => 0,
+    }
+}
+
+/**
+ * A polygon with its rings oriented as by `normalize_winding`, which is only copied if one of its
+ * rings needs to be reversed.
+ */
+pub fn normalized_polygon(polygon: &Polygon) -> Cow<'_, Polygon> {
+    let misoriented = spherical_winding(polygon.exterior()) == Some(WindingOrder::Clockwise)
+        || polygon
+            .interiors()
+            .iter()
+            .any(|ring| spherical_winding(ring) == Some(WindingOrder::CounterClockwise));
+    match misoriented {
+        true => {
+            let mut polygon = polygon.clone();
+            normalize_polygon_winding(&mut polygon);
+            Cow::Owned(polygon)
+        }
+        false => Cow::Borrowed(polygon),
+    }
+}
+
+/**
+ * Removes the holes of every polygon in a geometry, so that coverings include the area within them.
+ * Returns how many holes were removed.
+ */
+pub fn fill_holes(geometry: &mut Geometry) -> usize {
+    let fill = |polygon: &mut Polygon| {
+        let (exterior, holes) =
+            std::mem::replace(polygon, Polygon::new(LineString::new(vec![]), vec![])).into_inner();
+        *polygon = Polygon::new(exterior, vec![]);
+        holes.len()
+    };
+    match geometry {
+        Geometry::Polygon(polygon) => fill(polygon),
+        Geometry::MultiPolygon(mpoly) => mpoly.iter_mut().map(fill).sum(),
+        Geometry::GeometryCollection(collection) => collection.iter_mut().map(fill_holes).sum(),
+        _ => 0,
+    }
+}
+
+fn normalize_polygon_winding(polygon: &mut Polygon) -> usize {
+    let mut reversed = 0;
+    polygon.exterior_mut(|ring| {
+        if spherical_winding(ring) == Some(WindingOrder::Clockwise) {
+            ring.0.reverse();
+            reversed += 1;
+        }
+    });
+    polygon.interiors_mut(|rings| {
+        for ring in rings.iter_mut() {
+            if spherical_winding(ring) == Some(WindingOrder::CounterClockwise) {
+                ring.0.reverse();
+                reversed += 1;
+            }
+        }
+    });
+    reversed
+}
+
+/**
+ * The winding of a ring with its longitudes unwrapped across ±180°, so that rings crossing the
+ * antimeridian are oriented as they are drawn on the globe. Rings that circle a pole have no
+ * planar winding and give `None`.
+ */
+fn spherical_winding(ring: &LineString) -> Option<WindingOrder> {
+    let start = ring.0.first()?.x;
+    let unwrapped = unwrap_ring(ring, start);
+    match unwrapped.is_closed() {
+        true => unwrapped.winding_order(),
+        false => None,
+    }
+}
+
+/**
+ * Flattens a geometry into its constituent points. Only point-like geometries (points,
+ * multi-points, and collections thereof) are accepted.
+ */
+pub fn collect_points(geometry: &Geometry) -> Result<Vec<Point>, Box<dyn Error>> {
+    match geometry {
+        Geometry::Point(point) => Ok(vec![*point]),
+        Geometry::MultiPoint(mpoint) => Ok(mpoint.iter().copied().collect_vec()),
+        Geometry::GeometryCollection(collection) => collection
+            .iter()
+            .map(collect_points)
+            .flatten_ok()
+            .collect::<Result<Vec<Point>, _>>(),
+        _ => Err("expected a POINT, MULTIPOINT, or GEOMETRYCOLLECTION of points".into()),
+    }
+}
+
+/**
+ * Flattens a geometry into its constituent line strings. Only linear geometries (line strings,
+ * multi-line strings, and collections thereof) are accepted.
+ */
+pub fn collect_lines(geometry: &Geometry) -> Result<Vec<LineString>, Box<dyn Error>> {
+    match geometry {
+        Geometry::LineString(line) => Ok(vec![line.clone()]),
+        Geometry::MultiLineString(mline) => Ok(mline.0.clone()),
+        Geometry::GeometryCollection(collection) => collection
+            .iter()
+            .map(collect_lines)
+            .flatten_ok()
+            .collect::<Result<Vec<LineString>, _>>(),
+        _ => Err("expected a LINESTRING, MULTILINESTRING, or GEOMETRYCOLLECTION of lines".into()),
+    }
+}
+
+/**
+ * Flattens a geometry into its constituent polygons. Only polygonal geometries (polygons,
+ * multi-polygons, and collections thereof) are accepted.
+ */
+pub fn collect_polygons(geometry: &Geometry) -> Result<Vec<Polygon>, Box<dyn Error>> {
+    match geometry {
+        Geometry::Polygon(polygon) => Ok(vec![polygon.clone()]),
+        Geometry::MultiPolygon(mpolygon) => Ok(mpolygon.0.clone()),
+        Geometry::GeometryCollection(collection) => collection
+            .iter()
+            .map(collect_polygons)
+            .flatten_ok()
+            .collect::<Result<Vec<Polygon>, _>>(),
+        _ => Err("expected a POLYGON, MULTIPOLYGON, or GEOMETRYCOLLECTION of polygons".into()),
+    }
+}
+
+/**
+ * Distance in meters between two geographic points.
+ */
+pub fn distance(method: &DistanceMethod, p1: &Point, p2: &Point) -> f64 {
+    match method {
+        DistanceMethod::Haversine => p1.haversine_distance(p2),
+        DistanceMethod::Geodesic => p1.geodesic_distance(p2),
+    }
+}
+
+/**
+ * Initial bearing in degrees clockwise from north, in [0, 360), when traveling from one point to
+ * another along the shortest path.
+ */
+pub fn bearing(method: &DistanceMethod, p1: &Point, p2: &Point) -> f64 {
+    let bearing = match method {
+        DistanceMethod::Haversine => p1.haversine_bearing(*p2),
+        DistanceMethod::Geodesic => p1.geodesic_bearing(*p2),
+    };
+    bearing.rem_euclid(360.0)
+}
+
+/**
+ * The point reached by traveling a distance in meters from a point along the shortest path with
+ * the given initial bearing in degrees clockwise from north.
+ */
+pub fn destination(method: &DistanceMethod, p: &Point, bearing_deg: f64, distance_m: f64) -> Point {
+    match method {
+        DistanceMethod::Haversine => p.haversine_destination(bearing_deg, distance_m),
+        DistanceMethod::Geodesic => p.geodesic_destination(bearing_deg, distance_m),
+    }
+}
+
+/**
+ * Computes the matrix of pairwise distances in meters, where entry (i, j) is the distance from
+ * `from[i]` to `to[j]`.
+ */
+pub fn distance_matrix(method: &DistanceMethod, from: &[Point], to: &[Point]) -> Vec<Vec<f64>> {
+    from.iter()
+        .map(|p1| to.iter().map(|p2| distance(method, p1, p2)).collect_vec())
+        .collect_vec()
+}
+
+/**
+ * Computes the Hausdorff distance in meters between two geometries: the farthest that a vertex of
+ * either geometry is from the other geometry along great circles. Vertices within a polygon of the
+ * other geometry are at distance 0. Returns `None` if either geometry is empty.
+ */
+pub fn hausdorff_distance(a: &Geometry, b: &Geometry) -> Option<f64> {
+    if a.coords_count() == 0 || b.coords_count() == 0 {
+        return None;
+    }
+    let directed = |from: &Geometry, to: &Geometry| {
+        let segments = collect_segments(to);
+        from.coords_iter()
+            .map(|c| haversine_distance_to(to, &segments, &Point::from(c)))
+            .fold(0.0, f64::max)
+    };
+    Some(directed(a, b).max(directed(b, a)))
+}
+
+/**
+ * The great-circle distance in meters from a point to the nearest vertex or segment of a geometry.
+ */
+fn haversine_distance_to(geometry: &Geometry, segments: &[Line], point: &Point) -> f64 {
+    if geometry.intersects(point) {
+        return 0.0;
+    }
+    let to_vertices = geometry
+        .coords_iter()
+        .map(|c| Point::from(c).haversine_distance(point));
+    let to_segments =
+        segments
+            .iter()
+            .filter_map(|line| match line.haversine_closest_point(point) {
+                Closest::Intersection(p) | Closest::SinglePoint(p) => {
+                    Some(p.haversine_distance(point))
+                }
+                Closest::Indeterminate => None,
+            });
+    to_vertices.chain(to_segments).fold(f64::INFINITY, f64::min)
+}
+
+fn collect_segments(geometry: &Geometry) -> Vec<Line> {
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => vec![],
+        Geometry::Line(line) => vec![*line],
+        Geometry::LineString(line) => line.lines().collect_vec(),
+        Geometry::MultiLineString(mline) => mline.lines_iter().collect_vec(),
+        Geometry::Polygon(polygon) => polygon.lines_iter().collect_vec(),
+        Geometry::MultiPolygon(mpolygon) => mpolygon.lines_iter().collect_vec(),
+        Geometry::Rect(rect) => rect.lines_iter().collect_vec(),
+        Geometry::Triangle(triangle) => triangle.lines_iter().collect_vec(),
+        Geometry::GeometryCollection(collection) => {
+            collection.iter().flat_map(collect_segments).collect_vec()
+        }
+    }
+}
+
+/**
+ * Creates a polygon approximating the geodesic circle of the given radius around a center point on
+ * the WGS84 ellipsoid, with one vertex every `360 / segments` degrees of bearing. If an inner radius
+ * is given, the polygon is an annulus with the inner circle as a hole. The exterior ring is
+ * counter-clockwise and the hole is clockwise.
+ *
+ * Circles that contain a pole or cross the antimeridian are not split, so their polygons are only
+ * meaningful in spherical coordinates.
+ */
+pub fn geodesic_circle(
+    center: &Point,
+    radius_m: f64,
+    inner_radius_m: Option<f64>,
+    segments: usize,
+) -> Result<Polygon, Box<dyn Error>> {
+    if radius_m <= 0.0 {
+        return Err("radius must be positive".into());
+    }
+    if segments < 3 {
+        return Err("a circle needs at least 3 segments".into());
+    }
+    if inner_radius_m.is_some_and(|r| r <= 0.0 || r >= radius_m) {
+        return Err("inner radius must be positive and smaller than the radius".into());
+    }
+
+    // Bearings are clockwise from north, so counting them down traces a counter-clockwise ring.
+    let ring = |radius_m: f64| {
+        let coords = (0..segments)
+            .map(|i| 360.0 * (segments - i) as f64 / segments as f64)
+            .map(|bearing| center.geodesic_destination(bearing, radius_m).0)
+            .collect_vec();
+        // LineString::new does not close rings, but Polygon::new does.
+        LineString::new(coords)
+    };
+    let holes = inner_radius_m
+        .map(|r| {
+            let mut hole = ring(r);
+            hole.0.reverse();
+            hole
+        })
+        .into_iter()
+        .collect_vec();
+    Ok(Polygon::new(ring(radius_m), holes))
+}
+
+/**
+ * Computes the two antipodal points where the great circles through two pairs of points intersect,
+ * as the normalized cross product of the n-vectors normal to each great circle. Fails if either pair
+ * does not define a unique great circle, or if both pairs lie on the same great circle.
+ */
+pub fn great_circle_intersections(
+    a: (Point, Point),
+    b: (Point, Point),
+) -> Result<[Point; 2], Box<dyn Error>> {
+    let (normal_a, normal_b) = (great_circle_normal(a)?, great_circle_normal(b)?);
+    let intersection = normal_a.cross(&normal_b);
+    if intersection.norm() < 1e-12 {
+        return Err("both paths lie on the same great circle".into());
+    }
+    let intersection = intersection.normalize();
+    Ok([
+        Coord::from(intersection).into(),
+        Coord::from(-1.0 * intersection).into(),
+    ])
+}
+
+/**
+ * Computes the point where the shortest great circle paths between two pairs of points cross, if
+ * they do.
+ */
+pub fn great_circle_path_intersection(
+    a: (Point, Point),
+    b: (Point, Point),
+) -> Result<Option<Point>, Box<dyn Error>> {
+    let candidates = great_circle_intersections(a, b)?;
+    Ok(candidates
+        .into_iter()
+        .find(|p| is_on_minor_arc(a, p) && is_on_minor_arc(b, p)))
+}
+
+fn great_circle_normal((p1, p2): (Point, Point)) -> Result<NVec, Box<dyn Error>> {
+    let normal = NVec::from(p1.0).cross(&NVec::from(p2.0));
+    if normal.norm() < 1e-12 {
+        return Err(format!(
+            "points {:?} and {:?} do not define a unique great circle",
+            p1.x_y(),
+            p2.x_y()
+        )
+        .into());
+    }
+    Ok(normal.normalize())
+}
+
+/**
+ * Whether a point on the great circle through two points lies on the shorter arc between them,
+ * i.e. the great circle turns in the same direction from the start to the point and from the point
+ * to the end. Points within a few micrometers of the arc's ends are included.
+ */
+fn is_on_minor_arc((p1, p2): (Point, Point), p: &Point) -> bool {
+    const TOLERANCE: f64 = 1e-12;
+    let (start, end, p) = (NVec::from(p1.0), NVec::from(p2.0), NVec::from(p.0));
+    let normal = start.cross(&end);
+    start.cross(&p).dot(&normal) >= -TOLERANCE && p.cross(&end).dot(&normal) >= -TOLERANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use geo::{Area, BooleanOps, GeodesicDistance, Winding};
+    use geo_types::{polygon, Geometry, Point, Polygon, Rect};
+    use itertools::Itertools;
+
+    use super::{
+        bearing, cut_polygons, destination, distance, distance_matrix, enclosed_pole,
+        geodesic_circle, great_circle_intersections, great_circle_path_intersection,
+        hausdorff_distance, normalize_winding, normalized_polygon, partition_region,
+        split_antimeridian, DistanceMethod, InvalidEdgeProportion, PolygonCutter,
+    };
+
+    #[test]
+    fn test_normalize_winding() {
+        // A clockwise exterior with a counterclockwise hole.
+        let mut geometry = Geometry::Polygon(polygon!(
+            exterior: [(x: 0.0, y: 0.0), (x: 0.0, y: 4.0), (x: 4.0, y: 4.0), (x: 4.0, y: 0.0)],
+            interiors: [[(x: 1.0, y: 1.0), (x: 2.0, y: 1.0), (x: 2.0, y: 2.0), (x: 1.0, y: 2.0)]],
+        ));
+        assert_eq!(normalize_winding(&mut geometry), 2);
+        let Geometry::Polygon(polygon) = &geometry else {
+            unreachable!();
+        };
+        assert!(polygon.exterior().is_ccw() && polygon.interiors()[0].is_cw());
+        assert_eq!(normalize_winding(&mut geometry), 0);
+    }
+
+    #[test]
+    fn test_normalized_polygon() {
+        let ccw = polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 1.0)];
+        assert!(matches!(normalized_polygon(&ccw), Cow::Borrowed(_)));
+
+        let mut cw = ccw.clone();
+        cw.exterior_mut(|ring| ring.0.reverse());
+        let normalized = normalized_polygon(&cw);
+        assert!(matches!(normalized, Cow::Owned(_)));
+        assert!(normalized.exterior().is_ccw());
+
+        // Cutting orients the polygon first, unless told not to.
+        let partitions = [polygon![(x: -1.0, y: -1.0), (x: 2.0, y: -1.0), (x: 2.0, y: 2.0)]];
+        let cut = |polygon, normalize| {
+            let cutter = PolygonCutter::new(std::slice::from_ref(polygon), normalize).unwrap();
+            cutter.cut(&partitions[0])
+        };
+        assert_eq!(cut(&cw, true), cut(&ccw, true));
+        assert_eq!(
+            cut(&cw, true),
+            cut_polygons(&[cw.clone()], &partitions, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_normalize_winding_across_antimeridian() {
+        // Counterclockwise on the globe, though clockwise in the plane.
+        let ccw = polygon![
+            (x: 179.0, y: -17.0),
+            (x: -179.0, y: -17.0),
+            (x: -179.0, y: -16.0),
+            (x: 179.0, y: -16.0),
+        ];
+        let mut geometry = Geometry::Polygon(ccw.clone());
+        assert_eq!(normalize_winding(&mut geometry), 0);
+        assert_eq!(geometry, Geometry::Polygon(ccw.clone()));
+
+        // Clockwise on the globe.
+        let mut cw = ccw.clone();
+        cw.exterior_mut(|ring| ring.0.reverse());
+        let mut geometry = Geometry::Polygon(cw);
+        assert_eq!(normalize_winding(&mut geometry), 1);
+        assert_eq!(geometry, Geometry::Polygon(ccw));
+    }
+
+    #[test]
+    fn test_split_antimeridian() {
+        let polygon = polygon![
+            (x: 179.0, y: -17.0),
+            (x: -179.0, y: -17.0),
+            (x: -179.0, y: -16.0),
+            (x: 179.0, y: -16.0),
+        ];
+        let parts = split_antimeridian(&polygon);
+        let east = Rect::new((179.0, -17.0), (180.0, -16.0)).to_polygon();
+        let west = Rect::new((-180.0, -17.0), (-179.0, -16.0)).to_polygon();
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| {
+            part.xor(&east).unsigned_area() < 1e-9 || part.xor(&west).unsigned_area() < 1e-9
+        }));
+
+        let square = Rect::new((1.0, 1.0), (2.0, 2.0)).to_polygon();
+        assert_eq!(split_antimeridian(&square), vec![square.clone()]);
+
+        // Rings around a pole are closed through it.
+        let arctic = polygon![
+            (x: 0.0, y: 80.0),
+            (x: 90.0, y: 80.0),
+            (x: 180.0, y: 80.0),
+            (x: -90.0, y: 80.0),
+        ];
+        assert_eq!(enclosed_pole(&arctic), Some(90.0));
+        let area = split_antimeridian(&arctic)
+            .iter()
+            .map(|part| part.unsigned_area())
+            .sum::<f64>();
+        assert!((area - 360.0 * 10.0).abs() < 1e-9);
+        assert_eq!(enclosed_pole(&square), None);
+    }
+
+    #[test]
+    fn test_partition_region_edge_proportions() {
+        let square =
+            polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 1.0)];
+        assert_eq!(partition_region(&square, 0.5, None, true).unwrap().len(), 4);
+        assert_eq!(
+            partition_region(&square, 0.01, None, true).unwrap().len(),
+            10_000
+        );
+        // Proportions that would never finish dividing the box are rejected.
+        for edge_proportion in [0.0, -1.0, 0.0009, f64::NAN, f64::INFINITY] {
+            let error = partition_region(&square, edge_proportion, None, true).unwrap_err();
+            assert!(error.is::<InvalidEdgeProportion>(), "{}", edge_proportion);
+        }
+    }
+
+    #[test]
+    fn test_cut_polygons_matches_exact_intersections() {
+        // A square with a hole, and a triangle, cut by a 10x10 grid over both.
+        let polygons = vec![
+            polygon!(
+                exterior: [(x: 0.5, y: 0.5), (x: 6.5, y: 0.5), (x: 6.5, y: 6.5), (x: 0.5, y: 6.5)],
+                interiors: [[(x: 2.5, y: 2.5), (x: 4.5, y: 2.5), (x: 4.5, y: 4.5), (x: 2.5, y: 4.5)]],
+            ),
+            polygon![(x: 7.0, y: 7.0), (x: 9.5, y: 7.0), (x: 9.5, y: 9.5)],
+        ];
+        let grid = (0..10)
+            .cartesian_product(0..10)
+            .map(|(x, y)| {
+                Rect::new((x as f64, y as f64), (x as f64 + 1.0, y as f64 + 1.0)).to_polygon()
+            })
+            .collect_vec();
+
+        let expected: Vec<Polygon> = polygons
+            .iter()
+            .flat_map(|polygon| grid.iter().flat_map(|cell| cell.intersection(polygon)))
+            .collect();
+        let cuts = cut_polygons(&polygons, &grid, true).unwrap();
+        assert_eq!(cuts.len(), expected.len());
+        for (cut, expected) in cuts.iter().zip(&expected) {
+            assert!((cut.unsigned_area() - expected.unsigned_area()).abs() < 1e-9);
+            assert!(cut.xor(expected).unsigned_area() < 1e-9);
+        }
+
+        // Cut one cell at a time, the pieces come out in order of the cells instead.
+        let cutter = PolygonCutter::new(&polygons, true).unwrap();
+        let pieces = cutter
+            .cut_each(grid.iter().cloned().enumerate())
+            .collect_vec();
+        assert_eq!(pieces.len(), expected.len());
+        assert!(pieces.iter().map(|(i, _)| i).is_sorted());
+        for (i, piece) in &pieces {
+            assert!(piece.difference(&grid[*i]).unsigned_area() < 1e-9);
+        }
+        let total: f64 = pieces.iter().map(|(_, piece)| piece.unsigned_area()).sum();
+        let expected_total: f64 = expected.iter().map(Area::unsigned_area).sum();
+        assert!((total - expected_total).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_geodesic_circle_vertices_are_on_the_circle() {
+        let center = Point::new(-122.4, 37.8);
+        let annulus = geodesic_circle(&center, 1000.0, Some(400.0), 16).unwrap();
+
+        // Rings are closed, so each has one more coordinate than there are segments.
+        assert_eq!(annulus.exterior().0.len(), 17);
+        assert!(annulus.exterior().is_ccw());
+        assert!(annulus.interiors()[0].is_cw());
+        for p in annulus.exterior().points() {
+            assert!((p.geodesic_distance(&center) - 1000.0).abs() < 1e-6);
+        }
+        for p in annulus.interiors()[0].points() {
+            assert!((p.geodesic_distance(&center) - 400.0).abs() < 1e-6);
+        }
+
+        assert!(geodesic_circle(&center, 1000.0, Some(1000.0), 16).is_err());
+        assert!(geodesic_circle(&center, 1000.0, None, 2).is_err());
+    }
+
+    #[test]
+    fn test_great_circle_intersections() {
+        // The equator and the prime meridian cross at (0, 0) and (180, 0).
+        let equator = (Point::new(-10.0, 0.0), Point::new(10.0, 0.0));
+        let meridian = (Point::new(0.0, -10.0), Point::new(0.0, 10.0));
+        let [p1, p2] = great_circle_intersections(equator, meridian).unwrap();
+        assert!(p1.x().abs() < 1e-9 && p1.y().abs() < 1e-9);
+        assert!((p2.x().abs() - 180.0).abs() < 1e-9 && p2.y().abs() < 1e-9);
+
+        let crossing = great_circle_path_intersection(equator, meridian).unwrap();
+        assert!(crossing.is_some_and(|p| p.x().abs() < 1e-9 && p.y().abs() < 1e-9));
+
+        // Paths that stop short of each other do not cross.
+        let north = (Point::new(0.0, 5.0), Point::new(0.0, 10.0));
+        assert_eq!(
+            great_circle_path_intersection(equator, north).unwrap(),
+            None
+        );
+
+        // Paths on the same great circle have no unique intersection.
+        let east = (Point::new(20.0, 0.0), Point::new(30.0, 0.0));
+        assert!(great_circle_intersections(equator, east).is_err());
+    }
+
+    #[test]
+    fn test_destination_round_trips_bearing_and_distance() {
+        let start = Point::new(-122.4, 37.8);
+        for method in [DistanceMethod::Haversine, DistanceMethod::Geodesic] {
+            let end = destination(&method, &start, 300.0, 50_000.0);
+            assert!((distance(&method, &start, &end) - 50_000.0).abs() < 1e-6);
+            assert!((bearing(&method, &start, &end) - 300.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_distance_matrix() {
+        let from = [Point::new(0.0, 0.0), Point::new(1.0, 0.0)];
+        let to = [
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(2.0, 0.0),
+        ];
+
+        // One degree along the equator is 111,195 m on the mean-radius sphere and 111,319 m on the
+        // WGS84 ellipsoid.
+        for (method, degree) in [
+            (DistanceMethod::Haversine, 111_195.08),
+            (DistanceMethod::Geodesic, 111_319.49),
+        ] {
+            let matrix = distance_matrix(&method, &from, &to);
+            assert_eq!(matrix.len(), 2);
+            assert!(matrix.iter().all(|row| row.len() == 3));
+            for (i, row) in matrix.iter().enumerate() {
+                for (j, d) in row.iter().enumerate() {
+                    let expected = degree * (i as f64 - j as f64).abs();
+                    assert!((d - expected).abs() < 0.01, "{:?} {} {}", method, i, j);
+                }
+            }
+        }
+
+        let method = DistanceMethod::Haversine;
+        assert!(distance_matrix(&method, &[], &to).is_empty());
+        assert_eq!(
+            distance_matrix(&method, &from, &[]),
+            vec![Vec::<f64>::new(); 2]
+        );
+    }
+
+    #[test]
+    fn test_hausdorff_distance() {
+        let square = Geometry::from(
+            polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 1.0)],
+        );
+        // The same square with a spike 0.1 degrees north of its top edge.
+        let spiked = Geometry::from(
+            polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.5, y: 1.1), (x: 0.0, y: 1.0)],
+        );
+        assert_eq!(hausdorff_distance(&square, &square), Some(0.0));
+        let d = hausdorff_distance(&square, &spiked).unwrap();
+        assert!((d - 11_119.5).abs() < 10.0);
+        assert_eq!(hausdorff_distance(&spiked, &square), Some(d));
+
+        let point = Geometry::from(Point::new(0.5, 0.5));
+        assert!(hausdorff_distance(&point, &square).unwrap() > 0.0);
+    }
+}
@@ -0,0 +1,65 @@
+/*!
+ * Writing of points as GPX waypoints, e.g. to load sample points onto handheld GPS units.
+ */
+use std::io::{self, Write};
+
+use geo_types::Point;
+
+use crate::kml::escape;
+
+/**
+ * Writes the start of a GPX document, which the waypoints follow.
+ */
+pub fn write_gpx_start(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<gpx version="1.1" creator="geos" xmlns="http://www.topografix.com/GPX/1/1">"#
+    )
+}
+
+/**
+ * Writes the end of a GPX document.
+ */
+pub fn write_gpx_end(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "</gpx>")
+}
+
+/**
+ * Writes a lng/lat point as a waypoint on one line, optionally named.
+ */
+pub fn write_waypoint(out: &mut dyn Write, name: Option<&str>, point: Point) -> io::Result<()> {
+    write!(out, r#"<wpt lat="{}" lon="{}">"#, point.y(), point.x())?;
+    if let Some(name) = name {
+        write!(out, "<name>{}</name>", escape(name))?;
+    }
+    writeln!(out, "</wpt>")
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::point;
+
+    use super::{write_gpx_end, write_gpx_start, write_waypoint};
+
+    #[test]
+    fn test_write_gpx() {
+        let mut out = vec![];
+        write_gpx_start(&mut out).unwrap();
+        write_waypoint(&mut out, Some("plot & 1"), point!(x: -122.5, y: 37.75)).unwrap();
+        write_waypoint(&mut out, None, point!(x: 0.0, y: 1.0)).unwrap();
+        write_gpx_end(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            [
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<gpx version="1.1" creator="geos" xmlns="http://www.topografix.com/GPX/1/1">"#,
+                r#"<wpt lat="37.75" lon="-122.5"><name>plot &amp; 1</name></wpt>"#,
+                r#"<wpt lat="1" lon="0"></wpt>"#,
+                "</gpx>",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,525 @@
+use std::any::type_name;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+
+use geo::{
+    Geometry, GeometryCollection, Line, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon, Rect, Triangle,
+};
+use geo_types::coord;
+use h3o::error::InvalidGeometry;
+use h3o::geom::{ContainmentMode, PolyfillConfig, ToCells, ToGeo};
+use h3o::{CellIndex, LatLng, Resolution};
+use itertools::Itertools;
+
+use crate::bins::{bin_points, Bin};
+use crate::geom::{cut_polygons_by_partition, normalized_polygon};
+
+/**
+ * Parses a polyfill containment mode by name. Unrecognized names fall back to the default mode of
+ * covering everything that intersects the geometry's boundary.
+ */
+pub fn parse_containment_mode(value: &str) -> ContainmentMode {
+    match value.to_ascii_lowercase().as_str() {
+        "containscentroid" | "centroid" => ContainmentMode::ContainsCentroid,
+        "containsboundary" | "contains" => ContainmentMode::ContainsBoundary,
+        _ => ContainmentMode::IntersectsBoundary,
+    }
+}
+
+/**
+ * Cuts a geometry by intersection with H3 cells. Each returned polygon is the part of a polygonal
+ * member of the geometry that lies within one of the cells. With `normalize_winding`, polygon rings
+ * are oriented as the intersection expects first.
+ */
+pub fn cut_geometry(
+    geometry: &Geometry,
+    cells: &[CellIndex],
+    normalize_winding: bool,
+) -> Result<Vec<Polygon>, Box<dyn Error>> {
+    let pieces = cut_geometry_by_cell(geometry, cells, normalize_winding)?;
+    Ok(pieces.into_iter().map(|(_, piece)| piece).collect())
+}
+
+/**
+ * Like `cut_geometry`, but with the cell that each polygon lies within.
+ */
+pub fn cut_geometry_by_cell(
+    geometry: &Geometry,
+    cells: &[CellIndex],
+    normalize_winding: bool,
+) -> Result<Vec<(CellIndex, Polygon)>, Box<dyn Error>> {
+    let partitions = h3_cells_to_polys(cells.iter().copied()).collect_vec();
+    let pieces = match &geometry {
+        Geometry::Polygon(poly) => {
+            cut_polygons_by_partition(std::slice::from_ref(poly), &partitions, normalize_winding)?
+        }
+
+        Geometry::MultiPolygon(mpoly) => {
+            cut_polygons_by_partition(&mpoly.0, &partitions, normalize_winding)?
+        }
+
+        // Recurse.
+        Geometry::GeometryCollection(collection) => {
+            return collection
+                .into_iter()
+                .map(|g| cut_geometry_by_cell(g, cells, normalize_winding))
+                .flatten_ok()
+                .collect();
+        }
+
+        _ => return Err(not_polygonal(geometry)),
+    };
+    Ok(pieces
+        .into_iter()
+        .map(|(i, piece)| (cells[i], piece))
+        .collect())
+}
+
+/**
+ * Creates a polygon from the vertices of an H3 cell. This will be a hexagon in most cases, except
+ * for the pentagons on icosahedron vertices.
+ */
+pub fn h3_cell_to_poly(cell_id: &CellIndex) -> Polygon {
+    let boundary = cell_id.boundary();
+    // The ring is allocated at its closed size, so that closing it doesn't reallocate.
+    let mut ring = Vec::with_capacity(boundary.len() + 1);
+    ring.extend(boundary.iter().map(|v| coord![x: v.lng(), y: v.lat()]));
+    ring.push(ring[0]);
+    Polygon::new(LineString::new(ring), vec![])
+}
+
+/**
+ * Creates polygons from the vertices of H3 cells as they are consumed. Nothing is allocated per cell
+ * beyond the polygon itself, so this is the conversion to use for large cell lists.
+ */
+pub fn h3_cells_to_polys(
+    cells: impl IntoIterator<Item = CellIndex>,
+) -> impl Iterator<Item = Polygon> {
+    cells.into_iter().map(|cell| h3_cell_to_poly(&cell))
+}
+
+/**
+ * Dissolves H3 cells into the polygons of their outline, with holes where the cells surround
+ * uncovered areas. Duplicate cells are ignored, but all cells must have the same resolution.
+ */
+pub fn dissolve_h3_cells(
+    cells: impl IntoIterator<Item = CellIndex>,
+) -> Result<MultiPolygon, Box<dyn Error>> {
+    let cells = cells.into_iter().sorted().dedup().collect_vec();
+    Ok(cells.to_geom(true)?)
+}
+
+/**
+ * The exact size of an H3 cell, computed from its boundary on the sphere rather than averaged over
+ * its resolution. Cells vary in area by a factor of two within a resolution, and most of all near
+ * the pentagons.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct H3CellMetrics {
+    pub area_m2: f64,
+    /// The lengths of the edges in H3's direction order, five for pentagons and six otherwise.
+    pub edge_lengths_m: Vec<f64>,
+}
+
+pub fn h3_cell_metrics(cell: CellIndex) -> H3CellMetrics {
+    H3CellMetrics {
+        area_m2: cell.area_m2(),
+        edge_lengths_m: cell.edges().map(|edge| edge.length_m()).collect_vec(),
+    }
+}
+
+/**
+ * Computes the H3 cells at the given resolution that cover a geometry. Points are covered by the
+ * cell containing them, and polygonal geometries are polyfilled according to the containment mode.
+ * Polygon holes are honored like exteriors, so cells within a hole are left out in every mode.
+ * With `normalize_winding`, exteriors are wound counterclockwise and holes clockwise before they
+ * are polyfilled, as H3 expects.
+ */
+pub fn get_h3_covering(
+    geometry: &Geometry,
+    resolution: Resolution,
+    mode: ContainmentMode,
+    normalize_winding: bool,
+) -> Result<Vec<CellIndex>, Box<dyn Error>> {
+    Ok(h3_covering_cells(geometry, resolution, mode, normalize_winding)?.collect_vec())
+}
+
+/**
+ * The cells of `get_h3_covering`, produced lazily in the same order, so that fine-resolution
+ * coverings can be written out as they are found. The geometry is validated up front, so producing
+ * the cells cannot fail.
+ *
+ * H3 polyfills take ownership of their polygon, so each polygon is copied only while its cells are
+ * produced, rather than the whole geometry at once.
+ */
+pub fn h3_covering_cells(
+    geometry: &Geometry,
+    resolution: Resolution,
+    mode: ContainmentMode,
+    normalize_winding: bool,
+) -> Result<impl Iterator<Item = CellIndex> + '_, Box<dyn Error>> {
+    let mut parts = vec![];
+    push_covering_parts(geometry, resolution, &mut parts)?;
+    let config = PolyfillConfig::new(resolution).containment_mode(mode);
+    Ok(parts.into_iter().flat_map(move |part| match part {
+        CoveringPart::Cell(cell) => vec![cell],
+        CoveringPart::Polygon(polygon) => to_h3_polygon(polygon, normalize_winding)
+            .expect("polygons are validated before they are covered")
+            .to_cells(config)
+            .collect_vec(),
+    }))
+}
+
+enum CoveringPart<'a> {
+    Cell(CellIndex),
+    Polygon(&'a Polygon),
+}
+
+fn push_covering_parts<'a>(
+    geometry: &'a Geometry,
+    resolution: Resolution,
+    parts: &mut Vec<CoveringPart<'a>>,
+) -> Result<(), Box<dyn Error>> {
+    match geometry {
+        // Point and point composite types.
+        Geometry::Point(point) => parts.push(CoveringPart::Cell(get_h3_point_covering(
+            point, resolution,
+        )?)),
+        Geometry::MultiPoint(mpoint) => {
+            for point in mpoint {
+                parts.push(CoveringPart::Cell(get_h3_point_covering(
+                    point, resolution,
+                )?));
+            }
+        }
+
+        // Polygon and polygon composite types.
+        Geometry::Polygon(poly) => {
+            to_h3_polygon(poly, false)?;
+            parts.push(CoveringPart::Polygon(poly));
+        }
+        Geometry::MultiPolygon(mpoly) => {
+            for poly in mpoly {
+                to_h3_polygon(poly, false)?;
+                parts.push(CoveringPart::Polygon(poly));
+            }
+        }
+
+        // Recurse on geometry collection.
+        Geometry::GeometryCollection(collection) => {
+            for geometry in collection {
+                push_covering_parts(geometry, resolution, parts)?;
+            }
+        }
+
+        _ => return Err(not_polygonal(geometry)),
+    }
+    Ok(())
+}
+
+/**
+ * Merges H3 cells into a sorted list without duplicates or cells whose ancestor is also present.
+ * If `compact` is set, complete sets of sibling cells are then replaced by their parent until no
+ * more can be, like `CellIndex::compact` but for cells of mixed resolutions.
+ */
+pub fn merge_h3_cells(cells: impl IntoIterator<Item = CellIndex>, compact: bool) -> Vec<CellIndex> {
+    let cells: HashSet<CellIndex> = cells.into_iter().collect();
+    let mut merged: HashSet<CellIndex> = cells
+        .iter()
+        .filter(|cell| {
+            !std::iter::successors(cell.resolution().pred(), |r| r.pred())
+                .filter_map(|r| cell.parent(r))
+                .any(|ancestor| cells.contains(&ancestor))
+        })
+        .copied()
+        .collect();
+
+    if compact {
+        // Finest resolutions first, so that new parents can be compacted in turn.
+        let mut resolution = Resolution::Fifteen;
+        while let Some(parent_resolution) = resolution.pred() {
+            let parents = merged
+                .iter()
+                .filter(|cell| cell.resolution() == resolution)
+                .filter_map(|cell| cell.parent(parent_resolution))
+                .counts();
+            for (parent, count) in parents {
+                if count as u64 == parent.children_count(resolution) {
+                    parent.children(resolution).for_each(|child| {
+                        merged.remove(&child);
+                    });
+                    merged.insert(parent);
+                }
+            }
+            resolution = parent_resolution;
+        }
+    }
+    merged.into_iter().sorted().collect_vec()
+}
+
+/**
+ * Converts H3 cells to a sorted list of unique cells at one resolution. Finer cells are replaced by
+ * their ancestor and coarser cells by their descendants.
+ */
+pub fn h3_cells_at_resolution(
+    cells: impl IntoIterator<Item = CellIndex>,
+    resolution: Resolution,
+) -> Vec<CellIndex> {
+    cells
+        .into_iter()
+        .flat_map(|cell| match cell.parent(resolution) {
+            Some(ancestor) => vec![ancestor],
+            None => cell.children(resolution).collect_vec(),
+        })
+        .sorted()
+        .dedup()
+        .collect_vec()
+}
+
+/**
+ * Counts the points within each H3 cell at a resolution, and sums their weights.
+ */
+pub fn bin_h3_points(
+    points: impl IntoIterator<Item = (Point, f64)>,
+    resolution: Resolution,
+) -> Result<BTreeMap<CellIndex, Bin>, Box<dyn Error>> {
+    bin_points(points, |point| get_h3_point_covering(&point, resolution))
+}
+
+fn get_h3_point_covering(
+    point: &Point,
+    resolution: Resolution,
+) -> Result<CellIndex, Box<dyn Error>> {
+    Ok(LatLng::new(point.y(), point.x()).map(|c| c.to_cell(resolution))?)
+}
+
+fn to_h3_polygon(
+    polygon: &Polygon,
+    normalize_winding: bool,
+) -> Result<h3o::geom::Polygon, InvalidGeometry> {
+    let polygon = match normalize_winding {
+        true => normalized_polygon(polygon).into_owned(),
+        false => polygon.clone(),
+    };
+    h3o::geom::Polygon::from_degrees(polygon)
+}
+
+/**
+ * The error for geometries without area, which cannot be polyfilled or cut. This matches the error
+ * of converting them to a polygon, without copying them to find out.
+ */
+fn not_polygonal(geometry: &Geometry) -> Box<dyn Error> {
+    let found = match geometry {
+        Geometry::Line(_) => type_name::<Line>(),
+        Geometry::LineString(_) => type_name::<LineString>(),
+        Geometry::MultiLineString(_) => type_name::<MultiLineString>(),
+        Geometry::Rect(_) => type_name::<Rect>(),
+        Geometry::Triangle(_) => type_name::<Triangle>(),
+        Geometry::Point(_) => type_name::<Point>(),
+        Geometry::MultiPoint(_) => type_name::<MultiPoint>(),
+        Geometry::Polygon(_) => type_name::<Polygon>(),
+        Geometry::MultiPolygon(_) => type_name::<MultiPolygon>(),
+        Geometry::GeometryCollection(_) => type_name::<GeometryCollection>(),
+    };
+    Box::new(geo_types::Error::MismatchedGeometry {
+        expected: type_name::<Polygon>(),
+        found,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{polygon, Geometry, MultiPolygon};
+    use h3o::geom::ContainmentMode;
+    use h3o::{CellIndex, LatLng, Resolution};
+    use itertools::Itertools;
+    use wkt::TryFromWkt;
+
+    use super::{
+        dissolve_h3_cells, get_h3_covering, h3_cell_metrics, h3_cells_at_resolution,
+        h3_covering_cells, merge_h3_cells,
+    };
+    use crate::geom::fill_holes;
+
+    #[test]
+    fn test_h3_covering_across_antimeridian() {
+        // h3o covers polygons across the antimeridian as they are drawn on the globe, rather than
+        // around the rest of the world: with the cells of its halves on either side, and about as
+        // many as the same polygon at 0°, give or take the alignment of the hexagons.
+        let wkt = |wkt| Geometry::<f64>::try_from_wkt_str(wkt).unwrap();
+        let crossing = wkt("POLYGON((179 -17,-179 -17,-179 -16,179 -16,179 -17))");
+        let east = wkt("POLYGON((179 -17,180 -17,180 -16,179 -16,179 -17))");
+        let west = wkt("POLYGON((-180 -17,-179 -17,-179 -16,-180 -16,-180 -17))");
+        let centered = wkt("POLYGON((-1 -17,1 -17,1 -16,-1 -16,-1 -17))");
+        for mode in [
+            ContainmentMode::ContainsCentroid,
+            ContainmentMode::IntersectsBoundary,
+        ] {
+            let cover = |geometry| get_h3_covering(geometry, Resolution::Five, mode, true).unwrap();
+            let halves = cover(&east)
+                .into_iter()
+                .chain(cover(&west))
+                .sorted()
+                .dedup()
+                .collect_vec();
+            let covering = cover(&crossing).into_iter().sorted().collect_vec();
+            assert_eq!(covering, halves, "{:?}", mode);
+
+            let ratio = covering.len() as f64 / cover(&centered).len() as f64;
+            assert!((ratio - 1.0).abs() < 0.2, "{:?}: {}", mode, ratio);
+        }
+    }
+
+    #[test]
+    fn test_h3_covering_cells() {
+        let geometry = Geometry::<f64>::try_from_wkt_str(
+            "GEOMETRYCOLLECTION(POINT(1 1),MULTIPOLYGON(((0 0,0.1 0,0.1 0.1,0 0)),((2 2,2.1 2,2.1 2.1,2 2))))",
+        )
+        .unwrap();
+        let mode = ContainmentMode::IntersectsBoundary;
+        let cells = get_h3_covering(&geometry, Resolution::Eight, mode, true).unwrap();
+        assert_eq!(
+            cells[0],
+            LatLng::new(1.0, 1.0).unwrap().to_cell(Resolution::Eight)
+        );
+        assert!(cells.len() > 2);
+
+        // Invalid parts fail before any cells are produced.
+        let line = Geometry::<f64>::try_from_wkt_str("LINESTRING(0 0,1 1)").unwrap();
+        assert!(h3_covering_cells(&line, Resolution::Eight, mode, true).is_err());
+        let infinite = Geometry::MultiPolygon(MultiPolygon(vec![
+            polygon![(x: 0.0, y: 0.0), (x: 0.1, y: 0.0), (x: 0.1, y: 0.1)],
+            polygon![(x: 2.0, y: 2.0), (x: f64::INFINITY, y: 2.0), (x: 2.1, y: 2.1)],
+        ]));
+        assert!(h3_covering_cells(&infinite, Resolution::Eight, mode, true).is_err());
+    }
+
+    #[test]
+    fn test_h3_covering_normalizes_winding() {
+        let ccw = Geometry::<f64>::try_from_wkt_str(
+            "POLYGON((0 0,1 0,1 1,0 1,0 0),(0.2 0.2,0.2 0.8,0.8 0.8,0.8 0.2,0.2 0.2))",
+        )
+        .unwrap();
+        let cw = Geometry::<f64>::try_from_wkt_str(
+            "POLYGON((0 0,0 1,1 1,1 0,0 0),(0.2 0.2,0.8 0.2,0.8 0.8,0.2 0.8,0.2 0.2))",
+        )
+        .unwrap();
+        let mode = ContainmentMode::ContainsCentroid;
+        assert_eq!(
+            get_h3_covering(&cw, Resolution::Six, mode, true).unwrap(),
+            get_h3_covering(&ccw, Resolution::Six, mode, true).unwrap()
+        );
+        assert_eq!(
+            get_h3_covering(&ccw, Resolution::Six, mode, false).unwrap(),
+            get_h3_covering(&ccw, Resolution::Six, mode, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_h3_covering_excludes_holes() {
+        let mut lake = Geometry::<f64>::try_from_wkt_str(
+            "POLYGON((0 0,1 0,1 1,0 1,0 0),(0.2 0.2,0.2 0.8,0.8 0.8,0.8 0.2,0.2 0.2))",
+        )
+        .unwrap();
+        let water = LatLng::new(0.5, 0.5).unwrap().to_cell(Resolution::Six);
+        for mode in [
+            ContainmentMode::IntersectsBoundary,
+            ContainmentMode::ContainsCentroid,
+            ContainmentMode::ContainsBoundary,
+        ] {
+            let cells = get_h3_covering(&lake, Resolution::Six, mode, true).unwrap();
+            assert!(!cells.is_empty() && !cells.contains(&water));
+        }
+
+        assert_eq!(fill_holes(&mut lake), 1);
+        let cells = get_h3_covering(
+            &lake,
+            Resolution::Six,
+            ContainmentMode::IntersectsBoundary,
+            true,
+        )
+        .unwrap();
+        assert!(cells.contains(&water));
+    }
+
+    #[test]
+    fn test_h3_point_covering_in_degrees() {
+        let point = Geometry::<f64>::try_from_wkt_str("POINT(-122.4 37.8)").unwrap();
+        let cells = get_h3_covering(
+            &point,
+            Resolution::Five,
+            ContainmentMode::IntersectsBoundary,
+            true,
+        )
+        .unwrap();
+        assert_eq!(cells, [CellIndex::try_from(0x85283083fffffff).unwrap()]);
+    }
+
+    #[test]
+    fn test_dissolve_h3_cells() {
+        let parent = CellIndex::try_from(0x851fb467fffffff).unwrap();
+        let children = parent.children(Resolution::Six).collect_vec();
+        let dissolved = dissolve_h3_cells(children.iter().chain(&children).copied()).unwrap();
+        assert_eq!(dissolved.0.len(), 1);
+        assert!(dissolved.0[0].interiors().is_empty());
+
+        // A ring of cells dissolves into a polygon with a hole.
+        let ring = children[0].grid_ring_fast(1).map(Option::unwrap);
+        let dissolved = dissolve_h3_cells(ring).unwrap();
+        assert_eq!(dissolved.0.len(), 1);
+        assert_eq!(dissolved.0[0].interiors().len(), 1);
+
+        assert!(dissolve_h3_cells([parent, children[0]]).is_err());
+    }
+
+    #[test]
+    fn test_h3_cell_metrics() {
+        let cell = CellIndex::try_from(0x8a1fb46622dffff).unwrap();
+        let metrics = h3_cell_metrics(cell);
+        assert!((metrics.area_m2 - 13407.827).abs() < 1e-3);
+        assert_eq!(metrics.edge_lengths_m.len(), 6);
+
+        // Pentagons are smaller than the hexagons around them.
+        let pentagon = CellIndex::base_cells()
+            .find(|cell| cell.is_pentagon())
+            .unwrap()
+            .center_child(Resolution::Five)
+            .unwrap();
+        let metrics = h3_cell_metrics(pentagon);
+        assert_eq!(metrics.edge_lengths_m.len(), 5);
+        let hexagon = pentagon.grid_disk::<Vec<_>>(1)[1];
+        assert!(metrics.area_m2 < h3_cell_metrics(hexagon).area_m2);
+    }
+
+    #[test]
+    fn test_merge_h3_cells() {
+        let parent = CellIndex::try_from(0x851fb467fffffff).unwrap();
+        let children = parent.children(Resolution::Six).collect_vec();
+        let grandchild = children[0].children(Resolution::Seven).next().unwrap();
+        let other = CellIndex::try_from(0x85283473fffffff).unwrap();
+
+        // Duplicates and descendants of other cells are dropped.
+        let mut cells = vec![children[0], grandchild, children[0], children[1]];
+        assert_eq!(
+            merge_h3_cells(cells.clone(), false),
+            vec![children[0], children[1]]
+        );
+
+        // Completing the siblings compacts them into their parent.
+        cells.extend(&children[2..]);
+        cells.push(other);
+        assert_eq!(
+            merge_h3_cells(cells.clone(), false).len(),
+            children.len() + 1
+        );
+        let mut expected = vec![parent, other];
+        expected.sort();
+        assert_eq!(merge_h3_cells(cells, true), expected);
+
+        assert_eq!(
+            h3_cells_at_resolution([parent, grandchild], Resolution::Six),
+            children
+        );
+    }
+}
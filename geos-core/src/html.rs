@@ -0,0 +1,141 @@
+/*!
+ * Writing of geometries as a single HTML page with a Leaflet map, to share results with anyone who
+ * has a browser. The geometries are embedded in the page as GeoJSON, and Leaflet and the
+ * OpenStreetMap base map are loaded from the web when the page is opened.
+ */
+use std::io::{self, Write};
+
+use geo_types::Geometry;
+
+use crate::kml::escape;
+
+/// The version of Leaflet loaded by the page.
+const LEAFLET_URL: &str = "https://unpkg.com/leaflet@1.9.4/dist";
+
+/// The colors that layers are drawn in, in turn.
+const COLORS: [&str; 8] = [
+    "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#a65628", "#f781bf", "#999999",
+];
+
+/**
+ * Writes an HTML page with a map of named lng/lat geometries, each drawn as a layer of its own color
+ * that can be toggled. The map starts zoomed to fit all of them.
+ */
+pub fn write_leaflet_html(
+    out: &mut dyn Write,
+    title: &str,
+    layers: &[(String, Geometry)],
+) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, r#"<meta charset="utf-8">"#)?;
+    writeln!(
+        out,
+        r#"<meta name="viewport" content="width=device-width, initial-scale=1">"#
+    )?;
+    writeln!(out, "<title>{}</title>", escape(title))?;
+    writeln!(
+        out,
+        r#"<link rel="stylesheet" href="{}/leaflet.css">"#,
+        LEAFLET_URL
+    )?;
+    writeln!(out, r#"<script src="{}/leaflet.js"></script>"#, LEAFLET_URL)?;
+    writeln!(
+        out,
+        "<style>html, body, #map {{ height: 100%; margin: 0; }}</style>"
+    )?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, r#"<div id="map"></div>"#)?;
+    writeln!(out, "<script>")?;
+    writeln!(out, "const layers = [")?;
+    for (i, (name, geometry)) in layers.iter().enumerate() {
+        let geometry = geojson::Geometry::from(geometry);
+        writeln!(
+            out,
+            "  {{name: {}, color: \"{}\", geometry: {}}},",
+            script_string(name),
+            COLORS[i % COLORS.len()],
+            // A `</script>` within the data would end the script early.
+            geometry.to_string().replace("</", "<\\/")
+        )?;
+    }
+    writeln!(out, "];")?;
+    out.write_all(SCRIPT.as_bytes())?;
+    writeln!(out, "</script>")?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+/// Draws the layers over an OpenStreetMap base map, with a control to toggle them.
+const SCRIPT: &str = r#"const map = L.map("map");
+L.tileLayer("https://tile.openstreetmap.org/{z}/{x}/{y}.png", {
+  maxZoom: 19,
+  attribution: '&copy; <a href="https://www.openstreetmap.org/copyright">OpenStreetMap</a> contributors',
+}).addTo(map);
+const overlays = {};
+for (const {name, color, geometry} of layers) {
+  overlays[name] = L.geoJSON(geometry, {
+    style: {color, weight: 2, fillOpacity: 0.2},
+    pointToLayer: (_, latlng) => L.circleMarker(latlng, {color, radius: 4}),
+  }).bindTooltip(name).addTo(map);
+}
+L.control.layers(null, overlays, {collapsed: false}).addTo(map);
+const bounds = L.featureGroup(Object.values(overlays)).getBounds();
+if (bounds.isValid()) {
+  map.fitBounds(bounds, {padding: [20, 20]});
+} else {
+  map.setView([0, 0], 2);
+}
+"#;
+
+/**
+ * A string as a JavaScript string literal that can be embedded in a script element.
+ */
+fn script_string(s: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '<' => literal.push_str("\\u003c"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, Geometry};
+
+    use super::write_leaflet_html;
+
+    #[test]
+    fn test_write_leaflet_html() {
+        let layers = [
+            (
+                "a \"b\"".to_string(),
+                Geometry::Point(point!(x: 1.0, y: 2.0)),
+            ),
+            (
+                "</script>".to_string(),
+                Geometry::Point(point!(x: 3.0, y: 4.0)),
+            ),
+        ];
+        let mut out = vec![];
+        write_leaflet_html(&mut out, "<map>", &layers).unwrap();
+        let html = String::from_utf8(out).unwrap();
+
+        assert!(html.contains("<title>&lt;map&gt;</title>"));
+        assert!(html.contains(
+            r##"{name: "a \"b\"", color: "#e41a1c", geometry: {"type":"Point","coordinates":[1.0,2.0]}},"##
+        ));
+        // Only the page's own script element is closed.
+        assert_eq!(html.matches("</script>").count(), 2);
+    }
+}
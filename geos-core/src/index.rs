@@ -0,0 +1,220 @@
+use std::f64::consts::PI;
+
+use geo::{BoundingRect, Closest, HaversineClosestPoint, HaversineDistance, Intersects};
+use geo_types::{Geometry, Point};
+use itertools::Itertools;
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+
+/// Mean Earth radius in meters, matching the radius used by `geo`'s haversine algorithms.
+pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+type IndexedEnvelope = GeomWithData<Rectangle<[f64; 2]>, usize>;
+
+/**
+ * Computes lat/lng envelopes that together contain every point within `radius_m` meters of
+ * `center`. Searches that wrap around the antimeridian are split into two envelopes, and searches
+ * that reach a pole span all longitudes.
+ *
+ * The envelopes are conservative: they may contain points that are farther away than the radius,
+ * so callers should refine candidates by their exact distance.
+ *
+ * http://janmatuschek.de/LatitudeLongitudeBoundingCoordinates
+ */
+pub fn search_envelopes(center: &Point, radius_m: f64) -> Vec<AABB<[f64; 2]>> {
+    let angular_radius = radius_m / EARTH_RADIUS_M;
+    let (lng, lat) = (center.x(), center.y());
+    let min_lat = lat - angular_radius.to_degrees();
+    let max_lat = lat + angular_radius.to_degrees();
+
+    let reaches_pole = min_lat <= -90.0 || max_lat >= 90.0;
+    if reaches_pole || angular_radius.sin() >= lat.to_radians().cos() {
+        return vec![AABB::from_corners(
+            [-180.0, f64::max(min_lat, -90.0)],
+            [180.0, f64::min(max_lat, 90.0)],
+        )];
+    }
+
+    let dlng = (angular_radius.sin() / lat.to_radians().cos())
+        .asin()
+        .to_degrees();
+    let (min_lng, max_lng) = (lng - dlng, lng + dlng);
+    if min_lng < -180.0 {
+        vec![
+            AABB::from_corners([min_lng + 360.0, min_lat], [180.0, max_lat]),
+            AABB::from_corners([-180.0, min_lat], [max_lng, max_lat]),
+        ]
+    } else if max_lng > 180.0 {
+        vec![
+            AABB::from_corners([min_lng, min_lat], [180.0, max_lat]),
+            AABB::from_corners([-180.0, min_lat], [max_lng - 360.0, max_lat]),
+        ]
+    } else {
+        vec![AABB::from_corners([min_lng, min_lat], [max_lng, max_lat])]
+    }
+}
+
+/**
+ * Haversine distance in meters from a point to the closest point of a geometry. Returns `None` for
+ * empty geometries.
+ */
+pub fn haversine_distance_to(geometry: &Geometry, point: &Point) -> Option<f64> {
+    match geometry.haversine_closest_point(point) {
+        Closest::Intersection(p) | Closest::SinglePoint(p) => Some(point.haversine_distance(&p)),
+        Closest::Indeterminate => None,
+    }
+}
+
+/**
+ * An R-tree over the bounding boxes of a set of geometries, used to answer nearest-neighbor
+ * queries by haversine distance without comparing against every geometry.
+ */
+pub struct GeometryIndex<'a> {
+    geometries: &'a [Geometry],
+    tree: RTree<IndexedEnvelope>,
+}
+
+impl<'a> GeometryIndex<'a> {
+    pub fn new(geometries: &'a [Geometry]) -> Self {
+        let envelopes = geometries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, g)| {
+                let bbox = g.bounding_rect()?;
+                let rect =
+                    Rectangle::from_corners(bbox.min().x_y().into(), bbox.max().x_y().into());
+                Some(IndexedEnvelope::new(rect, i))
+            })
+            .collect_vec();
+
+        Self {
+            geometries,
+            tree: RTree::bulk_load(envelopes),
+        }
+    }
+
+    /**
+     * Finds the k geometries closest to a point, returned as (index, distance in meters) pairs in
+     * order of increasing distance.
+     *
+     * A first guess at the search radius is taken from the k nearest bounding boxes in lat/lng
+     * space. All geometries whose bounding box intersects the search envelope of that radius are
+     * then ranked by exact haversine distance. If fewer than k of them lie within the radius, the
+     * radius is doubled and the search repeated.
+     */
+    pub fn nearest(&self, point: &Point, k: usize) -> Vec<(usize, f64)> {
+        let k = usize::min(k, self.tree.size());
+        if k == 0 {
+            return vec![];
+        }
+
+        let mut radius_m = self
+            .tree
+            .nearest_neighbor_iter(&[point.x(), point.y()])
+            .take(k)
+            .filter_map(|e| self.distance(e.data, point))
+            .fold(0.0, f64::max);
+
+        loop {
+            // Past half the Earth's circumference, every geometry is within the search radius.
+            let covers_world = radius_m >= PI * EARTH_RADIUS_M;
+            let candidates = if covers_world {
+                self.tree.iter().map(|e| e.data).collect_vec()
+            } else {
+                search_envelopes(point, radius_m)
+                    .iter()
+                    .flat_map(|envelope| self.tree.locate_in_envelope_intersecting(envelope))
+                    .map(|e| e.data)
+                    .unique()
+                    .collect_vec()
+            };
+
+            let nearest = candidates
+                .into_iter()
+                .filter_map(|i| self.distance(i, point).map(|d| (i, d)))
+                .filter(|(_, d)| covers_world || *d <= radius_m)
+                .sorted_by(|(i1, d1), (i2, d2)| d1.total_cmp(d2).then(i1.cmp(i2)))
+                .collect_vec();
+
+            if nearest.len() >= k || covers_world {
+                return nearest.into_iter().take(k).collect_vec();
+            }
+            radius_m = f64::max(2.0 * radius_m, 1.0);
+        }
+    }
+
+    /**
+     * Finds the geometries that a point lies within or on the boundary of, returned as indices in
+     * increasing order. Only geometries whose bounding box contains the point are tested exactly.
+     */
+    pub fn containing(&self, point: &Point) -> Vec<usize> {
+        self.tree
+            .locate_all_at_point(&[point.x(), point.y()])
+            .map(|e| e.data)
+            .filter(|i| self.geometries[*i].intersects(point))
+            .sorted()
+            .collect_vec()
+    }
+
+    fn distance(&self, i: usize, point: &Point) -> Option<f64> {
+        haversine_distance_to(&self.geometries[i], point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{polygon, Geometry, Point};
+    use itertools::Itertools;
+    use rand::Rng;
+
+    use super::{haversine_distance_to, GeometryIndex};
+    use crate::samplers::create_rng;
+
+    #[test]
+    fn test_nearest_matches_brute_force() {
+        let mut rng = create_rng(0);
+        let mut random_point =
+            || Point::new(rng.gen_range(-180.0..180.0), rng.gen_range(-90.0..90.0));
+        let candidates = (0..500)
+            .map(|_| Geometry::from(random_point()))
+            .collect_vec();
+        let index = GeometryIndex::new(&candidates);
+
+        // Include queries next to the antimeridian and the poles.
+        let mut queries = vec![
+            Point::new(179.9, 0.0),
+            Point::new(-179.9, 10.0),
+            Point::new(0.0, 89.9),
+        ];
+        queries.extend((0..20).map(|_| random_point()));
+        for query in queries {
+            let expected = candidates
+                .iter()
+                .enumerate()
+                .map(|(i, g)| (i, haversine_distance_to(g, &query).unwrap()))
+                .sorted_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+                .take(5)
+                .collect_vec();
+            assert_eq!(index.nearest(&query, 5), expected);
+        }
+    }
+
+    #[test]
+    fn test_containing() {
+        let fences = vec![
+            Geometry::from(
+                polygon![(x: 0.0, y: 0.0), (x: 2.0, y: 0.0), (x: 2.0, y: 2.0), (x: 0.0, y: 2.0)],
+            ),
+            Geometry::from(
+                polygon![(x: 1.0, y: 1.0), (x: 3.0, y: 1.0), (x: 3.0, y: 3.0), (x: 1.0, y: 3.0)],
+            ),
+            // A triangle whose bounding box contains (1.8, 0.2) although the triangle does not.
+            Geometry::from(polygon![(x: 0.0, y: 0.0), (x: 0.0, y: 2.0), (x: 2.0, y: 2.0)]),
+        ];
+        let index = GeometryIndex::new(&fences);
+        assert_eq!(index.containing(&Point::new(1.5, 1.5)), vec![0, 1, 2]);
+        assert_eq!(index.containing(&Point::new(1.8, 0.2)), vec![0]);
+        assert_eq!(index.containing(&Point::new(3.0, 2.0)), vec![1]);
+        assert!(index.containing(&Point::new(5.0, 5.0)).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+/*!
+ * Writing of geometries as KML placemarks, e.g. to view coverings in Google Earth.
+ */
+use std::io::{self, Write};
+
+use geo_types::{Coord, Geometry, LineString, Polygon};
+
+/**
+ * Writes the start of a KML document, which the placemarks follow.
+ */
+pub fn write_kml_start(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(out, "<Document>")
+}
+
+/**
+ * Writes the end of a KML document.
+ */
+pub fn write_kml_end(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "</Document>")?;
+    writeln!(out, "</kml>")
+}
+
+/**
+ * Writes a lng/lat geometry as a placemark on one line, optionally named. Collections are written
+ * as a MultiGeometry, and lines, rectangles, and triangles as line strings and polygons, which KML
+ * has no elements for.
+ */
+pub fn write_placemark(
+    out: &mut dyn Write,
+    name: Option<&str>,
+    geometry: &Geometry,
+) -> io::Result<()> {
+    out.write_all(b"<Placemark>")?;
+    if let Some(name) = name {
+        write!(out, "<name>{}</name>", escape(name))?;
+    }
+    write_geometry(out, geometry)?;
+    writeln!(out, "</Placemark>")
+}
+
+fn write_geometry(out: &mut dyn Write, geometry: &Geometry) -> io::Result<()> {
+    match geometry {
+        Geometry::Point(point) => {
+            out.write_all(b"<Point>")?;
+            write_coords(out, &[point.0])?;
+            out.write_all(b"</Point>")
+        }
+        Geometry::Line(line) => write_line_string(out, &LineString::from(*line)),
+        Geometry::LineString(line) => write_line_string(out, line),
+        Geometry::Polygon(polygon) => write_polygon(out, polygon),
+        Geometry::Rect(rect) => write_polygon(out, &rect.to_polygon()),
+        Geometry::Triangle(triangle) => write_polygon(out, &triangle.to_polygon()),
+        Geometry::MultiPoint(points) => {
+            let members = points.iter().map(|point| Geometry::Point(*point));
+            write_multi_geometry(out, members)
+        }
+        Geometry::MultiLineString(lines) => {
+            let members = lines.iter().map(|line| Geometry::LineString(line.clone()));
+            write_multi_geometry(out, members)
+        }
+        Geometry::MultiPolygon(polygons) => {
+            let members = polygons.iter().map(|p| Geometry::Polygon(p.clone()));
+            write_multi_geometry(out, members)
+        }
+        Geometry::GeometryCollection(collection) => {
+            write_multi_geometry(out, collection.iter().cloned())
+        }
+    }
+}
+
+fn write_multi_geometry(
+    out: &mut dyn Write,
+    members: impl Iterator<Item = Geometry>,
+) -> io::Result<()> {
+    out.write_all(b"<MultiGeometry>")?;
+    for member in members {
+        write_geometry(out, &member)?;
+    }
+    out.write_all(b"</MultiGeometry>")
+}
+
+fn write_line_string(out: &mut dyn Write, line: &LineString) -> io::Result<()> {
+    out.write_all(b"<LineString>")?;
+    write_coords(out, &line.0)?;
+    out.write_all(b"</LineString>")
+}
+
+fn write_polygon(out: &mut dyn Write, polygon: &Polygon) -> io::Result<()> {
+    out.write_all(b"<Polygon><outerBoundaryIs><LinearRing>")?;
+    write_coords(out, &polygon.exterior().0)?;
+    out.write_all(b"</LinearRing></outerBoundaryIs>")?;
+    for hole in polygon.interiors() {
+        out.write_all(b"<innerBoundaryIs><LinearRing>")?;
+        write_coords(out, &hole.0)?;
+        out.write_all(b"</LinearRing></innerBoundaryIs>")?;
+    }
+    out.write_all(b"</Polygon>")
+}
+
+fn write_coords(out: &mut dyn Write, coords: &[Coord]) -> io::Result<()> {
+    out.write_all(b"<coordinates>")?;
+    for (i, c) in coords.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b" ")?;
+        }
+        write!(out, "{},{}", c.x, c.y)?;
+    }
+    out.write_all(b"</coordinates>")
+}
+
+/**
+ * Escapes the characters of text that are special in XML.
+ */
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, polygon, Geometry, GeometryCollection};
+
+    use super::{write_kml_end, write_kml_start, write_placemark};
+
+    #[test]
+    fn test_write_kml() {
+        let mut out = vec![];
+        write_kml_start(&mut out).unwrap();
+        let square =
+            polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 0.0)];
+        write_placemark(&mut out, Some("a<b"), &Geometry::Polygon(square)).unwrap();
+        let collection = GeometryCollection::new_from(vec![point!(x: 1.5, y: -2.0).into()]);
+        write_placemark(&mut out, None, &Geometry::GeometryCollection(collection)).unwrap();
+        write_kml_end(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            [
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#,
+                "<Document>",
+                "<Placemark><name>a&lt;b</name><Polygon><outerBoundaryIs><LinearRing><coordinates>0,0 1,0 1,1 0,0</coordinates></LinearRing></outerBoundaryIs></Polygon></Placemark>",
+                "<Placemark><MultiGeometry><Point><coordinates>1.5,-2</coordinates></Point></MultiGeometry></Placemark>",
+                "</Document>",
+                "</kml>",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,36 @@
+/*!
+ * Core geographic operations behind the GeoS commandline tool: S2 and H3 coverings, cutting and
+ * partitioning of regions, random sampling, clustering, raster sampling, and n-vector math.
+ */
+pub mod arrow;
+pub mod bins;
+pub mod braille;
+pub mod cluster;
+pub mod crs;
+pub mod geom;
+pub mod gpx;
+pub mod h3_cells;
+pub mod html;
+pub mod index;
+pub mod kml;
+pub mod linear;
+pub mod magnetic;
+pub mod matching;
+pub mod measure;
+pub mod mmap;
+pub mod morph;
+pub mod mvt;
+pub mod nvec;
+mod parallel;
+pub mod parquet;
+pub mod raster;
+pub mod read;
+pub mod s2_cells;
+pub mod samplers;
+pub mod shapefile;
+pub mod simulate;
+pub mod sun;
+pub mod tiles;
+pub mod topojson;
+pub mod validity;
+pub mod wkb;
@@ -0,0 +1,202 @@
+use geo::{
+    Closest, GeodesicDistance, GeodesicIntermediate, HaversineClosestPoint, HaversineDistance,
+};
+use geo_types::{LineString, Point};
+use itertools::Itertools;
+
+/**
+ * The result of snapping a point onto a line string.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Snap {
+    /// The closest point on the line.
+    pub point: Point,
+    /// The haversine distance in meters from the original point to the line.
+    pub distance_m: f64,
+    /// The haversine distance in meters along the line from its start to the snapped point.
+    pub measure_m: f64,
+    /// The index of the line segment that the point was snapped onto.
+    pub segment: usize,
+}
+
+/**
+ * Cumulative haversine lengths in meters along a line string, starting with 0 at the first
+ * coordinate. Entry i is the length of the line up to its i-th coordinate.
+ */
+pub fn cumulative_lengths(line: &LineString) -> Vec<f64> {
+    let mut total = 0.0;
+    std::iter::once(0.0)
+        .chain(line.lines().map(|l| {
+            total += Point::from(l.start).haversine_distance(&Point::from(l.end));
+            total
+        }))
+        .collect_vec()
+}
+
+/**
+ * Snaps a point onto the closest point of a line string by haversine distance, returning `None`
+ * for empty lines. Ties between segments go to the earliest segment.
+ */
+pub fn snap_point(line: &LineString, point: &Point) -> Option<Snap> {
+    snap_point_with_lengths(line, &cumulative_lengths(line), point)
+}
+
+/**
+ * Like `snap_point`, but reuses precomputed cumulative lengths when snapping many points onto the
+ * same line.
+ */
+pub fn snap_point_with_lengths(
+    line: &LineString,
+    cumulative_m: &[f64],
+    point: &Point,
+) -> Option<Snap> {
+    if line.0.len() == 1 {
+        let snapped = Point::from(line.0[0]);
+        return Some(Snap {
+            point: snapped,
+            distance_m: point.haversine_distance(&snapped),
+            measure_m: 0.0,
+            segment: 0,
+        });
+    }
+
+    line.lines()
+        .enumerate()
+        .filter_map(|(i, segment)| {
+            let snapped = match segment.haversine_closest_point(point) {
+                Closest::Intersection(p) | Closest::SinglePoint(p) => p,
+                Closest::Indeterminate => return None,
+            };
+            Some(Snap {
+                point: snapped,
+                distance_m: point.haversine_distance(&snapped),
+                measure_m: cumulative_m[i]
+                    + Point::from(segment.start).haversine_distance(&snapped),
+                segment: i,
+            })
+        })
+        .min_by(|s1, s2| s1.distance_m.total_cmp(&s2.distance_m))
+}
+
+/**
+ * Resamples a line string into points spaced `spacing_m` geodesic meters apart along the line,
+ * starting at its first coordinate. The end of the line is only included if the length of the line
+ * is a multiple of the spacing. Each point is returned with its geodesic distance along the line.
+ */
+pub fn resample_every(line: &LineString, spacing_m: f64) -> Vec<(f64, Point)> {
+    let cumulative_m = geodesic_cumulative_lengths(line);
+    let length_m = cumulative_m.last().copied().unwrap_or_default();
+    // Allow for rounding error when the length is a multiple of the spacing.
+    let count = (length_m / spacing_m + 1e-9).floor() as usize + 1;
+    let measures = (0..count).map(|i| i as f64 * spacing_m).collect_vec();
+    points_along(line, &cumulative_m, &measures)
+}
+
+/**
+ * Resamples a line string into `count` points spaced equally along the line by geodesic distance,
+ * including both ends. Each point is returned with its geodesic distance along the line.
+ */
+pub fn resample_count(line: &LineString, count: usize) -> Vec<(f64, Point)> {
+    let cumulative_m = geodesic_cumulative_lengths(line);
+    let length_m = cumulative_m.last().copied().unwrap_or_default();
+    let measures = match count {
+        0 => vec![],
+        1 => vec![0.0],
+        _ => (0..count)
+            .map(|i| length_m * i as f64 / (count - 1) as f64)
+            .collect_vec(),
+    };
+    points_along(line, &cumulative_m, &measures)
+}
+
+pub(crate) fn geodesic_cumulative_lengths(line: &LineString) -> Vec<f64> {
+    let mut total = 0.0;
+    std::iter::once(0.0)
+        .chain(line.lines().map(|l| {
+            total += Point::from(l.start).geodesic_distance(&Point::from(l.end));
+            total
+        }))
+        .collect_vec()
+}
+
+/**
+ * Looks up the points at increasing distances along a line string, following the geodesic of the
+ * segment that each distance falls within.
+ */
+fn points_along(line: &LineString, cumulative_m: &[f64], measures: &[f64]) -> Vec<(f64, Point)> {
+    let coords = &line.0;
+    if coords.is_empty() {
+        return vec![];
+    }
+    let mut segment = 0;
+    measures
+        .iter()
+        .map(|&measure_m| {
+            while segment + 2 < coords.len() && cumulative_m[segment + 1] < measure_m {
+                segment += 1;
+            }
+            if coords.len() == 1 {
+                return (measure_m, coords[0].into());
+            }
+            let (start_m, end_m) = (cumulative_m[segment], cumulative_m[segment + 1]);
+            let t = if end_m > start_m {
+                ((measure_m - start_m) / (end_m - start_m)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let (start, end) = (
+                Point::from(coords[segment]),
+                Point::from(coords[segment + 1]),
+            );
+            (measure_m, start.geodesic_intermediate(&end, t))
+        })
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{GeodesicDistance, HaversineDistance};
+    use geo_types::{line_string, Point};
+
+    use super::{cumulative_lengths, resample_count, resample_every, snap_point};
+
+    #[test]
+    fn test_snap_point_reports_distance_and_measure() {
+        let line = line_string![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0)];
+        let lengths = cumulative_lengths(&line);
+        assert_eq!(lengths.len(), 3);
+
+        // A point just off the second segment.
+        let point = Point::new(1.01, 0.5);
+        let snap = snap_point(&line, &point).unwrap();
+        assert_eq!(snap.segment, 1);
+        assert!(snap.point.haversine_distance(&Point::new(1.0, 0.5)) < 1.0);
+        assert!((snap.distance_m - point.haversine_distance(&Point::new(1.0, 0.5))).abs() < 1.0);
+        assert!((snap.measure_m - (lengths[1] + lengths[2]) / 2.0).abs() < 1.0);
+
+        // Points beyond the ends snap to the endpoints.
+        let snap = snap_point(&line, &Point::new(-1.0, 0.0)).unwrap();
+        assert_eq!((snap.point, snap.measure_m), (Point::new(0.0, 0.0), 0.0));
+    }
+
+    #[test]
+    fn test_resample_spaces_points_equally() {
+        let line = line_string![(x: 0.0, y: 0.0), (x: 0.01, y: 0.0), (x: 0.01, y: 0.01)];
+
+        let points = resample_every(&line, 100.0);
+        for ((m1, p1), (m2, p2)) in points.iter().zip(points.iter().skip(1)) {
+            assert!((m2 - m1 - 100.0).abs() < 1e-9);
+            // Points on either side of the corner are closer than the spacing.
+            assert!(p1.geodesic_distance(p2) <= 100.0 + 1e-6);
+        }
+        assert_eq!(points[0].1, Point::new(0.0, 0.0));
+
+        // The first segment is slightly longer than the second, so the midpoint is on it.
+        let points = resample_count(&line, 3);
+        assert_eq!(points.len(), 3);
+        let half_m = points[2].0 / 2.0;
+        assert_eq!(points[1].0, half_m);
+        assert!((points[1].1.geodesic_distance(&Point::new(0.0, 0.0)) - half_m).abs() < 1e-6);
+        assert!(points[2].1.geodesic_distance(&Point::new(0.01, 0.01)) < 1e-6);
+    }
+}
@@ -0,0 +1,419 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate};
+use geo_types::Point;
+
+/// The geomagnetic reference radius of the World Magnetic Model in km.
+const REFERENCE_RADIUS_KM: f64 = 6371.2;
+/// WGS84 semi-major axis in km.
+const WGS84_A_KM: f64 = 6378.137;
+/// WGS84 flattening.
+const WGS84_F: f64 = 1.0 / 298.257223563;
+/// The number of years after its epoch for which a World Magnetic Model is valid.
+const VALIDITY_YEARS: f64 = 5.0;
+/// The coefficients of the World Magnetic Model for 2025-2030 (WMM2025), as NOAA distributes them.
+const WMM_COF: &str = include_str!("../data/WMM.COF");
+
+/**
+ * A spherical harmonic model of the Earth's main magnetic field, such as the World Magnetic Model
+ * (WMM), with Gauss coefficients in nT and their secular variation in nT per year.
+ */
+#[derive(Debug, Clone)]
+pub struct MagneticModel {
+    pub name: String,
+    pub epoch: f64,
+    max_degree: usize,
+    /// (n, m, g, h, g per year, h per year)
+    coefficients: Vec<(usize, usize, f64, f64, f64, f64)>,
+}
+
+/**
+ * The magnetic field vector in nT, with components pointing north, east, and down.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MagneticField {
+    pub north: f64,
+    pub east: f64,
+    pub down: f64,
+}
+
+impl MagneticField {
+    /**
+     * Degrees east of true north that a compass points.
+     */
+    pub fn declination_deg(&self) -> f64 {
+        self.east.atan2(self.north).to_degrees()
+    }
+
+    /**
+     * Degrees below the horizontal that the field points.
+     */
+    pub fn inclination_deg(&self) -> f64 {
+        self.down.atan2(self.north.hypot(self.east)).to_degrees()
+    }
+
+    pub fn total_intensity(&self) -> f64 {
+        (self.north.powi(2) + self.east.powi(2) + self.down.powi(2)).sqrt()
+    }
+}
+
+impl MagneticModel {
+    /**
+     * The current World Magnetic Model, WMM2025, which is valid from 2025 to 2030.
+     */
+    pub fn wmm() -> Self {
+        Self::parse(WMM_COF).expect("the embedded WMM coefficients are valid")
+    }
+
+    /**
+     * Reads a model in the `.COF` format that NOAA distributes the WMM in.
+     */
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let cof = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&cof)
+    }
+
+    /**
+     * Parses a `.COF` model: a header line with the epoch and model name, followed by one
+     * `n m g h g_dot h_dot` line per coefficient, and terminated by a line of 9s.
+     */
+    pub fn parse(cof: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = cof.lines().filter(|l| !l.trim().is_empty());
+        let header = lines.next().ok_or("empty magnetic model")?;
+        let mut header = header.split_whitespace();
+        let epoch: f64 = header
+            .next()
+            .and_then(|e| e.parse().ok())
+            .ok_or("magnetic model header must start with the epoch")?;
+        let name = header.next().unwrap_or_default().to_string();
+
+        let mut coefficients = vec![];
+        for line in lines {
+            if line.trim_start().starts_with("9999") {
+                break;
+            }
+            let values = line.split_whitespace().collect::<Vec<_>>();
+            let parse_error = || format!("invalid magnetic model coefficients: {}", line.trim());
+            if values.len() < 6 {
+                return Err(parse_error().into());
+            }
+            let (n, m) = (
+                values[0].parse::<usize>().map_err(|_| parse_error())?,
+                values[1].parse::<usize>().map_err(|_| parse_error())?,
+            );
+            let mut floats = [0.0; 4];
+            for (f, v) in floats.iter_mut().zip(&values[2..6]) {
+                *f = v.parse().map_err(|_| parse_error())?;
+            }
+            if n == 0 || m > n {
+                return Err(parse_error().into());
+            }
+            coefficients.push((n, m, floats[0], floats[1], floats[2], floats[3]));
+        }
+        let max_degree = coefficients.iter().map(|c| c.0).max().unwrap_or(0);
+        if max_degree == 0 {
+            return Err("magnetic model has no coefficients".into());
+        }
+
+        Ok(Self {
+            name,
+            epoch,
+            max_degree,
+            coefficients,
+        })
+    }
+
+    /**
+     * Computes the magnetic field at a point, an altitude above the WGS84 ellipsoid, and a date
+     * given as a decimal year. Dates outside of the model's validity period are rejected, as are
+     * the geographic poles, where the north and east directions are undefined.
+     *
+     * Chulliat, A. et al. "The US/UK World Magnetic Model for 2025-2030: Technical Report." 2025.
+     */
+    pub fn field(
+        &self,
+        point: &Point,
+        altitude_m: f64,
+        year: f64,
+    ) -> Result<MagneticField, Box<dyn Error>> {
+        if year < self.epoch || year > self.epoch + VALIDITY_YEARS {
+            return Err(format!(
+                "{} is outside of the magnetic model's validity period {}-{}",
+                year,
+                self.epoch,
+                self.epoch + VALIDITY_YEARS
+            )
+            .into());
+        }
+        if point.y().abs() >= 90.0 {
+            return Err("the magnetic field direction is undefined at the poles".into());
+        }
+
+        // Geodetic to geocentric spherical coordinates.
+        let lat = point.y().to_radians();
+        let lng = point.x().to_radians();
+        let e2 = WGS84_F * (2.0 - WGS84_F);
+        let prime_vertical_km = WGS84_A_KM / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        let altitude_km = altitude_m / 1000.0;
+        let p = (prime_vertical_km + altitude_km) * lat.cos();
+        let z = (prime_vertical_km * (1.0 - e2) + altitude_km) * lat.sin();
+        let r = p.hypot(z);
+        let lat_c = (z / r).asin();
+
+        let legendre = SchmidtLegendre::new(self.max_degree, lat_c);
+        let dt = year - self.epoch;
+        let (mut north, mut east, mut down) = (0.0, 0.0, 0.0);
+        for &(n, m, g, h, g_dot, h_dot) in &self.coefficients {
+            let (g, h) = (g + dt * g_dot, h + dt * h_dot);
+            let scale = (REFERENCE_RADIUS_KM / r).powi(n as i32 + 2);
+            let (cos_m, sin_m) = ((m as f64 * lng).cos(), (m as f64 * lng).sin());
+            let (pnm, dpnm) = (legendre.p[n][m], legendre.dp[n][m]);
+            north -= scale * (g * cos_m + h * sin_m) * dpnm;
+            east += scale * m as f64 * (g * sin_m - h * cos_m) * pnm;
+            down -= scale * (n as f64 + 1.0) * (g * cos_m + h * sin_m) * pnm;
+        }
+        east /= lat_c.cos();
+
+        // Rotate from geocentric to geodetic north and down.
+        let rotation = lat_c - lat;
+        Ok(MagneticField {
+            north: north * rotation.cos() - down * rotation.sin(),
+            east,
+            down: north * rotation.sin() + down * rotation.cos(),
+        })
+    }
+}
+
+/**
+ * The date as a decimal year, counting days elapsed since the start of the year.
+ */
+pub fn decimal_year(date: &NaiveDate) -> f64 {
+    let days_in_year = if date.leap_year() { 366.0 } else { 365.0 };
+    date.year() as f64 + date.ordinal0() as f64 / days_in_year
+}
+
+/**
+ * Schmidt semi-normalized associated Legendre functions of sin(latitude), and their derivatives with
+ * respect to latitude, indexed by degree n and order m.
+ */
+struct SchmidtLegendre {
+    p: Vec<Vec<f64>>,
+    dp: Vec<Vec<f64>>,
+}
+
+impl SchmidtLegendre {
+    fn new(max_degree: usize, lat: f64) -> Self {
+        let (mu, sin_theta) = (lat.sin(), lat.cos());
+
+        // Unnormalized functions without the Condon-Shortley phase.
+        let mut p = vec![vec![0.0; max_degree + 1]; max_degree + 1];
+        for m in 0..=max_degree {
+            // P(m, m) = (2m - 1)!! sin^m(theta)
+            p[m][m] =
+                (1..=m).map(|k| (2 * k - 1) as f64).product::<f64>() * sin_theta.powi(m as i32);
+            if m < max_degree {
+                p[m + 1][m] = mu * (2 * m + 1) as f64 * p[m][m];
+            }
+            for n in m + 2..=max_degree {
+                p[n][m] = ((2 * n - 1) as f64 * mu * p[n - 1][m]
+                    - (n + m - 1) as f64 * p[n - 2][m])
+                    / (n - m) as f64;
+            }
+        }
+
+        // dP/dlat = -dP/dtheta = ((n + m) P(n - 1, m) - n mu P(n, m)) / sin(theta)
+        let mut dp = vec![vec![0.0; max_degree + 1]; max_degree + 1];
+        for n in 0..=max_degree {
+            for m in 0..=n {
+                let previous = if n > m { p[n - 1][m] } else { 0.0 };
+                dp[n][m] = ((n + m) as f64 * previous - n as f64 * mu * p[n][m]) / sin_theta;
+            }
+        }
+
+        // Schmidt semi-normalization: sqrt((2 - [m == 0]) (n - m)! / (n + m)!)
+        let factorial = |k: usize| (1..=k).map(|i| i as f64).product::<f64>();
+        for n in 0..=max_degree {
+            for m in 0..=n {
+                let norm =
+                    (if m == 0 { 1.0 } else { 2.0 } * factorial(n - m) / factorial(n + m)).sqrt();
+                p[n][m] *= norm;
+                dp[n][m] *= norm;
+            }
+        }
+        Self { p, dp }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use geo_types::Point;
+
+    use super::{decimal_year, MagneticModel};
+
+    #[test]
+    fn test_tilted_dipole_declination() {
+        // A tilted dipole whose h11 term drifts by 100 nT per year.
+        let cof = "    2025.0            TEST-DIPOLE     01/01/2025
+  1  0  -30000.0       0.0        0.0        0.0
+  1  1   -2000.0    5000.0        0.0      100.0
+999999999999999999999999999999999999999999999999
+";
+        let model = MagneticModel::parse(cof).unwrap();
+        assert_eq!((model.epoch, model.name.as_str()), (2025.0, "TEST-DIPOLE"));
+
+        // On the equator at the prime meridian, north = -g10 and east = -h11.
+        let field = model.field(&Point::new(0.0, 0.0), 0.0, 2025.0).unwrap();
+        let expected = (-5000.0f64).atan2(30000.0).to_degrees();
+        assert!((field.declination_deg() - expected).abs() < 1e-9);
+        let field = model.field(&Point::new(0.0, 0.0), 0.0, 2027.0).unwrap();
+        let expected = (-5200.0f64).atan2(30000.0).to_degrees();
+        assert!((field.declination_deg() - expected).abs() < 1e-9);
+
+        assert!(model.field(&Point::new(0.0, 0.0), 0.0, 2031.0).is_err());
+        assert!(model.field(&Point::new(0.0, 90.0), 0.0, 2025.0).is_err());
+
+        let date = NaiveDate::from_ymd_opt(2025, 7, 2).unwrap();
+        assert_eq!(decimal_year(&date), 2025.0 + 182.0 / 365.0);
+    }
+
+    #[test]
+    fn test_wmm_test_values() {
+        // NOAA's test values for WMM2025 (WMM2025 technical report, table 6): the decimal year,
+        // altitude in km, latitude, longitude, and the resulting X, Y, Z in nT, inclination, and
+        // declination in degrees.
+        let test_values = [
+            (
+                2025.0,
+                0.0,
+                80.0,
+                0.0,
+                [6521.6, 145.9, 54791.5],
+                83.21,
+                1.28,
+            ),
+            (
+                2025.0,
+                0.0,
+                0.0,
+                120.0,
+                [39677.8, -109.6, -10580.2],
+                -14.93,
+                -0.16,
+            ),
+            (
+                2025.0,
+                0.0,
+                -80.0,
+                240.0,
+                [6117.5, 15751.9, -52022.5],
+                -72.00,
+                68.78,
+            ),
+            (
+                2025.0,
+                100.0,
+                80.0,
+                0.0,
+                [6216.0, 92.4, 52598.8],
+                83.26,
+                0.85,
+            ),
+            (
+                2025.0,
+                100.0,
+                0.0,
+                120.0,
+                [37688.6, -96.2, -10152.1],
+                -15.08,
+                -0.15,
+            ),
+            (
+                2025.0,
+                100.0,
+                -80.0,
+                240.0,
+                [5907.6, 14780.3, -49540.7],
+                -72.19,
+                68.21,
+            ),
+            (
+                2027.5,
+                0.0,
+                80.0,
+                0.0,
+                [6500.8, 294.5, 54869.4],
+                83.24,
+                2.59,
+            ),
+            (
+                2027.5,
+                0.0,
+                0.0,
+                120.0,
+                [39701.6, -167.4, -10381.8],
+                -14.65,
+                -0.24,
+            ),
+            (
+                2027.5,
+                0.0,
+                -80.0,
+                240.0,
+                [6200.7, 15730.3, -51783.7],
+                -71.92,
+                68.49,
+            ),
+            (
+                2027.5,
+                100.0,
+                80.0,
+                0.0,
+                [6196.7, 233.8, 52670.5],
+                83.29,
+                2.16,
+            ),
+            (
+                2027.5,
+                100.0,
+                0.0,
+                120.0,
+                [37711.5, -148.7, -9969.8],
+                -14.81,
+                -0.23,
+            ),
+            (
+                2027.5,
+                100.0,
+                -80.0,
+                240.0,
+                [5984.0, 14760.1, -49317.7],
+                -72.10,
+                67.93,
+            ),
+        ];
+        let model = MagneticModel::wmm();
+        assert_eq!((model.epoch, model.name.as_str()), (2025.0, "WMM-2025"));
+        for (year, altitude_km, lat, lng, [x, y, z], inclination, declination) in test_values {
+            let point = Point::new(lng, lat);
+            let field = model.field(&point, altitude_km * 1000.0, year).unwrap();
+            let case = format!("{} {} {} {}", year, altitude_km, lat, lng);
+            // The values are published to 0.1 nT and 0.01 degrees.
+            assert!((field.north - x).abs() < 0.15, "{}: {:?}", case, field);
+            assert!((field.east - y).abs() < 0.15, "{}: {:?}", case, field);
+            assert!((field.down - z).abs() < 0.15, "{}: {:?}", case, field);
+            assert!(
+                (field.inclination_deg() - inclination).abs() < 0.006,
+                "{}",
+                case
+            );
+            assert!(
+                (field.declination_deg() - declination).abs() < 0.006,
+                "{}",
+                case
+            );
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use geo::HaversineDistance;
+use geo_types::{Coord, LineString, Point};
+use itertools::Itertools;
+
+use crate::linear::{cumulative_lengths, snap_point, snap_point_with_lengths, Snap};
+
+/**
+ * Matches a noisy sequence of points to candidate lines with a hidden Markov model, returning the
+ * index of the matched line and the snapped position for each point.
+ *
+ * The hidden states are the candidate lines. Emissions score how far a point is from a line, using
+ * a Gaussian with standard deviation `sigma_m`. Transitions score how well the distance traveled
+ * between consecutive snapped positions agrees with the distance between the observed points, with
+ * an exponential falloff of `beta_m`. Travel along a single line is measured along the line, while
+ * switching lines is measured as a straight jump plus `switch_penalty_m` meters. Without a road
+ * network to route over, the penalty is what keeps matches from flip-flopping between nearby
+ * parallel lines. The most likely sequence of lines is found with the Viterbi algorithm.
+ *
+ * Newson, P. and Krumm, J. "Hidden Markov Map Matching Through Noise and Sparseness." 2009.
+ */
+pub fn match_trace(
+    trace: &[Point],
+    lines: &[LineString],
+    sigma_m: f64,
+    beta_m: f64,
+    switch_penalty_m: f64,
+) -> Vec<(usize, Snap)> {
+    let lengths = lines.iter().map(cumulative_lengths).collect_vec();
+    // Candidate snaps of each trace point onto each (non-empty) line.
+    let snaps = trace
+        .iter()
+        .map(|p| {
+            lines
+                .iter()
+                .zip(&lengths)
+                .map(|(line, lengths)| snap_point_with_lengths(line, lengths, p))
+                .collect_vec()
+        })
+        .collect_vec();
+
+    let emission = |snap: &Option<Snap>| match snap {
+        Some(snap) => -0.5 * (snap.distance_m / sigma_m).powi(2),
+        None => f64::NEG_INFINITY,
+    };
+    let transition = |t: usize, (i, from): (usize, &Snap), (j, to): (usize, &Snap)| {
+        let observed_m = trace[t - 1].haversine_distance(&trace[t]);
+        let traveled_m = if i == j {
+            (to.measure_m - from.measure_m).abs()
+        } else {
+            from.point.haversine_distance(&to.point) + switch_penalty_m
+        };
+        -(traveled_m - observed_m).abs() / beta_m
+    };
+
+    // Viterbi over log-probabilities, with back pointers to the best previous line.
+    let mut scores = match snaps.first() {
+        Some(first) => first.iter().map(emission).collect_vec(),
+        None => return vec![],
+    };
+    let mut back_pointers: Vec<Vec<usize>> = vec![];
+    for t in 1..trace.len() {
+        let (next_scores, pointers): (Vec<f64>, Vec<usize>) = (0..lines.len())
+            .map(|j| {
+                let Some(to) = &snaps[t][j] else {
+                    return (f64::NEG_INFINITY, 0);
+                };
+                let (best_i, best_score) = (0..lines.len())
+                    .filter_map(|i| {
+                        let from = snaps[t - 1][i].as_ref()?;
+                        Some((i, scores[i] + transition(t, (i, from), (j, to))))
+                    })
+                    .max_by(|(_, s1), (_, s2)| s1.total_cmp(s2))
+                    .unwrap_or((0, f64::NEG_INFINITY));
+                (best_score + emission(&snaps[t][j]), best_i)
+            })
+            .unzip();
+        scores = next_scores;
+        back_pointers.push(pointers);
+    }
+
+    let Some((mut state, _)) = scores
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_finite())
+        .max_by(|(_, s1), (_, s2)| s1.total_cmp(s2))
+    else {
+        return vec![];
+    };
+    let mut states = vec![state];
+    for pointers in back_pointers.iter().rev() {
+        state = pointers[state];
+        states.push(state);
+    }
+    states.reverse();
+
+    states
+        .into_iter()
+        .enumerate()
+        .map(|(t, i)| (i, snaps[t][i].expect("matched lines have snaps")))
+        .collect_vec()
+}
+
+/**
+ * Builds the path traced out by a map matching. Consecutive matches on the same line follow the
+ * line's vertices between the snapped positions, in either direction. When switching lines, the
+ * path follows the previous line to where it comes closest to the next match, and then joins the
+ * next line at the point closest to there, so that turns at intersections follow the lines.
+ */
+pub fn matched_path(lines: &[LineString], matches: &[(usize, Snap)]) -> LineString {
+    let mut coords: Vec<Coord> = vec![];
+    for (t, (line, snap)) in matches.iter().enumerate() {
+        match t.checked_sub(1).map(|t| matches[t]) {
+            None => coords.push(snap.point.into()),
+            Some((prev_line, prev_snap)) if prev_line == *line => {
+                follow_line(&mut coords, &lines[*line], &prev_snap, snap);
+            }
+            Some((prev_line, prev_snap)) => {
+                let exit = snap_point(&lines[prev_line], &snap.point).unwrap_or(prev_snap);
+                let entry = snap_point(&lines[*line], &exit.point).unwrap_or(*snap);
+                follow_line(&mut coords, &lines[prev_line], &prev_snap, &exit);
+                coords.push(entry.point.into());
+                follow_line(&mut coords, &lines[*line], &entry, snap);
+            }
+        }
+    }
+    coords.dedup();
+    LineString::new(coords)
+}
+
+/**
+ * Appends the vertices of a line between two snapped positions on it, followed by the end position.
+ */
+fn follow_line(coords: &mut Vec<Coord>, line: &LineString, from: &Snap, to: &Snap) {
+    let vertices = &line.0;
+    if from.segment <= to.segment {
+        coords.extend(&vertices[from.segment + 1..=to.segment]);
+    } else {
+        coords.extend(vertices[to.segment + 1..=from.segment].iter().rev());
+    }
+    coords.push(to.point.into());
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{line_string, Point};
+    use itertools::Itertools;
+
+    use super::{match_trace, matched_path};
+
+    #[test]
+    fn test_match_trace_follows_continuous_lines() {
+        let lines = vec![
+            // An east-west road that turns onto a north-south road.
+            line_string![(x: 0.0, y: 0.0), (x: 0.01, y: 0.0)],
+            line_string![(x: 0.01, y: 0.0), (x: 0.01, y: 0.01)],
+            // A parallel road ~33m north of the first one.
+            line_string![(x: 0.0, y: 0.0003), (x: 0.01, y: 0.0003)],
+        ];
+        // Noisy points that drift towards the parallel road.
+        let trace = vec![
+            Point::new(0.001, 0.0001),
+            Point::new(0.003, 0.00017),
+            Point::new(0.005, 0.0001),
+            Point::new(0.007, 0.00005),
+            Point::new(0.0101, 0.003),
+            Point::new(0.0099, 0.006),
+        ];
+
+        let matches = match_trace(&trace, &lines, 10.0, 50.0, 100.0);
+        let labels = matches.iter().map(|(i, _)| *i).collect_vec();
+        assert_eq!(labels, vec![0, 0, 0, 0, 1, 1]);
+
+        // The path turns the corner at the shared vertex.
+        let path = matched_path(&lines, &matches);
+        assert!(path.0.contains(&(0.01, 0.0).into()));
+    }
+}
@@ -0,0 +1,255 @@
+/*!
+ * Units of length and area for measurements, which are computed in meters and square meters and
+ * converted only for output.
+ */
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+//==================================================
+// Units.
+//==================================================
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LengthUnit {
+    #[default]
+    Meters,
+    Kilometers,
+    Miles,
+    NauticalMiles,
+    Feet,
+}
+
+impl LengthUnit {
+    pub const ALL: [LengthUnit; 5] = [
+        LengthUnit::Meters,
+        LengthUnit::Kilometers,
+        LengthUnit::Miles,
+        LengthUnit::NauticalMiles,
+        LengthUnit::Feet,
+    ];
+
+    /// Meters in one of this unit.
+    pub fn meters(self) -> f64 {
+        match self {
+            LengthUnit::Meters => 1.0,
+            LengthUnit::Kilometers => 1000.0,
+            LengthUnit::Miles => 1609.344,
+            LengthUnit::NauticalMiles => 1852.0,
+            LengthUnit::Feet => 0.3048,
+        }
+    }
+
+    pub fn from_meters(self, meters: f64) -> f64 {
+        meters / self.meters()
+    }
+
+    /// The symbol of the unit, which also suffixes output field names such as `distance_km`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            LengthUnit::Meters => "m",
+            LengthUnit::Kilometers => "km",
+            LengthUnit::Miles => "mi",
+            LengthUnit::NauticalMiles => "nmi",
+            LengthUnit::Feet => "ft",
+        }
+    }
+
+    /// The area of a square with sides of one of this unit.
+    pub fn squared(self) -> AreaUnit {
+        match self {
+            LengthUnit::Meters => AreaUnit::SquareMeters,
+            LengthUnit::Kilometers => AreaUnit::SquareKilometers,
+            LengthUnit::Miles => AreaUnit::SquareMiles,
+            LengthUnit::NauticalMiles => AreaUnit::SquareNauticalMiles,
+            LengthUnit::Feet => AreaUnit::SquareFeet,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AreaUnit {
+    #[default]
+    SquareMeters,
+    SquareKilometers,
+    Hectares,
+    Acres,
+    SquareMiles,
+    SquareNauticalMiles,
+    SquareFeet,
+}
+
+impl AreaUnit {
+    pub const ALL: [AreaUnit; 7] = [
+        AreaUnit::SquareMeters,
+        AreaUnit::SquareKilometers,
+        AreaUnit::Hectares,
+        AreaUnit::Acres,
+        AreaUnit::SquareMiles,
+        AreaUnit::SquareNauticalMiles,
+        AreaUnit::SquareFeet,
+    ];
+
+    /// Square meters in one of this unit.
+    pub fn square_meters(self) -> f64 {
+        match self {
+            AreaUnit::SquareMeters => 1.0,
+            AreaUnit::SquareKilometers => 1e6,
+            AreaUnit::Hectares => 1e4,
+            // The international acre is 1/640 of a square mile.
+            AreaUnit::Acres => 4046.8564224,
+            AreaUnit::SquareMiles => LengthUnit::Miles.meters().powi(2),
+            AreaUnit::SquareNauticalMiles => LengthUnit::NauticalMiles.meters().powi(2),
+            AreaUnit::SquareFeet => LengthUnit::Feet.meters().powi(2),
+        }
+    }
+
+    pub fn from_square_meters(self, square_meters: f64) -> f64 {
+        square_meters / self.square_meters()
+    }
+
+    /// The symbol of the unit in ASCII, which also suffixes output field names such as `area_ha`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            AreaUnit::SquareMeters => "m2",
+            AreaUnit::SquareKilometers => "km2",
+            AreaUnit::Hectares => "ha",
+            AreaUnit::Acres => "acres",
+            AreaUnit::SquareMiles => "mi2",
+            AreaUnit::SquareNauticalMiles => "nmi2",
+            AreaUnit::SquareFeet => "ft2",
+        }
+    }
+}
+
+/**
+ * A unit of either length or area, as given on the commandline. Units are named by their symbols,
+ * with `²` accepted for `2` in areas.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Length(LengthUnit),
+    Area(AreaUnit),
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let symbol = s.trim().to_lowercase().replace('²', "2");
+        let symbol = match symbol.as_str() {
+            "acre" => "acres",
+            symbol => symbol,
+        };
+        LengthUnit::ALL
+            .into_iter()
+            .find(|unit| unit.symbol() == symbol)
+            .map(Unit::Length)
+            .or_else(|| {
+                AreaUnit::ALL
+                    .into_iter()
+                    .find(|unit| unit.symbol() == symbol)
+                    .map(Unit::Area)
+            })
+            .ok_or_else(|| {
+                let symbols = LengthUnit::ALL
+                    .map(LengthUnit::symbol)
+                    .into_iter()
+                    .chain(AreaUnit::ALL.map(AreaUnit::symbol))
+                    .collect::<Vec<_>>();
+                format!(
+                    "unknown unit '{}', expected one of {}",
+                    s,
+                    symbols.join(", ")
+                )
+            })
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unit::Length(unit) => write!(f, "{}", unit.symbol()),
+            Unit::Area(unit) => write!(f, "{}", unit.symbol()),
+        }
+    }
+}
+
+//==================================================
+// Unit selection.
+//==================================================
+/**
+ * The units that lengths and areas are reported in, meters and square meters by default.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Units {
+    pub length: LengthUnit,
+    pub area: AreaUnit,
+}
+
+impl Units {
+    /**
+     * Selects units from at most one unit of length and one of area. Areas are reported in the
+     * square of the length unit unless an area unit is also given, so `km` alone gives km² while
+     * `km,ha` gives hectares.
+     */
+    pub fn select(units: &[Unit]) -> Result<Self, Box<dyn Error>> {
+        let lengths: Vec<_> = units
+            .iter()
+            .filter_map(|unit| match unit {
+                Unit::Length(unit) => Some(*unit),
+                Unit::Area(_) => None,
+            })
+            .collect();
+        let areas: Vec<_> = units
+            .iter()
+            .filter_map(|unit| match unit {
+                Unit::Area(unit) => Some(*unit),
+                Unit::Length(_) => None,
+            })
+            .collect();
+        if lengths.len() > 1 || areas.len() > 1 {
+            return Err("at most one unit of length and one of area can be selected".into());
+        }
+        let length = lengths.first().copied().unwrap_or_default();
+        Ok(Self {
+            length,
+            area: areas.first().copied().unwrap_or(length.squared()),
+        })
+    }
+
+    pub fn length(&self, meters: f64) -> f64 {
+        self.length.from_meters(meters)
+    }
+
+    pub fn area(&self, square_meters: f64) -> f64 {
+        self.area.from_square_meters(square_meters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AreaUnit, LengthUnit, Unit, Units};
+
+    #[test]
+    fn test_units() {
+        let parse = |units: &[&str]| {
+            let units: Vec<Unit> = units.iter().map(|u| u.parse().unwrap()).collect();
+            Units::select(&units)
+        };
+        assert_eq!(parse(&[]).unwrap(), Units::default());
+        let units = parse(&["nmi"]).unwrap();
+        assert_eq!(units.area, AreaUnit::SquareNauticalMiles);
+        assert_eq!(units.length(3704.0), 2.0);
+        assert_eq!(units.area(1852.0 * 1852.0), 1.0);
+
+        let units = parse(&["mi", "acres"]).unwrap();
+        assert_eq!(units.length, LengthUnit::Miles);
+        assert!((units.area(AreaUnit::SquareMiles.square_meters()) - 640.0).abs() < 1e-9);
+        assert_eq!(parse(&["km²"]).unwrap().area(2e6), 2.0);
+        assert_eq!(parse(&["ha"]).unwrap().length, LengthUnit::Meters);
+        assert!((parse(&["ft"]).unwrap().length(1.0) - 3.28084).abs() < 1e-5);
+
+        assert!(parse(&["m", "km"]).is_err());
+        assert!("furlong".parse::<Unit>().is_err());
+    }
+}
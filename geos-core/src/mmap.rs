@@ -0,0 +1,198 @@
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::ops::Deref;
+use std::path::Path;
+
+/**
+ * A read-only memory map of a file. Parsers read the mapped bytes directly, so that multi-gigabyte
+ * inputs are paged in from disk as they are parsed rather than copied into memory first, and pages
+ * already parsed can be reclaimed by the OS. On platforms without `mmap`, the file is read into
+ * memory instead.
+ *
+ * The file must not be truncated while it is mapped.
+ */
+pub struct Mmap {
+    #[cfg(unix)]
+    ptr: *mut libc::c_void,
+    #[cfg(unix)]
+    len: usize,
+    #[cfg(not(unix))]
+    bytes: Vec<u8>,
+}
+
+// The mapping is private and read-only, so sharing it between threads is no different from sharing
+// a `&[u8]`.
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::map(&File::open(path)?)
+    }
+
+    #[cfg(unix)]
+    pub fn map(file: &File) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file too large to map"))?;
+        // Empty mappings are invalid, so empty files are represented by a dangling pointer.
+        if len == 0 {
+            return Ok(Self {
+                ptr: std::ptr::NonNull::<u8>::dangling().as_ptr().cast(),
+                len,
+            });
+        }
+
+        // SAFETY: a fresh private read-only mapping of a file we hold open; `len` is its size.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // Inputs are parsed front to back, so read ahead aggressively and drop pages behind. This
+        // is only a hint, so failure is ignored.
+        // SAFETY: `ptr` and `len` describe the mapping created above.
+        unsafe { libc::madvise(ptr, len, libc::MADV_SEQUENTIAL) };
+        Ok(Self { ptr, len })
+    }
+
+    #[cfg(not(unix))]
+    pub fn map(mut file: &File) -> io::Result<Self> {
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        Ok(Self { bytes })
+    }
+
+    /**
+     * The mapped bytes as text, without copying them.
+     */
+    pub fn as_str(&self) -> io::Result<&str> {
+        std::str::from_utf8(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    #[cfg(unix)]
+    fn deref(&self) -> &[u8] {
+        // SAFETY: the mapping is valid for `len` bytes until it is unmapped on drop.
+        unsafe { std::slice::from_raw_parts(self.ptr.cast::<u8>(), self.len) }
+    }
+
+    #[cfg(not(unix))]
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: `ptr` and `len` describe a mapping created by `map` that is no longer used.
+            unsafe { libc::munmap(self.ptr, self.len) };
+        }
+    }
+}
+
+/// How far a reader advances before releasing the pages it has read.
+const RELEASE_INTERVAL: usize = 8 << 20;
+
+/**
+ * Reads a memory-mapped file through the `BufRead` interface. Each fill of the buffer is the rest
+ * of the mapping, so no bytes are copied before the consumer sees them. Pages that have been read
+ * are released as the reader advances, so that memory use stays bounded for inputs read once.
+ */
+pub struct MmapReader {
+    map: Mmap,
+    pos: usize,
+    released: usize,
+}
+
+impl MmapReader {
+    pub fn new(map: Mmap) -> Self {
+        Self {
+            map,
+            pos: 0,
+            released: 0,
+        }
+    }
+
+    #[cfg(unix)]
+    fn release_read_pages(&mut self) {
+        if self.pos - self.released < RELEASE_INTERVAL {
+            return;
+        }
+        // SAFETY: sysconf has no preconditions.
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as usize;
+        let end = self.pos / page_size * page_size;
+        // Dropping pages of a private read-only file mapping only means that they are read from the
+        // file again if accessed. This is only a hint, so failure is ignored.
+        // SAFETY: [released, end) is page-aligned and lies within the mapping.
+        unsafe {
+            libc::madvise(
+                self.map.ptr.cast::<u8>().add(self.released).cast(),
+                end - self.released,
+                libc::MADV_DONTNEED,
+            )
+        };
+        self.released = end;
+    }
+
+    #[cfg(not(unix))]
+    fn release_read_pages(&mut self) {}
+}
+
+impl Read for MmapReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = (&self.map[self.pos..]).read(buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for MmapReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(&self.map[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.map.len());
+        self.release_read_pages();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, Write};
+
+    use super::{Mmap, MmapReader};
+
+    #[test]
+    fn test_mmap() {
+        let path = std::env::temp_dir().join(format!("geos-mmap-{}.txt", std::process::id()));
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(b"first\nsecond\n")
+            .unwrap();
+        let map = Mmap::open(&path).unwrap();
+        assert_eq!(map.as_str().unwrap(), "first\nsecond\n");
+
+        let lines: Vec<String> = MmapReader::new(map).lines().map(Result::unwrap).collect();
+        assert_eq!(lines, ["first", "second"]);
+
+        std::fs::File::create(&path).unwrap();
+        assert!(Mmap::open(&path).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
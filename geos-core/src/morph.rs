@@ -0,0 +1,255 @@
+/*!
+ * Morphing between two polygons by interpolating corresponding vertices, e.g. to animate how a
+ * boundary changed between two versions of a dataset.
+ */
+use std::error::Error;
+
+use geo::GeodesicIntermediate;
+use geo_types::{Coord, LineString, Point, Polygon};
+use itertools::Itertools;
+
+use crate::geom::lerp;
+use crate::linear::geodesic_cumulative_lengths;
+use crate::nvec::NVec;
+
+/// The number of vertices of the first ring compared against each start of the second when
+/// matching them up, which bounds the time taken for rings with many vertices.
+const MAX_SAMPLES: usize = 256;
+
+/// Points closer than this to a vertex, in meters, are taken to be the vertex.
+const VERTEX_TOLERANCE_M: f64 = 1e-6;
+
+/**
+ * The correspondence between the vertices of two polygons, from which the intermediate shapes are
+ * interpolated. Each pair of rings is sampled at the same fractions of their lengths, which include
+ * the vertices of both rings so that neither loses its corners, starting from the pair of vertices
+ * that keeps corresponding points closest together.
+ */
+#[derive(Debug, Clone)]
+pub struct Morph {
+    /// The corresponding coordinates of the exterior ring followed by the holes, without the
+    /// closing coordinates.
+    rings: Vec<(Vec<Coord>, Vec<Coord>)>,
+}
+
+impl Morph {
+    /**
+     * Finds the correspondence between two polygons. Holes correspond in order, so both polygons
+     * must have the same number of holes.
+     */
+    pub fn new(from: &Polygon, to: &Polygon) -> Result<Self, Box<dyn Error>> {
+        if from.interiors().len() != to.interiors().len() {
+            return Err(format!(
+                "cannot morph a polygon with {} holes into one with {}",
+                from.interiors().len(),
+                to.interiors().len()
+            )
+            .into());
+        }
+        let from_rings = std::iter::once(from.exterior()).chain(from.interiors());
+        let to_rings = std::iter::once(to.exterior()).chain(to.interiors());
+        let rings = from_rings
+            .zip(to_rings)
+            .map(|(from, to)| corresponding_rings(from, to))
+            .try_collect()?;
+        Ok(Self { rings })
+    }
+
+    /**
+     * The shape at `t` between the polygons, from the first at 0 to the second at 1. Corresponding
+     * vertices move along the great circles between them.
+     */
+    pub fn at(&self, t: f64) -> Polygon {
+        let mut rings = self.rings.iter().map(|(from, to)| {
+            // The ends are copied rather than interpolated, which would round them.
+            let mut ring = match t {
+                0.0 => from.clone(),
+                1.0 => to.clone(),
+                t => from
+                    .iter()
+                    .zip(to)
+                    .map(|(c1, c2)| lerp(t, *c1, *c2))
+                    .collect_vec(),
+            };
+            ring.push(ring[0]);
+            LineString::new(ring)
+        });
+        let exterior = rings.next().expect("polygons have an exterior");
+        Polygon::new(exterior, rings.collect())
+    }
+}
+
+/**
+ * Samples two rings at the same fractions of their lengths, after rotating the second to start at
+ * the vertex that minimizes the distances between the vertices of the first and the corresponding
+ * points of the second.
+ */
+fn corresponding_rings(
+    from: &LineString,
+    to: &LineString,
+) -> Result<(Vec<Coord>, Vec<Coord>), Box<dyn Error>> {
+    let from = Ring::new(open_ring(from))?;
+    let to = Ring::new(open_ring(to))?;
+
+    // Starting the second ring at its vertex k shifts the fractions along it by the fraction of
+    // vertex k. Every start is tried against a sample of the first ring's vertices, with the
+    // distances measured by the squared chord between unit n-vectors, 2(1 - cos θ).
+    let stride = (from.coords.len() / MAX_SAMPLES).max(1);
+    let samples = (0..from.coords.len())
+        .step_by(stride)
+        .map(|i| (from.fractions[i], NVec::from(from.coords[i])))
+        .collect_vec();
+    let cost = |offset: f64| -> f64 {
+        samples
+            .iter()
+            .map(|(fraction, nvec)| 1.0 - nvec.dot(&NVec::from(to.point_at(fraction + offset))))
+            .sum()
+    };
+    let offset = to
+        .fractions
+        .iter()
+        .map(|&offset| (offset, cost(offset)))
+        .min_by(|(_, c1), (_, c2)| c1.total_cmp(c2))
+        .map(|(offset, _)| offset)
+        .unwrap_or_default();
+
+    let fractions = from
+        .fractions
+        .iter()
+        .copied()
+        .chain(to.fractions.iter().map(|f| (f - offset).rem_euclid(1.0)))
+        .sorted_by(f64::total_cmp)
+        .dedup_by(|f1, f2| (f1 - f2).abs() < 1e-12)
+        .collect_vec();
+    Ok((
+        fractions.iter().map(|f| from.point_at(*f)).collect(),
+        fractions.iter().map(|f| to.point_at(f + offset)).collect(),
+    ))
+}
+
+/**
+ * A ring with the fractions of its length at which each of its vertices lies.
+ */
+struct Ring {
+    /// The vertices without the closing coordinate.
+    coords: Vec<Coord>,
+    cumulative_m: Vec<f64>,
+    length_m: f64,
+    fractions: Vec<f64>,
+}
+
+impl Ring {
+    fn new(coords: Vec<Coord>) -> Result<Self, Box<dyn Error>> {
+        let first = *coords.first().ok_or("cannot morph empty polygons")?;
+        let closed = LineString::new(coords.iter().copied().chain([first]).collect());
+        let cumulative_m = geodesic_cumulative_lengths(&closed);
+        let length_m = cumulative_m.last().copied().unwrap_or_default();
+        if length_m <= 0.0 {
+            return Err("cannot morph rings without length".into());
+        }
+        let fractions = cumulative_m[..coords.len()]
+            .iter()
+            .map(|m| m / length_m)
+            .collect();
+        Ok(Self {
+            coords,
+            cumulative_m,
+            length_m,
+            fractions,
+        })
+    }
+
+    /**
+     * The point at a fraction of the ring's length, wrapping around past its end. Points at the
+     * ring's vertices are the vertices themselves, rather than rounded by interpolating along an
+     * edge.
+     */
+    fn point_at(&self, fraction: f64) -> Coord {
+        let n = self.coords.len();
+        let measure_m = fraction.rem_euclid(1.0) * self.length_m;
+        // The edge from vertex i to i + 1 that the point is on.
+        let i = self
+            .cumulative_m
+            .partition_point(|m| *m <= measure_m)
+            .clamp(1, n)
+            - 1;
+        let (start_m, end_m) = (self.cumulative_m[i], self.cumulative_m[i + 1]);
+        let (start, end) = (self.coords[i], self.coords[(i + 1) % n]);
+        if measure_m - start_m < VERTEX_TOLERANCE_M {
+            start
+        } else if end_m - measure_m < VERTEX_TOLERANCE_M {
+            end
+        } else {
+            let t = (measure_m - start_m) / (end_m - start_m);
+            Point::from(start)
+                .geodesic_intermediate(&Point::from(end), t)
+                .0
+        }
+    }
+}
+
+/**
+ * The coordinates of a ring without the closing coordinate.
+ */
+fn open_ring(ring: &LineString) -> Vec<Coord> {
+    let mut coords = ring.0.clone();
+    if coords.len() > 1 && coords.first() == coords.last() {
+        coords.pop();
+    }
+    coords
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{Area, Centroid, HaversineDistance};
+    use geo_types::{polygon, Point};
+
+    use super::Morph;
+
+    #[test]
+    fn test_morph() {
+        let square = polygon![
+            (x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 1.0), (x: 0.0, y: 0.0)
+        ];
+        // A triangle listed from a different start, offset to the east.
+        let triangle = polygon![
+            (x: 3.0, y: 1.0), (x: 2.0, y: 0.0), (x: 4.0, y: 0.0), (x: 3.0, y: 1.0)
+        ];
+        let morph = Morph::new(&square, &triangle).unwrap();
+
+        // The ends reproduce the polygons' vertices, plus the other's vertices along their edges.
+        let close = |a: &geo_types::Polygon, b: &geo_types::Polygon| {
+            (a.unsigned_area() / b.unsigned_area() - 1.0).abs() < 1e-3
+                && a.centroid()
+                    .unwrap()
+                    .haversine_distance(&b.centroid().unwrap())
+                    < 1.0
+        };
+        assert!(close(&morph.at(0.0), &square));
+        assert!(close(&morph.at(1.0), &triangle));
+        assert!(morph.at(0.0).exterior().0.len() > square.exterior().0.len());
+
+        // Halfway, the shape is centered halfway between them.
+        let centroid = morph.at(0.5).centroid().unwrap();
+        assert!(centroid.haversine_distance(&Point::new(1.75, 0.42)) < 10_000.0);
+
+        // The square's first corner moves to the nearest corner of the triangle rather than to its
+        // first vertex.
+        let start = Point::from(morph.at(1.0).exterior().0[0]);
+        assert!(start.haversine_distance(&Point::new(2.0, 0.0)) < 1.0);
+
+        let holed = polygon![
+            exterior: [(x: 0.0, y: 0.0), (x: 4.0, y: 0.0), (x: 4.0, y: 4.0), (x: 0.0, y: 0.0)],
+            interiors: [[(x: 2.0, y: 1.0), (x: 3.0, y: 1.0), (x: 3.0, y: 2.0), (x: 2.0, y: 1.0)]],
+        ];
+        assert!(Morph::new(&square, &holed).is_err());
+        assert_eq!(
+            Morph::new(&holed, &holed)
+                .unwrap()
+                .at(0.3)
+                .interiors()
+                .len(),
+            1
+        );
+    }
+}
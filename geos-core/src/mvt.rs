@@ -0,0 +1,452 @@
+/*!
+ * Encoding of geometries as Mapbox Vector Tiles (version 2.1 of the spec): a protobuf message with
+ * one layer of features, whose coordinates are integers within a single z/x/y tile.
+ */
+use geo_types::{Coord, Geometry, LineString, Polygon};
+
+use crate::tiles::Tile;
+
+/// The width of a tile in integer coordinates, as used by most renderers.
+pub const DEFAULT_EXTENT: u32 = 4096;
+
+/// How far geometries are kept past the edges of the tile, in tile coordinates, so that renderers
+/// don't draw the clipped edges of polygons or the ends of lines at tile borders.
+const BUFFER: f64 = 64.0;
+
+const POINT: u32 = 1;
+const LINESTRING: u32 = 2;
+const POLYGON: u32 = 3;
+
+const MOVE_TO: u32 = 1;
+const LINE_TO: u32 = 2;
+const CLOSE_PATH: u32 = 7;
+
+/**
+ * Encodes named geometries in lng/lat as one layer of a vector tile. Geometries are projected to
+ * web mercator, clipped to the tile with a small buffer, and snapped to a grid of `extent` by
+ * `extent` units. Names are written as a `name` property. Each member of a `GEOMETRYCOLLECTION` is
+ * a feature of its own, and geometries that fall outside the tile are left out.
+ */
+pub fn encode_tile(
+    tile: &Tile,
+    layer: &str,
+    extent: u32,
+    geometries: &[(Option<String>, Geometry)],
+) -> Vec<u8> {
+    let mut values: Vec<&str> = vec![];
+    let mut features = vec![];
+    for (name, geometry) in geometries {
+        let tags = match name {
+            Some(name) => {
+                let index = match values.iter().position(|v| v == name) {
+                    Some(index) => index,
+                    None => {
+                        values.push(name);
+                        values.len() - 1
+                    }
+                };
+                vec![0, index as u32]
+            }
+            None => vec![],
+        };
+        let project = |c: &Coord| {
+            let c = tile.project(*c);
+            Coord {
+                x: c.x * extent as f64,
+                y: c.y * extent as f64,
+            }
+        };
+        for (kind, commands) in encode_geometry(geometry, &project, extent as f64) {
+            let mut feature = vec![];
+            if !tags.is_empty() {
+                write_packed(&mut feature, 2, &tags);
+            }
+            write_varint_field(&mut feature, 3, kind as u64);
+            write_packed(&mut feature, 4, &commands);
+            features.push(feature);
+        }
+    }
+
+    let mut message = vec![];
+    write_bytes_field(&mut message, 1, layer.as_bytes());
+    for feature in &features {
+        write_bytes_field(&mut message, 2, feature);
+    }
+    if !values.is_empty() {
+        write_bytes_field(&mut message, 3, b"name");
+    }
+    for value in values {
+        let mut value_message = vec![];
+        write_bytes_field(&mut value_message, 1, value.as_bytes());
+        write_bytes_field(&mut message, 4, &value_message);
+    }
+    write_varint_field(&mut message, 5, extent as u64);
+    write_varint_field(&mut message, 15, 2);
+
+    let mut tile = vec![];
+    write_bytes_field(&mut tile, 3, &message);
+    tile
+}
+
+//==================================================
+// Geometry encoding.
+//==================================================
+/**
+ * The feature types and command integers of a geometry, with one entry per feature.
+ */
+fn encode_geometry(
+    geometry: &Geometry,
+    project: &dyn Fn(&Coord) -> Coord,
+    extent: f64,
+) -> Vec<(u32, Vec<u32>)> {
+    let (min, max) = (-BUFFER, extent + BUFFER);
+    let inside = |c: &Coord| (min..=max).contains(&c.x) && (min..=max).contains(&c.y);
+    let points = |coords: Vec<Coord>| {
+        let points: Vec<(i64, i64)> = coords.iter().filter(|c| inside(c)).map(snap).collect();
+        encode_points(&points)
+    };
+    let lines = |lines: Vec<&LineString>| {
+        let parts: Vec<Vec<(i64, i64)>> = lines
+            .into_iter()
+            .flat_map(|line| {
+                let coords: Vec<Coord> = line.0.iter().map(project).collect();
+                clip_line(&coords, min, max)
+            })
+            .map(|part| dedup(part.iter().map(snap).collect()))
+            .filter(|part| part.len() >= 2)
+            .collect();
+        encode_lines(&parts)
+    };
+    let polygons = |polygons: Vec<&Polygon>| {
+        let mut rings = vec![];
+        for polygon in polygons {
+            let mut polygon_rings = std::iter::once(polygon.exterior()).chain(polygon.interiors());
+            let Some(exterior) = polygon_rings
+                .next()
+                .and_then(|r| clip_ring(r, project, min, max))
+            else {
+                continue;
+            };
+            rings.push(wind(exterior, true));
+            rings.extend(
+                polygon_rings
+                    .filter_map(|r| clip_ring(r, project, min, max))
+                    .map(|r| wind(r, false)),
+            );
+        }
+        encode_rings(&rings)
+    };
+
+    let encoded = match geometry {
+        Geometry::Point(point) => points(vec![project(&point.0)]).map(|c| (POINT, c)),
+        Geometry::MultiPoint(multi) => {
+            points(multi.iter().map(|p| project(&p.0)).collect()).map(|c| (POINT, c))
+        }
+        Geometry::Line(line) => {
+            lines(vec![&LineString::new(vec![line.start, line.end])]).map(|c| (LINESTRING, c))
+        }
+        Geometry::LineString(line) => lines(vec![line]).map(|c| (LINESTRING, c)),
+        Geometry::MultiLineString(multi) => lines(multi.iter().collect()).map(|c| (LINESTRING, c)),
+        Geometry::Polygon(polygon) => polygons(vec![polygon]).map(|c| (POLYGON, c)),
+        Geometry::MultiPolygon(multi) => polygons(multi.iter().collect()).map(|c| (POLYGON, c)),
+        Geometry::Rect(rect) => polygons(vec![&rect.to_polygon()]).map(|c| (POLYGON, c)),
+        Geometry::Triangle(triangle) => {
+            polygons(vec![&triangle.to_polygon()]).map(|c| (POLYGON, c))
+        }
+        Geometry::GeometryCollection(collection) => {
+            return collection
+                .iter()
+                .flat_map(|g| encode_geometry(g, project, extent))
+                .collect()
+        }
+    };
+    encoded.into_iter().collect()
+}
+
+fn snap(c: &Coord) -> (i64, i64) {
+    (c.x.round() as i64, c.y.round() as i64)
+}
+
+fn dedup(mut coords: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    coords.dedup();
+    coords
+}
+
+fn encode_points(points: &[(i64, i64)]) -> Option<Vec<u32>> {
+    if points.is_empty() {
+        return None;
+    }
+    let mut encoder = CommandEncoder::default();
+    encoder.command(MOVE_TO, points);
+    Some(encoder.commands)
+}
+
+fn encode_lines(lines: &[Vec<(i64, i64)>]) -> Option<Vec<u32>> {
+    if lines.is_empty() {
+        return None;
+    }
+    let mut encoder = CommandEncoder::default();
+    for line in lines {
+        encoder.command(MOVE_TO, &line[..1]);
+        encoder.command(LINE_TO, &line[1..]);
+    }
+    Some(encoder.commands)
+}
+
+/**
+ * Encodes rings without their closing coordinates, which are implied by ClosePath.
+ */
+fn encode_rings(rings: &[Vec<(i64, i64)>]) -> Option<Vec<u32>> {
+    if rings.is_empty() {
+        return None;
+    }
+    let mut encoder = CommandEncoder::default();
+    for ring in rings {
+        encoder.command(MOVE_TO, &ring[..1]);
+        encoder.command(LINE_TO, &ring[1..ring.len() - 1]);
+        encoder.command(CLOSE_PATH, &[]);
+    }
+    Some(encoder.commands)
+}
+
+/**
+ * Writes commands with coordinates relative to the cursor, which carries over between commands.
+ */
+#[derive(Default)]
+struct CommandEncoder {
+    commands: Vec<u32>,
+    cursor: (i64, i64),
+}
+
+impl CommandEncoder {
+    fn command(&mut self, id: u32, coords: &[(i64, i64)]) {
+        let count = if id == CLOSE_PATH { 1 } else { coords.len() };
+        self.commands.push((id & 0x7) | ((count as u32) << 3));
+        for &(x, y) in coords {
+            self.commands.push(zigzag(x - self.cursor.0));
+            self.commands.push(zigzag(y - self.cursor.1));
+            self.cursor = (x, y);
+        }
+    }
+}
+
+fn zigzag(n: i64) -> u32 {
+    ((n << 1) ^ (n >> 63)) as u32
+}
+
+/**
+ * Twice the signed area of a closed ring, positive when the ring is clockwise on screen, with y
+ * increasing downwards.
+ */
+fn signed_area(ring: &[(i64, i64)]) -> i64 {
+    ring.windows(2)
+        .map(|pair| pair[0].0 * pair[1].1 - pair[1].0 * pair[0].1)
+        .sum()
+}
+
+/**
+ * Orients a ring as the spec requires: exterior rings clockwise on screen, and holes
+ * counterclockwise.
+ */
+fn wind(mut ring: Vec<(i64, i64)>, exterior: bool) -> Vec<(i64, i64)> {
+    if (signed_area(&ring) > 0) != exterior {
+        ring.reverse();
+    }
+    ring
+}
+
+//==================================================
+// Clipping.
+//==================================================
+/**
+ * Clips a ring to a square with the Sutherland-Hodgman algorithm, and snaps it to the grid. Rings
+ * that collapse to nothing are dropped.
+ */
+fn clip_ring(
+    ring: &LineString,
+    project: &dyn Fn(&Coord) -> Coord,
+    min: f64,
+    max: f64,
+) -> Option<Vec<(i64, i64)>> {
+    let mut coords: Vec<Coord> = ring.0.iter().map(project).collect();
+    // The ring is treated as closed, without its repeated first coordinate.
+    if coords.len() > 1 && coords.first() == coords.last() {
+        coords.pop();
+    }
+    // Each edge of the square, as whether it bounds x or y, where, and which side is kept.
+    let edges = [
+        (true, min, true),
+        (true, max, false),
+        (false, min, true),
+        (false, max, false),
+    ];
+    for (is_x, bound, above) in edges {
+        let axis = |c: &Coord| if is_x { c.x } else { c.y };
+        let keep = |c: &Coord| match above {
+            true => axis(c) >= bound,
+            false => axis(c) <= bound,
+        };
+        let mut clipped = vec![];
+        for (i, current) in coords.iter().enumerate() {
+            let previous = coords[(i + coords.len() - 1) % coords.len()];
+            if keep(current) != keep(&previous) {
+                let t = (bound - axis(&previous)) / (axis(current) - axis(&previous));
+                clipped.push(previous + (*current - previous) * t);
+            }
+            if keep(current) {
+                clipped.push(*current);
+            }
+        }
+        coords = clipped;
+    }
+
+    let mut ring = dedup(coords.iter().map(snap).collect());
+    while ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    ring.push(*ring.first()?);
+    (ring.len() >= 4 && signed_area(&ring) != 0).then_some(ring)
+}
+
+/**
+ * Clips a line to a square with the Liang-Barsky algorithm, splitting it where it leaves the
+ * square.
+ */
+fn clip_line(coords: &[Coord], min: f64, max: f64) -> Vec<Vec<Coord>> {
+    let mut parts: Vec<Vec<Coord>> = vec![];
+    let mut current: Vec<Coord> = vec![];
+    for pair in coords.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let delta = end - start;
+        let (mut t0, mut t1) = (0.0f64, 1.0f64);
+        let checks = [
+            (-delta.x, start.x - min),
+            (delta.x, max - start.x),
+            (-delta.y, start.y - min),
+            (delta.y, max - start.y),
+        ];
+        let visible = checks.iter().all(|&(p, q)| {
+            if p == 0.0 {
+                return q >= 0.0;
+            }
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+            t0 <= t1
+        });
+        if !visible {
+            continue;
+        }
+        let (a, b) = (start + delta * t0, start + delta * t1);
+        if current.last() != Some(&a) {
+            if current.len() > 1 {
+                parts.push(std::mem::take(&mut current));
+            }
+            current = vec![a];
+        }
+        current.push(b);
+    }
+    if current.len() > 1 {
+        parts.push(current);
+    }
+    parts
+}
+
+//==================================================
+// Protobuf encoding.
+//==================================================
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    write_varint(out, field << 3);
+    write_varint(out, value);
+}
+
+fn write_bytes_field(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(out, (field << 3) | 2);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_packed(out: &mut Vec<u8>, field: u64, values: &[u32]) {
+    let mut packed = vec![];
+    for &value in values {
+        write_varint(&mut packed, value as u64);
+    }
+    write_bytes_field(out, field, &packed);
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, polygon, Coord, Geometry};
+
+    use super::{encode_geometry, encode_tile, POINT, POLYGON};
+    use crate::tiles::Tile;
+
+    #[test]
+    fn test_encode_geometry() {
+        let identity = |c: &Coord| *c;
+
+        let point = Geometry::from(point!(x: 25.0, y: 17.0));
+        assert_eq!(
+            encode_geometry(&point, &identity, 4096.0),
+            [(POINT, vec![9, 50, 34])]
+        );
+
+        // The example from the spec, wound counterclockwise on screen, and so reversed.
+        let triangle =
+            Geometry::from(polygon![(x: 3.0, y: 6.0), (x: 20.0, y: 34.0), (x: 8.0, y: 12.0)]);
+        assert_eq!(
+            encode_geometry(&triangle, &identity, 4096.0),
+            [(POLYGON, vec![9, 6, 12, 18, 10, 12, 24, 44, 15])]
+        );
+
+        // A square larger than the tile is clipped to its buffer.
+        let large = Geometry::from(
+            polygon![(x: -1e4, y: -1e4), (x: 1e4, y: -1e4), (x: 1e4, y: 1e4), (x: -1e4, y: 1e4)],
+        );
+        assert_eq!(
+            encode_geometry(&large, &identity, 4096.0),
+            [(
+                POLYGON,
+                vec![9, 127, 8320, 26, 0, 8447, 8448, 0, 0, 8448, 15]
+            )]
+        );
+
+        let outside = Geometry::from(point!(x: -1000.0, y: 17.0));
+        assert!(encode_geometry(&outside, &identity, 4096.0).is_empty());
+    }
+
+    #[test]
+    fn test_encode_tile() {
+        let tile = Tile { z: 0, x: 0, y: 0 };
+        let geometries = [(
+            Some("a".to_string()),
+            Geometry::from(point!(x: 0.0, y: 0.0)),
+        )];
+        assert_eq!(
+            encode_tile(&tile, "cells", 4096, &geometries),
+            [
+                0x1a, 0x26, // Layer.
+                0x0a, 0x05, b'c', b'e', b'l', b'l', b's', // Name.
+                0x12, 0x0d, // Feature.
+                0x12, 0x02, 0x00, 0x00, // Tags.
+                0x18, 0x01, // Type.
+                0x22, 0x05, 0x09, 0x80, 0x20, 0x80, 0x20, // Geometry.
+                0x1a, 0x04, b'n', b'a', b'm', b'e', // Keys.
+                0x22, 0x03, 0x0a, 0x01, b'a', // Values.
+                0x28, 0x80, 0x20, // Extent.
+                0x78, 0x02, // Version.
+            ]
+        );
+    }
+}
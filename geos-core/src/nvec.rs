@@ -6,7 +6,7 @@ use std::ops::{Add, Mul};
  * n-vectors are essentially elliptical surface normals that provide an alternate representation
  * for geographic coordinates in which certain operations like interpolation are straightforward.
  */
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct NVec {
     x: f64,
     y: f64,
@@ -23,12 +23,31 @@ impl NVec {
         }
         .norm()
     }
+
+    pub fn dot(&self, other: &NVec) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /**
+     * The cross product, which is normal to the great circle through both n-vectors.
+     */
+    pub fn cross(&self, other: &NVec) -> NVec {
+        NVec {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    pub fn normalize(&self) -> NVec {
+        (1.0 / self.norm()) * *self
+    }
 }
 
-impl Into<Coord> for NVec {
-    fn into(self) -> Coord {
-        let lat = f64::atan2(self.z, f64::sqrt(self.y * self.y + self.x * self.x));
-        let lng = f64::atan2(self.y, self.x);
+impl From<NVec> for Coord {
+    fn from(nv: NVec) -> Coord {
+        let lat = f64::atan2(nv.z, f64::sqrt(nv.y * nv.y + nv.x * nv.x));
+        let lng = f64::atan2(nv.y, nv.x);
         Coord {
             x: to_angle(lng),
             y: to_angle(lat),
@@ -52,11 +71,11 @@ impl Mul<f64> for NVec {
     type Output = Self;
 
     fn mul(self, rhs: f64) -> Self::Output {
-        return Self::Output {
+        Self::Output {
             x: rhs * self.x,
             y: rhs * self.y,
             z: rhs * self.z,
-        };
+        }
     }
 }
 
@@ -64,7 +83,7 @@ impl Mul<NVec> for f64 {
     type Output = NVec;
 
     fn mul(self, rhs: Self::Output) -> Self::Output {
-        return rhs * self;
+        rhs * self
     }
 }
 
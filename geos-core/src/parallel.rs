@@ -0,0 +1,69 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Chunks per thread, so that threads that draw cheap chunks can take over the remaining work.
+const CHUNKS_PER_THREAD: usize = 8;
+
+/**
+ * Maps `f` over `items` on one thread per available core, returning the results in the order of the
+ * items. This is meant for independent, expensive operations like boolean operations on detailed
+ * polygons.
+ */
+pub fn par_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    par_map_with(threads, items, f)
+}
+
+fn par_map_with<T: Sync, R: Send>(
+    threads: usize,
+    items: &[T],
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let threads = threads.min(items.len());
+    if threads <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    // Threads claim chunks in order, and the chunks are reassembled by index afterward.
+    let chunks = items
+        .chunks(items.len().div_ceil(threads * CHUNKS_PER_THREAD))
+        .collect::<Vec<_>>();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(chunks.len()));
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(chunk) = chunks.get(i) else {
+                    break;
+                };
+                let mapped = chunk.iter().map(&f).collect::<Vec<R>>();
+                results
+                    .lock()
+                    .expect("no thread panics holding the lock")
+                    .push((i, mapped));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().expect("all threads have finished");
+    results.sort_unstable_by_key(|(i, _)| *i);
+    results.into_iter().flat_map(|(_, mapped)| mapped).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::par_map_with;
+
+    #[test]
+    fn test_par_map_preserves_order() {
+        let items = (0..1000).collect::<Vec<u64>>();
+        let expected = items.iter().map(|i| i * i).collect::<Vec<_>>();
+        for threads in [1, 3, 16] {
+            assert_eq!(par_map_with(threads, &items, |i| i * i), expected);
+        }
+        assert!(par_map_with(4, &[] as &[u64], |i| *i).is_empty());
+    }
+}
@@ -0,0 +1,535 @@
+/*!
+ * Writing of tables with a geometry column as GeoParquet, the columnar format read by data
+ * warehouses and dataframe libraries, for results too large for CSV. Columns are written
+ * uncompressed and PLAIN-encoded, with one data page per column per row group, and geometries as
+ * WKB.
+ */
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::io::Write;
+
+use geo::BoundingRect;
+use geo_types::{Coord, Geometry, Rect};
+use itertools::Itertools;
+
+use crate::wkb::write_wkb;
+
+/// The magic number that starts and ends Parquet files.
+const MAGIC: &[u8] = b"PAR1";
+
+/// The number of rows buffered before they are written as a row group.
+const ROW_GROUP_ROWS: usize = 1 << 16;
+
+/// The physical types and encodings of the Parquet format.
+const TYPE_INT32: i32 = 1;
+const TYPE_BYTE_ARRAY: i32 = 6;
+const CONVERTED_TYPE_UTF8: i32 = 0;
+const REPETITION_REQUIRED: i32 = 0;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const PAGE_TYPE_DATA: i32 = 0;
+const CODEC_UNCOMPRESSED: i32 = 0;
+
+/**
+ * The type of a column. Every column is required, i.e. has no nulls.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int32,
+    Utf8,
+    /// Geometries, encoded as WKB. A table has exactly one, named `geometry`.
+    Geometry,
+}
+
+/**
+ * A value in a row, which must have the type of its column.
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum Value<'a> {
+    Int32(i32),
+    Utf8(&'a str),
+    Geometry(&'a Geometry),
+}
+
+/**
+ * Writes rows to a GeoParquet file as they are produced, buffering only the current row group.
+ * Nothing is readable until [GeoParquetWriter::finish] writes the footer.
+ */
+pub struct GeoParquetWriter<W: Write> {
+    out: W,
+    /// The number of bytes written so far.
+    offset: usize,
+    columns: Vec<(String, ColumnType)>,
+    /// The PLAIN-encoded values of each column in the current row group.
+    buffers: Vec<Vec<u8>>,
+    rows: usize,
+    row_groups: Vec<RowGroup>,
+    geometry_types: BTreeSet<&'static str>,
+    bbox: Option<Rect>,
+}
+
+/**
+ * The location of a row group's column chunks, for the footer.
+ */
+struct RowGroup {
+    rows: usize,
+    /// The offset and size of each column chunk, in column order.
+    chunks: Vec<(usize, usize)>,
+}
+
+impl<W: Write> GeoParquetWriter<W> {
+    /**
+     * Starts a file with the given columns, one of which must be the geometry column.
+     */
+    pub fn new(mut out: W, columns: &[(&str, ColumnType)]) -> Result<Self, Box<dyn Error>> {
+        let geometry_columns = columns
+            .iter()
+            .filter(|(_, kind)| *kind == ColumnType::Geometry)
+            .collect_vec();
+        if geometry_columns.len() != 1 || geometry_columns[0].0 != "geometry" {
+            return Err(
+                "a GeoParquet table needs exactly one geometry column, named geometry".into(),
+            );
+        }
+        out.write_all(MAGIC)?;
+        Ok(Self {
+            out,
+            offset: MAGIC.len(),
+            columns: columns.iter().map(|(n, k)| (n.to_string(), *k)).collect(),
+            buffers: vec![vec![]; columns.len()],
+            rows: 0,
+            row_groups: vec![],
+            geometry_types: BTreeSet::new(),
+            bbox: None,
+        })
+    }
+
+    /**
+     * Adds a row, with a value for each column in order.
+     */
+    pub fn write_row(&mut self, row: &[Value]) -> Result<(), Box<dyn Error>> {
+        if row.len() != self.columns.len() {
+            return Err(format!(
+                "expected {} values in a row, got {}",
+                self.columns.len(),
+                row.len()
+            )
+            .into());
+        }
+        for ((value, (name, kind)), buffer) in row.iter().zip(&self.columns).zip(&mut self.buffers)
+        {
+            match (value, kind) {
+                (Value::Int32(value), ColumnType::Int32) => buffer.extend(value.to_le_bytes()),
+                (Value::Utf8(value), ColumnType::Utf8) => {
+                    write_byte_array(buffer, value.as_bytes())
+                }
+                (Value::Geometry(geometry), ColumnType::Geometry) => {
+                    write_byte_array(buffer, &write_wkb(geometry));
+                    self.geometry_types.insert(geometry_type(geometry));
+                    if let Some(bbox) = geometry.bounding_rect() {
+                        self.bbox = Some(self.bbox.map_or(bbox, |b| union(&b, &bbox)));
+                    }
+                }
+                _ => return Err(format!("wrong type of value for column {}", name).into()),
+            }
+        }
+        self.rows += 1;
+        if self.rows == ROW_GROUP_ROWS {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Writes the buffered rows and the footer, returning the number of rows written.
+     */
+    pub fn finish(mut self) -> Result<usize, Box<dyn Error>> {
+        if self.rows > 0 {
+            self.flush_row_group()?;
+        }
+        let footer = self.footer();
+        self.out.write_all(&footer)?;
+        self.out.write_all(&(footer.len() as u32).to_le_bytes())?;
+        self.out.write_all(MAGIC)?;
+        self.out.flush()?;
+        Ok(self.row_groups.iter().map(|group| group.rows).sum())
+    }
+
+    fn flush_row_group(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut chunks = vec![];
+        for buffer in &mut self.buffers {
+            let mut header = Compact::default();
+            header.i32(1, PAGE_TYPE_DATA);
+            header.i32(2, buffer.len() as i32);
+            header.i32(3, buffer.len() as i32);
+            header.begin_struct(5);
+            header.i32(1, self.rows as i32);
+            header.i32(2, ENCODING_PLAIN);
+            header.i32(3, ENCODING_RLE);
+            header.i32(4, ENCODING_RLE);
+            header.end_struct();
+            let header = header.finish();
+
+            self.out.write_all(&header)?;
+            self.out.write_all(buffer)?;
+            let size = header.len() + buffer.len();
+            chunks.push((self.offset, size));
+            self.offset += size;
+            buffer.clear();
+        }
+        self.row_groups.push(RowGroup {
+            rows: self.rows,
+            chunks,
+        });
+        self.rows = 0;
+        Ok(())
+    }
+
+    /**
+     * The file metadata, which gives the schema and the locations of the column chunks.
+     */
+    fn footer(&self) -> Vec<u8> {
+        let mut meta = Compact::default();
+        meta.i32(1, 1);
+
+        meta.begin_list(2, Compact::STRUCT, self.columns.len() + 1);
+        meta.begin_element();
+        meta.binary(4, b"schema");
+        meta.i32(5, self.columns.len() as i32);
+        meta.end_struct();
+        for (name, kind) in &self.columns {
+            meta.begin_element();
+            meta.i32(1, physical_type(*kind));
+            meta.i32(3, REPETITION_REQUIRED);
+            meta.binary(4, name.as_bytes());
+            if *kind == ColumnType::Utf8 {
+                meta.i32(6, CONVERTED_TYPE_UTF8);
+            }
+            meta.end_struct();
+        }
+
+        let rows: usize = self.row_groups.iter().map(|group| group.rows).sum();
+        meta.i64(3, rows as i64);
+
+        meta.begin_list(4, Compact::STRUCT, self.row_groups.len());
+        for group in &self.row_groups {
+            meta.begin_element();
+            meta.begin_list(1, Compact::STRUCT, group.chunks.len());
+            for ((name, kind), (offset, size)) in self.columns.iter().zip(&group.chunks) {
+                meta.begin_element();
+                meta.i64(2, *offset as i64);
+                meta.begin_struct(3);
+                meta.i32(1, physical_type(*kind));
+                meta.begin_list(2, Compact::I32, 2);
+                meta.varint(zigzag(ENCODING_PLAIN as i64));
+                meta.varint(zigzag(ENCODING_RLE as i64));
+                meta.begin_list(3, Compact::BINARY, 1);
+                meta.varint(name.len() as u64);
+                meta.out.extend(name.as_bytes());
+                meta.i32(4, CODEC_UNCOMPRESSED);
+                meta.i64(5, group.rows as i64);
+                meta.i64(6, *size as i64);
+                meta.i64(7, *size as i64);
+                meta.i64(9, *offset as i64);
+                meta.end_struct();
+                meta.end_struct();
+            }
+            let bytes: usize = group.chunks.iter().map(|(_, size)| size).sum();
+            meta.i64(2, bytes as i64);
+            meta.i64(3, group.rows as i64);
+            meta.end_struct();
+        }
+
+        meta.begin_list(5, Compact::STRUCT, 1);
+        meta.begin_element();
+        meta.binary(1, b"geo");
+        meta.binary(2, self.geo_metadata().as_bytes());
+        meta.end_struct();
+        meta.binary(
+            6,
+            concat!("geos version ", env!("CARGO_PKG_VERSION")).as_bytes(),
+        );
+        meta.finish()
+    }
+
+    /**
+     * The GeoParquet metadata of the geometry column. Coordinates are lng/lat, which is the
+     * default CRS and so is left out.
+     */
+    fn geo_metadata(&self) -> String {
+        let types = self
+            .geometry_types
+            .iter()
+            .map(|t| format!("\"{}\"", t))
+            .join(",");
+        let bbox = match self.bbox {
+            Some(bbox) => format!(
+                ",\"bbox\":[{},{},{},{}]",
+                bbox.min().x,
+                bbox.min().y,
+                bbox.max().x,
+                bbox.max().y
+            ),
+            None => String::new(),
+        };
+        format!(
+            r#"{{"version":"1.1.0","primary_column":"geometry","columns":{{"geometry":{{"encoding":"WKB","geometry_types":[{}]{}}}}}}}"#,
+            types, bbox
+        )
+    }
+}
+
+fn physical_type(kind: ColumnType) -> i32 {
+    match kind {
+        ColumnType::Int32 => TYPE_INT32,
+        ColumnType::Utf8 | ColumnType::Geometry => TYPE_BYTE_ARRAY,
+    }
+}
+
+/**
+ * The GeoParquet name of a geometry's type, as written to WKB.
+ */
+fn geometry_type(geometry: &Geometry) -> &'static str {
+    match geometry {
+        Geometry::Point(_) => "Point",
+        Geometry::Line(_) | Geometry::LineString(_) => "LineString",
+        Geometry::Polygon(_) | Geometry::Rect(_) | Geometry::Triangle(_) => "Polygon",
+        Geometry::MultiPoint(_) => "MultiPoint",
+        Geometry::MultiLineString(_) => "MultiLineString",
+        Geometry::MultiPolygon(_) => "MultiPolygon",
+        Geometry::GeometryCollection(_) => "GeometryCollection",
+    }
+}
+
+fn write_byte_array(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend((bytes.len() as u32).to_le_bytes());
+    out.extend(bytes);
+}
+
+fn union(a: &Rect, b: &Rect) -> Rect {
+    Rect::new(
+        Coord {
+            x: a.min().x.min(b.min().x),
+            y: a.min().y.min(b.min().y),
+        },
+        Coord {
+            x: a.max().x.max(b.max().x),
+            y: a.max().y.max(b.max().y),
+        },
+    )
+}
+
+//==================================================
+// Thrift compact protocol, which Parquet metadata is encoded in.
+//==================================================
+#[derive(Default)]
+struct Compact {
+    out: Vec<u8>,
+    /// The last field ID written in each enclosing struct, which field IDs are encoded relative to.
+    last_ids: Vec<i16>,
+    last_id: i16,
+}
+
+impl Compact {
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let delta = id - self.last_id;
+        if (1..=15).contains(&delta) {
+            self.out.push(((delta as u8) << 4) | kind);
+        } else {
+            self.out.push(kind);
+            self.varint(zigzag(id as i64));
+        }
+        self.last_id = id;
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.out.push(value as u8);
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, Self::I32);
+        self.varint(zigzag(value as i64));
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, Self::I64);
+        self.varint(zigzag(value));
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field(id, Self::BINARY);
+        self.varint(value.len() as u64);
+        self.out.extend(value);
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, Self::STRUCT);
+        self.begin_element();
+    }
+
+    /**
+     * Starts a struct that is an element of a list, and so has no field header.
+     */
+    fn begin_element(&mut self) {
+        self.last_ids.push(self.last_id);
+        self.last_id = 0;
+    }
+
+    fn end_struct(&mut self) {
+        self.out.push(0);
+        self.last_id = self.last_ids.pop().unwrap_or_default();
+    }
+
+    fn begin_list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field(id, Self::LIST);
+        if len < 15 {
+            self.out.push(((len as u8) << 4) | kind);
+        } else {
+            self.out.push(0xf0 | kind);
+            self.varint(len as u64);
+        }
+    }
+
+    /**
+     * Ends the outermost struct and returns its encoding.
+     */
+    fn finish(mut self) -> Vec<u8> {
+        self.out.push(0);
+        self.out
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, Geometry};
+    use parquet::basic::{ConvertedType, Type};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+
+    use super::{ColumnType, GeoParquetWriter, Value, ROW_GROUP_ROWS};
+    use crate::wkb::write_wkb;
+
+    #[test]
+    fn test_geoparquet_writer() {
+        let mut out = vec![];
+        let columns = [
+            ("cell", ColumnType::Utf8),
+            ("resolution", ColumnType::Int32),
+            ("geometry", ColumnType::Geometry),
+        ];
+        let mut writer = GeoParquetWriter::new(&mut out, &columns).unwrap();
+        let point = Geometry::Point(point!(x: 1.0, y: 2.0));
+        let row = [Value::Utf8("a"), Value::Int32(5), Value::Geometry(&point)];
+        writer.write_row(&row).unwrap();
+        writer.write_row(&row).unwrap();
+        assert!(writer.write_row(&row[..2]).is_err());
+        assert!(writer
+            .write_row(&[Value::Int32(1), Value::Int32(5), Value::Geometry(&point)])
+            .is_err());
+        assert_eq!(writer.finish().unwrap(), 2);
+
+        assert_eq!(&out[..4], b"PAR1");
+        assert_eq!(&out[out.len() - 4..], b"PAR1");
+        let footer_len = u32::from_le_bytes(out[out.len() - 8..out.len() - 4].try_into().unwrap());
+        let footer = &out[out.len() - 8 - footer_len as usize..out.len() - 8];
+        // The footer starts with the format version and ends the struct with a stop byte.
+        assert_eq!(&footer[..2], &[0x15, 0x02]);
+        assert_eq!(footer.last(), Some(&0));
+        let footer = String::from_utf8_lossy(footer);
+        assert!(footer.contains(r#""geometry_types":["Point"],"bbox":[1,2,1,2]"#));
+
+        // The column chunks follow the magic number.
+        let page_header = &out[4..8];
+        assert_eq!(page_header[0], 0x15);
+
+        assert!(GeoParquetWriter::new(vec![], &[("cell", ColumnType::Utf8)]).is_err());
+    }
+
+    #[test]
+    fn test_geoparquet_readback() {
+        let columns = [
+            ("cell", ColumnType::Utf8),
+            ("resolution", ColumnType::Int32),
+            ("geometry", ColumnType::Geometry),
+        ];
+        let points: Vec<Geometry> = (0..ROW_GROUP_ROWS + 3)
+            .map(|i| Geometry::Point(point!(x: i as f64 / 1000.0, y: -1.0)))
+            .collect();
+        let path =
+            std::env::temp_dir().join(format!("geos-parquet-{}.parquet", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+        let mut writer = GeoParquetWriter::new(file, &columns).unwrap();
+        for (i, point) in points.iter().enumerate() {
+            let cell = format!("cell-{}", i);
+            let row = [
+                Value::Utf8(&cell),
+                Value::Int32(i as i32 % 16),
+                Value::Geometry(point),
+            ];
+            writer.write_row(&row).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), points.len());
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), points.len() as i64);
+        assert_eq!(reader.num_row_groups(), 2);
+
+        let schema = metadata.schema_descr();
+        let types = schema
+            .columns()
+            .iter()
+            .map(|c| (c.name().to_string(), c.physical_type(), c.converted_type()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            [
+                ("cell".into(), Type::BYTE_ARRAY, ConvertedType::UTF8),
+                ("resolution".into(), Type::INT32, ConvertedType::NONE),
+                ("geometry".into(), Type::BYTE_ARRAY, ConvertedType::NONE),
+            ]
+        );
+
+        let geo = metadata
+            .key_value_metadata()
+            .unwrap()
+            .iter()
+            .find(|kv| kv.key == "geo")
+            .and_then(|kv| kv.value.clone())
+            .unwrap();
+        assert_eq!(
+            geo,
+            r#"{"version":"1.1.0","primary_column":"geometry","columns":{"geometry":{"encoding":"WKB","geometry_types":["Point"],"bbox":[0,-1,65.538,-1]}}}"#
+        );
+
+        let mut rows = 0;
+        for (i, row) in reader.get_row_iter(None).unwrap().enumerate() {
+            let fields = row
+                .unwrap()
+                .into_columns()
+                .into_iter()
+                .map(|(_, field)| field)
+                .collect::<Vec<_>>();
+            assert_eq!(fields[0], Field::Str(format!("cell-{}", i)));
+            assert_eq!(fields[1], Field::Int(i as i32 % 16));
+            assert_eq!(fields[2], Field::Bytes(write_wkb(&points[i]).into()));
+            rows += 1;
+        }
+        assert_eq!(rows, points.len());
+    }
+}
@@ -0,0 +1,423 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use geo::{BoundingRect, Intersects};
+use geo_types::{Coord, Point, Polygon};
+use tiff::decoder::{Decoder, DecodingResult, Limits};
+use tiff::tags::Tag;
+
+/// GeoTIFF key for the model type, which distinguishes projected from geographic rasters.
+const GT_MODEL_TYPE_GEO_KEY: u16 = 1024;
+/// GeoTIFF key for whether pixels represent areas or points.
+const GT_RASTER_TYPE_GEO_KEY: u16 = 1025;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+/**
+ * How raster values are looked up between pixel centers.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Interpolation {
+    /// The value of the pixel containing the point.
+    Nearest,
+    /// Bilinear interpolation between the four nearest pixel centers.
+    Bilinear,
+}
+
+/**
+ * Summary statistics of the raster values within a zone. Without any values, the minimum, maximum,
+ * and mean are NaN.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ZonalStats {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+}
+
+impl ZonalStats {
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+    }
+}
+
+impl Default for ZonalStats {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            min: f64::NAN,
+            max: f64::NAN,
+            sum: 0.0,
+        }
+    }
+}
+
+/**
+ * A single-band raster in geographic (lng/lat) coordinates, such as a digital elevation model.
+ *
+ * The affine transform maps continuous pixel coordinates (col, row), where the top-left corner of
+ * the raster is (0, 0), to lng/lat in the same layout as GDAL's geotransform:
+ * `lng = t[0] + col * t[1] + row * t[2]` and `lat = t[3] + col * t[4] + row * t[5]`.
+ */
+#[derive(Debug, Clone)]
+pub struct Raster {
+    width: usize,
+    height: usize,
+    transform: [f64; 6],
+    nodata: Option<f64>,
+    values: Vec<f64>,
+}
+
+impl Raster {
+    /**
+     * Creates a raster from row-major values. Values equal to `nodata`, or NaN, are treated as
+     * missing.
+     */
+    pub fn new(
+        width: usize,
+        height: usize,
+        transform: [f64; 6],
+        nodata: Option<f64>,
+        values: Vec<f64>,
+    ) -> Result<Self, Box<dyn Error>> {
+        if values.len() != width * height {
+            return Err(format!(
+                "expected {} raster values for {}x{} pixels, got {}",
+                width * height,
+                width,
+                height,
+                values.len()
+            )
+            .into());
+        }
+        if transform[1] * transform[5] - transform[2] * transform[4] == 0.0 {
+            return Err("raster transform is not invertible".into());
+        }
+        Ok(Self {
+            width,
+            height,
+            transform,
+            nodata,
+            values,
+        })
+    }
+
+    /**
+     * Reads the first band of a GeoTIFF file.
+     */
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_geotiff(BufReader::new(file))
+    }
+
+    /**
+     * Reads the first band of a GeoTIFF. Georeferencing is taken from the model transformation tag,
+     * or else from the tiepoint and pixel scale tags. Missing values are read from GDAL's nodata
+     * tag. Only geographic rasters are supported, since points are given in lng/lat.
+     */
+    pub fn from_geotiff<R: Read + Seek>(reader: R) -> Result<Self, Box<dyn Error>> {
+        let mut decoder = Decoder::new(reader)?.with_limits(Limits::unlimited());
+        let (width, height) = decoder.dimensions()?;
+        let (width, height) = (width as usize, height as usize);
+
+        let geo_keys = decoder
+            .find_tag(Tag::GeoKeyDirectoryTag)?
+            .map(|v| v.into_u16_vec())
+            .transpose()?
+            .unwrap_or_default();
+        let geo_key = |key: u16| {
+            // A 4-value header, followed by (key, location, count, value) entries. Short values are
+            // stored inline with a location of 0.
+            geo_keys
+                .get(4..)?
+                .chunks_exact(4)
+                .find(|entry| entry[0] == key && entry[1] == 0)
+                .map(|entry| entry[3])
+        };
+        if geo_key(GT_MODEL_TYPE_GEO_KEY).is_some_and(|t| t != MODEL_TYPE_GEOGRAPHIC) {
+            return Err(
+                "only geographic (lng/lat) rasters are supported; reproject the raster to EPSG:4326"
+                    .into(),
+            );
+        }
+
+        let mut transform = match decoder.find_tag(Tag::ModelTransformationTag)? {
+            Some(m) => {
+                let m = m.into_f64_vec()?;
+                if m.len() < 8 {
+                    return Err("malformed GeoTIFF model transformation".into());
+                }
+                [m[3], m[0], m[1], m[7], m[4], m[5]]
+            }
+            None => {
+                let tiepoint = decoder
+                    .find_tag(Tag::ModelTiepointTag)?
+                    .ok_or("raster is not georeferenced: missing GeoTIFF tiepoint")?
+                    .into_f64_vec()?;
+                let scale = decoder
+                    .find_tag(Tag::ModelPixelScaleTag)?
+                    .ok_or("raster is not georeferenced: missing GeoTIFF pixel scale")?
+                    .into_f64_vec()?;
+                if tiepoint.len() < 6 || scale.len() < 2 {
+                    return Err("malformed GeoTIFF tiepoint or pixel scale".into());
+                }
+                let (col, row, lng, lat) = (tiepoint[0], tiepoint[1], tiepoint[3], tiepoint[4]);
+                let (dx, dy) = (scale[0], -scale[1]);
+                [lng - col * dx, dx, 0.0, lat - row * dy, 0.0, dy]
+            }
+        };
+        if geo_key(GT_RASTER_TYPE_GEO_KEY) == Some(RASTER_PIXEL_IS_POINT) {
+            // The georeferencing refers to pixel centers rather than corners.
+            transform[0] -= 0.5 * (transform[1] + transform[2]);
+            transform[3] -= 0.5 * (transform[4] + transform[5]);
+        }
+
+        let nodata = match decoder.find_tag(Tag::GdalNodata)? {
+            Some(v) => {
+                let s = v.into_string()?;
+                let s = s.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+                Some(
+                    s.parse::<f64>()
+                        .map_err(|_| format!("invalid nodata value: {}", s))?,
+                )
+            }
+            None => None,
+        };
+
+        let values = decoding_result_to_f64(decoder.read_image()?);
+        // Bands are either interleaved per pixel or stored as consecutive planes.
+        let samples = values.len() / usize::max(1, width * height);
+        let planar = decoder.find_tag_unsigned::<u16>(Tag::PlanarConfiguration)? == Some(2);
+        let values = if samples <= 1 || planar {
+            values.into_iter().take(width * height).collect()
+        } else {
+            values.into_iter().step_by(samples).collect()
+        };
+
+        Self::new(width, height, transform, nodata, values)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /**
+     * The value of a pixel, or `None` if it is outside of the raster or missing.
+     */
+    pub fn value(&self, col: isize, row: isize) -> Option<f64> {
+        if col < 0 || row < 0 || col as usize >= self.width || row as usize >= self.height {
+            return None;
+        }
+        let value = self.values[row as usize * self.width + col as usize];
+        if value.is_nan() || self.nodata.is_some_and(|nodata| value == nodata) {
+            return None;
+        }
+        Some(value)
+    }
+
+    /**
+     * The lng/lat of the center of a pixel.
+     */
+    pub fn pixel_center(&self, col: usize, row: usize) -> Point {
+        let (col, row) = (col as f64 + 0.5, row as f64 + 0.5);
+        let t = &self.transform;
+        Point::new(
+            t[0] + col * t[1] + row * t[2],
+            t[3] + col * t[4] + row * t[5],
+        )
+    }
+
+    /**
+     * The continuous (col, row) pixel coordinates of a lng/lat coordinate.
+     */
+    pub fn to_pixel(&self, coord: Coord) -> (f64, f64) {
+        let t = &self.transform;
+        let (x, y) = (coord.x - t[0], coord.y - t[3]);
+        let det = t[1] * t[5] - t[2] * t[4];
+        ((x * t[5] - y * t[2]) / det, (y * t[1] - x * t[4]) / det)
+    }
+
+    /**
+     * Summarizes the values of the pixels whose centers are within a polygon. Zones smaller than a
+     * pixel may not contain any pixel centers.
+     */
+    pub fn zonal_stats(&self, polygon: &Polygon) -> ZonalStats {
+        let mut stats = ZonalStats::default();
+        let Some(bbox) = polygon.bounding_rect() else {
+            return stats;
+        };
+
+        // The range of pixels overlapping the bounding box, which may be rotated in pixel space.
+        let (min, max) = (bbox.min(), bbox.max());
+        let corners = [
+            min,
+            max,
+            Coord { x: min.x, y: max.y },
+            Coord { x: max.x, y: min.y },
+        ]
+        .map(|c| self.to_pixel(c));
+        let pixel_range = |values: [f64; 4], size: usize| {
+            let lo = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let hi = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let lo = lo.floor().clamp(0.0, size as f64) as usize;
+            let hi = hi.ceil().clamp(0.0, size as f64) as usize;
+            lo..hi
+        };
+        let cols = pixel_range(corners.map(|(col, _)| col), self.width);
+        let rows = pixel_range(corners.map(|(_, row)| row), self.height);
+
+        for row in rows {
+            for col in cols.clone() {
+                if !polygon.intersects(&self.pixel_center(col, row)) {
+                    continue;
+                }
+                if let Some(value) = self.value(col as isize, row as isize) {
+                    stats.add(value);
+                }
+            }
+        }
+        stats
+    }
+
+    /**
+     * The raster value at a point, or `None` if the point is outside of the raster or its value is
+     * missing. Bilinear interpolation falls back to the nearest pixel near the edges of the raster
+     * and next to missing values.
+     */
+    pub fn sample(&self, point: &Point, interpolation: Interpolation) -> Option<f64> {
+        let (col, row) = self.to_pixel(point.0);
+        let nearest = || self.value(col.floor() as isize, row.floor() as isize);
+        match interpolation {
+            Interpolation::Nearest => nearest(),
+            Interpolation::Bilinear => {
+                // Offsets from the pixel center up and to the left of the point.
+                let (x, y) = (col - 0.5, row - 0.5);
+                let (c0, r0) = (x.floor() as isize, y.floor() as isize);
+                let (tx, ty) = (x - x.floor(), y - y.floor());
+                let corners = [
+                    self.value(c0, r0),
+                    self.value(c0 + 1, r0),
+                    self.value(c0, r0 + 1),
+                    self.value(c0 + 1, r0 + 1),
+                ];
+                match corners {
+                    [Some(v00), Some(v10), Some(v01), Some(v11)] => {
+                        let top = v00 * (1.0 - tx) + v10 * tx;
+                        let bottom = v01 * (1.0 - tx) + v11 * tx;
+                        Some(top * (1.0 - ty) + bottom * ty)
+                    }
+                    _ => nearest(),
+                }
+            }
+        }
+    }
+}
+
+fn decoding_result_to_f64(result: DecodingResult) -> Vec<f64> {
+    match result {
+        DecodingResult::U8(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::U16(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::U32(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::U64(v) => v.into_iter().map(|x| x as f64).collect(),
+        DecodingResult::I8(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::I16(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::I32(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::I64(v) => v.into_iter().map(|x| x as f64).collect(),
+        DecodingResult::F32(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::F64(v) => v,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use geo_types::{polygon, Point};
+    use tiff::encoder::{colortype, TiffEncoder};
+    use tiff::tags::Tag;
+
+    use super::{Interpolation, Raster, ZonalStats};
+
+    #[test]
+    fn test_geotiff_sampling() {
+        // A 3x2 raster of 1 degree pixels whose top-left corner is at (10, 50), with one missing value.
+        let values: Vec<f32> = vec![100.0, 200.0, 300.0, 400.0, 500.0, -9999.0];
+        let mut bytes = Cursor::new(vec![]);
+        {
+            let mut encoder = TiffEncoder::new(&mut bytes).unwrap();
+            let mut image = encoder.new_image::<colortype::Gray32Float>(3, 2).unwrap();
+            let tags = image.encoder();
+            tags.write_tag(Tag::ModelPixelScaleTag, &[1.0, 1.0, 0.0][..])
+                .unwrap();
+            tags.write_tag(Tag::ModelTiepointTag, &[0.0, 0.0, 0.0, 10.0, 50.0, 0.0][..])
+                .unwrap();
+            tags.write_tag(Tag::GeoKeyDirectoryTag, &[1u16, 1, 0, 1, 1024, 0, 1, 2][..])
+                .unwrap();
+            tags.write_tag(Tag::GdalNodata, "-9999").unwrap();
+            image.write_data(&values).unwrap();
+        }
+        bytes.set_position(0);
+        let raster = Raster::from_geotiff(bytes).unwrap();
+        assert_eq!((raster.width(), raster.height()), (3, 2));
+        assert_eq!(raster.pixel_center(1, 1), Point::new(11.5, 48.5));
+
+        let nearest = |lng, lat| raster.sample(&Point::new(lng, lat), Interpolation::Nearest);
+        let bilinear = |lng, lat| raster.sample(&Point::new(lng, lat), Interpolation::Bilinear);
+        assert_eq!(nearest(10.2, 49.9), Some(100.0));
+        assert_eq!(nearest(11.9, 48.1), Some(500.0));
+        assert_eq!(nearest(12.5, 48.5), None);
+        assert_eq!(nearest(9.5, 49.5), None);
+
+        // Halfway between the centers of the top-left four pixels.
+        assert_eq!(bilinear(11.0, 49.0), Some(300.0));
+        // Next to the missing value, fall back to the nearest pixel.
+        assert_eq!(bilinear(11.9, 48.9), Some(500.0));
+    }
+
+    #[test]
+    fn test_zonal_stats() {
+        // A 4x4 raster of 1 degree pixels over (0, 0)..(4, 4) with values 0..16 in row-major order
+        // from the top-left, and one missing value.
+        let mut values = (0..16).map(f64::from).collect::<Vec<_>>();
+        values[5] = f64::NAN;
+        let raster = Raster::new(4, 4, [0.0, 1.0, 0.0, 4.0, 0.0, -1.0], None, values).unwrap();
+
+        // The top-left 2x2 pixels, one of which is missing.
+        let zone = polygon![(x: 0.0, y: 2.0), (x: 2.0, y: 2.0), (x: 2.0, y: 4.0), (x: 0.0, y: 4.0)];
+        let stats = raster.zonal_stats(&zone);
+        assert_eq!(
+            stats,
+            ZonalStats {
+                count: 3,
+                min: 0.0,
+                max: 4.0,
+                sum: 5.0
+            }
+        );
+
+        // A zone that extends past the raster only counts the pixels inside it.
+        let zone =
+            polygon![(x: 3.0, y: -1.0), (x: 9.0, y: -1.0), (x: 9.0, y: 1.0), (x: 3.0, y: 1.0)];
+        assert_eq!(raster.zonal_stats(&zone).sum, 15.0);
+
+        // A zone between pixel centers is empty.
+        let zone = polygon![(x: 0.6, y: 0.6), (x: 0.9, y: 0.6), (x: 0.9, y: 0.9)];
+        let stats = raster.zonal_stats(&zone);
+        assert_eq!(stats.count, 0);
+        assert!(stats.mean().is_nan());
+    }
+}
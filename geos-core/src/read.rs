@@ -0,0 +1,773 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead, Read};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon,
+};
+
+use crate::mmap::{Mmap, MmapReader};
+
+/**
+ * Reads a geometry from a WKT or GeoJSON file, detected by whether the file starts with `{`. The
+ * file is memory-mapped and parsed in place.
+ */
+pub fn read_geometry_file(path: &Path) -> Result<Geometry, Box<dyn Error>> {
+    read_geometry_file_with_warnings(path, false, &mut vec![]).map(|(_, geometry)| geometry)
+}
+
+/**
+ * Like `read_geometry_file`, but reports the parts of the input that were dropped because they
+ * cannot be represented, e.g. Z values, by adding a message to `warnings`. If `lenient` is set,
+ * sloppy WKT is fixed up with a warning rather than rejected, as with `read_wkt_with_warnings`.
+ * EWKT is accepted too, and its SRID returned along with the geometry.
+ */
+pub fn read_geometry_file_with_warnings(
+    path: &Path,
+    lenient: bool,
+    warnings: &mut Vec<String>,
+) -> Result<(Option<u32>, Geometry), Box<dyn Error>> {
+    let map = Mmap::open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    read_geometry_with_warnings(MmapReader::new(map), lenient, warnings).map_err(
+        |e| -> Box<dyn Error> {
+            match e.downcast::<WktError>() {
+                Ok(e) => Box::new(WktError {
+                    path: Some(path.to_path_buf()),
+                    ..*e
+                }),
+                Err(e) => format!("{}: {}", path.display(), e).into(),
+            }
+        },
+    )
+}
+
+/**
+ * A WKT syntax error. The byte range of the offending token is kept so that callers holding the
+ * input can point at it.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct WktError {
+    /// The file the WKT was read from, if any.
+    pub path: Option<PathBuf>,
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl Display for WktError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}: ", path.display())?;
+        }
+        write!(
+            f,
+            "invalid WKT at byte {}: {}",
+            self.span.start, self.message
+        )
+    }
+}
+
+impl Error for WktError {}
+
+/**
+ * Reads a geometry in WKT or GeoJSON from a reader, detected by whether the input starts with `{`.
+ */
+pub fn read_geometry(reader: impl BufRead) -> Result<Geometry, Box<dyn Error>> {
+    read_geometry_with_warnings(reader, false, &mut vec![]).map(|(_, geometry)| geometry)
+}
+
+fn read_geometry_with_warnings(
+    mut reader: impl BufRead,
+    lenient: bool,
+    warnings: &mut Vec<String>,
+) -> Result<(Option<u32>, Geometry), Box<dyn Error>> {
+    let is_json = loop {
+        let buf = reader.fill_buf()?;
+        match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(i) => break buf[i] == b'{',
+            None if buf.is_empty() => break false,
+            None => {
+                let len = buf.len();
+                reader.consume(len);
+            }
+        }
+    };
+    if is_json {
+        Ok((None, read_geojson(reader)?))
+    } else {
+        read_ewkt_with_warnings(reader, lenient, warnings)
+    }
+}
+
+/**
+ * Reads a GeoJSON geometry, feature, or feature collection. Features are reduced to their
+ * geometries, and collections to a geometry collection. The JSON is parsed directly from the
+ * reader, without first reading it into a string.
+ */
+pub fn read_geojson(reader: impl Read) -> Result<Geometry, Box<dyn Error>> {
+    let geojson = geojson::GeoJson::from_reader(reader)?;
+    Ok(Geometry::try_from(geojson)?)
+}
+
+/**
+ * Reads a WKT geometry. Unlike parsing a string with the `wkt` crate, the input is tokenized as it
+ * is read and coordinates are converted directly to `geo_types`, so that neither the text nor an
+ * intermediate syntax tree is ever held in memory. This matters for coastline-resolution polygons
+ * that are hundreds of megabytes of text. Z and M coordinates are accepted and dropped. Polygon
+ * rings must be closed.
+ */
+pub fn read_wkt(reader: impl BufRead) -> Result<Geometry, Box<dyn Error>> {
+    read_wkt_with_warnings(reader, false, &mut vec![])
+}
+
+/**
+ * Like `read_wkt`, but reports dropped Z and M values and empty points by adding a message to
+ * `warnings`.
+ *
+ * If `lenient` is set, common mistakes in hand-edited WKT are fixed rather than rejected, each with
+ * a warning: unclosed polygon rings are closed, stray commas before `)` or another `,` are dropped,
+ * and Fortran-style `D` exponents like `1.5D3` are read as `1.5E3`.
+ */
+pub fn read_wkt_with_warnings(
+    reader: impl BufRead,
+    lenient: bool,
+    warnings: &mut Vec<String>,
+) -> Result<Geometry, Box<dyn Error>> {
+    parse_wkt(reader, 0, lenient, warnings)
+}
+
+/**
+ * Like `read_wkt_with_warnings`, but also accepts EWKT, the WKT prefixed with the SRID of its
+ * coordinates as `SRID=<code>;` that PostGIS writes, and returns the SRID if one was given.
+ */
+pub fn read_ewkt_with_warnings(
+    mut reader: impl BufRead,
+    lenient: bool,
+    warnings: &mut Vec<String>,
+) -> Result<(Option<u32>, Geometry), Box<dyn Error>> {
+    let (srid, offset) = read_srid(&mut reader)?;
+    Ok((srid, parse_wkt(reader, offset, lenient, warnings)?))
+}
+
+/**
+ * Reads the `SRID=<code>;` prefix of EWKT, if the input starts with one, returning the SRID and the
+ * number of bytes read. The prefix must be within the reader's first buffer.
+ */
+fn read_srid(reader: &mut impl BufRead) -> Result<(Option<u32>, usize), Box<dyn Error>> {
+    let buf = reader.fill_buf()?;
+    let start = buf
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(buf.len());
+    let prefix = &buf[start..];
+    if !prefix
+        .get(..5)
+        .is_some_and(|p| p.eq_ignore_ascii_case(b"SRID="))
+    {
+        return Ok((None, 0));
+    }
+    let end = prefix.iter().position(|b| *b == b';');
+    let code = end
+        .and_then(|end| std::str::from_utf8(&prefix[5..end]).ok())
+        .and_then(|code| code.trim().parse::<u32>().ok());
+    let (Some(end), Some(code)) = (end, code) else {
+        let len = end.unwrap_or(prefix.len().min(5));
+        return Err(Box::new(WktError {
+            path: None,
+            span: start..start + len.max(1),
+            message: "expected an SRID like `SRID=4326;`".to_string(),
+        }));
+    };
+    let len = start + end + 1;
+    reader.consume(len);
+    Ok((Some(code), len))
+}
+
+/**
+ * Parses WKT starting at a byte offset of the input, which error spans are relative to.
+ */
+fn parse_wkt(
+    reader: impl BufRead,
+    offset: usize,
+    lenient: bool,
+    warnings: &mut Vec<String>,
+) -> Result<Geometry, Box<dyn Error>> {
+    let mut parser = WktParser {
+        tokens: Tokenizer {
+            reader,
+            offset,
+            start: offset,
+            buf: String::new(),
+            lenient,
+            d_exponents: 0,
+        },
+        peeked: None,
+        span: 0..0,
+        lenient,
+        dropped_dimensions: 0,
+        dropped_points: 0,
+        closed_rings: 0,
+        stray_commas: 0,
+    };
+    let geometry = parser
+        .geometry()?
+        .ok_or_else(|| parser.error("empty points are not supported"))?;
+    match parser.next()? {
+        Token::Eof => {}
+        token => return Err(parser.unexpected(&token, "end of input")),
+    }
+
+    if parser.dropped_dimensions > 0 {
+        warnings.push(format!(
+            "dropped the Z and M values of {} coordinates",
+            parser.dropped_dimensions
+        ));
+    }
+    if parser.dropped_points > 0 {
+        warnings.push(format!("dropped {} empty points", parser.dropped_points));
+    }
+    if parser.closed_rings > 0 {
+        warnings.push(format!("closed {} unclosed rings", parser.closed_rings));
+    }
+    if parser.stray_commas > 0 {
+        warnings.push(format!("dropped {} stray commas", parser.stray_commas));
+    }
+    if parser.tokens.d_exponents > 0 {
+        warnings.push(format!(
+            "read {} numbers with `D` exponents",
+            parser.tokens.d_exponents
+        ));
+    }
+    Ok(geometry)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    LeftParen,
+    RightParen,
+    Comma,
+    Eof,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "`{}`", word),
+            Token::Number(n) => write!(f, "`{}`", n),
+            Token::LeftParen => f.write_str("`(`"),
+            Token::RightParen => f.write_str("`)`"),
+            Token::Comma => f.write_str("`,`"),
+            Token::Eof => f.write_str("end of input"),
+        }
+    }
+}
+
+struct Tokenizer<R> {
+    reader: R,
+    /// The byte offset of the reader, for error messages.
+    offset: usize,
+    /// The byte offset at which the last token started.
+    start: usize,
+    /// Reused across tokens to avoid an allocation per number.
+    buf: String,
+    /// Whether `D` exponents are read as `E`.
+    lenient: bool,
+    /// The number of numbers read with `D` exponents.
+    d_exponents: usize,
+}
+
+impl<R: BufRead> Tokenizer<R> {
+    fn peek_byte(&mut self) -> Result<Option<u8>, Box<dyn Error>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    fn consume_byte(&mut self) {
+        self.reader.consume(1);
+        self.offset += 1;
+    }
+
+    /**
+     * Reads the next token along with its byte range.
+     */
+    fn next_spanned(&mut self) -> Result<(Token, Range<usize>), Box<dyn Error>> {
+        let token = self.next()?;
+        Ok((token, self.start..self.offset))
+    }
+
+    fn next(&mut self) -> Result<Token, Box<dyn Error>> {
+        while self.peek_byte()?.is_some_and(|b| b.is_ascii_whitespace()) {
+            self.consume_byte();
+        }
+        self.start = self.offset;
+        let Some(byte) = self.peek_byte()? else {
+            return Ok(Token::Eof);
+        };
+        let token = match byte {
+            b'(' => Token::LeftParen,
+            b')' => Token::RightParen,
+            b',' => Token::Comma,
+            b if b.is_ascii_alphabetic() => {
+                self.take_while(|b| b.is_ascii_alphabetic())?;
+                return Ok(Token::Word(self.buf.clone()));
+            }
+            b if b.is_ascii_digit() || b == b'-' || b == b'+' || b == b'.' => {
+                self.take_while(|b| {
+                    b.is_ascii_digit()
+                        || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'd' | b'D')
+                })?;
+                if self.lenient && self.buf.contains(['d', 'D']) {
+                    self.buf = self.buf.replace(['d', 'D'], "E");
+                    self.d_exponents += 1;
+                }
+                let number = self
+                    .buf
+                    .parse()
+                    .map_err(|_| self.error(&format!("invalid number `{}`", self.buf)))?;
+                return Ok(Token::Number(number));
+            }
+            b if b.is_ascii() => {
+                return Err(self.error(&format!("unexpected character {:?}", b as char)))
+            }
+            _ => return Err(self.error("unexpected non-ASCII character")),
+        };
+        self.consume_byte();
+        Ok(token)
+    }
+
+    /**
+     * Reads bytes matching a predicate into the token buffer.
+     */
+    fn take_while(&mut self, predicate: impl Fn(u8) -> bool) -> Result<(), Box<dyn Error>> {
+        self.buf.clear();
+        while let Some(b) = self.peek_byte()?.filter(|&b| predicate(b)) {
+            self.buf.push(b as char);
+            self.consume_byte();
+        }
+        Ok(())
+    }
+
+    /**
+     * An error in the token being read, which spans what has been read of it so far.
+     */
+    fn error(&self, message: &str) -> Box<dyn Error> {
+        Box::new(WktError {
+            path: None,
+            span: self.start..self.offset.max(self.start + 1),
+            message: message.to_string(),
+        })
+    }
+}
+
+struct WktParser<R> {
+    tokens: Tokenizer<R>,
+    peeked: Option<(Token, Range<usize>)>,
+    /// The byte range of the last token returned by `next`.
+    span: Range<usize>,
+    /// Whether sloppy WKT is fixed up rather than rejected.
+    lenient: bool,
+    /// The number of coordinates whose Z and M values were dropped.
+    dropped_dimensions: usize,
+    /// The number of empty points dropped from multipoints and collections.
+    dropped_points: usize,
+    /// The number of polygon rings closed in lenient mode.
+    closed_rings: usize,
+    /// The number of commas dropped in lenient mode.
+    stray_commas: usize,
+}
+
+impl<R: BufRead> WktParser<R> {
+    fn next(&mut self) -> Result<Token, Box<dyn Error>> {
+        let (token, span) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.tokens.next_spanned()?,
+        };
+        self.span = span;
+        Ok(token)
+    }
+
+    fn peek(&mut self) -> Result<&Token, Box<dyn Error>> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.tokens.next_spanned()?);
+        }
+        Ok(&self.peeked.as_ref().expect("just peeked").0)
+    }
+
+    /**
+     * An error in the last token returned by `next`.
+     */
+    fn error(&self, message: &str) -> Box<dyn Error> {
+        self.error_at(self.span.clone(), message)
+    }
+
+    fn error_at(&self, span: Range<usize>, message: &str) -> Box<dyn Error> {
+        Box::new(WktError {
+            path: None,
+            span,
+            message: message.to_string(),
+        })
+    }
+
+    fn unexpected(&self, token: &Token, expected: &str) -> Box<dyn Error> {
+        self.error(&format!("expected {}, found {}", expected, token))
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), Box<dyn Error>> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(self.unexpected(&token, &expected.to_string())),
+        }
+    }
+
+    /**
+     * Parses a tagged geometry. Returns `None` for `POINT EMPTY`, which `geo_types` cannot
+     * represent.
+     */
+    fn geometry(&mut self) -> Result<Option<Geometry>, Box<dyn Error>> {
+        let tag = match self.next()? {
+            Token::Word(tag) => tag.to_ascii_uppercase(),
+            token => return Err(self.unexpected(&token, "a geometry type")),
+        };
+        let tag_span = self.span.clone();
+        // Dimension markers, e.g. `POINT Z (1 2 3)`.
+        if let Token::Word(word) = self.peek()? {
+            if ["Z", "M", "ZM"].contains(&word.to_ascii_uppercase().as_str()) {
+                self.next()?;
+            }
+        }
+        let geometry = match tag.as_str() {
+            "POINT" if self.is_empty()? => {
+                self.dropped_points += 1;
+                return Ok(None);
+            }
+            "POINT" => Geometry::Point(self.point()?),
+            "LINESTRING" => {
+                Geometry::LineString(self.empty_or(LineString(vec![]), Self::line_string)?)
+            }
+            "POLYGON" => Geometry::Polygon(self.empty_or(empty_polygon(), Self::polygon)?),
+            "MULTIPOINT" => Geometry::MultiPoint(MultiPoint(
+                self.empty_or(vec![], |p| p.list(Self::multi_point_member))?
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            )),
+            "MULTILINESTRING" => {
+                Geometry::MultiLineString(MultiLineString(self.empty_or(vec![], |p| {
+                    p.list(|p| p.empty_or(LineString(vec![]), Self::line_string))
+                })?))
+            }
+            "MULTIPOLYGON" => Geometry::MultiPolygon(MultiPolygon(self.empty_or(vec![], |p| {
+                p.list(|p| p.empty_or(empty_polygon(), Self::polygon))
+            })?)),
+            "GEOMETRYCOLLECTION" => Geometry::GeometryCollection(GeometryCollection(
+                self.empty_or(vec![], |p| p.list(Self::geometry))?
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            )),
+            _ => {
+                let message = format!("unknown geometry type `{}`", tag);
+                return Err(self.error_at(tag_span, &message));
+            }
+        };
+        Ok(Some(geometry))
+    }
+
+    fn is_empty(&mut self) -> Result<bool, Box<dyn Error>> {
+        match self.peek()? {
+            Token::Word(word) if word.eq_ignore_ascii_case("EMPTY") => {
+                self.next()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn empty_or<T>(
+        &mut self,
+        empty: T,
+        parse: impl FnOnce(&mut Self) -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        if self.is_empty()? {
+            Ok(empty)
+        } else {
+            parse(self)
+        }
+    }
+
+    /**
+     * Parses a parenthesized, comma-separated list.
+     */
+    fn list<T>(
+        &mut self,
+        mut parse: impl FnMut(&mut Self) -> Result<T, Box<dyn Error>>,
+    ) -> Result<Vec<T>, Box<dyn Error>> {
+        self.expect(Token::LeftParen)?;
+        let mut items = vec![parse(self)?];
+        loop {
+            match self.next()? {
+                Token::Comma if self.lenient && self.skip_stray_commas()? => {}
+                Token::Comma => items.push(parse(self)?),
+                Token::RightParen => return Ok(items),
+                token => return Err(self.unexpected(&token, "`,` or `)`")),
+            }
+        }
+    }
+
+    /**
+     * Skips the commas following a comma, returning whether the list ends after them, in which case
+     * the first comma was stray too.
+     */
+    fn skip_stray_commas(&mut self) -> Result<bool, Box<dyn Error>> {
+        while *self.peek()? == Token::Comma {
+            self.next()?;
+            self.stray_commas += 1;
+        }
+        let ends = *self.peek()? == Token::RightParen;
+        if ends {
+            self.stray_commas += 1;
+        }
+        Ok(ends)
+    }
+
+    fn coord(&mut self) -> Result<Coord, Box<dyn Error>> {
+        let mut values = [0.0; 2];
+        for value in values.iter_mut() {
+            *value = match self.next()? {
+                Token::Number(n) => n,
+                token => return Err(self.unexpected(&token, "a number")),
+            };
+        }
+        // Z and M values.
+        if let Token::Number(_) = self.peek()? {
+            self.dropped_dimensions += 1;
+        }
+        while let Token::Number(_) = self.peek()? {
+            self.next()?;
+        }
+        Ok(Coord {
+            x: values[0],
+            y: values[1],
+        })
+    }
+
+    fn point(&mut self) -> Result<Point, Box<dyn Error>> {
+        self.expect(Token::LeftParen)?;
+        let coord = self.coord()?;
+        self.expect(Token::RightParen)?;
+        Ok(Point(coord))
+    }
+
+    /**
+     * Parses a multipoint member, which may or may not be parenthesized, or may be `EMPTY`.
+     */
+    fn multi_point_member(&mut self) -> Result<Option<Point>, Box<dyn Error>> {
+        if *self.peek()? == Token::LeftParen {
+            Ok(Some(self.point()?))
+        } else if self.is_empty()? {
+            self.dropped_points += 1;
+            Ok(None)
+        } else {
+            Ok(Some(Point(self.coord()?)))
+        }
+    }
+
+    fn line_string(&mut self) -> Result<LineString, Box<dyn Error>> {
+        Ok(LineString(self.list(Self::coord)?))
+    }
+
+    /**
+     * Parses a polygon ring, which must end where it starts.
+     */
+    fn ring(&mut self) -> Result<LineString, Box<dyn Error>> {
+        let mut ring = self.line_string()?;
+        let (Some(&first), Some(&last)) = (ring.0.first(), ring.0.last()) else {
+            return Ok(ring);
+        };
+        if first != last {
+            if !self.lenient {
+                let message = format!(
+                    "ring is not closed: it starts at ({} {}) but ends at ({} {})",
+                    first.x, first.y, last.x, last.y
+                );
+                return Err(self.error(&message));
+            }
+            ring.0.push(first);
+            self.closed_rings += 1;
+        }
+        Ok(ring)
+    }
+
+    fn polygon(&mut self) -> Result<Polygon, Box<dyn Error>> {
+        let mut rings = self
+            .list(|p| p.empty_or(LineString(vec![]), Self::ring))?
+            .into_iter();
+        let exterior = rings.next().unwrap_or(LineString(vec![]));
+        Ok(Polygon::new(exterior, rings.collect()))
+    }
+}
+
+fn empty_polygon() -> Polygon {
+    Polygon::new(LineString(vec![]), vec![])
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{Geometry, Point};
+    use wkt::TryFromWkt;
+
+    use super::{
+        read_ewkt_with_warnings, read_geometry, read_wkt, read_wkt_with_warnings, WktError,
+    };
+
+    #[test]
+    fn test_read_wkt_matches_wkt_crate() {
+        for wkt in [
+            "POINT (1 2)",
+            "LINESTRING(0 0,1 1,2 0)",
+            "POLYGON((0 0,4 0,4 4,0 4,0 0),(1 1,2 1,2 2,1 1))",
+            "MULTIPOINT((0 0),(1 1))",
+            "MULTIPOINT(0 0,1 1)",
+            "MULTILINESTRING((0 0,1 1),(2 2,3 3))",
+            "MULTIPOLYGON(((0 0,1 0,1 1,0 0)),((2 2,3 2,3 3,2 2)))",
+            "GEOMETRYCOLLECTION(POINT(1 2),LINESTRING(0 0,1e1 -1.5))",
+            "POLYGON EMPTY",
+        ] {
+            let expected = Geometry::<f64>::try_from_wkt_str(wkt).expect(wkt);
+            assert_eq!(read_wkt(wkt.as_bytes()).unwrap(), expected, "{}", wkt);
+        }
+
+        assert_eq!(
+            read_wkt("point zm (1 2 3 4)".as_bytes()).unwrap(),
+            Geometry::Point(Point::new(1.0, 2.0))
+        );
+        assert!(read_wkt("POINT(1 2".as_bytes()).is_err());
+        assert!(read_wkt("POINT(1 2) POINT(3 4)".as_bytes()).is_err());
+        assert!(read_wkt("CIRCLE(1 2)".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_read_wkt_warnings() {
+        let mut warnings = vec![];
+        let geometry = read_wkt_with_warnings(
+            "GEOMETRYCOLLECTION(POINT EMPTY,MULTIPOINT Z(1 2 3,EMPTY,(4 5 6)),POINT(7 8))"
+                .as_bytes(),
+            false,
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(
+            geometry,
+            Geometry::<f64>::try_from_wkt_str("GEOMETRYCOLLECTION(MULTIPOINT(1 2,4 5),POINT(7 8))")
+                .unwrap()
+        );
+        assert_eq!(
+            warnings,
+            [
+                "dropped the Z and M values of 2 coordinates",
+                "dropped 2 empty points"
+            ]
+        );
+
+        warnings.clear();
+        read_wkt_with_warnings("POINT(1 2)".as_bytes(), false, &mut warnings).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_read_wkt_lenient() {
+        let sloppy = "MULTIPOLYGON(((0 0,1 0,1D0 1,)),((2 2,3 2,,3 3,2 2)))";
+        let error = read_wkt(sloppy.as_bytes()).unwrap_err();
+        assert_eq!(
+            error.downcast::<WktError>().unwrap().message,
+            "invalid number `1D0`"
+        );
+
+        let mut warnings = vec![];
+        let geometry = read_wkt_with_warnings(sloppy.as_bytes(), true, &mut warnings).unwrap();
+        assert_eq!(
+            geometry,
+            Geometry::<f64>::try_from_wkt_str(
+                "MULTIPOLYGON(((0 0,1 0,1 1,0 0)),((2 2,3 2,3 3,2 2)))"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            warnings,
+            [
+                "closed 1 unclosed rings",
+                "dropped 2 stray commas",
+                "read 1 numbers with `D` exponents"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_wkt_error_spans() {
+        let error = |wkt: &str| {
+            let error = read_wkt(wkt.as_bytes()).unwrap_err();
+            let error = error.downcast::<WktError>().unwrap();
+            (error.span, error.message)
+        };
+        assert_eq!(
+            error("POINT(1 x)"),
+            (8..9, "expected a number, found `x`".to_string())
+        );
+        assert_eq!(
+            error("CIRCLE(1 2)"),
+            (0..6, "unknown geometry type `CIRCLE`".to_string())
+        );
+        assert_eq!(
+            error("POINT(1 2e)"),
+            (8..10, "invalid number `2e`".to_string())
+        );
+        assert_eq!(
+            error("POINT(1 2"),
+            (9..9, "expected `)`, found end of input".to_string())
+        );
+        assert_eq!(
+            error("POLYGON((0 0,1 0,1 1))"),
+            (
+                20..21,
+                "ring is not closed: it starts at (0 0) but ends at (1 1)".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_read_ewkt() {
+        let read = |ewkt: &str| read_ewkt_with_warnings(ewkt.as_bytes(), false, &mut vec![]);
+        assert_eq!(
+            read(" srid=3857;POINT(1 2)").unwrap(),
+            (Some(3857), Geometry::Point(Point::new(1.0, 2.0)))
+        );
+        assert_eq!(
+            read("POINT(1 2)").unwrap(),
+            (None, Geometry::Point(Point::new(1.0, 2.0)))
+        );
+        // Error spans are relative to the whole input, prefix included.
+        let error = read("SRID=4326;POINT(1 x)").unwrap_err();
+        assert_eq!(error.downcast::<WktError>().unwrap().span, 18..19);
+        assert!(read("SRID=4326 POINT(1 2)").is_err());
+        assert!(read("SRID=WGS84;POINT(1 2)").is_err());
+    }
+
+    #[test]
+    fn test_read_geometry_detects_geojson() {
+        let geojson = r#"
+            {"type": "Feature", "properties": {}, "geometry": {"type": "Point", "coordinates": [1, 2]}}
+        "#;
+        assert_eq!(
+            read_geometry(geojson.as_bytes()).unwrap(),
+            Geometry::Point(Point::new(1.0, 2.0))
+        );
+        assert_eq!(
+            read_geometry("  POINT(1 2)".as_bytes()).unwrap(),
+            Geometry::Point(Point::new(1.0, 2.0))
+        );
+    }
+}
@@ -0,0 +1,723 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use geo::{BoundingRect, Contains, GeodesicArea, Intersects, LineString, Point, Polygon};
+use geo_types::{Coord, Geometry};
+use itertools::Itertools;
+use s2::{
+    cap::Cap, cell::Cell, cellid::CellID, cellunion::CellUnion, latlng::LatLng, region::Region,
+};
+
+use crate::bins::{bin_points, Bin};
+use crate::geom::{
+    crosses_antimeridian, cut_polygons_by_partition, enclosed_pole, split_antimeridian,
+    split_geometry_antimeridian, BoundaryIndex,
+};
+use crate::validity::InvalidPolygon;
+
+/// The finest S2 cell level.
+pub const MAX_S2_LEVEL: u8 = 30;
+
+/**
+ * A cell level finer than S2 has, for which a covering would never finish.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidS2Level(pub u8);
+
+impl Display for InvalidS2Level {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "S2 level {} is not in 0..={}", self.0, MAX_S2_LEVEL)
+    }
+}
+
+impl Error for InvalidS2Level {}
+
+/**
+ * Checks that a level is one of S2's, so that the caller can handle it before covering.
+ */
+pub fn check_s2_level(level: u8) -> Result<u8, InvalidS2Level> {
+    match level <= MAX_S2_LEVEL {
+        true => Ok(level),
+        false => Err(InvalidS2Level(level)),
+    }
+}
+
+/**
+ * Computes an S2 cell covering of the given geometry, i.e. the cells at a level whose polygons
+ * intersect it in lng/lat.
+ */
+pub fn get_s2_covering(
+    geometry: &Geometry,
+    level: u8,
+    max_cells: usize,
+) -> Result<Vec<CellID>, InvalidS2Level> {
+    get_s2_covering_between(geometry, level, level, max_cells)
+}
+
+/**
+ * Computes a covering with cells from `min_level` to `max_level`, so that the parts of the geometry
+ * away from its edges are covered by fewer, coarser cells. Cells are coarsened further, down to
+ * `min_level`, to keep to `max_cells`.
+ */
+pub fn get_s2_covering_between(
+    geometry: &Geometry,
+    min_level: u8,
+    max_level: u8,
+    max_cells: usize,
+) -> Result<Vec<CellID>, InvalidS2Level> {
+    check_s2_level(max_level)?;
+    let region = GeometryRegion::new(geometry, max_level);
+    Ok(cover_region(&region, min_level, max_level, max_cells))
+}
+
+/**
+ * Computes an S2 cell covering of the given geometry by first computing a bounding box and then
+ * covering the bounding box. This is efficient but imprecise.
+ */
+pub fn get_s2_bbox_covering(
+    geometry: &Geometry,
+    level: u8,
+    max_cells: usize,
+) -> Result<Vec<CellID>, InvalidS2Level> {
+    get_s2_bbox_covering_between(geometry, level, level, max_cells)
+}
+
+/**
+ * Like `get_s2_covering_between`, but covering the bounding box of the geometry.
+ */
+pub fn get_s2_bbox_covering_between(
+    geometry: &Geometry,
+    min_level: u8,
+    max_level: u8,
+    max_cells: usize,
+) -> Result<Vec<CellID>, InvalidS2Level> {
+    check_s2_level(max_level)?;
+    let region = s2_bounding_rect(geometry);
+    Ok(cover_region(&region, min_level, max_level, max_cells))
+}
+
+fn cover_region(
+    region: &(impl Region + 'static),
+    min_level: u8,
+    max_level: u8,
+    max_cells: usize,
+) -> Vec<CellID> {
+    // A minimum level finer than the maximum is taken to be the maximum.
+    let rc = s2::region::RegionCoverer {
+        min_level: min_level.min(max_level),
+        max_level,
+        level_mod: 1,
+        max_cells,
+    };
+    rc.covering(region).0
+}
+
+/**
+ * Lazily produces the same cells as `get_s2_covering` without a cell limit, in sorted order.
+ */
+pub fn s2_covering_cells(
+    geometry: &Geometry,
+    level: u8,
+) -> Result<impl Iterator<Item = CellID>, InvalidS2Level> {
+    check_s2_level(level)?;
+    Ok(region_cells(GeometryRegion::new(geometry, level), level))
+}
+
+/**
+ * Lazily produces the same cells as `get_s2_bbox_covering` without a cell limit, in sorted order.
+ */
+pub fn s2_bbox_covering_cells(
+    geometry: &Geometry,
+    level: u8,
+) -> Result<impl Iterator<Item = CellID>, InvalidS2Level> {
+    check_s2_level(level)?;
+    Ok(region_cells(s2_bounding_rect(geometry), level))
+}
+
+/**
+ * Every cell at the level that intersects a region, in sorted order. Cells are found by descending
+ * from the cube faces into the children that intersect the region, so only the current path of the
+ * descent is held in memory.
+ */
+fn region_cells(region: impl Region, level: u8) -> impl Iterator<Item = CellID> {
+    let level = level as u64;
+
+    // Children are pushed in reverse so that cells are popped in increasing order.
+    let mut stack = (0..6).rev().map(CellID::from_face).collect_vec();
+    std::iter::from_fn(move || {
+        while let Some(cell) = stack.pop() {
+            if !region.intersects_cell(&Cell::from(cell)) {
+                continue;
+            }
+            if cell.level() >= level {
+                return Some(cell);
+            }
+            stack.extend(cell.child_iter().collect_vec().into_iter().rev());
+        }
+        None
+    })
+}
+
+/**
+ * A geometry as an S2 region, so that coverings follow its shape rather than its bounding box.
+ * Cells at the finest level of a covering belong to it if the polygon of their vertices intersects
+ * the geometry in lng/lat, as with the pieces that cells cut, while points belong to the one cell
+ * that S2 places them in. Coarser cells are tested by their bounding boxes, which hold all of their
+ * descendants, and are kept whole only when those lie within a polygon.
+ */
+struct GeometryRegion {
+    bound: s2::rect::Rect,
+    /// The finest level of the covering.
+    level: u8,
+    /// The leaf cells of the points, sorted.
+    points: Vec<CellID>,
+    polygons: Vec<(Polygon, geo::Rect, BoundaryIndex)>,
+    /// Lines, which are few enough not to need indexing.
+    others: Vec<(Geometry, geo::Rect)>,
+}
+
+impl GeometryRegion {
+    fn new(geometry: &Geometry, level: u8) -> Self {
+        let mut region = Self {
+            bound: s2_bounding_rect(geometry),
+            level,
+            points: vec![],
+            polygons: vec![],
+            others: vec![],
+        };
+        region.add(&split_geometry_antimeridian(geometry));
+        region.points.sort();
+        region
+    }
+
+    fn add(&mut self, geometry: &Geometry) {
+        match geometry {
+            Geometry::Point(point) => self.points.push(leaf_cell(point)),
+            Geometry::MultiPoint(points) => self.points.extend(points.iter().map(leaf_cell)),
+            Geometry::Polygon(polygon) => self.add_polygon(polygon.clone()),
+            Geometry::MultiPolygon(mpoly) => mpoly.iter().for_each(|p| self.add_polygon(p.clone())),
+            Geometry::Rect(rect) => self.add_polygon(rect.to_polygon()),
+            Geometry::Triangle(triangle) => self.add_polygon(triangle.to_polygon()),
+            Geometry::GeometryCollection(collection) => collection.iter().for_each(|g| self.add(g)),
+            geometry => {
+                if let Some(bbox) = geometry.bounding_rect() {
+                    self.others.push((geometry.clone(), bbox));
+                }
+            }
+        }
+    }
+
+    fn add_polygon(&mut self, polygon: Polygon) {
+        if let Some(bbox) = polygon.bounding_rect() {
+            let boundary = BoundaryIndex::new(&polygon);
+            self.polygons.push((polygon, bbox, boundary));
+        }
+    }
+
+    /**
+     * The lng/lat polygons that a cell is tested by, split on the antimeridian.
+     */
+    fn outline(&self, cell: &Cell) -> Vec<Polygon> {
+        match cell.level() >= self.level {
+            true => split_antimeridian(&s2_cell_to_poly(cell)),
+            false => lnglat_rects(&cell.rect_bound())
+                .into_iter()
+                .map(|rect| rect.to_polygon())
+                .collect(),
+        }
+    }
+}
+
+impl Region for GeometryRegion {
+    fn cap_bound(&self) -> Cap {
+        self.bound.cap_bound()
+    }
+
+    fn rect_bound(&self) -> s2::rect::Rect {
+        self.bound.clone()
+    }
+
+    fn contains_cell(&self, cell: &Cell) -> bool {
+        cell.level() < self.level
+            && self.outline(cell).iter().all(|part| {
+                let Some(bbox) = part.bounding_rect() else {
+                    return false;
+                };
+                self.polygons.iter().any(|(_, polygon_bbox, boundary)| {
+                    polygon_bbox.contains(&bbox) && boundary.contains_partition(part)
+                })
+            })
+    }
+
+    fn intersects_cell(&self, cell: &Cell) -> bool {
+        let first_point = self.points.partition_point(|&p| p < cell.id.range_min());
+        if self
+            .points
+            .get(first_point)
+            .is_some_and(|&p| p <= cell.id.range_max())
+        {
+            return true;
+        }
+        if !self.bound.intersects_cell(cell) {
+            return false;
+        }
+        self.outline(cell).iter().any(|part| {
+            let Some(bbox) = part.bounding_rect() else {
+                return false;
+            };
+            self.polygons
+                .iter()
+                .any(|(polygon, polygon_bbox, boundary)| {
+                    bbox.intersects(polygon_bbox) && boundary.intersects(polygon, part)
+                })
+                || self.others.iter().any(|(geometry, geometry_bbox)| {
+                    bbox.intersects(geometry_bbox) && geometry.intersects(part)
+                })
+        })
+    }
+}
+
+fn leaf_cell(point: &Point) -> CellID {
+    CellID::from(LatLng::from_degrees(point.y(), point.x()))
+}
+
+/**
+ * The lng/lat rects of an S2 rect, which is split in two if it crosses the antimeridian.
+ */
+fn lnglat_rects(rect: &s2::rect::Rect) -> Vec<geo::Rect> {
+    let (lat_lo, lat_hi) = (rect.lat.lo.to_degrees(), rect.lat.hi.to_degrees());
+    let (lng_lo, lng_hi) = (rect.lng.lo.to_degrees(), rect.lng.hi.to_degrees());
+    let lnglat_rect = |lng_lo, lng_hi| {
+        geo::Rect::new(
+            Coord {
+                x: lng_lo,
+                y: lat_lo,
+            },
+            Coord {
+                x: lng_hi,
+                y: lat_hi,
+            },
+        )
+    };
+    match rect.lng.is_inverted() {
+        true => vec![lnglat_rect(lng_lo, 180.0), lnglat_rect(-180.0, lng_hi)],
+        false => vec![lnglat_rect(lng_lo, lng_hi)],
+    }
+}
+
+/**
+ * Bounds a geometry by the union of the bounding boxes of its parts. Unions take the shorter way
+ * around, so geometries on both sides of the antimeridian, e.g. polygons split on it, are bounded
+ * across it rather than around the globe.
+ */
+fn s2_bounding_rect(geometry: &Geometry) -> s2::rect::Rect {
+    match geometry {
+        Geometry::Polygon(polygon) => s2_polygon_rect(polygon),
+        Geometry::MultiPolygon(mpoly) => union_rects(mpoly.iter().map(s2_polygon_rect)),
+        Geometry::GeometryCollection(collection) => {
+            union_rects(collection.iter().map(s2_bounding_rect))
+        }
+        _ => s2_rect(geometry.bounding_rect()),
+    }
+}
+
+fn s2_polygon_rect(polygon: &Polygon) -> s2::rect::Rect {
+    // The bounding boxes of polygons around a pole miss the pole, so they are bounded by the cap
+    // around the pole that reaches their farthest vertex instead. Polar caps are exactly the
+    // rects of all longitudes between a latitude and the pole.
+    if let Some(pole) = enclosed_pole(polygon) {
+        let farthest = polygon
+            .exterior()
+            .coords()
+            .map(|c| c.y)
+            .reduce(if pole > 0.0 { f64::min } else { f64::max })
+            .expect("rings around a pole have coordinates");
+        let (lat_lo, lat_hi) = if pole > 0.0 {
+            (farthest, pole)
+        } else {
+            (pole, farthest)
+        };
+        return s2::rect::Rect::from_degrees(lat_lo, -180.0, lat_hi, 180.0);
+    }
+    match crosses_antimeridian(polygon) {
+        true => union_rects(
+            split_antimeridian(polygon)
+                .iter()
+                .map(|part| s2_rect(part.bounding_rect())),
+        ),
+        false => s2_rect(polygon.bounding_rect()),
+    }
+}
+
+fn s2_rect(bbox: Option<geo::Rect>) -> s2::rect::Rect {
+    match bbox {
+        Some(bbox) => {
+            let pmin: Point = bbox.min().into();
+            let pmax: Point = bbox.max().into();
+            s2::rect::Rect::from_degrees(pmin.y(), pmin.x(), pmax.y(), pmax.x())
+        }
+        None => s2::rect::Rect::empty(),
+    }
+}
+
+fn union_rects(rects: impl Iterator<Item = s2::rect::Rect>) -> s2::rect::Rect {
+    rects.fold(s2::rect::Rect::empty(), |union, rect| union.union(&rect))
+}
+
+/**
+ * Counts the points within each S2 cell at a level, and sums their weights.
+ */
+pub fn bin_s2_points(
+    points: impl IntoIterator<Item = (Point, f64)>,
+    level: u8,
+) -> Result<BTreeMap<CellID, Bin>, Box<dyn Error>> {
+    bin_points(points, |point| -> Result<_, Box<dyn Error>> {
+        let cell = CellID::from(LatLng::from_degrees(point.y(), point.x()));
+        if !cell.is_valid() {
+            return Err(format!("invalid point {:?}", point).into());
+        }
+        Ok(cell.parent(level as u64))
+    })
+}
+
+/**
+ * Creates a polygon from the vertices of an S2 cell.
+ */
+pub fn s2_cell_to_poly(cell: &Cell) -> Polygon {
+    let vertices: [Coord; 4] = cell.vertices().map(LatLng::from).map(|c| Coord {
+        x: c.lng.deg(),
+        y: c.lat.deg(),
+    });
+    // The ring is allocated at its closed size, so that closing it doesn't reallocate.
+    let mut ring = Vec::with_capacity(vertices.len() + 1);
+    ring.extend(vertices);
+    ring.push(vertices[0]);
+    Polygon::new(LineString::new(ring), vec![])
+}
+
+/**
+ * Creates polygons from the vertices of S2 cells as they are consumed. Nothing is allocated per cell
+ * beyond the polygon itself, so this is the conversion to use for large cell lists.
+ */
+pub fn s2_cells_to_polys(cells: impl IntoIterator<Item = CellID>) -> impl Iterator<Item = Polygon> {
+    cells
+        .into_iter()
+        .map(|cell_id| s2_cell_to_poly(&Cell::from(cell_id)))
+}
+
+/**
+ * Cuts a region using S2 cells. Each returned geometry in the collection will be a partition of
+ * the geometry bounded to a passed in S2 cell. With `normalize_winding`, the polygon's rings are
+ * oriented as the intersection expects first.
+ */
+pub fn cut_region(
+    polygon: &Polygon,
+    s2_cells: &[Cell],
+    normalize_winding: bool,
+) -> Result<Vec<Polygon>, InvalidPolygon> {
+    let pieces = cut_region_by_cell(polygon, s2_cells, normalize_winding)?;
+    Ok(pieces.into_iter().map(|(_, piece)| piece).collect())
+}
+
+/**
+ * Like `cut_region`, but with the ID of the cell that each partition lies within.
+ */
+pub fn cut_region_by_cell(
+    polygon: &Polygon,
+    s2_cells: &[Cell],
+    normalize_winding: bool,
+) -> Result<Vec<(CellID, Polygon)>, InvalidPolygon> {
+    let partitions = s2_cells.iter().map(s2_cell_to_poly).collect_vec();
+    let pieces = cut_polygons_by_partition(
+        std::slice::from_ref(polygon),
+        &partitions,
+        normalize_winding,
+    )?;
+    Ok(pieces
+        .into_iter()
+        .map(|(i, piece)| (s2_cells[i].id, piece))
+        .collect())
+}
+
+/**
+ * Merges S2 cells into a sorted list without duplicates or cells contained by another cell. If
+ * `compact` is set, complete sets of four sibling cells are then replaced by their parent until no
+ * more can be.
+ */
+pub fn merge_s2_cells(cells: impl IntoIterator<Item = CellID>, compact: bool) -> Vec<CellID> {
+    let mut cells = cells.into_iter().collect_vec();
+    if compact {
+        let mut union = CellUnion(cells);
+        union.normalize();
+        return union.0;
+    }
+
+    // Ancestors come before their descendants in order of leaf ranges, so only the last kept cell
+    // can contain the next one.
+    cells.sort_by_key(|cell| (cell.range_min(), cell.level()));
+    let mut merged: Vec<CellID> = Vec::with_capacity(cells.len());
+    for cell in cells {
+        if !merged.last().is_some_and(|last| last.contains(&cell)) {
+            merged.push(cell);
+        }
+    }
+    merged.sort();
+    merged
+}
+
+/**
+ * Converts S2 cells to a sorted list of unique cells at one level. Finer cells are replaced by
+ * their ancestor and coarser cells by their descendants.
+ */
+pub fn s2_cells_at_level(cells: impl IntoIterator<Item = CellID>, level: u8) -> Vec<CellID> {
+    let level = level as u64;
+    cells
+        .into_iter()
+        .flat_map(|cell| {
+            if cell.level() >= level {
+                vec![cell.parent(level)]
+            } else {
+                cell.child_iter_at_level(level).collect_vec()
+            }
+        })
+        .sorted()
+        .dedup()
+        .collect_vec()
+}
+
+/**
+ * How closely a covering fits its geometry. Areas are geodesic on the WGS84 ellipsoid, with each
+ * cell measured as the polygon of its vertices, so that cells and geometries are measured alike.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CoveringStats {
+    pub cells: usize,
+    pub covered_area_m2: f64,
+    pub geometry_area_m2: f64,
+}
+
+impl CoveringStats {
+    /**
+     * Measures a covering of a geometry. The cells are assumed not to overlap, as in the coverings
+     * produced by this module.
+     */
+    pub fn new(geometry: &Geometry, cells: impl IntoIterator<Item = CellID>) -> Self {
+        let (cells, covered_area_m2) = s2_cells_to_polys(cells)
+            .fold((0, 0.0), |(n, area), cell| {
+                (n + 1, area + cell.geodesic_area_unsigned())
+            });
+        Self {
+            cells,
+            covered_area_m2,
+            geometry_area_m2: geometry.geodesic_area_unsigned(),
+        }
+    }
+
+    /**
+     * The covered area as a multiple of the geometry's area, where 1 is a perfect fit. `None` for
+     * geometries without area, like points and lines.
+     */
+    pub fn over_coverage(&self) -> Option<f64> {
+        (self.geometry_area_m2 > 0.0).then(|| self.covered_area_m2 / self.geometry_area_m2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, Geometry};
+    use itertools::Itertools;
+    use s2::cell::Cell;
+    use s2::cellid::CellID;
+    use s2::latlng::LatLng;
+    use wkt::TryFromWkt;
+
+    use super::{
+        bin_s2_points, cut_region_by_cell, get_s2_bbox_covering, get_s2_bbox_covering_between,
+        get_s2_covering, get_s2_covering_between, merge_s2_cells, s2_bbox_covering_cells,
+        s2_cell_to_poly, s2_cells_at_level, s2_covering_cells, CoveringStats, InvalidS2Level,
+    };
+
+    #[test]
+    fn test_s2_covering_across_antimeridian() {
+        // The covering wraps across the antimeridian, like that of the same polygon at 0°.
+        let crossing = Geometry::<f64>::try_from_wkt_str(
+            "POLYGON((179 -17,-179 -17,-179 -16,179 -16,179 -17))",
+        )
+        .unwrap();
+        let centered =
+            Geometry::<f64>::try_from_wkt_str("POLYGON((-1 -17,1 -17,1 -16,-1 -16,-1 -17))")
+                .unwrap();
+        assert_eq!(
+            get_s2_covering(&crossing, 8, usize::MAX).unwrap().len(),
+            get_s2_covering(&centered, 8, usize::MAX).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_s2_covering_around_poles() {
+        // A ring around a pole covers the same cells as the polygon drawn through the pole.
+        for (around, through) in [
+            (
+                "POLYGON((-180 -70,-90 -70,0 -70,90 -70,180 -70,-180 -70))",
+                "POLYGON((-180 -70,180 -70,180 -90,-180 -90,-180 -70))",
+            ),
+            (
+                "POLYGON((0 80,90 80,180 80,-90 80,0 80))",
+                "POLYGON((-180 80,180 80,180 90,-180 90,-180 80))",
+            ),
+        ] {
+            let around = Geometry::<f64>::try_from_wkt_str(around).unwrap();
+            let through = Geometry::<f64>::try_from_wkt_str(through).unwrap();
+            for level in [2, 5] {
+                assert_eq!(
+                    get_s2_covering(&around, level, usize::MAX).unwrap(),
+                    get_s2_covering(&through, level, usize::MAX).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_s2_covering_cells() {
+        for (wkt, levels) in [
+            (
+                "POLYGON((-122.45 37.75,-122.40 37.75,-122.40 37.80,-122.45 37.80,-122.45 37.75))",
+                [0, 5, 14],
+            ),
+            ("LINESTRING(179.9 -10,-179.9 10)", [0, 2, 5]),
+            ("POINT(0 90)", [0, 5, 14]),
+            (
+                "POLYGON((179 -17,-179 -17,-179 -16,179 -16,179 -17))",
+                [0, 5, 10],
+            ),
+        ] {
+            let geometry = Geometry::<f64>::try_from_wkt_str(wkt).unwrap();
+            for level in levels {
+                // Coverings are in ascending order of cell ID, so that outputs can be diffed.
+                let cells = s2_covering_cells(&geometry, level).unwrap().collect_vec();
+                assert!(cells.windows(2).all(|pair| pair[0] < pair[1]), "{}", wkt);
+                assert_eq!(
+                    cells,
+                    get_s2_covering(&geometry, level, usize::MAX).unwrap()
+                );
+                assert_eq!(
+                    s2_bbox_covering_cells(&geometry, level)
+                        .unwrap()
+                        .collect_vec(),
+                    get_s2_bbox_covering(&geometry, level, usize::MAX).unwrap()
+                );
+            }
+        }
+
+        // Levels finer than leaf cells are rejected rather than covered forever.
+        let point = Geometry::<f64>::try_from_wkt_str("POINT(1 1)").unwrap();
+        assert_eq!(
+            get_s2_covering(&point, 31, usize::MAX),
+            Err(InvalidS2Level(31))
+        );
+        assert!(s2_covering_cells(&point, 31).is_err());
+        assert!(get_s2_bbox_covering_between(&point, 0, 40, 8).is_err());
+    }
+
+    #[test]
+    fn test_s2_covering_follows_shape() {
+        let diagonal =
+            Geometry::<f64>::try_from_wkt_str("POLYGON((0 0,0.1 0,1 0.9,1 1,0.9 1,0 0.1,0 0))")
+                .unwrap();
+        let Geometry::Polygon(polygon) = &diagonal else {
+            unreachable!()
+        };
+        let exact = get_s2_covering(&diagonal, 10, usize::MAX).unwrap();
+        let bbox = get_s2_bbox_covering(&diagonal, 10, usize::MAX).unwrap();
+        assert!(exact.len() * 3 < bbox.len());
+
+        // Every cell that cuts a piece from the polygon is kept.
+        let cells = bbox.iter().map(|&cell| Cell::from(cell)).collect_vec();
+        for (cell, _) in cut_region_by_cell(polygon, &cells, true).unwrap() {
+            assert!(exact.contains(&cell));
+        }
+    }
+
+    #[test]
+    fn test_s2_covering_between() {
+        let geometry = Geometry::<f64>::try_from_wkt_str(
+            "POLYGON((-106 32,-94 32,-94 36,-100 36,-100 37,-103 37,-103 32,-106 32))",
+        )
+        .unwrap();
+        let single = get_s2_covering(&geometry, 9, usize::MAX).unwrap();
+        let mixed = get_s2_covering_between(&geometry, 4, 9, usize::MAX).unwrap();
+        assert!(mixed.len() * 5 < single.len());
+        assert!(mixed.iter().all(|cell| (4..=9).contains(&cell.level())));
+
+        // The coarser cells hold exactly the cells of the single-level covering.
+        assert_eq!(s2_cells_at_level(mixed, 9), single);
+
+        let limited = get_s2_covering_between(&geometry, 2, 9, 20).unwrap();
+        assert!(limited.len() <= 20);
+    }
+
+    #[test]
+    fn test_covering_stats() {
+        let cell = CellID::from_face(2).child_begin_at_level(10);
+        let polygon = Geometry::from(s2_cell_to_poly(&Cell::from(cell)));
+        let stats = CoveringStats::new(&polygon, [cell]);
+        assert_eq!(stats.cells, 1);
+        assert_eq!(stats.over_coverage(), Some(1.0));
+
+        // Finer coverings fit more closely.
+        let sf = Geometry::<f64>::try_from_wkt_str(
+            "POLYGON((-122.45 37.75,-122.40 37.75,-122.40 37.80,-122.45 37.80,-122.45 37.75))",
+        )
+        .unwrap();
+        let over_coverage = |level| {
+            CoveringStats::new(&sf, s2_covering_cells(&sf, level).unwrap())
+                .over_coverage()
+                .unwrap()
+        };
+        assert!(over_coverage(10) > over_coverage(14));
+        assert!(over_coverage(14) > 1.0);
+
+        let point = Geometry::<f64>::try_from_wkt_str("POINT(1 1)").unwrap();
+        assert_eq!(
+            CoveringStats::new(&point, s2_covering_cells(&point, 5).unwrap()).over_coverage(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_merge_s2_cells() {
+        let parent = CellID::from_face(2).child_begin_at_level(10);
+        let children = parent.child_iter_at_level(11).collect::<Vec<_>>();
+        let grandchild = children[3].child_begin_at_level(12);
+        let other = parent.next();
+
+        // Duplicates and descendants of other cells are dropped.
+        let cells = vec![grandchild, children[3], other, children[0], children[3]];
+        let mut expected = vec![children[0], children[3], other];
+        expected.sort();
+        assert_eq!(merge_s2_cells(cells, false), expected);
+
+        // Completing the siblings compacts them into their parent.
+        let cells = children.iter().copied().chain([grandchild, other]);
+        assert_eq!(merge_s2_cells(cells.clone(), false).len(), 5);
+        assert_eq!(merge_s2_cells(cells, true), vec![parent, other]);
+
+        assert_eq!(s2_cells_at_level([parent, grandchild], 11), children);
+    }
+
+    #[test]
+    fn test_bin_s2_points() {
+        let points = [
+            (point!(x: -122.4194, y: 37.7749), 12.5),
+            (point!(x: -122.4180, y: 37.7760), 21.0),
+            (point!(x: -122.2711, y: 37.8044), 30.25),
+        ];
+        let bins = bin_s2_points(points, 12).unwrap();
+        assert_eq!(bins.len(), 2);
+        let (cell, bin) = bins.iter().find(|(_, bin)| bin.count == 2).unwrap();
+        assert_eq!(cell.level(), 12);
+        assert_eq!(bin.weight, 33.5);
+        assert!(cell.contains(&CellID::from(LatLng::from_degrees(37.7749, -122.4194))));
+    }
+}
@@ -0,0 +1,462 @@
+use std::error::Error;
+use std::io::{self, Read, Write};
+
+use geo::{Area, CoordsIter, TriangulateEarcut};
+use geo_types::{Coord, Point, Polygon, Triangle};
+use rand::distributions::{Distribution, Uniform};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::nvec::NVec;
+use crate::validity::{check_area, DegeneratePolygon};
+
+const MIN_LAT: f64 = -90.0;
+const MAX_LAT: f64 = 90.0;
+const MIN_LNG: f64 = -180.0;
+const MAX_LNG: f64 = 180.0;
+
+/** Leading bytes of a serialized `PolygonalSampler`, including a format version. */
+const SAMPLER_MAGIC: &[u8; 8] = b"GEOSSMP1";
+
+/**
+ * Creates the random number generator used by every seeded command. ChaCha12 is named explicitly,
+ * rather than using `StdRng`, whose algorithm may change between releases of `rand`, so that a seed
+ * produces the same samples on every platform and in every build.
+ */
+pub fn create_rng(seed: u64) -> ChaCha12Rng {
+    ChaCha12Rng::seed_from_u64(seed)
+}
+
+/**
+ * Samples an index in `0..len`. Sampling a `usize` range directly draws 32 or 64 bits depending on
+ * the platform, so the index is drawn as a `u64` to keep seeded results the same everywhere.
+ */
+pub(crate) fn gen_index<R: Rng>(rng: &mut R, len: usize) -> usize {
+    rng.gen_range(0..len as u64) as usize
+}
+
+pub trait GeoSampler<R> {
+    fn sample_coord(&self, rng: &mut R) -> Coord;
+}
+
+pub struct UniformSampler;
+impl<R: Rng> GeoSampler<R> for UniformSampler {
+    fn sample_coord(&self, rng: &mut R) -> Coord {
+        let dist_lat = Uniform::new(MIN_LAT, MAX_LAT);
+        let dist_lng = Uniform::new(MIN_LNG, MAX_LNG);
+        Coord {
+            x: dist_lng.sample(rng),
+            y: dist_lat.sample(rng),
+        }
+    }
+}
+
+/**
+ * GeoSampler uniformly samples random coordinates within a polygonal geometry. Each GeoSampler
+ * instance is tied to a specific polygon, which allows for more efficient repeated sampling calls.
+ *
+ * The underlying sampling algorithm is:
+ * 1. Triangulate the polygon.
+ * 2. Select a random triangle (with probability poroportional to the triangle's area).
+ * 3. Sample a random point within the triangle.
+ *
+ * Triangulation dominates the cost of sampling few points from a large polygon, so samplers can be
+ * saved with `write` and loaded again with `read`.
+ */
+pub struct PolygonalSampler {
+    triangulation: Vec<Triangle>,
+    alias_table: AliasTable,
+    unit: Uniform<f64>,
+    fingerprint: u64,
+}
+impl<R: Rng> GeoSampler<R> for PolygonalSampler {
+    fn sample_coord(&self, rng: &mut R) -> Coord {
+        // Select a triangle with probability proportional to its area.
+        let triangle = self.triangulation[self.alias_table.sample(rng)];
+        sample_point_in_triangle(rng, &self.unit, triangle).into()
+    }
+}
+impl PolygonalSampler {
+    /**
+     * Triangulates a polygon to sample from. Polygons without area are rejected, since there is
+     * nothing to sample and ear clipping panics on some of them.
+     */
+    pub fn new(polygon: Polygon) -> Result<Self, DegeneratePolygon> {
+        check_area(&polygon)?;
+        // Triangles are rebuilt from their vertices in the same way as `read`, which normalizes
+        // their winding, so that saved samplers draw exactly the same points.
+        let triangulation: Vec<Triangle> = polygon
+            .earcut_triangles_iter()
+            .map(|triangle| Triangle::from(triangle.to_array()))
+            .collect();
+        let areas: Vec<f64> = triangulation.iter().map(|t| t.unsigned_area()).collect();
+        // Ear clipping may leave no triangles, or only flat ones, for a polygon whose area is
+        // rounding error.
+        if areas.iter().sum::<f64>() <= 0.0 {
+            return Err(DegeneratePolygon);
+        }
+        Ok(Self::from_parts(
+            triangulation,
+            AliasTable::new(&areas),
+            fingerprint(&polygon),
+        ))
+    }
+
+    fn from_parts(triangulation: Vec<Triangle>, alias_table: AliasTable, fingerprint: u64) -> Self {
+        Self {
+            triangulation,
+            alias_table,
+            unit: Uniform::new_inclusive(0.0, 1.0),
+            fingerprint,
+        }
+    }
+
+    /**
+     * Whether this sampler was built from the given polygon, so that a saved sampler can be reused
+     * only while its polygon is unchanged.
+     */
+    pub fn is_for(&self, polygon: &Polygon) -> bool {
+        self.fingerprint == fingerprint(polygon)
+    }
+
+    /**
+     * Serializes the triangulation and its sampling table in a compact little-endian format.
+     */
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(SAMPLER_MAGIC)?;
+        w.write_all(&self.fingerprint.to_le_bytes())?;
+        w.write_all(&(self.triangulation.len() as u64).to_le_bytes())?;
+        for (i, triangle) in self.triangulation.iter().enumerate() {
+            for c in triangle.to_array() {
+                w.write_all(&c.x.to_le_bytes())?;
+                w.write_all(&c.y.to_le_bytes())?;
+            }
+            w.write_all(&self.alias_table.probs[i].to_le_bytes())?;
+            w.write_all(&(self.alias_table.aliases[i] as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Deserializes a sampler written by `write`.
+     */
+    pub fn read(r: &mut impl Read) -> Result<Self, Box<dyn Error>> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != SAMPLER_MAGIC {
+            return Err("not a polygon sampler file".into());
+        }
+        let fingerprint = read_u64(r)?;
+        let len = read_u64(r)? as usize;
+        if len == 0 {
+            return Err("corrupt polygon sampler file".into());
+        }
+
+        let mut triangulation = Vec::with_capacity(len);
+        let mut alias_table = AliasTable {
+            probs: Vec::with_capacity(len),
+            aliases: Vec::with_capacity(len),
+        };
+        for _ in 0..len {
+            let mut coords = [Coord::zero(); 3];
+            for c in coords.iter_mut() {
+                c.x = read_f64(r)?;
+                c.y = read_f64(r)?;
+            }
+            triangulation.push(Triangle::from(coords));
+            alias_table.probs.push(read_f64(r)?);
+            let alias = read_u64(r)? as usize;
+            if alias >= len {
+                return Err("corrupt polygon sampler file".into());
+            }
+            alias_table.aliases.push(alias);
+        }
+        Ok(Self::from_parts(triangulation, alias_table, fingerprint))
+    }
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f64(r: &mut impl Read) -> io::Result<f64> {
+    read_u64(r).map(f64::from_bits)
+}
+
+/**
+ * A stable FNV-1a hash of a polygon's coordinates.
+ */
+fn fingerprint(polygon: &Polygon) -> u64 {
+    polygon
+        .exterior_coords_iter()
+        .chain(
+            polygon
+                .interiors()
+                .iter()
+                .flat_map(|ring| ring.coords_iter()),
+        )
+        .flat_map(|c| [c.x.to_bits(), c.y.to_bits()])
+        .flat_map(u64::to_le_bytes)
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/**
+ * Walker's alias method for selecting indices with probability proportional to their weights in
+ * constant time. Weights are kept in full precision so that very small triangles are still
+ * sampled.
+ */
+struct AliasTable {
+    probs: Vec<f64>,
+    aliases: Vec<usize>,
+}
+impl AliasTable {
+    fn new(weights: &[f64]) -> Self {
+        let n = weights.len();
+        let total: f64 = weights.iter().sum();
+        let mut probs = vec![1.0; n];
+        let mut aliases: Vec<usize> = (0..n).collect();
+        if total <= 0.0 {
+            return Self { probs, aliases };
+        }
+
+        // Scale the weights so that their mean is 1, then pair each underfull index with an
+        // overfull one that tops it up.
+        let mut scaled: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| scaled[i] < 1.0);
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            probs[s] = scaled[s];
+            aliases[s] = l;
+            scaled[l] -= 1.0 - scaled[s];
+            if scaled[l] < 1.0 {
+                small.push(large.pop().expect("large is non-empty"));
+            }
+        }
+        Self { probs, aliases }
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        let i = gen_index(rng, self.probs.len());
+        if rng.gen::<f64>() < self.probs[i] {
+            i
+        } else {
+            self.aliases[i]
+        }
+    }
+}
+
+/** Uniformly samples coordinates within a triangular region on the Earth's surface. */
+fn sample_point_in_triangle<R: Rng>(rng: &mut R, unit: &Uniform<f64>, triangle: Triangle) -> Point {
+    let r1_sqrt = f64::sqrt(unit.sample(rng));
+    let r2 = unit.sample(rng);
+
+    // Randomly select a starting triangle vertex. Call this vertex `a`.
+    let vertices = triangle.to_array();
+    let na: NVec = vertices[0].into();
+    let nb: NVec = vertices[1].into();
+    let nc: NVec = vertices[2].into();
+
+    let c: Coord = ((1.0 - r1_sqrt) * na + r1_sqrt * (1.0 - r2) * nb + r2 * r1_sqrt * nc).into();
+    c.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use geo::{Area, HaversineDistance};
+    use geo_types::{Geometry, Point, Polygon};
+    use h3o::geom::ContainmentMode;
+    use h3o::{LatLng as H3LatLng, Resolution};
+    use itertools::Itertools;
+    use rand::Rng;
+    use s2::{cell::Cell, cellid::CellID, latlng::LatLng};
+    use statrs::distribution::{ChiSquared, ContinuousCDF};
+    use wkt::TryFromWkt;
+
+    use crate::{
+        h3_cells::get_h3_covering,
+        s2_cells::{cut_region, get_s2_covering},
+        samplers::{create_rng, AliasTable, GeoSampler, PolygonalSampler, UniformSampler},
+        validity::DegeneratePolygon,
+    };
+
+    const TEST_SEED: u64 = 0;
+    const RNG_FIRST_U64: u64 = 13486662071293341567;
+
+    #[test]
+    fn test_create_rng_is_stable() {
+        // Seeded outputs are compared across platforms and builds, so the generator must never
+        // change.
+        let mut rng = create_rng(TEST_SEED);
+        assert_eq!(rng.gen::<u64>(), RNG_FIRST_U64);
+    }
+
+    #[test]
+    fn test_alias_table_keeps_small_weights() {
+        let table = AliasTable::new(&[1e-4, 1.0, 3.0]);
+        let mut rng = create_rng(TEST_SEED);
+        let mut counts = [0usize; 3];
+        (0..400_000).for_each(|_| counts[table.sample(&mut rng)] += 1);
+
+        // f32 weights rounded to 1e-4 would never select the first index.
+        assert!(counts[0] > 0);
+        assert!((counts[2] as f64 / counts[1] as f64 - 3.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_uniform_sampler_axes() {
+        let mut rng = create_rng(TEST_SEED);
+        let points = (0..1000)
+            .map(|_| Point::from(UniformSampler.sample_coord(&mut rng)))
+            .collect_vec();
+        assert!(points
+            .iter()
+            .all(|p| p.x().abs() <= 180.0 && p.y().abs() <= 90.0));
+        assert!(points.iter().any(|p| p.x().abs() > 90.0));
+
+        // Each point lies within its H3 cell, so it is close to the cell's center.
+        for point in points {
+            let cells = get_h3_covering(
+                &Geometry::from(point),
+                Resolution::Five,
+                ContainmentMode::IntersectsBoundary,
+                true,
+            )
+            .unwrap();
+            let center = H3LatLng::from(cells[0]);
+            let center = Point::new(center.lng(), center.lat());
+            assert!(point.haversine_distance(&center) < 20_000.0);
+        }
+    }
+
+    #[test]
+    fn test_sampler_roundtrip() {
+        let polygon: Polygon =
+            Polygon::try_from_wkt_str("POLYGON((0 0,4 0,4 1,1 1,1 4,0 4,0 0))").unwrap();
+        let sampler = PolygonalSampler::new(polygon.clone()).unwrap();
+        let mut bytes = vec![];
+        sampler.write(&mut bytes).unwrap();
+        let loaded = PolygonalSampler::read(&mut bytes.as_slice()).unwrap();
+
+        assert!(loaded.is_for(&polygon));
+        let (mut rng_a, mut rng_b) = (create_rng(TEST_SEED), create_rng(TEST_SEED));
+        for _ in 0..100 {
+            assert_eq!(
+                sampler.sample_coord(&mut rng_a),
+                loaded.sample_coord(&mut rng_b)
+            );
+        }
+    }
+
+    #[test]
+    fn test_sampler_rejects_polygons_without_area() {
+        for wkt in ["POLYGON EMPTY", "POLYGON((0 0,1 1,2 2,0 0))"] {
+            let polygon = Polygon::try_from_wkt_str(wkt).unwrap();
+            assert_eq!(
+                PolygonalSampler::new(polygon).err(),
+                Some(DegeneratePolygon),
+                "{}",
+                wkt
+            );
+        }
+
+        // Nor can a saved sampler have no triangles to sample.
+        let mut bytes = b"GEOSSMP1".to_vec();
+        bytes.extend([0; 16]);
+        assert!(PolygonalSampler::read(&mut bytes.as_slice()).is_err());
+    }
+
+    /**
+     * This test performs a chi squared fitness test for the polygon sampler. The implementation
+     * indicates lack of uniformity; it may be necessary to tune the algorithm and/or fitness test
+     * if true uniformity is required. Until then, the sampler should only be used in scenarios
+     * where approximate uniformity is acceptable.
+     *
+     * This code is messy and not intended for anyone but me to read; I'm fine with that for now.
+     */
+    #[ignore]
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn test_uniformity() {
+        const WKT_STR: &str = "POLYGON ((-109.950142 38.19799, -109.888687 38.236292, -109.807663 38.157237, -109.929199 38.146438, -109.950142 38.19799))";
+
+        let geometry = Geometry::<f64>::try_from_wkt_str(WKT_STR).unwrap();
+        let sampler = PolygonalSampler::new(geometry.clone().try_into().unwrap()).unwrap();
+
+        let level: u8 = 13;
+        let s2_cover = get_s2_covering(&geometry, level, usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(Cell::from)
+            .collect_vec();
+
+        //let mut bin_areas: HashMap<u64, f64> = HashMap::new();
+        //s2_cover.iter().for_each(|c| {
+        //    bin_areas.insert(c.id.0, s2_cell_to_poly(c).unsigned_area());
+        //});
+        //let bin_areas = bin_areas;
+
+        let polygon: Polygon = geometry.try_into().unwrap();
+        let mut cut_areas: HashMap<u64, f64> = HashMap::new();
+        s2_cover.iter().for_each(|c| {
+            let cuts = cut_region(&polygon, std::slice::from_ref(c), true).unwrap();
+            if !cuts.is_empty() {
+                let area = cuts[0].unsigned_area();
+                cut_areas.insert(c.id.0, area);
+            }
+        });
+        let cut_areas = cut_areas;
+
+        let polygon_area = polygon.unsigned_area();
+        println!("polygon_area {}", polygon_area);
+        let mut bin_weights: HashMap<u64, f64> = HashMap::new();
+        s2_cover.iter().map(|c| c.id.0).for_each(|id| {
+            let ca = cut_areas.get(&id).unwrap_or(&0.0);
+            bin_weights.insert(id, ca / polygon_area);
+        });
+        let bin_weights = bin_weights;
+
+        //let mut s = 0.0;
+        //for (id, bin_weight) in &bin_weights {
+        //    s += bin_weight;
+        //}
+        //println!("bin_weights prob sum: {}", s);
+
+        let mut bin_counts: HashMap<u64, u64> = HashMap::new();
+        let bin_count = |id: CellID| *bin_counts.entry(id.0).or_default() += 1;
+
+        let mut rng = create_rng(TEST_SEED);
+        let num_samples = 1024;
+        (0..num_samples)
+            .map(|_| sampler.sample_coord(&mut rng))
+            .map(|c| CellID::from(LatLng::from_degrees(c.y, c.x)))
+            .map(|c| c.parent(level as u64))
+            .for_each(bin_count);
+        let bin_counts = bin_counts;
+
+        let mut errors: Vec<f64> = vec![];
+        for (id, bin_count) in &bin_counts {
+            let weight = bin_weights.get(id).unwrap();
+            let expected = weight * (num_samples as f64);
+            let error = ((*bin_count as f64) - expected).powi(2) / expected;
+            errors.push(error);
+        }
+
+        let degrees_of_freedom = (bin_counts.len() - 1) as f64;
+        let chisq = ChiSquared::new(degrees_of_freedom).unwrap();
+
+        let sum: f64 = errors.iter().sum();
+        let pval = chisq.cdf(sum);
+
+        println!("sum: {}, pval: {}", sum, pval);
+
+        // p-value must be small enough to reject the null-hypothesis.
+        assert!(pval < 0.05);
+        assert!(false);
+    }
+}
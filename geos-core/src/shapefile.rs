@@ -0,0 +1,385 @@
+/*!
+ * Writing of geometries as ESRI shapefiles, the `.shp`, `.shx`, and `.dbf` triple that legacy GIS
+ * software reads.
+ */
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate};
+use geo::{BoundingRect, CoordsIter, Winding};
+use geo_types::{Coord, Geometry, LineString, Polygon, Rect};
+use itertools::Itertools;
+
+/// The magic number that starts the `.shp` and `.shx` files.
+const FILE_CODE: i32 = 9994;
+const VERSION: i32 = 1000;
+const HEADER_BYTES: usize = 100;
+
+/// The width of the numeric `id` attribute in the `.dbf` file.
+const ID_WIDTH: usize = 10;
+
+/// The contents of the `.prj` file of a shapefile in lng/lat, which gives its CRS.
+pub const WGS84_PRJ: &str = r#"GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]]"#;
+
+/**
+ * The type of the shapes in a shapefile, which all records share. Empty geometries are written as
+ * null shapes.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeType {
+    Null = 0,
+    Point = 1,
+    PolyLine = 3,
+    Polygon = 5,
+    MultiPoint = 8,
+}
+
+/**
+ * The kinds of geometry that a shapefile can mix: multi-part shapes hold any number of points,
+ * lines, or polygons, but only one of them.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Points,
+    Lines,
+    Polygons,
+}
+
+/**
+ * The contents of the three files of a shapefile.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shapefile {
+    pub shape_type: ShapeType,
+    /// The shapes.
+    pub shp: Vec<u8>,
+    /// The offsets of the shapes in the `.shp` file.
+    pub shx: Vec<u8>,
+    /// The attributes of the shapes, which are only their `id`s, numbered from 1.
+    pub dbf: Vec<u8>,
+}
+
+impl Shapefile {
+    /**
+     * Encodes each geometry as a record. The geometries must all be points, all lines, or all
+     * polygons, as single or multi-part geometries or collections of them. Polygon exteriors are
+     * wound clockwise and holes counterclockwise, as shapefiles require. `updated` is the date
+     * recorded in the `.dbf` file.
+     */
+    pub fn new(geometries: &[Geometry], updated: NaiveDate) -> Result<Self, Box<dyn Error>> {
+        let kinds: Vec<Option<Kind>> = geometries.iter().map(kind).try_collect()?;
+        let kind = kinds
+            .iter()
+            .flatten()
+            .dedup()
+            .at_most_one()
+            .map_err(|_| "a shapefile can only hold one of points, lines, or polygons")?;
+        let shape_type = match kind {
+            None => ShapeType::Null,
+            Some(Kind::Points) if geometries.iter().all(|g| matches!(g, Geometry::Point(_))) => {
+                ShapeType::Point
+            }
+            Some(Kind::Points) => ShapeType::MultiPoint,
+            Some(Kind::Lines) => ShapeType::PolyLine,
+            Some(Kind::Polygons) => ShapeType::Polygon,
+        };
+
+        let records = geometries
+            .iter()
+            .map(|g| encode_shape(g, shape_type))
+            .collect_vec();
+        let bbox = geometries
+            .iter()
+            .filter_map(|g| g.bounding_rect())
+            .reduce(|a, b| union(&a, &b));
+
+        let shp_bytes = HEADER_BYTES + records.iter().map(|r| 8 + r.len()).sum::<usize>();
+        let mut shp = header(shape_type, bbox, shp_bytes);
+        let mut shx = header(shape_type, bbox, HEADER_BYTES + 8 * records.len());
+        for (i, record) in records.iter().enumerate() {
+            shx.extend(((shp.len() / 2) as i32).to_be_bytes());
+            shx.extend(((record.len() / 2) as i32).to_be_bytes());
+            shp.extend((i as i32 + 1).to_be_bytes());
+            shp.extend(((record.len() / 2) as i32).to_be_bytes());
+            shp.extend(record);
+        }
+        Ok(Self {
+            shape_type,
+            shp,
+            shx,
+            dbf: encode_dbf(geometries.len(), updated),
+        })
+    }
+
+    /**
+     * Writes the files next to each other, as `path` with the extensions `.shp`, `.shx`, and `.dbf`.
+     */
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        for (extension, contents) in [("shp", &self.shp), ("shx", &self.shx), ("dbf", &self.dbf)] {
+            let path = path.with_extension(extension);
+            fs::write(&path, contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * The kind of a geometry, or `None` if it is empty and so has no kind.
+ */
+fn kind(geometry: &Geometry) -> Result<Option<Kind>, Box<dyn Error>> {
+    if geometry.coords_count() == 0 {
+        return Ok(None);
+    }
+    Ok(Some(match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => Kind::Points,
+        Geometry::Line(_) | Geometry::LineString(_) | Geometry::MultiLineString(_) => Kind::Lines,
+        Geometry::Polygon(_)
+        | Geometry::MultiPolygon(_)
+        | Geometry::Rect(_)
+        | Geometry::Triangle(_) => Kind::Polygons,
+        Geometry::GeometryCollection(collection) => {
+            let kinds: Vec<Option<Kind>> = collection.iter().map(kind).try_collect()?;
+            match kinds.into_iter().flatten().dedup().at_most_one() {
+                Ok(kind) => kind.expect("a non-empty collection has a non-empty member"),
+                Err(_) => return Err(
+                    "a shapefile cannot hold a GEOMETRYCOLLECTION of different kinds of geometry"
+                        .into(),
+                ),
+            }
+        }
+    }))
+}
+
+/**
+ * The points, lines, or rings of a geometry, flattened out of any collections. Polygon rings are
+ * wound as shapefiles require.
+ */
+fn parts(geometry: &Geometry, parts: &mut Vec<Vec<Coord>>) {
+    match geometry {
+        Geometry::Point(point) => parts.push(vec![point.0]),
+        Geometry::MultiPoint(points) => parts.extend(points.iter().map(|p| vec![p.0])),
+        Geometry::Line(line) => parts.push(vec![line.start, line.end]),
+        Geometry::LineString(line) => parts.push(line.0.clone()),
+        Geometry::MultiLineString(lines) => parts.extend(lines.iter().map(|l| l.0.clone())),
+        Geometry::Polygon(polygon) => polygon_parts(polygon, parts),
+        Geometry::MultiPolygon(polygons) => {
+            for polygon in polygons {
+                polygon_parts(polygon, parts);
+            }
+        }
+        Geometry::Rect(rect) => polygon_parts(&rect.to_polygon(), parts),
+        Geometry::Triangle(triangle) => polygon_parts(&triangle.to_polygon(), parts),
+        Geometry::GeometryCollection(collection) => {
+            for member in collection {
+                self::parts(member, parts);
+            }
+        }
+    }
+}
+
+fn polygon_parts(polygon: &Polygon, parts: &mut Vec<Vec<Coord>>) {
+    if polygon.exterior().0.is_empty() {
+        return;
+    }
+    let mut exterior = polygon.exterior().clone();
+    exterior.make_cw_winding();
+    parts.push(exterior.0);
+    for hole in polygon.interiors() {
+        let mut hole: LineString = hole.clone();
+        hole.make_ccw_winding();
+        parts.push(hole.0);
+    }
+}
+
+/**
+ * The content of a record, without its header.
+ */
+fn encode_shape(geometry: &Geometry, shape_type: ShapeType) -> Vec<u8> {
+    let mut out = vec![];
+    let mut coords = vec![];
+    parts(geometry, &mut coords);
+    let bbox = geometry.bounding_rect();
+    match (shape_type, bbox) {
+        (ShapeType::Null, _) | (_, None) => write_i32(&mut out, ShapeType::Null as i32),
+        (ShapeType::Point, Some(_)) => {
+            write_i32(&mut out, shape_type as i32);
+            write_coord(&mut out, coords[0][0]);
+        }
+        (ShapeType::MultiPoint, Some(bbox)) => {
+            write_i32(&mut out, shape_type as i32);
+            write_bbox(&mut out, Some(bbox));
+            write_i32(&mut out, coords.len() as i32);
+            for coord in coords.iter().flatten() {
+                write_coord(&mut out, *coord);
+            }
+        }
+        (ShapeType::PolyLine | ShapeType::Polygon, Some(bbox)) => {
+            write_i32(&mut out, shape_type as i32);
+            write_bbox(&mut out, Some(bbox));
+            write_i32(&mut out, coords.len() as i32);
+            write_i32(&mut out, coords.iter().map(Vec::len).sum::<usize>() as i32);
+            let mut start = 0;
+            for part in &coords {
+                write_i32(&mut out, start as i32);
+                start += part.len();
+            }
+            for coord in coords.iter().flatten() {
+                write_coord(&mut out, *coord);
+            }
+        }
+    }
+    out
+}
+
+/**
+ * The header shared by the `.shp` and `.shx` files, for a file of `bytes` bytes in total.
+ */
+fn header(shape_type: ShapeType, bbox: Option<Rect>, bytes: usize) -> Vec<u8> {
+    let mut out = vec![];
+    out.extend(FILE_CODE.to_be_bytes());
+    out.extend([0; 20]);
+    out.extend(((bytes / 2) as i32).to_be_bytes());
+    write_i32(&mut out, VERSION);
+    write_i32(&mut out, shape_type as i32);
+    write_bbox(&mut out, bbox);
+    // The ranges of Z and M values, which are not written.
+    out.extend([0; 32]);
+    out
+}
+
+/**
+ * A dBASE III table with a numeric `id` field numbering the records from 1.
+ */
+fn encode_dbf(records: usize, updated: NaiveDate) -> Vec<u8> {
+    let record_bytes = 1 + ID_WIDTH;
+    let header_bytes = 32 + 32 + 1;
+    let mut out = vec![0x03];
+    out.extend([
+        (updated.year() - 1900).clamp(0, 255) as u8,
+        updated.month() as u8,
+        updated.day() as u8,
+    ]);
+    out.extend((records as u32).to_le_bytes());
+    out.extend((header_bytes as u16).to_le_bytes());
+    out.extend((record_bytes as u16).to_le_bytes());
+    out.extend([0; 20]);
+
+    let mut field = [0; 32];
+    field[..2].copy_from_slice(b"id");
+    field[11] = b'N';
+    field[16] = ID_WIDTH as u8;
+    out.extend(field);
+    out.push(0x0d);
+
+    for id in 1..=records {
+        // Records start with a space, meaning not deleted, and numbers are right-aligned.
+        out.extend(format!(" {:>width$}", id, width = ID_WIDTH).as_bytes());
+    }
+    out.push(0x1a);
+    out
+}
+
+fn union(a: &Rect, b: &Rect) -> Rect {
+    Rect::new(
+        Coord {
+            x: a.min().x.min(b.min().x),
+            y: a.min().y.min(b.min().y),
+        },
+        Coord {
+            x: a.max().x.max(b.max().x),
+            y: a.max().y.max(b.max().y),
+        },
+    )
+}
+
+fn write_bbox(out: &mut Vec<u8>, bbox: Option<Rect>) {
+    let (min, max) = bbox.map_or((Coord::zero(), Coord::zero()), |b| (b.min(), b.max()));
+    for value in [min.x, min.y, max.x, max.y] {
+        out.extend(value.to_le_bytes());
+    }
+}
+
+fn write_coord(out: &mut Vec<u8>, coord: Coord) {
+    out.extend(coord.x.to_le_bytes());
+    out.extend(coord.y.to_le_bytes());
+}
+
+fn write_i32(out: &mut Vec<u8>, value: i32) {
+    out.extend(value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use geo_types::Geometry;
+    use wkt::TryFromWkt;
+
+    use super::{ShapeType, Shapefile};
+
+    fn i32_le(bytes: &[u8], at: usize) -> i32 {
+        i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn i32_be(bytes: &[u8], at: usize) -> i32 {
+        i32::from_be_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn f64_le(bytes: &[u8], at: usize) -> f64 {
+        f64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_shapefile() {
+        let geometries = [
+            "POLYGON((0 0,2 0,2 2,0 2,0 0))",
+            "GEOMETRYCOLLECTION(POLYGON((3 0,4 0,4 1,3 0)),POLYGON EMPTY)",
+            "POLYGON EMPTY",
+        ]
+        .map(|wkt| Geometry::<f64>::try_from_wkt_str(wkt).unwrap());
+        let date = NaiveDate::from_ymd_opt(2024, 5, 17).unwrap();
+        let shapefile = Shapefile::new(&geometries, date).unwrap();
+        assert_eq!(shapefile.shape_type, ShapeType::Polygon);
+
+        let shp = &shapefile.shp;
+        assert_eq!(i32_be(shp, 0), 9994);
+        assert_eq!(i32_be(shp, 24) as usize * 2, shp.len());
+        assert_eq!(i32_le(shp, 32), 5);
+        assert_eq!(
+            [36, 44, 52, 60].map(|at| f64_le(shp, at)),
+            [0.0, 0.0, 4.0, 2.0]
+        );
+        // The first record's exterior is rewound clockwise.
+        let record = &shp[108..];
+        assert_eq!(i32_le(record, 0), 5);
+        assert_eq!((i32_le(record, 36), i32_le(record, 40)), (1, 5));
+        assert_eq!(
+            (f64_le(record, 48 + 16), f64_le(record, 48 + 24)),
+            (0.0, 2.0)
+        );
+
+        // The index points at each record, the last of which is a null shape.
+        let shx = &shapefile.shx;
+        assert_eq!(shx.len(), 100 + 3 * 8);
+        let last = i32_be(shx, 116) as usize * 2;
+        assert_eq!(i32_be(shp, last), 3);
+        assert_eq!(i32_le(shp, last + 8), 0);
+        assert_eq!(last + 12, shp.len());
+
+        let dbf = &shapefile.dbf;
+        assert_eq!(&dbf[1..4], &[124, 5, 17]);
+        assert_eq!(u32::from_le_bytes(dbf[4..8].try_into().unwrap()), 3);
+        assert_eq!(&dbf[65..76], b"          1");
+        assert_eq!(dbf.last(), Some(&0x1a));
+
+        let points =
+            ["POINT(1 2)", "POINT(3 4)"].map(|wkt| Geometry::<f64>::try_from_wkt_str(wkt).unwrap());
+        let shapefile = Shapefile::new(&points, date).unwrap();
+        assert_eq!(shapefile.shape_type, ShapeType::Point);
+        assert_eq!(shapefile.shp.len(), 100 + 2 * (8 + 20));
+
+        let mixed = ["POINT(1 2)", "LINESTRING(0 0,1 1)"]
+            .map(|wkt| Geometry::<f64>::try_from_wkt_str(wkt).unwrap());
+        assert!(Shapefile::new(&mixed, date).is_err());
+    }
+}
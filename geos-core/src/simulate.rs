@@ -0,0 +1,239 @@
+use geo::HaversineDestination;
+use geo_types::{LineString, Point};
+use itertools::Itertools;
+use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
+
+use crate::geom::lerp;
+use crate::linear::cumulative_lengths;
+
+/// The maximum change in heading, in degrees, of a random-walking agent per tick.
+const RANDOM_WALK_MAX_TURN_DEG: f64 = 30.0;
+
+/**
+ * A route that agents travel along, with precomputed cumulative haversine lengths so that
+ * positions can be looked up by the distance traveled.
+ */
+pub struct Route {
+    line: LineString,
+    cumulative_m: Vec<f64>,
+}
+
+impl Route {
+    pub fn new(line: LineString) -> Self {
+        let cumulative_m = cumulative_lengths(&line);
+        Self { line, cumulative_m }
+    }
+
+    pub fn length_m(&self) -> f64 {
+        self.cumulative_m.last().copied().unwrap_or_default()
+    }
+
+    /**
+     * The position after traveling `distance_m` meters from the start of the route. Distances past
+     * either end of the route are clamped to that end.
+     */
+    pub fn position_at(&self, distance_m: f64) -> Point {
+        let coords = &self.line.0;
+        if coords.len() < 2 {
+            return coords.first().copied().unwrap_or_default().into();
+        }
+        let distance_m = distance_m.clamp(0.0, self.length_m());
+
+        // Index of the end of the segment containing the distance.
+        let i = self
+            .cumulative_m
+            .partition_point(|d| *d <= distance_m)
+            .clamp(1, coords.len() - 1);
+
+        let (start_m, end_m) = (self.cumulative_m[i - 1], self.cumulative_m[i]);
+        let t = if end_m > start_m {
+            (distance_m - start_m) / (end_m - start_m)
+        } else {
+            0.0
+        };
+        lerp(t, coords[i - 1], coords[i]).into()
+    }
+}
+
+enum Motion {
+    OnRoute { route: usize, distance_m: f64 },
+    RandomWalk { position: Point, heading_deg: f64 },
+}
+
+struct Agent {
+    motion: Motion,
+    speed_mps: f64,
+    finished: bool,
+}
+
+/**
+ * A discrete-time simulation of agents moving at constant speeds, either along routes or as random
+ * walks. Each call to `step` advances the simulation by one tick.
+ */
+pub struct Simulation {
+    routes: Vec<Route>,
+    agents: Vec<Agent>,
+    tick_s: f64,
+    elapsed_s: f64,
+}
+
+impl Simulation {
+    /**
+     * Creates agents that travel along routes, with agent i assigned to route i modulo the number
+     * of routes. Each agent starts at the beginning of its route and finishes at its end.
+     */
+    pub fn on_routes(routes: Vec<Route>, speeds_mps: &[f64], tick_s: f64) -> Self {
+        let agents = speeds_mps
+            .iter()
+            .enumerate()
+            .map(|(i, speed_mps)| Agent {
+                motion: Motion::OnRoute {
+                    route: i % routes.len(),
+                    distance_m: 0.0,
+                },
+                speed_mps: *speed_mps,
+                finished: false,
+            })
+            .collect_vec();
+        Self {
+            routes,
+            agents,
+            tick_s,
+            elapsed_s: 0.0,
+        }
+    }
+
+    /**
+     * Creates agents that random walk from the given starting positions in random initial
+     * directions. Random walks never finish.
+     */
+    pub fn random_walks<R: Rng>(
+        rng: &mut R,
+        starts: Vec<Point>,
+        speeds_mps: &[f64],
+        tick_s: f64,
+    ) -> Self {
+        let agents = starts
+            .into_iter()
+            .zip(speeds_mps)
+            .map(|(position, speed_mps)| Agent {
+                motion: Motion::RandomWalk {
+                    position,
+                    heading_deg: rng.gen_range(0.0..360.0),
+                },
+                speed_mps: *speed_mps,
+                finished: false,
+            })
+            .collect_vec();
+        Self {
+            routes: vec![],
+            agents,
+            tick_s,
+            elapsed_s: 0.0,
+        }
+    }
+
+    pub fn elapsed_s(&self) -> f64 {
+        self.elapsed_s
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.agents.iter().all(|a| a.finished)
+    }
+
+    /**
+     * The current positions of all agents that have not finished, as (agent index, position)
+     * pairs.
+     */
+    pub fn positions(&self) -> Vec<(usize, Point)> {
+        self.agents
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| !a.finished)
+            .map(|(i, a)| (i, self.position(a)))
+            .collect_vec()
+    }
+
+    /**
+     * Advances all agents by one tick and returns the positions of the agents that moved. Agents
+     * that reach the end of their route during the tick are reported once at the end position.
+     */
+    pub fn step<R: Rng>(&mut self, rng: &mut R) -> Vec<(usize, Point)> {
+        let turn = Uniform::new_inclusive(-RANDOM_WALK_MAX_TURN_DEG, RANDOM_WALK_MAX_TURN_DEG);
+        self.elapsed_s += self.tick_s;
+
+        let mut moved = vec![];
+        for (i, agent) in self.agents.iter_mut().enumerate() {
+            if agent.finished {
+                continue;
+            }
+            let step_m = agent.speed_mps * self.tick_s;
+            match &mut agent.motion {
+                Motion::OnRoute { route, distance_m } => {
+                    *distance_m += step_m;
+                    // Agents finish once they reach the end, but still report the end position.
+                    agent.finished = *distance_m >= self.routes[*route].length_m();
+                }
+                Motion::RandomWalk {
+                    position,
+                    heading_deg,
+                } => {
+                    *heading_deg = (*heading_deg + turn.sample(rng)).rem_euclid(360.0);
+                    *position = position.haversine_destination(*heading_deg, step_m);
+                }
+            }
+            moved.push(i);
+        }
+
+        moved
+            .into_iter()
+            .map(|i| (i, self.position(&self.agents[i])))
+            .collect_vec()
+    }
+
+    fn position(&self, agent: &Agent) -> Point {
+        match &agent.motion {
+            Motion::OnRoute { route, distance_m } => self.routes[*route].position_at(*distance_m),
+            Motion::RandomWalk { position, .. } => *position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::HaversineDistance;
+    use geo_types::{line_string, Point};
+
+    use super::{Route, Simulation};
+    use crate::samplers::create_rng;
+
+    #[test]
+    fn test_agents_travel_routes_at_their_speed() {
+        let line = line_string![(x: 0.0, y: 0.0), (x: 0.01, y: 0.0), (x: 0.01, y: 0.01)];
+        let route = Route::new(line);
+        let length_m = route.length_m();
+        assert!(
+            (route
+                .position_at(length_m / 2.0)
+                .haversine_distance(&Point::new(0.01, 0.0)))
+                < 1.0
+        );
+
+        // Two agents with different speeds on the same route.
+        let mut rng = create_rng(0);
+        let mut simulation = Simulation::on_routes(vec![route], &[length_m / 4.0, length_m], 1.0);
+        let positions = simulation.step(&mut rng);
+        assert_eq!(positions.len(), 2);
+        assert!(positions[0].1.haversine_distance(&Point::new(0.005, 0.0)) < 1.0);
+        assert!(positions[1].1.haversine_distance(&Point::new(0.01, 0.01)) < 1.0);
+
+        // The fast agent has finished, and the slow agent finishes after four ticks in total.
+        assert_eq!(simulation.step(&mut rng).len(), 1);
+        simulation.step(&mut rng);
+        simulation.step(&mut rng);
+        assert!(simulation.is_finished());
+        assert!(simulation.step(&mut rng).is_empty());
+        assert_eq!(simulation.elapsed_s(), 5.0);
+    }
+}
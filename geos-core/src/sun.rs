@@ -0,0 +1,233 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use geo_types::Point;
+
+/// Zenith angle of the sun's center at sunrise and sunset, including atmospheric refraction and the
+/// sun's apparent radius.
+const SUNRISE_ZENITH_DEG: f64 = 90.833;
+/// Zenith angle of the sun's center at civil dawn and dusk.
+const CIVIL_TWILIGHT_ZENITH_DEG: f64 = 96.0;
+const MINUTES_PER_DAY: f64 = 1440.0;
+
+/**
+ * The apparent position of the sun in the sky as seen from a point on the Earth.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SolarPosition {
+    /// Degrees clockwise from north.
+    pub azimuth_deg: f64,
+    /// Degrees above the horizon, corrected for atmospheric refraction.
+    pub elevation_deg: f64,
+}
+
+/**
+ * Sunrise, sunset, and civil twilight times for one day. Events that do not occur during polar
+ * day or night are `None`.
+ */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SunTimes {
+    pub civil_dawn: Option<DateTime<Utc>>,
+    pub sunrise: Option<DateTime<Utc>>,
+    pub solar_noon: DateTime<Utc>,
+    pub sunset: Option<DateTime<Utc>>,
+    pub civil_dusk: Option<DateTime<Utc>>,
+}
+
+/**
+ * Computes the sun's azimuth and elevation at a point and time, using the NOAA solar calculator
+ * equations, which are accurate to a small fraction of a degree for dates between 1901 and 2099.
+ *
+ * Meeus, J. "Astronomical Algorithms." 1991.
+ * https://gml.noaa.gov/grad/solcalc/calcdetails.html
+ */
+pub fn solar_position(point: &Point, time: &DateTime<Utc>) -> SolarPosition {
+    let (declination, eq_time_min) = solar_declination_and_eq_time(time);
+    let lat = point.y().to_radians();
+
+    // The hour angle is zero at solar noon and increases by 15 degrees per hour.
+    let minutes = minutes_since_midnight(time);
+    let true_solar_min = (minutes + eq_time_min + 4.0 * point.x()).rem_euclid(MINUTES_PER_DAY);
+    let hour_angle = (true_solar_min / 4.0 - 180.0).to_radians();
+
+    let cos_zenith = (lat.sin() * declination.sin()
+        + lat.cos() * declination.cos() * hour_angle.cos())
+    .clamp(-1.0, 1.0);
+    let zenith = cos_zenith.acos();
+    let elevation_deg = 90.0 - zenith.to_degrees();
+
+    let azimuth_deg = if zenith.sin().abs() < 1e-12 || lat.cos().abs() < 1e-12 {
+        // Directly overhead, or at a pole, where every direction is north or south.
+        if point.y() > 0.0 {
+            180.0
+        } else {
+            0.0
+        }
+    } else {
+        let cos_azimuth = ((lat.sin() * cos_zenith - declination.sin())
+            / (lat.cos() * zenith.sin()))
+        .clamp(-1.0, 1.0);
+        let azimuth = cos_azimuth.acos().to_degrees();
+        if hour_angle > 0.0 {
+            (azimuth + 180.0).rem_euclid(360.0)
+        } else {
+            (540.0 - azimuth).rem_euclid(360.0)
+        }
+    };
+
+    SolarPosition {
+        azimuth_deg,
+        elevation_deg: elevation_deg + refraction_deg(elevation_deg),
+    }
+}
+
+/**
+ * Computes the sunrise, sunset, and civil twilight times of the solar day containing the given
+ * time at a point. Solar days run from midnight to midnight in local mean solar time, so that the
+ * result does not depend on time zones.
+ */
+pub fn sun_times(point: &Point, time: &DateTime<Utc>) -> SunTimes {
+    // Midnight UTC of the local solar date.
+    let local_mean = *time + Duration::milliseconds((point.x() * 240_000.0) as i64);
+    let day = Utc.from_utc_datetime(&local_mean.date_naive().and_hms_opt(0, 0, 0).unwrap());
+
+    let solar_noon = refine(day, |t| {
+        let (_, eq_time_min) = solar_declination_and_eq_time(t);
+        Some(720.0 - 4.0 * point.x() - eq_time_min)
+    })
+    .expect("solar noon always occurs");
+    let event = |zenith_deg: f64, sign: f64| {
+        refine(day, |t| {
+            let (declination, eq_time_min) = solar_declination_and_eq_time(t);
+            let hour_angle_deg = sunrise_hour_angle(point.y(), declination, zenith_deg)?;
+            Some(720.0 - 4.0 * (point.x() + sign * hour_angle_deg) - eq_time_min)
+        })
+    };
+
+    SunTimes {
+        civil_dawn: event(CIVIL_TWILIGHT_ZENITH_DEG, 1.0),
+        sunrise: event(SUNRISE_ZENITH_DEG, 1.0),
+        solar_noon,
+        sunset: event(SUNRISE_ZENITH_DEG, -1.0),
+        civil_dusk: event(CIVIL_TWILIGHT_ZENITH_DEG, -1.0),
+    }
+}
+
+/**
+ * Finds the time of a solar event from a function estimating its minutes after midnight UTC, given
+ * the time at which to evaluate the sun's declination and the equation of time. The estimate is
+ * refined by re-evaluating at the previous estimate, starting from noon UTC.
+ */
+fn refine(
+    day: DateTime<Utc>,
+    minutes_at: impl Fn(&DateTime<Utc>) -> Option<f64>,
+) -> Option<DateTime<Utc>> {
+    let to_time = |minutes: f64| day + Duration::milliseconds((minutes * 60_000.0) as i64);
+    let mut time = to_time(720.0);
+    for _ in 0..3 {
+        time = to_time(minutes_at(&time)?);
+    }
+    Some(time)
+}
+
+/**
+ * The hour angle in degrees at which the sun's center reaches a zenith angle, or `None` if it never
+ * does on that day.
+ */
+fn sunrise_hour_angle(lat_deg: f64, declination: f64, zenith_deg: f64) -> Option<f64> {
+    let lat = lat_deg.to_radians();
+    let cos_hour_angle = zenith_deg.to_radians().cos() / (lat.cos() * declination.cos())
+        - lat.tan() * declination.tan();
+    (-1.0..=1.0)
+        .contains(&cos_hour_angle)
+        .then(|| cos_hour_angle.acos().to_degrees())
+}
+
+/**
+ * The sun's declination in radians and the equation of time in minutes.
+ */
+fn solar_declination_and_eq_time(time: &DateTime<Utc>) -> (f64, f64) {
+    let julian_day = time.timestamp_millis() as f64 / 86_400_000.0 + 2_440_587.5;
+    let jc = (julian_day - 2_451_545.0) / 36_525.0;
+
+    let mean_long = (280.46646 + jc * (36000.76983 + jc * 0.0003032)).rem_euclid(360.0);
+    let mean_anomaly = 357.52911 + jc * (35999.05029 - 0.0001537 * jc);
+    let eccentricity = 0.016708634 - jc * (0.000042037 + 0.0000001267 * jc);
+    let m = mean_anomaly.to_radians();
+    let center = m.sin() * (1.914602 - jc * (0.004817 + 0.000014 * jc))
+        + (2.0 * m).sin() * (0.019993 - 0.000101 * jc)
+        + (3.0 * m).sin() * 0.000289;
+    let omega = (125.04 - 1934.136 * jc).to_radians();
+    let apparent_long = (mean_long + center - 0.00569 - 0.00478 * omega.sin()).to_radians();
+    let mean_obliquity =
+        23.0 + (26.0 + (21.448 - jc * (46.815 + jc * (0.00059 - jc * 0.001813))) / 60.0) / 60.0;
+    let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+
+    let declination = (obliquity.sin() * apparent_long.sin()).asin();
+
+    let y = (obliquity / 2.0).tan().powi(2);
+    let l = mean_long.to_radians();
+    let eq_time = y * (2.0 * l).sin() - 2.0 * eccentricity * m.sin()
+        + 4.0 * eccentricity * y * m.sin() * (2.0 * l).cos()
+        - 0.5 * y * y * (4.0 * l).sin()
+        - 1.25 * eccentricity * eccentricity * (2.0 * m).sin();
+
+    (declination, 4.0 * eq_time.to_degrees())
+}
+
+/**
+ * The approximate atmospheric refraction in degrees at an elevation, from the NOAA calculator.
+ */
+fn refraction_deg(elevation_deg: f64) -> f64 {
+    let tan_e = elevation_deg.to_radians().tan();
+    let arcsec = if elevation_deg > 85.0 {
+        0.0
+    } else if elevation_deg > 5.0 {
+        58.1 / tan_e - 0.07 / tan_e.powi(3) + 0.000086 / tan_e.powi(5)
+    } else if elevation_deg > -0.575 {
+        1735.0
+            + elevation_deg
+                * (-518.2
+                    + elevation_deg * (103.4 + elevation_deg * (-12.79 + elevation_deg * 0.711)))
+    } else {
+        -20.772 / tan_e
+    };
+    arcsec / 3600.0
+}
+
+fn minutes_since_midnight(time: &DateTime<Utc>) -> f64 {
+    let midnight = time.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+    (*time - midnight).num_milliseconds() as f64 / 60_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use geo_types::Point;
+
+    use super::{solar_position, sun_times};
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().into()
+    }
+
+    #[test]
+    fn test_matches_noaa_solar_calculator() {
+        // Boulder, CO on the 2024 summer solstice. Reference values were computed independently
+        // with the US Naval Observatory's low precision solar coordinates.
+        let boulder = Point::new(-105.27, 40.015);
+        let position = solar_position(&boulder, &utc("2024-06-21T18:00:00Z"));
+        assert!((position.azimuth_deg - 136.56).abs() < 0.05);
+        assert!((position.elevation_deg - 68.75).abs() < 0.05);
+
+        let times = sun_times(&boulder, &utc("2024-06-21T18:00:00Z"));
+        let minutes_off = |t: Option<DateTime<Utc>>, expected: &str| {
+            (t.unwrap() - utc(expected)).num_seconds().abs() as f64 / 60.0
+        };
+        assert!(minutes_off(times.sunrise, "2024-06-21T11:32:31Z") < 1.0);
+        assert!(minutes_off(times.sunset, "2024-06-22T02:33:35Z") < 1.0);
+
+        // The sun never sets above the arctic circle on the solstice.
+        let tromso = Point::new(18.96, 69.65);
+        let times = sun_times(&tromso, &utc("2024-06-21T12:00:00Z"));
+        assert_eq!((times.sunrise, times.sunset), (None, None));
+    }
+}
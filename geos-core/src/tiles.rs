@@ -0,0 +1,210 @@
+use std::borrow::Cow;
+use std::f64::consts::PI;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use geo::{BoundingRect, Intersects};
+use geo_types::{Coord, Geometry, Point, Polygon, Rect};
+use itertools::Itertools;
+
+use crate::geom::split_geometry_antimeridian;
+
+/// The maximum latitude of the web mercator projection, where the map becomes square.
+pub const MAX_LATITUDE: f64 = 85.0511287798066;
+/// The maximum zoom for which tile coordinates fit in a u32.
+pub const MAX_ZOOM: u8 = 31;
+
+/**
+ * An XYZ web mercator map tile, as used by slippy maps. Tile (0, 0) is the top-left tile at each
+ * zoom level.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tile {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl Tile {
+    /**
+     * The tile containing a point at a zoom level. Latitudes beyond the web mercator bounds are
+     * clamped to the top or bottom row of tiles.
+     */
+    pub fn from_point(point: &Point, z: u8) -> Self {
+        let n = (1u64 << z) as f64;
+        let (x, y) = project(point.0);
+        let max = (1u64 << z) - 1;
+        Self {
+            z,
+            x: ((x * n).floor() as u64).min(max) as u32,
+            y: ((y * n).floor() as u64).min(max) as u32,
+        }
+    }
+
+    /**
+     * The bounds of the tile in lng/lat.
+     */
+    pub fn bounds(&self) -> Rect {
+        let n = (1u64 << self.z) as f64;
+        let lng = |x: f64| x / n * 360.0 - 180.0;
+        let lat = |y: f64| (PI * (1.0 - 2.0 * y / n)).sinh().atan().to_degrees();
+        let (x, y) = (self.x as f64, self.y as f64);
+        Rect::new(
+            Coord {
+                x: lng(x),
+                y: lat(y + 1.0),
+            },
+            Coord {
+                x: lng(x + 1.0),
+                y: lat(y),
+            },
+        )
+    }
+
+    pub fn to_polygon(&self) -> Polygon {
+        self.bounds().to_polygon()
+    }
+
+    /**
+     * The web mercator position of a lng/lat coordinate relative to the tile, from (0, 0) at its
+     * top-left corner to (1, 1) at its bottom-right corner.
+     */
+    pub fn project(&self, coord: Coord) -> Coord {
+        let n = (1u64 << self.z) as f64;
+        let (x, y) = project(coord);
+        Coord {
+            x: x * n - self.x as f64,
+            y: y * n - self.y as f64,
+        }
+    }
+}
+
+impl FromStr for Tile {
+    type Err = String;
+
+    /**
+     * Parses a tile written as `z/x/y`.
+     */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid tile '{}', expected z/x/y", s);
+        let parts: Vec<&str> = s.trim().split('/').collect();
+        let [z, x, y] = parts[..] else {
+            return Err(invalid());
+        };
+        let z: u8 = z.parse().map_err(|_| invalid())?;
+        if z > MAX_ZOOM {
+            return Err(format!(
+                "tile zoom {} is beyond the maximum of {}",
+                z, MAX_ZOOM
+            ));
+        }
+        let (x, y): (u32, u32) = (
+            x.parse().map_err(|_| invalid())?,
+            y.parse().map_err(|_| invalid())?,
+        );
+        if (x as u64) >> z != 0 || (y as u64) >> z != 0 {
+            return Err(format!(
+                "tile {} is outside the {}x{} tiles at zoom {}",
+                s,
+                1u64 << z,
+                1u64 << z,
+                z
+            ));
+        }
+        Ok(Self { z, x, y })
+    }
+}
+
+impl Display for Tile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}/{}", self.z, self.x, self.y)
+    }
+}
+
+/**
+ * Computes the tiles at a zoom level that intersect a geometry, ordered by row and then column.
+ */
+pub fn get_tile_covering(geometry: &Geometry, z: u8) -> Vec<Tile> {
+    // The parts of polygons split on the antimeridian are covered separately, since together they
+    // are bounded by the whole width of the map.
+    if let Cow::Owned(split) = split_geometry_antimeridian(geometry) {
+        let parts = match split {
+            Geometry::MultiPolygon(mpoly) => mpoly.into_iter().map(Geometry::from).collect_vec(),
+            Geometry::GeometryCollection(collection) => collection.0,
+            split => vec![split],
+        };
+        return parts
+            .iter()
+            .flat_map(|part| get_tile_covering(part, z))
+            .sorted_by_key(|tile| (tile.y, tile.x))
+            .dedup()
+            .collect_vec();
+    }
+    get_bounded_tile_covering(geometry, z)
+}
+
+fn get_bounded_tile_covering(geometry: &Geometry, z: u8) -> Vec<Tile> {
+    let Some(bbox) = geometry.bounding_rect() else {
+        return vec![];
+    };
+    // Tile rows increase southwards.
+    let top_left = Tile::from_point(&Point::new(bbox.min().x, bbox.max().y), z);
+    let bottom_right = Tile::from_point(&Point::new(bbox.max().x, bbox.min().y), z);
+    (top_left.y..=bottom_right.y)
+        .cartesian_product(top_left.x..=bottom_right.x)
+        .map(|(y, x)| Tile { z, x, y })
+        .filter(|tile| tile.to_polygon().intersects(geometry))
+        .collect_vec()
+}
+
+/**
+ * Projects a lng/lat coordinate to web mercator coordinates in [0, 1], with y increasing
+ * southwards.
+ */
+fn project(coord: Coord) -> (f64, f64) {
+    let lat = coord.y.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+    let x = (coord.x + 180.0) / 360.0;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0;
+    (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::Contains;
+    use geo_types::{polygon, Geometry, Point};
+
+    use super::{get_tile_covering, Tile};
+
+    #[test]
+    fn test_tiles() {
+        let sf = Point::new(-122.4194, 37.7749);
+        let tile = Tile::from_point(&sf, 10);
+        assert_eq!(
+            tile,
+            Tile {
+                z: 10,
+                x: 163,
+                y: 395
+            }
+        );
+        assert_eq!(tile.to_string(), "10/163/395");
+        assert_eq!("10/163/395".parse(), Ok(tile));
+        assert!("10/1024/395".parse::<Tile>().is_err());
+        assert!("10/163".parse::<Tile>().is_err());
+        assert!(tile.bounds().contains(&sf));
+        assert_eq!(
+            Tile::from_point(&Point::new(180.0, -90.0), 2),
+            Tile { z: 2, x: 3, y: 3 }
+        );
+
+        // A polygon straddling the four tiles around (0, 0) at zoom 1.
+        let geometry = Geometry::from(
+            polygon![(x: -1.0, y: -1.0), (x: 1.0, y: -1.0), (x: 1.0, y: 1.0), (x: -1.0, y: 1.0)],
+        );
+        let tiles = get_tile_covering(&geometry, 1)
+            .iter()
+            .map(Tile::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(tiles, vec!["1/0/0", "1/1/0", "1/0/1", "1/1/1"]);
+    }
+}
@@ -0,0 +1,346 @@
+/*!
+ * Writing of geometries as a TopoJSON topology, in which borders shared by adjacent polygons, such
+ * as the pieces of a region cut by cells, are written once as arcs that the polygons refer to.
+ */
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use geo_types::{Coord, Geometry, LineString, Polygon};
+
+/// Coordinates are compared exactly, by their bits.
+type Key = (u64, u64);
+
+fn key(c: &Coord) -> Key {
+    // Adding zero turns -0 into 0, which are the same point.
+    ((c.x + 0.0).to_bits(), (c.y + 0.0).to_bits())
+}
+
+/**
+ * Writes geometries, each optionally named by an `id`, as a TopoJSON topology on one line, with
+ * the geometries as the `geometries` object. Coordinates are written as given, without
+ * quantization.
+ */
+pub fn write_topojson(
+    out: &mut dyn Write,
+    geometries: &[(Option<String>, Geometry)],
+) -> io::Result<()> {
+    let mut sequences = vec![];
+    for (_, geometry) in geometries {
+        collect_sequences(geometry, &mut sequences);
+    }
+    let mut topology = Topology {
+        junctions: find_junctions(&sequences),
+        arcs: vec![],
+        lookup: HashMap::new(),
+    };
+
+    out.write_all(br#"{"type":"Topology","objects":{"geometries":{"type":"GeometryCollection","geometries":["#)?;
+    for (i, (name, geometry)) in geometries.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        topology.write_object(out, name.as_deref(), geometry)?;
+    }
+    out.write_all(br#"]}},"arcs":["#)?;
+    for (i, arc) in topology.arcs.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write_positions(out, arc)?;
+    }
+    writeln!(out, "]}}")
+}
+
+/**
+ * A sequence of coordinates, without repeated consecutive points, that is either a closed ring,
+ * whose last point is not repeated, or a line.
+ */
+struct Sequence {
+    coords: Vec<Coord>,
+    closed: bool,
+}
+
+impl Sequence {
+    fn new(line: &LineString, closed: bool) -> Self {
+        let mut coords = line.0.clone();
+        coords.dedup_by_key(|c| key(c));
+        if closed && coords.len() > 1 && key(&coords[0]) == key(&coords[coords.len() - 1]) {
+            coords.pop();
+        }
+        Self { coords, closed }
+    }
+}
+
+fn collect_sequences(geometry: &Geometry, sequences: &mut Vec<Sequence>) {
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => {}
+        Geometry::Line(line) => sequences.push(Sequence::new(&(*line).into(), false)),
+        Geometry::LineString(line) => sequences.push(Sequence::new(line, false)),
+        Geometry::MultiLineString(lines) => {
+            sequences.extend(lines.iter().map(|line| Sequence::new(line, false)))
+        }
+        Geometry::Polygon(polygon) => collect_rings(polygon, sequences),
+        Geometry::MultiPolygon(polygons) => {
+            polygons.iter().for_each(|p| collect_rings(p, sequences))
+        }
+        Geometry::Rect(rect) => collect_rings(&rect.to_polygon(), sequences),
+        Geometry::Triangle(triangle) => collect_rings(&triangle.to_polygon(), sequences),
+        Geometry::GeometryCollection(collection) => collection
+            .iter()
+            .for_each(|geometry| collect_sequences(geometry, sequences)),
+    }
+}
+
+fn collect_rings(polygon: &Polygon, sequences: &mut Vec<Sequence>) {
+    sequences.push(Sequence::new(polygon.exterior(), true));
+    sequences.extend(polygon.interiors().iter().map(|r| Sequence::new(r, true)));
+}
+
+/**
+ * The points where arcs begin and end: the ends of lines, and the points where sequences that
+ * share a border part ways, i.e. points whose neighbors differ between the sequences they are in.
+ */
+fn find_junctions(sequences: &[Sequence]) -> HashSet<Key> {
+    let mut neighbors: HashMap<Key, (Option<Key>, Option<Key>)> = HashMap::new();
+    let mut junctions = HashSet::new();
+    for Sequence { coords, closed } in sequences {
+        let n = coords.len();
+        for (i, c) in coords.iter().enumerate() {
+            let (previous, next) = if *closed {
+                (Some(coords[(i + n - 1) % n]), Some(coords[(i + 1) % n]))
+            } else {
+                (
+                    i.checked_sub(1).map(|j| coords[j]),
+                    coords.get(i + 1).copied(),
+                )
+            };
+            let (previous, next) = (previous.map(|c| key(&c)), next.map(|c| key(&c)));
+            let pair = if previous <= next {
+                (previous, next)
+            } else {
+                (next, previous)
+            };
+            if !closed && (i == 0 || i == n - 1) {
+                junctions.insert(key(c));
+            }
+            if *neighbors.entry(key(c)).or_insert(pair) != pair {
+                junctions.insert(key(c));
+            }
+        }
+    }
+    junctions
+}
+
+/**
+ * The arcs found so far, looked up by their points to share them between geometries.
+ */
+struct Topology {
+    junctions: HashSet<Key>,
+    arcs: Vec<Vec<Coord>>,
+    lookup: HashMap<Vec<Key>, usize>,
+}
+
+impl Topology {
+    fn write_object(
+        &mut self,
+        out: &mut dyn Write,
+        name: Option<&str>,
+        geometry: &Geometry,
+    ) -> io::Result<()> {
+        let (kind, member) = match geometry {
+            Geometry::Point(_) => ("Point", "coordinates"),
+            Geometry::MultiPoint(_) => ("MultiPoint", "coordinates"),
+            Geometry::Line(_) | Geometry::LineString(_) => ("LineString", "arcs"),
+            Geometry::MultiLineString(_) => ("MultiLineString", "arcs"),
+            Geometry::Polygon(_) | Geometry::Rect(_) | Geometry::Triangle(_) => ("Polygon", "arcs"),
+            Geometry::MultiPolygon(_) => ("MultiPolygon", "arcs"),
+            Geometry::GeometryCollection(_) => ("GeometryCollection", "geometries"),
+        };
+        write!(out, r#"{{"type":"{}","#, kind)?;
+        if let Some(name) = name {
+            write!(out, r#""id":"{}","#, escape(name))?;
+        }
+        write!(out, r#""{}":"#, member)?;
+        match geometry {
+            Geometry::Point(point) => write_position(out, &point.0)?,
+            Geometry::MultiPoint(points) => {
+                let coords: Vec<Coord> = points.iter().map(|p| p.0).collect();
+                write_positions(out, &coords)?
+            }
+            Geometry::Line(line) => {
+                let arcs = self.arcs_of(&Sequence::new(&(*line).into(), false));
+                write_indices(out, &arcs)?
+            }
+            Geometry::LineString(line) => {
+                let arcs = self.arcs_of(&Sequence::new(line, false));
+                write_indices(out, &arcs)?
+            }
+            Geometry::MultiLineString(lines) => {
+                let arcs: Vec<Vec<i64>> = lines
+                    .iter()
+                    .map(|line| self.arcs_of(&Sequence::new(line, false)))
+                    .collect();
+                write_nested(out, &arcs)?
+            }
+            Geometry::Polygon(polygon) => self.write_polygon(out, polygon)?,
+            Geometry::Rect(rect) => self.write_polygon(out, &rect.to_polygon())?,
+            Geometry::Triangle(triangle) => self.write_polygon(out, &triangle.to_polygon())?,
+            Geometry::MultiPolygon(polygons) => {
+                out.write_all(b"[")?;
+                for (i, polygon) in polygons.iter().enumerate() {
+                    if i > 0 {
+                        out.write_all(b",")?;
+                    }
+                    self.write_polygon(out, polygon)?;
+                }
+                out.write_all(b"]")?;
+            }
+            Geometry::GeometryCollection(collection) => {
+                out.write_all(b"[")?;
+                for (i, member) in collection.iter().enumerate() {
+                    if i > 0 {
+                        out.write_all(b",")?;
+                    }
+                    self.write_object(out, None, member)?;
+                }
+                out.write_all(b"]")?;
+            }
+        }
+        out.write_all(b"}")
+    }
+
+    fn write_polygon(&mut self, out: &mut dyn Write, polygon: &Polygon) -> io::Result<()> {
+        let rings = std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(|ring| self.arcs_of(&Sequence::new(ring, true)))
+            .collect::<Vec<_>>();
+        write_nested(out, &rings)
+    }
+
+    /**
+     * The arcs making up a sequence, as indices into the arcs, or for arcs that are reversed, as
+     * their ones' complement.
+     */
+    fn arcs_of(&mut self, sequence: &Sequence) -> Vec<i64> {
+        let mut coords = sequence.coords.clone();
+        if coords.len() < 2 {
+            return vec![];
+        }
+        if sequence.closed {
+            // Rings start at a junction, or without any, at a point that doesn't depend on where
+            // they started, so that the same ring in two geometries gives the same arc.
+            let start = coords
+                .iter()
+                .position(|c| self.junctions.contains(&key(c)))
+                .unwrap_or_else(|| {
+                    let min = coords.iter().map(key).min().expect("rings have points");
+                    coords.iter().position(|c| key(c) == min).unwrap_or(0)
+                });
+            coords.rotate_left(start);
+            coords.push(coords[0]);
+        }
+        let mut arcs = vec![];
+        let mut begin = 0;
+        for i in 1..coords.len() {
+            if i == coords.len() - 1 || self.junctions.contains(&key(&coords[i])) {
+                arcs.push(self.arc(&coords[begin..=i]));
+                begin = i;
+            }
+        }
+        arcs
+    }
+
+    fn arc(&mut self, coords: &[Coord]) -> i64 {
+        let keys: Vec<Key> = coords.iter().map(key).collect();
+        if let Some(&index) = self.lookup.get(&keys) {
+            return index as i64;
+        }
+        let reversed: Vec<Key> = keys.iter().rev().copied().collect();
+        if let Some(&index) = self.lookup.get(&reversed) {
+            return !(index as i64);
+        }
+        self.lookup.insert(keys, self.arcs.len());
+        self.arcs.push(coords.to_vec());
+        self.arcs.len() as i64 - 1
+    }
+}
+
+fn write_position(out: &mut dyn Write, c: &Coord) -> io::Result<()> {
+    write!(out, "[{},{}]", c.x, c.y)
+}
+
+fn write_positions(out: &mut dyn Write, coords: &[Coord]) -> io::Result<()> {
+    out.write_all(b"[")?;
+    for (i, c) in coords.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write_position(out, c)?;
+    }
+    out.write_all(b"]")
+}
+
+fn write_indices(out: &mut dyn Write, indices: &[i64]) -> io::Result<()> {
+    let indices: Vec<String> = indices.iter().map(i64::to_string).collect();
+    write!(out, "[{}]", indices.join(","))
+}
+
+fn write_nested(out: &mut dyn Write, nested: &[Vec<i64>]) -> io::Result<()> {
+    out.write_all(b"[")?;
+    for (i, indices) in nested.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write_indices(out, indices)?;
+    }
+    out.write_all(b"]")
+}
+
+/**
+ * Escapes text for a JSON string.
+ */
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, polygon, Geometry};
+
+    use super::write_topojson;
+
+    #[test]
+    fn test_write_topojson() {
+        let left = polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 1.0)];
+        let right =
+            polygon![(x: 1.0, y: 0.0), (x: 2.0, y: 0.0), (x: 2.0, y: 1.0), (x: 1.0, y: 1.0)];
+        let geometries = [
+            (Some("a\"".to_string()), Geometry::Polygon(left)),
+            (None, Geometry::Polygon(right)),
+            (None, Geometry::Point(point!(x: 0.5, y: -1.0))),
+        ];
+        let mut out = vec![];
+        write_topojson(&mut out, &geometries).unwrap();
+        // The shared border is one arc, which the right square follows in reverse.
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"{"type":"Topology","objects":{"geometries":{"type":"GeometryCollection","geometries":["#,
+                r#"{"type":"Polygon","id":"a\"","arcs":[[0,1]]},"#,
+                r#"{"type":"Polygon","arcs":[[2,-1]]},"#,
+                r#"{"type":"Point","coordinates":[0.5,-1]}]}},"arcs":["#,
+                r#"[[1,0],[1,1]],[[1,1],[0,1],[0,0],[1,0]],[[1,0],[2,0],[2,1],[1,1]]]}"#,
+                "\n"
+            )
+        );
+    }
+}
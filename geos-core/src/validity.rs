@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use geo::line_intersection::{line_intersection, LineIntersection};
+use geo::{Area, BooleanOps};
+use geo_types::{Coord, Geometry, Line, LineString, MultiPolygon, Polygon};
+use itertools::Itertools;
+use rstar::primitives::{GeomWithData, Line as IndexedLine};
+use rstar::{RTree, RTreeObject};
+
+use crate::geom::{crosses_antimeridian, unwrap_polygon, wrap_polygon};
+
+/**
+ * A polygon that boolean operations can't be trusted with, e.g. a bowtie whose exterior crosses
+ * itself. Intersections of such polygons can panic or silently return the wrong area.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidPolygon {
+    pub reason: &'static str,
+    /// Where the problem was found, e.g. the point at which two edges cross.
+    pub location: Coord,
+}
+
+impl Display for InvalidPolygon {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid polygon: {} at POINT({} {})",
+            self.reason, self.location.x, self.location.y
+        )
+    }
+}
+
+impl Error for InvalidPolygon {}
+
+/**
+ * A polygon that encloses no area, e.g. `POLYGON EMPTY` or one whose exterior runs along a line, so
+ * there is nothing to partition or triangulate.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DegeneratePolygon;
+
+impl Display for DegeneratePolygon {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "degenerate polygon: it is empty or encloses no area")
+    }
+}
+
+impl Error for DegeneratePolygon {}
+
+/// An edge of a polygon, labeled with its ring and its position in the ring.
+type Edge = GeomWithData<IndexedLine<[f64; 2]>, (usize, usize)>;
+
+/**
+ * Checks that a polygon can be used in boolean operations: its coordinates are finite, each of its
+ * rings has at least 3 distinct points, and no two of its edges cross or overlap. Rings may touch
+ * themselves and each other at points. Polygons that cross the antimeridian are checked as they are
+ * drawn across it, rather than as their edges run across the map.
+ */
+pub fn check_polygon(polygon: &Polygon) -> Result<(), InvalidPolygon> {
+    let non_finite = polygon
+        .exterior()
+        .0
+        .iter()
+        .chain(polygon.interiors().iter().flat_map(|ring| &ring.0))
+        .find(|c| !c.x.is_finite() || !c.y.is_finite());
+    if let Some(&location) = non_finite {
+        return Err(InvalidPolygon {
+            reason: "coordinate is not finite",
+            location,
+        });
+    }
+
+    match crosses_antimeridian(polygon).then(|| unwrap_polygon(polygon)) {
+        Some(Some(unwrapped)) => check_planar_polygon(&unwrapped).map_err(|mut error| {
+            error.location.x -= (error.location.x / 360.0).round() * 360.0;
+            error
+        }),
+        _ => check_planar_polygon(polygon),
+    }
+}
+
+/**
+ * Checks that a polygon encloses some area. Polygons with non-finite coordinates don't.
+ */
+pub fn check_area(polygon: &Polygon) -> Result<(), DegeneratePolygon> {
+    match polygon.unsigned_area() > 0.0 {
+        true => Ok(()),
+        false => Err(DegeneratePolygon),
+    }
+}
+
+fn check_planar_polygon(polygon: &Polygon) -> Result<(), InvalidPolygon> {
+    let rings = std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .filter(|ring| !ring.0.is_empty())
+        .collect_vec();
+    for ring in &rings {
+        if ring.0.iter().unique_by(|&&c| coord_key(c)).count() < 3 {
+            return Err(InvalidPolygon {
+                reason: "ring has fewer than 3 distinct points",
+                location: ring.0[0],
+            });
+        }
+    }
+
+    let edges = rings
+        .iter()
+        .enumerate()
+        .flat_map(|(r, ring)| {
+            ring.lines()
+                .enumerate()
+                .filter(|(_, line)| line.start != line.end)
+                .map(move |(i, line)| Edge::new(indexed_line(line), (r, i)))
+        })
+        .collect_vec();
+    let tree = RTree::bulk_load(edges.clone());
+    for edge in &edges {
+        for other in tree
+            .locate_in_envelope_intersecting(&edge.envelope())
+            .filter(|other| other.data > edge.data)
+        {
+            match line_intersection(geo_line(edge), geo_line(other)) {
+                Some(LineIntersection::SinglePoint {
+                    intersection,
+                    is_proper: true,
+                }) => {
+                    return Err(InvalidPolygon {
+                        reason: match edge.data.0 == other.data.0 {
+                            true => "ring crosses itself",
+                            false => "rings cross",
+                        },
+                        location: intersection,
+                    })
+                }
+                Some(LineIntersection::Collinear { intersection }) => {
+                    return Err(InvalidPolygon {
+                        reason: "edges overlap",
+                        location: intersection.start,
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Repairs a polygon so that it passes `check_polygon`, returning the area it was meant to enclose.
+ * Non-finite coordinates are dropped, and each ring is split where it crosses itself into simple
+ * loops, which enclose the area covered an odd number of times, as a bowtie is split into its two
+ * triangles. The holes are then subtracted from the exterior. Valid polygons are returned as they
+ * are.
+ */
+pub fn repair_polygon(polygon: &Polygon) -> MultiPolygon {
+    if check_polygon(polygon).is_ok() {
+        return MultiPolygon::new(vec![polygon.clone()]);
+    }
+
+    let polygon = Polygon::new(
+        finite_ring(polygon.exterior()),
+        polygon.interiors().iter().map(finite_ring).collect_vec(),
+    );
+    match crosses_antimeridian(&polygon).then(|| unwrap_polygon(&polygon)) {
+        Some(Some(unwrapped)) => MultiPolygon::new(
+            repair_planar_polygon(&unwrapped)
+                .iter()
+                .flat_map(wrap_polygon)
+                .collect_vec(),
+        ),
+        _ => repair_planar_polygon(&polygon),
+    }
+}
+
+/**
+ * Repairs the invalid polygons of a geometry in place, as with `repair_polygon`. Returns how many
+ * polygons were repaired.
+ */
+pub fn repair_geometry(geometry: &mut Geometry) -> usize {
+    match geometry {
+        Geometry::Polygon(polygon) => {
+            if check_polygon(polygon).is_ok() {
+                return 0;
+            }
+            let mut repaired = repair_polygon(polygon);
+            *geometry = match repaired.0.len() {
+                1 => repaired.0.remove(0).into(),
+                _ => repaired.into(),
+            };
+            1
+        }
+        Geometry::MultiPolygon(mpoly) => {
+            let mut repaired = 0;
+            mpoly.0 = std::mem::take(&mut mpoly.0)
+                .into_iter()
+                .flat_map(|polygon| match check_polygon(&polygon) {
+                    Ok(()) => vec![polygon],
+                    Err(_) => {
+                        repaired += 1;
+                        repair_polygon(&polygon).0
+                    }
+                })
+                .collect_vec();
+            repaired
+        }
+        Geometry::GeometryCollection(collection) => {
+            collection.iter_mut().map(repair_geometry).sum()
+        }
+        _ => 0,
+    }
+}
+
+fn finite_ring(ring: &LineString) -> LineString {
+    ring.0
+        .iter()
+        .copied()
+        .filter(|c| c.x.is_finite() && c.y.is_finite())
+        .collect()
+}
+
+fn repair_planar_polygon(polygon: &Polygon) -> MultiPolygon {
+    polygon
+        .interiors()
+        .iter()
+        .fold(odd_coverage(polygon.exterior()), |area, hole| {
+            area.difference(&odd_coverage(hole))
+        })
+}
+
+/**
+ * The area that a ring winds around an odd number of times.
+ */
+fn odd_coverage(ring: &LineString) -> MultiPolygon {
+    simple_loops(ring)
+        .into_iter()
+        .fold(MultiPolygon::new(vec![]), |area, ring| {
+            area.xor(&MultiPolygon::new(vec![Polygon::new(ring, vec![])]))
+        })
+}
+
+/**
+ * Splits a ring into loops that don't cross themselves. The points where edges cross or overlap are
+ * inserted into both edges, and then the ring is walked, cutting off a loop whenever a point is
+ * revisited. Loops that enclose no area, like spikes, are dropped.
+ */
+fn simple_loops(ring: &LineString) -> Vec<LineString> {
+    let lines = ring
+        .lines()
+        .filter(|line| line.start != line.end)
+        .collect_vec();
+    let tree = RTree::bulk_load(
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| GeomWithData::new(indexed_line(*line), i))
+            .collect_vec(),
+    );
+    let mut splits = vec![vec![]; lines.len()];
+    for (i, line) in lines.iter().enumerate() {
+        for other in tree
+            .locate_in_envelope_intersecting(&indexed_line(*line).envelope())
+            .filter(|other| other.data > i)
+        {
+            let j = other.data;
+            let points = match line_intersection(*line, lines[j]) {
+                Some(LineIntersection::SinglePoint { intersection, .. }) => vec![intersection],
+                Some(LineIntersection::Collinear { intersection }) => {
+                    vec![intersection.start, intersection.end]
+                }
+                None => vec![],
+            };
+            splits[i].extend(&points);
+            splits[j].extend(&points);
+        }
+    }
+
+    let mut vertices = vec![];
+    for (line, points) in lines.iter().zip(&mut splits) {
+        let distance = |c: &Coord| (c.x - line.start.x).powi(2) + (c.y - line.start.y).powi(2);
+        points.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+        vertices.push(line.start);
+        vertices.extend(points.iter().copied());
+    }
+    vertices.extend(lines.first().map(|line| line.start));
+    vertices.dedup();
+
+    let mut loops = vec![];
+    let mut path: Vec<Coord> = vec![];
+    let mut seen = HashMap::new();
+    for vertex in vertices {
+        let Some(&start) = seen.get(&coord_key(vertex)) else {
+            seen.insert(coord_key(vertex), path.len());
+            path.push(vertex);
+            continue;
+        };
+        // The revisited point stays on the path, and the loop from it is cut off.
+        let mut cut = path.split_off(start + 1);
+        for c in &cut {
+            seen.remove(&coord_key(*c));
+        }
+        if cut.len() >= 2 {
+            cut.insert(0, vertex);
+            cut.push(vertex);
+            loops.push(LineString::new(cut));
+        }
+    }
+    loops
+}
+
+fn coord_key(c: Coord) -> (u64, u64) {
+    // Adding zero makes -0 and 0 the same point.
+    ((c.x + 0.0).to_bits(), (c.y + 0.0).to_bits())
+}
+
+fn indexed_line(line: Line) -> IndexedLine<[f64; 2]> {
+    IndexedLine::new(line.start.x_y().into(), line.end.x_y().into())
+}
+
+fn geo_line(edge: &Edge) -> Line {
+    let line = edge.geom();
+    Line::new(Coord::from(line.from), Coord::from(line.to))
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::Area;
+    use geo_types::{polygon, Coord, Geometry};
+
+    use super::{check_polygon, repair_geometry, repair_polygon, InvalidPolygon};
+
+    #[test]
+    fn test_check_and_repair_polygon() {
+        let square =
+            polygon![(x: 0.0, y: 0.0), (x: 2.0, y: 0.0), (x: 2.0, y: 2.0), (x: 0.0, y: 2.0)];
+        assert_eq!(check_polygon(&square), Ok(()));
+        assert_eq!(repair_polygon(&square).0, [square]);
+
+        // A bowtie crosses itself at its center, and is repaired into its two triangles.
+        let bowtie =
+            polygon![(x: 0.0, y: 0.0), (x: 2.0, y: 2.0), (x: 2.0, y: 0.0), (x: 0.0, y: 2.0)];
+        assert_eq!(
+            check_polygon(&bowtie),
+            Err(InvalidPolygon {
+                reason: "ring crosses itself",
+                location: Coord { x: 1.0, y: 1.0 },
+            })
+        );
+        let repaired = repair_polygon(&bowtie);
+        assert_eq!(repaired.0.len(), 2);
+        assert!(repaired.iter().all(|p| check_polygon(p).is_ok()));
+        assert!((repaired.unsigned_area() - 2.0).abs() < 1e-12);
+
+        // A spike doubles back along its own edge, and is dropped.
+        let spike = polygon![(x: 0.0, y: 0.0), (x: 2.0, y: 0.0), (x: 3.0, y: 0.0), (x: 2.0, y: 0.0), (x: 2.0, y: 2.0), (x: 0.0, y: 2.0)];
+        assert_eq!(check_polygon(&spike).unwrap_err().reason, "edges overlap");
+        let mut geometry = Geometry::Polygon(spike);
+        assert_eq!(repair_geometry(&mut geometry), 1);
+        assert_eq!(geometry.unsigned_area(), 4.0);
+
+        // A hole that crosses the exterior is cut out of it.
+        let holed = polygon!(
+            exterior: [(x: 0.0, y: 0.0), (x: 2.0, y: 0.0), (x: 2.0, y: 2.0), (x: 0.0, y: 2.0)],
+            interiors: [[(x: 1.0, y: 1.0), (x: 3.0, y: 1.0), (x: 3.0, y: 3.0), (x: 1.0, y: 3.0)]],
+        );
+        assert_eq!(check_polygon(&holed).unwrap_err().reason, "rings cross");
+        assert!((repair_polygon(&holed).unsigned_area() - 3.0).abs() < 1e-12);
+
+        let nan =
+            polygon![(x: 0.0, y: 0.0), (x: f64::NAN, y: 0.0), (x: 2.0, y: 2.0), (x: 0.0, y: 2.0)];
+        assert_eq!(
+            check_polygon(&nan).unwrap_err().reason,
+            "coordinate is not finite"
+        );
+        assert!((repair_polygon(&nan).unsigned_area() - 2.0).abs() < 1e-12);
+    }
+}
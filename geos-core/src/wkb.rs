@@ -0,0 +1,449 @@
+/*!
+ * Reading and writing of geometries as well-known binary (WKB), and its hex encoding as used by
+ * databases such as PostGIS.
+ */
+use std::error::Error;
+
+use geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon,
+};
+
+//==================================================
+// Reading.
+//==================================================
+/**
+ * Reads a WKB geometry in either byte order. Z and M values, in both the ISO and the PostGIS
+ * extended encodings, are dropped, as is the SRID of extended WKB.
+ */
+pub fn read_wkb(bytes: &[u8]) -> Result<Geometry, Box<dyn Error>> {
+    read_wkb_with_warnings(bytes, &mut vec![])
+}
+
+/**
+ * Like `read_wkb`, but reports dropped Z and M values and empty points by adding a message to
+ * `warnings`.
+ */
+pub fn read_wkb_with_warnings(
+    bytes: &[u8],
+    warnings: &mut Vec<String>,
+) -> Result<Geometry, Box<dyn Error>> {
+    let mut reader = WkbReader {
+        bytes,
+        offset: 0,
+        dropped_dimensions: 0,
+        dropped_points: 0,
+    };
+    let geometry = reader.geometry()?.ok_or("empty points are not supported")?;
+    if reader.offset != bytes.len() {
+        return Err(reader.error("unexpected bytes after the geometry"));
+    }
+    if reader.dropped_dimensions > 0 {
+        warnings.push(format!(
+            "dropped the Z and M values of {} coordinates",
+            reader.dropped_dimensions
+        ));
+    }
+    if reader.dropped_points > 0 {
+        warnings.push(format!("dropped {} empty points", reader.dropped_points));
+    }
+    Ok(geometry)
+}
+
+/**
+ * Decodes hex-encoded WKB, in upper or lower case, as written by databases.
+ */
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let hex = hex.trim();
+    let hex = hex.strip_prefix("\\x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) {
+        return Err("hex WKB has an odd number of digits".into());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| format!("invalid hex WKB at digit {}", i).into())
+        })
+        .collect()
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    /// The number of coordinates whose Z or M values were dropped.
+    dropped_dimensions: usize,
+    /// The number of empty points dropped from multipoints and collections.
+    dropped_points: usize,
+}
+
+/**
+ * The byte order and dimensions of a geometry, which its members may differ from.
+ */
+#[derive(Clone, Copy)]
+struct Header {
+    little_endian: bool,
+    /// The number of values per coordinate beyond x and y.
+    extra_dimensions: usize,
+}
+
+impl WkbReader<'_> {
+    /**
+     * Reads a geometry. Returns `None` for an empty point, which WKB encodes with NaN coordinates
+     * and `geo_types` cannot represent.
+     */
+    fn geometry(&mut self) -> Result<Option<Geometry>, Box<dyn Error>> {
+        let little_endian = match self.take(1)?[0] {
+            0 => false,
+            1 => true,
+            order => return Err(self.error(&format!("invalid byte order {}", order))),
+        };
+        let mut header = Header {
+            little_endian,
+            extra_dimensions: 0,
+        };
+        let code = self.u32(header)?;
+        // Extended WKB flags dimensions and the SRID in the high bits, and ISO WKB adds 1000 for
+        // Z, 2000 for M, and 3000 for both.
+        let (has_z, has_m, has_srid) = (
+            code & 0x8000_0000 != 0,
+            code & 0x4000_0000 != 0,
+            code & 0x2000_0000 != 0,
+        );
+        let code = code & 0x0fff_ffff;
+        let (kind, iso_dimensions) = (code % 1000, code / 1000);
+        header.extra_dimensions = match iso_dimensions {
+            0 => has_z as usize + has_m as usize,
+            1 | 2 => 1,
+            3 => 2,
+            _ => return Err(self.error(&format!("unknown geometry type {}", code))),
+        };
+        if has_srid {
+            self.u32(header)?;
+        }
+
+        let geometry = match kind {
+            1 => match self.coord(header)? {
+                Some(coord) => Geometry::Point(Point(coord)),
+                None => return Ok(None),
+            },
+            2 => Geometry::LineString(self.line_string(header)?),
+            3 => Geometry::Polygon(self.polygon(header)?),
+            4 => {
+                let members = self.members(header, |reader| match reader.geometry()? {
+                    Some(Geometry::Point(point)) => Ok(Some(point)),
+                    Some(_) => Err(reader.error("MULTIPOINT members must be points")),
+                    None => Ok(None),
+                })?;
+                Geometry::MultiPoint(MultiPoint(members))
+            }
+            5 => {
+                let members = self.members(header, |reader| match reader.geometry()? {
+                    Some(Geometry::LineString(line)) => Ok(Some(line)),
+                    _ => Err(reader.error("MULTILINESTRING members must be line strings")),
+                })?;
+                Geometry::MultiLineString(MultiLineString(members))
+            }
+            6 => {
+                let members = self.members(header, |reader| match reader.geometry()? {
+                    Some(Geometry::Polygon(polygon)) => Ok(Some(polygon)),
+                    _ => Err(reader.error("MULTIPOLYGON members must be polygons")),
+                })?;
+                Geometry::MultiPolygon(MultiPolygon(members))
+            }
+            7 => Geometry::GeometryCollection(GeometryCollection(
+                self.members(header, Self::geometry)?,
+            )),
+            _ => return Err(self.error(&format!("unsupported geometry type {}", code))),
+        };
+        Ok(Some(geometry))
+    }
+
+    /**
+     * Reads the members of a multi-geometry or collection, dropping empty points.
+     */
+    fn members<T>(
+        &mut self,
+        header: Header,
+        mut member: impl FnMut(&mut Self) -> Result<Option<T>, Box<dyn Error>>,
+    ) -> Result<Vec<T>, Box<dyn Error>> {
+        let count = self.count(header)?;
+        let mut members = Vec::with_capacity(count);
+        for _ in 0..count {
+            match member(self)? {
+                Some(m) => members.push(m),
+                None => self.dropped_points += 1,
+            }
+        }
+        Ok(members)
+    }
+
+    fn polygon(&mut self, header: Header) -> Result<Polygon, Box<dyn Error>> {
+        let count = self.count(header)?;
+        let mut rings = Vec::with_capacity(count);
+        for _ in 0..count {
+            rings.push(self.line_string(header)?);
+        }
+        let mut rings = rings.into_iter();
+        let exterior = rings.next().unwrap_or(LineString(vec![]));
+        Ok(Polygon::new(exterior, rings.collect()))
+    }
+
+    fn line_string(&mut self, header: Header) -> Result<LineString, Box<dyn Error>> {
+        let count = self.count(header)?;
+        let mut coords = Vec::with_capacity(count);
+        for _ in 0..count {
+            let coord = self
+                .coord(header)?
+                .ok_or_else(|| self.error("NaN coordinates in a line"))?;
+            coords.push(coord);
+        }
+        Ok(LineString(coords))
+    }
+
+    fn coord(&mut self, header: Header) -> Result<Option<Coord>, Box<dyn Error>> {
+        let x = self.f64(header)?;
+        let y = self.f64(header)?;
+        for _ in 0..header.extra_dimensions {
+            self.f64(header)?;
+        }
+        if x.is_nan() && y.is_nan() {
+            return Ok(None);
+        }
+        if header.extra_dimensions > 0 {
+            self.dropped_dimensions += 1;
+        }
+        Ok(Some(Coord { x, y }))
+    }
+
+    /**
+     * Reads the number of members, rings, or coordinates that follow, checking that the input
+     * could hold them before anything is allocated for them.
+     */
+    fn count(&mut self, header: Header) -> Result<usize, Box<dyn Error>> {
+        let count = self.u32(header)? as usize;
+        if count > self.bytes.len() - self.offset {
+            return Err(self.error(&format!("count {} exceeds the input", count)));
+        }
+        Ok(count)
+    }
+
+    fn u32(&mut self, header: Header) -> Result<u32, Box<dyn Error>> {
+        let bytes = self.take(4)?.try_into().expect("took 4 bytes");
+        Ok(match header.little_endian {
+            true => u32::from_le_bytes(bytes),
+            false => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn f64(&mut self, header: Header) -> Result<f64, Box<dyn Error>> {
+        let bytes = self.take(8)?.try_into().expect("took 8 bytes");
+        Ok(match header.little_endian {
+            true => f64::from_le_bytes(bytes),
+            false => f64::from_be_bytes(bytes),
+        })
+    }
+
+    fn take(&mut self, n: usize) -> Result<&[u8], Box<dyn Error>> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + n)
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        self.offset += n;
+        Ok(bytes)
+    }
+
+    fn error(&self, message: &str) -> Box<dyn Error> {
+        format!("invalid WKB at byte {}: {}", self.offset, message).into()
+    }
+}
+
+//==================================================
+// Writing.
+//==================================================
+/**
+ * Writes a geometry as little-endian 2D WKB. Lines, rectangles, and triangles are written as line
+ * strings and polygons, which WKB has no types for.
+ */
+pub fn write_wkb(geometry: &Geometry) -> Vec<u8> {
+    let mut out = vec![];
+    write_geometry(&mut out, geometry);
+    out
+}
+
+/**
+ * Writes a geometry as upper-case hex-encoded WKB, as PostGIS does.
+ */
+pub fn write_hex_wkb(geometry: &Geometry) -> String {
+    encode_hex(&write_wkb(geometry))
+}
+
+/**
+ * Writes a geometry as little-endian 2D extended WKB, as PostGIS does, with its SRID.
+ */
+pub fn write_ewkb(geometry: &Geometry, srid: u32) -> Vec<u8> {
+    let mut out = write_wkb(geometry);
+    // Only the outermost geometry has the SRID, flagged in the high bits of its type.
+    let code = u32::from_le_bytes([out[1], out[2], out[3], out[4]]) | 0x2000_0000;
+    out[1..5].copy_from_slice(&code.to_le_bytes());
+    out.splice(5..5, srid.to_le_bytes());
+    out
+}
+
+/**
+ * Writes a geometry as upper-case hex-encoded extended WKB with its SRID, as PostGIS does.
+ */
+pub fn write_hex_ewkb(geometry: &Geometry, srid: u32) -> String {
+    encode_hex(&write_ewkb(geometry, srid))
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn write_geometry(out: &mut Vec<u8>, geometry: &Geometry) {
+    out.push(1);
+    match geometry {
+        Geometry::Point(point) => {
+            write_u32(out, 1);
+            write_coord(out, point.0);
+        }
+        Geometry::Line(line) => {
+            write_u32(out, 2);
+            write_coords(out, &[line.start, line.end]);
+        }
+        Geometry::LineString(line) => {
+            write_u32(out, 2);
+            write_coords(out, &line.0);
+        }
+        Geometry::Polygon(polygon) => {
+            write_u32(out, 3);
+            write_polygon(out, polygon);
+        }
+        Geometry::Rect(rect) => {
+            write_u32(out, 3);
+            write_polygon(out, &rect.to_polygon());
+        }
+        Geometry::Triangle(triangle) => {
+            write_u32(out, 3);
+            write_polygon(out, &triangle.to_polygon());
+        }
+        Geometry::MultiPoint(points) => {
+            write_u32(out, 4);
+            write_u32(out, points.0.len() as u32);
+            for point in points {
+                write_geometry(out, &Geometry::Point(*point));
+            }
+        }
+        Geometry::MultiLineString(lines) => {
+            write_u32(out, 5);
+            write_u32(out, lines.0.len() as u32);
+            for line in lines {
+                out.push(1);
+                write_u32(out, 2);
+                write_coords(out, &line.0);
+            }
+        }
+        Geometry::MultiPolygon(polygons) => {
+            write_u32(out, 6);
+            write_u32(out, polygons.0.len() as u32);
+            for polygon in polygons {
+                out.push(1);
+                write_u32(out, 3);
+                write_polygon(out, polygon);
+            }
+        }
+        Geometry::GeometryCollection(collection) => {
+            write_u32(out, 7);
+            write_u32(out, collection.0.len() as u32);
+            for member in collection {
+                write_geometry(out, member);
+            }
+        }
+    }
+}
+
+fn write_polygon(out: &mut Vec<u8>, polygon: &Polygon) {
+    if polygon.exterior().0.is_empty() {
+        write_u32(out, 0);
+        return;
+    }
+    write_u32(out, 1 + polygon.interiors().len() as u32);
+    for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+        write_coords(out, &ring.0);
+    }
+}
+
+fn write_coords(out: &mut Vec<u8>, coords: &[Coord]) {
+    write_u32(out, coords.len() as u32);
+    for coord in coords {
+        write_coord(out, *coord);
+    }
+}
+
+fn write_coord(out: &mut Vec<u8>, coord: Coord) {
+    out.extend(coord.x.to_le_bytes());
+    out.extend(coord.y.to_le_bytes());
+}
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend(value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, Geometry};
+    use wkt::TryFromWkt;
+
+    use super::{
+        decode_hex, read_wkb, read_wkb_with_warnings, write_hex_ewkb, write_hex_wkb, write_wkb,
+    };
+
+    #[test]
+    fn test_wkb_round_trip() {
+        for wkt in [
+            "POINT(1 2)",
+            "LINESTRING(0 0,1 1,2 0)",
+            "POLYGON((0 0,4 0,4 4,0 0),(1 1,2 1,2 2,1 1))",
+            "MULTIPOINT((1 2),(3 4))",
+            "MULTIPOLYGON(((0 0,1 0,1 1,0 0)),((2 2,3 2,3 3,2 2)))",
+            "GEOMETRYCOLLECTION(POINT(1 2),LINESTRING(0 0,1 1))",
+        ] {
+            let geometry = Geometry::<f64>::try_from_wkt_str(wkt).unwrap();
+            assert_eq!(read_wkb(&write_wkb(&geometry)).unwrap(), geometry);
+        }
+    }
+
+    #[test]
+    fn test_read_wkb() {
+        // POINT(1 2) as written by PostGIS, in both byte orders.
+        let point = Geometry::Point(point!(x: 1.0, y: 2.0));
+        let little = "0101000000000000000000F03F0000000000000040";
+        assert_eq!(write_hex_wkb(&point), little);
+        let big = "00000000013FF00000000000004000000000000000";
+        assert_eq!(read_wkb(&decode_hex(big).unwrap()).unwrap(), point);
+
+        // POINT Z(1 2 3) in ISO and extended WKB with SRID 4326.
+        let mut warnings = vec![];
+        let iso = "01E9030000000000000000F03F00000000000000400000000000000840";
+        let iso = read_wkb_with_warnings(&decode_hex(iso).unwrap(), &mut warnings).unwrap();
+        assert_eq!(iso, point);
+        assert_eq!(warnings, ["dropped the Z and M values of 1 coordinates"]);
+        let ewkb = "01010000A0E6100000000000000000F03F00000000000000400000000000000840";
+        assert_eq!(read_wkb(&decode_hex(ewkb).unwrap()).unwrap(), point);
+
+        // POINT(1 2) with SRID 4326, as written by PostGIS, which has the SRID only once.
+        let ewkb = "0101000020E6100000000000000000F03F0000000000000040";
+        assert_eq!(write_hex_ewkb(&point, 4326), ewkb);
+        assert_eq!(read_wkb(&decode_hex(ewkb).unwrap()).unwrap(), point);
+        let collection = Geometry::GeometryCollection(vec![point.clone()].into());
+        let ewkb = "0107000020E6100000010000000101000000000000000000F03F0000000000000040";
+        assert_eq!(write_hex_ewkb(&collection, 4326), ewkb);
+
+        assert!(read_wkb(&decode_hex(&little[..20]).unwrap()).is_err());
+        assert!(decode_hex("01x1").is_err());
+        // A count larger than the input is rejected before allocating.
+        assert!(read_wkb(&decode_hex("0102000000FFFFFFFF").unwrap()).is_err());
+    }
+}
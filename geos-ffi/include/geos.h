@@ -0,0 +1,68 @@
+/*
+ * C bindings for geos, built as libgeos_ffi with `cargo build --release -p geos-ffi`.
+ *
+ * Geometries are passed in and returned as WKT strings. Results are returned as opaque
+ * GeosStrings lists that the caller must release with geos_strings_free. Functions that can fail
+ * return NULL and, if `error` is not NULL, set it to a message that the caller must release with
+ * geos_string_free. All functions are safe to call from multiple threads.
+ */
+#ifndef GEOS_FFI_H
+#define GEOS_FFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An owned list of NUL-terminated strings, such as cell IDs or WKT geometries. */
+typedef struct GeosStrings GeosStrings;
+
+/* The S2 cell IDs covering a geometry, as decimal strings. The level must be in 0..=30. A max_cells
+ * of 0 means no limit. */
+GeosStrings *geos_cover_s2(const char *wkt, uint8_t level, size_t max_cells, char **error);
+
+/* Cuts a polygon by the S2 cells covering it. The level must be in 0..=30. A max_cells of 0 means
+ * no limit. */
+GeosStrings *geos_cut_s2(const char *wkt, uint8_t level, size_t max_cells, char **error);
+
+/*
+ * The H3 cells covering a geometry, as hex strings. mode is one of the `geos h3 cover --mode`
+ * values, or NULL for "intersects".
+ */
+GeosStrings *geos_cover_h3(const char *wkt, uint8_t level, const char *mode, char **error);
+
+/* Cuts a geometry by the H3 cells covering it. */
+GeosStrings *geos_cut_h3(const char *wkt, uint8_t level, char **error);
+
+/* Converts S2 cell IDs, given as decimal strings, to polygons. */
+GeosStrings *geos_s2_cell_to_poly(const char *const *cells, size_t num_cells, char **error);
+
+/* Converts H3 cell indexes, given as hex strings, to polygons. */
+GeosStrings *geos_h3_cell_to_poly(const char *const *cells, size_t num_cells, char **error);
+
+/* Samples random points uniformly within a polygon, or over the whole globe if wkt is NULL. */
+GeosStrings *geos_sample_polygon(const char *wkt, size_t num_samples, uint64_t seed,
+                                 char **error);
+
+/* The number of strings in a list. */
+size_t geos_strings_len(const GeosStrings *strings);
+
+/*
+ * The string at an index of a list, or NULL if the index is out of bounds. The string is owned by
+ * the list and is valid until the list is freed.
+ */
+const char *geos_strings_get(const GeosStrings *strings, size_t index);
+
+/* Frees a list of strings. Freeing NULL does nothing. */
+void geos_strings_free(GeosStrings *strings);
+
+/* Frees an error message. Freeing NULL does nothing. */
+void geos_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GEOS_FFI_H */
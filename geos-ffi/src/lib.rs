@@ -0,0 +1,366 @@
+/*!
+ * C bindings for `geos-core`, declared in `include/geos.h`. Geometries are passed in and returned as
+ * WKT strings. Results are returned as opaque `GeosStrings` handles that the caller must release
+ * with `geos_strings_free`. Functions that can fail return NULL and, if `error` is not NULL, set it
+ * to a message that the caller must release with `geos_string_free`.
+ */
+use std::error::Error;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, UnwindSafe};
+use std::ptr;
+use std::str::FromStr;
+
+use geo_types::{Geometry, Point, Polygon};
+use geos_core::h3_cells::{
+    cut_geometry, get_h3_covering, h3_cells_to_polys, parse_containment_mode,
+};
+use geos_core::s2_cells::{cut_region, get_s2_covering, s2_cells_to_polys};
+use geos_core::samplers::{create_rng, GeoSampler, PolygonalSampler, UniformSampler};
+use h3o::{CellIndex, Resolution};
+use itertools::Itertools;
+use s2::{cell::Cell, cellid::CellID};
+use wkt::{ToWkt, TryFromWkt};
+
+/**
+ * An owned list of NUL-terminated strings, such as cell IDs or WKT geometries.
+ */
+pub struct GeosStrings(Vec<CString>);
+
+/**
+ * Computes the S2 cell IDs covering a geometry, as decimal strings. A `max_cells` of 0 means no
+ * limit. Equivalent to `geos s2 cover`.
+ *
+ * # Safety
+ * `wkt` must be a valid NUL-terminated string, and `error` must be NULL or valid for writes.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_cover_s2(
+    wkt: *const c_char,
+    level: u8,
+    max_cells: usize,
+    error: *mut *mut c_char,
+) -> *mut GeosStrings {
+    ffi_call(error, || {
+        let geometry = parse_wkt(wkt)?;
+        let cover = get_s2_covering(&geometry, level, max_cells_or_unlimited(max_cells))?;
+        Ok(cover.iter().map(|c| c.0.to_string()).collect_vec())
+    })
+}
+
+/**
+ * Cuts a polygon by the S2 cells covering it, returning WKT polygons. A `max_cells` of 0 means no
+ * limit. Equivalent to `geos s2 cut`.
+ *
+ * # Safety
+ * `wkt` must be a valid NUL-terminated string, and `error` must be NULL or valid for writes.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_cut_s2(
+    wkt: *const c_char,
+    level: u8,
+    max_cells: usize,
+    error: *mut *mut c_char,
+) -> *mut GeosStrings {
+    ffi_call(error, || {
+        let geometry = parse_wkt(wkt)?;
+        let cover = get_s2_covering(&geometry, level, max_cells_or_unlimited(max_cells))?
+            .into_iter()
+            .map(Cell::from)
+            .collect_vec();
+        let polygon: Polygon = geometry.try_into()?;
+        Ok(wkt_strings(cut_region(&polygon, &cover, true)?))
+    })
+}
+
+/**
+ * Computes the H3 cells covering a geometry, as hex strings. `mode` is one of the `geos h3 cover
+ * --mode` values, or NULL for `intersects`. Equivalent to `geos h3 cover`.
+ *
+ * # Safety
+ * `wkt` must be a valid NUL-terminated string, `mode` must be NULL or a valid NUL-terminated
+ * string, and `error` must be NULL or valid for writes.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_cover_h3(
+    wkt: *const c_char,
+    level: u8,
+    mode: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut GeosStrings {
+    ffi_call(error, || {
+        let geometry = parse_wkt(wkt)?;
+        let resolution = Resolution::try_from(level)?;
+        let mode = if mode.is_null() {
+            parse_containment_mode("intersectsboundary")
+        } else {
+            parse_containment_mode(to_str(mode)?)
+        };
+        let cells = get_h3_covering(&geometry, resolution, mode, true)?;
+        Ok(cells.iter().map(|c| c.to_string()).collect_vec())
+    })
+}
+
+/**
+ * Cuts a geometry by the H3 cells covering it, returning WKT polygons. Equivalent to
+ * `geos h3 cut`.
+ *
+ * # Safety
+ * `wkt` must be a valid NUL-terminated string, and `error` must be NULL or valid for writes.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_cut_h3(
+    wkt: *const c_char,
+    level: u8,
+    error: *mut *mut c_char,
+) -> *mut GeosStrings {
+    ffi_call(error, || {
+        let geometry = parse_wkt(wkt)?;
+        let resolution = Resolution::try_from(level)?;
+        let mode = parse_containment_mode("intersectsboundary");
+        let cover = get_h3_covering(&geometry, resolution, mode, true)?;
+        Ok(wkt_strings(cut_geometry(&geometry, &cover, true)?))
+    })
+}
+
+/**
+ * Converts S2 cell IDs, given as decimal strings, to WKT polygons. Equivalent to
+ * `geos s2 cell-to-poly`.
+ *
+ * # Safety
+ * `cells` must point to `num_cells` valid NUL-terminated strings, and `error` must be NULL or
+ * valid for writes.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_s2_cell_to_poly(
+    cells: *const *const c_char,
+    num_cells: usize,
+    error: *mut *mut c_char,
+) -> *mut GeosStrings {
+    ffi_call(error, || {
+        let cell_ids: Vec<_> = to_strs(cells, num_cells)?
+            .into_iter()
+            .map(|cell| {
+                let cell_id = CellID(cell.parse()?);
+                if !cell_id.is_valid() {
+                    return Err(format!("invalid S2 cell: {}", cell).into());
+                }
+                Ok::<_, Box<dyn Error>>(cell_id)
+            })
+            .try_collect()?;
+        Ok(wkt_strings(s2_cells_to_polys(cell_ids)))
+    })
+}
+
+/**
+ * Converts H3 cell indexes, given as hex strings, to WKT polygons. Equivalent to
+ * `geos h3 cell-to-poly`.
+ *
+ * # Safety
+ * `cells` must point to `num_cells` valid NUL-terminated strings, and `error` must be NULL or
+ * valid for writes.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_h3_cell_to_poly(
+    cells: *const *const c_char,
+    num_cells: usize,
+    error: *mut *mut c_char,
+) -> *mut GeosStrings {
+    ffi_call(error, || {
+        let cells: Vec<_> = to_strs(cells, num_cells)?
+            .into_iter()
+            .map(CellIndex::from_str)
+            .try_collect()?;
+        Ok(wkt_strings(h3_cells_to_polys(cells)))
+    })
+}
+
+/**
+ * Samples random WKT points uniformly within a polygon, or over the whole globe if `wkt` is NULL.
+ * Equivalent to `geos rand --seed <seed> point`.
+ *
+ * # Safety
+ * `wkt` must be NULL or a valid NUL-terminated string, and `error` must be NULL or valid for
+ * writes.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_sample_polygon(
+    wkt: *const c_char,
+    num_samples: usize,
+    seed: u64,
+    error: *mut *mut c_char,
+) -> *mut GeosStrings {
+    ffi_call(error, || {
+        let mut rng = create_rng(seed);
+        let points = if wkt.is_null() {
+            (0..num_samples)
+                .map(|_| Point::from(UniformSampler.sample_coord(&mut rng)))
+                .collect_vec()
+        } else {
+            let polygon: Polygon = parse_wkt(wkt)?.try_into()?;
+            let sampler = PolygonalSampler::new(polygon)?;
+            (0..num_samples)
+                .map(|_| Point::from(sampler.sample_coord(&mut rng)))
+                .collect_vec()
+        };
+        Ok(wkt_strings(points))
+    })
+}
+
+/**
+ * The number of strings in a list.
+ *
+ * # Safety
+ * `strings` must be a list returned by this library that has not been freed.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_strings_len(strings: *const GeosStrings) -> usize {
+    let strings = &*strings;
+    strings.0.len()
+}
+
+/**
+ * The string at an index of a list, or NULL if the index is out of bounds. The string is owned by
+ * the list and is valid until the list is freed.
+ *
+ * # Safety
+ * `strings` must be a list returned by this library that has not been freed.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_strings_get(
+    strings: *const GeosStrings,
+    index: usize,
+) -> *const c_char {
+    let strings = &*strings;
+    strings.0.get(index).map_or(ptr::null(), |s| s.as_ptr())
+}
+
+/**
+ * Frees a list of strings. Freeing NULL does nothing.
+ *
+ * # Safety
+ * `strings` must be NULL or a list returned by this library that has not already been freed.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_strings_free(strings: *mut GeosStrings) {
+    if !strings.is_null() {
+        drop(Box::from_raw(strings));
+    }
+}
+
+/**
+ * Frees an error message. Freeing NULL does nothing.
+ *
+ * # Safety
+ * `string` must be NULL or a string returned by this library that has not already been freed.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn geos_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+//==================================================
+// Utils.
+//==================================================
+/**
+ * Runs the body of an exported function, converting its result to an owned list or NULL with an
+ * error message. Panics are caught here, since unwinding into C is undefined behavior.
+ */
+unsafe fn ffi_call(
+    error: *mut *mut c_char,
+    f: impl FnOnce() -> Result<Vec<String>, Box<dyn Error>> + UnwindSafe,
+) -> *mut GeosStrings {
+    let result = catch_unwind(f).unwrap_or_else(|_| Err("geos panicked".into()));
+    let result = result.and_then(|strings| {
+        let strings: Vec<_> = strings.into_iter().map(CString::new).try_collect()?;
+        Ok(GeosStrings(strings))
+    });
+    match result {
+        Ok(strings) => Box::into_raw(Box::new(strings)),
+        Err(e) => {
+            if !error.is_null() {
+                // Messages never contain NUL bytes in practice, but must not fail here regardless.
+                let message = e.to_string().replace('\0', "");
+                *error = CString::new(message).unwrap_or_default().into_raw();
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Result<&'a str, Box<dyn Error>> {
+    if s.is_null() {
+        return Err("unexpected NULL string".into());
+    }
+    Ok(CStr::from_ptr(s).to_str()?)
+}
+
+unsafe fn to_strs<'a>(
+    strings: *const *const c_char,
+    len: usize,
+) -> Result<Vec<&'a str>, Box<dyn Error>> {
+    if len == 0 {
+        return Ok(vec![]);
+    }
+    if strings.is_null() {
+        return Err("unexpected NULL array".into());
+    }
+    std::slice::from_raw_parts(strings, len)
+        .iter()
+        .map(|&s| to_str(s))
+        .try_collect()
+}
+
+unsafe fn parse_wkt(wkt: *const c_char) -> Result<Geometry, Box<dyn Error>> {
+    Ok(Geometry::<f64>::try_from_wkt_str(to_str(wkt)?)?)
+}
+
+fn max_cells_or_unlimited(max_cells: usize) -> usize {
+    match max_cells {
+        0 => usize::MAX,
+        n => n,
+    }
+}
+
+fn wkt_strings<G: ToWkt<f64>>(geometries: impl IntoIterator<Item = G>) -> Vec<String> {
+    geometries.into_iter().map(|g| g.wkt_string()).collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn test_cover_and_errors() {
+        let wkt = CString::new("POINT(-122.4194 37.7749)").unwrap();
+        unsafe {
+            let mut error = ptr::null_mut();
+            let cells = geos_cover_s2(wkt.as_ptr(), 10, 0, &mut error);
+            assert!(!cells.is_null() && error.is_null());
+            assert_eq!(geos_strings_len(cells), 1);
+            let cell = CStr::from_ptr(geos_strings_get(cells, 0)).to_str().unwrap();
+            assert_eq!(cell, "9260950045757276160");
+            assert!(geos_strings_get(cells, 1).is_null());
+            geos_strings_free(cells);
+
+            let cells = geos_cut_s2(wkt.as_ptr(), 31, 0, &mut error);
+            assert!(cells.is_null());
+            let message = CStr::from_ptr(error).to_str().unwrap();
+            assert_eq!(message, "S2 level 31 is not in 0..=30");
+            geos_string_free(error);
+            error = ptr::null_mut();
+
+            let invalid = CString::new("nope").unwrap();
+            let cells = geos_cut_h3(invalid.as_ptr(), 6, &mut error);
+            assert!(cells.is_null() && !error.is_null());
+            geos_string_free(error);
+
+            // Errors may be ignored by passing NULL.
+            assert!(geos_cover_h3(invalid.as_ptr(), 6, ptr::null(), ptr::null_mut()).is_null());
+        }
+    }
+}
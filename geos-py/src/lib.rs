@@ -0,0 +1,171 @@
+/*!
+ * Python bindings for `geos-core`. Geometries are passed in and returned as WKT strings, and
+ * defaults mirror the corresponding `geos` CLI arguments so that notebook and commandline results
+ * agree.
+ */
+use std::error::Error;
+
+use geo_types::{Geometry, Point, Polygon};
+use geos_core::geom::partition_region;
+use geos_core::h3_cells::{cut_geometry, get_h3_covering, parse_containment_mode};
+use geos_core::s2_cells::{cut_region, get_s2_covering};
+use geos_core::samplers::{create_rng, GeoSampler, PolygonalSampler, UniformSampler};
+use h3o::Resolution;
+use itertools::Itertools;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use s2::cell::Cell;
+use wkt::{ToWkt, TryFromWkt};
+
+/**
+ * Computes the S2 cell IDs covering a geometry. Equivalent to `geos s2 cover`. Raises `ValueError`
+ * for levels outside 0..=30.
+ */
+#[pyfunction]
+#[pyo3(signature = (wkt, level=12, max_cells=None))]
+fn s2_cover(wkt: &str, level: u8, max_cells: Option<usize>) -> PyResult<Vec<u64>> {
+    let geometry = parse_wkt(wkt)?;
+    let cover =
+        get_s2_covering(&geometry, level, max_cells.unwrap_or(usize::MAX)).map_err(value_error)?;
+    Ok(cover.iter().map(|c| c.0).collect_vec())
+}
+
+/**
+ * Cuts a polygon by the S2 cells covering it. Equivalent to `geos s2 cut`. Raises `ValueError` for
+ * levels outside 0..=30.
+ */
+#[pyfunction]
+#[pyo3(signature = (wkt, level=12, max_cells=None))]
+fn s2_cut(wkt: &str, level: u8, max_cells: Option<usize>) -> PyResult<Vec<String>> {
+    let geometry = parse_wkt(wkt)?;
+    let cover = get_s2_covering(&geometry, level, max_cells.unwrap_or(usize::MAX))
+        .map_err(value_error)?
+        .into_iter()
+        .map(Cell::from)
+        .collect_vec();
+    let polygon: Polygon = geometry.try_into().map_err(value_error)?;
+    Ok(wkt_strings(
+        cut_region(&polygon, &cover, true).map_err(value_error)?,
+    ))
+}
+
+/**
+ * Computes the H3 cells covering a geometry, as hex strings. Equivalent to `geos h3 cover`.
+ */
+#[pyfunction]
+#[pyo3(signature = (wkt, level=12, mode="intersectsboundary"))]
+fn h3_cover(wkt: &str, level: u8, mode: &str) -> PyResult<Vec<String>> {
+    let geometry = parse_wkt(wkt)?;
+    let resolution = Resolution::try_from(level).map_err(value_error)?;
+    let cells = get_h3_covering(&geometry, resolution, parse_containment_mode(mode), true)
+        .map_err(boxed_value_error)?;
+    Ok(cells.iter().map(|c| c.to_string()).collect_vec())
+}
+
+/**
+ * Cuts a geometry by the H3 cells covering it. Equivalent to `geos h3 cut`.
+ */
+#[pyfunction]
+#[pyo3(signature = (wkt, level=6))]
+fn h3_cut(wkt: &str, level: u8) -> PyResult<Vec<String>> {
+    let geometry = parse_wkt(wkt)?;
+    let resolution = Resolution::try_from(level).map_err(value_error)?;
+    let mode = parse_containment_mode("intersectsboundary");
+    let cover = get_h3_covering(&geometry, resolution, mode, true).map_err(boxed_value_error)?;
+    let cuts = cut_geometry(&geometry, &cover, true).map_err(boxed_value_error)?;
+    Ok(wkt_strings(cuts))
+}
+
+/**
+ * Samples random points, uniformly over the globe or within a polygon. Equivalent to
+ * `geos rand --seed <seed> point`.
+ */
+#[pyfunction]
+#[pyo3(signature = (wkt=None, num_samples=1, seed=0))]
+fn sample_points(wkt: Option<&str>, num_samples: u64, seed: u64) -> PyResult<Vec<String>> {
+    let mut rng = create_rng(seed);
+    let points = match wkt {
+        None => (0..num_samples)
+            .map(|_| Point::from(UniformSampler.sample_coord(&mut rng)))
+            .collect_vec(),
+        Some(wkt) => {
+            let polygon: Polygon = parse_wkt(wkt)?.try_into().map_err(value_error)?;
+            let sampler = PolygonalSampler::new(polygon).map_err(value_error)?;
+            (0..num_samples)
+                .map(|_| Point::from(sampler.sample_coord(&mut rng)))
+                .collect_vec()
+        }
+    };
+    Ok(wkt_strings(points))
+}
+
+/**
+ * Approximately partitions a polygon into uniform rectangular regions. Equivalent to
+ * `geos geom split`.
+ */
+#[pyfunction]
+#[pyo3(signature = (wkt, edge_proportion, threshold=None))]
+fn partition(wkt: &str, edge_proportion: f64, threshold: Option<f64>) -> PyResult<Vec<String>> {
+    let polygon: Polygon = parse_wkt(wkt)?.try_into().map_err(value_error)?;
+    Ok(wkt_strings(
+        partition_region(&polygon, edge_proportion, threshold, true).map_err(boxed_value_error)?,
+    ))
+}
+
+#[pymodule]
+fn geos_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(s2_cover, m)?)?;
+    m.add_function(wrap_pyfunction!(s2_cut, m)?)?;
+    m.add_function(wrap_pyfunction!(h3_cover, m)?)?;
+    m.add_function(wrap_pyfunction!(h3_cut, m)?)?;
+    m.add_function(wrap_pyfunction!(sample_points, m)?)?;
+    m.add_function(wrap_pyfunction!(partition, m)?)?;
+    Ok(())
+}
+
+//==================================================
+// Utils.
+//==================================================
+fn parse_wkt(wkt: &str) -> PyResult<Geometry> {
+    Geometry::<f64>::try_from_wkt_str(wkt).map_err(value_error)
+}
+
+fn wkt_strings<G: ToWkt<f64>>(geometries: impl IntoIterator<Item = G>) -> Vec<String> {
+    geometries.into_iter().map(|g| g.wkt_string()).collect_vec()
+}
+
+fn value_error<E: Error>(e: E) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+fn boxed_value_error(e: Box<dyn Error>) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::exceptions::PyValueError;
+    use pyo3::Python;
+
+    use super::{s2_cover, s2_cut};
+
+    #[test]
+    fn test_s2_levels() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let point = "POINT(-122.4194 37.7749)";
+            assert_eq!(s2_cover(point, 10, None).unwrap(), [9260950045757276160]);
+            for level in [31, 255] {
+                let error = s2_cover(point, level, None).unwrap_err();
+                assert!(error.is_instance_of::<PyValueError>(py));
+                assert_eq!(
+                    error.value(py).to_string(),
+                    format!("S2 level {} is not in 0..=30", level)
+                );
+                let polygon = "POLYGON((0 0,1 0,1 1,0 0))";
+                let error = s2_cut(polygon, level, None).unwrap_err();
+                assert!(error.is_instance_of::<PyValueError>(py));
+            }
+        });
+    }
+}
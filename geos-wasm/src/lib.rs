@@ -0,0 +1,201 @@
+/*!
+ * JavaScript bindings for `geos-core`, built with `wasm-pack build geos-wasm`. Geometries are passed
+ * in and returned as WKT strings, and 64-bit S2 cell IDs as decimal strings since JS numbers cannot
+ * represent them exactly. Defaults mirror the corresponding `geos` CLI arguments, so that results
+ * agree between the browser and the commandline.
+ */
+use std::error::Error;
+use std::str::FromStr;
+
+use geo_types::{Geometry, Point, Polygon};
+use geos_core::h3_cells::{cut_geometry, get_h3_covering, h3_cell_to_poly, parse_containment_mode};
+use geos_core::s2_cells::{cut_region, get_s2_covering, s2_cell_to_poly, InvalidS2Level};
+use geos_core::samplers::{create_rng, GeoSampler, PolygonalSampler, UniformSampler};
+use h3o::{CellIndex, Resolution};
+use itertools::Itertools;
+use s2::{cell::Cell, cellid::CellID};
+use wasm_bindgen::prelude::*;
+use wkt::{ToWkt, TryFromWkt};
+
+/**
+ * Computes the S2 cell IDs covering a geometry. Equivalent to `geos s2 cover`. Throws for levels
+ * outside 0..=30.
+ */
+#[wasm_bindgen(js_name = s2Cover)]
+pub fn s2_cover(
+    wkt: &str,
+    level: Option<u8>,
+    max_cells: Option<usize>,
+) -> Result<Vec<String>, JsError> {
+    let geometry = parse_wkt(wkt)?;
+    let cover = s2_covering(&geometry, level, max_cells).map_err(js_error)?;
+    Ok(cover.iter().map(|c| c.0.to_string()).collect_vec())
+}
+
+/**
+ * Cuts a polygon by the S2 cells covering it. Equivalent to `geos s2 cut`. Throws for levels outside
+ * 0..=30.
+ */
+#[wasm_bindgen(js_name = s2Cut)]
+pub fn s2_cut(
+    wkt: &str,
+    level: Option<u8>,
+    max_cells: Option<usize>,
+) -> Result<Vec<String>, JsError> {
+    let geometry = parse_wkt(wkt)?;
+    let cover = s2_covering(&geometry, level, max_cells)
+        .map_err(js_error)?
+        .into_iter()
+        .map(Cell::from)
+        .collect_vec();
+    let polygon: Polygon = geometry.try_into().map_err(js_error)?;
+    Ok(wkt_strings(
+        cut_region(&polygon, &cover, true).map_err(js_error)?,
+    ))
+}
+
+/**
+ * Converts an S2 cell ID to its polygon. Equivalent to `geos s2 cell-to-poly`.
+ */
+#[wasm_bindgen(js_name = s2CellToPoly)]
+pub fn s2_cell_to_wkt(cell: &str) -> Result<String, JsError> {
+    let cell_id = CellID(cell.parse().map_err(js_error)?);
+    if !cell_id.is_valid() {
+        return Err(JsError::new(&format!("invalid S2 cell: {}", cell)));
+    }
+    Ok(s2_cell_to_poly(&cell_id.into()).wkt_string())
+}
+
+/**
+ * Computes the H3 cells covering a geometry, as hex strings. Equivalent to `geos h3 cover`.
+ */
+#[wasm_bindgen(js_name = h3Cover)]
+pub fn h3_cover(
+    wkt: &str,
+    level: Option<u8>,
+    mode: Option<String>,
+) -> Result<Vec<String>, JsError> {
+    let geometry = parse_wkt(wkt)?;
+    let resolution = Resolution::try_from(level.unwrap_or(12)).map_err(js_error)?;
+    let mode = parse_containment_mode(mode.as_deref().unwrap_or("intersectsboundary"));
+    let cells = get_h3_covering(&geometry, resolution, mode, true).map_err(boxed_js_error)?;
+    Ok(cells.iter().map(|c| c.to_string()).collect_vec())
+}
+
+/**
+ * Cuts a geometry by the H3 cells covering it. Equivalent to `geos h3 cut`.
+ */
+#[wasm_bindgen(js_name = h3Cut)]
+pub fn h3_cut(wkt: &str, level: Option<u8>) -> Result<Vec<String>, JsError> {
+    let geometry = parse_wkt(wkt)?;
+    let resolution = Resolution::try_from(level.unwrap_or(6)).map_err(js_error)?;
+    let mode = parse_containment_mode("intersectsboundary");
+    let cover = get_h3_covering(&geometry, resolution, mode, true).map_err(boxed_js_error)?;
+    let cuts = cut_geometry(&geometry, &cover, true).map_err(boxed_js_error)?;
+    Ok(wkt_strings(cuts))
+}
+
+/**
+ * Converts an H3 cell index to its polygon. Equivalent to `geos h3 cell-to-poly`.
+ */
+#[wasm_bindgen(js_name = h3CellToPoly)]
+pub fn h3_cell_to_wkt(cell: &str) -> Result<String, JsError> {
+    let cell = CellIndex::from_str(cell).map_err(js_error)?;
+    Ok(h3_cell_to_poly(&cell).wkt_string())
+}
+
+/**
+ * Samples random points, uniformly over the globe or within a polygon. Equivalent to
+ * `geos rand --seed <seed> point`.
+ */
+#[wasm_bindgen(js_name = samplePoints)]
+pub fn sample_points(
+    wkt: Option<String>,
+    num_samples: Option<u32>,
+    seed: Option<u32>,
+) -> Result<Vec<String>, JsError> {
+    let mut rng = create_rng(seed.unwrap_or(0) as u64);
+    let num_samples = num_samples.unwrap_or(1);
+    let points = match wkt {
+        None => (0..num_samples)
+            .map(|_| Point::from(UniformSampler.sample_coord(&mut rng)))
+            .collect_vec(),
+        Some(wkt) => {
+            let polygon: Polygon = parse_wkt(&wkt)?.try_into().map_err(js_error)?;
+            let sampler = PolygonalSampler::new(polygon).map_err(js_error)?;
+            (0..num_samples)
+                .map(|_| Point::from(sampler.sample_coord(&mut rng)))
+                .collect_vec()
+        }
+    };
+    Ok(wkt_strings(points))
+}
+
+//==================================================
+// Utils.
+//==================================================
+fn s2_covering(
+    geometry: &Geometry,
+    level: Option<u8>,
+    max_cells: Option<usize>,
+) -> Result<Vec<CellID>, InvalidS2Level> {
+    get_s2_covering(
+        geometry,
+        level.unwrap_or(12),
+        max_cells.unwrap_or(usize::MAX),
+    )
+}
+
+fn parse_wkt(wkt: &str) -> Result<Geometry, JsError> {
+    Geometry::<f64>::try_from_wkt_str(wkt).map_err(js_error)
+}
+
+fn wkt_strings<G: ToWkt<f64>>(geometries: impl IntoIterator<Item = G>) -> Vec<String> {
+    geometries.into_iter().map(|g| g.wkt_string()).collect_vec()
+}
+
+fn js_error<E: Error>(e: E) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+fn boxed_js_error(e: Box<dyn Error>) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, Geometry};
+    use geos_core::s2_cells::InvalidS2Level;
+
+    use super::{s2_cover, s2_covering};
+
+    #[test]
+    fn test_s2_levels() {
+        let point = "POINT(-122.4194 37.7749)";
+        assert_eq!(
+            s2_cover(point, Some(10), None).unwrap(),
+            ["9260950045757276160"]
+        );
+        // `JsError` can't be created outside of wasm, so errors are checked before conversion here.
+        let geometry = Geometry::Point(point!(x: -122.4194, y: 37.7749));
+        assert_eq!(
+            s2_covering(&geometry, Some(31), None),
+            Err(InvalidS2Level(31))
+        );
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::{s2_cover, s2_cut};
+
+    #[wasm_bindgen_test]
+    fn test_s2_level_errors() {
+        for level in [31, 255] {
+            assert!(s2_cover("POINT(-122.4194 37.7749)", Some(level), None).is_err());
+            assert!(s2_cut("POLYGON((0 0,1 0,1 1,0 0))", Some(level), None).is_err());
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use itertools::Itertools;
+use serde_json::json;
+
+use crate::error::warn;
+use crate::run_captured;
+use crate::stdin::append_stdin;
+use crate::template::{write_output, OutputTemplate};
+
+/// The placeholders of an output template that name the input file.
+const INPUT_PLACEHOLDERS: [&str; 3] = ["id", "stem", "name"];
+
+/**
+ * Runs a command once for each file in `dir` whose name matches `glob`, with the file added to its
+ * data arguments as `@path`. Files are run in name order. Each file's output is written to the path
+ * given by `template`, or without one, to `out` as one `{"file": <path>, "output": [<lines>...]}`
+ * object per line. `args` are the full commandline arguments.
+ */
+pub fn run_batch(
+    args: &[String],
+    dir: &Path,
+    glob: &str,
+    template: Option<&str>,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let template = template
+        .map(|template| OutputTemplate::new(template, args, &INPUT_PLACEHOLDERS))
+        .transpose()?;
+    let patterns = expand_braces(glob);
+    let mut files = vec![];
+    for entry in fs::read_dir(dir).map_err(|e| format!("cannot read {}: {}", dir.display(), e))? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_file() && patterns.iter().any(|p| matches_glob(p, &name)) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    if files.is_empty() {
+        warn(format!("no files in {} match {}", dir.display(), glob))?;
+    }
+
+    let args = &args[1.min(args.len())..];
+    for file in files {
+        let mut file_args = args.to_vec();
+        append_stdin(&mut file_args, &format!("@{}", file.display()));
+        tracing::info!("running {}", file.display());
+        let output = run_captured(file_args)
+            .inspect_err(|_| eprintln!("error: {} failed", file.display()))?;
+
+        match &template {
+            Some(template) => write_output(&output_path(template, &file), &output)?,
+            None => {
+                let lines = output.lines().collect_vec();
+                let group = json!({"file": file.display().to_string(), "output": lines});
+                writeln!(out, "{}", group)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * The output path for an input file, with `{id}` and `{stem}` in the template replaced by the file
+ * name without its extension, and `{name}` by the whole file name.
+ */
+fn output_path(template: &OutputTemplate, file: &Path) -> PathBuf {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    template.render(&[("id", &stem), ("stem", &stem), ("name", &name)])
+}
+
+/**
+ * Expands `{a,b}` alternatives in a glob into one pattern per alternative.
+ */
+fn expand_braces(glob: &str) -> Vec<String> {
+    let Some((prefix, rest)) = glob.split_once('{') else {
+        return vec![glob.to_string()];
+    };
+    let Some((alternatives, suffix)) = rest.split_once('}') else {
+        return vec![glob.to_string()];
+    };
+    alternatives
+        .split(',')
+        .flat_map(|alternative| expand_braces(&format!("{}{}{}", prefix, alternative, suffix)))
+        .collect()
+}
+
+/**
+ * Whether a file name matches a glob, where `*` matches any characters and `?` any one character.
+ */
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect_vec();
+    let name = name.chars().collect_vec();
+    // The positions in the pattern and name to resume from when the last `*` takes one more
+    // character.
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{expand_braces, matches_glob, output_path, INPUT_PLACEHOLDERS};
+    use crate::template::OutputTemplate;
+
+    #[test]
+    fn test_globs() {
+        let patterns = expand_braces("*.{wkt,geojson}");
+        assert_eq!(patterns, ["*.wkt", "*.geojson"]);
+        let matches = |name| patterns.iter().any(|p| matches_glob(p, name));
+        assert!(matches("county.wkt"));
+        assert!(matches("county.2024.geojson"));
+        assert!(!matches("county.wkt.bak"));
+        assert!(!matches("county.json"));
+        assert!(matches_glob("tract_??.wkt", "tract_06.wkt"));
+        assert!(!matches_glob("tract_??.wkt", "tract_6.wkt"));
+        assert!(matches_glob("*a*b", "xaxxb"));
+
+        let args = ["geos", "--input-dir", "in", "h3", "cover", "-l", "5"].map(String::from);
+        let template = OutputTemplate::new("out/{stem}_{level}.txt", &args, &INPUT_PLACEHOLDERS);
+        assert_eq!(
+            output_path(&template.unwrap(), Path::new("in/county.wkt")),
+            Path::new("out/county_5.txt")
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::error::Error;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use clap::{Args, Subcommand};
+use clap_stdin::MaybeStdin;
+use geo_types::Polygon;
+use geos_core::h3_cells::{cut_geometry, get_h3_covering};
+use geos_core::s2_cells::{cut_region, get_s2_covering};
+use geos_core::samplers::{create_rng, GeoSampler, PolygonalSampler, UniformSampler};
+use geos_core::tiles::get_tile_covering;
+use h3o::{geom::ContainmentMode, Resolution};
+use itertools::Itertools;
+use s2::cell::Cell;
+use serde_json::{json, Value};
+
+use crate::format::{parse_geometry, RunContext};
+use crate::pyramid_cmd::{check_level, Grid};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(
+    about = "Time operations in-process, without process startup noise. Outputs one JSON object of timing statistics in milliseconds per benchmark."
+)]
+#[command(args_conflicts_with_subcommands = false)]
+#[command(arg_required_else_help = true)]
+pub struct BenchArgs {
+    #[arg(
+        short,
+        long,
+        global = true,
+        default_value_t = 100,
+        help = "The number of timed iterations of each benchmark."
+    )]
+    iterations: usize,
+
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 1,
+        help = "The number of untimed iterations to run before timing."
+    )]
+    warmup: usize,
+
+    #[command(subcommand)]
+    command: Option<BenchCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BenchCommands {
+    #[command(
+        about = "Time coverings of a geometry, once per combination of grid and level. The output size is the number of cells."
+    )]
+    #[command(arg_required_else_help = true)]
+    Cover {
+        #[arg(
+            last = true,
+            help = "A valid WKT string encoding the geometry to cover."
+        )]
+        wkt: MaybeStdin<String>,
+
+        #[arg(
+            short,
+            long,
+            required = true,
+            value_delimiter = ',',
+            help = "Comma-separated grid systems to compare."
+        )]
+        grid: Vec<Grid>,
+
+        #[arg(
+            short,
+            long,
+            required = true,
+            value_delimiter = ',',
+            help = "Comma-separated levels to compare."
+        )]
+        level: Vec<u8>,
+    },
+
+    #[command(
+        about = "Time coverings followed by cutting of a polygon, once per combination of grid and level. The output size is the number of pieces."
+    )]
+    #[command(arg_required_else_help = true)]
+    Cut {
+        #[arg(last = true, help = "A valid WKT string encoding the polygon to cut.")]
+        wkt: MaybeStdin<String>,
+
+        #[arg(
+            short,
+            long,
+            required = true,
+            value_delimiter = ',',
+            help = "Comma-separated grid systems to compare. XYZ tiles are not supported."
+        )]
+        grid: Vec<Grid>,
+
+        #[arg(
+            short,
+            long,
+            required = true,
+            value_delimiter = ',',
+            help = "Comma-separated levels to compare."
+        )]
+        level: Vec<u8>,
+    },
+
+    #[command(about = "Time random point sampling, as with `rand point`.")]
+    Sample {
+        #[arg(
+            short,
+            long,
+            help = "A WKT POLYGON to sample within. Samples uniformly over the globe if omitted."
+        )]
+        wkt: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 1000,
+            help = "Number of samples drawn per iteration."
+        )]
+        num_samples: u64,
+
+        #[arg(short, long, default_value_t = 0, help = "Random seed to use")]
+        seed: u64,
+    },
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_bench_subcommand(
+    bench: &BenchArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    if bench.iterations == 0 {
+        return Err("--iterations must be positive".into());
+    }
+    let run = |f: &mut dyn FnMut() -> Result<usize, Box<dyn Error>>| {
+        time_iterations(bench.iterations, bench.warmup, f)
+    };
+
+    match &bench.command {
+        Some(BenchCommands::Cover { wkt, grid, level }) => {
+            let geometry = parse_geometry(wkt, ctx)?;
+            for (grid, level) in grid.iter().cartesian_product(level) {
+                check_level(grid, *level)?;
+                let level = *level;
+                let (output_size, timings) = match grid {
+                    Grid::S2 => {
+                        run(&mut || Ok(get_s2_covering(&geometry, level, usize::MAX)?.len()))
+                    }
+                    Grid::H3 => {
+                        let resolution = Resolution::try_from(level)?;
+                        run(&mut || {
+                            Ok(get_h3_covering(
+                                &geometry,
+                                resolution,
+                                ContainmentMode::IntersectsBoundary,
+                                true,
+                            )?
+                            .len())
+                        })
+                    }
+                    Grid::Xyz => run(&mut || Ok(get_tile_covering(&geometry, level).len())),
+                }?;
+                let params =
+                    json!({"operation": "cover", "grid": grid.to_string(), "level": level});
+                writeln!(out, "{}", fmt_timings(params, output_size, &timings))?;
+            }
+        }
+
+        Some(BenchCommands::Cut { wkt, grid, level }) => {
+            let geometry = parse_geometry(wkt, ctx)?;
+            let polygon: Polygon = geometry.clone().try_into()?;
+            for (grid, level) in grid.iter().cartesian_product(level) {
+                check_level(grid, *level)?;
+                let level = *level;
+                let (output_size, timings) = match grid {
+                    Grid::S2 => run(&mut || {
+                        let cover = get_s2_covering(&geometry, level, usize::MAX)?
+                            .into_iter()
+                            .map(Cell::from)
+                            .collect_vec();
+                        Ok(cut_region(&polygon, &cover, true)?.len())
+                    }),
+                    Grid::H3 => {
+                        let resolution = Resolution::try_from(level)?;
+                        run(&mut || {
+                            let cover = get_h3_covering(
+                                &geometry,
+                                resolution,
+                                ContainmentMode::IntersectsBoundary,
+                                true,
+                            )?;
+                            Ok(cut_geometry(&geometry, &cover, true)?.len())
+                        })
+                    }
+                    Grid::Xyz => Err("XYZ tiles cannot be used for cutting".into()),
+                }?;
+                let params = json!({"operation": "cut", "grid": grid.to_string(), "level": level});
+                writeln!(out, "{}", fmt_timings(params, output_size, &timings))?;
+            }
+        }
+
+        Some(BenchCommands::Sample {
+            wkt,
+            num_samples,
+            seed,
+        }) => {
+            let mut rng = create_rng(*seed);
+            let (output_size, timings) = match wkt {
+                None => run(&mut || {
+                    let coords = (0..*num_samples)
+                        .map(|_| UniformSampler.sample_coord(&mut rng))
+                        .collect_vec();
+                    Ok(coords.len())
+                }),
+                Some(wkt) => {
+                    let polygon: Polygon = parse_geometry(wkt, ctx)?.try_into()?;
+                    // Building the sampler is part of every `rand point` invocation.
+                    run(&mut || {
+                        let sampler = PolygonalSampler::new(polygon.clone())?;
+                        let coords = (0..*num_samples)
+                            .map(|_| sampler.sample_coord(&mut rng))
+                            .collect_vec();
+                        Ok(coords.len())
+                    })
+                }
+            }?;
+            let params = json!({"operation": "sample", "polygonal": wkt.is_some()});
+            writeln!(out, "{}", fmt_timings(params, output_size, &timings))?;
+        }
+
+        None => {}
+    }
+    Ok(())
+}
+
+/**
+ * Runs an operation returning its output size for some untimed warmup iterations, and then times
+ * it for the given number of iterations. Returns the output size of the last iteration and the
+ * sorted iteration times.
+ */
+fn time_iterations(
+    iterations: usize,
+    warmup: usize,
+    operation: &mut dyn FnMut() -> Result<usize, Box<dyn Error>>,
+) -> Result<(usize, Vec<Duration>), Box<dyn Error>> {
+    for _ in 0..warmup {
+        operation()?;
+    }
+    let mut output_size = 0;
+    let mut timings = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        output_size = operation()?;
+        timings.push(start.elapsed());
+    }
+    timings.sort();
+    Ok((output_size, timings))
+}
+
+/**
+ * Adds timing statistics in milliseconds to a JSON object of benchmark parameters. Percentiles use
+ * the nearest-rank method.
+ */
+fn fmt_timings(mut params: Value, output_size: usize, timings: &[Duration]) -> Value {
+    let ms = |d: &Duration| d.as_secs_f64() * 1000.0;
+    let percentile = |p: f64| {
+        let rank = ((p / 100.0 * timings.len() as f64).ceil() as usize).max(1);
+        ms(&timings[rank - 1])
+    };
+    let mean = timings.iter().map(ms).sum::<f64>() / timings.len() as f64;
+    let stats = json!({
+        "iterations": timings.len(),
+        "output_size": output_size,
+        "min_ms": ms(&timings[0]),
+        "mean_ms": mean,
+        "p50_ms": percentile(50.0),
+        "p90_ms": percentile(90.0),
+        "p99_ms": percentile(99.0),
+        "max_ms": ms(&timings[timings.len() - 1]),
+    });
+    if let (Some(params), Value::Object(stats)) = (params.as_object_mut(), stats) {
+        params.extend(stats);
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::run_captured;
+
+    fn run(args: &str) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+        let output = run_captured(shlex::split(args).unwrap())?;
+        Ok(output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn test_bench_output() {
+        let results = run("bench -i 3 --warmup 0 cover -g s2,h3 -l 5,6 -- 'POINT(1 1)'").unwrap();
+        // One result per combination of grid and level, in order.
+        let params = results
+            .iter()
+            .map(|r| (r["grid"].as_str().unwrap(), r["level"].as_u64().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(params, [("s2", 5), ("s2", 6), ("h3", 5), ("h3", 6)]);
+        for result in &results {
+            assert_eq!(result["operation"], "cover");
+            assert_eq!(result["iterations"], 3);
+            assert_eq!(result["output_size"], 1);
+            let ms = |key: &str| result[key].as_f64().unwrap();
+            assert!(ms("min_ms") <= ms("p50_ms") && ms("p50_ms") <= ms("p90_ms"));
+            assert!(ms("p90_ms") <= ms("p99_ms") && ms("p99_ms") <= ms("max_ms"));
+            assert!(ms("min_ms") <= ms("mean_ms") && ms("mean_ms") <= ms("max_ms"));
+        }
+
+        let results = run("bench -i 2 sample -n 10").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["polygonal"], false);
+        assert_eq!(results[0]["output_size"], 10);
+
+        assert!(run("bench -i 0 sample").is_err());
+        assert!(run("bench cut -g xyz -l 5 -- 'POLYGON((0 0,1 0,1 1,0 0))'").is_err());
+        assert!(run("bench cover -g s2 -l 31 -- 'POINT(1 1)'").is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::format::{InputOptions, OutputOptions};
+use crate::Commands;
+
+/**
+ * An on-disk cache of command outputs, keyed by a hash of the parsed command and its arguments.
+ * Only commands whose output depends on nothing but their arguments and the `@file`s they read
+ * geometries from are cached, which excludes anything reading other files, clocks, or the network.
+ */
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/**
+ * A cached command output, with what its run reported besides the output: the number of results,
+ * which `--strict` checks, and the warnings written to stderr. The latter two are stored as JSON in
+ * a file next to the output.
+ */
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    #[serde(skip)]
+    pub output: Vec<u8>,
+    pub results: Option<usize>,
+    pub warnings: Vec<String>,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /**
+     * Whether a command's output can be cached: the S2 and H3 commands other than those reading
+     * cells from files, and pyramids of coverings.
+     */
+    pub fn is_cacheable(command: &Commands) -> bool {
+        match command {
+            Commands::S2(s2) => s2.is_cacheable(),
+            Commands::H3(h3) => h3.is_cacheable(),
+            Commands::Pyramid(_) => true,
+            _ => false,
+        }
+    }
+
+    /**
+     * The cache key of a command, the contents of the `@file`s it reads geometries from, how its
+     * geometry arguments are read, and how its results are written. The version is included so
+     * that upgrades never serve stale results.
+     */
+    pub fn key(
+        command: &Commands,
+        input: &InputOptions,
+        output: &OutputOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update(format!("{:?}", command));
+        for path in geometry_args(command)
+            .into_iter()
+            .filter_map(|arg| arg.strip_prefix('@'))
+        {
+            let contents =
+                fs::read(path).map_err(|e| format!("cannot read file {}: {}", path, e))?;
+            hasher.update(Sha256::digest(contents));
+        }
+        hasher.update(format!("{:?}", input));
+        hasher.update(format!("{:?}", output));
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect())
+    }
+
+    /**
+     * The entry stored under a key, if both its output and its metadata have been written.
+     */
+    pub fn get(&self, key: &str) -> Result<Option<CacheEntry>, Box<dyn Error>> {
+        let (Some(metadata), Some(output)) = (self.read(&metadata_name(key))?, self.read(key)?)
+        else {
+            return Ok(None);
+        };
+        let mut entry: CacheEntry = serde_json::from_slice(&metadata)
+            .map_err(|e| format!("cannot read cache entry {}: {}", key, e))?;
+        entry.output = output;
+        Ok(Some(entry))
+    }
+
+    /**
+     * Stores an entry. Each file is written to a temporary file and then renamed, so that
+     * concurrent invocations never read a partially written entry.
+     */
+    pub fn put(&self, key: &str, entry: &CacheEntry) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("cannot create cache dir {}: {}", self.dir.display(), e))?;
+        self.write(&metadata_name(key), &serde_json::to_vec(entry)?)?;
+        self.write(key, &entry.output)
+    }
+
+    fn read(&self, name: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        match fs::read(self.dir.join(name)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("cannot read cache entry {}: {}", name, e).into()),
+        }
+    }
+
+    fn write(&self, name: &str, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+        let tmp = self
+            .dir
+            .join(format!(".{}.{}.tmp", name, std::process::id()));
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, self.dir.join(name))?;
+        Ok(())
+    }
+}
+
+/**
+ * The arguments of a cacheable command that are read as geometries.
+ */
+fn geometry_args(command: &Commands) -> Vec<&str> {
+    match command {
+        Commands::S2(s2) => s2.geometry_args(),
+        Commands::H3(h3) => h3.geometry_args(),
+        Commands::Pyramid(pyramid) => pyramid.geometry_args(),
+        _ => vec![],
+    }
+}
+
+fn metadata_name(key: &str) -> String {
+    format!("{}.json", key)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use geos_core::crs::{CoordinateSystem, Crs};
+    use geos_core::measure::{LengthUnit, Units};
+
+    use super::{Cache, CacheEntry};
+    use crate::error::exit_code;
+    use crate::format::{InputFormat, InputOptions, InputUnits, OutputOptions};
+    use crate::input::CsvColumns;
+    use crate::Cli;
+
+    #[test]
+    fn test_cache_round_trip() {
+        let command = |args: &[&str]| Cli::parse_from(args).command.unwrap();
+        let cover = command(&["geos", "s2", "cover", "-l", "10", "--", "POINT(0 0)"]);
+        let input = InputOptions::default();
+        let output = OutputOptions::default();
+        let key = Cache::key(&cover, &input, &output).unwrap();
+        assert_eq!(key.len(), 64);
+        assert_eq!(
+            key,
+            Cache::key(
+                &command(&["geos", "s2", "cover", "-l", "10", "--", "POINT(0 0)"]),
+                &input,
+                &output
+            )
+            .unwrap()
+        );
+        for changed in [
+            InputOptions {
+                format: InputFormat::Wkb,
+                ..input.clone()
+            },
+            InputOptions {
+                units: InputUnits::Radians,
+                ..input.clone()
+            },
+            InputOptions {
+                crs: CoordinateSystem::Projected(Crs::WebMercator),
+                ..input.clone()
+            },
+            InputOptions {
+                normalize_winding: false,
+                ..input.clone()
+            },
+            InputOptions {
+                repair: true,
+                ..input.clone()
+            },
+            InputOptions {
+                csv: CsvColumns {
+                    id: Some("id".to_string()),
+                    ..CsvColumns::default()
+                },
+                ..input.clone()
+            },
+            InputOptions {
+                lenient: true,
+                ..input.clone()
+            },
+        ] {
+            assert_ne!(key, Cache::key(&cover, &changed, &output).unwrap());
+        }
+        for changed in [
+            OutputOptions {
+                units: Units {
+                    length: LengthUnit::Miles,
+                    ..output.units
+                },
+                ..output
+            },
+            OutputOptions {
+                crs: CoordinateSystem::Projected(Crs::WebMercator),
+                ..output
+            },
+            OutputOptions {
+                srid: Some(4326),
+                ..output
+            },
+            OutputOptions {
+                precision: Some(6),
+                ..output
+            },
+        ] {
+            assert_ne!(key, Cache::key(&cover, &input, &changed).unwrap());
+        }
+        assert_ne!(
+            key,
+            Cache::key(
+                &command(&["geos", "s2", "cover", "-l", "11", "--", "POINT(0 0)"]),
+                &input,
+                &output
+            )
+            .unwrap()
+        );
+        assert!(Cache::is_cacheable(&cover));
+        assert!(!Cache::is_cacheable(&command(&["geos", "rand", "point"])));
+        assert!(!Cache::is_cacheable(&command(&[
+            "geos",
+            "h3",
+            "merge",
+            "--",
+            "cells.txt"
+        ])));
+        let missing = command(&["geos", "s2", "cover", "--", "@missing.wkt"]);
+        assert!(Cache::is_cacheable(&missing));
+        assert!(Cache::key(&missing, &input, &output).is_err());
+
+        let dir = std::env::temp_dir().join(format!("geos-cache-test-{}", std::process::id()));
+        let cache = Cache::new(dir.clone());
+        assert_eq!(cache.get(&key).unwrap(), None);
+        let entry = CacheEntry {
+            output: b"9926595690882924544\n".to_vec(),
+            results: Some(1),
+            warnings: vec!["argument: dropped the Z and M values of 1 coordinates".to_string()],
+        };
+        cache.put(&key, &entry).unwrap();
+        assert_eq!(cache.get(&key).unwrap(), Some(entry));
+        // An interrupted write leaves the metadata without the output, which is not an entry.
+        std::fs::remove_file(dir.join(&key)).unwrap();
+        assert_eq!(cache.get(&key).unwrap(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_strict_cache_hits() {
+        let dir = std::env::temp_dir().join(format!("geos-strict-test-{}", std::process::id()));
+        let run = |strict: bool, wkt: &str| {
+            let mut args = vec!["geos", "--cache-dir", dir.to_str().unwrap()];
+            args.extend(strict.then_some("--strict"));
+            args.extend(["s2", "cover", "-l", "10", "--", wkt]);
+            let mut out = vec![];
+            crate::run(&Cli::parse_from(args), &mut out)
+                .map(|()| String::from_utf8(out).unwrap())
+                .map_err(|e| exit_code(&*e))
+        };
+        let entries = || std::fs::read_dir(&dir).map_or(0, |entries| entries.count());
+
+        // Warnings are replayed on a hit, failing strict runs of the cached output.
+        let dropped_z = "POINT Z(1 2 3)";
+        assert_eq!(
+            run(false, dropped_z),
+            Ok("1153887975327662080\n".to_string())
+        );
+        assert_eq!(entries(), 2);
+        assert_eq!(
+            run(false, dropped_z),
+            Ok("1153887975327662080\n".to_string())
+        );
+        assert_eq!(run(true, dropped_z), Err(6));
+
+        // Empty results fail strict runs whether or not they are cached, and failed runs are not
+        // stored.
+        let empty = "MULTIPOINT EMPTY";
+        assert_eq!(run(true, empty), Err(4));
+        assert_eq!(entries(), 2);
+        assert_eq!(run(false, empty), Ok(String::new()));
+        assert_eq!(entries(), 4);
+        assert_eq!(run(true, empty), Err(4));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_cache_hits() {
+        let dir = std::env::temp_dir().join(format!("geos-file-cache-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("point.wkt");
+        let cache_dir = dir.join("cache");
+        let run = || {
+            let file_arg = format!("@{}", path.display());
+            let args = ["geos", "--cache-dir", cache_dir.to_str().unwrap()];
+            let args = [&args[..], &["s2", "cover", "-l", "10", "--", &file_arg]].concat();
+            let mut out = vec![];
+            crate::run(&Cli::parse_from(args), &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let entries = || std::fs::read_dir(&cache_dir).map_or(0, |entries| entries.count());
+
+        std::fs::write(&path, "POINT(1 2)").unwrap();
+        assert_eq!(run(), "1153887975327662080\n");
+        assert_eq!(entries(), 2);
+        // The second run is served from the cache, as a doctored entry shows.
+        let key = std::fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .find(|name| !name.ends_with(".json"))
+            .unwrap();
+        std::fs::write(cache_dir.join(&key), "cached\n").unwrap();
+        assert_eq!(run(), "cached\n");
+        assert_eq!(entries(), 2);
+
+        // Changing the file changes the key.
+        std::fs::write(&path, "POINT(-122.4194 37.7749)").unwrap();
+        assert_eq!(run(), "9260950045757276160\n");
+        assert_eq!(entries(), 4);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+use std::error::Error;
+use std::io::Write;
+
+use clap::{Args, Subcommand};
+use geo::ConvexHull;
+use geo_types::{Geometry, MultiPoint, Point};
+use itertools::Itertools;
+
+use geos_core::cluster::{dbscan, kmeans};
+use geos_core::samplers::create_rng;
+
+use crate::error::GeosError;
+use crate::format::{fmt_geometry, fmt_lines, parse_points, OutputFormat, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Commands for clustering point sets.")]
+#[command(args_conflicts_with_subcommands = false)]
+#[command(arg_required_else_help = true)]
+pub struct ClusterArgs {
+    #[command(subcommand)]
+    command: Option<ClusterCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ClusterCommands {
+    #[command(arg_required_else_help = true)]
+    Dbscan {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POINT, MULTIPOINT, or GEOMETRYCOLLECTION strings containing the points to cluster."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "The neighborhood radius in meters. Points within this haversine distance of each other are considered neighbors."
+        )]
+        eps_m: f64,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 4,
+            help = "The minimum number of points (including the point itself) within the neighborhood radius for a point to be a core point."
+        )]
+        min_points: usize,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Output the convex hull of each cluster as a WKT POLYGON instead of per-point cluster labels."
+        )]
+        hulls: bool,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs the cluster label of each input point on separate lines, in input order. Noise points are labeled -1.")]
+        format: OutputFormat,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Kmeans {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POINT, MULTIPOINT, or GEOMETRYCOLLECTION strings containing the points to cluster."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(short, long, help = "The number of clusters.")]
+        k: usize,
+
+        #[arg(
+            long,
+            default_value_t = 100,
+            help = "The maximum number of assignment/update iterations to run."
+        )]
+        max_iterations: usize,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 0,
+            help = "Random seed to use for centroid initialization."
+        )]
+        seed: u64,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Output the centroid of each cluster as a WKT POINT instead of per-point cluster labels."
+        )]
+        centroids: bool,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs the cluster label of each input point on separate lines, in input order.")]
+        format: OutputFormat,
+    },
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_cluster_subcommand(
+    cluster: &ClusterArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match &cluster.command {
+        Some(ClusterCommands::Dbscan {
+            wkt,
+            eps_m,
+            min_points,
+            hulls,
+            format,
+        }) => {
+            let points = parse_points(wkt, ctx)?;
+            let labels = dbscan(&points, *eps_m, *min_points);
+
+            if *hulls {
+                fmt_geometry(out, ctx, format, cluster_hulls(&points, &labels))?;
+            } else {
+                let labels = labels.iter().map(|l| match l {
+                    Some(l) => l.to_string(),
+                    None => "-1".to_string(),
+                });
+                fmt_lines(out, ctx, format, labels)?;
+            }
+        }
+
+        Some(ClusterCommands::Kmeans {
+            wkt,
+            k,
+            max_iterations,
+            seed,
+            centroids,
+            format,
+        }) => {
+            let points = parse_points(wkt, ctx)?;
+            if *k == 0 || *k > points.len() {
+                return Err(GeosError::InvalidParameter(format!(
+                    "invalid k {}: k must be in [1, {}], the number of points",
+                    k,
+                    points.len()
+                ))
+                .into());
+            }
+            let mut rng = create_rng(*seed);
+            let (labels, cluster_centroids) = kmeans(&mut rng, &points, *k, *max_iterations);
+
+            if *centroids {
+                let centroids = cluster_centroids
+                    .into_iter()
+                    .map(Geometry::from)
+                    .collect_vec();
+                fmt_geometry(out, ctx, format, centroids)?;
+            } else {
+                fmt_lines(out, ctx, format, labels.iter().map(usize::to_string))?;
+            }
+        }
+
+        None => {}
+    }
+    Ok(())
+}
+
+/**
+ * Computes the convex hull of each cluster, ordered by cluster label. Noise points are ignored.
+ */
+fn cluster_hulls(points: &[Point], labels: &[Option<usize>]) -> Vec<Geometry> {
+    points
+        .iter()
+        .zip(labels)
+        .filter_map(|(p, l)| l.map(|l| (l, *p)))
+        .into_group_map()
+        .into_iter()
+        .sorted_by_key(|(l, _)| *l)
+        .map(|(_, members)| Geometry::from(MultiPoint::new(members).convex_hull()))
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::exit_code;
+    use crate::run_captured;
+
+    #[test]
+    fn test_kmeans_rejects_invalid_k() {
+        let run = |args: &str| run_captured(shlex::split(args).unwrap());
+        let points = "'MULTIPOINT(0 0, 1 1, 10 10)'";
+        for k in [0, 4] {
+            let error = run(&format!("cluster kmeans -k {} -- {}", k, points)).unwrap_err();
+            assert_eq!(exit_code(&*error), 2, "k = {}", k);
+        }
+        assert_eq!(
+            run(&format!("cluster kmeans -k 3 -- {}", points))
+                .unwrap()
+                .lines()
+                .count(),
+            3
+        );
+    }
+}
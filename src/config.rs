@@ -0,0 +1,255 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Command;
+use itertools::Itertools;
+use serde::Deserialize;
+
+/**
+ * User-provided defaults for commonly repeated options. These replace the built-in defaults of the
+ * corresponding arguments, so flags given on the commandline always take precedence.
+ *
+ * Defaults are read from `~/.config/geos/config.toml` (or `$GEOS_CONFIG`), and `GEOS_*` environment
+ * variables override the file:
+ *
+ * ```toml
+ * format = "oneline"  # GEOS_FORMAT
+ * seed = 42           # GEOS_SEED
+ * h3_level = 9        # GEOS_H3_LEVEL
+ * s2_level = 14       # GEOS_S2_LEVEL
+ * precision = 6       # GEOS_PRECISION
+ * ```
+ */
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    format: Option<String>,
+    seed: Option<u64>,
+    h3_level: Option<u8>,
+    s2_level: Option<u8>,
+    precision: Option<u8>,
+}
+
+impl Config {
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let mut config = match config_path() {
+            Some(path) if path.exists() => {
+                let contents = fs::read_to_string(&path)?;
+                toml::from_str(&contents)
+                    .map_err(|e| format!("invalid config file {}: {}", path.display(), e))?
+            }
+            _ => Config::default(),
+        };
+
+        if let Some(format) = env_var("GEOS_FORMAT")? {
+            config.format = Some(format);
+        }
+        if let Some(seed) = env_var("GEOS_SEED")? {
+            config.seed = Some(seed);
+        }
+        if let Some(level) = env_var("GEOS_H3_LEVEL")? {
+            config.h3_level = Some(level);
+        }
+        if let Some(level) = env_var("GEOS_S2_LEVEL")? {
+            config.s2_level = Some(level);
+        }
+        if let Some(precision) = env_var("GEOS_PRECISION")? {
+            config.precision = Some(precision);
+        }
+        Ok(config)
+    }
+
+    /**
+     * Replaces the defaults of matching arguments throughout the command tree. The H3 and S2
+     * levels only apply below the `h3` and `s2` commands respectively. Arguments without a
+     * built-in default are left required, other than the optional `--precision`, and values that an
+     * argument does not accept (e.g. a `oneline` format for a matrix) are skipped for that argument.
+     */
+    pub fn apply(&self, command: Command) -> Command {
+        self.apply_to(command, None)
+    }
+
+    fn apply_to(&self, mut command: Command, scope: Option<&str>) -> Command {
+        // The scope, ID, and value of each default, and whether it also applies without a built-in
+        // default.
+        let defaults = [
+            (None, "format", self.format.clone(), false),
+            (None, "seed", self.seed.map(|s| s.to_string()), false),
+            (
+                Some("h3"),
+                "level",
+                self.h3_level.map(|l| l.to_string()),
+                false,
+            ),
+            (
+                Some("s2"),
+                "level",
+                self.s2_level.map(|l| l.to_string()),
+                false,
+            ),
+            (
+                None,
+                "precision",
+                self.precision.map(|p| p.to_string()),
+                true,
+            ),
+        ];
+
+        for (arg_scope, id, value, optional) in defaults {
+            let Some(value) = value else { continue };
+            if arg_scope.is_some() && arg_scope != scope {
+                continue;
+            }
+            let accepts_default = command.get_arguments().any(|arg| {
+                arg.get_id() == id
+                    && (optional || !arg.get_default_values().is_empty())
+                    && (arg.get_possible_values().is_empty()
+                        || arg
+                            .get_possible_values()
+                            .iter()
+                            .any(|p| p.matches(&value, true)))
+            });
+            if accepts_default {
+                command = command.mut_arg(id, |arg| arg.default_value(value));
+            }
+        }
+
+        let names = command
+            .get_subcommands()
+            .map(|s| s.get_name().to_string())
+            .collect_vec();
+        for name in names {
+            // Subcommands inherit the scope of the top-level command they are nested under.
+            let scope = scope.or(Some(name.as_str()));
+            command = command.mut_subcommand(&name, |s| self.apply_to(s, scope));
+        }
+        command
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("GEOS_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+    Some(config_dir.join("geos").join("config.toml"))
+}
+
+fn env_var<T: std::str::FromStr>(name: &str) -> Result<Option<T>, Box<dyn Error>>
+where
+    T::Err: std::fmt::Display,
+{
+    match env::var(name) {
+        Ok(value) => {
+            Ok(Some(value.parse().map_err(|e| {
+                format!("invalid value for {}: {}", name, e)
+            })?))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::Config;
+    use crate::{run, Cli};
+
+    #[test]
+    fn test_apply_replaces_matching_defaults() {
+        let config: Config =
+            toml::from_str("format = \"oneline\"\nh3_level = 9\nseed = 7").unwrap();
+        let command = config.apply(Cli::command());
+
+        let matches = command
+            .clone()
+            .try_get_matches_from(["geos", "h3", "cover", "--", "POINT(0 0)"])
+            .unwrap();
+        let cover = matches
+            .subcommand_matches("h3")
+            .and_then(|m| m.subcommand_matches("cover"))
+            .unwrap();
+        assert_eq!(cover.get_one::<u8>("level"), Some(&9));
+
+        // The S2 level is unaffected by the H3 level.
+        let matches = command
+            .clone()
+            .try_get_matches_from(["geos", "s2", "cover", "--", "POINT(0 0)"])
+            .unwrap();
+        let cover = matches
+            .subcommand_matches("s2")
+            .and_then(|m| m.subcommand_matches("cover"))
+            .unwrap();
+        assert_eq!(cover.get_one::<u8>("level"), Some(&12));
+
+        // Matrices cannot be formatted as a single line, so the built-in default is kept.
+        let matches = command
+            .clone()
+            .try_get_matches_from(["geos", "geom", "distance-matrix", "--", "POINT(0 0)"])
+            .unwrap();
+        let matrix = matches
+            .subcommand_matches("geom")
+            .and_then(|m| m.subcommand_matches("distance-matrix"))
+            .unwrap();
+        assert_eq!(matrix.get_raw("format").unwrap().next().unwrap(), "csv");
+
+        // Explicit flags always win.
+        let matches = command
+            .try_get_matches_from(["geos", "rand", "-s", "3", "point"])
+            .unwrap();
+        assert_eq!(
+            matches
+                .subcommand_matches("rand")
+                .unwrap()
+                .get_one::<u64>("seed"),
+            Some(&3)
+        );
+    }
+
+    #[test]
+    fn test_precision_from_file_and_env() {
+        let swap_axes = |config: &Config, args: &[&str]| {
+            let args = ["geos", "geom", "swap-axes"].iter().chain(args);
+            let matches = config
+                .apply(Cli::command())
+                .try_get_matches_from(args)
+                .unwrap();
+            let mut out = vec![];
+            run(&Cli::from_arg_matches(&matches).unwrap(), &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let point = ["--", "POINT(1.23456 2.34567)"];
+
+        let path = env::temp_dir().join(format!("geos-config-test-{}.toml", std::process::id()));
+        fs::write(&path, "precision = 3\n").unwrap();
+        env::set_var("GEOS_CONFIG", &path);
+        env::remove_var("GEOS_PRECISION");
+        let from_file = Config::load().unwrap();
+        env::set_var("GEOS_PRECISION", "1");
+        let from_env = Config::load().unwrap();
+        env::set_var("GEOS_PRECISION", "one");
+        let invalid = Config::load();
+        env::remove_var("GEOS_PRECISION");
+        env::remove_var("GEOS_CONFIG");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(swap_axes(&from_file, &point), "POINT(2.346 1.235)\n");
+        // The environment takes precedence over the file, and flags over both.
+        assert_eq!(swap_axes(&from_env, &point), "POINT(2.3 1.2)\n");
+        let flag = ["--precision", "2", "--", "POINT(1.23456 2.34567)"];
+        assert_eq!(swap_axes(&from_env, &flag), "POINT(2.35 1.23)\n");
+        assert!(invalid.unwrap_err().to_string().contains("GEOS_PRECISION"));
+        assert_eq!(
+            swap_axes(&Config::default(), &point),
+            "POINT(2.34567 1.23456)\n"
+        );
+    }
+}
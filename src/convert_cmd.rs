@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::Utc;
+use clap::{Args, ValueEnum};
+use geo_types::Geometry;
+use geos_core::crs::CoordinateSystem;
+use geos_core::shapefile::{Shapefile, WGS84_PRJ};
+
+use crate::error::warn;
+use crate::format::{fmt_value_enum, parse_geometry, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(
+    about = "Convert geometries to a file format that cannot be written to stdout, such as a shapefile, e.g. `geos h3 cut -l 5 -- @county.wkt | geos convert --to shapefile -o cuts`."
+)]
+#[command(arg_required_else_help = true)]
+pub struct ConvertArgs {
+    #[arg(long, help = "The format to convert to.")]
+    to: ConvertFormat,
+
+    #[arg(
+        short,
+        long,
+        help = "The path to write to. Shapefiles are written as this path with the extensions .shp, .shx, .dbf, and for lng/lat, .prj."
+    )]
+    output: PathBuf,
+
+    #[arg(
+        last = true,
+        num_args = 1..,
+        help = "The geometries to convert, each of which is written as one record. The members of a GEOMETRYCOLLECTION, such as the output of `-f oneline`, are written as separate records."
+    )]
+    wkt: Vec<String>,
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum ConvertFormat {
+    /// An ESRI shapefile, whose geometries must all be points, all lines, or all polygons.
+    Shapefile,
+}
+impl Display for ConvertFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+//==================================================
+// Core logic.
+//==================================================
+pub fn handle_convert_command(
+    convert: &ConvertArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut geometries = vec![];
+    for wkt in &convert.wkt {
+        match parse_geometry(wkt, ctx)? {
+            Geometry::GeometryCollection(collection) => geometries.extend(collection),
+            geometry => geometries.push(geometry),
+        }
+    }
+    let crs = ctx.output.crs;
+    for geometry in &mut geometries {
+        crs.from_wgs84(geometry);
+    }
+
+    match convert.to {
+        ConvertFormat::Shapefile => {
+            if let Some(parent) = convert
+                .output
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+            {
+                std::fs::create_dir_all(parent)?;
+            }
+            let shapefile = Shapefile::new(&geometries, Utc::now().date_naive())?;
+            shapefile.write(&convert.output)?;
+            let mut extensions = vec!["shp", "shx", "dbf"];
+            match crs {
+                CoordinateSystem::Wgs84 => {
+                    let prj = convert.output.with_extension("prj");
+                    std::fs::write(&prj, WGS84_PRJ)
+                        .map_err(|e| format!("{}: {}", prj.display(), e))?;
+                    extensions.push("prj");
+                }
+                crs => warn(format!(
+                    "no .prj file is written for {}, so readers have to be told the CRS",
+                    crs
+                ))?,
+            }
+            tracing::info!("wrote {} records", geometries.len());
+            for extension in extensions {
+                writeln!(
+                    out,
+                    "{}",
+                    convert.output.with_extension(extension).display()
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+use clap::{Args, Subcommand, ValueEnum};
+use clap_stdin::MaybeStdin;
+use geos_core::crs::{self, suggest_crs};
+
+use crate::format::{fmt_value_enum, parse_geometry, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Commands for choosing coordinate reference systems.")]
+#[command(args_conflicts_with_subcommands = false)]
+#[command(arg_required_else_help = true)]
+pub struct CrsArgs {
+    #[command(subcommand)]
+    command: Option<CrsCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CrsCommands {
+    #[command(
+        about = "Suggest a projected CRS for accurate area or distance work on a geometry: its UTM zone, a polar UPS projection, or a custom projection centered on it. Outputs `EPSG:<code>` when there is one, otherwise a PROJ string."
+    )]
+    #[command(arg_required_else_help = true)]
+    Suggest {
+        #[arg(
+            last = true,
+            help = "A valid WKT string encoding the geometry to project."
+        )]
+        wkt: MaybeStdin<String>,
+
+        #[arg(short, long, default_value_t = Purpose::Area, help = "What the CRS will be used for. Only affects geometries too large for a single UTM zone.")]
+        purpose: Purpose,
+
+        #[arg(long, help = "Output the PROJ string even when there is an EPSG code.")]
+        proj: bool,
+    },
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum Purpose {
+    /// Measuring areas. Large geometries use a Lambert azimuthal equal-area projection.
+    Area,
+    /// Measuring distances and buffering. Large geometries use an azimuthal equidistant projection.
+    Distance,
+}
+impl Display for Purpose {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+impl From<Purpose> for crs::Purpose {
+    fn from(purpose: Purpose) -> Self {
+        match purpose {
+            Purpose::Area => crs::Purpose::Area,
+            Purpose::Distance => crs::Purpose::Distance,
+        }
+    }
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_crs_subcommand(
+    crs: &CrsArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match &crs.command {
+        Some(CrsCommands::Suggest { wkt, purpose, proj }) => {
+            let geometry = parse_geometry(wkt, ctx)?;
+            let crs = suggest_crs(&geometry, (*purpose).into())
+                .ok_or("cannot suggest a CRS for an empty geometry")?;
+            if *proj {
+                writeln!(out, "{}", crs.proj_string())?;
+            } else {
+                writeln!(out, "{}", crs)?;
+            }
+        }
+
+        None => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Write};
+use std::panic::catch_unwind;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use clap::Args;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::run_captured;
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Run commands read as NDJSON from stdin, writing one JSON result per line to stdout."
+)]
+pub struct DaemonArgs {
+    #[arg(
+        short,
+        long,
+        default_value_t = 1,
+        help = "The number of commands to run concurrently."
+    )]
+    jobs: usize,
+
+    #[arg(
+        long,
+        help = "Write each response as soon as it is ready instead of in request order. Responses \
+                are matched to requests by their id."
+    )]
+    unordered: bool,
+}
+
+/// Runs the arguments of a request, returning its output. This is [run_captured] except in tests.
+type Runner = fn(Vec<String>) -> Result<String, Box<dyn Error>>;
+
+/// The number of requests that may be in flight per job. Bounds memory when responses are
+/// reordered behind a slow request.
+const IN_FLIGHT_PER_JOB: usize = 4;
+
+/**
+ * A single command read from stdin, e.g. `{"id": 1, "args": ["h3", "cell-to-poly", "8a2a1072b59ffff"]}`.
+ * The id is echoed back unchanged so that callers can match responses to requests.
+ */
+#[derive(Deserialize)]
+struct DaemonRequest {
+    #[serde(default)]
+    id: Value,
+    args: Vec<String>,
+}
+
+//==================================================
+// Daemon loop.
+//==================================================
+/**
+ * Reads one JSON command per line from stdin and writes one JSON response per line to stdout until
+ * stdin is closed. Responses are flushed immediately, so callers can issue commands one at a time.
+ */
+pub fn run_daemon(daemon: &DaemonArgs) -> Result<(), Box<dyn Error>> {
+    let input = stdin_reader()?;
+    if daemon.jobs > 1 {
+        return run_parallel(
+            input,
+            &mut io::stdout().lock(),
+            daemon.jobs,
+            daemon.unordered,
+            run_captured,
+        );
+    }
+
+    let mut stdout = io::stdout().lock();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(stdout, "{}", handle_line(&line, run_captured))?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+/**
+ * Reads stdin in place when it is redirected from a file, e.g. `geos daemon < batch.ndjson`, so that
+ * large batches are not copied through a pipe buffer. Pipes and terminals are read as usual.
+ */
+fn stdin_reader() -> io::Result<Box<dyn BufRead + Send>> {
+    #[cfg(unix)]
+    {
+        use std::fs::File;
+        use std::os::fd::AsFd;
+
+        use geos_core::mmap::{Mmap, MmapReader};
+
+        let file = File::from(io::stdin().as_fd().try_clone_to_owned()?);
+        if file.metadata()?.is_file() {
+            return Ok(Box::new(MmapReader::new(Mmap::map(&file)?)));
+        }
+    }
+    Ok(Box::new(BufReader::new(io::stdin())))
+}
+
+/**
+ * Runs requests on a pool of worker threads. A reader thread dispatches lines to the workers, and
+ * this thread writes their responses, either as they complete or reordered to match the requests.
+ * The reader may only run ahead of the writer by a fixed number of requests, so that memory stays
+ * bounded regardless of the input size.
+ */
+fn run_parallel(
+    input: impl BufRead + Send + 'static,
+    out: &mut dyn Write,
+    jobs: usize,
+    unordered: bool,
+    run: Runner,
+) -> Result<(), Box<dyn Error>> {
+    let capacity = jobs * IN_FLIGHT_PER_JOB;
+    let (line_tx, line_rx) = sync_channel::<(usize, String)>(jobs);
+    let (response_tx, response_rx) = sync_channel::<(usize, Value)>(capacity);
+    // One permit per request in flight, returned once its response is written.
+    let (permit_tx, permit_rx) = sync_channel::<()>(capacity);
+
+    let reader = thread::spawn(move || -> io::Result<()> {
+        let lines = input.lines();
+        for (seq, line) in lines.filter_ok(|line| !line.trim().is_empty()).enumerate() {
+            let line = line?;
+            if permit_tx.send(()).is_err() || line_tx.send((seq, line)).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    });
+
+    let line_rx = Arc::new(Mutex::new(line_rx));
+    let workers = (0..jobs)
+        .map(|_| {
+            let (line_rx, response_tx) = (Arc::clone(&line_rx), response_tx.clone());
+            thread::spawn(move || {
+                while let Some((seq, line)) = next_line(&line_rx) {
+                    if response_tx.send((seq, handle_line(&line, run))).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect_vec();
+    drop(response_tx);
+
+    let mut pending = BTreeMap::new();
+    let mut next_seq = 0;
+    for (seq, response) in response_rx {
+        pending.insert(seq, response);
+        loop {
+            let response = if unordered {
+                pending.pop_first().map(|(_, response)| response)
+            } else {
+                pending.remove(&next_seq)
+            };
+            let Some(response) = response else {
+                break;
+            };
+            writeln!(out, "{}", response)?;
+            next_seq += 1;
+            let _ = permit_rx.recv();
+        }
+        out.flush()?;
+    }
+
+    for worker in workers {
+        worker.join().map_err(|_| "daemon worker panicked")?;
+    }
+    reader.join().map_err(|_| "daemon reader panicked")??;
+    Ok(())
+}
+
+fn next_line(line_rx: &Mutex<Receiver<(usize, String)>>) -> Option<(usize, String)> {
+    line_rx.lock().ok()?.recv().ok()
+}
+
+/**
+ * Runs the command in a request line. Failures, including malformed requests and panics, are
+ * reported in the response rather than ending the daemon.
+ */
+fn handle_line(line: &str, run: Runner) -> Value {
+    let request: DaemonRequest = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return json!({ "id": null, "ok": false, "error": e.to_string() }),
+    };
+
+    match catch_unwind(|| run(request.args)).unwrap_or_else(|_| Err("the request panicked".into()))
+    {
+        Ok(output) => json!({
+            "id": request.id,
+            "ok": true,
+            "output": output.lines().collect_vec(),
+        }),
+        Err(e) => json!({
+            "id": request.id,
+            "ok": false,
+            "error": e.to_string().trim_end(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::io::Cursor;
+
+    use itertools::Itertools;
+    use serde_json::{json, Value};
+
+    use super::{handle_line, run_parallel};
+    use crate::run_captured;
+
+    /// Runs commands as the daemon does, except that `panic` panics.
+    fn run_or_panic(args: Vec<String>) -> Result<String, Box<dyn Error>> {
+        if args == ["panic"] {
+            panic!("requested panic");
+        }
+        run_captured(args)
+    }
+
+    #[test]
+    fn test_handle_line_reports_output_and_errors() {
+        let response = handle_line(
+            r#"{"id": 7, "args": ["geom", "triangulate", "--", "POLYGON((0 0, 1 0, 1 1, 0 0))"]}"#,
+            run_captured,
+        );
+        assert_eq!(
+            response,
+            json!({ "id": 7, "ok": true, "output": ["POLYGON((1 0,0 0,1 1,1 0))"] })
+        );
+
+        let response = handle_line(
+            r#"{"id": "a", "args": ["h3", "cell-to-poly", "nope"]}"#,
+            run_captured,
+        );
+        assert_eq!(response["id"], "a");
+        assert_eq!(response["ok"], false);
+
+        let response = handle_line(r#"{"id": 8, "args": ["daemon"]}"#, run_captured);
+        assert_eq!(response["ok"], false);
+
+        let response = handle_line("not json", run_captured);
+        assert_eq!(response["id"], json!(null));
+        assert_eq!(response["ok"], false);
+    }
+
+    #[test]
+    fn test_handle_line_reports_panics() {
+        let response = handle_line(r#"{"id": 3, "args": ["panic"]}"#, run_or_panic);
+        assert_eq!(
+            response,
+            json!({ "id": 3, "ok": false, "error": "the request panicked" })
+        );
+
+        // The thread goes on running commands as before.
+        let response = handle_line(
+            r#"{"id": 5, "args": ["h3", "cover", "-l", "5", "--", "POINT(-122.4194 37.7749)"]}"#,
+            run_or_panic,
+        );
+        assert_eq!(response["output"], json!(["85283083fffffff"]));
+    }
+
+    #[test]
+    fn test_run_parallel_preserves_request_order() {
+        let input = (0..20)
+            .map(|i| {
+                format!(
+                    r#"{{"id": {}, "args": ["h3", "cover", "-l", "{}", "--", "POINT(1 2)"]}}"#,
+                    i,
+                    i % 10
+                )
+            })
+            .join("\n");
+        let mut out = vec![];
+        run_parallel(Cursor::new(input), &mut out, 4, false, run_captured).unwrap();
+        let ids = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["id"].clone())
+            .collect_vec();
+        assert_eq!(ids, (0..20).map(|i| json!(i)).collect_vec());
+    }
+
+    #[test]
+    fn test_run_parallel_reports_panics() {
+        // More requests than are allowed in flight follow the panic, which must not hold them up.
+        let input = (0..40)
+            .map(|i| match i {
+                5 => r#"{"id": 5, "args": ["panic"]}"#.to_string(),
+                i => format!(
+                    r#"{{"id": {}, "args": ["h3", "cover", "--", "POINT(1 2)"]}}"#,
+                    i
+                ),
+            })
+            .join("\n");
+        for unordered in [false, true] {
+            let mut out = vec![];
+            run_parallel(
+                Cursor::new(input.clone()),
+                &mut out,
+                2,
+                unordered,
+                run_or_panic,
+            )
+            .unwrap();
+            let responses = String::from_utf8(out)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str::<Value>(line).unwrap())
+                .collect_vec();
+            assert_eq!(responses.len(), 40);
+            if !unordered {
+                assert!(responses.iter().enumerate().all(|(i, r)| r["id"] == i));
+            }
+            let panicked = responses.iter().find(|r| r["id"] == 5).unwrap();
+            assert_eq!(panicked["error"], "the request panicked");
+            assert_eq!(responses.iter().filter(|r| r["ok"] == true).count(), 39);
+        }
+    }
+}
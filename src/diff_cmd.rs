@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand, ValueEnum};
+use geo::{BooleanOps, GeodesicArea, MultiPolygon};
+use geos_core::geom::{collect_polygons, hausdorff_distance};
+use geos_core::validity::check_polygon;
+use itertools::Itertools;
+use serde_json::{json, Value};
+
+use crate::error::{is_strict, GeosError};
+use crate::format::{fmt_value_enum, parse_geometry, read_cells, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Commands for comparing geometries and cell lists.")]
+#[command(args_conflicts_with_subcommands = false)]
+#[command(arg_required_else_help = true)]
+pub struct DiffArgs {
+    #[command(subcommand)]
+    command: Option<DiffCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DiffCommands {
+    #[command(
+        about = "Compare two geometries by their Hausdorff distance and, if both are polygonal, their geodesic areas, in meters and square meters or the units given by --unit."
+    )]
+    #[command(arg_required_else_help = true)]
+    Geom {
+        #[arg(
+            last = true,
+            num_args = 2,
+            help = "Two valid WKT strings: the geometry before and after."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(short, long, default_value_t = DiffFormat::Text, help = "The output format of the report.")]
+        format: DiffFormat,
+    },
+
+    #[command(
+        about = "Compare two cell lists, reporting the cells that were added and removed. Any cell IDs can be compared, e.g. the output of `s2 cover`, `h3 cover`, or `pyramid`."
+    )]
+    #[command(arg_required_else_help = true)]
+    Cells {
+        #[arg(help = "A file of the cells before, one per line or comma-separated.")]
+        before: PathBuf,
+
+        #[arg(help = "A file of the cells after, one per line or comma-separated.")]
+        after: PathBuf,
+
+        #[arg(short, long, default_value_t = DiffFormat::Text, help = "The output format of the report.")]
+        format: DiffFormat,
+    },
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum DiffFormat {
+    /// A human-readable report. Cell diffs list removed cells as `-<cell>` and added cells as
+    /// `+<cell>`, followed by a summary.
+    Text,
+    /// A JSON object.
+    Json,
+}
+impl Display for DiffFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_diff_subcommand(
+    diff: &DiffArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match &diff.command {
+        Some(DiffCommands::Geom { wkt, format }) => {
+            let before = parse_geometry(&wkt[0], ctx)?;
+            let after = parse_geometry(&wkt[1], ctx)?;
+
+            // Pairs are kept in report order.
+            let distance = hausdorff_distance(&before, &after);
+
+            // Keys are suffixed by the unit their values are in.
+            let units = ctx.output.units;
+            let length_key = |key| format!("{}_{}", key, units.length.symbol());
+            let area_key = |key| format!("{}_{}", key, units.area.symbol());
+            let mut report = vec![(
+                length_key("hausdorff_distance"),
+                json!(distance.map(|d| units.length(d))),
+            )];
+            if let (Ok(before), Ok(after)) = (collect_polygons(&before), collect_polygons(&after)) {
+                before.iter().chain(&after).try_for_each(check_polygon)?;
+                let (before, after) = (MultiPolygon::new(before), MultiPolygon::new(after));
+                let area =
+                    |polygons: MultiPolygon| json!(units.area(polygons.geodesic_area_unsigned()));
+                report.extend([
+                    (area_key("area_before"), area(before.clone())),
+                    (area_key("area_after"), area(after.clone())),
+                    (
+                        area_key("intersection_area"),
+                        area(before.intersection(&after)),
+                    ),
+                    (
+                        area_key("symmetric_difference_area"),
+                        area(before.xor(&after)),
+                    ),
+                ]);
+            }
+
+            match format {
+                DiffFormat::Text => report
+                    .iter()
+                    .try_for_each(|(key, value)| writeln!(out, "{}: {}", key, value))?,
+                DiffFormat::Json => {
+                    writeln!(out, "{}", Value::Object(report.into_iter().collect()))?
+                }
+            }
+            // Without a distance, one of the geometries is empty.
+            let differ = distance.map_or(before != after, |d| d > 0.0);
+            if is_strict() && differ {
+                return Err(GeosError::PredicateFalse("the geometries differ".to_string()).into());
+            }
+        }
+
+        Some(DiffCommands::Cells {
+            before,
+            after,
+            format,
+        }) => {
+            let before = read_cells(before)?;
+            let after = read_cells(after)?;
+            let before_set: HashSet<&String> = before.iter().collect();
+            let after_set: HashSet<&String> = after.iter().collect();
+            let removed = before
+                .iter()
+                .filter(|c| !after_set.contains(c))
+                .collect_vec();
+            let added = after
+                .iter()
+                .filter(|c| !before_set.contains(c))
+                .collect_vec();
+            let unchanged = before.len() - removed.len();
+
+            match format {
+                DiffFormat::Text => {
+                    for cell in &removed {
+                        writeln!(out, "-{}", cell)?;
+                    }
+                    for cell in &added {
+                        writeln!(out, "+{}", cell)?;
+                    }
+                    writeln!(
+                        out,
+                        "{} removed, {} added, {} unchanged",
+                        removed.len(),
+                        added.len(),
+                        unchanged
+                    )?;
+                }
+                DiffFormat::Json => writeln!(
+                    out,
+                    "{}",
+                    json!({"removed": removed, "added": added, "unchanged": unchanged})
+                )?,
+            }
+            if is_strict() && !(removed.is_empty() && added.is_empty()) {
+                return Err(GeosError::PredicateFalse("the cell lists differ".to_string()).into());
+            }
+        }
+
+        None => {}
+    }
+    Ok(())
+}
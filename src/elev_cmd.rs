@@ -0,0 +1,133 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand, ValueEnum};
+use geo_types::{Geometry, LineString, Point};
+use geos_core::geom::{collect_lines, collect_points};
+use geos_core::raster::{self, Raster};
+use itertools::Itertools;
+
+use crate::format::{fmt_lines, fmt_value_enum, parse_geometry, OutputFormat, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Commands for looking up elevations in digital elevation models.")]
+#[command(args_conflicts_with_subcommands = false)]
+#[command(arg_required_else_help = true)]
+pub struct ElevArgs {
+    #[command(subcommand)]
+    command: Option<ElevCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ElevCommands {
+    #[command(
+        about = "Sample elevations at points, or drape lines with per-vertex elevations. Missing elevations are output as NaN."
+    )]
+    #[command(arg_required_else_help = true)]
+    Sample {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT strings. POINT and MULTIPOINT geometries output one elevation per point. LINESTRING and MULTILINESTRING geometries output each line as a 3D WKT LINESTRING."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            long,
+            help = "A single-band GeoTIFF elevation model in geographic (lng/lat) coordinates."
+        )]
+        dem: PathBuf,
+
+        #[arg(short, long, default_value_t = Interpolation::Bilinear, help = "How to interpolate elevations between pixel centers.")]
+        interpolation: Interpolation,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV)]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum Interpolation {
+    /// The value of the pixel containing the point.
+    Nearest,
+    /// Bilinear interpolation between the four nearest pixel centers.
+    Bilinear,
+}
+impl Display for Interpolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+impl From<Interpolation> for raster::Interpolation {
+    fn from(interpolation: Interpolation) -> Self {
+        match interpolation {
+            Interpolation::Nearest => raster::Interpolation::Nearest,
+            Interpolation::Bilinear => raster::Interpolation::Bilinear,
+        }
+    }
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_elev_subcommand(
+    elev: &ElevArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match &elev.command {
+        Some(ElevCommands::Sample {
+            wkt,
+            dem,
+            interpolation,
+            format,
+        }) => {
+            let dem = Raster::open(dem)?;
+            let elevation = |p: &Point| dem.sample(p, (*interpolation).into()).unwrap_or(f64::NAN);
+
+            let mut values = vec![];
+            for s in wkt {
+                let geometry = parse_geometry(s, ctx)?;
+                match geometry {
+                    Geometry::LineString(_) | Geometry::MultiLineString(_) => {
+                        values.extend(
+                            collect_lines(&geometry)?
+                                .iter()
+                                .map(|line| drape(line, elevation)),
+                        );
+                    }
+                    _ => values.extend(
+                        collect_points(&geometry)?
+                            .iter()
+                            .map(|p| elevation(p).to_string()),
+                    ),
+                }
+            }
+            fmt_lines(out, ctx, format, values.into_iter())?;
+        }
+
+        None => {}
+    }
+    Ok(())
+}
+
+/**
+ * Formats a line as a 3D WKT LINESTRING with the elevation at each vertex.
+ */
+fn drape(line: &LineString, elevation: impl Fn(&Point) -> f64) -> String {
+    let coords = line
+        .points()
+        .map(|p| wkt::types::Coord {
+            x: p.x(),
+            y: p.y(),
+            z: Some(elevation(&p)),
+            m: None,
+        })
+        .collect_vec();
+    wkt::types::LineString(coords).to_string()
+}
@@ -0,0 +1,388 @@
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+use geos_core::geom::InvalidEdgeProportion;
+use geos_core::read::WktError;
+use geos_core::validity::{DegeneratePolygon, InvalidPolygon};
+use h3o::error::InvalidGeometry;
+use thiserror::Error;
+
+/// Characters of context shown on either side of the offending token in long lines.
+const CONTEXT_CHARS: usize = 40;
+
+//==================================================
+// Exit codes.
+//==================================================
+/// Any failure without a more specific exit code, e.g. an unreadable file.
+pub const EXIT_FAILURE: i32 = 1;
+/// Invalid arguments, e.g. an unknown flag or an out-of-range level. This is also clap's exit code
+/// for usage errors.
+pub const EXIT_INVALID_PARAMETER: i32 = 2;
+/// An input geometry or cell could not be parsed, or a polygon is invalid.
+pub const EXIT_PARSE_ERROR: i32 = 3;
+/// With `--strict`, the command produced no results.
+pub const EXIT_EMPTY_RESULT: i32 = 4;
+/// With `--strict`, a comparison found a difference.
+pub const EXIT_PREDICATE_FALSE: i32 = 5;
+/// With `--strict`, a warning was raised.
+pub const EXIT_WARNING: i32 = 6;
+
+/**
+ * The exit code for a command that failed with `error`.
+ */
+pub fn exit_code(error: &(dyn Error + 'static)) -> i32 {
+    match error.downcast_ref::<GeosError>() {
+        Some(
+            GeosError::Wkt(_)
+            | GeosError::WktAtOffset { .. }
+            | GeosError::Parse(_)
+            | GeosError::Cell { .. },
+        ) => EXIT_PARSE_ERROR,
+        Some(GeosError::Level { .. } | GeosError::InvalidParameter(_)) => EXIT_INVALID_PARAMETER,
+        None if error.is::<InvalidEdgeProportion>() => EXIT_INVALID_PARAMETER,
+        Some(GeosError::EmptyResult) => EXIT_EMPTY_RESULT,
+        Some(GeosError::PredicateFalse(_)) => EXIT_PREDICATE_FALSE,
+        Some(GeosError::Warning(_)) => EXIT_WARNING,
+        None if error.is::<WktError>()
+            || error.is::<InvalidPolygon>()
+            || error.is::<DegeneratePolygon>()
+            || error.is::<InvalidGeometry>() =>
+        {
+            EXIT_PARSE_ERROR
+        }
+        None => EXIT_FAILURE,
+    }
+}
+
+//==================================================
+// Strict mode.
+//==================================================
+// Strictness is set per thread, so that commands run concurrently by the daemon can differ.
+thread_local! {
+    static STRICT: Cell<bool> = const { Cell::new(false) };
+    static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+/**
+ * Sets whether warnings, empty results, and differences fail the commands run afterward on this
+ * thread.
+ */
+pub fn set_strict(strict: bool) {
+    STRICT.with(|s| s.set(strict));
+}
+
+pub fn is_strict() -> bool {
+    STRICT.with(Cell::get)
+}
+
+/**
+ * Reports a warning on stderr, e.g. that part of an input was dropped. In strict mode, the warning
+ * is returned as an error instead.
+ */
+pub fn warn(message: String) -> Result<(), GeosError> {
+    if is_strict() {
+        return Err(GeosError::Warning(message));
+    }
+    eprintln!("warning: {}", message);
+    WARNINGS.with(|w| w.borrow_mut().push(message));
+    Ok(())
+}
+
+/**
+ * The warnings reported on this thread since the last call, so that they can be replayed along with
+ * a cached output.
+ */
+pub fn take_warnings() -> Vec<String> {
+    WARNINGS.with(RefCell::take)
+}
+
+//==================================================
+// Errors.
+//==================================================
+
+/**
+ * Errors in command inputs. Errors in text inputs quote the input and point at the offending token,
+ * so that a typo in a multi-megabyte geometry or a long list of cells can be found.
+ */
+#[derive(Debug, Error)]
+pub enum GeosError {
+    #[error("invalid WKT\n{0}")]
+    Wkt(Box<Snippet>),
+
+    /// A WKT syntax error located by its offset too, for editors and tools that seek by offset.
+    #[error("invalid WKT at byte {byte} (character {character})\n{snippet}")]
+    WktAtOffset {
+        byte: usize,
+        character: usize,
+        snippet: Box<Snippet>,
+    },
+
+    /// A geometry that could not be parsed, where the position is not known, e.g. in GeoJSON.
+    #[error("{0}")]
+    Parse(String),
+
+    #[error("invalid {grid} cell `{cell}`\n{snippet}")]
+    Cell {
+        grid: &'static str,
+        cell: String,
+        snippet: Box<Snippet>,
+    },
+
+    #[error("invalid {grid} level {level}: levels must be in [0, {max}]")]
+    Level { grid: String, level: u8, max: u8 },
+
+    /// A parameter outside of the values a command accepts, e.g. a count of zero.
+    #[error("{0}")]
+    InvalidParameter(String),
+
+    #[error("the result is empty")]
+    EmptyResult,
+
+    #[error("{0}")]
+    PredicateFalse(String),
+
+    #[error("{0}")]
+    Warning(String),
+}
+
+impl GeosError {
+    /**
+     * Points a WKT syntax error at the input it was found in, which is named by `origin`.
+     */
+    pub fn wkt(error: &WktError, origin: &str, source: &str) -> Self {
+        Self::Wkt(Box::new(Snippet::new(
+            Some(origin),
+            source,
+            error.span.clone(),
+            &error.message,
+        )))
+    }
+
+    /**
+     * Like [GeosError::wkt], but also gives the byte and character offsets of the error in `source`.
+     */
+    pub fn wkt_at_offset(error: &WktError, origin: &str, source: &str) -> Self {
+        let byte = floor_char_boundary(source, error.span.start);
+        Self::WktAtOffset {
+            byte,
+            character: source[..byte].chars().count(),
+            snippet: Box::new(Snippet::new(
+                Some(origin),
+                source,
+                error.span.clone(),
+                &error.message,
+            )),
+        }
+    }
+
+    /**
+     * An invalid cell in a list of cells. The list is quoted as it would be given as a
+     * comma-separated argument.
+     */
+    pub fn cell_in_list(grid: &'static str, cells: &[String], index: usize, reason: &str) -> Self {
+        let start = cells[..index].iter().map(|c| c.len() + 1).sum::<usize>();
+        let span = start..start + cells[index].len();
+        Self::Cell {
+            grid,
+            cell: cells[index].clone(),
+            snippet: Box::new(Snippet::new(
+                Some("argument"),
+                &cells.join(","),
+                span,
+                reason,
+            )),
+        }
+    }
+
+    /**
+     * An invalid cell read on its own, e.g. from a file of cells.
+     */
+    pub fn cell(grid: &'static str, cell: &str, reason: &str) -> Self {
+        Self::Cell {
+            grid,
+            cell: cell.to_string(),
+            snippet: Box::new(Snippet::new(None, cell, 0..cell.len(), reason)),
+        }
+    }
+}
+
+/**
+ * A line of an input with a token underlined and labeled, rendered like a compiler diagnostic:
+ *
+ * ```text
+ *   --> argument:1:9
+ *   |
+ * 1 | POINT(1 x)
+ *   |         ^ expected a number, found `x`
+ * ```
+ *
+ * Only the part of a long line around the token is kept.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    /// Where the input came from, e.g. a path, along with the line and column of the token.
+    location: Option<(String, usize, usize)>,
+    line: usize,
+    text: String,
+    /// The underlined characters of `text`.
+    marker: Range<usize>,
+    label: String,
+}
+
+impl Snippet {
+    /**
+     * Creates a snippet for the byte range `span` of `source`. Empty spans, e.g. at the end of the
+     * input, are shown as a single character.
+     */
+    pub fn new(origin: Option<&str>, source: &str, span: Range<usize>, label: &str) -> Self {
+        let start = floor_char_boundary(source, span.start);
+        let end = floor_char_boundary(source, span.end).max(start);
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let line = source[..start].matches('\n').count() + 1;
+        let column = source[line_start..start].chars().count() + 1;
+
+        // Keep the context around the token, marking where the line was cut.
+        let before = source[line_start..start].chars().collect::<Vec<_>>();
+        let token = source[start..end.min(line_end)].chars().collect::<Vec<_>>();
+        let after = source[end.min(line_end)..line_end]
+            .chars()
+            .collect::<Vec<_>>();
+        let mut text = String::new();
+        if before.len() > CONTEXT_CHARS {
+            text.push_str("...");
+        }
+        text.extend(&before[before.len().saturating_sub(CONTEXT_CHARS)..]);
+        let marker_start = text.chars().count();
+        text.extend(&token[..token.len().min(CONTEXT_CHARS)]);
+        let marker_end = text.chars().count().max(marker_start + 1);
+        text.extend(&after[..after.len().min(CONTEXT_CHARS)]);
+        if after.len() > CONTEXT_CHARS || token.len() > CONTEXT_CHARS {
+            text.push_str("...");
+        }
+
+        Self {
+            location: origin.map(|origin| (origin.to_string(), line, column)),
+            line,
+            text: text.replace(['\t', '\r'], " "),
+            marker: marker_start..marker_end,
+            label: label.to_string(),
+        }
+    }
+}
+
+impl Display for Snippet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let line = self.line.to_string();
+        let pad = " ".repeat(line.len());
+        if let Some((origin, line, column)) = &self.location {
+            writeln!(f, "{} --> {}:{}:{}", pad, origin, line, column)?;
+        }
+        writeln!(f, "{} |", pad)?;
+        writeln!(f, "{} | {}", line, self.text)?;
+        write!(
+            f,
+            "{} | {}{} {}",
+            pad,
+            " ".repeat(self.marker.start),
+            "^".repeat(self.marker.len()),
+            self.label
+        )
+    }
+}
+
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    let mut index = index.min(s.len());
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::{exit_code, set_strict, take_warnings, warn, GeosError, Snippet};
+    use crate::run_captured;
+
+    #[test]
+    fn test_snippet() {
+        let snippet = Snippet::new(Some("argument"), "POINT(1 x)", 8..9, "expected a number");
+        assert_eq!(
+            snippet.to_string(),
+            "  --> argument:1:9\n  |\n1 | POINT(1 x)\n  |         ^ expected a number"
+        );
+
+        // Long lines are cut around the token, and empty spans at the end are still marked.
+        let long = format!("LINESTRING({} 1 2", "1 2,".repeat(100));
+        let source = format!("\n{}", long);
+        let end = source.len();
+        let snippet = Snippet::new(Some("a.wkt"), &source, end..end, "here");
+        assert_eq!(
+            snippet.to_string(),
+            format!(
+                "  --> a.wkt:2:{}\n  |\n2 | ...{}\n  | {}^ here",
+                long.len() + 1,
+                &long[long.len() - 40..],
+                " ".repeat(43)
+            )
+        );
+    }
+
+    #[test]
+    fn test_strict_warnings() {
+        set_strict(false);
+        assert!(warn("dropped".to_string()).is_ok());
+        set_strict(true);
+        let error: Box<dyn Error> = warn("dropped".to_string()).unwrap_err().into();
+        assert_eq!(exit_code(&*error), 6);
+        set_strict(false);
+
+        let error: Box<dyn Error> = "unreadable".into();
+        assert_eq!(exit_code(&*error), 1);
+    }
+
+    #[test]
+    fn test_empty_polygon_exit_codes() {
+        let run = |args: &str| run_captured(shlex::split(args).unwrap());
+        for command in ["h3 cover -l 5", "h3 cover -l 5 --fill-holes", "h3 cut -l 5"] {
+            for wkt in ["POLYGON EMPTY", "POLYGON((0 0,1 1,0 0))"] {
+                let error = run(&format!("{} -- '{}'", command, wkt)).unwrap_err();
+                assert_eq!(exit_code(&*error), 3, "{} -- {}", command, wkt);
+            }
+        }
+    }
+
+    #[test]
+    fn test_winding_across_antimeridian() {
+        let run = |args: &str| run_captured(shlex::split(args).unwrap());
+        let ccw = "POLYGON((179 -17, -179 -17, -179 -16, 179 -16, 179 -17))";
+        let cw = "POLYGON((179 -16, -179 -16, -179 -17, 179 -17, 179 -16))";
+
+        take_warnings();
+        assert!(run(&format!("s2 cover -l 6 -- '{}'", ccw)).is_ok());
+        assert!(take_warnings().is_empty());
+        assert!(run(&format!("s2 cover -l 6 -- '{}'", cw)).is_ok());
+        assert_eq!(take_warnings().len(), 1);
+
+        assert!(run(&format!("--strict s2 cover -l 6 -- '{}'", ccw)).is_ok());
+        let error = run(&format!("--strict s2 cover -l 6 -- '{}'", cw)).unwrap_err();
+        assert_eq!(exit_code(&*error), 6);
+        set_strict(false);
+    }
+
+    #[test]
+    fn test_cell_in_list() {
+        let cells = ["85283473fffffff", "zz", "85283447fffffff"].map(String::from);
+        let error = GeosError::cell_in_list("H3", &cells, 1, "invalid 64-bit hex number");
+        assert_eq!(
+            error.to_string(),
+            "invalid H3 cell `zz`\n  --> argument:1:17\n  |\n1 | 85283473fffffff,zz,85283447fffffff\n  |                 ^^ invalid 64-bit hex number"
+        );
+    }
+}
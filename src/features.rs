@@ -0,0 +1,391 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::Path;
+
+use clap::ValueEnum;
+use geo_types::{Geometry, GeometryCollection};
+use geojson::feature::Id;
+use geojson::{Feature, FeatureCollection, GeoJson, JsonObject};
+use geos_core::read::read_geometry;
+use itertools::Itertools;
+use serde_json::{json, Value};
+use wkt::ToWkt;
+
+use crate::format::{
+    fmt_value_enum, json_geometry, read_text_file, InputFormat, InputOptions, OutputOptions,
+    RunContext,
+};
+use crate::input::{read_csv_rows, CsvColumns};
+use crate::template::{write_output, OutputTemplate};
+use crate::{run_args_in, run_captured};
+
+/**
+ * How the outputs of a command run on each feature of a FeatureCollection are grouped.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FeatureOutput {
+    /// One `{"id": <id>, "properties": {...}, "output": [<lines>...]}` object per line.
+    Ndjson,
+    /// A FeatureCollection with the features' ids and properties. Outputs that are all geometries
+    /// become the feature's geometry, and other outputs are added to its properties as `output`.
+    FeatureCollection,
+    /// A FeatureCollection with a feature for each geometry output for each input feature, such as
+    /// each piece of a cut, carrying the input feature's properties plus its `feature_id`, and the
+    /// `cell` of cell polygons and cuts. Other output lines are features without a geometry, with
+    /// the line in `output`.
+    Features,
+}
+impl Display for FeatureOutput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+/**
+ * A feature to run a command on, with the argument that stands in for it.
+ */
+struct InputFeature {
+    id: Value,
+    properties: JsonObject,
+    arg: String,
+}
+
+/**
+ * Runs a command once for each feature of its FeatureCollection argument, with the argument
+ * replaced by the feature's geometry, and writes the outputs grouped by feature id, or with a
+ * `template`, each to the path it gives for the feature's id. Features without an id are keyed by
+ * their index. `args` are the full commandline arguments, and exactly one of them must be a
+ * FeatureCollection, given inline or as `@path`. With `--input-format csv` in `input`, each row of a
+ * CSV argument is a feature instead, keyed by its `--id-column`, with its columns as properties.
+ */
+pub fn run_per_feature(
+    args: &[String],
+    input: &InputOptions,
+    format: FeatureOutput,
+    template: Option<&str>,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let template = template
+        .map(|template| OutputTemplate::new(template, args, &["id"]))
+        .transpose()?;
+    let args = &args[1.min(args.len())..];
+    let csv = input.format == InputFormat::Csv;
+    let mut collections = vec![];
+    for (i, arg) in args.iter().enumerate() {
+        let features = if csv {
+            read_csv_features(arg, &input.csv)?
+        } else {
+            read_feature_collection(arg)?
+                .map(|collection| geojson_features(collection.features))
+                .transpose()?
+        };
+        if let Some(features) = features {
+            collections.push((i, features));
+        }
+    }
+    let kind = if csv { "CSV" } else { "FeatureCollection" };
+    let (index, features) = match <[_; 1]>::try_from(collections) {
+        Ok([found]) => found,
+        Err(found) if found.is_empty() && csv => {
+            return Err("--per-feature needs a CSV argument with a header row".into())
+        }
+        Err(found) if found.is_empty() => {
+            return Err("--per-feature needs a GeoJSON FeatureCollection argument".into())
+        }
+        Err(_) => return Err(format!("--per-feature takes only one {} argument", kind).into()),
+    };
+
+    // Outputs written to files aren't grouped.
+    let format = template.is_none().then_some(format);
+    let collection = matches!(
+        format,
+        Some(FeatureOutput::FeatureCollection | FeatureOutput::Features)
+    );
+    if collection {
+        write!(out, r#"{{"type":"FeatureCollection","features":["#)?;
+    }
+    let mut written = 0;
+    for feature in features {
+        let InputFeature {
+            id,
+            properties,
+            arg,
+        } = feature;
+        let mut feature_args = args.to_vec();
+        feature_args[index] = arg;
+        tracing::info!("running feature {}", id);
+        let failed = |_: &_| eprintln!("error: feature {} failed", id);
+        // Split features are made from the geometries themselves, with their cells.
+        let (output, geometries, output_options) = match format {
+            Some(FeatureOutput::Features) => {
+                let mut text = vec![];
+                let mut feature_ctx = RunContext::default();
+                feature_ctx.capture();
+                let result = run_args_in(feature_args, &mut feature_ctx, &mut text);
+                result.inspect_err(failed)?;
+                let geometries = feature_ctx.take_captured();
+                (String::from_utf8(text)?, geometries, feature_ctx.output)
+            }
+            _ => (
+                run_captured(feature_args).inspect_err(failed)?,
+                vec![],
+                OutputOptions::default(),
+            ),
+        };
+        let lines = output.lines().collect_vec();
+
+        match format {
+            None => {
+                let id = match &id {
+                    Value::String(id) => id.clone(),
+                    id => id.to_string(),
+                };
+                let template = template
+                    .as_ref()
+                    .expect("outputs without a format have a template");
+                write_output(&template.render(&[("id", &id)]), &output)?;
+            }
+            Some(FeatureOutput::Ndjson) => {
+                let group = json!({"id": id, "properties": properties, "output": lines});
+                writeln!(out, "{}", group)?;
+            }
+            Some(FeatureOutput::FeatureCollection) => {
+                if written > 0 {
+                    write!(out, ",")?;
+                }
+                write!(out, "{}", output_feature(id, properties, &lines))?;
+                written += 1;
+            }
+            Some(FeatureOutput::Features) => {
+                let features =
+                    split_features(&id, &properties, geometries, &lines, &output_options);
+                for feature in features {
+                    if written > 0 {
+                        write!(out, ",")?;
+                    }
+                    write!(out, "{}", feature)?;
+                    written += 1;
+                }
+            }
+        }
+    }
+    if collection {
+        writeln!(out, "]}}")?;
+    }
+    Ok(())
+}
+
+/**
+ * Reads an argument as a FeatureCollection, or `None` if it is anything else. Only arguments that
+ * look like GeoJSON objects are parsed.
+ */
+fn read_feature_collection(arg: &str) -> Result<Option<FeatureCollection>, Box<dyn Error>> {
+    let text = match arg.strip_prefix('@') {
+        Some(path) => match read_text_file(path.as_ref()) {
+            Ok(map) => String::from_utf8_lossy(&map).into_owned(),
+            // Arguments that merely start with `@` aren't necessarily files.
+            Err(_) => return Ok(None),
+        },
+        None => arg.to_string(),
+    };
+    if !text.trim_start().starts_with('{') || !text.contains("FeatureCollection") {
+        return Ok(None);
+    }
+    match text.parse::<GeoJson>() {
+        Ok(GeoJson::FeatureCollection(collection)) => Ok(Some(collection)),
+        Ok(_) => Ok(None),
+        Err(e) => Err(format!("{}: {}", arg, e).into()),
+    }
+}
+
+/**
+ * The features of a FeatureCollection, each standing in for it as the WKT of its geometry.
+ */
+fn geojson_features(features: Vec<Feature>) -> Result<Vec<InputFeature>, Box<dyn Error>> {
+    features
+        .into_iter()
+        .enumerate()
+        .map(|(i, feature)| {
+            let id = feature_id(&feature, i);
+            let geometry = feature
+                .geometry
+                .as_ref()
+                .ok_or_else(|| format!("feature {} has no geometry", id))?;
+            Ok(InputFeature {
+                arg: Geometry::<f64>::try_from(geometry)?.wkt_string(),
+                id,
+                properties: feature.properties.unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/**
+ * Reads an argument as rows of CSV, or `None` if it is neither a file nor several lines. Each row
+ * stands in for the argument as CSV of that row alone.
+ */
+fn read_csv_features(
+    arg: &str,
+    columns: &CsvColumns,
+) -> Result<Option<Vec<InputFeature>>, Box<dyn Error>> {
+    let is_csv = match arg.strip_prefix('@') {
+        Some(path) => Path::new(path).is_file(),
+        None => arg.trim().contains('\n'),
+    };
+    if !is_csv {
+        return Ok(None);
+    }
+    let features = read_csv_rows(arg, columns)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| InputFeature {
+            id: row.id.map_or_else(|| json!(i), |id| json!(id)),
+            properties: row
+                .columns
+                .into_iter()
+                .map(|(name, value)| (name, json!(value)))
+                .collect(),
+            arg: row.csv,
+        })
+        .collect();
+    Ok(Some(features))
+}
+
+fn feature_id(feature: &Feature, index: usize) -> Value {
+    match &feature.id {
+        Some(Id::String(id)) => json!(id),
+        Some(Id::Number(id)) => json!(id),
+        None => json!(index),
+    }
+}
+
+/**
+ * A feature holding the output of a command for the feature with `id` and `properties`.
+ */
+fn output_feature(id: Value, mut properties: JsonObject, lines: &[&str]) -> Value {
+    let geometries: Option<Vec<Geometry>> = lines
+        .iter()
+        .map(|line| read_geometry(line.as_bytes()).ok())
+        .collect();
+    let geometry = match geometries {
+        Some(geometries) if !lines.is_empty() => {
+            let geometry = match <[_; 1]>::try_from(geometries) {
+                Ok([geometry]) => geometry,
+                Err(geometries) => Geometry::GeometryCollection(GeometryCollection(geometries)),
+            };
+            json!(geojson::Geometry::from(&geometry))
+        }
+        _ => {
+            properties.insert("output".to_string(), json!(lines));
+            Value::Null
+        }
+    };
+    json!({"type": "Feature", "id": id, "properties": properties, "geometry": geometry})
+}
+
+/**
+ * A feature for each geometry and other output line of a command run for the feature with `id` and
+ * `properties`, each with the input feature's properties. The geometries are written with the
+ * command's output `options`.
+ */
+fn split_features(
+    id: &Value,
+    properties: &JsonObject,
+    geometries: Vec<(Option<String>, Geometry)>,
+    lines: &[&str],
+    options: &OutputOptions,
+) -> Vec<Value> {
+    let feature = |cell: Option<String>, geometry: Value, output: Option<&str>| {
+        let mut properties = properties.clone();
+        properties.insert("feature_id".to_string(), id.clone());
+        if let Some(cell) = cell {
+            properties.insert("cell".to_string(), json!(cell));
+        }
+        if let Some(output) = output {
+            properties.insert("output".to_string(), json!(output));
+        }
+        json!({"type": "Feature", "properties": properties, "geometry": geometry})
+    };
+    let geometries = geometries
+        .into_iter()
+        .map(|(cell, geometry)| feature(cell, json_geometry(geometry, options), None));
+    let lines = lines
+        .iter()
+        .map(|line| feature(None, Value::Null, Some(line)));
+    geometries.chain(lines).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, Geometry};
+    use serde_json::json;
+
+    use super::{output_feature, split_features};
+    use crate::format::OutputOptions;
+
+    #[test]
+    fn test_output_feature() {
+        let properties = json!({"name": "park"}).as_object().unwrap().clone();
+        assert_eq!(
+            output_feature(json!("a"), properties.clone(), &["POINT(1 2)"]),
+            json!({
+                "type": "Feature",
+                "id": "a",
+                "properties": {"name": "park"},
+                "geometry": {"type": "Point", "coordinates": [1.0, 2.0]},
+            })
+        );
+        assert_eq!(
+            output_feature(
+                json!(3),
+                properties,
+                &["8a2a1072b59ffff", "8a2a1072b5b7fff"]
+            ),
+            json!({
+                "type": "Feature",
+                "id": 3,
+                "properties": {"name": "park", "output": ["8a2a1072b59ffff", "8a2a1072b5b7fff"]},
+                "geometry": null,
+            })
+        );
+    }
+
+    #[test]
+    fn test_split_features() {
+        let properties = json!({"name": "park"}).as_object().unwrap().clone();
+        let geometries = vec![
+            (
+                Some("85283083fffffff".to_string()),
+                Geometry::from(point!(x: 1.0, y: 2.0)),
+            ),
+            (None, Geometry::from(point!(x: 3.0, y: 4.0))),
+        ];
+        assert_eq!(
+            split_features(
+                &json!("a"),
+                &properties,
+                geometries,
+                &["done"],
+                &OutputOptions::default()
+            ),
+            [
+                json!({
+                    "type": "Feature",
+                    "properties": {"name": "park", "feature_id": "a", "cell": "85283083fffffff"},
+                    "geometry": {"type": "Point", "coordinates": [1.0, 2.0]},
+                }),
+                json!({
+                    "type": "Feature",
+                    "properties": {"name": "park", "feature_id": "a"},
+                    "geometry": {"type": "Point", "coordinates": [3.0, 4.0]},
+                }),
+                json!({
+                    "type": "Feature",
+                    "properties": {"name": "park", "feature_id": "a", "output": "done"},
+                    "geometry": null,
+                }),
+            ]
+        );
+    }
+}
@@ -1,8 +1,40 @@
-use clap::ValueEnum;
-use geo_types::{Geometry, GeometryCollection};
+use clap::{Args, Command, ValueEnum};
+use geo::{BoundingRect, CoordsIter, MapCoordsInPlace};
+use geo_types::{coord, Geometry, GeometryCollection, Point, Polygon};
+use geos_core::arrow::{self, ArrowWriter};
+use geos_core::bins::Bin;
+use geos_core::crs::CoordinateSystem;
+use geos_core::geom::{self, collect_points, normalize_winding};
+use geos_core::gpx::{write_gpx_end, write_gpx_start, write_waypoint};
+use geos_core::kml::{write_kml_end, write_kml_start, write_placemark};
+use geos_core::measure::Units;
+use geos_core::mmap::Mmap;
+use geos_core::mvt::{encode_tile, DEFAULT_EXTENT};
+use geos_core::parquet::{ColumnType, GeoParquetWriter, Value};
+use geos_core::read::{read_ewkt_with_warnings, read_geometry_file_with_warnings, WktError};
+use geos_core::tiles::Tile;
+use geos_core::topojson::write_topojson;
+use geos_core::validity::repair_geometry;
+use geos_core::wkb::{decode_hex, read_wkb_with_warnings, write_hex_ewkb, write_hex_wkb};
+use h3o::{CellIndex, LatLng, Resolution};
+use itertools::Itertools;
+use s2::cellid::CellID;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashSet;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
 use wkt::ToWkt;
 
+use crate::error::{warn, GeosError};
+use crate::input::{is_csv_row, read_csv_rows, read_csv_table, CsvColumns};
+use crate::timing::{timed, Stage};
+
 pub fn fmt_value_enum<T: ValueEnum>(t: &T, f: &mut Formatter<'_>) -> std::fmt::Result {
     t.to_possible_value()
         .expect("no values are skipped")
@@ -10,23 +42,1317 @@ pub fn fmt_value_enum<T: ValueEnum>(t: &T, f: &mut Formatter<'_>) -> std::fmt::R
         .fmt(f)
 }
 
-pub fn fmt_geometry(fmt: &OutputFormat, geometries: Vec<Geometry>) {
+/**
+ * Writes values as they are produced, one per line or comma-separated on a single line.
+ */
+pub fn fmt_lines(
+    out: &mut dyn Write,
+    ctx: &mut RunContext,
+    fmt: &OutputFormat,
+    values: impl Iterator<Item = String>,
+) -> io::Result<()> {
+    let mut results = 0;
+    let mut values = values.inspect(|_| results += 1);
+    let options = ctx.output;
+    let written = timed(Stage::Format, || match fmt {
+        OutputFormat::Delimited => {
+            write_header(out, &options, &["value"])?;
+            values.try_for_each(|v| write_row(out, &options, &[&v]))
+        }
+        OutputFormat::Oneline => {
+            for (i, v) in values.by_ref().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                out.write_all(v.as_bytes())?;
+            }
+            writeln!(out)
+        }
+        OutputFormat::Json => {
+            let values = values.by_ref().collect_vec();
+            write_json_envelope(out, ctx, values.len(), vec![("values", json!(values))])
+        }
+        // Every other format writes values as CSV does.
+        _ => values.try_for_each(|v| writeln!(out, "{}", v)),
+    });
+    ctx.count_results(results);
+    written
+}
+
+/**
+ * Writes cells as they are produced, given their formatted IDs and levels. Delimited output has a
+ * `cell_id` and a `level` column, and other formats write the IDs as [fmt_lines] does.
+ */
+pub fn fmt_cells(
+    out: &mut dyn Write,
+    ctx: &mut RunContext,
+    fmt: &OutputFormat,
+    cells: impl Iterator<Item = (String, u8)>,
+) -> io::Result<()> {
+    let options = ctx.output;
+    match fmt {
+        OutputFormat::Delimited => {
+            let mut results = 0;
+            let written = timed(Stage::Format, || {
+                write_header(out, &options, &["cell_id", "level"])?;
+                cells.inspect(|_| results += 1).try_for_each(|(id, level)| {
+                    write_row(out, &options, &[&id, &level.to_string()])
+                })
+            });
+            ctx.count_results(results);
+            written
+        }
+        OutputFormat::Json => timed(Stage::Format, || {
+            let (ids, levels): (Vec<String>, Vec<u8>) = cells.unzip();
+            ctx.count_results(ids.len());
+            let fields = vec![
+                ("levels", distinct_levels(levels.into_iter())),
+                ("cells", json!(ids)),
+            ];
+            write_json_envelope(out, ctx, ids.len(), fields)
+        }),
+        _ => fmt_lines(out, ctx, fmt, cells.map(|(id, _)| id)),
+    }
+}
+
+/**
+ * Writes cell polygons, or the parts of geometries cut by cells, as they are produced, given the
+ * cells' formatted IDs and levels. Delimited output has `cell_id`, `level`, and `wkt` columns, and
+ * with `named`, KML placemarks are named by the IDs. Other formats write the polygons as
+ * [fmt_geometry] does. Captured polygons always keep their IDs.
+ */
+pub fn fmt_cell_polygons(
+    out: &mut dyn Write,
+    ctx: &mut RunContext,
+    fmt: &OutputFormat,
+    cells: impl Iterator<Item = (String, u8, Geometry)>,
+    named: bool,
+) -> io::Result<()> {
+    let options = ctx.output;
     match fmt {
-        OutputFormat::CSV => {
-            geometries
+        OutputFormat::Delimited if ctx.captured.is_none() => {
+            let mut results = 0;
+            let srid = ewkt_prefix(&options);
+            let written = timed(Stage::Format, || {
+                write_header(out, &options, &["cell_id", "level", "wkt"])?;
+                cells
+                    .inspect(|_| results += 1)
+                    .try_for_each(|(id, level, polygon)| {
+                        let mut wkt = srid.as_bytes().to_vec();
+                        write_wkt(&mut wkt, &polygon, &options)?;
+                        let wkt = String::from_utf8_lossy(&wkt);
+                        write_row(out, &options, &[&id, &level.to_string(), &wkt])
+                    })
+            });
+            ctx.count_results(results);
+            written
+        }
+        // The cell IDs are written whether or not the polygons are named.
+        OutputFormat::Json if ctx.captured.is_none() => timed(Stage::Format, || {
+            let cells = cells.collect_vec();
+            let count = cells.len();
+            ctx.count_results(count);
+            let fields = vec![
+                ("levels", distinct_levels(cells.iter().map(|c| c.1))),
+                ("cells", json!(cells.iter().map(|c| &c.0).collect_vec())),
+                (
+                    "geometries",
+                    json!(cells
+                        .into_iter()
+                        .map(|c| json_geometry(c.2, &options))
+                        .collect_vec()),
+                ),
+            ];
+            write_json_envelope(out, ctx, count, fields)
+        }),
+        _ => {
+            let named = named || ctx.captured.is_some();
+            let polygons = cells.map(|(id, _, polygon)| (named.then_some(id), polygon));
+            fmt_named_geometry(out, ctx, fmt, polygons)
+        }
+    }
+}
+
+/**
+ * Writes cells as a GeoJSON FeatureCollection of their polygons, each with the properties
+ * `cell_id`, its level under `level_key`, and its area in m² or the area unit given by --unit.
+ * Features are written as they are produced, so that large coverings aren't held in memory.
+ */
+pub fn fmt_cell_features(
+    out: &mut dyn Write,
+    ctx: &mut RunContext,
+    cells: impl Iterator<Item = (String, u8, Geometry, f64)>,
+    level_key: &str,
+) -> io::Result<()> {
+    if ctx.captured.is_some() {
+        let polygons = cells.map(|(id, level, polygon, _)| (id, level, polygon));
+        return fmt_cell_polygons(out, ctx, &OutputFormat::GeoJsonSeq, polygons, true);
+    }
+    let options = ctx.output;
+    let units = options.units;
+    let area_key = format!("area_{}", units.area.symbol());
+    let mut results = 0;
+    let written = timed(Stage::Format, || {
+        write!(out, r#"{{"type":"FeatureCollection","features":["#)?;
+        for (i, (id, level, mut polygon, area_m2)) in cells.enumerate() {
+            results += 1;
+            // GeoJSON coordinates are always lng/lat, whatever --output-crs is.
+            round_to_precision(&mut polygon, &options);
+            let properties =
+                json!({"cell_id": id, level_key: level, &area_key: units.area(area_m2)});
+            let geometry = geojson::Geometry::from(&polygon);
+            let feature = json!({"type": "Feature", "id": id, "properties": properties, "geometry": geometry});
+            if i > 0 {
+                write!(out, ",")?;
+            }
+            write!(out, "{}", feature)?;
+        }
+        writeln!(out, "]}}")
+    });
+    ctx.count_results(results);
+    written
+}
+
+/**
+ * Writes the header row of delimited output, if `--header` was given.
+ */
+fn write_header(out: &mut dyn Write, options: &OutputOptions, columns: &[&str]) -> io::Result<()> {
+    if options.header {
+        write_row(out, options, columns)?;
+    }
+    Ok(())
+}
+
+/**
+ * Writes a row of delimited output. Fields containing the delimiter, quotes, or line breaks, such as
+ * WKT in comma-separated output, are quoted, with their quotes doubled.
+ */
+fn write_row(out: &mut dyn Write, options: &OutputOptions, fields: &[&str]) -> io::Result<()> {
+    let delimiter = options.delimiter;
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(out, "{}", delimiter)?;
+        }
+        if field.contains([delimiter, '"', '\n', '\r']) {
+            write!(out, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            out.write_all(field.as_bytes())?;
+        }
+    }
+    writeln!(out)
+}
+
+/**
+ * Parses the `--delimiter` of delimited output, a single character or `tab`.
+ */
+pub fn parse_delimiter(arg: &str) -> Result<char, String> {
+    if arg == "tab" || arg == "\\t" {
+        return Ok('\t');
+    }
+    let mut chars = arg.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c != '"' && c != '\n' && c != '\r' => Ok(c),
+        _ => Err(format!(
+            "expected a single character other than a quote or line break, or `tab`, not '{}'",
+            arg
+        )),
+    }
+}
+
+/**
+ * The `SRID=<code>;` prefix of EWKT output, or nothing without `--ewkt`.
+ */
+fn ewkt_prefix(options: &OutputOptions) -> String {
+    options
+        .srid
+        .map(|srid| format!("SRID={};", srid))
+        .unwrap_or_default()
+}
+
+/**
+ * Writes results as a single JSON object: the number of results, the metadata recorded by the run,
+ * the milliseconds the run has taken so far, and then the given fields holding the results.
+ */
+fn write_json_envelope(
+    out: &mut dyn Write,
+    ctx: &RunContext,
+    count: usize,
+    fields: Vec<(&str, JsonValue)>,
+) -> io::Result<()> {
+    let elapsed = ctx.start.map(|start| start.elapsed());
+    let elapsed_ms = elapsed.map_or(0.0, |d| d.as_secs_f64() * 1000.0);
+    let metadata = ctx.metadata.clone();
+    let fields = std::iter::once(("count".to_string(), json!(count)))
+        .chain(metadata)
+        .chain(std::iter::once((
+            "elapsed_ms".to_string(),
+            json!(elapsed_ms),
+        )))
+        .chain(fields.into_iter().map(|(k, v)| (k.to_string(), v)));
+    out.write_all(b"{")?;
+    for (i, (key, value)) in fields.enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write!(out, "{}:{}", JsonValue::from(key), value)?;
+    }
+    writeln!(out, "}}")
+}
+
+/**
+ * A geometry as a GeoJSON geometry object, rounded to --precision. GeoJSON coordinates are always
+ * lng/lat, whatever --output-crs is.
+ */
+pub fn json_geometry(mut geometry: Geometry, options: &OutputOptions) -> JsonValue {
+    round_to_precision(&mut geometry, options);
+    json!(geojson::Geometry::from(&geometry))
+}
+
+/**
+ * The distinct levels of cells, in increasing order.
+ */
+fn distinct_levels(levels: impl Iterator<Item = u8>) -> JsonValue {
+    json!(levels.sorted().dedup().collect_vec())
+}
+
+/**
+ * How geometry arguments are read, as set by the global flags.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputOptions {
+    pub format: InputFormat,
+    /// The columns that points are read from with `--input-format csv`.
+    pub csv: CsvColumns,
+    pub units: InputUnits,
+    /// The coordinate system of geometry arguments, which are reprojected to lng/lat.
+    pub crs: CoordinateSystem,
+    /// Whether polygon rings are reoriented to wind exteriors counterclockwise and holes clockwise.
+    pub normalize_winding: bool,
+    /// Whether invalid polygons, e.g. bowties, are repaired rather than rejected by the commands
+    /// that intersect them.
+    pub repair: bool,
+    /// Whether sloppy WKT, e.g. with unclosed rings, is fixed up rather than rejected.
+    pub lenient: bool,
+}
+
+impl Default for InputOptions {
+    fn default() -> Self {
+        Self {
+            format: InputFormat::Wkt,
+            csv: CsvColumns::default(),
+            units: InputUnits::Degrees,
+            crs: CoordinateSystem::Wgs84,
+            normalize_winding: true,
+            repair: false,
+            lenient: false,
+        }
+    }
+}
+
+/**
+ * How results are written, as set by the global flags.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputOptions {
+    /// The units that lengths and areas are reported in.
+    pub units: Units,
+    /// The coordinate system that output geometries are reprojected to.
+    pub crs: CoordinateSystem,
+    /// The SRID that WKT output geometries are prefixed with as EWKT, if any.
+    pub srid: Option<u32>,
+    /// The separator of the columns of `-f delimited` output.
+    pub delimiter: char,
+    /// Whether `-f delimited` output starts with a header row naming its columns.
+    pub header: bool,
+    /// The z/x/y tile that `-f mvt` output is encoded for.
+    pub tile: Option<Tile>,
+    /// The decimals that output coordinates are rounded to, if any.
+    pub precision: Option<u8>,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            units: Units::default(),
+            crs: CoordinateSystem::default(),
+            srid: None,
+            delimiter: '\t',
+            header: false,
+            tile: None,
+            precision: None,
+        }
+    }
+}
+
+/// The prefix of the arguments that stand for geometries piped from the previous stage of a pipe.
+const PIPED_PREFIX: &str = "@pipe:";
+
+/// Geometries with their names, such as cell IDs.
+pub type NamedGeometries = Vec<(Option<String>, Geometry)>;
+
+/**
+ * The options a command runs with, as set by the global flags, and what it has written so far. Each
+ * run has its own, which is passed to the functions that read its arguments and write its results,
+ * so that commands run concurrently by the daemon or as the stages of a pipe don't share them.
+ */
+#[derive(Debug, Default)]
+pub struct RunContext {
+    pub input: InputOptions,
+    pub output: OutputOptions,
+    /// The number of results written with the `fmt_*` functions, so that `--strict` can fail on
+    /// empty results. It is unknown for commands that write their output without them.
+    results: Option<usize>,
+    /// Facts about the run, such as the containment mode of a covering, which `-f json` writes
+    /// alongside the results.
+    metadata: Vec<(String, JsonValue)>,
+    start: Option<Instant>,
+    /// The geometries written with [fmt_geometry], if they are captured instead of formatted.
+    captured: Option<NamedGeometries>,
+    /// Geometries passed from the previous stage of a pipe in memory rather than as WKT, which
+    /// placeholder arguments stand for.
+    piped: Vec<Geometry>,
+}
+
+impl RunContext {
+    /**
+     * Forgets what an earlier run recorded, and starts timing a new run with the given options.
+     * Captured and piped geometries are kept, since they are set up for the run by its caller.
+     */
+    pub fn start(&mut self, input: InputOptions, output: OutputOptions) {
+        self.input = input;
+        self.output = output;
+        self.results = None;
+        self.metadata.clear();
+        self.start = Some(Instant::now());
+    }
+
+    /**
+     * Records a fact about the run for `-f json` output, replacing any earlier value.
+     */
+    pub fn record_metadata(&mut self, key: &str, value: impl Into<JsonValue>) {
+        self.metadata.retain(|(k, _)| k != key);
+        self.metadata.push((key.to_string(), value.into()));
+    }
+
+    fn count_results(&mut self, results: usize) {
+        self.results = Some(self.results.unwrap_or(0) + results);
+    }
+
+    /**
+     * The number of results written, or `None` if nothing was written with the `fmt_*` functions.
+     */
+    pub fn result_count(&self) -> Option<usize> {
+        self.results
+    }
+
+    /**
+     * Sets the number of results written, e.g. to that of a run whose output is replayed from the
+     * cache.
+     */
+    pub fn set_result_count(&mut self, results: Option<usize>) {
+        self.results = results;
+    }
+
+    /**
+     * Captures the geometries written afterward with [fmt_geometry] instead of formatting them, with
+     * their names, such as the cell IDs of cell polygons and cuts, which are kept whether or not the
+     * command would write them.
+     */
+    pub fn capture(&mut self) {
+        self.captured = Some(vec![]);
+    }
+
+    /**
+     * Stops capturing, returning the geometries captured since [RunContext::capture].
+     */
+    pub fn take_captured(&mut self) -> NamedGeometries {
+        self.captured.take().unwrap_or_default()
+    }
+
+    /**
+     * Makes geometries available to the geometry arguments parsed afterward, returning the
+     * placeholder arguments that stand for them.
+     */
+    pub fn pipe_geometries(&mut self, geometries: Vec<Geometry>) -> Vec<String> {
+        let placeholders = (0..geometries.len())
+            .map(|i| format!("{}{}", PIPED_PREFIX, i))
+            .collect();
+        self.piped = geometries;
+        placeholders
+    }
+
+    fn piped_geometry(&self, arg: &str) -> Option<Geometry> {
+        let index: usize = arg.strip_prefix(PIPED_PREFIX)?.parse().ok()?;
+        self.piped.get(index).cloned()
+    }
+}
+
+/**
+ * Parses a geometry argument. Arguments of the form `@path` are read from a WKT or GeoJSON file as
+ * they are parsed, so that very large geometries are never held in memory as text. Geometries in
+ * radians or a projected CRS are converted to the lng/lat degrees used by every command, and
+ * polygon rings are reoriented unless disabled, since the H3 polyfill and boolean operations expect
+ * the standard orientation. With `--repair`, invalid polygons are repaired first, and with
+ * `--lenient`, sloppy WKT is fixed up with a warning. Geometries piped from an earlier stage of a
+ * pipe are used as they are.
+ */
+pub fn parse_geometry(arg: &str, ctx: &RunContext) -> Result<Geometry, Box<dyn Error>> {
+    read_geometry_arg(arg, ctx, true)
+}
+
+/**
+ * Like `parse_geometry`, but without the warning for coordinates out of range for lng/lat degrees,
+ * for geometries whose axes may be in either order, e.g. lat/lng.
+ */
+pub fn parse_geometry_either_axes(arg: &str, ctx: &RunContext) -> Result<Geometry, Box<dyn Error>> {
+    read_geometry_arg(arg, ctx, false)
+}
+
+/**
+ * Parses WKT given in a request, e.g. to `geos serve`, read with the given options as
+ * [parse_geometry] reads arguments with those of the run. The WKT is never read as an `@path` file
+ * or a piped geometry, and `--input-format` doesn't apply.
+ */
+pub fn parse_wkt_with_options(
+    wkt: &str,
+    options: &InputOptions,
+) -> Result<Geometry, Box<dyn Error>> {
+    let options = InputOptions {
+        format: InputFormat::Wkt,
+        ..options.clone()
+    };
+    read_geometry(wkt, None, &options, true)
+}
+
+fn read_geometry_arg(
+    arg: &str,
+    ctx: &RunContext,
+    check_range: bool,
+) -> Result<Geometry, Box<dyn Error>> {
+    // Piped geometries were already read by an earlier stage.
+    if let Some(geometry) = ctx.piped_geometry(arg) {
+        return Ok(geometry);
+    }
+    read_geometry(arg, arg.strip_prefix('@'), &ctx.input, check_range)
+}
+
+/**
+ * Reads a geometry from the file at `path`, if given, or else from `arg` itself.
+ */
+fn read_geometry(
+    arg: &str,
+    path: Option<&str>,
+    options: &InputOptions,
+    check_range: bool,
+) -> Result<Geometry, Box<dyn Error>> {
+    timed(Stage::Parse, || {
+        let mut warnings = vec![];
+        let (origin, geometry) = match (path, options.format) {
+            (Some(path), InputFormat::Wkt) => (
+                path,
+                read_geometry_file_with_warnings(Path::new(path), options.lenient, &mut warnings),
+            ),
+            (None, InputFormat::Wkt) => (
+                "argument",
+                read_ewkt_with_warnings(arg.as_bytes(), options.lenient, &mut warnings),
+            ),
+            (Some(path), InputFormat::Wkb) => (
+                path,
+                read_wkb_file(path, &mut warnings).map(|geometry| (None, geometry)),
+            ),
+            (None, InputFormat::Wkb) => (
+                "argument",
+                decode_hex(arg)
+                    .and_then(|wkb| read_wkb_with_warnings(&wkb, &mut warnings))
+                    .map(|geometry| (None, geometry)),
+            ),
+            (path, InputFormat::Csv) => (
+                path.unwrap_or("argument"),
+                match path {
+                    Some(_) => read_csv_rows(arg, &options.csv),
+                    None => read_csv_table("argument", arg, &options.csv),
+                }
+                .map(|rows| {
+                    let points = rows.into_iter().map(|row| row.point).collect();
+                    (None, Geometry::MultiPoint(points))
+                }),
+            ),
+        };
+        let (srid, mut geometry) = geometry.map_err(|e| -> Box<dyn Error> {
+            if e.is::<io::Error>() {
+                return e;
+            }
+            let Some(wkt_error) = e.downcast_ref::<WktError>() else {
+                return GeosError::Parse(e.to_string()).into();
+            };
+            match path {
+                Some(path) => match read_text_file(Path::new(path)) {
+                    Ok(map) => GeosError::wkt(wkt_error, path, &String::from_utf8_lossy(&map)),
+                    Err(_) => GeosError::Parse(e.to_string()),
+                },
+                None => GeosError::wkt(wkt_error, origin, arg),
+            }
+            .into()
+        })?;
+        for warning in warnings {
+            warn(format!("{}: {}", origin, warning))?;
+        }
+        // The SRID of EWKT gives the CRS of its coordinates in place of --crs. PostGIS uses 0 for
+        // an unknown SRID.
+        let srid_options;
+        let options = match srid {
+            Some(srid) if srid != 0 => {
+                srid_options = InputOptions {
+                    crs: srid.to_string().parse().map_err(|e| {
+                        GeosError::Parse(format!("{}: SRID={}: {}", origin, srid, e))
+                    })?,
+                    ..options.clone()
+                };
+                &srid_options
+            }
+            _ => options,
+        };
+        to_lng_lat(origin, &mut geometry, options, check_range)?;
+        if options.repair {
+            let repaired = repair_geometry(&mut geometry);
+            if repaired > 0 {
+                warn(format!(
+                    "{}: repaired {} invalid polygons",
+                    origin, repaired
+                ))?;
+            }
+        }
+        if options.normalize_winding {
+            let reversed = normalize_winding(&mut geometry);
+            if reversed > 0 {
+                warn(format!(
+                    "{}: reversed {} polygon rings to wind exteriors counterclockwise and holes clockwise",
+                    origin, reversed
+                ))?;
+            }
+        }
+        if let Some(bbox) = geometry.bounding_rect() {
+            tracing::debug!(
+                "parsed {}: {} with {} coordinates within BOX({} {},{} {})",
+                origin,
+                geometry_type(&geometry),
+                geometry.coords_count(),
+                bbox.min().x,
+                bbox.min().y,
+                bbox.max().x,
+                bbox.max().y
+            );
+        }
+        Ok(geometry)
+    })
+}
+
+/**
+ * Reads a WKB file, which may hold either the raw bytes or their hex encoding.
+ */
+fn read_wkb_file(path: &str, warnings: &mut Vec<String>) -> Result<Geometry, Box<dyn Error>> {
+    let map = read_text_file(Path::new(path))?;
+    match map.first() {
+        // Raw WKB starts with its byte order, 0 or 1, and hex WKB with the digit 0.
+        Some(0 | 1) => read_wkb_with_warnings(&map, warnings),
+        _ => read_wkb_with_warnings(&decode_hex(&String::from_utf8_lossy(&map))?, warnings),
+    }
+}
+
+/**
+ * Converts a geometry read in radians or a projected CRS to lng/lat degrees.
+ */
+fn to_lng_lat(
+    origin: &str,
+    geometry: &mut Geometry,
+    options: &InputOptions,
+    check_range: bool,
+) -> Result<(), Box<dyn Error>> {
+    if options.units == InputUnits::Radians {
+        geometry.map_coords_in_place(|c| coord! {x: c.x.to_degrees(), y: c.y.to_degrees()});
+    }
+    match options.crs {
+        // Projected coordinates read as degrees give nonsense results rather than errors.
+        CoordinateSystem::Wgs84 => {
+            if check_range
+                && geometry.bounding_rect().is_some_and(|bbox| {
+                    bbox.min().x < -360.0
+                        || bbox.max().x > 360.0
+                        || bbox.min().y < -90.0
+                        || bbox.max().y > 90.0
+                })
+            {
+                warn(format!(
+                    "{}: coordinates are out of range for lng/lat degrees; give the CRS of projected coordinates with --crs",
+                    origin
+                ))?;
+            }
+        }
+        crs => crs.to_wgs84(geometry),
+    }
+    Ok(())
+}
+
+/**
+ * Parses arguments containing points, such as POINT, MULTIPOINT, or GEOMETRYCOLLECTION geometries,
+ * into their points.
+ */
+pub fn parse_points(args: &[String], ctx: &RunContext) -> Result<Vec<Point>, Box<dyn Error>> {
+    args.iter()
+        .map(|arg| collect_points(&parse_geometry(arg, ctx)?))
+        .flatten_ok()
+        .collect()
+}
+
+/**
+ * Parses arguments containing points with weights. CSV, given inline, piped, or as `@path.csv`, is
+ * read with [read_csv_rows], with the weight in `weight_column`, a column name from a header row or
+ * a 0-based index. Consecutive rows given as separate arguments form one table, so that a header
+ * row can be given on its own. Other arguments are parsed as geometries, with each of their points
+ * weighing one. With `--input-format csv`, every argument is CSV.
+ */
+pub fn parse_weighted_points(
+    args: &[String],
+    weight_column: Option<&str>,
+    ctx: &RunContext,
+) -> Result<Vec<(Point, f64)>, Box<dyn Error>> {
+    let options = &ctx.input;
+    let all_csv = options.format == InputFormat::Csv;
+    let mut tables = vec![];
+    let mut inline_rows: Vec<&str> = vec![];
+    let mut geometries = vec![];
+    for arg in args {
+        let is_table = match arg.strip_prefix('@') {
+            Some(path) => all_csv || path.ends_with(".csv"),
+            None => all_csv || is_csv_row(arg.lines().next().unwrap_or_default()),
+        };
+        if !is_table {
+            geometries.push(arg.clone());
+        } else if arg.starts_with('@') || arg.contains('\n') {
+            if !inline_rows.is_empty() {
+                tables.push(inline_rows.drain(..).join("\n"));
+            }
+            tables.push(arg.clone());
+        } else {
+            inline_rows.push(arg);
+        }
+    }
+    if !inline_rows.is_empty() {
+        tables.push(inline_rows.join("\n"));
+    }
+    if !geometries.is_empty() && weight_column.is_some() {
+        return Err("--weight-column needs points given as CSV rows".into());
+    }
+
+    let mut points = parse_points(&geometries, ctx)?
+        .into_iter()
+        .map(|point| (point, 1.0))
+        .collect_vec();
+    let mut rows = vec![];
+    for table in &tables {
+        for row in read_csv_rows(table, &options.csv)? {
+            let weight = match weight_column {
+                Some(column) => row.number(column)?,
+                None => 1.0,
+            };
+            rows.push((row.point, weight));
+        }
+    }
+    points.extend(weighted_points_to_lng_lat(rows, options)?);
+    Ok(points)
+}
+
+/**
+ * Converts points read in radians or a projected CRS to lng/lat degrees, keeping their weights.
+ */
+fn weighted_points_to_lng_lat(
+    points: Vec<(Point, f64)>,
+    options: &InputOptions,
+) -> Result<Vec<(Point, f64)>, Box<dyn Error>> {
+    let mut geometry = Geometry::MultiPoint(points.iter().map(|(point, _)| *point).collect());
+    to_lng_lat("CSV", &mut geometry, options, true)?;
+    let Geometry::MultiPoint(converted) = geometry else {
+        unreachable!("conversion keeps the geometry type");
+    };
+    Ok(converted
+        .into_iter()
+        .zip(points)
+        .map(|(p, (_, w))| (p, w))
+        .collect())
+}
+
+pub fn geometry_type(geometry: &Geometry) -> &'static str {
+    match geometry {
+        Geometry::Point(_) => "Point",
+        Geometry::Line(_) => "Line",
+        Geometry::LineString(_) => "LineString",
+        Geometry::Polygon(_) => "Polygon",
+        Geometry::MultiPoint(_) => "MultiPoint",
+        Geometry::MultiLineString(_) => "MultiLineString",
+        Geometry::MultiPolygon(_) => "MultiPolygon",
+        Geometry::GeometryCollection(_) => "GeometryCollection",
+        Geometry::Rect(_) => "Rect",
+        Geometry::Triangle(_) => "Triangle",
+    }
+}
+
+/**
+ * Parses a list of H3 cell arguments. An invalid cell is pointed at within the list.
+ */
+pub fn parse_h3_cells(cells: &[String]) -> Result<Vec<CellIndex>, GeosError> {
+    parse_cells("H3", cells, parse_h3_cell)
+}
+
+/**
+ * Parses a list of S2 cell arguments given as long IDs. An invalid cell is pointed at within the
+ * list.
+ */
+pub fn parse_s2_cells(cells: &[String]) -> Result<Vec<CellID>, GeosError> {
+    parse_cells("S2", cells, parse_s2_cell)
+}
+
+fn parse_cells<T>(
+    grid: &'static str,
+    cells: &[String],
+    parse: fn(&str) -> Result<T, String>,
+) -> Result<Vec<T>, GeosError> {
+    cells
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| parse(cell).map_err(|e| GeosError::cell_in_list(grid, cells, i, &e)))
+        .collect()
+}
+
+/**
+ * Parses an H3 cell read from a file, where it is not part of an argument list.
+ */
+pub fn parse_h3_cell_from_file(cell: &str) -> Result<CellIndex, GeosError> {
+    parse_h3_cell(cell).map_err(|e| GeosError::cell("H3", cell, &e))
+}
+
+/**
+ * Parses an S2 cell read from a file, where it is not part of an argument list.
+ */
+pub fn parse_s2_cell_from_file(cell: &str) -> Result<CellID, GeosError> {
+    parse_s2_cell(cell).map_err(|e| GeosError::cell("S2", cell, &e))
+}
+
+fn parse_h3_cell(cell: &str) -> Result<CellIndex, String> {
+    CellIndex::from_str(cell).map_err(|e| e.reason.to_string())
+}
+
+fn parse_s2_cell(cell: &str) -> Result<CellID, String> {
+    let cell_id = CellID(cell.parse().map_err(|e: ParseIntError| e.to_string())?);
+    if !cell_id.is_valid() {
+        return Err("not a valid S2 cell ID".to_string());
+    }
+    Ok(cell_id)
+}
+
+/**
+ * Parses an H3 level argument, which must be in [0, 15].
+ */
+pub fn parse_h3_level(arg: &str) -> Result<u8, String> {
+    parse_level(arg, 15, false)
+}
+
+/**
+ * Parses an S2 level argument, which must be in [0, 30].
+ */
+pub fn parse_s2_level(arg: &str) -> Result<u8, String> {
+    parse_level(arg, 30, false)
+}
+
+fn parse_level(arg: &str, max: u8, clamp: bool) -> Result<u8, String> {
+    let level: i64 = arg.parse().map_err(|e: ParseIntError| e.to_string())?;
+    if clamp {
+        return Ok(level.clamp(0, max.into()) as u8);
+    }
+    match u8::try_from(level) {
+        Ok(level) if level <= max => Ok(level),
+        _ => Err(format!("{} is not in 0..={}", level, max)),
+    }
+}
+
+/// The top-level command, ID, and maximum of each level argument parsed by [parse_h3_level] or
+/// [parse_s2_level].
+const LEVEL_ARGS: [(&str, &str, u8); 6] = [
+    ("h3", "level", 15),
+    ("s2", "level", 30),
+    ("s2", "min_level", 30),
+    ("s2", "max_level", 30),
+    ("raster", "h3_level", 15),
+    ("raster", "s2_level", 30),
+];
+
+/**
+ * Makes the H3 and S2 level arguments throughout a command tree saturate at the nearest valid level
+ * instead of failing when they are out of range, for `--clamp`. Levels are checked by clap as they
+ * are parsed, so this is done to the command before the arguments are parsed.
+ */
+pub fn clamp_levels(command: Command) -> Command {
+    clamp_levels_under(command, None)
+}
+
+fn clamp_levels_under(mut command: Command, scope: Option<&str>) -> Command {
+    for (arg_scope, id, max) in LEVEL_ARGS {
+        if scope == Some(arg_scope) && command.get_arguments().any(|arg| arg.get_id() == id) {
+            command = command.mut_arg(id, |arg| {
+                arg.value_parser(move |level: &str| parse_level(level, max, true))
+            });
+        }
+    }
+    let names = command
+        .get_subcommands()
+        .map(|s| s.get_name().to_string())
+        .collect_vec();
+    for name in names {
+        // Subcommands inherit the scope of the top-level command they are nested under.
+        let scope = scope.or(Some(name.as_str()));
+        command = command.mut_subcommand(&name, |s| clamp_levels_under(s, scope));
+    }
+    command
+}
+
+/**
+ * Checks an H3 level argument.
+ */
+pub fn h3_resolution(level: u8) -> Result<Resolution, GeosError> {
+    Resolution::try_from(level).map_err(|_| GeosError::Level {
+        grid: "H3".to_string(),
+        level,
+        max: 15,
+    })
+}
+
+/**
+ * Reads the unique cell IDs in a file in order of first appearance. Cells are separated by newlines
+ * or commas, so that both the CSV and oneline outputs of other commands can be read back.
+ */
+pub fn read_cells(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    timed(Stage::Parse, || {
+        let map = read_text_file(path)?;
+        let contents = map
+            .as_str()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(contents
+            .split(['\n', ','])
+            .map(str::trim)
+            .filter(|cell| !cell.is_empty())
+            .unique()
+            .map(String::from)
+            .collect_vec())
+    })
+}
+
+/**
+ * Memory-maps a text file, so that large inputs are parsed in place rather than read into memory.
+ * Use `Mmap::as_str` for its contents.
+ */
+pub fn read_text_file(path: &Path) -> Result<Mmap, Box<dyn Error>> {
+    Ok(Mmap::open(path).map_err(|e| format!("{}: {}", path.display(), e))?)
+}
+
+pub fn fmt_matrix(
+    out: &mut dyn Write,
+    fmt: &MatrixFormat,
+    matrix: Vec<Vec<f64>>,
+) -> io::Result<()> {
+    timed(Stage::Format, || match fmt {
+        MatrixFormat::CSV => matrix
+            .iter()
+            .try_for_each(|row| writeln!(out, "{}", row.iter().join(","))),
+        MatrixFormat::Json => {
+            let mut rows = matrix
                 .iter()
-                .for_each(|p| println!("{}", p.wkt_string()));
+                .map(|row| format!("[{}]", row.iter().join(",")));
+            writeln!(out, "[{}]", rows.join(","))
+        }
+    })
+}
+
+/**
+ * Writes geometries as WKT as they are produced, one per line or as a single `GEOMETRYCOLLECTION`.
+ */
+pub fn fmt_geometry(
+    out: &mut dyn Write,
+    ctx: &mut RunContext,
+    fmt: &OutputFormat,
+    geometries: impl IntoIterator<Item = Geometry>,
+) -> io::Result<()> {
+    let geometries = geometries.into_iter().map(|g| (None, g));
+    fmt_named_geometry(out, ctx, fmt, geometries)
+}
+
+/**
+ * Like [fmt_geometry], but with a name for each geometry, such as its cell ID. Only KML output
+ * writes the names, as the names of the placemarks.
+ */
+pub fn fmt_named_geometry(
+    out: &mut dyn Write,
+    ctx: &mut RunContext,
+    fmt: &OutputFormat,
+    geometries: impl IntoIterator<Item = (Option<String>, Geometry)>,
+) -> io::Result<()> {
+    let mut results = 0;
+    let named = geometries.into_iter().inspect(|_| results += 1);
+    if let Some(captured) = &mut ctx.captured {
+        match fmt {
+            OutputFormat::Oneline => {
+                let geometries = named.map(|(_, g)| g).collect_vec();
+                captured.push((
+                    None,
+                    Geometry::GeometryCollection(GeometryCollection(geometries)),
+                ));
+            }
+            _ => captured.extend(named),
+        }
+        ctx.count_results(results);
+        return Ok(());
+    }
+    let options = ctx.output;
+    let srid = ewkt_prefix(&options);
+    let written = timed(Stage::Format, || match fmt {
+        OutputFormat::CSV => named.into_iter().try_for_each(|(_, g)| {
+            out.write_all(srid.as_bytes())?;
+            write_wkt(out, &g, &options)?;
+            writeln!(out)
+        }),
+        OutputFormat::Delimited => {
+            write_header(out, &options, &["wkt"])?;
+            named.into_iter().try_for_each(|(_, g)| {
+                let mut wkt = srid.as_bytes().to_vec();
+                write_wkt(&mut wkt, &g, &options)?;
+                write_row(out, &options, &[&String::from_utf8_lossy(&wkt)])
+            })
+        }
+        OutputFormat::Wkb => named.into_iter().try_for_each(|(_, mut g)| {
+            prepare_output(&mut g, &options);
+            writeln!(out, "{}", write_hex_wkb(&g))
+        }),
+        OutputFormat::Ewkb => {
+            let crs = options.crs;
+            let srid = crs.epsg().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "EWKB output needs an output CRS with an EPSG code, not {}",
+                        crs
+                    ),
+                )
+            })?;
+            named.into_iter().try_for_each(|(_, mut g)| {
+                prepare_output(&mut g, &options);
+                writeln!(out, "{}", write_hex_ewkb(&g, srid))
+            })
+        }
+        // Each feature is written as it is produced, unlike a FeatureCollection, which would have
+        // to be closed after the last one.
+        OutputFormat::GeoJsonSeq => named.into_iter().try_for_each(|(_, mut g)| {
+            // GeoJSON coordinates are always lng/lat, whatever --output-crs is.
+            round_to_precision(&mut g, &options);
+            let geometry = geojson::Geometry::from(&g);
+            let feature = json!({"type": "Feature", "properties": {}, "geometry": geometry});
+            writeln!(out, "{}", feature)
+        }),
+        // The shared borders are only known once every geometry has been produced.
+        OutputFormat::TopoJson => {
+            let named = named
+                .map(|(name, mut g)| {
+                    prepare_output(&mut g, &options);
+                    (name, g)
+                })
+                .collect_vec();
+            write_topojson(out, &named)
+        }
+        OutputFormat::Json => {
+            let (names, geometries): (Vec<Option<String>>, Vec<JsonValue>) = named
+                .map(|(name, g)| (name, json_geometry(g, &options)))
+                .unzip();
+            let mut fields = vec![];
+            if names.iter().any(Option::is_some) {
+                fields.push(("names", json!(names)));
+            }
+            fields.push(("geometries", json!(geometries)));
+            write_json_envelope(out, ctx, geometries.len(), fields)
+        }
+        // Tile coordinates are always web mercator, whatever --output-crs is.
+        OutputFormat::Mvt => {
+            let tile = options.tile.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "MVT output needs the tile to encode, given by --tile z/x/y",
+                )
+            })?;
+            let named = named.collect_vec();
+            out.write_all(&encode_tile(&tile, "geos", DEFAULT_EXTENT, &named))
+        }
+        // KML coordinates are always lng/lat, whatever --output-crs is.
+        OutputFormat::Kml => {
+            write_kml_start(out)?;
+            for (name, mut g) in named {
+                round_to_precision(&mut g, &options);
+                write_placemark(out, name.as_deref(), &g)?;
+            }
+            write_kml_end(out)
+        }
+        // GPX coordinates are always lng/lat too.
+        OutputFormat::Gpx => {
+            write_gpx_start(out)?;
+            for (name, mut g) in named {
+                round_to_precision(&mut g, &options);
+                let points = collect_points(&g).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("GPX output only has waypoints: {}", e),
+                    )
+                })?;
+                for point in points {
+                    write_waypoint(out, name.as_deref(), point)?;
+                }
+            }
+            write_gpx_end(out)
         }
         OutputFormat::Oneline => {
-            println!("{}", GeometryCollection::new_from(geometries).wkt_string());
+            let mut geometries = named.map(|(_, g)| g).peekable();
+            out.write_all(srid.as_bytes())?;
+            if geometries.peek().is_none() {
+                let empty = GeometryCollection::<f64>::new_from(vec![]);
+                return writeln!(out, "{}", empty.wkt_string());
+            }
+            out.write_all(b"GEOMETRYCOLLECTION(")?;
+            for (i, g) in geometries.enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                write_wkt(out, &g, &options)?;
+            }
+            writeln!(out, ")")
+        }
+    });
+    ctx.count_results(results);
+    written
+}
+
+/**
+ * Writes the bins of points aggregated into cells, in the given order, either as `<cell>,<count>[,<weight>][,<density>]`
+ * lines or as a GeoJSON FeatureCollection of cell polygons. Weights are only written when
+ * `weighted`, and densities only given the cell areas in m², as the weight or count per unit of the
+ * area units given by --unit.
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn fmt_bins<C>(
+    out: &mut dyn Write,
+    ctx: &mut RunContext,
+    fmt: &BinFormat,
+    bins: Vec<(C, Bin)>,
+    weighted: bool,
+    area_m2: Option<&dyn Fn(&C) -> f64>,
+    name: impl Fn(&C) -> String,
+    polygon: impl Fn(&C) -> Polygon,
+) -> io::Result<()> {
+    let options = ctx.output;
+    let units = options.units;
+    let density = |cell: &C, bin: &Bin| {
+        let total = if weighted {
+            bin.weight
+        } else {
+            bin.count as f64
+        };
+        area_m2.map(|area_m2| total / units.area(area_m2(cell)))
+    };
+    match fmt {
+        BinFormat::CSV => {
+            let lines = bins.iter().map(|(cell, bin)| {
+                let mut line = format!("{},{}", name(cell), bin.count);
+                if weighted {
+                    line += &format!(",{}", bin.weight);
+                }
+                if let Some(density) = density(cell, bin) {
+                    line += &format!(",{}", density);
+                }
+                line
+            });
+            fmt_lines(out, ctx, &OutputFormat::CSV, lines)
+        }
+        BinFormat::Geojson => {
+            ctx.count_results(bins.len());
+            let density_key = format!("density_per_{}", units.area.symbol());
+            let features = bins.iter().map(|(cell, bin)| {
+                let mut properties = json!({"cell": name(cell), "count": bin.count});
+                if weighted {
+                    properties["weight"] = json!(bin.weight);
+                }
+                if let Some(density) = density(cell, bin) {
+                    properties[&density_key] = json!(density);
+                }
+                let mut polygon = Geometry::from(polygon(cell));
+                round_to_precision(&mut polygon, &options);
+                let geometry = geojson::Geometry::from(&polygon);
+                json!({"type": "Feature", "id": name(cell), "properties": properties, "geometry": geometry})
+            });
+            let collection =
+                json!({"type": "FeatureCollection", "features": features.collect_vec()});
+            timed(Stage::Format, || writeln!(out, "{}", collection))
+        }
+    }
+}
+
+/**
+ * Formats a geometry as WKT, prepared by [prepare_output].
+ */
+pub fn output_wkt(geometry: impl Into<Geometry>, options: &OutputOptions) -> String {
+    let mut geometry = geometry.into();
+    prepare_output(&mut geometry, options);
+    geometry.wkt_string()
+}
+
+/**
+ * Prepares a geometry to be written: reprojects it to --output-crs, and rounds its coordinates to
+ * --precision decimals.
+ */
+pub fn prepare_output(geometry: &mut Geometry, options: &OutputOptions) {
+    options.crs.from_wgs84(geometry);
+    round_coords(geometry, options.precision);
+}
+
+/**
+ * Rounds the coordinates of a geometry to --precision decimals, if given, so that output doesn't
+ * imply more accuracy than there is. Rounded coordinates are written with as few digits as they
+ * need.
+ */
+pub fn round_to_precision(geometry: &mut Geometry, options: &OutputOptions) {
+    round_coords(geometry, options.precision);
+}
+
+fn round_coords(geometry: &mut Geometry, precision: Option<u8>) {
+    if let Some(precision) = precision {
+        let scale = 10f64.powi(precision as i32);
+        // Adding zero turns the -0 of small negative coordinates into 0.
+        let round = |v: f64| (v * scale).round() / scale + 0.0;
+        geometry.map_coords_in_place(|c| coord! {x: round(c.x), y: round(c.y)});
+    }
+}
+
+/**
+ * Writes a geometry as WKT, prepared by [prepare_output]. Polygons, which make up most large
+ * outputs as cell geometries and cuts, are written coordinate by coordinate instead of through an
+ * intermediate WKT value and string.
+ */
+fn write_wkt(out: &mut dyn Write, geometry: &Geometry, options: &OutputOptions) -> io::Result<()> {
+    let prepared;
+    let geometry = match (options.crs, options.precision) {
+        (CoordinateSystem::Wgs84, None) => geometry,
+        _ => {
+            let mut geometry = geometry.clone();
+            prepare_output(&mut geometry, options);
+            prepared = geometry;
+            &prepared
+        }
+    };
+    match geometry {
+        Geometry::Polygon(polygon) if !polygon.exterior().0.is_empty() => {
+            write_polygon_wkt(out, polygon)
+        }
+        _ => out.write_all(geometry.wkt_string().as_bytes()),
+    }
+}
+
+fn write_polygon_wkt(out: &mut dyn Write, polygon: &Polygon) -> io::Result<()> {
+    out.write_all(b"POLYGON(")?;
+    let rings = std::iter::once(polygon.exterior()).chain(polygon.interiors());
+    for (i, ring) in rings.enumerate() {
+        out.write_all(if i > 0 { b",(" } else { b"(" })?;
+        for (j, c) in ring.0.iter().enumerate() {
+            if j > 0 {
+                out.write_all(b",")?;
+            }
+            write!(out, "{} {}", c.x, c.y)?;
         }
+        out.write_all(b")")?;
+    }
+    out.write_all(b")")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputUnits {
+    Degrees,
+    Radians,
+}
+impl Display for InputUnits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, ValueEnum)]
+pub enum BinFormat {
+    /// `<cell>,<count>`, followed by the summed weight when weighted and the density when
+    /// normalized by cell area.
+    CSV,
+    /// A FeatureCollection of cell polygons with `cell`, `count`, `weight`, and
+    /// `density_per_<unit>` properties, for hexbin maps.
+    Geojson,
+}
+impl Display for BinFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// WKT, or GeoJSON for inputs starting with `{`.
+    Wkt,
+    /// Hex-encoded WKB as written by databases, or for `@path` arguments, raw or hex WKB.
+    Wkb,
+    /// CSV with a header row, each row a point in the `--lng-column` and `--lat-column`.
+    Csv,
+}
+impl Display for InputFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, ValueEnum)]
 pub enum OutputFormat {
     CSV,
     Oneline,
+    /// Geometries as hex-encoded WKB, one per line. Other results are written as with CSV.
+    Wkb,
+    /// Geometries as hex-encoded extended WKB with the SRID of --output-crs, one per line, as read
+    /// by PostGIS. Other results are written as with CSV.
+    Ewkb,
+    /// Geometries as GeoJSON features, one per line, as read by tippecanoe. Other results are
+    /// written as with CSV.
+    #[value(name = "geojsonseq")]
+    GeoJsonSeq,
+    /// Geometries as a single TopoJSON topology, in which borders shared by adjacent polygons, such
+    /// as the pieces of a cut, are written once. Other results are written as with CSV.
+    #[value(name = "topojson")]
+    TopoJson,
+    /// Results as a single JSON object with metadata about the run: `count`, facts such as the
+    /// containment mode of a covering, `elapsed_ms`, and then the results as arrays of `cells` with
+    /// their distinct `levels`, GeoJSON `geometries`, or other `values`. Results written in other
+    /// shapes are written as with CSV.
+    Json,
+    /// Geometries as a binary Mapbox Vector Tile of the --tile z/x/y, with one layer named `geos`
+    /// whose features are clipped to the tile. Cells are named by a `name` property. Other results
+    /// are written as with CSV.
+    Mvt,
+    /// Geometries as a KML document of placemarks, e.g. for Google Earth. Other results are
+    /// written as with CSV.
+    Kml,
+    /// Points as a GPX document of waypoints, e.g. for handheld GPS units. Other results are
+    /// written as with CSV.
+    Gpx,
+    /// Rows of columns separated by --delimiter, a tab by default, with fields quoted as needed so
+    /// that WKT imports cleanly into spreadsheets. Cells have `cell_id` and `level` columns, and
+    /// geometries a `wkt` column. With --header, a header row names the columns.
+    Delimited,
 }
 impl Display for OutputFormat {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -34,6 +1360,236 @@ impl Display for OutputFormat {
     }
 }
 
+/**
+ * Options for ordering and deduplicating the cells written by a command, shared by every command
+ * that writes a list of cells so that they all behave the same.
+ */
+#[derive(Debug, Args)]
+pub struct CellListArgs {
+    #[arg(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "id",
+        help = "Sort the cells by ID, or along a Hilbert curve with `--sorted hilbert` so that nearby cells are listed together. Sorting requires the whole list in memory."
+    )]
+    sorted: Option<CellOrder>,
+
+    #[arg(long, help = "Drop repeated cells, keeping the first of each.")]
+    unique: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CellOrder {
+    /// In order of cell ID.
+    Id,
+    /// Along the Hilbert curve of S2 cells, which S2 cell IDs already follow.
+    Hilbert,
+}
+impl Display for CellOrder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+/**
+ * Options for writing the cells of a command to a GeoParquet file instead of stdout, for coverings
+ * too large to load from CSV.
+ */
+#[derive(Debug, Args)]
+pub struct GeoParquetArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write a GeoParquet file to this path instead of writing to stdout, with each cell's ID, resolution, and polygon (or cut piece) as lng/lat WKB. The path is printed once the file is complete."
+    )]
+    output: Option<PathBuf>,
+}
+
+impl GeoParquetArgs {
+    /**
+     * The path to write to, if output goes to a file rather than stdout.
+     */
+    pub fn path(&self) -> Option<&Path> {
+        self.output.as_deref()
+    }
+}
+
+/**
+ * Writes rows of cells, their resolutions, and their geometries as a GeoParquet file, and then
+ * its path to `out`. Geometries are lng/lat regardless of `--output-crs`, as GeoParquet expects.
+ */
+pub fn write_cell_geoparquet(
+    out: &mut dyn Write,
+    path: &Path,
+    rows: impl IntoIterator<Item = (String, u8, Geometry)>,
+) -> Result<(), Box<dyn Error>> {
+    let file = std::fs::File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let columns = [
+        ("cell", ColumnType::Utf8),
+        ("resolution", ColumnType::Int32),
+        ("geometry", ColumnType::Geometry),
+    ];
+    let mut writer = GeoParquetWriter::new(io::BufWriter::new(file), &columns)?;
+    for (cell, resolution, geometry) in rows {
+        writer.write_row(&[
+            Value::Utf8(&cell),
+            Value::Int32(resolution.into()),
+            Value::Geometry(&geometry),
+        ])?;
+    }
+    let rows = writer.finish()?;
+    tracing::info!("wrote {} rows", rows);
+    writeln!(out, "{}", path.display())?;
+    Ok(())
+}
+
+/**
+ * Options for writing a list of cells as an Arrow IPC file.
+ */
+#[derive(Debug, Args)]
+pub struct ArrowArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write an Arrow IPC (Feather) file to this path instead of writing to stdout, with each cell's ID as a 64-bit unsigned integer and its resolution, for loading into pandas or polars without parsing. The path is printed once the file is complete."
+    )]
+    arrow: Option<PathBuf>,
+}
+
+impl ArrowArgs {
+    /**
+     * The path to write to, if output goes to a file rather than stdout.
+     */
+    pub fn path(&self) -> Option<&Path> {
+        self.arrow.as_deref()
+    }
+}
+
+/**
+ * Writes rows of cell IDs and their resolutions as an Arrow IPC file, and then its path to `out`.
+ */
+pub fn write_cell_arrow(
+    out: &mut dyn Write,
+    path: &Path,
+    rows: impl IntoIterator<Item = (u64, u8)>,
+) -> Result<(), Box<dyn Error>> {
+    let file = std::fs::File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let columns = [
+        ("cell", arrow::ColumnType::UInt64),
+        ("resolution", arrow::ColumnType::UInt8),
+    ];
+    let mut writer = ArrowWriter::new(io::BufWriter::new(file), &columns)?;
+    for (cell, resolution) in rows {
+        writer.write_row(&[cell, resolution.into()])?;
+    }
+    let rows = writer.finish()?;
+    tracing::info!("wrote {} rows", rows);
+    writeln!(out, "{}", path.display())?;
+    Ok(())
+}
+
+/// The RFC 4648 alphabets of base32 and of URL-safe base64.
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE64_URL_ALPHABET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/**
+ * An encoding of the 64-bit ID of a cell, shared by the cell formats of both grids so that they
+ * write IDs the same way.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellEncoding {
+    Decimal,
+    /// Lowercase hex without leading zeros, as H3 writes its cells.
+    Hex,
+    Octal,
+    Binary,
+    /// The 8 big-endian bytes of the ID in RFC 4648 base32, without padding.
+    Base32,
+    /// The 8 big-endian bytes of the ID in RFC 4648 URL-safe base64, without padding.
+    Base64,
+}
+
+impl CellEncoding {
+    pub fn encode(&self, id: u64) -> String {
+        match self {
+            CellEncoding::Decimal => id.to_string(),
+            CellEncoding::Hex => format!("{:x}", id),
+            CellEncoding::Octal => format!("{:o}", id),
+            CellEncoding::Binary => format!("{:b}", id),
+            CellEncoding::Base32 => encode_bits(id, 5, BASE32_ALPHABET),
+            CellEncoding::Base64 => encode_bits(id, 6, BASE64_URL_ALPHABET),
+        }
+    }
+}
+
+/**
+ * Encodes the bits of an ID from the most significant, `bits` at a time, with zeros after the last
+ * bit to fill the last character.
+ */
+fn encode_bits(id: u64, bits: u32, alphabet: &[u8]) -> String {
+    let chars = 64u32.div_ceil(bits);
+    let padded = (id as u128) << (chars * bits - 64);
+    let mask = (1u128 << bits) - 1;
+    (0..chars)
+        .rev()
+        .map(|i| alphabet[((padded >> (i * bits)) & mask) as usize] as char)
+        .collect()
+}
+
+/**
+ * A cell of a grid system, as written by commands that output cells.
+ */
+pub trait GridCell: Copy + Ord + Hash {
+    /**
+     * The position of the cell along a Hilbert curve over the globe.
+     */
+    fn hilbert_key(&self) -> u64;
+}
+
+impl GridCell for CellID {
+    fn hilbert_key(&self) -> u64 {
+        self.0
+    }
+}
+
+impl GridCell for CellIndex {
+    fn hilbert_key(&self) -> u64 {
+        // H3 cells are ordered by the leaf S2 cell containing their center.
+        let center = LatLng::from(*self);
+        CellID::from(s2::latlng::LatLng::from_degrees(center.lat(), center.lng())).0
+    }
+}
+
+impl CellListArgs {
+    /**
+     * Orders and deduplicates cells as requested. Unsorted cells are still produced lazily, so
+     * that long lists are never held in memory unless they are sorted.
+     */
+    pub fn apply<'a, C: GridCell + 'a>(
+        &self,
+        cells: impl Iterator<Item = C> + 'a,
+    ) -> Box<dyn Iterator<Item = C> + 'a> {
+        let Some(order) = self.sorted else {
+            if self.unique {
+                let mut seen = HashSet::new();
+                return Box::new(cells.filter(move |cell| seen.insert(*cell)));
+            }
+            return Box::new(cells);
+        };
+
+        let mut cells = cells.collect_vec();
+        match order {
+            CellOrder::Id => cells.sort_unstable(),
+            CellOrder::Hilbert => cells.sort_unstable_by_key(|cell| (cell.hilbert_key(), *cell)),
+        }
+        if self.unique {
+            cells.dedup();
+        }
+        Box::new(cells.into_iter())
+    }
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 pub enum SplitStrategy {
     Bbox,
@@ -44,3 +1600,397 @@ impl Display for SplitStrategy {
         fmt_value_enum(self, f)
     }
 }
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, ValueEnum)]
+pub enum MatrixFormat {
+    CSV,
+    Json,
+}
+impl Display for MatrixFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum DistanceMethod {
+    /// Great-circle distance on a spherical Earth. Fast, but with up to ~0.5% error.
+    Haversine,
+    /// Geodesic distance on the WGS84 ellipsoid with Karney's algorithm, accurate to nanometers.
+    #[value(alias = "karney")]
+    Geodesic,
+}
+impl Display for DistanceMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+impl From<DistanceMethod> for geom::DistanceMethod {
+    fn from(method: DistanceMethod) -> Self {
+        match method {
+            DistanceMethod::Haversine => geom::DistanceMethod::Haversine,
+            DistanceMethod::Geodesic => geom::DistanceMethod::Geodesic,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{point, polygon, Geometry};
+    use wkt::{ToWkt, TryFromWkt};
+
+    use s2::cellid::CellID;
+    use serde_json::{json, Value as JsonValue};
+
+    use super::{
+        fmt_cell_features, fmt_cell_polygons, fmt_cells, fmt_geometry, json_geometry,
+        parse_delimiter, parse_h3_level, parse_level, parse_s2_level, parse_weighted_points,
+        write_wkt, CellEncoding, CellListArgs, CellOrder, InputFormat, InputOptions, OutputFormat,
+        OutputOptions, RunContext,
+    };
+    use crate::input::CsvColumns;
+
+    #[test]
+    fn test_cell_list_args() {
+        let cells = [3, 1, 3, 2].map(CellID);
+        let apply = |sorted, unique| {
+            CellListArgs { sorted, unique }
+                .apply(cells.into_iter())
+                .map(|c| c.0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(apply(None, false), [3, 1, 3, 2]);
+        assert_eq!(apply(None, true), [3, 1, 2]);
+        assert_eq!(apply(Some(CellOrder::Id), false), [1, 2, 3, 3]);
+        assert_eq!(apply(Some(CellOrder::Hilbert), true), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_h3_level("15"), Ok(15));
+        assert_eq!(parse_h3_level("16"), Err("16 is not in 0..=15".to_string()));
+        assert!(parse_s2_level("-1").is_err());
+
+        assert_eq!(parse_level("16", 15, true), Ok(15));
+        assert_eq!(parse_level("-1", 30, true), Ok(0));
+        assert_eq!(parse_level("300", 30, true), Ok(30));
+        assert!(parse_level("x", 30, true).is_err());
+    }
+
+    #[test]
+    fn test_write_wkt_matches_wkt_crate() {
+        for wkt in [
+            "POLYGON((0 0,1.5 0,1 1,0 0))",
+            "POLYGON((0 0,10 0,10 10,0 10,0 0),(1 1,2 1,2 2,1 1),(5 5,6 5,6 6,5 5))",
+            "POLYGON EMPTY",
+            "POINT(-0.1 1e-7)",
+        ] {
+            let geometry = Geometry::<f64>::try_from_wkt_str(wkt).unwrap();
+            let mut out = vec![];
+            write_wkt(&mut out, &geometry, &OutputOptions::default()).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), geometry.wkt_string());
+        }
+    }
+
+    #[test]
+    fn test_write_wkt_precision() {
+        let options = OutputOptions {
+            precision: Some(2),
+            ..OutputOptions::default()
+        };
+        let polygon = Geometry::Polygon(
+            polygon![(x: 0.0, y: 0.0), (x: 1.23456, y: -0.004), (x: 0.1, y: 99.999)],
+        );
+        let mut out = vec![];
+        write_wkt(&mut out, &polygon, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "POLYGON((0 0,1.23 0,0.1 100,0 0))"
+        );
+    }
+
+    #[test]
+    fn test_parse_weighted_points() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let ctx = RunContext::default();
+        let points = parse_weighted_points(
+            &args(&["lng,lat,fare", "-122.4,37.8,12.5", "1, 2, 3"]),
+            Some("fare"),
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(
+            points,
+            [
+                (point!(x: -122.4, y: 37.8), 12.5),
+                (point!(x: 1.0, y: 2.0), 3.0)
+            ]
+        );
+        assert_eq!(
+            parse_weighted_points(&args(&["3,4,5"]), Some("2"), &ctx).unwrap(),
+            [(point!(x: 3.0, y: 4.0), 5.0)]
+        );
+        assert_eq!(
+            parse_weighted_points(&args(&["MULTIPOINT(1 2,3 4)", "5,6"]), None, &ctx).unwrap(),
+            [
+                (point!(x: 1.0, y: 2.0), 1.0),
+                (point!(x: 3.0, y: 4.0), 1.0),
+                (point!(x: 5.0, y: 6.0), 1.0)
+            ]
+        );
+        assert!(parse_weighted_points(&args(&["POINT(1 2)"]), Some("2"), &ctx).is_err());
+        assert!(parse_weighted_points(&args(&["1,2"]), Some("fare"), &ctx).is_err());
+        assert!(parse_weighted_points(&args(&["1,2,x"]), Some("2"), &ctx).is_err());
+
+        // With --input-format csv, the columns are those given by the flags.
+        let ctx = RunContext {
+            input: InputOptions {
+                format: InputFormat::Csv,
+                csv: CsvColumns {
+                    lng: "longitude".to_string(),
+                    lat: "1".to_string(),
+                    id: None,
+                },
+                ..InputOptions::default()
+            },
+            ..RunContext::default()
+        };
+        let csv = "store,latitude,longitude,sales\na, 37.8,-122.4,10\nb,1,2,20\n";
+        assert_eq!(
+            parse_weighted_points(&[csv.to_string()], Some("sales"), &ctx).unwrap(),
+            [
+                (point!(x: -122.4, y: 37.8), 10.0),
+                (point!(x: 2.0, y: 1.0), 20.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delimited_output() {
+        assert_eq!(parse_delimiter("tab"), Ok('\t'));
+        assert_eq!(parse_delimiter(";"), Ok(';'));
+        assert!(parse_delimiter("\"").is_err());
+        assert!(parse_delimiter(";;").is_err());
+
+        let mut ctx = RunContext {
+            output: OutputOptions {
+                delimiter: ',',
+                header: true,
+                ..OutputOptions::default()
+            },
+            ..RunContext::default()
+        };
+        let mut out = vec![];
+        let polygon =
+            Geometry::Polygon(polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 0.0, y: 1.0)]);
+        let cells = [("a\"b".to_string(), 3, polygon)];
+        fmt_cell_polygons(
+            &mut out,
+            &mut ctx,
+            &OutputFormat::Delimited,
+            cells.into_iter(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "cell_id,level,wkt\n\"a\"\"b\",3,\"POLYGON((0 0,1 0,0 1,0 0))\"\n"
+        );
+    }
+
+    #[test]
+    fn test_json_output() {
+        // The context wasn't started, so no time has elapsed.
+        let mut ctx = RunContext::default();
+        ctx.record_metadata("level", 3);
+        ctx.record_metadata("mode", "first");
+        ctx.record_metadata("mode", "second");
+        let mut out = vec![];
+        let cells = [
+            ("b".to_string(), 4),
+            ("a".to_string(), 3),
+            ("c".to_string(), 4),
+        ];
+        fmt_cells(&mut out, &mut ctx, &OutputFormat::Json, cells.into_iter()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"count\":3,\"level\":3,\"mode\":\"second\",\"elapsed_ms\":0.0,\"levels\":[3,4],\"cells\":[\"b\",\"a\",\"c\"]}\n"
+        );
+    }
+
+    #[test]
+    fn test_json_geometry() {
+        let options = OutputOptions {
+            crs: "EPSG:3857".parse().unwrap(),
+            precision: Some(2),
+            ..OutputOptions::default()
+        };
+        let point = Geometry::Point(point!(x: -122.4194, y: 37.7749));
+        assert_eq!(
+            json_geometry(point, &options),
+            json!({"type": "Point", "coordinates": [-122.42, 37.77]})
+        );
+    }
+
+    #[test]
+    fn test_cell_encoding() {
+        let id = 0x85283083fffffff;
+        let encode = |encoding: CellEncoding| encoding.encode(id);
+        assert_eq!(encode(CellEncoding::Decimal), "599685771850416127");
+        assert_eq!(encode(CellEncoding::Hex), "85283083fffffff");
+        assert_eq!(encode(CellEncoding::Octal), "41224060407777777777");
+        assert_eq!(encode(CellEncoding::Base32), "BBJIGCB777776");
+        assert_eq!(encode(CellEncoding::Base64), "CFKDCD____8");
+        assert_eq!(CellEncoding::Base64.encode(u64::MAX), "__________8");
+    }
+
+    #[test]
+    fn test_fmt_cell_features() {
+        let mut out = vec![];
+        let cells = [
+            (
+                "a".to_string(),
+                3,
+                Geometry::Point(point!(x: 1.0, y: 2.0)),
+                10.0,
+            ),
+            (
+                "b".to_string(),
+                4,
+                Geometry::Point(point!(x: 3.0, y: 4.0)),
+                2.5,
+            ),
+        ];
+        fmt_cell_features(
+            &mut out,
+            &mut RunContext::default(),
+            cells.into_iter(),
+            "level",
+        )
+        .unwrap();
+        let collection: JsonValue = serde_json::from_slice(&out).unwrap();
+        assert_eq!(collection["features"].as_array().unwrap().len(), 2);
+        let feature = &collection["features"][1];
+        assert_eq!(feature["id"], "b");
+        assert_eq!(
+            feature["properties"],
+            json!({"cell_id": "b", "level": 4, "area_m2": 2.5})
+        );
+        assert_eq!(feature["geometry"]["coordinates"], json!([3.0, 4.0]));
+    }
+
+    #[test]
+    fn test_csv_file_without_input_format() {
+        let path = std::env::temp_dir().join(format!("geos-csv-test-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "store,lat,lng,sales\nmission,37.76,-122.42,\"1,200\"\n",
+        )
+        .unwrap();
+        let arg = format!("@{}", path.display());
+
+        // The columns are named by the header rather than taken by position.
+        let mut ctx = RunContext::default();
+        let points = parse_weighted_points(std::slice::from_ref(&arg), None, &ctx).unwrap();
+        assert_eq!(points, [(point!(x: -122.42, y: 37.76), 1.0)]);
+        ctx.input.csv = CsvColumns {
+            lng: "sales".to_string(),
+            ..CsvColumns::default()
+        };
+        let points = parse_weighted_points(&[arg], None, &ctx);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            points.unwrap_err().to_string(),
+            format!("{}: row 2: sales '1,200' is not a number", path.display())
+        );
+    }
+
+    #[test]
+    fn test_capture_geometries() {
+        let mut ctx = RunContext::default();
+        let point = Geometry::Point(point!(x: 1.0, y: 2.0));
+        ctx.capture();
+        let mut out = vec![];
+        fmt_geometry(&mut out, &mut ctx, &OutputFormat::CSV, [point.clone()]).unwrap();
+        assert!(out.is_empty());
+        assert_eq!(ctx.take_captured(), [(None, point.clone())]);
+
+        // Geometries are written again rather than captured.
+        fmt_geometry(&mut out, &mut ctx, &OutputFormat::CSV, [point]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "POINT(1 2)\n");
+        assert_eq!(ctx.result_count(), Some(2));
+    }
+
+    #[test]
+    fn test_geojsonseq_output() {
+        // Coordinates are rounded, but not reprojected.
+        let mut ctx = RunContext {
+            output: OutputOptions {
+                crs: "EPSG:3857".parse().unwrap(),
+                precision: Some(1),
+                ..OutputOptions::default()
+            },
+            ..RunContext::default()
+        };
+        let triangles = [
+            Geometry::Polygon(polygon![(x: 0.0, y: 1.0), (x: 1.0, y: 1.0), (x: 1.0, y: 0.0)]),
+            Geometry::Polygon(polygon![(x: 1.04, y: 0.0), (x: 0.0, y: 0.0), (x: 0.0, y: 1.0)]),
+        ];
+        let mut out = vec![];
+        fmt_geometry(&mut out, &mut ctx, &OutputFormat::GeoJsonSeq, triangles).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        // Each triangle is a feature of its own line, without a collection around them.
+        assert!(!out.contains("FeatureCollection"), "{}", out);
+        let features = out
+            .lines()
+            .map(|line| serde_json::from_str::<JsonValue>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(features.len(), 2);
+        for feature in &features {
+            assert_eq!(feature["type"], "Feature");
+            assert_eq!(feature["geometry"]["type"], "Polygon");
+        }
+        assert_eq!(
+            features[1]["geometry"]["coordinates"],
+            json!([[[1.0, 0.0], [0.0, 0.0], [0.0, 1.0], [1.0, 0.0]]])
+        );
+    }
+
+    #[test]
+    fn test_ewkb_output() {
+        let triangle = polygon![(x: 1.0, y: 0.0), (x: 0.0, y: 0.0), (x: 1.0, y: 1.0)];
+        let ewkb = |options: OutputOptions| {
+            let mut ctx = RunContext {
+                output: options,
+                ..RunContext::default()
+            };
+            let mut out = vec![];
+            let geometries = [Geometry::Polygon(triangle.clone())];
+            fmt_geometry(&mut out, &mut ctx, &OutputFormat::Ewkb, geometries).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        // POLYGON((1 0,0 0,1 1,1 0)) with SRID 4326, as written by PostGIS.
+        let ewkb_4326 = ewkb(OutputOptions::default());
+        let expected = [
+            "0103000020E6100000",
+            "0100000004000000",
+            "000000000000F03F0000000000000000",
+            "00000000000000000000000000000000",
+            "000000000000F03F000000000000F03F",
+            "000000000000F03F0000000000000000\n",
+        ];
+        assert_eq!(ewkb_4326, expected.concat());
+        // The type has the SRID flag, and is followed by the little-endian SRID.
+        assert_eq!(&ewkb_4326[2..10], "03000020");
+        assert_eq!(&ewkb_4326[10..18], "E6100000");
+
+        let ewkb_3857 = ewkb(OutputOptions {
+            crs: "EPSG:3857".parse().unwrap(),
+            ..OutputOptions::default()
+        });
+        assert_eq!(&ewkb_3857[2..18], "03000020110F0000");
+    }
+}
@@ -0,0 +1,106 @@
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use geo_types::Geometry;
+use geos_core::index::GeometryIndex;
+use itertools::Itertools;
+
+use crate::format::{parse_geometry, parse_points, read_text_file, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Commands for testing points against named regions.")]
+#[command(args_conflicts_with_subcommands = false)]
+#[command(arg_required_else_help = true)]
+pub struct GeofenceArgs {
+    #[command(subcommand)]
+    command: Option<GeofenceCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GeofenceCommands {
+    #[command(
+        about = "Find the fence that each point falls within, outputting `index,name` lines in input order, where index is the point's input position. Points on a fence's boundary are within it, and points within no fence have an empty name."
+    )]
+    #[command(arg_required_else_help = true)]
+    Classify {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POINT, MULTIPOINT, or GEOMETRYCOLLECTION strings containing the points to classify."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            long,
+            help = "A file of fences, one `name,WKT` line per fence. Names cannot contain commas."
+        )]
+        fences: PathBuf,
+
+        #[arg(
+            long,
+            help = "Output a line for every fence that a point falls within, in file order, instead of only the first."
+        )]
+        all: bool,
+    },
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_geofence_subcommand(
+    geofence: &GeofenceArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match &geofence.command {
+        Some(GeofenceCommands::Classify { wkt, fences, all }) => {
+            let (names, geometries) = read_fences(fences, ctx)?;
+            let index = GeometryIndex::new(&geometries);
+
+            for (i, point) in parse_points(wkt, ctx)?.iter().enumerate() {
+                let containing = index.containing(point);
+                match (containing.as_slice(), all) {
+                    ([], _) => writeln!(out, "{},", i)?,
+                    ([first, ..], false) => writeln!(out, "{},{}", i, names[*first])?,
+                    (containing, true) => containing
+                        .iter()
+                        .try_for_each(|fence| writeln!(out, "{},{}", i, names[*fence]))?,
+                }
+            }
+        }
+
+        None => {}
+    }
+    Ok(())
+}
+
+/**
+ * Reads `name,WKT` lines into fence names and geometries, ignoring blank lines.
+ */
+fn read_fences(
+    path: &Path,
+    ctx: &RunContext,
+) -> Result<(Vec<String>, Vec<Geometry>), Box<dyn Error>> {
+    let map = read_text_file(path)?;
+    let contents = map
+        .as_str()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let fences: Vec<(String, Geometry)> = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (name, wkt) = line
+                .split_once(',')
+                .ok_or_else(|| format!("expected a `name,WKT` fence, got: {}", line))?;
+            let geometry = parse_geometry(wkt.trim(), ctx)
+                .map_err(|e| format!("invalid fence `{}`: {}", name, e))?;
+            Ok::<_, Box<dyn Error>>((name.trim().to_string(), geometry))
+        })
+        .try_collect()?;
+    Ok(fences.into_iter().unzip())
+}
@@ -1,14 +1,33 @@
 use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
 
-use clap::{command, Args, Subcommand};
+use clap::{Args, Subcommand, ValueEnum};
 use clap_stdin::MaybeStdin;
-use geo::{Triangle, TriangulateEarcut};
-use geo_types::{Geometry, Polygon};
+use geo::MapCoordsInPlace;
+use geo_types::{coord, Geometry, LineString, Point, Polygon};
+use geos_core::crs::{LocalFrame, LocalProjection};
+use geos_core::geom::{
+    bearing, collect_lines, collect_polygons, destination, distance, distance_matrix,
+    geodesic_circle, great_circle_intersections, great_circle_path_intersection, normalize_winding,
+    partition_region, triangulate,
+};
+use geos_core::index::GeometryIndex;
+use geos_core::linear::{
+    cumulative_lengths, resample_count, resample_every, snap_point_with_lengths, Snap,
+};
+use geos_core::matching::{match_trace, matched_path};
+use geos_core::morph::Morph;
 use itertools::Itertools;
-use wkt::TryFromWkt;
+use wkt::ToWkt;
 
-use crate::format::{fmt_geometry, OutputFormat};
-use crate::geom::partition_region;
+use crate::error::GeosError;
+use crate::format::{
+    fmt_geometry, fmt_lines, fmt_matrix, fmt_value_enum, output_wkt, parse_geometry,
+    parse_geometry_either_axes, parse_points, read_text_file, round_to_precision, DistanceMethod,
+    MatrixFormat, OutputFormat, RunContext,
+};
 
 //==================================================
 // CLI spec.
@@ -50,6 +69,354 @@ pub enum GeomCommands {
         threshold: Option<f64>,
     },
 
+    #[command(arg_required_else_help = true)]
+    DistanceMatrix {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POINT, MULTIPOINT, or GEOMETRYCOLLECTION strings containing the origin points."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "[optional] A WKT MULTIPOINT or GEOMETRYCOLLECTION of destination points. By default, the origin points are also used as destinations, producing a square matrix."
+        )]
+        to: Option<String>,
+
+        #[arg(short, long, default_value_t = DistanceMethod::Haversine, help = "The method used to compute distances in meters, or the unit given by --unit.")]
+        method: DistanceMethod,
+
+        #[arg(short, long, default_value_t = MatrixFormat::CSV, help = "By default, outputs one row of comma-separated distances per origin point. The json format outputs a single nested array.")]
+        format: MatrixFormat,
+    },
+
+    #[command(
+        about = "Compute the distance in meters, or the unit given by --unit, along each leg between consecutive input points."
+    )]
+    #[command(arg_required_else_help = true)]
+    Distance {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POINT, MULTIPOINT, or GEOMETRYCOLLECTION strings containing at least two points."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(short, long, default_value_t = DistanceMethod::Haversine, help = "The method used to compute distances.")]
+        method: DistanceMethod,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs the distance of each leg on separate lines.")]
+        format: OutputFormat,
+    },
+
+    #[command(
+        about = "Compute the initial bearing in degrees clockwise from north along each leg between consecutive input points."
+    )]
+    #[command(arg_required_else_help = true)]
+    Bearing {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POINT, MULTIPOINT, or GEOMETRYCOLLECTION strings containing at least two points."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(short, long, default_value_t = DistanceMethod::Haversine, help = "The method used to compute bearings.")]
+        method: DistanceMethod,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs the bearing of each leg on separate lines.")]
+        format: OutputFormat,
+    },
+
+    #[command(
+        about = "Compute the points reached by traveling a distance along a bearing from each input point."
+    )]
+    #[command(arg_required_else_help = true)]
+    Destination {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POINT, MULTIPOINT, or GEOMETRYCOLLECTION strings containing the starting points."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "The initial bearing in degrees clockwise from north."
+        )]
+        bearing_deg: f64,
+
+        #[arg(short, long, help = "The distance to travel in meters.")]
+        distance_m: f64,
+
+        #[arg(short, long, default_value_t = DistanceMethod::Haversine, help = "The method used to compute destinations.")]
+        method: DistanceMethod,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs each destination as a WKT POINT on a separate line. Specifying the oneline format will consolidate the points into a WKT GEOMETRYCOLLECTION on a single line.")]
+        format: OutputFormat,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Knn {
+        #[arg(
+            last = true,
+            help = "WKT strings encoding the candidate geometries. GEOMETRYCOLLECTIONs are expanded into their members."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(short, long, help = "A WKT POINT to find the nearest candidates to.")]
+        point: String,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 1,
+            help = "The number of nearest candidates to return."
+        )]
+        k: usize,
+
+        #[arg(
+            long,
+            help = "[optional] A file containing additional candidate geometries, one WKT string per line."
+        )]
+        file: Option<PathBuf>,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs each nearest candidate on a separate line as `index,distance,WKT`, where index is the candidate's input position and distance is the haversine distance in meters or the unit given by --unit. Specifying the oneline format will consolidate the candidates into a WKT GEOMETRYCOLLECTION on a single line.")]
+        format: OutputFormat,
+    },
+
+    #[command(arg_required_else_help = true)]
+    SnapPoints {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POINT, MULTIPOINT, or GEOMETRYCOLLECTION strings containing the points to snap."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(short, long, help = "The WKT LINESTRING to snap the points onto.")]
+        line: String,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs each snapped point on a separate line as `distance,measure,WKT`, where distance is the haversine distance from the point to the line and measure is the distance along the line to the snapped point, both in meters or the unit given by --unit. Specifying the oneline format will consolidate the snapped points into a WKT GEOMETRYCOLLECTION on a single line.")]
+        format: OutputFormat,
+    },
+
+    #[command(arg_required_else_help = true)]
+    MapMatch {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POINT, MULTIPOINT, or GEOMETRYCOLLECTION strings containing the trace points, in order."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            required = true,
+            help = "WKT LINESTRING, MULTILINESTRING, or GEOMETRYCOLLECTION strings containing the candidate lines. May be given multiple times."
+        )]
+        lines: Vec<String>,
+
+        #[arg(
+            long,
+            default_value_t = 10.0,
+            help = "The expected noise of the trace points in meters. Larger values tolerate points farther from the lines."
+        )]
+        sigma_m: f64,
+
+        #[arg(
+            long,
+            default_value_t = 50.0,
+            help = "How strongly to penalize mismatches in meters between the distance traveled along the matched lines and the distance between trace points. Smaller values penalize detours more."
+        )]
+        beta_m: f64,
+
+        #[arg(
+            long,
+            default_value_t = 100.0,
+            help = "An extra distance in meters charged for switching between lines, which favors staying on the same line."
+        )]
+        switch_penalty_m: f64,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Output the index of the matched candidate line for each trace point instead of the matched LINESTRING."
+        )]
+        labels: bool,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "The format for labels. By default, outputs the label of each trace point on separate lines, in input order.")]
+        format: OutputFormat,
+    },
+
+    #[command(
+        about = "Resample lines into points at equal geodesic spacing along each line, starting at its first vertex."
+    )]
+    #[command(arg_required_else_help = true)]
+    Resample {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT LINESTRING, MULTILINESTRING, or GEOMETRYCOLLECTION strings containing the lines to resample."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            conflicts_with = "count",
+            required_unless_present = "count",
+            help = "The spacing between points in meters. The end of a line is only included if its length is a multiple of the spacing."
+        )]
+        every_m: Option<f64>,
+
+        #[arg(
+            short,
+            long,
+            help = "The number of points per line, including both ends."
+        )]
+        count: Option<usize>,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Prefix each point with its measure, the geodesic distance along its line in meters or the unit given by --unit, as `measure,WKT`."
+        )]
+        measures: bool,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs each point as a WKT POINT on a separate line. Specifying the oneline format will consolidate the points into a WKT GEOMETRYCOLLECTION on a single line.")]
+        format: OutputFormat,
+    },
+
+    #[command(
+        about = "Generate a geodesic circle, or an annulus if an inner radius is given, as a WKT POLYGON."
+    )]
+    #[command(arg_required_else_help = true)]
+    Circle {
+        #[arg(short, long, help = "A WKT POINT at the center of the circle.")]
+        center: String,
+
+        #[arg(short, long, help = "The geodesic radius of the circle in meters.")]
+        radius_m: f64,
+
+        #[arg(
+            short,
+            long,
+            help = "[optional] The geodesic radius in meters of a hole in the middle of the circle, which makes it an annulus."
+        )]
+        inner_radius_m: Option<f64>,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 64,
+            help = "The number of vertices used to approximate each circle."
+        )]
+        segments: usize,
+    },
+
+    #[command(
+        about = "Find where two great circle paths cross. Each path is the shortest great circle arc between two points."
+    )]
+    #[command(arg_required_else_help = true)]
+    GreatCircleIntersect {
+        #[arg(
+            last = true,
+            num_args = 2,
+            help = "Two WKT LINESTRINGs with two points each, defining the paths."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Output both antipodal points where the full great circles through the paths intersect, even if the paths themselves do not cross."
+        )]
+        full_circles: bool,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs each intersection as a WKT POINT on a separate line. Nothing is output if the paths do not cross.")]
+        format: OutputFormat,
+    },
+
+    #[command(
+        about = "Interpolate between two polygons, e.g. to animate how a boundary changed between two versions of a dataset."
+    )]
+    #[command(arg_required_else_help = true)]
+    Morph {
+        #[arg(
+            last = true,
+            num_args = 2,
+            help = "Two WKT POLYGONs with the same number of holes, the shapes at t = 0 and t = 1."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            conflicts_with = "steps",
+            required_unless_present = "steps",
+            help = "The position in [0, 1] of the shape between the polygons."
+        )]
+        t: Option<f64>,
+
+        #[arg(
+            short,
+            long,
+            help = "Output N + 1 shapes at t = 0, 1/N, ..., 1, e.g. as the frames of an animation."
+        )]
+        steps: Option<usize>,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs each shape as a WKT POLYGON on a separate line.")]
+        format: OutputFormat,
+    },
+
+    #[command(
+        about = "Swap the x and y coordinates of geometries, e.g. to rescue data given in lat/lng order."
+    )]
+    #[command(arg_required_else_help = true)]
+    SwapAxes {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more valid WKT strings. Each is output with its axes swapped on a separate line."
+        )]
+        wkt: Vec<String>,
+    },
+
+    #[command(
+        about = "Project a geometry into a local plane in meters centered on its centroid, with x east and y north, or back to lng/lat with --inverse."
+    )]
+    #[command(arg_required_else_help = true)]
+    ProjectLocal {
+        #[arg(
+            last = true,
+            help = "A valid WKT string encoding the geometry to project, in lng/lat or, with --inverse, in meters."
+        )]
+        wkt: MaybeStdin<String>,
+
+        #[arg(short, long, default_value_t = Projection::Aeqd, help = "The projection of the local plane.")]
+        projection: Projection,
+
+        #[arg(
+            short,
+            long,
+            help = "[optional] A WKT POINT at the center of the plane, instead of the centroid of the geometry. Required with --inverse, since the centroid of the original geometry is unknown."
+        )]
+        center: Option<String>,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            requires = "center",
+            help = "Unproject a geometry in meters back to lng/lat."
+        )]
+        inverse: bool,
+    },
+
     Triangulate {
         #[arg(last = true)]
         wkt: MaybeStdin<String>,
@@ -59,10 +426,35 @@ pub enum GeomCommands {
     },
 }
 
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum Projection {
+    /// Azimuthal equidistant, with exact distances and azimuths from the center.
+    Aeqd,
+    /// Transverse Mercator, which keeps shapes and angles near the center's meridian.
+    Tmerc,
+}
+impl Display for Projection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+impl From<Projection> for LocalProjection {
+    fn from(projection: Projection) -> Self {
+        match projection {
+            Projection::Aeqd => LocalProjection::Aeqd,
+            Projection::Tmerc => LocalProjection::Tmerc,
+        }
+    }
+}
+
 //==================================================
 // Core subcommand logic.
 //==================================================
-pub fn handle_geom_subcommand(geom: &GeomArgs) -> Result<(), Box<dyn Error>> {
+pub fn handle_geom_subcommand(
+    geom: &GeomArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
     match &geom.command {
         // Split geometry.
         Some(GeomCommands::Split {
@@ -71,27 +463,445 @@ pub fn handle_geom_subcommand(geom: &GeomArgs) -> Result<(), Box<dyn Error>> {
             format,
             threshold,
         }) => {
-            let geometry = Geometry::<f64>::try_from_wkt_str(wkt)?;
+            let geometry = parse_geometry(wkt, ctx)?;
+            let polygon: Polygon = geometry.try_into()?;
+            let partitions = partition_region(
+                &polygon,
+                *edge_proportion,
+                *threshold,
+                ctx.input.normalize_winding,
+            )?
+            .into_iter()
+            .map(Geometry::from)
+            .collect_vec();
+            fmt_geometry(out, ctx, format, partitions)?;
+        }
+
+        Some(GeomCommands::Triangulate { wkt, format }) => {
+            let geometry = parse_geometry(wkt, ctx)?;
             let polygon: Polygon = geometry.try_into()?;
-            let partitions = partition_region(&polygon, *edge_proportion, *threshold)
+            let triangles = triangulate(&polygon)?
                 .into_iter()
                 .map(Geometry::from)
                 .collect_vec();
-            fmt_geometry(format, partitions);
+            fmt_geometry(out, ctx, format, triangles)?;
         }
 
-        Some(GeomCommands::Triangulate { wkt, format }) => {
-            let geometry = Geometry::<f64>::try_from_wkt_str(wkt)?;
-            let polygon: Polygon = geometry.try_into()?;
-            let triangles: Vec<Geometry> = polygon
-                .earcut_triangles_iter()
+        Some(GeomCommands::DistanceMatrix {
+            wkt,
+            to,
+            method,
+            format,
+        }) => {
+            let from = parse_points(wkt, ctx)?;
+            let to = match to {
+                Some(to) => parse_points(std::slice::from_ref(to), ctx)?,
+                None => from.clone(),
+            };
+            let units = ctx.output.units;
+            let mut matrix = distance_matrix(&(*method).into(), &from, &to);
+            matrix
+                .iter_mut()
+                .flatten()
+                .for_each(|d| *d = units.length(*d));
+            fmt_matrix(out, format, matrix)?;
+        }
+
+        Some(GeomCommands::Distance {
+            wkt,
+            method,
+            format,
+        }) => {
+            let method = (*method).into();
+            let units = ctx.output.units;
+            let distances = parse_legs(wkt, ctx)?
                 .into_iter()
-                .map(Triangle::into)
-                .collect();
-            fmt_geometry(format, triangles);
+                .map(|(p1, p2)| units.length(distance(&method, &p1, &p2)).to_string());
+            fmt_lines(out, ctx, format, distances)?;
+        }
+
+        Some(GeomCommands::Bearing {
+            wkt,
+            method,
+            format,
+        }) => {
+            let method = (*method).into();
+            let bearings = parse_legs(wkt, ctx)?
+                .into_iter()
+                .map(|(p1, p2)| bearing(&method, &p1, &p2).to_string());
+            fmt_lines(out, ctx, format, bearings)?;
+        }
+
+        Some(GeomCommands::Destination {
+            wkt,
+            bearing_deg,
+            distance_m,
+            method,
+            format,
+        }) => {
+            let method = (*method).into();
+            let destinations = parse_points(wkt, ctx)?
+                .iter()
+                .map(|p| Geometry::from(destination(&method, p, *bearing_deg, *distance_m)))
+                .collect_vec();
+            fmt_geometry(out, ctx, format, destinations)?;
+        }
+
+        Some(GeomCommands::Knn {
+            wkt,
+            point,
+            k,
+            file,
+            format,
+        }) => {
+            let point: Point = parse_geometry(point, ctx)?.try_into()?;
+
+            let mut lines = wkt.clone();
+            if let Some(file) = file {
+                let map = read_text_file(file)?;
+                lines.extend(map.as_str()?.lines().map(str::to_string));
+            }
+            let candidates = parse_candidates(&lines, ctx)?;
+
+            let nearest = GeometryIndex::new(&candidates).nearest(&point, *k);
+            let units = ctx.output.units;
+            match format {
+                OutputFormat::CSV => nearest.iter().try_for_each(|(i, d)| {
+                    let d = units.length(*d);
+                    writeln!(
+                        out,
+                        "{},{},{}",
+                        i,
+                        d,
+                        output_wkt(candidates[*i].clone(), &ctx.output)
+                    )
+                })?,
+                _ => fmt_geometry(
+                    out,
+                    ctx,
+                    format,
+                    nearest.iter().map(|(i, _)| candidates[*i].clone()),
+                )?,
+            }
+        }
+
+        Some(GeomCommands::SnapPoints { wkt, line, format }) => {
+            let line: LineString = parse_geometry(line, ctx)?.try_into()?;
+            let lengths = cumulative_lengths(&line);
+            let snaps: Vec<Snap> = parse_points(wkt, ctx)?
+                .iter()
+                .map(|p| snap_point_with_lengths(&line, &lengths, p).ok_or("empty LINESTRING"))
+                .try_collect()?;
+
+            let units = ctx.output.units;
+            match format {
+                OutputFormat::CSV => snaps.iter().try_for_each(|s| {
+                    writeln!(
+                        out,
+                        "{},{},{}",
+                        units.length(s.distance_m),
+                        units.length(s.measure_m),
+                        output_wkt(s.point, &ctx.output)
+                    )
+                })?,
+                _ => fmt_geometry(
+                    out,
+                    ctx,
+                    format,
+                    snaps.iter().map(|s| Geometry::from(s.point)),
+                )?,
+            }
+        }
+
+        Some(GeomCommands::MapMatch {
+            wkt,
+            lines,
+            sigma_m,
+            beta_m,
+            switch_penalty_m,
+            labels,
+            format,
+        }) => {
+            let trace = parse_points(wkt, ctx)?;
+            let lines: Vec<LineString> = lines
+                .iter()
+                .map(|s| collect_lines(&parse_geometry(s, ctx)?))
+                .flatten_ok()
+                .try_collect()?;
+
+            let matches = match_trace(&trace, &lines, *sigma_m, *beta_m, *switch_penalty_m);
+            if *labels {
+                fmt_lines(out, ctx, format, matches.iter().map(|(i, _)| i.to_string()))?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    output_wkt(matched_path(&lines, &matches), &ctx.output)
+                )?;
+            }
+        }
+
+        Some(GeomCommands::Resample {
+            wkt,
+            every_m,
+            count,
+            measures,
+            format,
+        }) => {
+            if every_m.is_some_and(|d| d <= 0.0) {
+                return Err(
+                    GeosError::InvalidParameter("--every-m must be positive".into()).into(),
+                );
+            }
+            if *count == Some(0) {
+                return Err(GeosError::InvalidParameter("--count must be positive".into()).into());
+            }
+            let lines: Vec<LineString> = wkt
+                .iter()
+                .map(|s| collect_lines(&parse_geometry(s, ctx)?))
+                .flatten_ok()
+                .try_collect()?;
+            let points = lines
+                .iter()
+                .flat_map(|line| match (every_m, count) {
+                    (Some(every_m), _) => resample_every(line, *every_m),
+                    (None, count) => resample_count(line, count.unwrap_or_default()),
+                })
+                .collect_vec();
+
+            match format {
+                OutputFormat::CSV if *measures => {
+                    let units = ctx.output.units;
+                    points.iter().try_for_each(|(m, p)| {
+                        writeln!(out, "{},{}", units.length(*m), output_wkt(*p, &ctx.output))
+                    })?
+                }
+                _ => fmt_geometry(
+                    out,
+                    ctx,
+                    format,
+                    points.into_iter().map(|(_, p)| Geometry::from(p)),
+                )?,
+            }
+        }
+
+        Some(GeomCommands::Circle {
+            center,
+            radius_m,
+            inner_radius_m,
+            segments,
+        }) => {
+            let center: Point = parse_geometry(center, ctx)?.try_into()?;
+            // Every error of a circle is one of its parameters.
+            let circle = geodesic_circle(&center, *radius_m, *inner_radius_m, *segments)
+                .map_err(|e| GeosError::InvalidParameter(e.to_string()))?;
+            writeln!(out, "{}", output_wkt(circle, &ctx.output))?;
+        }
+
+        Some(GeomCommands::GreatCircleIntersect {
+            wkt,
+            full_circles,
+            format,
+        }) => {
+            let paths: Vec<(Point, Point)> =
+                wkt.iter().map(|s| parse_path(s, ctx)).try_collect()?;
+            let (a, b) = (paths[0], paths[1]);
+            let points = if *full_circles {
+                great_circle_intersections(a, b)?.to_vec()
+            } else {
+                great_circle_path_intersection(a, b)?
+                    .into_iter()
+                    .collect_vec()
+            };
+            fmt_geometry(out, ctx, format, points.into_iter().map(Geometry::from))?;
+        }
+
+        Some(GeomCommands::Morph {
+            wkt,
+            t,
+            steps,
+            format,
+        }) => {
+            let ts = match (t, steps) {
+                (Some(t), _) if !(0.0..=1.0).contains(t) => {
+                    return Err("-t must be in [0, 1]".into())
+                }
+                (Some(t), _) => vec![*t],
+                (None, Some(0)) => return Err("--steps must be positive".into()),
+                (None, steps) => {
+                    let steps = steps.unwrap_or(1);
+                    (0..=steps).map(|i| i as f64 / steps as f64).collect_vec()
+                }
+            };
+            let polygons: Vec<Polygon> = wkt.iter().map(|s| parse_polygon(s, ctx)).try_collect()?;
+            let morph = Morph::new(&polygons[0], &polygons[1])?;
+            let shapes = ts.into_iter().map(|t| Geometry::from(morph.at(t)));
+            fmt_geometry(out, ctx, format, shapes)?;
+        }
+
+        Some(GeomCommands::SwapAxes { wkt }) => {
+            for wkt in wkt {
+                // The input may be lat/lng, so it is not range-checked as lng/lat.
+                let mut geometry = parse_geometry_either_axes(wkt, ctx)?;
+                geometry.map_coords_in_place(|c| coord! {x: c.y, y: c.x});
+                // Swapping the axes mirrors the geometry, which reverses the winding of its rings.
+                if ctx.input.normalize_winding {
+                    normalize_winding(&mut geometry);
+                }
+                writeln!(out, "{}", output_wkt(geometry, &ctx.output))?;
+            }
+        }
+
+        Some(GeomCommands::ProjectLocal {
+            wkt,
+            projection,
+            center,
+            inverse,
+        }) => {
+            let geometry = parse_geometry(wkt, ctx)?;
+            let frame = match center {
+                Some(center) => LocalFrame::new(
+                    (*projection).into(),
+                    parse_geometry(center, ctx)?.try_into()?,
+                ),
+                None => LocalFrame::centered_on(&geometry, (*projection).into())
+                    .ok_or("an empty geometry has no centroid to center the plane on")?,
+            };
+            tracing::info!("local plane: {}", frame.crs());
+            let mut geometry = if *inverse {
+                frame.unproject_geometry(&geometry)
+            } else {
+                frame.project_geometry(&geometry)
+            };
+            round_to_precision(&mut geometry, &ctx.output);
+            writeln!(out, "{}", geometry.wkt_string())?;
         }
 
         None => {}
     }
     Ok(())
 }
+
+/**
+ * Parses points into the legs between consecutive points.
+ */
+fn parse_legs(wkt: &[String], ctx: &RunContext) -> Result<Vec<(Point, Point)>, Box<dyn Error>> {
+    let points = parse_points(wkt, ctx)?;
+    if points.len() < 2 {
+        return Err("at least two points are required".into());
+    }
+    Ok(points.into_iter().tuple_windows().collect_vec())
+}
+
+/**
+ * Parses a great circle path from a WKT LINESTRING with exactly two points.
+ */
+fn parse_path(wkt: &str, ctx: &RunContext) -> Result<(Point, Point), Box<dyn Error>> {
+    let line: LineString = parse_geometry(wkt, ctx)?.try_into()?;
+    match line.0.as_slice() {
+        [start, end] => Ok(((*start).into(), (*end).into())),
+        _ => Err(format!("expected a LINESTRING with two points, got: {}", wkt).into()),
+    }
+}
+
+/**
+ * Parses a single polygon, which may be a MULTIPOLYGON or GEOMETRYCOLLECTION with one polygon.
+ */
+fn parse_polygon(wkt: &str, ctx: &RunContext) -> Result<Polygon, Box<dyn Error>> {
+    let polygons = collect_polygons(&parse_geometry(wkt, ctx)?)?;
+    match <[_; 1]>::try_from(polygons) {
+        Ok([polygon]) => Ok(polygon),
+        Err(polygons) => {
+            Err(format!("expected a single polygon, got {} polygons", polygons.len()).into())
+        }
+    }
+}
+
+fn parse_candidates(lines: &[String], ctx: &RunContext) -> Result<Vec<Geometry>, Box<dyn Error>> {
+    let mut candidates = vec![];
+    for line in lines.iter().filter(|l| !l.trim().is_empty()) {
+        match parse_geometry(line, ctx)? {
+            Geometry::GeometryCollection(collection) => candidates.extend(collection),
+            geometry => candidates.push(geometry),
+        }
+    }
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::exit_code;
+    use crate::run_captured;
+
+    #[test]
+    fn test_split_and_triangulate() {
+        let run = |args: &[&str]| run_captured(args.iter().map(|a| a.to_string()).collect());
+        let triangle = "POLYGON((0 0,1 0,1 1,0 0))";
+        assert_eq!(
+            run(&["geom", "triangulate", "--", triangle]).unwrap(),
+            "POLYGON((1 0,0 0,1 1,1 0))\n"
+        );
+        assert_eq!(
+            run(&["geom", "split", "-e", "0.5", "--", triangle])
+                .unwrap()
+                .lines()
+                .count(),
+            4
+        );
+
+        // Polygons without area are input errors rather than panics.
+        for wkt in ["POLYGON EMPTY", "POLYGON((0 0,1 1,2 2,0 0))"] {
+            for command in [
+                &["geom", "split", "-e", "0.1"][..],
+                &["geom", "triangulate"],
+            ] {
+                let args = [command, &["--", wkt]].concat();
+                let error = run(&args).unwrap_err();
+                assert_eq!(exit_code(&*error), 3, "{:?}", args);
+            }
+        }
+    }
+
+    #[test]
+    fn test_swap_axes() {
+        let run = |args: &[&str]| run_captured(args.iter().map(|a| a.to_string()).collect());
+        // Latitudes first are not out of range, even in strict mode.
+        let lat_lng = [
+            "--strict",
+            "geom",
+            "swap-axes",
+            "--",
+            "POINT(37.77 -122.42)",
+        ];
+        assert_eq!(run(&lat_lng).unwrap(), "POINT(-122.42 37.77)\n");
+        let polygon = "POLYGON((37.7 -122.5,37.8 -122.5,37.8 -122.4,37.7 -122.5))";
+        assert_eq!(
+            run(&["--strict", "geom", "swap-axes", "--", polygon]).unwrap(),
+            "POLYGON((-122.5 37.7,-122.4 37.8,-122.5 37.8,-122.5 37.7))\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let run = |args: &[&str]| run_captured(args.iter().map(|a| a.to_string()).collect());
+        let line = "LINESTRING(0 0,1 1)";
+        for args in [
+            &[
+                "geom",
+                "circle",
+                "-c",
+                "POINT(0 0)",
+                "-r",
+                "1000",
+                "-s",
+                "0",
+            ][..],
+            &["geom", "circle", "-c", "POINT(0 0)", "-r=-1"],
+            &["geom", "resample", "-c", "0", "--", line],
+            &["geom", "resample", "-e", "0", "--", line],
+        ] {
+            let error = run(args).unwrap_err();
+            assert_eq!(exit_code(&*error), 2, "{:?}", args);
+        }
+    }
+}
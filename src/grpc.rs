@@ -0,0 +1,647 @@
+use std::error::Error;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::str::FromStr;
+
+use geo_types::{Point, Polygon};
+use geos_core::geom::{collect_polygons, PolygonCutter};
+use geos_core::h3_cells::{h3_cell_to_poly, h3_covering_cells};
+use geos_core::s2_cells::s2_cell_to_poly;
+use geos_core::samplers::{create_rng, GeoSampler, PolygonalSampler, UniformSampler};
+use h3o::geom::ContainmentMode;
+use h3o::{CellIndex, Resolution};
+use s2::{cell::Cell as S2Cell, cellid::CellID};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::error::take_warnings;
+use crate::format::{output_wkt, parse_wkt_with_options, OutputOptions};
+use crate::h3_cmd::{self, H3CellFormat, H3CoveringMode};
+use crate::s2_cmd::{self, S2CellFormat};
+use crate::serve_cmd::{parse_value_enum, ServeOptions};
+
+mod service {
+    include!(concat!(env!("OUT_DIR"), "/geos.v1.Geos.rs"));
+}
+use service::geos_server::{Geos, GeosServer};
+
+//==================================================
+// Messages. These mirror `proto/geos.proto`, which is the schema that clients should compile, and
+// are checked against it by `test_messages_match_proto`.
+//==================================================
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct S2CoverRequest {
+    #[prost(string, tag = "1")]
+    pub wkt: String,
+    #[prost(uint32, optional, tag = "2")]
+    pub level: Option<u32>,
+    #[prost(uint64, optional, tag = "3")]
+    pub max_cells: Option<u64>,
+    #[prost(string, optional, tag = "4")]
+    pub cell_format: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct S2CutRequest {
+    #[prost(string, tag = "1")]
+    pub wkt: String,
+    #[prost(uint32, optional, tag = "2")]
+    pub level: Option<u32>,
+    #[prost(uint64, optional, tag = "3")]
+    pub max_cells: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct H3CoverRequest {
+    #[prost(string, tag = "1")]
+    pub wkt: String,
+    #[prost(uint32, optional, tag = "2")]
+    pub level: Option<u32>,
+    #[prost(string, optional, tag = "3")]
+    pub mode: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub cell_format: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct H3CutRequest {
+    #[prost(string, tag = "1")]
+    pub wkt: String,
+    #[prost(uint32, optional, tag = "2")]
+    pub level: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SampleRequest {
+    #[prost(string, optional, tag = "1")]
+    pub wkt: Option<String>,
+    #[prost(uint64, optional, tag = "2")]
+    pub num_samples: Option<u64>,
+    #[prost(uint64, tag = "3")]
+    pub seed: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Cell {
+    #[prost(string, tag = "1")]
+    pub id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Geometry {
+    #[prost(string, tag = "1")]
+    pub wkt: String,
+}
+
+// Defaults mirror the corresponding CLI arguments.
+const DEFAULT_S2_LEVEL: u32 = 12;
+const DEFAULT_H3_LEVEL: u32 = 12;
+const DEFAULT_H3_CUT_LEVEL: u32 = 6;
+const DEFAULT_NUM_SAMPLES: u64 = 1;
+
+/// The number of results computed ahead of a client that reads them more slowly.
+const STREAM_BUFFER: usize = 256;
+
+//==================================================
+// Server logic.
+//==================================================
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/**
+ * Serves the `geos.v1.Geos` gRPC service until the process is killed.
+ */
+pub fn serve_grpc(addr: &str, options: ServeOptions) -> Result<(), Box<dyn Error>> {
+    let addr = addr.parse()?;
+    eprintln!("Listening for gRPC on {}", addr);
+    tokio::runtime::Runtime::new()?.block_on(
+        tonic::transport::Server::builder()
+            .add_service(GeosServer::new(GeosService { options }))
+            .serve(addr),
+    )?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct GeosService {
+    options: ServeOptions,
+}
+
+#[tonic::async_trait]
+impl Geos for GeosService {
+    type S2CoverStream = ResponseStream<Cell>;
+    type S2CutStream = ResponseStream<Geometry>;
+    type S2CellToPolyStream = ResponseStream<Geometry>;
+    type H3CoverStream = ResponseStream<Cell>;
+    type H3CutStream = ResponseStream<Geometry>;
+    type H3CellToPolyStream = ResponseStream<Geometry>;
+    type SampleStream = ResponseStream<Geometry>;
+
+    async fn s2_cover(
+        &self,
+        request: Request<S2CoverRequest>,
+    ) -> Result<Response<Self::S2CoverStream>, Status> {
+        let req = request.into_inner();
+        let options = self.options.clone();
+        Ok(stream_blocking(move |send| s2_cover(req, &options, send)))
+    }
+
+    async fn s2_cut(
+        &self,
+        request: Request<S2CutRequest>,
+    ) -> Result<Response<Self::S2CutStream>, Status> {
+        let req = request.into_inner();
+        let options = self.options.clone();
+        Ok(stream_blocking(move |send| s2_cut(req, &options, send)))
+    }
+
+    async fn s2_cell_to_poly(
+        &self,
+        request: Request<Streaming<Cell>>,
+    ) -> Result<Response<Self::S2CellToPolyStream>, Status> {
+        let cells = request.into_inner();
+        Ok(stream_cell_to_poly(
+            cells,
+            s2_cell_polygon,
+            self.options.output,
+        ))
+    }
+
+    async fn h3_cover(
+        &self,
+        request: Request<H3CoverRequest>,
+    ) -> Result<Response<Self::H3CoverStream>, Status> {
+        let req = request.into_inner();
+        let options = self.options.clone();
+        Ok(stream_blocking(move |send| h3_cover(req, &options, send)))
+    }
+
+    async fn h3_cut(
+        &self,
+        request: Request<H3CutRequest>,
+    ) -> Result<Response<Self::H3CutStream>, Status> {
+        let req = request.into_inner();
+        let options = self.options.clone();
+        Ok(stream_blocking(move |send| h3_cut(req, &options, send)))
+    }
+
+    async fn h3_cell_to_poly(
+        &self,
+        request: Request<Streaming<Cell>>,
+    ) -> Result<Response<Self::H3CellToPolyStream>, Status> {
+        let cells = request.into_inner();
+        Ok(stream_cell_to_poly(
+            cells,
+            |id| Ok(h3_cell_to_poly(&CellIndex::from_str(id)?)),
+            self.options.output,
+        ))
+    }
+
+    async fn sample(
+        &self,
+        request: Request<SampleRequest>,
+    ) -> Result<Response<Self::SampleStream>, Status> {
+        let req = request.into_inner();
+        let options = self.options.clone();
+        Ok(stream_blocking(move |send| sample(req, &options, send)))
+    }
+}
+
+//==================================================
+// Core logic.
+//==================================================
+// The results of each method are sent as they are found, like the corresponding commands write
+// them, until sending reports that the client has gone away.
+type Sink<'a, T> = &'a mut dyn FnMut(T) -> bool;
+
+fn s2_cover(
+    req: S2CoverRequest,
+    options: &ServeOptions,
+    send: Sink<Cell>,
+) -> Result<(), Box<dyn Error>> {
+    let format = parse_value_enum(req.cell_format.as_deref(), S2CellFormat::Long)?;
+    let geometry = parse_wkt_with_options(&req.wkt, &options.input)?;
+    let level = s2_level(req.level)?;
+    for cell in s2_cmd::covering(&geometry, level, level, max_cells(req.max_cells), false)? {
+        if !send(Cell {
+            id: s2_cmd::fmt_cell(&format, &cell),
+        }) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn s2_cut(
+    req: S2CutRequest,
+    options: &ServeOptions,
+    send: Sink<Geometry>,
+) -> Result<(), Box<dyn Error>> {
+    let geometry = parse_wkt_with_options(&req.wkt, &options.input)?;
+    let level = s2_level(req.level)?;
+    let polygons = collect_polygons(&geometry)?;
+    let cutter = PolygonCutter::new(&polygons, options.input.normalize_winding)?;
+    let cover = s2_cmd::covering(&geometry, level, level, max_cells(req.max_cells), false)?;
+    let cells = cover.map(|cell| ((), s2_cell_to_poly(&S2Cell::from(cell))));
+    send_cuts(cutter.cut_each(cells), &options.output, send);
+    Ok(())
+}
+
+fn h3_cover(
+    req: H3CoverRequest,
+    options: &ServeOptions,
+    send: Sink<Cell>,
+) -> Result<(), Box<dyn Error>> {
+    let format = parse_value_enum(req.cell_format.as_deref(), H3CellFormat::Hex)?;
+    let mode: ContainmentMode = match req.mode {
+        Some(mode) => H3CoveringMode::from(mode.as_str()).into(),
+        None => ContainmentMode::IntersectsBoundary,
+    };
+    let resolution = h3_resolution(req.level.unwrap_or(DEFAULT_H3_LEVEL))?;
+    let geometry = parse_wkt_with_options(&req.wkt, &options.input)?;
+    for cell in h3_covering_cells(&geometry, resolution, mode, options.input.normalize_winding)? {
+        if !send(Cell {
+            id: h3_cmd::fmt_cell(&format, &cell),
+        }) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn h3_cut(
+    req: H3CutRequest,
+    options: &ServeOptions,
+    send: Sink<Geometry>,
+) -> Result<(), Box<dyn Error>> {
+    let resolution = h3_resolution(req.level.unwrap_or(DEFAULT_H3_CUT_LEVEL))?;
+    let geometry = parse_wkt_with_options(&req.wkt, &options.input)?;
+    let polygons = collect_polygons(&geometry)?;
+    let cutter = PolygonCutter::new(&polygons, options.input.normalize_winding)?;
+    let covering = h3_covering_cells(
+        &geometry,
+        resolution,
+        ContainmentMode::IntersectsBoundary,
+        options.input.normalize_winding,
+    )?;
+    let cells = covering.map(|cell| ((), h3_cell_to_poly(&cell)));
+    send_cuts(cutter.cut_each(cells), &options.output, send);
+    Ok(())
+}
+
+fn s2_cell_polygon(id: &str) -> Result<Polygon, Box<dyn Error>> {
+    let cell_id = CellID(id.parse()?);
+    if !cell_id.is_valid() {
+        return Err("not a valid S2 cell ID".into());
+    }
+    Ok(s2_cell_to_poly(&cell_id.into()))
+}
+
+fn sample(
+    req: SampleRequest,
+    options: &ServeOptions,
+    send: Sink<Geometry>,
+) -> Result<(), Box<dyn Error>> {
+    let sampler = match &req.wkt {
+        None => None,
+        Some(wkt) => Some(PolygonalSampler::new(
+            parse_wkt_with_options(wkt, &options.input)?.try_into()?,
+        )?),
+    };
+    let mut rng = create_rng(req.seed);
+    for _ in 0..req.num_samples.unwrap_or(DEFAULT_NUM_SAMPLES) {
+        let coord = match &sampler {
+            None => UniformSampler.sample_coord(&mut rng),
+            Some(sampler) => sampler.sample_coord(&mut rng),
+        };
+        if !send(Geometry {
+            wkt: output_wkt(Point::from(coord), &options.output),
+        }) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+//==================================================
+// Utils.
+//==================================================
+/**
+ * Streams the results that `produce` sends. It runs on a blocking thread, so that CPU-bound work
+ * doesn't stall the async runtime, and at most `STREAM_BUFFER` results ahead of the client. An error
+ * ends the stream with `INVALID_ARGUMENT`, and a panic with `INTERNAL`.
+ */
+fn stream_blocking<T: Send + 'static>(
+    produce: impl FnOnce(Sink<T>) -> Result<(), Box<dyn Error>> + Send + 'static,
+) -> Response<ResponseStream<T>> {
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    tokio::task::spawn_blocking(move || {
+        let mut send = |item| tx.blocking_send(Ok(item)).is_ok();
+        let result = catch_unwind(AssertUnwindSafe(|| produce(&mut send)));
+        // Warnings were already printed as the request was read, and blocking threads are reused.
+        take_warnings();
+        let status = match result {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => invalid_argument(e),
+            Err(_) => Status::internal("the request panicked"),
+        };
+        // If the client has gone away, there is no one left to tell.
+        let _ = tx.blocking_send(Err(status));
+    });
+    Response::new(Box::pin(ReceiverStream::new(rx)))
+}
+
+fn send_cuts<T>(
+    cuts: impl Iterator<Item = (T, Polygon)>,
+    options: &OutputOptions,
+    send: Sink<Geometry>,
+) {
+    for (_, cut) in cuts {
+        if !send(Geometry {
+            wkt: output_wkt(cut, options),
+        }) {
+            break;
+        }
+    }
+}
+
+/**
+ * Converts each cell of a request stream as it arrives. A cell that fails to convert ends the
+ * response with an error, so that responses always line up with the requested cells.
+ */
+fn stream_cell_to_poly(
+    cells: Streaming<Cell>,
+    to_poly: fn(&str) -> Result<Polygon, Box<dyn Error>>,
+    options: OutputOptions,
+) -> Response<ResponseStream<Geometry>> {
+    let geometries = cells.map(move |cell| {
+        let cell = cell?;
+        let poly = to_poly(&cell.id)
+            .map_err(|e| Status::invalid_argument(format!("{}: {}", cell.id, e)))?;
+        Ok(Geometry {
+            wkt: output_wkt(poly, &options),
+        })
+    });
+    Response::new(Box::pin(geometries))
+}
+
+fn s2_level(level: Option<u32>) -> Result<u8, Box<dyn Error>> {
+    match level.unwrap_or(DEFAULT_S2_LEVEL) {
+        level @ 0..=30 => Ok(level as u8),
+        level => Err(format!("invalid S2 level {}", level).into()),
+    }
+}
+
+fn h3_resolution(level: u32) -> Result<Resolution, Box<dyn Error>> {
+    Ok(Resolution::try_from(u8::try_from(level)?)?)
+}
+
+fn max_cells(max_cells: Option<u64>) -> Option<usize> {
+    max_cells.map(|n| usize::try_from(n).unwrap_or(usize::MAX))
+}
+
+fn invalid_argument(e: Box<dyn Error>) -> Status {
+    Status::invalid_argument(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use itertools::Itertools;
+    use prost::Message;
+    use tokio_stream::StreamExt;
+    use tonic::{Code, Request, Status};
+
+    use super::service::geos_server::Geos;
+    use super::{
+        s2_cell_polygon, Cell, Geometry, GeosService, H3CoverRequest, H3CutRequest, ResponseStream,
+        S2CoverRequest, S2CutRequest, SampleRequest,
+    };
+    use crate::format::InputOptions;
+    use crate::run_captured;
+    use crate::serve_cmd::ServeOptions;
+
+    /// A message field as (name, number, wire type, whether it is optional).
+    type Field = (String, u64, u64, bool);
+
+    /**
+     * The fields of each message in the proto file, in order.
+     */
+    fn proto_messages() -> Vec<(String, Vec<Field>)> {
+        let proto = include_str!("../proto/geos.proto");
+        let mut messages = vec![];
+        for line in proto.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix("message ") {
+                messages.push((name.trim_end_matches(" {").to_string(), vec![]));
+            } else if let (Some((_, fields)), Some((field, number))) =
+                (messages.last_mut(), line.split_once(" = "))
+            {
+                let words = field.split_whitespace().collect_vec();
+                let (kind, name) = (words[words.len() - 2], words[words.len() - 1]);
+                let wire_type = match kind {
+                    "string" => 2,
+                    "uint32" | "uint64" => 0,
+                    kind => panic!("unexpected field type {}", kind),
+                };
+                let number = number.trim_end_matches(';').parse().unwrap();
+                fields.push((name.to_string(), number, wire_type, words[0] == "optional"));
+            }
+        }
+        messages
+    }
+
+    /**
+     * The fields of a message with every field set, from its Debug names and its encoding.
+     */
+    fn fields<M: Message + Debug>(message: M) -> Vec<Field> {
+        let debug = format!("{:?}", message);
+        let names = debug[debug.find('{').unwrap() + 1..debug.rfind('}').unwrap()]
+            .split(", ")
+            .map(|field| field.split_once(": ").unwrap())
+            .map(|(name, value)| (name.trim().to_string(), value.starts_with("Some(")));
+
+        let bytes = message.encode_to_vec();
+        let mut buf = bytes.as_slice();
+        let mut keys = vec![];
+        while !buf.is_empty() {
+            let key = prost::encoding::decode_varint(&mut buf).unwrap();
+            let length = match key & 7 {
+                0 => prost::encoding::decode_varint(&mut buf).map(|_| 0).unwrap(),
+                2 => prost::encoding::decode_varint(&mut buf).unwrap() as usize,
+                wire_type => panic!("unexpected wire type {}", wire_type),
+            };
+            buf = &buf[length..];
+            keys.push((key >> 3, key & 7));
+        }
+        names
+            .zip_eq(keys)
+            .map(|((name, optional), (number, wire_type))| (name, number, wire_type, optional))
+            .collect()
+    }
+
+    #[test]
+    fn test_messages_match_proto() {
+        let wkt = || "POINT(1 2)".to_string();
+        let messages = [
+            (
+                "S2CoverRequest",
+                fields(S2CoverRequest {
+                    wkt: wkt(),
+                    level: Some(1),
+                    max_cells: Some(1),
+                    cell_format: Some("hex".to_string()),
+                }),
+            ),
+            (
+                "S2CutRequest",
+                fields(S2CutRequest {
+                    wkt: wkt(),
+                    level: Some(1),
+                    max_cells: Some(1),
+                }),
+            ),
+            (
+                "H3CoverRequest",
+                fields(H3CoverRequest {
+                    wkt: wkt(),
+                    level: Some(1),
+                    mode: Some("contains".to_string()),
+                    cell_format: Some("hex".to_string()),
+                }),
+            ),
+            (
+                "H3CutRequest",
+                fields(H3CutRequest {
+                    wkt: wkt(),
+                    level: Some(1),
+                }),
+            ),
+            (
+                "SampleRequest",
+                fields(SampleRequest {
+                    wkt: Some(wkt()),
+                    num_samples: Some(1),
+                    seed: 1,
+                }),
+            ),
+            (
+                "Cell",
+                fields(Cell {
+                    id: "1".to_string(),
+                }),
+            ),
+            ("Geometry", fields(Geometry { wkt: wkt() })),
+        ]
+        .map(|(name, fields)| (name.to_string(), fields));
+        assert_eq!(proto_messages(), messages);
+    }
+
+    async fn collect<T>(
+        response: Result<tonic::Response<ResponseStream<T>>, Status>,
+    ) -> Result<Vec<T>, Status> {
+        response?.into_inner().collect().await
+    }
+
+    #[test]
+    fn test_service() {
+        let run = |args: &str| run_captured(shlex::split(args).unwrap()).unwrap();
+        let wkts =
+            |geometries: Vec<Geometry>| geometries.into_iter().map(|g| g.wkt + "\n").join("");
+        let triangle = "POLYGON((0 0,1 0,1 1,0 0))";
+        let service = GeosService::default();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let cells = service.s2_cover(Request::new(S2CoverRequest {
+                wkt: "POINT(-122.4194 37.7749)".to_string(),
+                level: Some(10),
+                ..Default::default()
+            }));
+            assert_eq!(
+                collect(cells.await).await.unwrap(),
+                [Cell {
+                    id: "9260950045757276160".to_string()
+                }]
+            );
+
+            // Cuts match those of the commands.
+            let cuts = service.s2_cut(Request::new(S2CutRequest {
+                wkt: triangle.to_string(),
+                level: Some(6),
+                ..Default::default()
+            }));
+            assert_eq!(
+                wkts(collect(cuts.await).await.unwrap()),
+                run(&format!("s2 cut -l 6 -- '{}'", triangle))
+            );
+            let cuts = service.h3_cut(Request::new(H3CutRequest {
+                wkt: triangle.to_string(),
+                level: Some(4),
+            }));
+            assert_eq!(
+                wkts(collect(cuts.await).await.unwrap()),
+                run(&format!("h3 cut -l 4 -- '{}'", triangle))
+            );
+
+            // Coverings are streamed as they are found, so the first cells of one far too large to
+            // compute in full arrive right away.
+            let cells = service.s2_cover(Request::new(S2CoverRequest {
+                wkt: "POLYGON((0 0,10 0,10 10,0 10,0 0))".to_string(),
+                level: Some(30),
+                ..Default::default()
+            }));
+            let first = cells.await.unwrap().into_inner().take(3);
+            assert_eq!(first.collect::<Vec<_>>().await.len(), 3);
+
+            // Invalid requests end the stream with an error status.
+            let cells = service.s2_cover(Request::new(S2CoverRequest {
+                wkt: "POINT(1 1)".to_string(),
+                level: Some(31),
+                ..Default::default()
+            }));
+            let error = collect(cells.await).await.unwrap_err();
+            assert_eq!(error.code(), Code::InvalidArgument);
+            let cells = service.h3_cover(Request::new(H3CoverRequest {
+                wkt: "POINT(1".to_string(),
+                ..Default::default()
+            }));
+            let error = collect(cells.await).await.unwrap_err();
+            assert_eq!(error.code(), Code::InvalidArgument);
+            let points = service.sample(Request::new(SampleRequest {
+                wkt: Some("POLYGON EMPTY".to_string()),
+                ..Default::default()
+            }));
+            let error = collect(points.await).await.unwrap_err();
+            assert_eq!(error.code(), Code::InvalidArgument);
+
+            // Request geometries are read with the global flags given to `geos serve`.
+            let service = GeosService {
+                options: ServeOptions {
+                    input: InputOptions {
+                        crs: "EPSG:3857".parse().unwrap(),
+                        ..InputOptions::default()
+                    },
+                    ..ServeOptions::default()
+                },
+            };
+            let cells = service.s2_cover(Request::new(S2CoverRequest {
+                wkt: "POINT(-13627665.27 4547675.35)".to_string(),
+                level: Some(10),
+                ..Default::default()
+            }));
+            assert_eq!(
+                collect(cells.await).await.unwrap(),
+                [Cell {
+                    id: "9260950045757276160".to_string()
+                }]
+            );
+        });
+
+        assert!(s2_cell_polygon("9260950045757276160").is_ok());
+        assert_eq!(
+            s2_cell_polygon("0").unwrap_err().to_string(),
+            "not a valid S2 cell ID"
+        );
+    }
+}
@@ -1,17 +1,29 @@
 use std::error::Error;
+use std::ffi::OsStr;
 use std::fmt::{Display, Formatter};
-use std::str::FromStr;
+use std::io::Write;
+use std::path::PathBuf;
 
-use clap::{command, Args, Subcommand, ValueEnum};
+use clap::builder::{PossibleValue, StringValueParser, TypedValueParser, ValueParserFactory};
+use clap::{ArgGroup, Args, Subcommand, ValueEnum};
 use clap_stdin::MaybeStdin;
-use geo::{BooleanOps, Geometry, LineString, Point, Polygon};
-use geo_types::coord;
-use h3o::geom::{ContainmentMode, PolyfillConfig, ToCells};
-use h3o::{CellIndex, LatLng, Resolution};
+use geo::Geometry;
+use geos_core::geom::{self, PolygonCutter};
+use geos_core::h3_cells::{
+    bin_h3_points, dissolve_h3_cells, h3_cell_metrics, h3_cell_to_poly, h3_cells_at_resolution,
+    h3_cells_to_polys, h3_covering_cells, merge_h3_cells, parse_containment_mode,
+};
+use h3o::geom::ContainmentMode;
+use h3o::CellIndex;
 use itertools::Itertools;
-use wkt::{ToWkt, TryFromWkt};
+use serde_json::json;
 
-use crate::format::{fmt_geometry, fmt_value_enum, OutputFormat};
+use crate::format::{
+    fmt_bins, fmt_cell_features, fmt_cell_polygons, fmt_cells, fmt_geometry, fmt_value_enum,
+    h3_resolution, parse_geometry, parse_h3_cell_from_file, parse_h3_cells, parse_h3_level,
+    parse_weighted_points, read_cells, write_cell_arrow, write_cell_geoparquet, ArrowArgs,
+    BinFormat, CellEncoding, CellListArgs, GeoParquetArgs, OutputFormat, RunContext,
+};
 
 //==================================================
 // CLI spec.
@@ -25,9 +37,41 @@ pub struct H3Args {
     command: Option<H3Commands>,
 }
 
+impl H3Args {
+    /**
+     * Whether the output depends only on the arguments, and not on the contents of files.
+     */
+    pub fn is_cacheable(&self) -> bool {
+        match &self.command {
+            Some(H3Commands::Merge { .. }) => false,
+            Some(H3Commands::Cover { parquet, arrow, .. }) => {
+                parquet.path().is_none() && arrow.path().is_none()
+            }
+            Some(H3Commands::Cut { parquet, .. }) => parquet.path().is_none(),
+            Some(H3Commands::Compact { arrow, .. } | H3Commands::Uncompact { arrow, .. }) => {
+                arrow.path().is_none()
+            }
+            _ => true,
+        }
+    }
+
+    /**
+     * The arguments read as geometries or points, which may name `@file`s that the output depends
+     * on.
+     */
+    pub fn geometry_args(&self) -> Vec<&str> {
+        match &self.command {
+            Some(H3Commands::Cover { wkt, .. } | H3Commands::Cut { wkt, .. }) => vec![wkt.as_str()],
+            Some(H3Commands::Bin { points, .. }) => points.iter().map(String::as_str).collect(),
+            _ => vec![],
+        }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum H3Commands {
     #[command(arg_required_else_help = true)]
+    #[command(group(ArgGroup::new("file_output").args(["output", "arrow"])))]
     Cover {
         #[arg(
             last = true,
@@ -39,6 +83,7 @@ pub enum H3Commands {
             short,
             long,
             default_value_t = 12,
+            value_parser = parse_h3_level,
             help = "The H3 cell level [0, 15] at which to perform the covering."
         )]
         level: u8,
@@ -51,6 +96,46 @@ pub enum H3Commands {
         )]
         mode: H3CoveringMode,
 
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Cover the holes of polygons too. By default, cells within a hole, e.g. a lake within a boundary, are left out."
+        )]
+        fill_holes: bool,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with = "arrow",
+            help = "Output the polygon of each cell as WKT instead of its ID."
+        )]
+        as_geometry: bool,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            requires = "as_geometry",
+            conflicts_with = "output",
+            help = "Dissolve the cell polygons into the outline of the covering, with holes where it surrounds uncovered areas."
+        )]
+        dissolve: bool,
+
+        #[arg(
+            long,
+            requires = "as_geometry",
+            conflicts_with = "dissolve",
+            help = "Name each cell polygon by its cell ID, as the placemark names of KML output or the `name` property of MVT features."
+        )]
+        name_cells: bool,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with_all = ["as_geometry", "output", "arrow"],
+            help = "Output a GeoJSON FeatureCollection of the cell polygons instead of their IDs, with `cell_id`, `resolution`, and `area_m2` properties, or the area in the unit given by --unit."
+        )]
+        as_features: bool,
+
         #[arg(
             long,
             default_value_t = H3CellFormat::Hex,
@@ -65,6 +150,15 @@ pub enum H3Commands {
             help = "By default, outputs each cell ID on separate lines."
         )]
         format: OutputFormat,
+
+        #[command(flatten)]
+        cell_list: CellListArgs,
+
+        #[command(flatten)]
+        parquet: GeoParquetArgs,
+
+        #[command(flatten)]
+        arrow: ArrowArgs,
     },
 
     #[command(arg_required_else_help = true)]
@@ -79,7 +173,8 @@ pub enum H3Commands {
             short,
             long,
             default_value_t = 6,
-            help = "The H3 cell level at which to perform the covering."
+            value_parser = parse_h3_level,
+            help = "The H3 cell level [0, 15] at which to perform the covering."
         )]
         level: u8,
 
@@ -90,12 +185,47 @@ pub enum H3Commands {
             help = "By default, outputs each cell ID on separate lines."
         )]
         format: OutputFormat,
+
+        #[command(flatten)]
+        parquet: GeoParquetArgs,
     },
 
     #[command(arg_required_else_help = true)]
     CellToPoly {
-        #[arg(last = true, help = "A valid H3 cell index.")]
-        cell: String,
+        #[arg(
+            last = true,
+            num_args = 1..,
+            use_value_delimiter = true,
+            value_delimiter = ',',
+            help = "A comma-separated list of valid H3 cell indices."
+        )]
+        cells: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::CSV,
+            help = "By default, outputs each cell polygon on separate lines."
+        )]
+        format: OutputFormat,
+    },
+
+    #[command(
+        about = "Compute the exact area and edge lengths of cells from their boundaries, rather than the averages for their resolution, in square meters and meters or the units given by --unit."
+    )]
+    #[command(arg_required_else_help = true)]
+    Metrics {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            use_value_delimiter = true,
+            value_delimiter = ',',
+            help = "A comma-separated list of valid H3 cell indices."
+        )]
+        cells: Vec<String>,
+
+        #[arg(short, long, default_value_t = MetricsFormat::CSV, help = "The output format for cells. Each cell is written on a separate line.")]
+        format: MetricsFormat,
     },
 
     #[command(arg_required_else_help = true)]
@@ -123,6 +253,12 @@ pub enum H3Commands {
             help = "By default, outputs each cell ID on separate lines."
         )]
         format: OutputFormat,
+
+        #[command(flatten)]
+        cell_list: CellListArgs,
+
+        #[command(flatten)]
+        arrow: ArrowArgs,
     },
 
     #[command(arg_required_else_help = true)]
@@ -136,7 +272,12 @@ pub enum H3Commands {
         )]
         cells: Vec<String>,
 
-        #[arg(short, long, help = "The H3 cell level at which to uncompact to.")]
+        #[arg(
+            short,
+            long,
+            value_parser = parse_h3_level,
+            help = "The H3 cell level [0, 15] at which to uncompact to."
+        )]
         level: u8,
 
         #[arg(long, default_value_t = H3CellFormat::Hex, help = "The output format for H3 cells.")]
@@ -149,6 +290,96 @@ pub enum H3Commands {
             help = "By default, outputs each cell ID on separate lines."
         )]
         format: OutputFormat,
+
+        #[command(flatten)]
+        cell_list: CellListArgs,
+
+        #[command(flatten)]
+        arrow: ArrowArgs,
+    },
+
+    #[command(
+        about = "Merge cell lists into one sorted covering without duplicates or cells whose ancestor is also present."
+    )]
+    #[command(arg_required_else_help = true)]
+    Merge {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "Files of H3 cell indices, one per line or comma-separated."
+        )]
+        files: Vec<PathBuf>,
+
+        #[arg(
+            long,
+            conflicts_with = "level",
+            help = "Also replace complete sets of sibling cells by their parent."
+        )]
+        compact: bool,
+
+        #[arg(
+            short,
+            long,
+            value_parser = parse_h3_level,
+            help = "Normalize the cells to this level [0, 15], replacing finer cells by their ancestor and coarser cells by their descendants."
+        )]
+        level: Option<u8>,
+
+        #[arg(long, default_value_t = H3CellFormat::Hex, help = "The output format for H3 cells.")]
+        h3_cell_format: H3CellFormat,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::CSV,
+            help = "By default, outputs each cell ID on separate lines."
+        )]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        cell_list: CellListArgs,
+    },
+
+    #[command(
+        about = "Count the points within each cell, optionally summing a weight column, for density maps."
+    )]
+    #[command(arg_required_else_help = true)]
+    Bin {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "Points as `<lng>,<lat>[,<columns>...]` CSV rows, inline, piped, or as `@path.csv`, or as WKT or GeoJSON geometries whose points are counted."
+        )]
+        points: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            value_parser = parse_h3_level,
+            help = "The H3 cell level [0, 15] at which to count points."
+        )]
+        level: u8,
+
+        #[arg(
+            short,
+            long,
+            help = "Sum the weights in this CSV column, named by the header row or a 0-based index, as well as counting points."
+        )]
+        weight_column: Option<String>,
+
+        #[arg(long, default_value_t = H3CellFormat::Hex, help = "The output format for H3 cells.")]
+        h3_cell_format: H3CellFormat,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = BinFormat::CSV,
+            help = "By default, outputs each cell and its count on separate lines."
+        )]
+        format: BinFormat,
+
+        #[command(flatten)]
+        cell_list: CellListArgs,
     },
 }
 
@@ -156,11 +387,7 @@ pub enum H3Commands {
 pub struct H3CoveringMode(ContainmentMode);
 impl From<&str> for H3CoveringMode {
     fn from(value: &str) -> Self {
-        match value.to_ascii_lowercase().as_str() {
-            "containscentroid" | "centroid" => H3CoveringMode(ContainmentMode::ContainsCentroid),
-            "containsboundary" | "contains" => H3CoveringMode(ContainmentMode::ContainsBoundary),
-            _ => H3CoveringMode(ContainmentMode::IntersectsBoundary),
-        }
+        H3CoveringMode(parse_containment_mode(value))
     }
 }
 impl Display for H3CoveringMode {
@@ -168,9 +395,45 @@ impl Display for H3CoveringMode {
         write!(f, "{:?}", self.0)
     }
 }
-impl Into<ContainmentMode> for H3CoveringMode {
-    fn into(self) -> ContainmentMode {
-        self.0
+impl From<H3CoveringMode> for ContainmentMode {
+    fn from(mode: H3CoveringMode) -> Self {
+        mode.0
+    }
+}
+impl ValueParserFactory for H3CoveringMode {
+    type Parser = H3CoveringModeParser;
+
+    fn value_parser() -> Self::Parser {
+        H3CoveringModeParser
+    }
+}
+
+/**
+ * Parses covering modes leniently, like `H3CoveringMode::from`, while still advertising the
+ * canonical mode names for help text and shell completion.
+ */
+#[derive(Clone)]
+pub struct H3CoveringModeParser;
+impl TypedValueParser for H3CoveringModeParser {
+    type Value = H3CoveringMode;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = StringValueParser::new().parse_ref(cmd, arg, value)?;
+        Ok(H3CoveringMode::from(value.as_str()))
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let modes = [
+            PossibleValue::new("intersectsboundary"),
+            PossibleValue::new("containsboundary").alias("contains"),
+            PossibleValue::new("containscentroid").alias("centroid"),
+        ];
+        Some(Box::new(modes.into_iter()))
     }
 }
 
@@ -179,6 +442,12 @@ pub enum H3CellFormat {
     Hex,
     Octal,
     Binary,
+    /// The 64-bit index as an unsigned integer, as databases store it.
+    Decimal,
+    /// The 8 big-endian bytes of the index in RFC 4648 base32, without padding.
+    Base32,
+    /// The 8 big-endian bytes of the index in URL-safe base64, without padding.
+    Base64,
 }
 impl Display for H3CellFormat {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -186,75 +455,191 @@ impl Display for H3CellFormat {
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, ValueEnum)]
+pub enum MetricsFormat {
+    /// `<cell>,<area>,<edge lengths>...`, with five edge lengths for pentagons and six otherwise.
+    CSV,
+    /// `{"cell": <cell>, "area_m2": <area>, "edge_lengths_m": [<edge lengths>...]}`, with the keys
+    /// suffixed by the units given by --unit.
+    Ndjson,
+}
+impl Display for MetricsFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
 //==================================================
 // Core logic for subcommands.
 //==================================================
-fn fmt_cell(format: &H3CellFormat, c: &CellIndex) -> String {
-    match &format {
-        H3CellFormat::Hex => format!("{}", c),
-        H3CellFormat::Octal => format!("{:o}", c),
-        H3CellFormat::Binary => format!("{:b}", c),
-    }
+pub fn fmt_cell(format: &H3CellFormat, c: &CellIndex) -> String {
+    let encoding = match &format {
+        H3CellFormat::Hex => CellEncoding::Hex,
+        H3CellFormat::Octal => CellEncoding::Octal,
+        H3CellFormat::Binary => CellEncoding::Binary,
+        H3CellFormat::Decimal => CellEncoding::Decimal,
+        H3CellFormat::Base32 => CellEncoding::Base32,
+        H3CellFormat::Base64 => CellEncoding::Base64,
+    };
+    encoding.encode(u64::from(*c))
 }
 
-pub fn handle_h3_subcommand(h3: &H3Args) -> Result<(), Box<dyn Error>> {
+pub fn handle_h3_subcommand(
+    h3: &H3Args,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
     match &h3.command {
         Some(H3Commands::Cover {
             wkt,
             level,
             mode,
+            fill_holes,
+            as_geometry,
+            dissolve,
+            name_cells,
+            as_features,
             h3_cell_format,
             format,
+            cell_list,
+            parquet,
+            arrow,
         }) => {
             // convenience shadow copies
             let mode: ContainmentMode = (*mode).into();
-            let resolution = Resolution::try_from(*level)?;
-            let geometry = Geometry::<f64>::try_from_wkt_str(wkt)?;
-            let cells = get_h3_covering(&geometry, resolution, mode)?;
+            let resolution = h3_resolution(*level)?;
+            ctx.record_metadata("resolution", *level);
+            ctx.record_metadata("containment", format!("{:?}", mode));
+            let mut geometry = parse_geometry(wkt, ctx)?;
+            if *fill_holes {
+                geom::fill_holes(&mut geometry);
+            }
+            let covering =
+                h3_covering_cells(&geometry, resolution, mode, ctx.input.normalize_winding)?;
 
             // Output
-            let mut cells = cells.iter().map(|c| fmt_cell(h3_cell_format, c));
-            match &format {
-                OutputFormat::Oneline => println!("{}", cells.join(",")),
-                OutputFormat::CSV => cells.for_each(|c| println!("{}", c)),
+            let cells = cell_list.apply(covering);
+            if let Some(path) = parquet.path() {
+                let rows = cells.map(|cell| {
+                    let polygon = Geometry::from(h3_cell_to_poly(&cell));
+                    (fmt_cell(h3_cell_format, &cell), *level, polygon)
+                });
+                write_cell_geoparquet(out, path, rows)?;
+            } else if let Some(path) = arrow.path() {
+                let rows = cells.map(|c| (u64::from(c), u8::from(c.resolution())));
+                write_cell_arrow(out, path, rows)?;
+            } else if *as_features {
+                let features = cells.map(|cell| {
+                    let polygon = Geometry::from(h3_cell_to_poly(&cell));
+                    (
+                        fmt_cell(h3_cell_format, &cell),
+                        *level,
+                        polygon,
+                        cell.area_m2(),
+                    )
+                });
+                fmt_cell_features(out, ctx, features, "resolution")?;
+            } else if *dissolve {
+                let outline = dissolve_h3_cells(cells)?;
+                fmt_geometry(out, ctx, format, outline.into_iter().map(Geometry::from))?;
+            } else if *as_geometry {
+                let polys = cells.map(|cell| {
+                    let polygon = Geometry::from(h3_cell_to_poly(&cell));
+                    (fmt_cell(h3_cell_format, &cell), *level, polygon)
+                });
+                fmt_cell_polygons(out, ctx, format, polys, *name_cells)?;
+            } else {
+                let cells = cells.map(|c| (fmt_cell(h3_cell_format, &c), u8::from(c.resolution())));
+                fmt_cells(out, ctx, format, cells)?;
             }
         }
 
-        Some(H3Commands::Cut { wkt, level, format }) => {
-            let geometry = Geometry::<f64>::try_from_wkt_str(wkt)?;
-            let resolution = Resolution::try_from(*level)?;
-            let cover =
-                get_h3_covering(&geometry, resolution, ContainmentMode::IntersectsBoundary)?;
-            let cuts = cut_geometry(&geometry, &cover)?
-                .into_iter()
-                .map(Geometry::from)
-                .collect_vec();
-            fmt_geometry(format, cuts)
+        Some(H3Commands::Cut {
+            wkt,
+            level,
+            format,
+            parquet,
+        }) => {
+            let geometry = parse_geometry(wkt, ctx)?;
+            let resolution = h3_resolution(*level)?;
+            let mode = ContainmentMode::IntersectsBoundary;
+            ctx.record_metadata("resolution", *level);
+            ctx.record_metadata("containment", format!("{:?}", mode));
+            // The pieces are cut and written as the cells of the covering are found, so that
+            // fine coverings aren't held in memory.
+            let polygons = geom::collect_polygons(&geometry)?;
+            let cutter = PolygonCutter::new(&polygons, ctx.input.normalize_winding)?;
+            let covering =
+                h3_covering_cells(&geometry, resolution, mode, ctx.input.normalize_winding)?;
+            let cells = covering.map(|cell| (cell, h3_cell_to_poly(&cell)));
+            let cuts = cutter
+                .cut_each(cells)
+                .map(|(cell, cut)| (cell.to_string(), *level, Geometry::from(cut)));
+            if let Some(path) = parquet.path() {
+                write_cell_geoparquet(out, path, cuts)?;
+            } else {
+                fmt_cell_polygons(out, ctx, format, cuts, false)?;
+            }
+        }
+
+        Some(H3Commands::CellToPoly { cells, format }) => {
+            let cells = parse_h3_cells(cells)?;
+            let polys = h3_cells_to_polys(cells).map(Geometry::from);
+            fmt_geometry(out, ctx, format, polys)?;
         }
 
-        Some(H3Commands::CellToPoly { cell }) => {
-            let cell = CellIndex::from_str(cell)?;
-            let poly = h3_cell_to_poly(&cell);
-            println!("{}", poly.wkt_string());
+        Some(H3Commands::Metrics { cells, format }) => {
+            let cells = parse_h3_cells(cells)?;
+            let units = ctx.output.units;
+            let area_key = format!("area_{}", units.area.symbol());
+            let lengths_key = format!("edge_lengths_{}", units.length.symbol());
+            let lines = cells.into_iter().map(|cell| {
+                let metrics = h3_cell_metrics(cell);
+                let area = units.area(metrics.area_m2);
+                let lengths = metrics
+                    .edge_lengths_m
+                    .iter()
+                    .map(|length| units.length(*length))
+                    .collect_vec();
+                match format {
+                    MetricsFormat::CSV => std::iter::once(cell.to_string())
+                        .chain(std::iter::once(area.to_string()))
+                        .chain(lengths.iter().map(f64::to_string))
+                        .join(","),
+                    MetricsFormat::Ndjson => json!({
+                        "cell": cell.to_string(),
+                        &area_key: area,
+                        &lengths_key: lengths,
+                    })
+                    .to_string(),
+                }
+            });
+            for line in lines {
+                writeln!(out, "{}", line)?;
+            }
         }
 
         Some(H3Commands::Compact {
             cells,
             h3_cell_format,
             format,
+            cell_list,
+            arrow,
         }) => {
-            let cells: Vec<CellIndex> = cells
-                .into_iter()
-                .map(|s| s.as_str())
-                .map(CellIndex::from_str)
-                .try_collect()?;
-            let cells_compacted = CellIndex::compact(cells)?.collect_vec();
+            let cells = parse_h3_cells(cells)?;
+            // The order of compacted cells is up to h3o, so it's fixed here.
+            let cells_compacted = CellIndex::compact(cells)?.sorted_unstable();
 
             // Output
-            let mut cells_compacted = cells_compacted.iter().map(|c| fmt_cell(h3_cell_format, c));
-            match &format {
-                OutputFormat::Oneline => println!("{}", cells_compacted.join(",")),
-                OutputFormat::CSV => cells_compacted.for_each(|c| println!("{}", c)),
+            let cells_compacted = cell_list.apply(cells_compacted);
+            if let Some(path) = arrow.path() {
+                let rows = cells_compacted.map(|c| (u64::from(c), u8::from(c.resolution())));
+                write_cell_arrow(out, path, rows)?;
+            } else {
+                let cells_compacted = cells_compacted
+                    .map(|c| (fmt_cell(h3_cell_format, &c), u8::from(c.resolution())));
+                fmt_cells(out, ctx, format, cells_compacted)?;
             }
         }
 
@@ -263,130 +648,104 @@ pub fn handle_h3_subcommand(h3: &H3Args) -> Result<(), Box<dyn Error>> {
             level,
             h3_cell_format,
             format,
+            cell_list,
+            arrow,
         }) => {
-            let resolution = Resolution::try_from(*level)?;
-            let cells: Vec<CellIndex> = cells
-                .into_iter()
-                .map(|s| s.as_str())
-                .map(CellIndex::from_str)
-                .try_collect()?;
-            let cells_uncompacted = CellIndex::uncompact(cells, resolution).collect_vec();
+            let resolution = h3_resolution(*level)?;
+            let cells = parse_h3_cells(cells)?;
+            let cells_uncompacted = CellIndex::uncompact(cells, resolution);
 
             // Output
-            let mut cells_uncompacted = cells_uncompacted
-                .iter()
-                .map(|c| fmt_cell(h3_cell_format, c));
-            match &format {
-                OutputFormat::Oneline => println!("{}", cells_uncompacted.join(",")),
-                OutputFormat::CSV => cells_uncompacted.for_each(|c| println!("{}", c)),
+            let cells_uncompacted = cell_list.apply(cells_uncompacted);
+            if let Some(path) = arrow.path() {
+                let rows = cells_uncompacted.map(|c| (u64::from(c), u8::from(c.resolution())));
+                write_cell_arrow(out, path, rows)?;
+            } else {
+                let cells_uncompacted = cells_uncompacted
+                    .map(|c| (fmt_cell(h3_cell_format, &c), u8::from(c.resolution())));
+                fmt_cells(out, ctx, format, cells_uncompacted)?;
             }
         }
 
-        None => {}
-    }
-    Ok(())
-}
+        Some(H3Commands::Merge {
+            files,
+            compact,
+            level,
+            h3_cell_format,
+            format,
+            cell_list,
+        }) => {
+            let mut cells: Vec<CellIndex> = vec![];
+            for file in files {
+                for cell in read_cells(file)? {
+                    cells.push(parse_h3_cell_from_file(&cell)?);
+                }
+            }
+            let merged = match level {
+                Some(level) => h3_cells_at_resolution(cells, h3_resolution(*level)?),
+                None => merge_h3_cells(cells, *compact),
+            };
 
-//==================================================
-// Geometry utils
-//==================================================
-fn cut_geometry(
-    geometry: &Geometry,
-    cells: &Vec<CellIndex>,
-) -> Result<Vec<Polygon>, Box<dyn Error>> {
-    let partitions = cells.iter().map(h3_cell_to_poly).collect_vec();
-
-    Ok(match &geometry {
-        Geometry::Polygon(poly) => partitions
-            .iter()
-            .map(|p| p.intersection(poly))
-            .flatten()
-            .collect_vec(),
-
-        Geometry::MultiPolygon(mpoly) => mpoly
-            .iter()
-            .flat_map(|mp| partitions.iter().map(|p| p.intersection(mp)).flatten())
-            .collect_vec(),
-
-        // Recurse.
-        Geometry::GeometryCollection(collection) => collection
-            .into_iter()
-            .map(|g| cut_geometry(g, cells))
-            .flatten_ok()
-            .collect::<Result<Vec<Polygon>, _>>()?,
-
-        // Default to trying a polygon conversion.
-        _ => {
-            let poly = Polygon::try_from(geometry.clone())?;
-            partitions
-                .iter()
-                .map(|p| p.intersection(&poly))
-                .flatten()
-                .collect_vec()
+            // Output
+            let merged = cell_list
+                .apply(merged.into_iter())
+                .map(|c| (fmt_cell(h3_cell_format, &c), u8::from(c.resolution())));
+            fmt_cells(out, ctx, format, merged)?;
         }
-    })
-}
 
-/**
- * Creates a polygon from the vertices of an H3 cell. This will be a hexagon in most cases, except
- * for the pentagons on icosahedron vertices.
- */
-fn h3_cell_to_poly(cell_id: &CellIndex) -> Polygon {
-    let boundary = cell_id.boundary();
-    let vertices = boundary
-        .iter()
-        .map(|v| coord![x: v.lng(), y: v.lat()])
-        .collect_vec();
-    Polygon::new(LineString::from(vertices), vec![])
-}
+        Some(H3Commands::Bin {
+            points,
+            level,
+            weight_column,
+            h3_cell_format,
+            format,
+            cell_list,
+        }) => {
+            let resolution = h3_resolution(*level)?;
+            let points = parse_weighted_points(points, weight_column.as_deref(), ctx)?;
+            let bins = bin_h3_points(points, resolution)?;
+            tracing::debug!("binned points into {} cells", bins.len());
+            let bins = cell_list
+                .apply(bins.keys().copied())
+                .map(|cell| (cell, bins[&cell]))
+                .collect_vec();
+            fmt_bins(
+                out,
+                ctx,
+                format,
+                bins,
+                weight_column.is_some(),
+                None,
+                |cell| fmt_cell(h3_cell_format, cell),
+                h3_cell_to_poly,
+            )?;
+        }
 
-fn get_h3_covering(
-    geometry: &Geometry,
-    resolution: Resolution,
-    mode: ContainmentMode,
-) -> Result<Vec<CellIndex>, Box<dyn Error>> {
-    match geometry {
-        // Point and point composite types.
-        Geometry::Point(point) => get_h3_point_covering(point, resolution).map(|p| vec![p]),
-        Geometry::MultiPoint(mpoint) => mpoint
-            .into_iter()
-            .map(|p| get_h3_point_covering(p, resolution))
-            .collect::<Result<Vec<CellIndex>, _>>(),
-
-        // Polygon and polygon composite types.
-        Geometry::Polygon(poly) => get_h3_polygon_covering(poly, resolution, mode),
-        Geometry::MultiPolygon(mpoly) => mpoly
-            .into_iter()
-            .map(|p| get_h3_polygon_covering(p, resolution, mode))
-            .flatten_ok()
-            .collect::<Result<Vec<CellIndex>, _>>(),
-
-        // Recurse on geometry collection.
-        Geometry::GeometryCollection(collection) => collection
-            .into_iter()
-            .map(|g| get_h3_covering(g, resolution, mode))
-            .flatten_ok()
-            .collect::<Result<Vec<CellIndex>, _>>(),
-
-        // Default to trying a polygon conversion for the remaining geometries.
-        _ => get_h3_polygon_covering(&geometry.clone().try_into()?, resolution, mode),
+        None => {}
     }
+    Ok(())
 }
 
-fn get_h3_point_covering(
-    point: &Point,
-    resolution: Resolution,
-) -> Result<CellIndex, Box<dyn Error>> {
-    Ok(LatLng::from_radians(point.y(), point.x()).map(|c| c.to_cell(resolution))?)
-}
+#[cfg(test)]
+mod tests {
+    use crate::run_captured;
 
-fn get_h3_polygon_covering(
-    polygon: &Polygon,
-    resolution: Resolution,
-    mode: ContainmentMode,
-) -> Result<Vec<CellIndex>, Box<dyn Error>> {
-    let h3_poly = h3o::geom::Polygon::from_degrees(polygon.clone().try_into()?)?;
-    let config = PolyfillConfig::new(resolution).containment_mode(mode);
-    let cells = h3_poly.to_cells(config).collect_vec();
-    Ok(cells)
+    fn run(args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+        run_captured(args.iter().map(|a| a.to_string()).collect())
+    }
+
+    #[test]
+    fn test_bin_sorted() {
+        let points = "MULTIPOINT(10 10, 1 1, 10 10, -50 -50, 100 40)";
+        // Bins are in order of cell ID by default, and along the Hilbert curve with `--sorted
+        // hilbert`.
+        assert_eq!(
+            run(&["h3", "bin", "-l", "3", "--", points]).unwrap(),
+            "8324a8fffffffff,1\n8358e0fffffffff,2\n837541fffffffff,1\n83dee3fffffffff,1\n"
+        );
+        assert_eq!(
+            run(&["h3", "bin", "-l", "3", "--sorted", "hilbert", "--", points]).unwrap(),
+            "837541fffffffff,1\n8358e0fffffffff,2\n8324a8fffffffff,1\n83dee3fffffffff,1\n"
+        );
+    }
 }
@@ -0,0 +1,293 @@
+use std::error::Error;
+use std::path::Path;
+
+use geo_types::Point;
+
+use crate::format::read_text_file;
+
+/**
+ * The columns of CSV input that points are read from, each named by the header row or given as a
+ * 0-based index.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvColumns {
+    pub lng: String,
+    pub lat: String,
+    /// The column identifying each row, which keys its output with `--per-feature`.
+    pub id: Option<String>,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            lng: "lng".to_string(),
+            lat: "lat".to_string(),
+            id: None,
+        }
+    }
+}
+
+/**
+ * A row of CSV input, read as a point.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRow {
+    /// Where the row was read from, e.g. `stores.csv: row 3`, for errors.
+    pub location: String,
+    /// The point in the columns given by `--lng-column` and `--lat-column`, in the units and CRS of
+    /// the input.
+    pub point: Point,
+    /// The value of the column given by `--id-column`, if any.
+    pub id: Option<String>,
+    /// The row's values, with the header names of their columns.
+    pub columns: Vec<(String, String)>,
+    /// The header and this row, i.e. CSV input of this row alone.
+    pub csv: String,
+}
+
+impl CsvRow {
+    /**
+     * The value in a column, named by the header row or given as a 0-based index.
+     */
+    pub fn value(&self, column: &str) -> Result<&str, Box<dyn Error>> {
+        let value = match column.parse::<usize>() {
+            Ok(index) => self.columns.get(index),
+            Err(_) => self.columns.iter().find(|(name, _)| name == column),
+        };
+        value
+            .map(|(_, value)| value.as_str())
+            .ok_or_else(|| format!("{}: no column '{}'", self.location, column).into())
+    }
+
+    /**
+     * The number in a column, named by the header row or given as a 0-based index.
+     */
+    pub fn number(&self, column: &str) -> Result<f64, Box<dyn Error>> {
+        let value = self.value(column)?;
+        value.parse().map_err(|_| {
+            format!("{}: {} '{}' is not a number", self.location, column, value).into()
+        })
+    }
+}
+
+/**
+ * Reads CSV, given inline, piped, or as `@path`, as points in the given columns. See
+ * [read_csv_table] for how the columns are named.
+ */
+pub fn read_csv_rows(arg: &str, columns: &CsvColumns) -> Result<Vec<CsvRow>, Box<dyn Error>> {
+    match arg.strip_prefix('@') {
+        Some(path) => {
+            let map = read_text_file(Path::new(path))?;
+            read_csv_table(path, &String::from_utf8_lossy(&map), columns)
+        }
+        None => read_csv_table("argument", arg, columns),
+    }
+}
+
+/**
+ * Reads a table of CSV as points in the given columns. The first non-blank row is a header naming
+ * the columns if none of its values are numbers. Tables without one are read as `lng,lat[,...]`
+ * rows, whose other columns are given by their 0-based index.
+ */
+pub fn read_csv_table(
+    origin: &str,
+    text: &str,
+    columns: &CsvColumns,
+) -> Result<Vec<CsvRow>, Box<dyn Error>> {
+    let mut lines = split_csv_records(text).peekable();
+    let Some(&(_, first_line)) = lines.peek() else {
+        return Ok(vec![]);
+    };
+    let first = split_csv_row(first_line);
+    // Only the first row can be a header, so a typo in a later row is an error rather than a new
+    // header.
+    let (header_line, header) = match first.iter().all(|value| value.parse::<f64>().is_err()) {
+        true => {
+            lines.next();
+            (Some(first_line), first)
+        }
+        false => (None, vec!["lng".to_string(), "lat".to_string()]),
+    };
+    lines
+        .map(|(line_number, line)| {
+            let values = split_csv_row(line);
+            let mut row = CsvRow {
+                location: format!("{}: row {}", origin, line_number),
+                point: Point::new(0.0, 0.0),
+                id: None,
+                columns: values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        let name = header.get(i).cloned();
+                        (name.unwrap_or_else(|| i.to_string()), value)
+                    })
+                    .collect(),
+                csv: match header_line {
+                    Some(header_line) => format!("{}\n{}", header_line, line),
+                    None => line.to_string(),
+                },
+            };
+            row.point = Point::new(row.number(&columns.lng)?, row.number(&columns.lat)?);
+            if let Some(column) = &columns.id {
+                row.id = Some(row.value(column)?.to_string());
+            }
+            Ok(row)
+        })
+        .collect()
+}
+
+/**
+ * Splits CSV into its non-blank rows, with the line numbers they start on. Rows end with `\n` or
+ * `\r\n`, except within quoted values, which may span lines.
+ */
+fn split_csv_records(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut records = vec![];
+    let (mut start, mut line, mut start_line, mut quoted) = (0, 1, 1, false);
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '\n' => {
+                line += 1;
+                if !quoted {
+                    records.push((start_line, &text[start..i]));
+                    (start, start_line) = (i + 1, line);
+                }
+            }
+            _ => {}
+        }
+    }
+    records.push((start_line, &text[start..]));
+    records
+        .into_iter()
+        .map(|(line, record)| (line, record.strip_suffix('\r').unwrap_or(record)))
+        .filter(|(_, record)| !record.trim().is_empty())
+}
+
+/**
+ * Splits a row of CSV into its values, with surrounding whitespace removed. Values may be quoted to
+ * contain commas or line breaks, with `""` standing for a quote, as in `-f delimited` output.
+ */
+fn split_csv_row(row: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let (mut start, mut quoted) = (0, false);
+    for (i, c) in row.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                fields.push(&row[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&row[start..]);
+    fields
+        .into_iter()
+        .map(|field| {
+            let field = field.trim();
+            match field.strip_prefix('"').and_then(|f| f.strip_suffix('"')) {
+                Some(quoted) => quoted.replace("\"\"", "\""),
+                None => field.to_string(),
+            }
+        })
+        .collect()
+}
+
+/**
+ * Whether an argument is a row of CSV rather than a geometry, judged by whether its first two
+ * columns are numbers or names.
+ */
+pub fn is_csv_row(arg: &str) -> bool {
+    let columns = split_csv_row(arg);
+    let is_column = |c: &String| {
+        c.parse::<f64>().is_ok()
+            || (!c.is_empty() && c.chars().all(|c| c.is_alphanumeric() || c == '_'))
+    };
+    columns.len() >= 2 && columns[..2].iter().all(is_column)
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::point;
+
+    use super::{read_csv_rows, split_csv_row, CsvColumns};
+
+    #[test]
+    fn test_read_csv_rows() {
+        let columns = CsvColumns {
+            lng: "longitude".to_string(),
+            lat: "1".to_string(),
+            id: Some("store".to_string()),
+        };
+        let csv = "store,latitude,\"longitude\",sales\n\na, 37.8,-122.4,10\nb,1,2,20\n";
+        let rows = read_csv_rows(csv, &columns).unwrap();
+        assert_eq!(
+            rows.iter()
+                .map(|row| (row.id.as_deref(), row.point))
+                .collect::<Vec<_>>(),
+            [
+                (Some("a"), point!(x: -122.4, y: 37.8)),
+                (Some("b"), point!(x: 2.0, y: 1.0))
+            ]
+        );
+        assert_eq!(rows[1].location, "argument: row 4");
+        assert_eq!(rows[1].csv, "store,latitude,\"longitude\",sales\nb,1,2,20");
+        assert_eq!(
+            read_csv_rows("store,latitude,longitude\na,x,1", &columns)
+                .unwrap_err()
+                .to_string(),
+            "argument: row 2: 1 'x' is not a number"
+        );
+        assert!(read_csv_rows("lat,lng\n1,2", &columns).is_err());
+    }
+
+    #[test]
+    fn test_read_quoted_csv() {
+        let columns = CsvColumns {
+            id: Some("name".to_string()),
+            ..CsvColumns::default()
+        };
+        // As written by `-f delimited --delimiter ,`.
+        let csv = "name,wkt,lng,lat\n\"Smith, \"\"J\"\"\",\"POINT(1 2),x\",-122.4,37.8\n";
+        let rows = read_csv_rows(csv, &columns).unwrap();
+        assert_eq!(rows[0].id.as_deref(), Some("Smith, \"J\""));
+        assert_eq!(rows[0].value("wkt").unwrap(), "POINT(1 2),x");
+        assert_eq!(rows[0].point, point!(x: -122.4, y: 37.8));
+
+        // Without a header, the first two columns are the coordinates, and only the first row can
+        // be a header.
+        let rows = read_csv_rows("1,2,a\n3,4,b", &CsvColumns::default()).unwrap();
+        assert_eq!(rows[1].point, point!(x: 3.0, y: 4.0));
+        assert_eq!(rows[1].value("2").unwrap(), "b");
+        assert!(read_csv_rows("lng,lat\n1,2\nlgn,3", &CsvColumns::default()).is_err());
+    }
+
+    #[test]
+    fn test_read_csv_quoting() {
+        let columns = CsvColumns {
+            id: Some("name".to_string()),
+            ..CsvColumns::default()
+        };
+        // Quotes within values, CRLF line endings, and line breaks within quoted values.
+        let csv = "name,lng,lat\r\n\"say \"\"hi\"\"\",1,2\r\n\"two\nlines\",3,4\r\n\r\n\"crlf\r\nwithin\",5,6\r\nlast,7,8";
+        let rows = read_csv_rows(csv, &columns).unwrap();
+        assert_eq!(
+            rows.iter()
+                .map(|row| (row.location.as_str(), row.id.as_deref().unwrap(), row.point))
+                .collect::<Vec<_>>(),
+            [
+                ("argument: row 2", "say \"hi\"", point!(x: 1.0, y: 2.0)),
+                ("argument: row 3", "two\nlines", point!(x: 3.0, y: 4.0)),
+                ("argument: row 6", "crlf\r\nwithin", point!(x: 5.0, y: 6.0)),
+                ("argument: row 8", "last", point!(x: 7.0, y: 8.0))
+            ]
+        );
+        assert_eq!(rows[1].csv, "name,lng,lat\n\"two\nlines\",3,4");
+
+        assert_eq!(
+            split_csv_row(" a , \"b,c\" ,\"\"\"\",,\"\""),
+            ["a", "b,c", "\"", "", ""]
+        );
+    }
+}
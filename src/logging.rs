@@ -0,0 +1,174 @@
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/**
+ * The most verbose level logged for the number of times `-d`/`--debug` was given: `-d` logs the
+ * parsed parameters and result sizes, `-dd` adds geometry stats and covering sizes, and `-ddd` adds
+ * the time spent in each stage.
+ */
+pub fn level_filter(debug: u8) -> LevelFilter {
+    match debug {
+        0 => LevelFilter::OFF,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/**
+ * Logs the events of `geos` itself to stderr at the verbosity set by `--debug`, if any.
+ */
+pub fn init_logging(debug: u8) {
+    if debug == 0 {
+        return;
+    }
+    let logger = Logger::new(level_filter(debug), io::stderr());
+    // Logging is only set up once, so there is no other subscriber to conflict with.
+    let _ = tracing::subscriber::set_global_default(logger);
+}
+
+/**
+ * A minimal subscriber that writes one line per event, prefixed with the time since startup, the
+ * level, and the module that logged it:
+ *
+ * ```text
+ * [   0.412ms DEBUG geos::format] parsed argument: Polygon with 5 coordinates
+ * ```
+ *
+ * Spans aren't rendered, so commands log events only.
+ */
+struct Logger<W> {
+    max_level: LevelFilter,
+    start: Instant,
+    next_span: AtomicU64,
+    out: Mutex<W>,
+}
+
+impl<W: Write> Logger<W> {
+    fn new(max_level: LevelFilter, out: W) -> Self {
+        Self {
+            max_level,
+            start: Instant::now(),
+            next_span: AtomicU64::new(1),
+            out: Mutex::new(out),
+        }
+    }
+}
+
+impl<W: Write + Send + 'static> Subscriber for Logger<W> {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= &self.max_level && metadata.target().starts_with("geos")
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.max_level)
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let line = format!(
+            "[{:>10.3}ms {:<5} {}] {}{}\n",
+            self.start.elapsed().as_secs_f64() * 1000.0,
+            metadata.level(),
+            metadata.target(),
+            fields.message,
+            fields.rest
+        );
+        // Logging must never fail a command, so write errors are ignored.
+        if let Ok(mut out) = self.out.lock() {
+            let _ = out.write_all(line.as_bytes());
+        }
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+/**
+ * The fields of an event, with the message first and the rest as `key=value` pairs.
+ */
+#[derive(Default)]
+struct Fields {
+    message: String,
+    rest: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message.push_str(value),
+            name => {
+                let _ = write!(self.rest, " {}={:?}", name, value);
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let _ = match field.name() {
+            "message" => write!(self.message, "{:?}", value),
+            name => write!(self.rest, " {}={:?}", name, value),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    use tracing::level_filters::LevelFilter;
+
+    use super::{level_filter, Logger};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_logger() {
+        assert_eq!(level_filter(0), LevelFilter::OFF);
+        assert_eq!(level_filter(2), LevelFilter::DEBUG);
+        assert_eq!(level_filter(7), LevelFilter::TRACE);
+
+        let buffer = Buffer::default();
+        let logger = Logger::new(LevelFilter::DEBUG, buffer.clone());
+        tracing::subscriber::with_default(logger, || {
+            tracing::info!(level = 3, "covering {}", "POINT(1 1)");
+            tracing::trace!("not logged");
+        });
+        let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let (_, line) = log.split_once("ms ").unwrap();
+        assert_eq!(
+            line,
+            "INFO  geos::logging::tests] covering POINT(1 1) level=3\n"
+        );
+    }
+}
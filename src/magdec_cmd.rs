@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{NaiveDate, Utc};
+use clap::Args;
+use geo_types::Point;
+use geos_core::magnetic::{decimal_year, MagneticModel};
+
+use crate::format::{parse_geometry, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(
+    about = "Compute the magnetic declination at a point in degrees east of true north, using the World Magnetic Model."
+)]
+#[command(arg_required_else_help = true)]
+pub struct MagdecArgs {
+    #[arg(short, long, help = "A WKT POINT to compute the declination at.")]
+    at: String,
+
+    #[arg(
+        short,
+        long,
+        help = "The date as YYYY-MM-DD, which must be within the model's five year validity period. Defaults to today."
+    )]
+    date: Option<NaiveDate>,
+
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        help = "The altitude in meters above the WGS84 ellipsoid."
+    )]
+    altitude_m: f64,
+
+    #[arg(
+        long,
+        help = "Model coefficients in NOAA's .COF format to use instead of the built-in WMM2025, e.g. a newer WMM.COF."
+    )]
+    cof: Option<PathBuf>,
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_magdec_command(
+    magdec: &MagdecArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let point: Point = parse_geometry(&magdec.at, ctx)?.try_into()?;
+    let date = magdec.date.unwrap_or_else(|| Utc::now().date_naive());
+    let model = match &magdec.cof {
+        Some(path) => MagneticModel::open(path)?,
+        None => MagneticModel::wmm(),
+    };
+
+    let field = model.field(&point, magdec.altitude_m, decimal_year(&date))?;
+    writeln!(out, "{}", field.declination_deg())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_captured;
+
+    #[test]
+    fn test_magdec_defaults_to_wmm() {
+        let args = ["magdec", "--at", "POINT(0 80)", "--date", "2025-01-01"];
+        let out = run_captured(args.iter().map(|s| s.to_string()).collect()).unwrap();
+        let declination: f64 = out.trim().parse().unwrap();
+        // NOAA's WMM2025 test value at 80°N 0°E at the start of 2025.
+        assert!((declination - 1.28).abs() < 0.006, "{}", declination);
+    }
+}
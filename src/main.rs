@@ -1,20 +1,91 @@
+mod batch;
+mod bench_cmd;
+mod cache;
+mod cluster_cmd;
+mod config;
+mod convert_cmd;
+mod crs_cmd;
+mod daemon;
+mod diff_cmd;
+mod elev_cmd;
+mod error;
+mod features;
 mod format;
-mod geom;
+mod geofence_cmd;
 mod geom_cmd;
+#[cfg(feature = "grpc")]
+mod grpc;
 mod h3_cmd;
-mod nvec;
+mod input;
+mod logging;
+mod magdec_cmd;
+mod man_cmd;
+mod pipe_cmd;
+mod plugin;
+mod preview_cmd;
+mod pyramid_cmd;
 mod rand_cmd;
+mod raster_cmd;
+mod render_cmd;
+mod repl;
 mod s2_cmd;
-mod samplers;
+mod serve_cmd;
+mod simulate_cmd;
+mod stdin;
+mod sun_cmd;
+mod template;
+mod timing;
+mod wkt_cmd;
 
-use std::{error::Error, io};
+use std::error::Error;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
 
-use clap::{command, Parser, Subcommand};
+use clap::{Command, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::{generate, Shell};
+use geos_core::crs::CoordinateSystem;
+use geos_core::measure::{Unit, Units};
+use geos_core::tiles::Tile;
+use itertools::Itertools;
 
+use batch::run_batch;
+use bench_cmd::{handle_bench_subcommand, BenchArgs};
+use cache::{Cache, CacheEntry};
+use cluster_cmd::{handle_cluster_subcommand, ClusterArgs};
+use config::Config;
+use convert_cmd::{handle_convert_command, ConvertArgs};
+use crs_cmd::{handle_crs_subcommand, CrsArgs};
+use daemon::{run_daemon, DaemonArgs};
+use diff_cmd::{handle_diff_subcommand, DiffArgs};
+use elev_cmd::{handle_elev_subcommand, ElevArgs};
+use error::{exit_code, set_strict, take_warnings, warn, GeosError};
+use features::{run_per_feature, FeatureOutput};
+use format::{
+    clamp_levels, parse_delimiter, InputFormat, InputOptions, InputUnits, OutputOptions, RunContext,
+};
+use geofence_cmd::{handle_geofence_subcommand, GeofenceArgs};
 use geom_cmd::{handle_geom_subcommand, GeomArgs};
 use h3_cmd::{handle_h3_subcommand, H3Args};
+use input::CsvColumns;
+use logging::init_logging;
+use magdec_cmd::{handle_magdec_command, MagdecArgs};
+use man_cmd::{handle_man_command, ManArgs};
+use pipe_cmd::{handle_pipe_command, PipeArgs};
+use plugin::{exec_plugin, run_plugin};
+use preview_cmd::{handle_preview_command, PreviewArgs};
+use pyramid_cmd::{handle_pyramid_command, PyramidArgs};
 use rand_cmd::{handle_rand_subcommand, RandArgs};
+use raster_cmd::{handle_raster_subcommand, RasterArgs};
+use render_cmd::{handle_render_subcommand, RenderArgs};
+use repl::run_repl;
 use s2_cmd::{handle_s2_subcommand, S2Args};
+use serve_cmd::{handle_serve_command, ServeArgs, ServeOptions};
+use simulate_cmd::{handle_simulate_command, SimulateArgs};
+use stdin::{append_stdin, StdinMode};
+use sun_cmd::{handle_sun_command, SunArgs};
+use timing::report_timings;
+use wkt_cmd::{handle_wkt_subcommand, WktArgs};
 
 //==================================================
 // CLI spec.
@@ -26,10 +97,129 @@ use s2_cmd::{handle_s2_subcommand, S2Args};
 #[command(about = "GeoS: Commandline tool for some handy geographic operations.", long_about = None)]
 #[command(arg_required_else_help = true)]
 pub struct Cli {
-    /// Turn debugging information on
+    /// Log parameters, result sizes, and more to stderr; repeat for more detail (-dd, -ddd)
     #[arg(short, long, action = clap::ArgAction::Count)]
     debug: u8,
 
+    /// Reuse the outputs of S2, H3, and pyramid commands other than `merge` previously run with
+    /// identical arguments, storing them in this directory
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+
+    /// Report how long parsing, computing, and formatting took on stderr
+    #[arg(long, global = true)]
+    timing: bool,
+
+    /// The format of geometries given as arguments or in files
+    #[arg(long, global = true, default_value_t = InputFormat::Wkt)]
+    input_format: InputFormat,
+
+    /// The column of longitudes in CSV input, named by its header row or given as a 0-based index
+    #[arg(long, global = true, default_value = "lng")]
+    lng_column: String,
+
+    /// The column of latitudes in CSV input, named by its header row or given as a 0-based index
+    #[arg(long, global = true, default_value = "lat")]
+    lat_column: String,
+
+    /// The column identifying each row of CSV input, which keys its output with --per-feature
+    #[arg(long, global = true)]
+    id_column: Option<String>,
+
+    /// The units of geometry coordinates given as arguments or in files
+    #[arg(long, global = true, default_value_t = InputUnits::Degrees)]
+    units: InputUnits,
+
+    /// Units of length and area to report measurements in, comma-separated: m, km, mi, nmi, or ft
+    /// for lengths, and m2, km2, ha, acres, mi2, nmi2, or ft2 for areas. A length unit alone also
+    /// sets areas to its square. Arguments suffixed `_m` are always in meters
+    #[arg(long, global = true, value_delimiter = ',')]
+    unit: Vec<Unit>,
+
+    /// The CRS of geometry coordinates given as arguments or in files, such as EPSG:3857, which
+    /// are reprojected to lng/lat before any operation
+    #[arg(long, global = true, default_value_t = CoordinateSystem::Wgs84)]
+    crs: CoordinateSystem,
+
+    /// The CRS to reproject output geometries to, such as the one given by --crs
+    #[arg(long, global = true, default_value_t = CoordinateSystem::Wgs84)]
+    output_crs: CoordinateSystem,
+
+    /// Write WKT geometries as EWKT, prefixed with the SRID of --output-crs as `SRID=<code>;`,
+    /// which PostGIS reads. EWKT is always accepted as input, and its SRID overrides --crs
+    #[arg(long, global = true)]
+    ewkt: bool,
+
+    /// The separator of the columns of `-f delimited` output: a single character, or `tab`
+    #[arg(long, global = true, default_value = "tab", value_parser = parse_delimiter)]
+    delimiter: char,
+
+    /// Start `-f delimited` output with a header row naming its columns, such as `cell_id`,
+    /// `level`, and `wkt`
+    #[arg(long, global = true)]
+    header: bool,
+
+    /// Round output coordinates to this many decimals, e.g. 6 for about 0.1m in lng/lat, rather than
+    /// writing every digit of their full precision
+    #[arg(long, global = true, value_parser = clap::value_parser!(u8).range(0..=15))]
+    precision: Option<u8>,
+
+    /// The z/x/y web mercator tile that `-f mvt` output is encoded for
+    #[arg(long, global = true)]
+    tile: Option<Tile>,
+
+    /// Keep the winding of polygon rings as given, rather than reorienting exteriors
+    /// counterclockwise and holes clockwise with a warning
+    #[arg(long, global = true)]
+    no_normalize: bool,
+
+    /// Repair invalid polygons, such as rings that cross themselves, with a warning, rather than
+    /// failing commands that intersect them
+    #[arg(long, global = true)]
+    repair: bool,
+
+    /// Fix up common mistakes in hand-edited WKT, such as unclosed rings and stray commas, with a
+    /// warning, rather than rejecting it
+    #[arg(long, global = true)]
+    lenient: bool,
+
+    /// Fail on warnings, such as dropped Z values, on empty results, and on differences found by
+    /// `diff`, each with its own exit code
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Saturate out-of-range H3 and S2 levels at the nearest valid level instead of failing
+    #[arg(long, global = true)]
+    clamp: bool,
+
+    /// Run the command on each feature of a GeoJSON FeatureCollection argument separately, writing
+    /// the outputs grouped by feature id
+    #[arg(long, global = true)]
+    per_feature: Option<FeatureOutput>,
+
+    /// Run the command on each file in this directory whose name matches --glob, with the file
+    /// added to its data arguments as `@path`
+    #[arg(long, global = true, conflicts_with = "per_feature")]
+    input_dir: Option<PathBuf>,
+
+    /// The file names to run on with --input-dir, where `*` matches any characters, `?` any one
+    /// character, and `{a,b}` either alternative
+    #[arg(long, global = true, default_value = "*.{wkt,geojson,json}")]
+    glob: String,
+
+    /// Write the output for each file of --input-dir or feature of --per-feature to this path,
+    /// rather than to stdout. `{id}` is replaced by the feature id or file name without its
+    /// extension, `{command}` by the command such as `h3-cover`, and `{<arg>}` by the value of an
+    /// argument such as `{level}`. Files also have `{stem}` and `{name}` for their name without and
+    /// with the extension
+    #[arg(long, global = true)]
+    output_template: Option<String>,
+
+    /// How piped stdin is read: one data argument per non-blank line, one geometry spanning all
+    /// lines, or one raw argument per line, which may include flags
+    #[arg(long, global = true, default_value_t = StdinMode::Lines)]
+    stdin_mode: StdinMode,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -39,35 +229,537 @@ pub enum Commands {
     S2(S2Args),
     H3(H3Args),
     Geom(GeomArgs),
+    Pyramid(PyramidArgs),
+    Diff(DiffArgs),
+    Geofence(GeofenceArgs),
     Rand(RandArgs),
+    Cluster(ClusterArgs),
+    Elev(ElevArgs),
+    Raster(RasterArgs),
+    Simulate(SimulateArgs),
+    Sun(SunArgs),
+    Crs(CrsArgs),
+    Wkt(WktArgs),
+    Convert(ConvertArgs),
+    Preview(PreviewArgs),
+    Render(RenderArgs),
+    Pipe(PipeArgs),
+    Magdec(MagdecArgs),
+    Bench(BenchArgs),
+    Serve(ServeArgs),
+    #[command(about = "Start an interactive session with named variables and tab completion.")]
+    Repl,
+    Daemon(DaemonArgs),
+    #[command(
+        about = "Generate shell completions, e.g. `geos completions bash > /etc/bash_completion.d/geos`."
+    )]
+    Completions {
+        #[arg(help = "The shell to generate completions for.")]
+        shell: Shell,
+    },
+    Man(ManArgs),
+    /// Any other subcommand `<name>` runs the `geos-<name>` executable found on PATH.
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+impl Cli {
+    fn input_options(&self) -> InputOptions {
+        InputOptions {
+            format: self.input_format,
+            csv: CsvColumns {
+                lng: self.lng_column.clone(),
+                lat: self.lat_column.clone(),
+                id: self.id_column.clone(),
+            },
+            units: self.units,
+            crs: self.crs,
+            normalize_winding: !self.no_normalize,
+            repair: self.repair,
+            lenient: self.lenient,
+        }
+    }
+
+    fn output_options(&self) -> Result<OutputOptions, Box<dyn Error>> {
+        Ok(OutputOptions {
+            units: Units::select(&self.unit)?,
+            crs: self.output_crs,
+            srid: self
+                .ewkt
+                .then(|| {
+                    self.output_crs.epsg().ok_or_else(|| {
+                        format!(
+                            "--ewkt needs an output CRS with an EPSG code, not {}",
+                            self.output_crs
+                        )
+                    })
+                })
+                .transpose()?,
+            delimiter: self.delimiter,
+            header: self.header,
+            tile: self.tile,
+            precision: self.precision,
+        })
+    }
+
+    /**
+     * The value of every global option, keyed by its argument ID, for passing to plugins. Flags are
+     * `true` or `false`, and options that were not given have no value.
+     */
+    fn plugin_env(&self) -> Vec<(&'static str, Option<String>)> {
+        let flag = |set: bool| Some(set.to_string());
+        let path = |path: &Option<PathBuf>| path.as_ref().map(|p| p.display().to_string());
+        vec![
+            ("debug", Some(self.debug.to_string())),
+            ("cache_dir", path(&self.cache_dir)),
+            ("timing", flag(self.timing)),
+            ("input_format", Some(self.input_format.to_string())),
+            ("lng_column", Some(self.lng_column.clone())),
+            ("lat_column", Some(self.lat_column.clone())),
+            ("id_column", self.id_column.clone()),
+            ("units", Some(self.units.to_string())),
+            (
+                "unit",
+                (!self.unit.is_empty()).then(|| self.unit.iter().join(",")),
+            ),
+            ("crs", Some(self.crs.to_string())),
+            ("output_crs", Some(self.output_crs.to_string())),
+            ("ewkt", flag(self.ewkt)),
+            ("delimiter", Some(self.delimiter.to_string())),
+            ("header", flag(self.header)),
+            ("precision", self.precision.map(|p| p.to_string())),
+            ("tile", self.tile.map(|t| t.to_string())),
+            ("no_normalize", flag(self.no_normalize)),
+            ("repair", flag(self.repair)),
+            ("lenient", flag(self.lenient)),
+            ("strict", flag(self.strict)),
+            ("clamp", flag(self.clamp)),
+            ("per_feature", self.per_feature.map(|f| f.to_string())),
+            ("input_dir", path(&self.input_dir)),
+            ("glob", Some(self.glob.clone())),
+            ("output_template", self.output_template.clone()),
+            ("stdin_mode", Some(self.stdin_mode.to_string())),
+        ]
+    }
 }
 
 //==================================================
 // CLI runtime logic.
 //==================================================
-fn collect_args() -> Vec<String> {
+fn collect_args() -> io::Result<Vec<String>> {
     // Args read from the commandline.
     let mut args: Vec<String> = std::env::args().collect();
 
-    // Args possibly read from stdin via redirection. This allows for piping values from other
-    // commands.
-    if !atty::is(atty::Stream::Stdin) {
+    // Data possibly read from stdin via redirection. This allows for piping values from other
+    // commands. Interactive commands and external subcommands consume stdin themselves.
+    if !atty::is(atty::Stream::Stdin) && !reads_stdin(&args) {
         // Redirection has occurred.
-        let stdin = io::stdin();
-        let stdin_args: Vec<String> = stdin.lines().map(Result::unwrap).collect();
-        args.extend_from_slice(stdin_args.as_slice());
+        append_stdin(&mut args, &io::read_to_string(io::stdin())?);
     }
 
+    Ok(args)
+}
+
+/**
+ * Whether `--clamp` is given. Levels are checked as they are parsed, so this has to be known before
+ * the arguments are parsed.
+ */
+fn clamps_levels(args: &[String]) -> bool {
+    args.iter()
+        .take_while(|a| *a != "--")
+        .any(|a| a == "--clamp")
+}
+
+/**
+ * The arguments to check before running a command. Commands run with `--input-dir` get their data
+ * arguments from the files, so a stand-in for them is added until the files are read.
+ */
+fn args_to_parse(args: &[String]) -> Vec<String> {
+    let mut args = args.to_vec();
+    let takes_input_dir = args
+        .iter()
+        .take_while(|a| *a != "--")
+        .any(|a| a == "--input-dir" || a.starts_with("--input-dir="));
+    if takes_input_dir {
+        append_stdin(&mut args, "@<input-dir>");
+    }
     args
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let cli = Cli::parse_from(collect_args().iter());
+fn reads_stdin(args: &[String]) -> bool {
+    matches!(
+        Cli::try_parse_from(args).map(|cli| cli.command),
+        Ok(Some(
+            Commands::Repl | Commands::Daemon(_) | Commands::External(_)
+        ))
+    )
+}
+
+/**
+ * Runs a parsed command, writing its results to `out`. This is shared by the commandline entrypoint,
+ * the interactive REPL, and the daemon.
+ */
+pub fn run(cli: &Cli, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    run_in(cli, &mut RunContext::default(), out)
+}
+
+/**
+ * Like [run], but in a context set up by the caller, e.g. with geometries piped from the previous
+ * stage of a pipe, or capturing the geometries that the command writes.
+ */
+pub fn run_in(cli: &Cli, ctx: &mut RunContext, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    ctx.start(cli.input_options(), cli.output_options()?);
+    set_strict(cli.strict);
+    if let Some(command) = &cli.command {
+        tracing::info!("running {:?}", command);
+    }
+    take_warnings();
+    let start = Instant::now();
+    if cli.timing {
+        report_timings(&mut io::stderr(), || run_cached(cli, ctx, out))??
+    } else {
+        run_cached(cli, ctx, out)?
+    }
+    let results = ctx.result_count();
+    tracing::trace!(
+        "finished in {:.3}ms",
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    if let Some(results) = results {
+        tracing::info!("wrote {} results", results);
+    }
+    if cli.strict && results == Some(0) {
+        return Err(GeosError::EmptyResult.into());
+    }
+    Ok(())
+}
+
+fn run_cached(cli: &Cli, ctx: &mut RunContext, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    match (&cli.cache_dir, &cli.command) {
+        (Some(dir), Some(command)) if Cache::is_cacheable(command) => {
+            let cache = Cache::new(dir.clone());
+            let key = Cache::key(command, &ctx.input, &ctx.output)?;
+            let entry = match cache.get(&key)? {
+                Some(entry) => {
+                    tracing::info!("reusing cached output {}", key);
+                    // The run is replayed as it happened, so that `--strict` fails on a hit just as
+                    // it would have on the original run.
+                    for warning in &entry.warnings {
+                        warn(warning.clone())?;
+                    }
+                    ctx.set_result_count(entry.results);
+                    entry
+                }
+                None => {
+                    let mut output = vec![];
+                    // Failed runs return here, so only successful ones are stored.
+                    run_command(cli, ctx, &mut output)?;
+                    let entry = CacheEntry {
+                        output,
+                        results: ctx.result_count(),
+                        warnings: take_warnings(),
+                    };
+                    // Runs that are about to fail `--strict` for want of results are not stored either.
+                    if !(cli.strict && entry.results == Some(0)) {
+                        cache.put(&key, &entry)?;
+                    }
+                    entry
+                }
+            };
+            out.write_all(&entry.output)?;
+            Ok(())
+        }
+        _ => run_command(cli, ctx, out),
+    }
+}
+
+fn run_command(cli: &Cli, ctx: &mut RunContext, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
     match &cli.command {
-        Some(Commands::S2(s2)) => handle_s2_subcommand(s2),
-        Some(Commands::H3(h3)) => handle_h3_subcommand(h3),
-        Some(Commands::Geom(geom)) => handle_geom_subcommand(geom),
-        Some(Commands::Rand(rand)) => handle_rand_subcommand(rand),
+        Some(Commands::S2(s2)) => handle_s2_subcommand(s2, ctx, out),
+        Some(Commands::H3(h3)) => handle_h3_subcommand(h3, ctx, out),
+        Some(Commands::Geom(geom)) => handle_geom_subcommand(geom, ctx, out),
+        Some(Commands::Pyramid(pyramid)) => handle_pyramid_command(pyramid, ctx, out),
+        Some(Commands::Diff(diff)) => handle_diff_subcommand(diff, ctx, out),
+        Some(Commands::Geofence(geofence)) => handle_geofence_subcommand(geofence, ctx, out),
+        Some(Commands::Rand(rand)) => handle_rand_subcommand(rand, ctx, out),
+        Some(Commands::Cluster(cluster)) => handle_cluster_subcommand(cluster, ctx, out),
+        Some(Commands::Elev(elev)) => handle_elev_subcommand(elev, ctx, out),
+        Some(Commands::Raster(raster)) => handle_raster_subcommand(raster, ctx, out),
+        Some(Commands::Simulate(simulate)) => handle_simulate_command(simulate, ctx, out),
+        Some(Commands::Sun(sun)) => handle_sun_command(sun, ctx, out),
+        Some(Commands::Crs(crs)) => handle_crs_subcommand(crs, ctx, out),
+        Some(Commands::Wkt(wkt)) => handle_wkt_subcommand(wkt, ctx, out),
+        Some(Commands::Convert(convert)) => handle_convert_command(convert, ctx, out),
+        Some(Commands::Preview(preview)) => handle_preview_command(preview, ctx, out),
+        Some(Commands::Render(render)) => handle_render_subcommand(render, ctx, out),
+        Some(Commands::Pipe(pipe)) => handle_pipe_command(pipe, ctx, out),
+        Some(Commands::Magdec(magdec)) => handle_magdec_command(magdec, ctx, out),
+        Some(Commands::Bench(bench)) => handle_bench_subcommand(bench, ctx, out),
+        Some(Commands::Serve(serve)) => {
+            let options = ServeOptions {
+                input: ctx.input.clone(),
+                output: ctx.output,
+            };
+            handle_serve_command(serve, options)
+        }
+        Some(Commands::Repl) => run_repl(),
+        Some(Commands::Daemon(daemon)) => run_daemon(daemon),
+        Some(Commands::Completions { shell }) => {
+            generate(*shell, &mut Cli::command(), "geos", out);
+            Ok(())
+        }
+        Some(Commands::Man(man)) => handle_man_command(man, out),
+        Some(Commands::External(args)) => run_plugin(args, &cli.plugin_env(), out),
         None => Ok(()),
     }
 }
+
+/**
+ * Parses and runs a command given without the leading program name, returning its output. Commands
+ * that take over stdin or block forever cannot be nested this way.
+ */
+pub fn run_captured(args: Vec<String>) -> Result<String, Box<dyn Error>> {
+    let mut out: Vec<u8> = vec![];
+    run_args(args, &mut out)?;
+    Ok(String::from_utf8(out)?)
+}
+
+/**
+ * The CLI with the user's configured defaults applied, and with its level arguments clamped for
+ * `--clamp`. Tests use the built-in defaults, so that the user's config file and `GEOS_*` variables
+ * don't change their results.
+ */
+pub fn configured_command(clamp: bool) -> Result<Command, Box<dyn Error>> {
+    let config = match cfg!(test) {
+        true => Config::default(),
+        false => Config::load()?,
+    };
+    let command = config.apply(Cli::command());
+    Ok(if clamp {
+        clamp_levels(command)
+    } else {
+        command
+    })
+}
+
+/**
+ * Parses and runs a command given without the leading program name, writing its results to `out`.
+ */
+pub fn run_args(args: Vec<String>, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    run_args_in(args, &mut RunContext::default(), out)
+}
+
+/**
+ * Like [run_args], but in a context set up by the caller, as with [run_in].
+ */
+pub fn run_args_in(
+    args: Vec<String>,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let command = configured_command(clamps_levels(&args))?;
+    let matches = command.try_get_matches_from(std::iter::once("geos".to_string()).chain(args))?;
+    let cli = Cli::from_arg_matches(&matches)?;
+    if let Some(Commands::Serve(_) | Commands::Repl | Commands::Daemon(_)) = cli.command {
+        return Err("interactive commands cannot be run from here".into());
+    }
+    run_in(&cli, ctx, out)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = collect_args()?;
+    let command = configured_command(clamps_levels(&args))?;
+    let matches = command.get_matches_from(args_to_parse(&args));
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    init_logging(cli.debug);
+
+    // External subcommands take over the terminal and determine the exit code, like git and cargo.
+    if let Some(Commands::External(args)) = &cli.command {
+        std::process::exit(exec_plugin(args, &cli.plugin_env())?);
+    }
+
+    let result = write_buffered(io::stdout().lock(), |out| {
+        match (cli.per_feature, &cli.input_dir) {
+            // The feature argument of --per-feature is read with the global input flags.
+            (Some(format), _) => run_per_feature(
+                &args,
+                &cli.input_options(),
+                format,
+                cli.output_template.as_deref(),
+                out,
+            ),
+            (None, Some(dir)) => {
+                run_batch(&args, dir, &cli.glob, cli.output_template.as_deref(), out)
+            }
+            (None, None) if cli.output_template.is_some() => {
+                Err("--output-template needs --input-dir or --per-feature".into())
+            }
+            (None, None) => run(&cli, out),
+        }
+    });
+    if let Err(e) = result {
+        // Errors are displayed rather than debug-printed, so that input errors show their snippet.
+        eprintln!("Error: {}", e);
+        std::process::exit(exit_code(&*e));
+    }
+    Ok(())
+}
+
+/**
+ * Runs a command writing through one buffer rather than a syscall per line. Commands that stream in
+ * real time flush it themselves. The buffer is flushed even if the command fails, so the results
+ * written before an error still reach the writer.
+ */
+fn write_buffered<W: Write>(
+    writer: W,
+    command: impl FnOnce(&mut dyn Write) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut out = BufWriter::new(writer);
+    let result = command(&mut out);
+    let flushed = out.flush();
+    let result = result.and_then(|()| Ok(flushed?));
+    // Results are written as they are produced, so a reader that stops early, like `head`, closes
+    // the pipe before the command is done, which is not an error.
+    result.or_else(|e| match e.downcast_ref::<io::Error>() {
+        Some(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        _ => Err(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use clap::{Command, CommandFactory, Parser};
+
+    use super::{run_captured, write_buffered, Cli};
+
+    /// The names of every visible command below a command, from the top down.
+    pub(crate) fn command_paths(command: &Command, path: &[String]) -> Vec<Vec<String>> {
+        command
+            .get_subcommands()
+            .filter(|s| !s.is_hide_set() && s.get_name() != "help")
+            .flat_map(|s| {
+                let path = [path, &[s.get_name().to_string()]].concat();
+                std::iter::once(path.clone()).chain(command_paths(s, &path))
+            })
+            .collect()
+    }
+
+    /// A reader that closes the pipe after taking some bytes, like `head -c`.
+    struct Head {
+        taken: Vec<u8>,
+        limit: usize,
+    }
+    impl Write for Head {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.taken.len() >= self.limit {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            let n = buf.len().min(self.limit - self.taken.len());
+            self.taken.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_buffered_flushes_before_errors() {
+        let mut written = vec![];
+        let result = write_buffered(&mut written, |out| {
+            writeln!(out, "1")?;
+            writeln!(out, "2")?;
+            Err("failed after two rows".into())
+        });
+        assert_eq!(result.unwrap_err().to_string(), "failed after two rows");
+        assert_eq!(written, b"1\n2\n");
+
+        // A reader that stops early gets what it asked for, and isn't an error.
+        let mut head = Head {
+            taken: vec![],
+            limit: 4,
+        };
+        let result = write_buffered(&mut head, |out| {
+            for i in 0.. {
+                writeln!(out, "{}", i)?;
+            }
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert_eq!(head.taken, b"0\n1\n");
+    }
+
+    #[test]
+    fn test_completions_cover_every_command() {
+        let completions = run_captured(vec!["completions".into(), "bash".into()]).unwrap();
+        let paths = command_paths(&Cli::command(), &[]);
+        for path in [&["h3", "compact"][..], &["s2", "parent"], &["serve"]] {
+            assert!(paths.contains(&path.iter().map(|s| s.to_string()).collect()));
+        }
+        for path in paths {
+            // Bash function names can't contain dashes, so they separate words like subcommands.
+            let name = path.join("__subcmd__").replace('-', "__subcmd__");
+            let cmd = format!("cmd=\"geos__subcmd__{}\"", name);
+            assert!(completions.contains(&cmd), "{}", path.join(" "));
+        }
+    }
+
+    #[test]
+    fn test_plugin_env_covers_every_global_option() {
+        let cli = Cli::parse_from(["geos", "repl"]);
+        let env = cli.plugin_env();
+        let command = Cli::command();
+        let ids = command
+            .get_arguments()
+            .map(|arg| arg.get_id().as_str())
+            .filter(|id| !["help", "version"].contains(id));
+        for id in ids {
+            assert!(env.iter().any(|(name, _)| *name == id), "{}", id);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugins_get_global_options() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("geos-plugin-env-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let plugin = dir.join("geos-env-test");
+        std::fs::write(&plugin, "#!/bin/sh\nenv | grep '^GEOS_' | sort\n").unwrap();
+        std::fs::set_permissions(&plugin, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let paths = std::iter::once(dir.clone()).chain(std::env::split_paths(&path));
+        std::env::set_var("PATH", std::env::join_paths(paths).unwrap());
+
+        let args = [
+            "-dd",
+            "--precision",
+            "3",
+            "--strict",
+            "--unit",
+            "km,ha",
+            "env-test",
+        ];
+        let out = run_captured(args.iter().map(|s| s.to_string()).collect()).unwrap();
+        std::env::set_var("PATH", path);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let lines = out.lines().collect::<Vec<_>>();
+        for line in [
+            "GEOS_DEBUG=2",
+            "GEOS_PRECISION=3",
+            "GEOS_STRICT=true",
+            "GEOS_REPAIR=false",
+            "GEOS_UNIT=km,ha",
+            "GEOS_INPUT_FORMAT=wkt",
+            "GEOS_GLOB=*.{wkt,geojson,json}",
+        ] {
+            assert!(lines.contains(&line), "{} not in {:?}", line, lines);
+        }
+        assert!(lines.iter().any(|line| line.starts_with("GEOS_BIN=")));
+        assert!(!lines.iter().any(|line| line.starts_with("GEOS_TILE=")));
+    }
+}
@@ -0,0 +1,96 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Command, CommandFactory};
+use clap_mangen::Man;
+
+use crate::Cli;
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Generate man pages for the full command tree.")]
+pub struct ManArgs {
+    #[arg(
+        short,
+        long,
+        help = "Write one page per command (geos.1, geos-h3.1, geos-h3-cover.1, ...) into this directory and print their paths. By default, only the top-level page is written to stdout."
+    )]
+    out_dir: Option<PathBuf>,
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_man_command(man: &ManArgs, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let mut command = Cli::command().name("geos");
+    command.build();
+
+    match &man.out_dir {
+        None => Man::new(command).render(out)?,
+        Some(out_dir) => {
+            fs::create_dir_all(out_dir)?;
+            write_pages(command, out_dir, out)?;
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Recursively writes the man page of a command and all of its visible subcommands. Subcommand pages
+ * are named after their full path, e.g. `geos-h3-cover.1`, so that the pages can be opened with
+ * `man geos-h3-cover`.
+ */
+fn write_pages(
+    command: Command,
+    out_dir: &Path,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let subcommands = command
+        .get_subcommands()
+        .filter(|s| !s.is_hide_set() && s.get_name() != "help");
+    for subcommand in subcommands {
+        // Subcommands carry the version of the tool so that it shows up in each page's footer.
+        let name = format!("{}-{}", command.get_name(), subcommand.get_name());
+        let version = command.get_version().unwrap_or_default().to_string();
+        write_pages(subcommand.clone().name(name).version(version), out_dir, out)?;
+    }
+
+    let man = Man::new(command);
+    let path = out_dir.join(man.get_filename());
+    man.render(&mut File::create(&path)?)?;
+    writeln!(out, "{}", path.display())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use clap::CommandFactory;
+
+    use crate::tests::command_paths;
+    use crate::{run_captured, Cli};
+
+    #[test]
+    fn test_man_pages_cover_every_command() {
+        let dir = std::env::temp_dir().join(format!("geos-man-test-{}", std::process::id()));
+        let dir_arg = dir.display().to_string();
+        let written = run_captured(vec!["man".into(), "--out-dir".into(), dir_arg]).unwrap();
+
+        let paths = command_paths(&Cli::command(), &[]);
+        assert_eq!(written.lines().count(), paths.len() + 1);
+        for name in ["geos-h3-compact", "geos-s2-parent", "geos-serve", "geos"] {
+            assert!(dir.join(format!("{}.1", name)).exists(), "{}", name);
+        }
+        for path in paths {
+            let page = dir.join(format!("geos-{}.1", path.join("-")));
+            assert!(written.contains(&page.display().to_string()), "{:?}", page);
+            assert!(fs::read_to_string(&page).unwrap().contains(".TH "));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+use std::error::Error;
+use std::io::Write;
+
+use clap::Args;
+use itertools::Itertools;
+
+use crate::format::RunContext;
+use crate::run_args_in;
+use crate::stdin::append_stdin;
+
+/// The token separating the stages of a pipeline.
+const SEPARATOR: &str = "|";
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(
+    about = "Run a pipeline of commands, e.g. `geos pipe 'h3 cover -l 5 --as-geometry -- POINT(1 2) | s2 cover -l 8'`, passing the results of each stage to the next in memory."
+)]
+#[command(arg_required_else_help = true)]
+pub struct PipeArgs {
+    #[arg(
+        help = "Commands without the leading `geos`, separated by ` | `. The results of each stage are added to the data arguments of the next, like piped stdin, with geometries passed as they are rather than as WKT."
+    )]
+    pipeline: String,
+}
+
+//==================================================
+// Core logic.
+//==================================================
+pub fn handle_pipe_command(
+    pipe: &PipeArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let stages = split_stages(&pipe.pipeline)?;
+    let result = run_stages(stages, ctx, out);
+    // Piped geometries are only needed while the pipeline runs.
+    ctx.pipe_geometries(vec![]);
+    result
+}
+
+fn run_stages(
+    stages: Vec<Vec<String>>,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let num_stages = stages.len();
+    let mut piped = None;
+    for (i, mut args) in stages.into_iter().enumerate() {
+        let last = i + 1 == num_stages;
+        // The last stage writes the results of the pipe, so it runs in the pipe's own context.
+        let mut stage = RunContext::default();
+        let stage_ctx = if last { &mut *ctx } else { &mut stage };
+        if let Some((geometries, text)) = piped.take() {
+            let mut data = stage_ctx.pipe_geometries(geometries);
+            data.extend(String::from_utf8(text)?.lines().map(str::to_string));
+            append_stdin(&mut args, &data.join("\n"));
+        }
+        tracing::info!("running stage {}: {}", i + 1, args.iter().take(2).join(" "));
+        let failed = |_: &_| eprintln!("error: stage {} failed", i + 1);
+
+        if last {
+            run_args_in(args, stage_ctx, out).inspect_err(failed)?;
+        } else {
+            let mut text = vec![];
+            stage_ctx.capture();
+            let result = run_args_in(args, stage_ctx, &mut text);
+            let geometries = stage_ctx.take_captured().into_iter().map(|(_, g)| g);
+            let geometries = geometries.collect_vec();
+            result.inspect_err(failed)?;
+            tracing::debug!("stage {} piped {} geometries", i + 1, geometries.len());
+            piped = Some((geometries, text));
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Splits a pipeline into the arguments of its stages. Arguments are split like a shell would, so
+ * WKT containing spaces has to be quoted.
+ */
+fn split_stages(pipeline: &str) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+    let tokens = shlex::split(pipeline).ok_or("unbalanced quotes in pipeline")?;
+    let stages = tokens
+        .split(|token| token == SEPARATOR)
+        .map(<[String]>::to_vec)
+        .collect_vec();
+    for (i, stage) in stages.iter().enumerate() {
+        match stage.iter().find(|token| !token.starts_with('-')) {
+            None => return Err(format!("stage {} of the pipeline is empty", i + 1).into()),
+            Some(command) if command == "pipe" => {
+                return Err("pipes cannot be nested".into());
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(stages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{handle_pipe_command, split_stages, PipeArgs};
+    use crate::format::RunContext;
+
+    #[test]
+    fn test_pipe() {
+        assert_eq!(
+            split_stages("h3 cover -l 5 -- 'POINT(1 2)' | h3 cell-to-poly").unwrap(),
+            [
+                vec!["h3", "cover", "-l", "5", "--", "POINT(1 2)"],
+                vec!["h3", "cell-to-poly"]
+            ]
+        );
+        assert!(split_stages("h3 cover -l 5 | | h3 cell-to-poly").is_err());
+        assert!(split_stages("h3 cover -l 5 | pipe 'h3 cell-to-poly'").is_err());
+
+        let run = |pipeline: &str| {
+            let mut out = vec![];
+            let pipe = PipeArgs {
+                pipeline: pipeline.to_string(),
+            };
+            handle_pipe_command(&pipe, &mut RunContext::default(), &mut out)
+                .map(|()| String::from_utf8(out).unwrap())
+        };
+        let cell = "85283473fffffff";
+        let polygon = run(&format!("h3 cell-to-poly -- {}", cell)).unwrap();
+        assert_eq!(
+            run(&format!("h3 cell-to-poly -- {} | h3 cover -l 5", cell)).unwrap(),
+            run(&format!("h3 cover -l 5 -- '{}'", polygon.trim())).unwrap()
+        );
+        // Cells are passed on as text.
+        assert_eq!(
+            run("h3 cover -l 5 -- 'POINT(-122 37.4)' | h3 cell-to-poly").unwrap(),
+            polygon
+        );
+        assert!(run("h3 cell-to-poly -- 123 | h3 cover -l 5").is_err());
+    }
+}
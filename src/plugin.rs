@@ -0,0 +1,107 @@
+use std::env;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Prefix of the executables that provide external subcommands, e.g. `geos-foo` for `geos foo`.
+const PLUGIN_PREFIX: &str = "geos-";
+
+/// A global option's argument ID and its value, if it has one.
+pub type PluginEnv = (&'static str, Option<String>);
+
+/**
+ * Runs an external subcommand with inherited stdio, as if it had been invoked directly, and returns
+ * its exit code.
+ */
+pub fn exec_plugin(args: &[String], globals: &[PluginEnv]) -> Result<i32, Box<dyn Error>> {
+    let status = plugin_command(args, globals)?.status()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+/**
+ * Runs an external subcommand and copies its stdout to `out`. Used when commands are run from
+ * within the REPL or daemon, where stdin belongs to geos itself.
+ */
+pub fn run_plugin(
+    args: &[String],
+    globals: &[PluginEnv],
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut child = plugin_command(args, globals)?
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    io::copy(child.stdout.as_mut().expect("stdout is piped"), out)?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(format!("`{}{}` failed with {}", PLUGIN_PREFIX, args[0], status).into());
+    }
+    Ok(())
+}
+
+/**
+ * Builds the process for an external subcommand. Parsed global options are passed through as
+ * environment variables so that plugins do not need to re-implement the global argument parsing:
+ *
+ * - `GEOS_<OPTION>`: The value of each global option, e.g. `GEOS_PRECISION` for `--precision` and
+ *   `GEOS_DEBUG` for the number of times `--debug` was given. Flags are `true` or `false`, and
+ *   options that were not given are unset, even if they were set in the environment of geos.
+ * - `GEOS_BIN`: The path of the geos executable, for plugins that call back into geos.
+ */
+fn plugin_command(args: &[String], globals: &[PluginEnv]) -> Result<Command, Box<dyn Error>> {
+    let (name, args) = args.split_first().ok_or("missing subcommand name")?;
+    let path = env::var_os("PATH").unwrap_or_default();
+    let plugin = find_plugin(name, &path).ok_or_else(|| format!("no such command: `{}`", name))?;
+
+    let mut command = Command::new(plugin);
+    command.args(args);
+    for (id, value) in globals {
+        let name = format!("GEOS_{}", id.to_uppercase());
+        match value {
+            Some(value) => command.env(name, value),
+            None => command.env_remove(name),
+        };
+    }
+    if let Ok(bin) = env::current_exe() {
+        command.env("GEOS_BIN", bin);
+    }
+    Ok(command)
+}
+
+/**
+ * Searches a PATH-style list of directories for the executable providing an external subcommand.
+ */
+fn find_plugin(name: &str, path: &OsStr) -> Option<PathBuf> {
+    let filename = format!("{}{}{}", PLUGIN_PREFIX, name, env::consts::EXE_SUFFIX);
+    env::split_paths(path)
+        .map(|dir| dir.join(&filename))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::find_plugin;
+
+    #[test]
+    fn test_find_plugin_searches_path_in_order() {
+        let root = env::temp_dir().join(format!("geos-plugin-test-{}", std::process::id()));
+        let (first, second) = (root.join("first"), root.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        let filename = format!("geos-hello{}", env::consts::EXE_SUFFIX);
+        fs::write(first.join(&filename), "").unwrap();
+        fs::write(second.join(&filename), "").unwrap();
+
+        let path = env::join_paths([&second, &first]).unwrap();
+        assert_eq!(find_plugin("hello", &path), Some(second.join(&filename)));
+        assert_eq!(find_plugin("missing", &path), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
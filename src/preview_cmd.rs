@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::io::Write;
+
+use clap::Args;
+use geo::BoundingRect;
+use geo_types::{Geometry, GeometryCollection};
+use geos_core::braille::BrailleCanvas;
+
+use crate::format::{parse_geometry, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(
+    about = "Render geometries as a rough braille map in the terminal, e.g. to sanity-check a covering mid-pipeline with `geos h3 cover -l 5 --as-geometry -- @county.wkt | geos preview`."
+)]
+#[command(arg_required_else_help = true)]
+pub struct PreviewArgs {
+    #[arg(
+        last = true,
+        num_args = 1..,
+        help = "The geometries to draw together, such as a region and the cells covering it."
+    )]
+    wkt: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 80,
+        help = "The most characters across the map may take."
+    )]
+    width: usize,
+
+    #[arg(
+        long,
+        default_value_t = 40,
+        help = "The most lines down the map may take."
+    )]
+    height: usize,
+
+    #[arg(long, help = "Fill polygons rather than drawing their outlines.")]
+    fill: bool,
+}
+
+//==================================================
+// Core logic.
+//==================================================
+pub fn handle_preview_command(
+    preview: &PreviewArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let geometries = preview
+        .wkt
+        .iter()
+        .map(|wkt| parse_geometry(wkt, ctx))
+        .collect::<Result<Vec<_>, _>>()?;
+    let bbox = Geometry::GeometryCollection(GeometryCollection(geometries.clone()))
+        .bounding_rect()
+        .ok_or("there is nothing to preview in empty geometries")?;
+
+    let mut canvas = BrailleCanvas::new(bbox, preview.width, preview.height);
+    for geometry in &geometries {
+        if preview.fill {
+            canvas.fill(geometry);
+        } else {
+            canvas.draw(geometry);
+        }
+    }
+    for line in canvas.render() {
+        writeln!(out, "{}", line)?;
+    }
+    writeln!(
+        out,
+        "BOX({} {},{} {})",
+        bbox.min().x,
+        bbox.min().y,
+        bbox.max().x,
+        bbox.max().y
+    )?;
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+use clap::{Args, ValueEnum};
+use clap_stdin::MaybeStdin;
+use geos_core::h3_cells::get_h3_covering;
+use geos_core::s2_cells::get_s2_covering;
+use geos_core::tiles::{get_tile_covering, MAX_ZOOM};
+use h3o::{geom::ContainmentMode, Resolution};
+
+use crate::error::GeosError;
+use crate::format::{fmt_value_enum, parse_geometry, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(
+    about = "Cover a geometry at every level in a range, outputting `level,cell` lines from the coarsest level to the finest."
+)]
+#[command(arg_required_else_help = true)]
+pub struct PyramidArgs {
+    #[arg(
+        last = true,
+        help = "A valid WKT string encoding the geometry to cover."
+    )]
+    wkt: MaybeStdin<String>,
+
+    #[arg(short, long, help = "The grid system of the cells.")]
+    grid: Grid,
+
+    #[arg(long, help = "The coarsest level to cover at.")]
+    min_level: u8,
+
+    #[arg(long, help = "The finest level to cover at.")]
+    max_level: u8,
+}
+
+impl PyramidArgs {
+    /**
+     * The arguments read as geometries, which may name `@file`s that the output depends on.
+     */
+    pub fn geometry_args(&self) -> Vec<&str> {
+        vec![self.wkt.as_str()]
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum Grid {
+    /// S2 cells at levels [0, 30], as long IDs.
+    S2,
+    /// H3 cells at levels [0, 15], as hex IDs. Polygons are covered by every cell they intersect.
+    H3,
+    /// XYZ web mercator tiles at zoom levels [0, 31], as `z/x/y`.
+    Xyz,
+}
+impl Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_pyramid_command(
+    pyramid: &PyramidArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    if pyramid.min_level > pyramid.max_level {
+        return Err("--min-level must not be greater than --max-level".into());
+    }
+    check_level(&pyramid.grid, pyramid.max_level)?;
+    let geometry = parse_geometry(&pyramid.wkt, ctx)?;
+
+    for level in pyramid.min_level..=pyramid.max_level {
+        let cells: Vec<String> = match pyramid.grid {
+            Grid::S2 => get_s2_covering(&geometry, level, usize::MAX)?
+                .iter()
+                .map(|c| c.0.to_string())
+                .collect(),
+            Grid::H3 => {
+                let resolution = Resolution::try_from(level)?;
+                get_h3_covering(
+                    &geometry,
+                    resolution,
+                    ContainmentMode::IntersectsBoundary,
+                    ctx.input.normalize_winding,
+                )?
+                .iter()
+                .map(|c| c.to_string())
+                .collect()
+            }
+            Grid::Xyz => get_tile_covering(&geometry, level)
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+        };
+        for cell in cells {
+            writeln!(out, "{},{}", level, cell)?;
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Checks that a level is valid for the grid system.
+ */
+pub fn check_level(grid: &Grid, level: u8) -> Result<(), GeosError> {
+    let max = match grid {
+        Grid::S2 => 30,
+        Grid::H3 => 15,
+        Grid::Xyz => MAX_ZOOM,
+    };
+    if level > max {
+        return Err(GeosError::Level {
+            grid: grid.to_string(),
+            level,
+            max,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_level, Grid};
+    use crate::error::exit_code;
+    use crate::run_captured;
+
+    fn run(args: &str) -> Result<String, Box<dyn std::error::Error>> {
+        run_captured(shlex::split(args).unwrap())
+    }
+
+    #[test]
+    fn test_check_level() {
+        for (grid, max) in [(Grid::S2, 30), (Grid::H3, 15), (Grid::Xyz, 31)] {
+            assert!(check_level(&grid, max).is_ok());
+            let error = check_level(&grid, max + 1).unwrap_err();
+            assert_eq!(exit_code(&error), 2);
+        }
+    }
+
+    #[test]
+    fn test_pyramid() {
+        let point = "'POINT(-122.4194 37.7749)'";
+        let output = run(&format!(
+            "pyramid -g s2 --min-level 5 --max-level 10 -- {}",
+            point
+        ));
+        let levels = output
+            .unwrap()
+            .lines()
+            .map(|line| line.split_once(',').unwrap().0.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(levels, ["5", "6", "7", "8", "9", "10"]);
+        let output = run(&format!(
+            "pyramid -g h3 --min-level 4 --max-level 5 -- {}",
+            point
+        ));
+        assert_eq!(output.unwrap(), "4,8428309ffffffff\n5,85283083fffffff\n");
+
+        // Levels out of range are rejected before any level is covered.
+        for grid in [
+            "s2 --min-level 29 --max-level 31",
+            "xyz --min-level 30 --max-level 32",
+        ] {
+            let error = run(&format!("pyramid -g {} -- {}", grid, point)).unwrap_err();
+            assert_eq!(exit_code(&*error), 2, "{}", grid);
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-use clap::{command, Args, Subcommand};
-use geo_types::{Coord, Geometry, Point};
-use wkt::TryFromWkt;
+use clap::{Args, Subcommand};
+use geo_types::{Geometry, Point, Polygon};
+use geos_core::samplers::{create_rng, GeoSampler, PolygonalSampler, UniformSampler};
 
-use crate::format::{fmt_geometry, OutputFormat};
-use crate::samplers::{create_rng, GeoSampler, PolygonalSampler, UniformSampler};
+use crate::format::{fmt_geometry, parse_geometry, OutputFormat, RunContext};
 
 //==================================================
 // CLI spec.
@@ -36,6 +38,12 @@ pub enum RandCommands {
         )]
         num_samples: u64,
 
+        #[arg(
+            long,
+            help = "File in which to save the triangulated polygon, so that later calls with the same polygon skip triangulation. Without --wkt, samples from the saved polygon."
+        )]
+        sampler: Option<PathBuf>,
+
         #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs each sampled point on a separate line. Specifying the oneline format will consolidate lines into a WKT GEOMETRYCOLLECTION on a single line.")]
         format: OutputFormat,
     },
@@ -44,39 +52,70 @@ pub enum RandCommands {
 //==================================================
 // Core subcommand logic.
 //==================================================
-pub fn handle_rand_subcommand(rand: &RandArgs) -> Result<(), Box<dyn Error>> {
+pub fn handle_rand_subcommand(
+    rand: &RandArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
     let mut rng = create_rng(rand.seed);
 
     match &rand.command {
         Some(RandCommands::Point {
             wkt,
             num_samples,
+            sampler,
             format,
         }) => {
-            let coords: Vec<Coord> = match wkt {
-                None => (0..*num_samples)
-                    .map(|_| UniformSampler.sample_coord(&mut rng))
-                    .collect(),
-
-                Some(wkt) => {
-                    let geometry = Geometry::<f64>::try_from_wkt_str(wkt)?;
-                    let sampler = PolygonalSampler::new(geometry.try_into()?);
-                    (0..*num_samples)
-                        .map(|_| sampler.sample_coord(&mut rng))
-                        .collect()
-                }
+            let polygon: Option<Polygon> = match wkt {
+                Some(wkt) => Some(parse_geometry(wkt, ctx)?.try_into()?),
+                None => None,
+            };
+            let sampler: Box<dyn GeoSampler<_>> = match (polygon, sampler) {
+                (None, None) => Box::new(UniformSampler),
+                (Some(polygon), None) => Box::new(PolygonalSampler::new(polygon)?),
+                (polygon, Some(path)) => Box::new(saved_sampler(path, polygon)?),
             };
 
-            let samples: Vec<Geometry> = coords
-                .into_iter()
-                .map(Point::from)
-                .map(Geometry::from)
-                .collect();
+            // Samples are written as they are drawn, so that large samples are never held in
+            // memory.
+            let samples = (0..*num_samples)
+                .map(|_| Geometry::from(Point::from(sampler.sample_coord(&mut rng))));
 
-            fmt_geometry(format, samples);
+            fmt_geometry(out, ctx, format, samples)?;
         }
 
         None => {}
     }
     Ok(())
 }
+
+/**
+ * Loads the sampler saved at `path`. When a polygon is given and the saved sampler is missing or
+ * was built from a different polygon, it is rebuilt and saved instead.
+ */
+fn saved_sampler(
+    path: &Path,
+    polygon: Option<Polygon>,
+) -> Result<PolygonalSampler, Box<dyn Error>> {
+    let saved = match File::open(path) {
+        Ok(file) => Some(
+            PolygonalSampler::read(&mut BufReader::new(file))
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+        ),
+        Err(e) if polygon.is_some() && e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+    };
+
+    match (saved, polygon) {
+        (Some(saved), None) => Ok(saved),
+        (Some(saved), Some(polygon)) if saved.is_for(&polygon) => Ok(saved),
+        (_, Some(polygon)) => {
+            let sampler = PolygonalSampler::new(polygon)?;
+            let mut file = BufWriter::new(File::create(path)?);
+            sampler.write(&mut file)?;
+            file.flush()?;
+            Ok(sampler)
+        }
+        (None, None) => unreachable!("a missing sampler without a polygon is an error above"),
+    }
+}
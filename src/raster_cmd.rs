@@ -0,0 +1,173 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand, ValueEnum};
+use geo_types::{Geometry, Polygon};
+use geos_core::geom::collect_polygons;
+use geos_core::h3_cells::{get_h3_covering, h3_cell_to_poly};
+use geos_core::raster::{Raster, ZonalStats};
+use geos_core::s2_cells::{get_s2_covering, s2_cell_to_poly};
+use h3o::geom::ContainmentMode;
+use itertools::Itertools;
+use s2::cell::Cell;
+use serde_json::json;
+
+use crate::format::{
+    fmt_value_enum, h3_resolution, parse_geometry, parse_h3_level, parse_s2_level, RunContext,
+};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Commands for aggregating raster values.")]
+#[command(args_conflicts_with_subcommands = false)]
+#[command(arg_required_else_help = true)]
+pub struct RasterArgs {
+    #[command(subcommand)]
+    command: Option<RasterCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RasterCommands {
+    #[command(
+        about = "Compute the count, min, max, mean, and sum of the raster values within zones. A pixel is within a zone if its center is."
+    )]
+    #[command(arg_required_else_help = true)]
+    Zonal {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "One or more WKT POLYGON, MULTIPOLYGON, or GEOMETRYCOLLECTION strings. Each polygon is a zone, numbered from 0 in input order."
+        )]
+        wkt: Vec<String>,
+
+        #[arg(
+            long,
+            help = "A single-band GeoTIFF raster in geographic (lng/lat) coordinates."
+        )]
+        raster: PathBuf,
+
+        #[arg(
+            long,
+            conflicts_with = "s2_level",
+            value_parser = parse_h3_level,
+            help = "Use the H3 cells at this level [0, 15] covering the input polygons as zones, instead of the polygons themselves."
+        )]
+        h3_level: Option<u8>,
+
+        #[arg(
+            long,
+            value_parser = parse_s2_level,
+            help = "Use the S2 cells at this level [0, 30] covering the input polygons as zones, instead of the polygons themselves."
+        )]
+        s2_level: Option<u8>,
+
+        #[arg(short, long, default_value_t = ZonalFormat::CSV, help = "The output format for zones. Each zone is written on a separate line.")]
+        format: ZonalFormat,
+    },
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, ValueEnum)]
+pub enum ZonalFormat {
+    /// `<zone>,<count>,<min>,<max>,<mean>,<sum>`, where the zone is the polygon index or cell ID.
+    CSV,
+    /// `{"zone": <zone>, "count": <count>, "min": <min>, "max": <max>, "mean": <mean>, "sum": <sum>}`
+    Ndjson,
+}
+impl Display for ZonalFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_raster_subcommand(
+    raster: &RasterArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match &raster.command {
+        Some(RasterCommands::Zonal {
+            wkt,
+            raster,
+            h3_level,
+            s2_level,
+            format,
+        }) => {
+            let raster = Raster::open(raster)?;
+            let geometries: Vec<Geometry> =
+                wkt.iter().map(|s| parse_geometry(s, ctx)).try_collect()?;
+
+            // Zones as (ID, polygon) pairs.
+            let zones: Vec<(String, Polygon)> = match (h3_level, s2_level) {
+                (Some(level), _) => {
+                    let resolution = h3_resolution(*level)?;
+                    geometries
+                        .iter()
+                        .map(|g| {
+                            get_h3_covering(
+                                g,
+                                resolution,
+                                ContainmentMode::IntersectsBoundary,
+                                ctx.input.normalize_winding,
+                            )
+                        })
+                        .flatten_ok()
+                        .map_ok(|cell| (cell.to_string(), h3_cell_to_poly(&cell)))
+                        .try_collect()?
+                }
+                (None, Some(level)) => geometries
+                    .iter()
+                    .map(|g| get_s2_covering(g, *level, usize::MAX))
+                    .flatten_ok()
+                    .map_ok(|cell| (cell.0.to_string(), s2_cell_to_poly(&Cell::from(cell))))
+                    .try_collect()?,
+                (None, None) => geometries
+                    .iter()
+                    .map(collect_polygons)
+                    .flatten_ok()
+                    .enumerate()
+                    .map(|(i, polygon)| polygon.map(|polygon| (i.to_string(), polygon)))
+                    .try_collect()?,
+            };
+
+            for (zone, polygon) in zones {
+                let stats = raster.zonal_stats(&polygon);
+                writeln!(out, "{}", fmt_zone(format, &zone, &stats))?;
+            }
+        }
+
+        None => {}
+    }
+    Ok(())
+}
+
+fn fmt_zone(format: &ZonalFormat, zone: &str, stats: &ZonalStats) -> String {
+    match format {
+        ZonalFormat::CSV => format!(
+            "{},{},{},{},{},{}",
+            zone,
+            stats.count,
+            stats.min,
+            stats.max,
+            stats.mean(),
+            stats.sum
+        ),
+        // NaN statistics of empty zones become nulls.
+        ZonalFormat::Ndjson => json!({
+            "zone": zone,
+            "count": stats.count,
+            "min": stats.min,
+            "max": stats.max,
+            "mean": stats.mean(),
+            "sum": stats.sum,
+        })
+        .to_string(),
+    }
+}
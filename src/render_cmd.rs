@@ -0,0 +1,91 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+use geos_core::html::write_leaflet_html;
+
+use crate::format::{parse_geometry, round_to_precision, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Commands for rendering geometries as maps to share.")]
+#[command(args_conflicts_with_subcommands = false)]
+#[command(arg_required_else_help = true)]
+pub struct RenderArgs {
+    #[command(subcommand)]
+    command: Option<RenderCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RenderCommands {
+    #[command(
+        about = "Write an HTML page with a Leaflet map of geometries, each drawn as a layer of its own color, e.g. `geos render html -o map.html -- @county.wkt \"$(geos h3 cover -l 5 --as-geometry -f oneline -- @county.wkt)\"`. The geometries are embedded in the page, while Leaflet and the OpenStreetMap base map are loaded when it is opened."
+    )]
+    #[command(arg_required_else_help = true)]
+    Html {
+        #[arg(short, long, help = "The path to write the page to.")]
+        output: PathBuf,
+
+        #[arg(long, default_value = "geos", help = "The title of the page.")]
+        title: String,
+
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Comma-separated names of the layers, in the order of the geometries. Layers without a name are numbered."
+        )]
+        names: Vec<String>,
+
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "The geometries to draw, each as one layer, such as a region and the cells covering it. Use `-f oneline` to pass many cells as a single layer."
+        )]
+        wkt: Vec<String>,
+    },
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_render_subcommand(
+    render: &RenderArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match &render.command {
+        Some(RenderCommands::Html {
+            output,
+            title,
+            names,
+            wkt,
+        }) => {
+            let mut layers = vec![];
+            for (i, wkt) in wkt.iter().enumerate() {
+                let mut geometry = parse_geometry(wkt, ctx)?;
+                round_to_precision(&mut geometry, &ctx.output);
+                let name = names
+                    .get(i)
+                    .cloned()
+                    .unwrap_or_else(|| format!("Layer {}", i + 1));
+                layers.push((name, geometry));
+            }
+
+            if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = File::create(output).map_err(|e| format!("{}: {}", output.display(), e))?;
+            let mut writer = BufWriter::new(file);
+            write_leaflet_html(&mut writer, title, &layers)?;
+            writer.flush()?;
+            writeln!(out, "{}", output.display())?;
+        }
+
+        None => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use clap::CommandFactory;
+use itertools::Itertools;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::{run_captured, Cli};
+
+/// Name of the variable that always holds the result of the most recent command.
+const LAST_RESULT: &str = "_";
+
+const BUILTINS: [&str; 4] = ["let", "vars", "help", "exit"];
+
+const HELP: &str = "\
+Run any geos command without the leading `geos`, e.g. `h3 cover -l 5 -- $area`.
+
+  let <name> = <command>   Store the output of a command in a variable.
+  let <name> = <value>     Store a literal value (e.g. a quoted WKT string) in a variable.
+  $<name>                  Substitute a variable. Multi-line values expand to one argument per line.
+  $_                       The output of the most recent command.
+  vars                     List variables.
+  exit                     Leave the REPL.";
+
+//==================================================
+// REPL loop.
+//==================================================
+pub fn run_repl() -> Result<(), Box<dyn Error>> {
+    let mut editor: Editor<ReplHelper, _> = Editor::new()?;
+    editor.set_helper(Some(ReplHelper::default()));
+
+    loop {
+        let line = match editor.readline("geos> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+
+        let helper = editor.helper_mut().expect("helper is set");
+        match line {
+            "exit" | "quit" => break,
+            "help" => println!("{}", HELP),
+            "vars" => helper.vars.iter().for_each(|(name, value)| {
+                println!("${} = {}", name, preview(value));
+            }),
+            _ => match eval_line(&mut helper.vars, line) {
+                Ok(Some(output)) => println!("{}", output),
+                Ok(None) => {}
+                Err(e) => eprintln!("{}", e.to_string().trim_end()),
+            },
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Evaluates a single REPL line, updating the variables. Returns the output to display, if any.
+ */
+fn eval_line(
+    vars: &mut BTreeMap<String, String>,
+    line: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    match parse_assignment(line) {
+        Some((name, rhs)) => {
+            let value = eval_expression(vars, rhs)?;
+            vars.insert(name.to_string(), value);
+            Ok(None)
+        }
+        None => {
+            let value = eval_expression(vars, line)?;
+            Ok(Some(value))
+        }
+    }
+}
+
+/**
+ * Evaluates the right-hand side of an assignment or a bare command. Inputs that start with a
+ * geos command are run, and anything else is treated as a literal value.
+ */
+fn eval_expression(
+    vars: &mut BTreeMap<String, String>,
+    expression: &str,
+) -> Result<String, Box<dyn Error>> {
+    let tokens = shlex::split(expression).ok_or("unbalanced quotes")?;
+    let is_command = tokens
+        .first()
+        .map(|t| Cli::command().find_subcommand(t).is_some())
+        .unwrap_or(false);
+
+    let value = if is_command {
+        let args = substitute(vars, tokens, true)?;
+        run_captured(args)?.trim_end().to_string()
+    } else {
+        substitute(vars, tokens, false)?.join(" ")
+    };
+
+    vars.insert(LAST_RESULT.to_string(), value.clone());
+    Ok(value)
+}
+
+fn parse_assignment(line: &str) -> Option<(&str, &str)> {
+    let (lhs, rhs) = line.strip_prefix("let ")?.split_once('=')?;
+    let name = lhs.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((name, rhs.trim()))
+}
+
+/**
+ * Replaces `$name` tokens with the value of the named variable. When `split_lines` is set, each
+ * line of the value becomes a separate token, which mirrors how piped stdin lines become separate
+ * arguments on the commandline.
+ */
+fn substitute(
+    vars: &BTreeMap<String, String>,
+    tokens: Vec<String>,
+    split_lines: bool,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut args = vec![];
+    for token in tokens {
+        match token.strip_prefix('$') {
+            Some(name) => {
+                let value = vars
+                    .get(name)
+                    .ok_or_else(|| format!("undefined variable ${}", name))?;
+                if split_lines {
+                    args.extend(value.lines().map(str::to_string));
+                } else {
+                    args.push(value.clone());
+                }
+            }
+            None => args.push(token),
+        }
+    }
+    Ok(args)
+}
+
+fn preview(value: &str) -> String {
+    const MAX_CHARS: usize = 72;
+    let first_line = value.lines().next().unwrap_or_default();
+    let num_lines = value.lines().count();
+    let mut preview: String = first_line.chars().take(MAX_CHARS).collect();
+    if first_line.chars().count() > MAX_CHARS {
+        preview.push_str("...");
+    }
+    if num_lines > 1 {
+        preview.push_str(&format!(" ({} lines)", num_lines));
+    }
+    preview
+}
+
+//==================================================
+// Tab completion.
+//==================================================
+#[derive(Default)]
+struct ReplHelper {
+    vars: BTreeMap<String, String>,
+}
+
+impl Helper for ReplHelper {}
+impl Highlighter for ReplHelper {}
+impl Validator for ReplHelper {}
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+        let word = &line[start..];
+        let preceding = line[..start].split_whitespace().collect_vec();
+
+        let candidates = if let Some(prefix) = word.strip_prefix('$') {
+            self.vars
+                .keys()
+                .filter(|name| name.starts_with(prefix))
+                .map(|name| format!("${}", name))
+                .collect_vec()
+        } else {
+            command_completions(&preceding, word)
+        };
+
+        let pairs = candidates
+            .into_iter()
+            .map(|c| Pair {
+                display: c.clone(),
+                replacement: c,
+            })
+            .collect_vec();
+        Ok((start, pairs))
+    }
+}
+
+/**
+ * Completes subcommand names and long flags by walking the CLI spec along the preceding tokens.
+ */
+fn command_completions(preceding: &[&str], word: &str) -> Vec<String> {
+    // Skip over a leading assignment so that `let x = h3 co<TAB>` completes like `h3 co<TAB>`.
+    let preceding = match preceding {
+        ["let", _, "=", rest @ ..] => rest,
+        _ => preceding,
+    };
+
+    let mut command = Cli::command();
+    for token in preceding {
+        match command.find_subcommand(token) {
+            Some(subcommand) => command = subcommand.clone(),
+            None => break,
+        }
+    }
+
+    let mut candidates = if word.starts_with('-') {
+        command
+            .get_arguments()
+            .filter_map(|a| a.get_long())
+            .map(|long| format!("--{}", long))
+            .collect_vec()
+    } else {
+        command
+            .get_subcommands()
+            .map(|s| s.get_name().to_string())
+            .collect_vec()
+    };
+    if preceding.is_empty() {
+        candidates.extend(BUILTINS.iter().map(|b| b.to_string()));
+    }
+
+    candidates
+        .into_iter()
+        .filter(|c| c.starts_with(word))
+        .sorted()
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::eval_line;
+
+    #[test]
+    fn test_eval_line_chains_variables() {
+        let mut vars = BTreeMap::new();
+
+        let assigned = eval_line(&mut vars, "let p = 'MULTIPOINT(0 0, 1 0)'").unwrap();
+        assert_eq!(assigned, None);
+        assert_eq!(vars["p"], "MULTIPOINT(0 0, 1 0)");
+
+        eval_line(&mut vars, "let d = geom distance-matrix -f json -- $p").unwrap();
+        assert_eq!(vars["d"], "[[0,111195.0802335329],[111195.0802335329,0]]");
+
+        let output = eval_line(&mut vars, "cluster dbscan -e 200000 -m 2 -- $p").unwrap();
+        assert_eq!(output.as_deref(), Some("0\n0"));
+        assert_eq!(vars["_"], "0\n0");
+
+        assert!(eval_line(&mut vars, "h3 cover -- $missing").is_err());
+    }
+}
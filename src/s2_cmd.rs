@@ -1,16 +1,27 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
 
-use clap::{command, Args, Subcommand, ValueEnum};
+use clap::{ArgGroup, Args, Subcommand, ValueEnum};
 use clap_stdin::MaybeStdin;
-use geo::{BoundingRect, Point, Polygon};
-use geo_types::{polygon, Coord, Geometry};
-use itertools::Itertools;
-use s2::{cell::Cell, cellid::CellID, latlng::LatLng};
-use wkt::{ToWkt, TryFromWkt};
+use geo::GeodesicArea;
+use geo_types::Geometry;
+use geos_core::geom::{collect_polygons, PolygonCutter};
+use geos_core::s2_cells::{
+    bin_s2_points, get_s2_bbox_covering_between, get_s2_covering_between, merge_s2_cells,
+    s2_bbox_covering_cells, s2_cell_to_poly, s2_cells_at_level, s2_cells_to_polys,
+    s2_covering_cells, CoveringStats, InvalidS2Level,
+};
+use s2::{cell::Cell, cellid::CellID};
 
-use crate::format::{fmt_geometry, fmt_value_enum, OutputFormat};
-use crate::geom::cut_polygon;
+use crate::error::GeosError;
+use crate::format::{
+    fmt_bins, fmt_cell_features, fmt_cell_polygons, fmt_cells, fmt_geometry, fmt_value_enum,
+    parse_geometry, parse_s2_cell_from_file, parse_s2_cells, parse_s2_level, parse_weighted_points,
+    read_cells, write_cell_arrow, write_cell_geoparquet, ArrowArgs, BinFormat, CellEncoding,
+    CellListArgs, GeoParquetArgs, OutputFormat, RunContext,
+};
 
 //==================================================
 // CLI spec.
@@ -24,9 +35,38 @@ pub struct S2Args {
     command: Option<S2Commands>,
 }
 
+impl S2Args {
+    /**
+     * Whether the output depends only on the arguments, and not on the contents of files.
+     */
+    pub fn is_cacheable(&self) -> bool {
+        match &self.command {
+            Some(S2Commands::Merge { .. }) => false,
+            Some(S2Commands::Cover { parquet, arrow, .. }) => {
+                parquet.path().is_none() && arrow.path().is_none()
+            }
+            Some(S2Commands::Cut { parquet, .. }) => parquet.path().is_none(),
+            _ => true,
+        }
+    }
+
+    /**
+     * The arguments read as geometries or points, which may name `@file`s that the output depends
+     * on.
+     */
+    pub fn geometry_args(&self) -> Vec<&str> {
+        match &self.command {
+            Some(S2Commands::Cover { wkt, .. } | S2Commands::Cut { wkt, .. }) => vec![wkt.as_str()],
+            Some(S2Commands::Bin { points, .. }) => points.iter().map(String::as_str).collect(),
+            _ => vec![],
+        }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum S2Commands {
     #[command(arg_required_else_help = true)]
+    #[command(group(ArgGroup::new("file_output").args(["output", "arrow"])))]
     Cover {
         #[arg(
             last = true,
@@ -38,10 +78,26 @@ pub enum S2Commands {
             short,
             long,
             default_value_t = 12,
-            help = "The S2 cell level [1, 30] at which to perform the covering."
+            value_parser = parse_s2_level,
+            help = "The S2 cell level [0, 30] at which to perform the covering."
         )]
         level: u8,
 
+        #[arg(
+            long,
+            value_parser = parse_s2_level,
+            help = "The coarsest level of a mixed-level covering, whose cells range from it to --max-level. Cells within the geometry are as coarse as they can be, while those along its edges are at --max-level. Defaults to --max-level, for cells of one level."
+        )]
+        min_level: Option<u8>,
+
+        #[arg(
+            long,
+            value_parser = parse_s2_level,
+            conflicts_with = "level",
+            help = "The finest level of a mixed-level covering. Defaults to --level."
+        )]
+        max_level: Option<u8>,
+
         #[arg(long, default_value_t = S2CellFormat::Long, help = "Format for the S2 cell IDs.")]
         s2_cell_format: S2CellFormat,
 
@@ -50,6 +106,52 @@ pub enum S2Commands {
 
         #[arg(short, long, help = "Max number of S2 cells to return.")]
         max_num_s2_cells: Option<usize>,
+
+        #[arg(
+            long,
+            help = "Cover the bounding box of the geometry rather than its shape. Faster, but coverings of L-shaped or diagonal polygons take many cells that miss them."
+        )]
+        bbox_approx: bool,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with_all = ["output", "arrow"],
+            help = "Output a report of how closely the covering fits the geometry instead of its cells: the cell count, the covered and geometry areas in square meters or the unit given by --unit, and the covered area as a multiple of the geometry area."
+        )]
+        stats: bool,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with_all = ["stats", "arrow"],
+            help = "Output the polygon of each cell as WKT instead of its ID."
+        )]
+        as_geometry: bool,
+
+        #[arg(
+            long,
+            requires = "as_geometry",
+            help = "Name each cell polygon by its cell ID, as the placemark names of KML output or the `name` property of MVT features."
+        )]
+        name_cells: bool,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with_all = ["as_geometry", "stats", "output", "arrow"],
+            help = "Output a GeoJSON FeatureCollection of the cell polygons instead of their IDs, with `cell_id`, `level`, and `area_m2` properties, or the area in the unit given by --unit."
+        )]
+        as_features: bool,
+
+        #[command(flatten)]
+        cell_list: CellListArgs,
+
+        #[command(flatten)]
+        parquet: GeoParquetArgs,
+
+        #[command(flatten)]
+        arrow: ArrowArgs,
     },
 
     #[command(arg_required_else_help = true)]
@@ -64,7 +166,8 @@ pub enum S2Commands {
             short,
             long,
             default_value_t = 12,
-            help = "The S2 cell level at which to perform the covering."
+            value_parser = parse_s2_level,
+            help = "The S2 cell level [0, 30] at which to perform the covering."
         )]
         level: u8,
 
@@ -73,23 +176,169 @@ pub enum S2Commands {
 
         #[arg(short, long, help = "Max number of S2 cells to return.")]
         max_num_s2_cells: Option<usize>,
+
+        #[arg(
+            long,
+            help = "Find the cells to cut by from the bounding box of the geometry rather than its shape. Cells that miss the geometry are still dropped, as they leave no pieces."
+        )]
+        bbox_approx: bool,
+
+        #[command(flatten)]
+        parquet: GeoParquetArgs,
     },
 
     #[command(arg_required_else_help = true)]
     CellToPoly {
         #[arg(
             last = true,
-            help = "A valid S2 cell index. Only long values are accepted."
+            num_args = 1..,
+            use_value_delimiter = true,
+            value_delimiter = ',',
+            help = "A comma-separated list of valid S2 cell indices. Only long values are accepted."
+        )]
+        cells: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::CSV,
+            help = "By default, outputs each cell polygon on separate lines."
+        )]
+        format: OutputFormat,
+    },
+
+    #[command(
+        about = "Map cells to their ancestors at a coarser level, e.g. to aggregate S2-keyed data. Each cell is mapped in turn, so that the output lines up with the input."
+    )]
+    #[command(arg_required_else_help = true)]
+    Parent {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            use_value_delimiter = true,
+            value_delimiter = ',',
+            help = "A comma-separated list of valid S2 cell indices. Only long values are accepted."
+        )]
+        cells: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            value_parser = parse_s2_level,
+            help = "The S2 cell level [0, 30] of the ancestors, which must be no finer than any of the cells."
+        )]
+        level: u8,
+
+        #[arg(long, default_value_t = S2CellFormat::Long, help = "Format for the S2 cell IDs.")]
+        s2_cell_format: S2CellFormat,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs each cell ID on separate lines.")]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        cell_list: CellListArgs,
+    },
+
+    #[command(
+        about = "Merge cell lists into one sorted covering without duplicates or cells contained by another cell."
+    )]
+    #[command(arg_required_else_help = true)]
+    Merge {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "Files of S2 cell indices, one per line or comma-separated. Only long values are accepted."
+        )]
+        files: Vec<PathBuf>,
+
+        #[arg(
+            long,
+            conflicts_with = "level",
+            help = "Also replace complete sets of four sibling cells by their parent."
+        )]
+        compact: bool,
+
+        #[arg(
+            short,
+            long,
+            value_parser = parse_s2_level,
+            help = "Normalize the cells to this level [0, 30], replacing finer cells by their ancestor and coarser cells by their descendants."
+        )]
+        level: Option<u8>,
+
+        #[arg(long, default_value_t = S2CellFormat::Long, help = "Format for the S2 cell IDs.")]
+        s2_cell_format: S2CellFormat,
+
+        #[arg(short, long, default_value_t = OutputFormat::CSV, help = "By default, outputs each cell ID on separate lines.")]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        cell_list: CellListArgs,
+    },
+
+    #[command(
+        about = "Count the points within each cell, optionally summing a weight column, for density maps."
+    )]
+    #[command(arg_required_else_help = true)]
+    Bin {
+        #[arg(
+            last = true,
+            num_args = 1..,
+            help = "Points as `<lng>,<lat>[,<columns>...]` CSV rows, inline, piped, or as `@path.csv`, or as WKT or GeoJSON geometries whose points are counted."
+        )]
+        points: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            value_parser = parse_s2_level,
+            help = "The S2 cell level [0, 30] at which to count points."
+        )]
+        level: u8,
+
+        #[arg(
+            short,
+            long,
+            help = "Sum the weights in this CSV column, named by the header row or a 0-based index, as well as counting points."
+        )]
+        weight_column: Option<String>,
+
+        #[arg(
+            long,
+            help = "Also write the count, or the summed weight, per unit of cell area in m² or the area unit given by --unit. S2 cells of one level vary in area by a factor of two."
         )]
-        cell: String,
+        density: bool,
+
+        #[arg(long, default_value_t = S2CellFormat::Long, help = "Format for the S2 cell IDs.")]
+        s2_cell_format: S2CellFormat,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = BinFormat::CSV,
+            help = "By default, outputs each cell and its count on separate lines."
+        )]
+        format: BinFormat,
+
+        #[command(flatten)]
+        cell_list: CellListArgs,
     },
 }
 
 #[derive(Debug, Clone, ValueEnum)]
 pub enum S2CellFormat {
+    /// The 64-bit ID as an unsigned integer.
+    #[value(alias = "decimal")]
     Long,
+    /// The hex token of the ID, without trailing zeros.
     Hex,
     Quad,
+    Octal,
+    Binary,
+    /// The 8 big-endian bytes of the ID in RFC 4648 base32, without padding.
+    Base32,
+    /// The 8 big-endian bytes of the ID in URL-safe base64, without padding.
+    Base64,
 }
 impl Display for S2CellFormat {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -100,35 +349,138 @@ impl Display for S2CellFormat {
 //==================================================
 // Core subcommand logic.
 //==================================================
-pub fn handle_s2_subcommand(s2: &S2Args) -> Result<(), Box<dyn Error>> {
+pub fn fmt_cell(format: &S2CellFormat, c: &CellID) -> String {
+    match format {
+        S2CellFormat::Long => CellEncoding::Decimal.encode(c.0),
+        S2CellFormat::Hex => c.to_token(),
+        S2CellFormat::Quad => format!("{:#?}", c),
+        S2CellFormat::Octal => CellEncoding::Octal.encode(c.0),
+        S2CellFormat::Binary => CellEncoding::Binary.encode(c.0),
+        S2CellFormat::Base32 => CellEncoding::Base32.encode(c.0),
+        S2CellFormat::Base64 => CellEncoding::Base64.encode(c.0),
+    }
+}
+
+/**
+ * The cells covering a geometry, found lazily when they are of one level and their number is
+ * unlimited. Mixed-level coverings are compact, so they are computed in full.
+ */
+pub fn covering(
+    geometry: &Geometry,
+    min_level: u8,
+    max_level: u8,
+    max_cells: Option<usize>,
+    bbox_approx: bool,
+) -> Result<Box<dyn Iterator<Item = CellID>>, InvalidS2Level> {
+    let (level, lazy) = (max_level, min_level == max_level && max_cells.is_none());
+    let max_cells = max_cells.unwrap_or(usize::MAX);
+    Ok(match (lazy, bbox_approx) {
+        (true, false) => Box::new(s2_covering_cells(geometry, level)?),
+        (true, true) => Box::new(s2_bbox_covering_cells(geometry, level)?),
+        (false, false) => Box::new(
+            get_s2_covering_between(geometry, min_level, max_level, max_cells)?.into_iter(),
+        ),
+        (false, true) => Box::new(
+            get_s2_bbox_covering_between(geometry, min_level, max_level, max_cells)?.into_iter(),
+        ),
+    })
+}
+
+pub fn handle_s2_subcommand(
+    s2: &S2Args,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
     match &s2.command {
         // Cover geometry.
         Some(S2Commands::Cover {
             wkt,
             level,
+            min_level,
+            max_level,
             s2_cell_format,
             format,
             max_num_s2_cells,
+            bbox_approx,
+            stats,
+            as_geometry,
+            name_cells,
+            as_features,
+            cell_list,
+            parquet,
+            arrow,
         }) => {
-            let fmt_cell = |c: CellID| match s2_cell_format {
-                S2CellFormat::Long => format!("{}", c.0),
-                S2CellFormat::Hex => format!("{}", c.to_token()),
-                S2CellFormat::Quad => format!("{:#?}", c),
-            };
-
-            let max_num_s2_cells = max_num_s2_cells.unwrap_or(usize::max_value());
+            let geometry = parse_geometry(wkt, ctx)?;
+            let max_level = max_level.unwrap_or(*level);
+            let min_level = min_level.unwrap_or(max_level);
+            if min_level > max_level {
+                return Err(GeosError::InvalidParameter(format!(
+                    "--min-level {} is finer than the max level {}",
+                    min_level, max_level
+                ))
+                .into());
+            }
+            ctx.record_metadata("level", max_level);
+            ctx.record_metadata("min_level", min_level);
+            ctx.record_metadata("max_cells", *max_num_s2_cells);
+            ctx.record_metadata("bbox_approx", *bbox_approx);
 
-            let geometry = Geometry::<f64>::try_from_wkt_str(wkt)?;
-            let cover = get_s2_covering(&geometry, *level, max_num_s2_cells);
+            // Unlimited coverings are written as they are found, since fine levels can have
+            // very many cells.
+            let cover = covering(
+                &geometry,
+                min_level,
+                max_level,
+                *max_num_s2_cells,
+                *bbox_approx,
+            )?;
 
-            match format {
-                OutputFormat::Oneline => {
-                    println!("{}", cover.into_iter().map(fmt_cell).join(","))
+            if let Some(path) = parquet.path() {
+                let rows = cell_list.apply(cover).map(|cell| {
+                    let polygon = Geometry::from(s2_cell_to_poly(&Cell::from(cell)));
+                    (fmt_cell(s2_cell_format, &cell), cell.level() as u8, polygon)
+                });
+                write_cell_geoparquet(out, path, rows)?;
+            } else if let Some(path) = arrow.path() {
+                let rows = cell_list
+                    .apply(cover)
+                    .map(|cell| (cell.0, cell.level() as u8));
+                write_cell_arrow(out, path, rows)?;
+            } else if *as_features {
+                let features = cell_list.apply(cover).map(|cell| {
+                    let polygon = Geometry::from(s2_cell_to_poly(&Cell::from(cell)));
+                    let area_m2 = polygon.geodesic_area_unsigned();
+                    (
+                        fmt_cell(s2_cell_format, &cell),
+                        cell.level() as u8,
+                        polygon,
+                        area_m2,
+                    )
+                });
+                fmt_cell_features(out, ctx, features, "level")?;
+            } else if *stats {
+                let stats = CoveringStats::new(&geometry, cell_list.apply(cover));
+                writeln!(out, "cells: {}", stats.cells)?;
+                let units = ctx.output.units;
+                let symbol = units.area.symbol();
+                let covered_area = units.area(stats.covered_area_m2);
+                let geometry_area = units.area(stats.geometry_area_m2);
+                writeln!(out, "covered_area_{}: {}", symbol, covered_area)?;
+                writeln!(out, "geometry_area_{}: {}", symbol, geometry_area)?;
+                if let Some(over_coverage) = stats.over_coverage() {
+                    writeln!(out, "over_coverage: {}", over_coverage)?;
                 }
-                OutputFormat::CSV => cover
-                    .into_iter()
-                    .map(fmt_cell)
-                    .for_each(|c| println!("{}", c)),
+            } else if *as_geometry {
+                let polys = cell_list.apply(cover).map(|cell| {
+                    let polygon = Geometry::from(s2_cell_to_poly(&Cell::from(cell)));
+                    (fmt_cell(s2_cell_format, &cell), cell.level() as u8, polygon)
+                });
+                fmt_cell_polygons(out, ctx, format, polys, *name_cells)?;
+            } else {
+                let cover = cell_list
+                    .apply(cover)
+                    .map(|c| (fmt_cell(s2_cell_format, &c), c.level() as u8));
+                fmt_cells(out, ctx, format, cover)?;
             }
         }
 
@@ -138,24 +490,112 @@ pub fn handle_s2_subcommand(s2: &S2Args) -> Result<(), Box<dyn Error>> {
             level,
             format,
             max_num_s2_cells,
+            bbox_approx,
+            parquet,
+        }) => {
+            ctx.record_metadata("level", *level);
+            ctx.record_metadata("max_cells", *max_num_s2_cells);
+            ctx.record_metadata("bbox_approx", *bbox_approx);
+            let geometry = parse_geometry(wkt, ctx)?;
+            // Repairing a polygon can split it, so each polygonal member is cut.
+            let polygons = collect_polygons(&geometry)?;
+            let cutter = PolygonCutter::new(&polygons, ctx.input.normalize_winding)?;
+
+            // Like coverings, unlimited cuts are written as the cells are found.
+            let cover = covering(&geometry, *level, *level, *max_num_s2_cells, *bbox_approx)?;
+            let cells = cover.map(|cell| (cell, s2_cell_to_poly(&Cell::from(cell))));
+            let cuts = cutter
+                .cut_each(cells)
+                .map(|(cell, cut)| (cell.0.to_string(), cell.level() as u8, cut.into()));
+            if let Some(path) = parquet.path() {
+                write_cell_geoparquet(out, path, cuts)?;
+            } else {
+                fmt_cell_polygons(out, ctx, format, cuts, false)?;
+            }
+        }
+
+        Some(S2Commands::CellToPoly { cells, format }) => {
+            let cells = parse_s2_cells(cells)?;
+            let polys = s2_cells_to_polys(cells).map(Geometry::from);
+            fmt_geometry(out, ctx, format, polys)?;
+        }
+
+        Some(S2Commands::Parent {
+            cells: args,
+            level,
+            s2_cell_format,
+            format,
+            cell_list,
+        }) => {
+            let cells = parse_s2_cells(args)?;
+            if let Some(i) = cells.iter().position(|c| c.level() < *level as u64) {
+                let reason = format!(
+                    "the cell is at level {}, coarser than {}",
+                    cells[i].level(),
+                    level
+                );
+                return Err(GeosError::cell_in_list("S2", args, i, &reason).into());
+            }
+            let parents = cells.into_iter().map(|c| c.parent(*level as u64));
+            let parents = cell_list
+                .apply(parents)
+                .map(|c| (fmt_cell(s2_cell_format, &c), c.level() as u8));
+            fmt_cells(out, ctx, format, parents)?;
+        }
+
+        Some(S2Commands::Merge {
+            files,
+            compact,
+            level,
+            s2_cell_format,
+            format,
+            cell_list,
         }) => {
-            let max_num_s2_cells = max_num_s2_cells.unwrap_or(usize::max_value());
-            let geometry = Geometry::<f64>::try_from_wkt_str(wkt)?;
-            let cover = get_s2_covering(&geometry, *level, max_num_s2_cells)
-                .into_iter()
-                .map(Cell::from)
-                .collect_vec();
-            let cuts = cut_region(geometry.try_into()?, &cover)
-                .into_iter()
-                .map(Geometry::from)
-                .collect_vec();
-            fmt_geometry(format, cuts);
+            let mut cells: Vec<CellID> = vec![];
+            for file in files {
+                for cell in read_cells(file)? {
+                    cells.push(parse_s2_cell_from_file(&cell)?);
+                }
+            }
+            let merged = match level {
+                Some(level) => s2_cells_at_level(cells, *level),
+                None => merge_s2_cells(cells, *compact),
+            };
+
+            let merged = cell_list
+                .apply(merged.into_iter())
+                .map(|c| (fmt_cell(s2_cell_format, &c), c.level() as u8));
+            fmt_cells(out, ctx, format, merged)?;
         }
 
-        Some(S2Commands::CellToPoly { cell }) => {
-            let cell_id = CellID { 0: cell.parse()? };
-            let poly = s2_cell_to_poly(&cell_id.into());
-            println!("{}", poly.wkt_string());
+        Some(S2Commands::Bin {
+            points,
+            level,
+            weight_column,
+            density,
+            s2_cell_format,
+            format,
+            cell_list,
+        }) => {
+            let points = parse_weighted_points(points, weight_column.as_deref(), ctx)?;
+            let bins = bin_s2_points(points, *level)?;
+            tracing::debug!("binned points into {} cells", bins.len());
+            let bins = cell_list
+                .apply(bins.keys().copied())
+                .map(|cell| (cell, bins[&cell]))
+                .collect();
+            let cell_area =
+                |cell: &CellID| s2_cell_to_poly(&Cell::from(cell)).geodesic_area_unsigned();
+            fmt_bins(
+                out,
+                ctx,
+                format,
+                bins,
+                weight_column.is_some(),
+                density.then_some(&cell_area as &dyn Fn(&CellID) -> f64),
+                |cell| fmt_cell(s2_cell_format, cell),
+                |cell| s2_cell_to_poly(&Cell::from(cell)),
+            )?;
         }
 
         None => {}
@@ -163,45 +603,82 @@ pub fn handle_s2_subcommand(s2: &S2Args) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-//==================================================
-// Geometry utils.
-//==================================================
-/**
- * Computes an S2 cell covering of the given geometry by first computing a bounding box and then
- * covering the bounding box. This is efficient but imprecise.
- */
-fn get_s2_covering(geometry: &Geometry, level: u8, max_cells: usize) -> Vec<CellID> {
-    let bbox = geometry.bounding_rect().unwrap();
-    let pmin: Point = bbox.min().try_into().unwrap();
-    let pmax: Point = bbox.max().try_into().unwrap();
-    let region = s2::rect::Rect::from_degrees(pmin.y(), pmin.x(), pmax.y(), pmax.x());
-
-    // compute covering of the bounding box.
-    let rc = s2::region::RegionCoverer {
-        min_level: level,
-        max_level: level,
-        level_mod: 1,
-        max_cells,
-    };
-    rc.covering(&region).0
-}
+#[cfg(test)]
+mod tests {
+    use crate::error::exit_code;
+    use crate::run_captured;
 
-/**
- * Creates a polygon from the vertices of an S2 cell.
- */
-fn s2_cell_to_poly(cell: &Cell) -> Polygon {
-    let vertices: [Coord; 4] = cell.vertices().map(LatLng::from).map(|c| Coord {
-        x: c.lng.deg(),
-        y: c.lat.deg(),
-    });
-    polygon!(vertices[0], vertices[1], vertices[2], vertices[3])
-}
+    fn run(args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+        run_captured(args.iter().map(|a| a.to_string()).collect())
+    }
 
-/**
- * Cuts a region using S2 cells. Each returned geometry in the collection will be a partition of
- * the geometry bounded to a passed in S2 cell.
- */
-fn cut_region(polygon: Polygon, s2_cells: &Vec<Cell>) -> Vec<Polygon> {
-    let partitions = s2_cells.iter().map(s2_cell_to_poly).collect_vec();
-    cut_polygon(&polygon, &partitions)
+    #[test]
+    fn test_parent() {
+        // The level 10 cell of San Francisco and its level 5 ancestor, as covered directly.
+        let cell = "9260950045757276160";
+        let point = "POINT(-122.4194 37.7749)";
+        assert_eq!(
+            run(&["s2", "cover", "-l", "10", "--", point]).unwrap(),
+            format!("{}\n", cell)
+        );
+        let ancestor = run(&["s2", "cover", "-l", "5", "--", point]).unwrap();
+        assert_eq!(ancestor, "9260526733780582400\n");
+        assert_eq!(
+            run(&["s2", "parent", "-l", "5", "--", cell]).unwrap(),
+            ancestor
+        );
+        // A cell is its own parent at its level.
+        assert_eq!(
+            run(&["s2", "parent", "-l", "10", "--", cell]).unwrap(),
+            format!("{}\n", cell)
+        );
+
+        // A level finer than a cell is an input error that points at the cell.
+        let cells = format!("{},{}", ancestor.trim(), cell);
+        let error = run(&["s2", "parent", "-l", "8", "--", &cells]).unwrap_err();
+        assert_eq!(exit_code(&*error), 3);
+        let message = error.to_string();
+        assert!(message.contains("`9260526733780582400`"), "{}", message);
+        assert!(
+            message.contains("the cell is at level 5, coarser than 8"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_cover_as_geometry() {
+        let region = "POLYGON((-122.5 37.7,-122.4 37.7,-122.4 37.8,-122.5 37.8,-122.5 37.7))";
+        let cells = run(&["s2", "cover", "-l", "10", "--", region]).unwrap();
+        assert_eq!(cells.lines().count(), 5);
+        let cells = cells.lines().collect::<Vec<_>>().join(",");
+
+        // The cells of a covering are drawn the same way as converting them one by one.
+        let polygons = run(&["s2", "cover", "-l", "10", "--as-geometry", "--", region]).unwrap();
+        assert_eq!(
+            polygons,
+            run(&["s2", "cell-to-poly", "--", &cells]).unwrap()
+        );
+        assert!(polygons.lines().all(|p| p.starts_with("POLYGON((")));
+    }
+
+    #[test]
+    fn test_bin_sorted() {
+        // Bins are in order of cell ID, which `--sorted` and `--unique` keep.
+        let points = "MULTIPOINT(10 10, 1 1, 10 10, -50 -50)";
+        let bins = "1170935903116328960,3\n13744986062734753792,1\n";
+        assert_eq!(run(&["s2", "bin", "-l", "3", "--", points]).unwrap(), bins);
+        assert_eq!(
+            run(&["s2", "bin", "-l", "3", "--sorted", "--unique", "--", points]).unwrap(),
+            bins
+        );
+    }
+
+    #[test]
+    fn test_cover_min_level_finer_than_max() {
+        let region = "POLYGON((-122.5 37.7,-122.4 37.7,-122.4 37.8,-122.5 37.8,-122.5 37.7))";
+        let error =
+            run(&["s2", "cover", "-l", "10", "--min-level", "11", "--", region]).unwrap_err();
+        assert_eq!(exit_code(&*error), 2);
+    }
 }
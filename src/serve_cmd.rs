@@ -0,0 +1,598 @@
+use std::error::Error;
+use std::io::Read;
+use std::panic::catch_unwind;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+
+use clap::{Args, ValueEnum};
+use geo_types::{Coord, Geometry, Point, Polygon};
+use geos_core::geom::{partition_region, triangulate};
+use geos_core::h3_cells::{cut_geometry, get_h3_covering, h3_cell_to_poly, h3_covering_cells};
+use geos_core::s2_cells::{cut_region, get_s2_covering, s2_cell_to_poly};
+use geos_core::samplers::{create_rng, GeoSampler, PolygonalSampler, UniformSampler};
+use h3o::geom::ContainmentMode;
+use h3o::{CellIndex, Resolution};
+use itertools::Itertools;
+use s2::cell::Cell;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::error::take_warnings;
+use crate::format::{
+    output_wkt, parse_s2_cell_from_file, parse_wkt_with_options, InputOptions, OutputOptions,
+};
+use crate::h3_cmd::{self, H3CellFormat, H3CoveringMode};
+use crate::s2_cmd::{self, S2CellFormat};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Serve GeoS operations as JSON endpoints over HTTP.")]
+pub struct ServeArgs {
+    #[arg(
+        short,
+        long,
+        default_value = "127.0.0.1:8080",
+        help = "The address to listen on."
+    )]
+    addr: String,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 4,
+        help = "The number of worker threads handling requests."
+    )]
+    threads: usize,
+
+    #[cfg(feature = "grpc")]
+    #[arg(
+        long,
+        conflicts_with = "threads",
+        help = "Serve the gRPC service defined in proto/geos.proto instead of JSON over HTTP."
+    )]
+    grpc: bool,
+}
+
+/// The most points that `/rand/point` samples, since its response is built in memory. The gRPC
+/// service streams larger samples.
+const MAX_NUM_SAMPLES: u64 = 100_000;
+
+/// The most cells that `/s2/cover`, `/s2/cut`, and `/h3/uncompact` return, for the same reason. S2
+/// coverings are coarsened to fit, as with `max_cells`.
+const MAX_NUM_CELLS: usize = 1_000_000;
+
+/// The largest request body that is read, so that a client can't exhaust the server's memory.
+const MAX_BODY_BYTES: u64 = 16 << 20;
+
+/**
+ * How request geometries are read and response geometries written, as set by the global flags.
+ * They're passed to the handlers, since requests are handled on worker threads of their own.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ServeOptions {
+    pub input: InputOptions,
+    pub output: OutputOptions,
+}
+
+//==================================================
+// Request bodies.
+//==================================================
+#[derive(Deserialize)]
+struct S2CoverRequest {
+    wkt: String,
+    #[serde(default = "default_s2_level")]
+    level: u8,
+    max_cells: Option<usize>,
+    cell_format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct S2CutRequest {
+    wkt: String,
+    #[serde(default = "default_s2_level")]
+    level: u8,
+    max_cells: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct H3CoverRequest {
+    wkt: String,
+    #[serde(default = "default_h3_level")]
+    level: u8,
+    mode: Option<String>,
+    cell_format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct H3CutRequest {
+    wkt: String,
+    #[serde(default = "default_h3_cut_level")]
+    level: u8,
+}
+
+#[derive(Deserialize)]
+struct H3CompactRequest {
+    cells: Vec<String>,
+    level: Option<u8>,
+    cell_format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CellRequest {
+    cell: String,
+}
+
+#[derive(Deserialize)]
+struct SplitRequest {
+    wkt: String,
+    edge_proportion: f64,
+    threshold: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct WktRequest {
+    wkt: String,
+}
+
+#[derive(Deserialize)]
+struct RandPointRequest {
+    wkt: Option<String>,
+    #[serde(default = "default_num_samples")]
+    num_samples: u64,
+    #[serde(default)]
+    seed: u64,
+}
+
+// Defaults mirror the corresponding CLI arguments.
+fn default_s2_level() -> u8 {
+    12
+}
+fn default_h3_level() -> u8 {
+    12
+}
+fn default_h3_cut_level() -> u8 {
+    6
+}
+fn default_num_samples() -> u64 {
+    1
+}
+
+//==================================================
+// Server logic.
+//==================================================
+pub fn handle_serve_command(
+    serve: &ServeArgs,
+    options: ServeOptions,
+) -> Result<(), Box<dyn Error>> {
+    #[cfg(feature = "grpc")]
+    if serve.grpc {
+        return crate::grpc::serve_grpc(&serve.addr, options);
+    }
+
+    let server = Arc::new(Server::http(&serve.addr).map_err(|e| e.to_string())?);
+    eprintln!("Listening on http://{}", serve.addr);
+
+    let workers = (0..usize::max(1, serve.threads))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let options = options.clone();
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle_request(request, &options);
+                }
+            })
+        })
+        .collect_vec();
+
+    for worker in workers {
+        worker.join().map_err(|_| "server worker panicked")?;
+    }
+    Ok(())
+}
+
+fn handle_request(mut request: Request, options: &ServeOptions) {
+    let (status, value) = match read_body(request.as_reader()) {
+        Ok(body) => respond(request.method(), request.url(), &body, options),
+        Err(response) => response,
+    };
+
+    let header = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    let response = Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    if let Err(e) = request.respond(response) {
+        eprintln!("Failed to send response: {}", e);
+    }
+}
+
+/**
+ * Reads a request body of at most `MAX_BODY_BYTES`, or gives the error response for it.
+ */
+fn read_body(reader: &mut dyn Read) -> Result<String, (u16, Value)> {
+    let mut body = String::new();
+    // One byte past the limit is read to tell bodies at the limit from those over it.
+    match reader.take(MAX_BODY_BYTES + 1).read_to_string(&mut body) {
+        Ok(n) if n as u64 > MAX_BODY_BYTES => Err((
+            413,
+            json!({ "error": format!("request bodies must be at most {} bytes", MAX_BODY_BYTES) }),
+        )),
+        Ok(_) => Ok(body),
+        Err(e) => Err((400, json!({ "error": e.to_string() }))),
+    }
+}
+
+/**
+ * The status and JSON body of the response to a request. A panic in an endpoint is answered with a
+ * 500 rather than taking the worker thread down with it.
+ */
+fn respond(method: &Method, url: &str, body: &str, options: &ServeOptions) -> (u16, Value) {
+    let response = catch_unwind(|| route(method, url, body, options));
+    // Warnings were already printed as the request was read; a worker thread has no command run to
+    // report them to afterward.
+    take_warnings();
+    match response {
+        Ok(Ok(Some(value))) => (200, value),
+        Ok(Ok(None)) => (404, json!({ "error": format!("no route for {}", url) })),
+        Ok(Err(e)) => (400, json!({ "error": e.to_string() })),
+        Err(_) => (500, json!({ "error": "the request panicked" })),
+    }
+}
+
+/**
+ * Dispatches a request body to the endpoint for the given path. Returns `None` if no endpoint
+ * matches.
+ */
+fn route(
+    method: &Method,
+    url: &str,
+    body: &str,
+    options: &ServeOptions,
+) -> Result<Option<Value>, Box<dyn Error>> {
+    if *method != Method::Post {
+        return Ok(None);
+    }
+
+    let value = match url {
+        "/s2/cover" => s2_cover(serde_json::from_str(body)?, options)?,
+        "/s2/cut" => s2_cut(serde_json::from_str(body)?, options)?,
+        "/s2/cell-to-poly" => s2_cell_to_poly_endpoint(serde_json::from_str(body)?, options)?,
+        "/h3/cover" => h3_cover(serde_json::from_str(body)?, options)?,
+        "/h3/cut" => h3_cut(serde_json::from_str(body)?, options)?,
+        "/h3/cell-to-poly" => h3_cell_to_poly_endpoint(serde_json::from_str(body)?, options)?,
+        "/h3/compact" => h3_compact(serde_json::from_str(body)?)?,
+        "/h3/uncompact" => h3_uncompact(serde_json::from_str(body)?)?,
+        "/geom/split" => geom_split(serde_json::from_str(body)?, options)?,
+        "/geom/triangulate" => geom_triangulate(serde_json::from_str(body)?, options)?,
+        "/rand/point" => rand_point(serde_json::from_str(body)?, options)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+//==================================================
+// Endpoints.
+//==================================================
+fn s2_cover(req: S2CoverRequest, options: &ServeOptions) -> Result<Value, Box<dyn Error>> {
+    let format = parse_value_enum(req.cell_format.as_deref(), S2CellFormat::Long)?;
+    let geometry = parse_wkt_with_options(&req.wkt, &options.input)?;
+    let cover = get_s2_covering(&geometry, req.level, max_num_cells(req.max_cells)?)?;
+    let cells = cover
+        .iter()
+        .map(|c| s2_cmd::fmt_cell(&format, c))
+        .collect_vec();
+    Ok(json!({ "cells": cells }))
+}
+
+fn s2_cut(req: S2CutRequest, options: &ServeOptions) -> Result<Value, Box<dyn Error>> {
+    let geometry = parse_wkt_with_options(&req.wkt, &options.input)?;
+    let cover = get_s2_covering(&geometry, req.level, max_num_cells(req.max_cells)?)?
+        .into_iter()
+        .map(Cell::from)
+        .collect_vec();
+    let cuts = cut_region(
+        &geometry.try_into()?,
+        &cover,
+        options.input.normalize_winding,
+    )?;
+    Ok(json!({ "geometries": wkt_strings(cuts, &options.output) }))
+}
+
+fn s2_cell_to_poly_endpoint(
+    req: CellRequest,
+    options: &ServeOptions,
+) -> Result<Value, Box<dyn Error>> {
+    let cell_id = parse_s2_cell_from_file(&req.cell)?;
+    let poly = s2_cell_to_poly(&cell_id.into());
+    Ok(json!({ "geometry": output_wkt(poly, &options.output) }))
+}
+
+fn h3_cover(req: H3CoverRequest, options: &ServeOptions) -> Result<Value, Box<dyn Error>> {
+    let format = parse_value_enum(req.cell_format.as_deref(), H3CellFormat::Hex)?;
+    let mode: ContainmentMode = match req.mode {
+        Some(mode) => H3CoveringMode::from(mode.as_str()).into(),
+        None => ContainmentMode::IntersectsBoundary,
+    };
+    let resolution = Resolution::try_from(req.level)?;
+    let geometry = parse_wkt_with_options(&req.wkt, &options.input)?;
+    let cells = h3_covering_cells(&geometry, resolution, mode, options.input.normalize_winding)?
+        .map(|c| h3_cmd::fmt_cell(&format, &c))
+        .collect_vec();
+    Ok(json!({ "cells": cells }))
+}
+
+fn h3_cut(req: H3CutRequest, options: &ServeOptions) -> Result<Value, Box<dyn Error>> {
+    let resolution = Resolution::try_from(req.level)?;
+    let geometry = parse_wkt_with_options(&req.wkt, &options.input)?;
+    let cover = get_h3_covering(
+        &geometry,
+        resolution,
+        ContainmentMode::IntersectsBoundary,
+        options.input.normalize_winding,
+    )?;
+    let cuts = cut_geometry(&geometry, &cover, options.input.normalize_winding)?;
+    Ok(json!({ "geometries": wkt_strings(cuts, &options.output) }))
+}
+
+fn h3_cell_to_poly_endpoint(
+    req: CellRequest,
+    options: &ServeOptions,
+) -> Result<Value, Box<dyn Error>> {
+    let cell = CellIndex::from_str(&req.cell)?;
+    let poly = h3_cell_to_poly(&cell);
+    Ok(json!({ "geometry": output_wkt(poly, &options.output) }))
+}
+
+fn h3_compact(req: H3CompactRequest) -> Result<Value, Box<dyn Error>> {
+    let format = parse_value_enum(req.cell_format.as_deref(), H3CellFormat::Hex)?;
+    let cells: Vec<CellIndex> = req
+        .cells
+        .iter()
+        .map(|s| CellIndex::from_str(s))
+        .try_collect()?;
+    // Sorted as `geos h3 compact` does, since the order of compacted cells is up to h3o.
+    let cells = CellIndex::compact(cells)?
+        .sorted_unstable()
+        .map(|c| h3_cmd::fmt_cell(&format, &c))
+        .collect_vec();
+    Ok(json!({ "cells": cells }))
+}
+
+fn h3_uncompact(req: H3CompactRequest) -> Result<Value, Box<dyn Error>> {
+    let format = parse_value_enum(req.cell_format.as_deref(), H3CellFormat::Hex)?;
+    let resolution = Resolution::try_from(req.level.ok_or("missing field `level`")?)?;
+    let cells: Vec<CellIndex> = req
+        .cells
+        .iter()
+        .map(|s| CellIndex::from_str(s))
+        .try_collect()?;
+    if CellIndex::uncompact_size(cells.iter().copied(), resolution) > MAX_NUM_CELLS as u64 {
+        return Err(format!("uncompacted cells must number at most {}", MAX_NUM_CELLS).into());
+    }
+    let cells = CellIndex::uncompact(cells, resolution)
+        .map(|c| h3_cmd::fmt_cell(&format, &c))
+        .collect_vec();
+    Ok(json!({ "cells": cells }))
+}
+
+fn geom_split(req: SplitRequest, options: &ServeOptions) -> Result<Value, Box<dyn Error>> {
+    let polygon: Polygon = parse_wkt_with_options(&req.wkt, &options.input)?.try_into()?;
+    let partitions = partition_region(
+        &polygon,
+        req.edge_proportion,
+        req.threshold,
+        options.input.normalize_winding,
+    )?;
+    Ok(json!({ "geometries": wkt_strings(partitions, &options.output) }))
+}
+
+fn geom_triangulate(req: WktRequest, options: &ServeOptions) -> Result<Value, Box<dyn Error>> {
+    let polygon: Polygon = parse_wkt_with_options(&req.wkt, &options.input)?.try_into()?;
+    let triangles = triangulate(&polygon)?;
+    Ok(json!({ "geometries": wkt_strings(triangles, &options.output) }))
+}
+
+fn rand_point(req: RandPointRequest, options: &ServeOptions) -> Result<Value, Box<dyn Error>> {
+    if req.num_samples > MAX_NUM_SAMPLES {
+        return Err(format!("num_samples must be at most {}", MAX_NUM_SAMPLES).into());
+    }
+    let mut rng = create_rng(req.seed);
+    let coords: Vec<Coord> = match req.wkt {
+        None => (0..req.num_samples)
+            .map(|_| UniformSampler.sample_coord(&mut rng))
+            .collect(),
+        Some(wkt) => {
+            let sampler =
+                PolygonalSampler::new(parse_wkt_with_options(&wkt, &options.input)?.try_into()?)?;
+            (0..req.num_samples)
+                .map(|_| sampler.sample_coord(&mut rng))
+                .collect()
+        }
+    };
+    let points = coords.into_iter().map(Point::from);
+    Ok(json!({ "geometries": wkt_strings(points, &options.output) }))
+}
+
+//==================================================
+// Utils.
+//==================================================
+/**
+ * The `max_cells` of an S2 request, which defaults to and must not exceed `MAX_NUM_CELLS`.
+ */
+fn max_num_cells(max_cells: Option<usize>) -> Result<usize, Box<dyn Error>> {
+    match max_cells {
+        Some(n) if n > MAX_NUM_CELLS => {
+            Err(format!("max_cells must be at most {}", MAX_NUM_CELLS).into())
+        }
+        Some(n) => Ok(n),
+        None => Ok(MAX_NUM_CELLS),
+    }
+}
+
+fn wkt_strings<G: Into<Geometry>>(
+    geometries: impl IntoIterator<Item = G>,
+    options: &OutputOptions,
+) -> Vec<String> {
+    geometries
+        .into_iter()
+        .map(|g| output_wkt(g, options))
+        .collect_vec()
+}
+
+pub fn parse_value_enum<T: ValueEnum>(
+    value: Option<&str>,
+    default: T,
+) -> Result<T, Box<dyn Error>> {
+    match value {
+        Some(value) => Ok(T::from_str(value, true)?),
+        None => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use tiny_http::Method;
+
+    use std::io::Cursor;
+
+    use super::{read_body, respond, ServeOptions, MAX_BODY_BYTES, MAX_NUM_CELLS};
+    use crate::format::{InputOptions, OutputOptions};
+
+    fn post(url: &str, body: Value) -> (u16, Value) {
+        post_with_options(url, body, &ServeOptions::default())
+    }
+
+    fn post_with_options(url: &str, body: Value, options: &ServeOptions) -> (u16, Value) {
+        respond(&Method::Post, url, &body.to_string(), options)
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let point = "POINT(-122.4194 37.7749)";
+        assert_eq!(
+            post("/s2/cover", json!({ "wkt": point, "level": 10 })),
+            (200, json!({ "cells": ["9260950045757276160"] }))
+        );
+        let (status, value) = post("/s2/cell-to-poly", json!({ "cell": "9260950045757276160" }));
+        assert_eq!(status, 200);
+        assert!(value["geometry"].as_str().unwrap().starts_with("POLYGON(("));
+        assert_eq!(
+            post("/h3/cover", json!({ "wkt": point, "level": 5 })),
+            (200, json!({ "cells": ["85283083fffffff"] }))
+        );
+        let (status, value) = post(
+            "/geom/triangulate",
+            json!({ "wkt": "POLYGON((0 0,1 0,1 1,0 0))" }),
+        );
+        assert_eq!(
+            (status, value),
+            (200, json!({ "geometries": ["POLYGON((1 0,0 0,1 1,1 0))"] }))
+        );
+        let get = respond(&Method::Get, "/s2/cover", "", &ServeOptions::default());
+        assert_eq!(get.0, 404);
+        assert_eq!(post("/s2/uncover", json!({})).0, 404);
+    }
+
+    #[test]
+    fn test_h3_compact_order() {
+        let uncompact = |cell: &str| {
+            let (_, value) = post("/h3/uncompact", json!({ "cells": [cell], "level": 6 }));
+            value["cells"].as_array().unwrap().clone()
+        };
+        // A lone cell listed before the children of another, which compact to their parent.
+        let other = uncompact("85283473fffffff")[0].clone();
+        let mut cells = vec![other.clone()];
+        cells.extend(uncompact("85283083fffffff").into_iter().rev());
+        assert_eq!(
+            post("/h3/compact", json!({ "cells": cells })),
+            (200, json!({ "cells": ["85283083fffffff", other] }))
+        );
+    }
+
+    #[test]
+    fn test_request_limits() {
+        // Requests that would never finish or build unbounded responses are rejected.
+        let square = "POLYGON((0 0,1 0,1 1,0 1,0 0))";
+        for edge_proportion in [0.0, -1.0, 1e-9] {
+            let body = json!({ "wkt": square, "edge_proportion": edge_proportion });
+            assert_eq!(post("/geom/split", body).0, 400, "{}", edge_proportion);
+        }
+        let body = json!({ "cells": ["8001fffffffffff"], "level": 15 });
+        assert_eq!(post("/h3/uncompact", body).0, 400);
+        let body = json!({ "wkt": square, "level": 10, "max_cells": MAX_NUM_CELLS + 1 });
+        assert_eq!(post("/s2/cover", body).0, 400);
+
+        assert_eq!(read_body(&mut Cursor::new("{}")).unwrap(), "{}");
+        let body = vec![b' '; MAX_BODY_BYTES as usize + 1];
+        assert_eq!(read_body(&mut Cursor::new(body)).unwrap_err().0, 413);
+    }
+
+    #[test]
+    fn test_endpoint_errors() {
+        let bad_requests = [
+            ("/s2/cover", json!({ "wkt": "POINT(1 1)", "level": 31 })),
+            (
+                "/s2/cut",
+                json!({ "wkt": "POLYGON((0 0,1 0,1 1,0 0))", "level": 31 }),
+            ),
+            ("/s2/cell-to-poly", json!({ "cell": "0" })),
+            ("/s2/cell-to-poly", json!({ "cell": "nope" })),
+            ("/h3/cover", json!({ "wkt": "POINT(1 1)", "level": 16 })),
+            (
+                "/geom/split",
+                json!({ "wkt": "POLYGON EMPTY", "edge_proportion": 0.1 }),
+            ),
+            ("/geom/triangulate", json!({ "wkt": "POLYGON EMPTY" })),
+            ("/geom/triangulate", json!({ "wkt": "POLYGON((0 0" })),
+            ("/s2/cover", json!({ "level": 10 })),
+            ("/rand/point", json!({ "wkt": "POLYGON EMPTY" })),
+            (
+                "/rand/point",
+                json!({ "wkt": "POLYGON((0 0,1 1,2 2,0 0))" }),
+            ),
+            ("/rand/point", json!({ "num_samples": u64::MAX })),
+            // Request WKT is never read as a file.
+            ("/s2/cover", json!({ "wkt": "@Cargo.toml" })),
+        ];
+        for (url, body) in bad_requests {
+            let (status, value) = post(url, body.clone());
+            assert_eq!(status, 400, "{} {}", url, body);
+            assert!(value["error"].is_string());
+        }
+    }
+
+    #[test]
+    fn test_endpoint_options() {
+        let options = ServeOptions {
+            input: InputOptions {
+                crs: "EPSG:3857".parse().unwrap(),
+                ..InputOptions::default()
+            },
+            output: OutputOptions {
+                precision: Some(1),
+                ..OutputOptions::default()
+            },
+        };
+        // POINT(-122.4194 37.7749) in Web Mercator.
+        let point = json!({ "wkt": "POINT(-13627665.27 4547675.35)", "level": 10 });
+        assert_eq!(
+            post_with_options("/s2/cover", point, &options),
+            (200, json!({ "cells": ["9260950045757276160"] }))
+        );
+        let (status, value) = post_with_options(
+            "/h3/cell-to-poly",
+            json!({ "cell": "85283083fffffff" }),
+            &options,
+        );
+        assert_eq!(status, 200);
+        let wkt = value["geometry"].as_str().unwrap();
+        let coords = wkt.trim_start_matches("POLYGON((").trim_end_matches("))");
+        for c in coords.split([',', ' ']) {
+            assert!(c.split('.').nth(1).is_none_or(|d| d.len() <= 1), "{}", wkt);
+        }
+    }
+}
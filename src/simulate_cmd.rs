@@ -0,0 +1,197 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use clap::{Args, ValueEnum};
+use geo_types::{Geometry, Point};
+use geos_core::geom::collect_lines;
+use geos_core::samplers::{create_rng, GeoSampler, PolygonalSampler, UniformSampler};
+use geos_core::simulate::{Route, Simulation};
+use itertools::Itertools;
+use rand::distributions::{Distribution, Uniform};
+use serde_json::json;
+use wkt::TryFromWkt;
+
+use crate::format::{fmt_value_enum, parse_geometry, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(
+    about = "Simulate agents moving along routes or random walks, emitting their positions at each tick."
+)]
+pub struct SimulateArgs {
+    #[arg(
+        last = true,
+        help = "WKT LINESTRING, MULTILINESTRING, or GEOMETRYCOLLECTION strings containing the routes to travel. Agents are assigned to routes round-robin. If omitted, agents random walk instead."
+    )]
+    wkt: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "The number of agents. Defaults to one agent per route, or a single random walker."
+    )]
+    num_agents: Option<usize>,
+
+    #[arg(
+        long,
+        default_value_t = 1.4,
+        help = "The speed of each agent in meters per second."
+    )]
+    speed_mps: f64,
+
+    #[arg(
+        long,
+        help = "If set, each agent's speed is sampled uniformly between --speed-mps and this speed."
+    )]
+    max_speed_mps: Option<f64>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 1.0,
+        help = "The simulated time between emitted positions, in seconds."
+    )]
+    tick_s: f64,
+
+    #[arg(
+        short,
+        long,
+        help = "Stop after this many simulated seconds. By default, the simulation stops once all agents reach the end of their routes. Required for random walks unless --real-time is set."
+    )]
+    duration_s: Option<f64>,
+
+    #[arg(
+        short,
+        long,
+        help = "A WKT POLYGON in which random walkers start. By default, they start anywhere on the globe."
+    )]
+    within: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 0,
+        help = "Random seed to use for speeds and random walks."
+    )]
+    seed: u64,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Wait for the tick interval between emitting positions, so that positions are emitted in real time."
+    )]
+    real_time: bool,
+
+    #[arg(short, long, default_value_t = SimulationFormat::Ndjson, help = "The output format for positions. Each position is written on a separate line.")]
+    format: SimulationFormat,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum SimulationFormat {
+    /// `{"t": <seconds>, "agent": <index>, "lng": <lng>, "lat": <lat>}`
+    Ndjson,
+    /// A GeoJSON point feature with `agent` and `t` properties.
+    Geojson,
+}
+impl Display for SimulationFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_simulate_command(
+    simulate: &SimulateArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    if simulate.tick_s <= 0.0 {
+        return Err("--tick-s must be positive".into());
+    }
+
+    let mut rng = create_rng(simulate.seed);
+    let routes: Vec<Route> = simulate
+        .wkt
+        .iter()
+        .map(|s| collect_lines(&parse_geometry(s, ctx)?))
+        .flatten_ok()
+        .map_ok(Route::new)
+        .try_collect()?;
+
+    let num_agents = simulate.num_agents.unwrap_or(usize::max(1, routes.len()));
+    let speeds_mps = match simulate.max_speed_mps {
+        Some(max_speed_mps) if max_speed_mps > simulate.speed_mps => {
+            let dist = Uniform::new_inclusive(simulate.speed_mps, max_speed_mps);
+            (0..num_agents).map(|_| dist.sample(&mut rng)).collect_vec()
+        }
+        _ => vec![simulate.speed_mps; num_agents],
+    };
+
+    let mut simulation = if routes.is_empty() {
+        if simulate.duration_s.is_none() && !simulate.real_time {
+            return Err(
+                "--duration-s is required for random walks unless --real-time is set".into(),
+            );
+        }
+        let starts: Vec<Point> = match &simulate.within {
+            None => (0..num_agents)
+                .map(|_| UniformSampler.sample_coord(&mut rng).into())
+                .collect(),
+            Some(wkt) => {
+                let sampler = PolygonalSampler::new(Geometry::try_from_wkt_str(wkt)?.try_into()?)?;
+                (0..num_agents)
+                    .map(|_| sampler.sample_coord(&mut rng).into())
+                    .collect()
+            }
+        };
+        Simulation::random_walks(&mut rng, starts, &speeds_mps, simulate.tick_s)
+    } else {
+        Simulation::on_routes(routes, &speeds_mps, simulate.tick_s)
+    };
+
+    emit(out, &simulate.format, 0.0, &simulation.positions())?;
+    loop {
+        let next_s = simulation.elapsed_s() + simulate.tick_s;
+        let past_duration = simulate.duration_s.is_some_and(|d| next_s > d + 1e-9);
+        if past_duration || simulation.is_finished() {
+            break;
+        }
+        if simulate.real_time {
+            thread::sleep(Duration::from_secs_f64(simulate.tick_s));
+        }
+        let positions = simulation.step(&mut rng);
+        emit(out, &simulate.format, simulation.elapsed_s(), &positions)?;
+    }
+    Ok(())
+}
+
+fn emit(
+    out: &mut dyn Write,
+    format: &SimulationFormat,
+    t: f64,
+    positions: &[(usize, Point)],
+) -> Result<(), Box<dyn Error>> {
+    for (agent, p) in positions {
+        let value = match format {
+            SimulationFormat::Ndjson => {
+                json!({ "t": t, "agent": agent, "lng": p.x(), "lat": p.y() })
+            }
+            SimulationFormat::Geojson => json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [p.x(), p.y()] },
+                "properties": { "agent": agent, "t": t },
+            }),
+        };
+        writeln!(out, "{}", value)?;
+    }
+    // Positions are flushed at every tick so that consumers can follow along in real time.
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::fmt::{Display, Formatter};
+
+use clap::ValueEnum;
+
+use crate::format::fmt_value_enum;
+
+/**
+ * How piped stdin is added to the commandline arguments.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StdinMode {
+    /// Each line is an argument as if it were typed, so that flags can be piped too.
+    Args,
+    /// All of stdin is a single argument, e.g. pretty-printed WKT spanning several lines.
+    Geometry,
+    /// Each non-blank line is a data argument, e.g. a geometry or a cell, and never a flag.
+    Lines,
+}
+impl Display for StdinMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_value_enum(self, f)
+    }
+}
+
+/**
+ * The `--stdin-mode` given in `args`. Stdin is read before the arguments are parsed, so this has to
+ * be found by hand. Invalid modes are left for the parser to report. CSV input is read whole by
+ * default, since its header row belongs with every other line.
+ */
+fn stdin_mode(args: &[String]) -> StdinMode {
+    let default = match flag_value(args, "--input-format") {
+        Some(format) if format.eq_ignore_ascii_case("csv") => StdinMode::Geometry,
+        _ => StdinMode::Lines,
+    };
+    flag_value(args, "--stdin-mode")
+        .and_then(|mode| StdinMode::from_str(mode, true).ok())
+        .unwrap_or(default)
+}
+
+/**
+ * The last value given for a flag in `args`, as either `--flag value` or `--flag=value`.
+ */
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let mut args = args.iter().take_while(|a| *a != "--");
+    let mut value = None;
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            value = args.next().map(String::as_str);
+        } else if let Some(v) = arg.strip_prefix(flag).and_then(|v| v.strip_prefix('=')) {
+            value = Some(v);
+        }
+    }
+    value
+}
+
+/**
+ * Adds piped stdin to the commandline arguments according to `--stdin-mode`. Data is added after a
+ * `--`, so that lines starting with `-` are never taken as flags.
+ */
+pub fn append_stdin(args: &mut Vec<String>, stdin: &str) {
+    let mode = stdin_mode(args);
+    let data = match mode {
+        StdinMode::Args => stdin.lines().map(String::from).collect(),
+        StdinMode::Lines => stdin
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(String::from)
+            .collect(),
+        StdinMode::Geometry if stdin.trim().is_empty() => vec![],
+        StdinMode::Geometry => vec![stdin.trim().to_string()],
+    };
+    if mode != StdinMode::Args && !data.is_empty() && !args.iter().any(|a| a == "--") {
+        args.push("--".to_string());
+    }
+    args.extend(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::append_stdin;
+
+    #[test]
+    fn test_append_stdin() {
+        let append = |args: &[&str], stdin: &str| {
+            let mut args = args.iter().map(|a| a.to_string()).collect();
+            append_stdin(&mut args, stdin);
+            args
+        };
+
+        let cells = "8a2a1072b59ffff\n\n8a2a1072b5b7fff\n";
+        assert_eq!(
+            append(&["geos", "h3", "cell-to-poly"], cells),
+            [
+                "geos",
+                "h3",
+                "cell-to-poly",
+                "--",
+                "8a2a1072b59ffff",
+                "8a2a1072b5b7fff"
+            ]
+        );
+        assert_eq!(
+            append(&["geos", "h3", "cell-to-poly", "--"], cells),
+            [
+                "geos",
+                "h3",
+                "cell-to-poly",
+                "--",
+                "8a2a1072b59ffff",
+                "8a2a1072b5b7fff"
+            ]
+        );
+
+        let wkt = "POLYGON((0 0,\n  1 0,\n  1 1,0 0))\n";
+        assert_eq!(
+            append(&["geos", "--stdin-mode", "geometry", "s2", "cover"], wkt),
+            [
+                "geos",
+                "--stdin-mode",
+                "geometry",
+                "s2",
+                "cover",
+                "--",
+                wkt.trim()
+            ]
+        );
+        assert_eq!(
+            append(&["geos", "s2", "cover", "--stdin-mode=args"], "-l\n3\n"),
+            ["geos", "s2", "cover", "--stdin-mode=args", "-l", "3"]
+        );
+        assert_eq!(
+            append(&["geos", "s2", "cover"], "\n"),
+            ["geos", "s2", "cover"]
+        );
+        assert_eq!(
+            append(
+                &["geos", "--input-format=csv", "h3", "cover"],
+                "lat,lng\n1,2\n"
+            ),
+            [
+                "geos",
+                "--input-format=csv",
+                "h3",
+                "cover",
+                "--",
+                "lat,lng\n1,2"
+            ]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::io::Write;
+
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use clap::Args;
+use geo_types::Point;
+use geos_core::sun::{solar_position, sun_times};
+use serde_json::json;
+
+use crate::format::{parse_geometry, RunContext};
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(
+    about = "Compute the sun's position and the sunrise, sunset, and civil twilight times at a point, as a JSON object."
+)]
+#[command(arg_required_else_help = true)]
+pub struct SunArgs {
+    #[arg(short, long, help = "A WKT POINT to compute the sun's position from.")]
+    at: String,
+
+    #[arg(
+        short,
+        long,
+        help = "An ISO 8601 / RFC 3339 time, e.g. 2024-06-21T12:00:00-06:00. Output times use the same UTC offset. Defaults to now, in UTC."
+    )]
+    time: Option<String>,
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_sun_command(
+    sun: &SunArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let point: Point = parse_geometry(&sun.at, ctx)?.try_into()?;
+    let time: DateTime<FixedOffset> = match &sun.time {
+        Some(time) => DateTime::parse_from_rfc3339(time)
+            .map_err(|e| format!("invalid time `{}`: {}", time, e))?,
+        None => Utc::now().fixed_offset(),
+    };
+    let offset = time.offset();
+    let utc = time.with_timezone(&Utc);
+
+    let position = solar_position(&point, &utc);
+    let times = sun_times(&point, &utc);
+    // Events that do not happen during polar day or night are null.
+    let fmt_time = |t: Option<DateTime<Utc>>| {
+        t.map(|t| {
+            t.with_timezone(offset)
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        })
+    };
+
+    let value = json!({
+        "azimuth_deg": position.azimuth_deg,
+        "elevation_deg": position.elevation_deg,
+        "civil_dawn": fmt_time(times.civil_dawn),
+        "sunrise": fmt_time(times.sunrise),
+        "solar_noon": fmt_time(Some(times.solar_noon)),
+        "sunset": fmt_time(times.sunset),
+        "civil_dusk": fmt_time(times.civil_dusk),
+    });
+    writeln!(out, "{}", value)?;
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ArgMatches;
+use itertools::Itertools;
+
+use crate::{args_to_parse, clamps_levels, configured_command};
+
+/**
+ * A template for the path that the output for each input of `--input-dir` or `--per-feature` is
+ * written to. `{id}` and the other placeholders naming the input are replaced per input, `{command}`
+ * by the command's path such as `h3-cover`, and any other placeholder by the value of the argument
+ * of that name, such as `{level}` for `-l 5`, with `-` and `_` interchangeable.
+ */
+#[derive(Debug)]
+pub struct OutputTemplate {
+    template: String,
+    /// The values of the command and its arguments, by placeholder name.
+    values: HashMap<String, String>,
+}
+
+impl OutputTemplate {
+    /**
+     * Checks a template against the full commandline arguments. `inputs` are the names of the
+     * placeholders replaced per input, one of which the template must contain, or every input's
+     * output would be written to the same path.
+     */
+    pub fn new(template: &str, args: &[String], inputs: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let command = configured_command(clamps_levels(args))?;
+        let matches = command.try_get_matches_from(args_to_parse(args))?;
+        let mut values = HashMap::new();
+        let mut commands = vec![];
+        let mut matches = &matches;
+        loop {
+            insert_argument_values(matches, &mut values);
+            match matches.subcommand() {
+                Some((name, subcommand)) => {
+                    commands.push(name.to_string());
+                    matches = subcommand;
+                }
+                None => break,
+            }
+        }
+        values.insert("command".to_string(), commands.join("-"));
+
+        let names = placeholders(template)?;
+        if !names.iter().any(|name| inputs.contains(&name.as_str())) {
+            let inputs = inputs
+                .iter()
+                .map(|name| format!("{{{}}}", name))
+                .join(" or ");
+            return Err(format!(
+                "--output-template must contain {}, or every output would be written to the same path",
+                inputs
+            )
+            .into());
+        }
+        for name in names {
+            if !inputs.contains(&name.as_str()) && !values.contains_key(&key(&name)) {
+                return Err(format!(
+                    "--output-template has {{{}}}, which is neither an input placeholder nor an argument of the command",
+                    name
+                )
+                .into());
+            }
+        }
+        Ok(Self {
+            template: template.to_string(),
+            values,
+        })
+    }
+
+    /**
+     * The output path for an input, given the values of the input placeholders.
+     */
+    pub fn render(&self, inputs: &[(&str, &str)]) -> PathBuf {
+        let mut path = String::new();
+        let mut rest = self.template.as_str();
+        while let Some((before, after)) = rest.split_once('{') {
+            path.push_str(before);
+            let Some((name, after)) = after.split_once('}') else {
+                break;
+            };
+            let value = inputs
+                .iter()
+                .find(|(input, _)| *input == name)
+                .map(|(_, value)| *value)
+                .or_else(|| self.values.get(&key(name)).map(String::as_str))
+                .unwrap_or_default();
+            path.push_str(&sanitize(value));
+            rest = after;
+        }
+        path.push_str(rest);
+        PathBuf::from(path)
+    }
+}
+
+/**
+ * Writes an output to a path, creating its missing directories.
+ */
+pub fn write_output(path: &Path, output: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, output).map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+    tracing::info!("wrote {}", path.display());
+    Ok(())
+}
+
+/**
+ * Adds the values of the arguments given to a command, or defaulted, by their names. Arguments
+ * with several values are joined by commas.
+ */
+fn insert_argument_values(matches: &ArgMatches, values: &mut HashMap<String, String>) {
+    for id in matches.ids() {
+        let Ok(Some(raw)) = matches.try_get_raw(id.as_str()) else {
+            continue;
+        };
+        let value = raw.map(|value| value.to_string_lossy()).join(",");
+        values.insert(key(id.as_str()), value);
+    }
+}
+
+/**
+ * The names of the `{name}` placeholders in a template.
+ */
+fn placeholders(template: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut names = vec![];
+    let mut rest = template;
+    while let Some((_, after)) = rest.split_once('{') {
+        let (name, after) = after
+            .split_once('}')
+            .ok_or("--output-template has an unclosed {")?;
+        names.push(name.to_string());
+        rest = after;
+    }
+    Ok(names)
+}
+
+fn key(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/**
+ * Keeps values such as WKT from adding directories to the path.
+ */
+fn sanitize(value: &str) -> String {
+    value.replace(['/', '\\'], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::OutputTemplate;
+
+    #[test]
+    fn test_output_template() {
+        let args = |args: &str| args.split(' ').map(str::to_string).collect::<Vec<_>>();
+        let template = OutputTemplate::new(
+            "out/{command}/{id}_{level}_{max-num-s2-cells}.txt",
+            &args("geos --per-feature ndjson s2 cover -l 12 --max-num-s2-cells 8 -- @in.geojson"),
+            &["id"],
+        )
+        .unwrap();
+        assert_eq!(
+            template.render(&[("id", "sf")]),
+            Path::new("out/s2-cover/sf_12_8.txt")
+        );
+        // Defaulted arguments have values too.
+        let batch = args("geos --input-dir in h3 cover -l 5");
+        let template = OutputTemplate::new("{stem}.{format}", &batch, &["stem"]).unwrap();
+        assert_eq!(template.render(&[("stem", "a/b")]), Path::new("a_b.csv"));
+
+        let inputs = &["id", "stem"];
+        assert!(OutputTemplate::new("out.txt", &batch, inputs).is_err());
+        assert!(OutputTemplate::new("{id}_{nope}", &batch, inputs).is_err());
+        assert!(OutputTemplate::new("{id", &batch, inputs).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/**
+ * The stages of a command that are timed separately by `--timing`. Everything else a command does
+ * is reported as compute time.
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum Stage {
+    /// Reading geometries and cells from arguments and files.
+    Parse,
+    /// Writing results. Results produced lazily as they are written are included here.
+    Format,
+}
+
+// Stages are timed per thread, so that commands run concurrently by the daemon don't mix timings.
+thread_local! {
+    static PARSE: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+    static FORMAT: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+fn stage_cell(stage: Stage) -> &'static std::thread::LocalKey<Cell<Duration>> {
+    match stage {
+        Stage::Parse => &PARSE,
+        Stage::Format => &FORMAT,
+    }
+}
+
+/**
+ * Runs `f`, adding its duration to the given stage.
+ */
+pub fn timed<T>(stage: Stage, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    tracing::trace!("{:?} took {:.3}ms", stage, elapsed.as_secs_f64() * 1000.0);
+    stage_cell(stage).with(|d| d.set(d.get() + elapsed));
+    result
+}
+
+/**
+ * Times a whole command run by `f`, writing the duration of each stage to `report` afterward. The
+ * report is written even if the command fails.
+ */
+pub fn report_timings<T>(report: &mut dyn Write, f: impl FnOnce() -> T) -> io::Result<T> {
+    PARSE.with(|d| d.set(Duration::ZERO));
+    FORMAT.with(|d| d.set(Duration::ZERO));
+
+    let start = Instant::now();
+    let result = f();
+    let total = start.elapsed();
+
+    let parse = PARSE.with(Cell::get);
+    let format = FORMAT.with(Cell::get);
+    let compute = total.saturating_sub(parse + format);
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    writeln!(
+        report,
+        "timing: parse={:.3}ms compute={:.3}ms format={:.3}ms total={:.3}ms",
+        ms(parse),
+        ms(compute),
+        ms(format),
+        ms(total)
+    )?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    use super::{report_timings, timed, Stage};
+
+    #[test]
+    fn test_report_timings() {
+        let mut report = vec![];
+        let result = report_timings(&mut report, || {
+            timed(Stage::Parse, || sleep(Duration::from_millis(5)));
+            42
+        })
+        .unwrap();
+        assert_eq!(result, 42);
+
+        let report = String::from_utf8(report).unwrap();
+        let parse_ms: f64 = report
+            .split_once("parse=")
+            .and_then(|(_, rest)| rest.split_once("ms"))
+            .map(|(ms, _)| ms.parse().unwrap())
+            .unwrap();
+        assert!(parse_ms >= 5.0);
+        assert!(report.contains(" compute=") && report.contains(" format=0.000ms"));
+    }
+}
@@ -0,0 +1,229 @@
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+
+use clap::{Args, Subcommand};
+use clap_stdin::MaybeStdin;
+use geo::CoordsIter;
+use geo_types::{Coord, Geometry, LineString, Polygon};
+use geos_core::read::{read_ewkt_with_warnings, WktError};
+use itertools::Itertools;
+use wkt::ToWkt;
+
+use crate::error::{warn, GeosError};
+use crate::format::{geometry_type, read_text_file, round_to_precision, RunContext};
+
+/// Spaces per level of nesting in pretty-printed WKT.
+const INDENT: usize = 2;
+
+//==================================================
+// CLI spec.
+//==================================================
+#[derive(Debug, Args)]
+#[command(about = "Commands for checking and formatting WKT.")]
+#[command(args_conflicts_with_subcommands = false)]
+#[command(arg_required_else_help = true)]
+pub struct WktArgs {
+    #[command(subcommand)]
+    command: Option<WktCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WktCommands {
+    #[command(
+        about = "Check the syntax of WKT, pointing at the byte and character offset of the first error. Valid WKT is summarized, or reformatted with --pretty or --minify."
+    )]
+    #[command(arg_required_else_help = true)]
+    Check {
+        #[arg(
+            last = true,
+            help = "The WKT to check, or `@path` to read it from a file."
+        )]
+        wkt: MaybeStdin<String>,
+
+        #[arg(
+            long,
+            conflicts_with = "minify",
+            help = "Output the WKT indented with one ring or member per line."
+        )]
+        pretty: bool,
+
+        #[arg(long, help = "Output the WKT on one line without optional whitespace.")]
+        minify: bool,
+    },
+}
+
+//==================================================
+// Core subcommand logic.
+//==================================================
+pub fn handle_wkt_subcommand(
+    wkt: &WktArgs,
+    ctx: &mut RunContext,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match &wkt.command {
+        Some(WktCommands::Check {
+            wkt,
+            pretty,
+            minify,
+        }) => {
+            let (origin, source) = match wkt.strip_prefix('@') {
+                Some(path) => (
+                    path,
+                    String::from_utf8_lossy(&read_text_file(Path::new(path))?).into_owned(),
+                ),
+                None => ("argument", wkt.to_string()),
+            };
+            let mut warnings = vec![];
+            let (srid, mut geometry) =
+                read_ewkt_with_warnings(source.as_bytes(), ctx.input.lenient, &mut warnings)
+                    .map_err(|e| match e.downcast_ref::<WktError>() {
+                        Some(error) => GeosError::wkt_at_offset(error, origin, &source).into(),
+                        None => e,
+                    })?;
+            for warning in warnings {
+                warn(format!("{}: {}", origin, warning))?;
+            }
+
+            // Reformatted EWKT keeps its SRID.
+            let srid = srid
+                .map(|srid| format!("SRID={};", srid))
+                .unwrap_or_default();
+            if *pretty || *minify {
+                round_to_precision(&mut geometry, &ctx.output);
+            }
+            if *pretty {
+                writeln!(out, "{}{}", srid, fmt_pretty(&geometry, 0))?;
+            } else if *minify {
+                writeln!(out, "{}{}", srid, geometry.wkt_string())?;
+            } else {
+                writeln!(
+                    out,
+                    "valid {} with {} coordinates",
+                    geometry_type(&geometry),
+                    geometry.coords_count()
+                )?;
+            }
+        }
+
+        None => {}
+    }
+    Ok(())
+}
+
+/**
+ * Formats a geometry as WKT with each ring, line, polygon, or member of a collection on its own
+ * line, indented by its depth. Coordinate sequences are kept on one line.
+ */
+fn fmt_pretty(geometry: &Geometry, indent: usize) -> String {
+    match geometry {
+        Geometry::Point(point) => format!("POINT({})", fmt_coords([point.0])),
+        Geometry::Line(line) => fmt_pretty(&LineString::from(*line).into(), indent),
+        Geometry::LineString(line) if line.0.is_empty() => "LINESTRING EMPTY".to_string(),
+        Geometry::LineString(line) => format!("LINESTRING{}", fmt_line(line)),
+        Geometry::Polygon(polygon) => format!("POLYGON{}", fmt_polygon(polygon, indent)),
+        Geometry::MultiPoint(points) if points.0.is_empty() => "MULTIPOINT EMPTY".to_string(),
+        Geometry::MultiPoint(points) => format!(
+            "MULTIPOINT({})",
+            points
+                .iter()
+                .map(|point| format!("({})", fmt_coords([point.0])))
+                .join(", ")
+        ),
+        Geometry::MultiLineString(lines) => format!(
+            "MULTILINESTRING{}",
+            fmt_nested(lines.iter().map(fmt_line), indent)
+        ),
+        Geometry::MultiPolygon(polygons) => format!(
+            "MULTIPOLYGON{}",
+            fmt_nested(
+                polygons
+                    .iter()
+                    .map(|polygon| fmt_polygon(polygon, indent + INDENT)),
+                indent
+            )
+        ),
+        Geometry::GeometryCollection(collection) => format!(
+            "GEOMETRYCOLLECTION{}",
+            fmt_nested(
+                collection
+                    .iter()
+                    .map(|geometry| fmt_pretty(geometry, indent + INDENT)),
+                indent
+            )
+        ),
+        Geometry::Rect(rect) => fmt_pretty(&rect.to_polygon().into(), indent),
+        Geometry::Triangle(triangle) => fmt_pretty(&triangle.to_polygon().into(), indent),
+    }
+}
+
+fn fmt_coords(coords: impl IntoIterator<Item = Coord>) -> String {
+    coords
+        .into_iter()
+        .map(|c| format!("{} {}", c.x, c.y))
+        .join(", ")
+}
+
+fn fmt_line(line: &LineString) -> String {
+    format!("({})", fmt_coords(line.coords().copied()))
+}
+
+fn fmt_polygon(polygon: &Polygon, indent: usize) -> String {
+    if polygon.exterior().0.is_empty() {
+        return " EMPTY".to_string();
+    }
+    let rings = std::iter::once(polygon.exterior()).chain(polygon.interiors());
+    fmt_nested(rings.map(fmt_line), indent)
+}
+
+/**
+ * Formats the members of a geometry in parentheses, one per line, indented one level deeper than
+ * the geometry.
+ */
+fn fmt_nested(members: impl Iterator<Item = String>, indent: usize) -> String {
+    let pad = " ".repeat(indent + INDENT);
+    let members = members
+        .map(|member| format!("{}{}", pad, member.trim_start()))
+        .join(",\n");
+    if members.is_empty() {
+        return " EMPTY".to_string();
+    }
+    format!("(\n{}\n{})", members, " ".repeat(indent))
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Geometry;
+    use wkt::TryFromWkt;
+
+    use super::fmt_pretty;
+
+    #[test]
+    fn test_fmt_pretty() {
+        let wkt = "GEOMETRYCOLLECTION(POINT(1 2),MULTIPOLYGON(((0 0,1 0,1 1,0 0)),((2 2,3 2,3 3,2 2),(2.1 2.1,2.2 2.2,2.1 2.2,2.1 2.1))),MULTIPOINT((1 1),(2 2)),POLYGON EMPTY)";
+        let geometry = Geometry::<f64>::try_from_wkt_str(wkt).unwrap();
+        let pretty = fmt_pretty(&geometry, 0);
+        assert_eq!(
+            pretty,
+            "\
+GEOMETRYCOLLECTION(
+  POINT(1 2),
+  MULTIPOLYGON(
+    (
+      (0 0, 1 0, 1 1, 0 0)
+    ),
+    (
+      (2 2, 3 2, 3 3, 2 2),
+      (2.1 2.1, 2.2 2.2, 2.1 2.2, 2.1 2.1)
+    )
+  ),
+  MULTIPOINT((1 1), (2 2)),
+  POLYGON EMPTY
+)"
+        );
+        assert_eq!(
+            Geometry::<f64>::try_from_wkt_str(&pretty).unwrap(),
+            geometry
+        );
+    }
+}